@@ -0,0 +1,619 @@
+use clap::{crate_description, crate_name, crate_version, App, AppSettings, Arg, ArgMatches, SubCommand};
+use solana_clap_utils::{
+    input_parsers::{pubkey_of, pubkeys_of, value_of},
+    input_validators::{is_parsable, is_url_or_moniker, is_valid_pubkey, normalize_to_url_if_moniker},
+};
+use solana_client::rpc_client::RpcClient;
+use solana_escrow::{
+    find_config_address, find_escrow_address, find_fee_authority_address, find_fee_vault_address,
+    instruction, merkle, metaplex,
+    state::{Config, Escrow, USD_DECIMALS},
+    token,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+    system_program,
+    transaction::Transaction,
+};
+use std::{
+    error::Error,
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+type CliResult = Result<(), Box<dyn Error>>;
+
+struct CliConfig {
+    rpc_client: RpcClient,
+    program_id: Pubkey,
+    keypair: Keypair,
+}
+
+fn main() {
+    let matches = App::new(crate_name!())
+        .about(crate_description!())
+        .version(crate_version!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("config_file")
+                .short("C")
+                .long("config")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .help("Configuration file to use"),
+        )
+        .arg(
+            Arg::with_name("json_rpc_url")
+                .short("u")
+                .long("url")
+                .value_name("URL_OR_MONIKER")
+                .takes_value(true)
+                .global(true)
+                .validator(is_url_or_moniker)
+                .help("JSON RPC URL for the cluster [default: value from configuration file]"),
+        )
+        .arg(
+            Arg::with_name("keypair")
+                .short("k")
+                .long("keypair")
+                .value_name("KEYPAIR")
+                .takes_value(true)
+                .global(true)
+                .help("Keypair file that signs and pays for transactions [default: value from configuration file]"),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .short("p")
+                .long("program-id")
+                .value_name("PROGRAM_ID")
+                .takes_value(true)
+                .global(true)
+                .required(true)
+                .validator(is_valid_pubkey)
+                .help("The address the escrow program is deployed at"),
+        )
+        .subcommand(
+            SubCommand::with_name("init")
+                .about("Open an escrow offering token X for token Y. Both amounts are in base units.")
+                .arg(pubkey_arg("token_x_mint", 1, "The mint of the token to offer"))
+                .arg(amount_arg("amount", 2, "How much of token X to put in the escrow"))
+                .arg(pubkey_arg("token_y_mint", 3, "The mint of the token to receive"))
+                .arg(amount_arg("expected_amount", 4, "How much of token Y to ask for"))
+                .arg(
+                    Arg::with_name("expires_in")
+                        .long("expires-in")
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .validator(is_parsable::<i64>)
+                        .help("Let the escrow expire this many seconds from now [default: the config's default expiry]"),
+                )
+                .arg(
+                    Arg::with_name("allowed_taker")
+                        .long("allowed-taker")
+                        .value_name("ADDRESS")
+                        .takes_value(true)
+                        .validator(is_valid_pubkey)
+                        .help("Only let this wallet take the escrow"),
+                )
+                .arg(
+                    Arg::with_name("id")
+                        .long("id")
+                        .value_name("ID")
+                        .takes_value(true)
+                        .validator(is_parsable::<u64>)
+                        .help("Give the escrow a non-zero id, e.g. an order id, which also determines its address"),
+                )
+                .arg(
+                    Arg::with_name("reservation_deposit")
+                        .long("reservation-deposit")
+                        .value_name("LAMPORTS")
+                        .takes_value(true)
+                        .validator(is_parsable::<u64>)
+                        .help("Let takers reserve the escrow for this deposit, which they forfeit if they don't exchange in time"),
+                )
+                .arg(taker_allowlist_arg("Only let the wallets in this file take the escrow")),
+        )
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("Show the state of an escrow")
+                .arg(pubkey_arg("escrow", 1, "The escrow account")),
+        )
+        .subcommand(
+            SubCommand::with_name("exchange")
+                .about("Take an escrow, paying from and receiving into the keypair's associated token accounts")
+                .arg(pubkey_arg("escrow", 1, "The escrow account"))
+                .arg(
+                    Arg::with_name("max_payment")
+                        .long("max-payment")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .validator(is_parsable::<u64>)
+                        .help("The most to pay in token Y, protocol fee included. Defaults to the current price plus the fee"),
+                )
+                .arg(taker_allowlist_arg("The allowlist the escrow was opened with, needed to prove the keypair is in it"))
+                .arg(
+                    Arg::with_name("gate_account")
+                        .long("gate-account")
+                        .value_name("ADDRESS")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .validator(is_valid_pubkey)
+                        .help("An account the config's gate program reads to approve the keypair, e.g. its credential. Can be repeated."),
+                )
+                .arg(memo_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("cancel")
+                .about("Cancel an escrow, returning token X to the keypair's associated token account")
+                .arg(pubkey_arg("escrow", 1, "The escrow account"))
+                .arg(memo_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("reserve")
+                .about("Lock an escrow's reservation deposit to be the only one who can exchange it for a while")
+                .arg(pubkey_arg("escrow", 1, "The escrow account"))
+                .arg(amount_arg("slots", 2, "How many slots to reserve the escrow for")),
+        )
+        .get_matches();
+
+    let (command, sub_matches) = matches.subcommand();
+    let sub_matches = sub_matches.unwrap();
+    let result = load_config(&matches).and_then(|config| match command {
+        "init" => command_init(&config, sub_matches),
+        "show" => command_show(&config, sub_matches),
+        "exchange" => command_exchange(&config, sub_matches),
+        "cancel" => command_cancel(&config, sub_matches),
+        "reserve" => command_reserve(&config, sub_matches),
+        _ => unreachable!(),
+    });
+    if let Err(error) = result {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn pubkey_arg<'a, 'b>(name: &'a str, index: u64, help: &'a str) -> Arg<'a, 'b> {
+    Arg::with_name(name)
+        .value_name("ADDRESS")
+        .index(index)
+        .required(true)
+        .validator(is_valid_pubkey)
+        .help(help)
+}
+
+fn amount_arg<'a, 'b>(name: &'a str, index: u64, help: &'a str) -> Arg<'a, 'b> {
+    Arg::with_name(name)
+        .value_name("AMOUNT")
+        .index(index)
+        .required(true)
+        .validator(is_parsable::<u64>)
+        .help(help)
+}
+
+fn memo_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("memo")
+        .long("memo")
+        .value_name("MEMO")
+        .takes_value(true)
+        .help("A memo to record through the SPL Memo program")
+}
+
+fn taker_allowlist_arg<'a, 'b>(help: &'a str) -> Arg<'a, 'b> {
+    Arg::with_name("taker_allowlist")
+        .long("taker-allowlist")
+        .value_name("FILE")
+        .takes_value(true)
+        .help(help)
+}
+
+// One address per line, in the same order for init and exchange, since the order determines the tree
+fn read_taker_allowlist(matches: &ArgMatches) -> Result<Option<Vec<Pubkey>>, Box<dyn Error>> {
+    let path = match matches.value_of("taker_allowlist") {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let takers = fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| line.parse().map_err(|_| format!("invalid address {} in {}", line, path)))
+        .collect::<Result<Vec<Pubkey>, _>>()?;
+    Ok(Some(takers))
+}
+
+// Without a --memo the instruction goes out as built, so the Memo program isn't needed
+fn with_memo(instruction: Instruction, matches: &ArgMatches) -> Result<Instruction, Box<dyn Error>> {
+    Ok(match matches.value_of("memo") {
+        Some(memo) => instruction::with_memo(instruction, memo)?,
+        None => instruction,
+    })
+}
+
+// The url and keypair default to the ones of the Solana CLI, so the CLI works against whatever cluster `solana config set` points at
+fn load_config(matches: &ArgMatches) -> Result<CliConfig, Box<dyn Error>> {
+    let cli_config = match matches.value_of("config_file").or(solana_cli_config::CONFIG_FILE.as_deref()) {
+        Some(config_file) => solana_cli_config::Config::load(config_file).unwrap_or_default(),
+        None => solana_cli_config::Config::default(),
+    };
+    let json_rpc_url = normalize_to_url_if_moniker(matches.value_of("json_rpc_url").unwrap_or(&cli_config.json_rpc_url));
+    let keypair_path = matches.value_of("keypair").unwrap_or(&cli_config.keypair_path);
+    let keypair = read_keypair_file(keypair_path)
+        .map_err(|error| format!("failed to read keypair {}: {}", keypair_path, error))?;
+
+    Ok(CliConfig {
+        rpc_client: RpcClient::new_with_commitment(json_rpc_url, CommitmentConfig::confirmed()),
+        program_id: pubkey_of(matches, "program_id").unwrap(),
+        keypair,
+    })
+}
+
+fn send(config: &CliConfig, instructions: &[Instruction], extra_signers: &[&Keypair]) -> CliResult {
+    let mut signers = vec![&config.keypair];
+    signers.extend_from_slice(extra_signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&config.keypair.pubkey()),
+        &signers,
+        config.rpc_client.get_latest_blockhash()?,
+    );
+    let signature = config.rpc_client.send_and_confirm_transaction_with_spinner(&transaction)?;
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+/// The token program of a mint is whichever program owns it
+fn token_program_of(config: &CliConfig, mint: &Pubkey) -> Result<Pubkey, Box<dyn Error>> {
+    let owner = config.rpc_client.get_account(mint)?.owner;
+    if !token::is_token_program(&owner) {
+        return Err(format!("{} is not a mint", mint).into());
+    }
+    Ok(owner)
+}
+
+fn account_exists(config: &CliConfig, address: &Pubkey) -> Result<bool, Box<dyn Error>> {
+    Ok(config
+        .rpc_client
+        .get_account_with_commitment(address, config.rpc_client.commitment())?
+        .value
+        .is_some())
+}
+
+fn fetch_escrow(config: &CliConfig, escrow_account: &Pubkey) -> Result<Escrow, Box<dyn Error>> {
+    let account = config.rpc_client.get_account(escrow_account)?;
+    if account.owner != config.program_id {
+        return Err(format!("{} is not an escrow of program {}", escrow_account, config.program_id).into());
+    }
+    Ok(Escrow::try_from_account_data(&account.data)?)
+}
+
+// The creators of an NFT who are owed royalties, None if token X isn't an NFT. Only NFTs with Metaplex metadata have creators.
+fn nft_creators(config: &CliConfig, mint: &Pubkey) -> Result<Option<Vec<Pubkey>>, Box<dyn Error>> {
+    let mint_data = config.rpc_client.get_account_data(mint)?;
+    let mint_info = spl_token::state::Mint::unpack_from_slice(mint_data.get(..spl_token::state::Mint::LEN).ok_or("invalid mint")?)?;
+    if mint_info.decimals != 0 || mint_info.supply != 1 {
+        return Ok(None);
+    }
+    let metadata = config
+        .rpc_client
+        .get_account_with_commitment(&metaplex::find_metadata_address(mint), config.rpc_client.commitment())?
+        .value;
+    let royalties = match metadata {
+        Some(metadata) if metadata.owner == metaplex::id() => metaplex::unpack_royalties(&metadata.data, mint)?,
+        _ => metaplex::Royalties::default(),
+    };
+    let creators = royalties.creators.iter().filter(|creator| creator.share > 0).map(|creator| creator.address);
+    Ok(Some(if royalties.seller_fee_basis_points == 0 { vec![] } else { creators.collect() }))
+}
+
+fn unix_timestamp() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+}
+
+// The program creates the escrow account and creates and funds the temp token account itself (see init_escrow_with_deposit and with_escrow_account_creation), so we only have to create the associated token account token Y is paid into if it doesn't exist yet
+fn command_init(config: &CliConfig, matches: &ArgMatches) -> CliResult {
+    let initializer = config.keypair.pubkey();
+    let token_x_mint = pubkey_of(matches, "token_x_mint").unwrap();
+    let token_y_mint = pubkey_of(matches, "token_y_mint").unwrap();
+    let amount: u64 = value_of(matches, "amount").unwrap();
+    let expected_amount: u64 = value_of(matches, "expected_amount").unwrap();
+    let expiry_unix_timestamp = value_of::<i64>(matches, "expires_in").map_or(0, |seconds| unix_timestamp() + seconds);
+    let allowed_taker = pubkey_of(matches, "allowed_taker");
+
+    let x_token_program_id = token_program_of(config, &token_x_mint)?;
+    let y_token_program_id = token_program_of(config, &token_y_mint)?;
+    let initializers_token_account = token::get_associated_token_address(&initializer, &token_x_mint, &x_token_program_id);
+    let token_to_receive_account = token::get_associated_token_address(&initializer, &token_y_mint, &y_token_program_id);
+
+    let temp_token_account = Keypair::new();
+    let escrow_account = Keypair::new();
+    let mut instructions = vec![];
+    if !account_exists(config, &token_to_receive_account)? {
+        instructions.push(token::create_associated_token_account(
+            &initializer,
+            &initializer,
+            &token_y_mint,
+            &y_token_program_id,
+        ));
+    }
+    let init_escrow = instruction::with_escrow_account_creation(instruction::init_escrow_with_deposit(
+        &config.program_id,
+        &initializer,
+        &temp_token_account.pubkey(),
+        &initializers_token_account,
+        &token_x_mint,
+        &token_to_receive_account,
+        &escrow_account.pubkey(),
+        &x_token_program_id,
+        amount,
+        expected_amount,
+        expiry_unix_timestamp,
+        allowed_taker.as_ref(),
+    )?);
+
+    let init_escrow = match value_of::<u64>(matches, "reservation_deposit") {
+        Some(reservation_deposit) => instruction::with_reservation_deposit(init_escrow, reservation_deposit)?,
+        None => init_escrow,
+    };
+    let init_escrow = match read_taker_allowlist(matches)? {
+        Some(takers) => {
+            let root = merkle::root(&takers).ok_or("the taker allowlist is empty")?;
+            instruction::with_taker_merkle_root(init_escrow, root)?
+        }
+        None => init_escrow,
+    };
+
+    // An escrow with an id lives at its PDA, so the new keypair isn't used
+    match value_of::<u64>(matches, "id") {
+        Some(id) => {
+            instructions.push(instruction::with_escrow_id(init_escrow, id)?);
+            send(config, &instructions, &[&temp_token_account])?;
+            println!("Escrow: {}", find_escrow_address(&initializer, id, &config.program_id).0);
+        }
+        None => {
+            instructions.push(init_escrow);
+            send(config, &instructions, &[&temp_token_account, &escrow_account])?;
+            println!("Escrow: {}", escrow_account.pubkey());
+        }
+    }
+    Ok(())
+}
+
+fn command_show(config: &CliConfig, matches: &ArgMatches) -> CliResult {
+    let escrow_account = pubkey_of(matches, "escrow").unwrap();
+    let escrow = fetch_escrow(config, &escrow_account)?;
+    let now = unix_timestamp();
+
+    println!("Escrow: {}", escrow_account);
+    println!("Initializer: {}", escrow.initializer_pubkey);
+    println!("Token X mint: {}", escrow.token_x_mint);
+    println!("Token Y mint: {}", escrow.token_y_mint);
+    println!("Temp token account: {}", escrow.temp_token_account_pubkey);
+    println!("Receiving account: {}", escrow.initializer_token_to_receive_account_pubkey);
+    if escrow.escrow_id != 0 {
+        println!("Id: {}", { escrow.escrow_id });
+    }
+    match escrow.current_price(now) {
+        Some(price) => println!("Expected amount: {}", price),
+        None => println!("Expected amount: overflow"),
+    }
+    if escrow.allowed_taker_pubkey != Pubkey::default() {
+        println!("Allowed taker: {}", escrow.allowed_taker_pubkey);
+    }
+    if escrow.price_feed != Pubkey::default() {
+        println!("Price feed: {} ({} bps premium)", escrow.price_feed, { escrow.premium_bps });
+    }
+    println!("Created: {}", { escrow.created_unix_timestamp });
+    match escrow.expiry_unix_timestamp {
+        0 => println!("Expiry: never"),
+        expiry => println!("Expiry: {}{}", expiry, if escrow.is_expired(now) { " (expired)" } else { "" }),
+    }
+    if escrow.reservation_deposit != 0 {
+        println!("Reservation deposit: {} lamports", { escrow.reservation_deposit });
+    }
+    if escrow.reserved_by != Pubkey::default() {
+        println!("Reserved by: {} until slot {}", escrow.reserved_by, { escrow.reserved_until_slot });
+    }
+    if escrow.has_taker_allowlist() {
+        println!("Taker allowlist root: {}", Hash::new_from_array(escrow.taker_merkle_root));
+    }
+    if escrow.is_collection_offer() {
+        println!("Collection offer for: {}", escrow.collection);
+    }
+    if escrow.is_usd_priced() {
+        println!("USD price: {}", spl_token::amount_to_ui_amount(escrow.usd_amount, USD_DECIMALS));
+    }
+    for mint in escrow.payment_option_mints() {
+        if let Some(price) = escrow.paid_in(mint).and_then(|paid_in| paid_in.current_price(now)) {
+            println!("Also accepts: {} of {}", price, mint);
+        }
+    }
+    if escrow.holds_lamports() {
+        println!("Escrowed lamports: {}", config.rpc_client.get_balance(&escrow.temp_token_account_pubkey)?);
+    }
+    Ok(())
+}
+
+// The taker pays from and receives into their associated token accounts. The receiving one is created by the program if it doesn't exist yet
+fn command_exchange(config: &CliConfig, matches: &ArgMatches) -> CliResult {
+    let taker = config.keypair.pubkey();
+    let escrow_account = pubkey_of(matches, "escrow").unwrap();
+    let escrow = fetch_escrow(config, &escrow_account)?;
+
+    let y_token_program_id = token_program_of(config, &escrow.token_y_mint)?;
+    let takers_sending_token_account = token::get_associated_token_address(&taker, &escrow.token_y_mint, &y_token_program_id);
+    // Lamport escrows pay out into the taker's own account, through the system program
+    let (x_token_program_id, takers_token_to_receive_account) = if escrow.holds_lamports() {
+        (system_program::id(), taker)
+    } else {
+        let x_token_program_id = token_program_of(config, &escrow.token_x_mint)?;
+        (x_token_program_id, token::get_associated_token_address(&taker, &escrow.token_x_mint, &x_token_program_id))
+    };
+
+    // Delegated escrows leave the tokens in the initializer's account, so its balance isn't what the escrow offers
+    let amount = if escrow.delegated_amount != 0 {
+        escrow.delegated_amount
+    } else if escrow.holds_lamports() {
+        config.rpc_client.get_balance(&escrow.temp_token_account_pubkey)?
+    } else {
+        config
+            .rpc_client
+            .get_token_account_balance(&escrow.temp_token_account_pubkey)?
+            .amount
+            .parse()?
+    };
+
+    let config_data = config.rpc_client.get_account_data(&find_config_address(&config.program_id).0)?;
+    // A fee collector is paid into their associated token account, see SetFee
+    let program_config = Config::unpack(&config_data)?;
+    let fee_vault = if program_config.fee_bps == 0 {
+        None
+    } else if program_config.fee_collector != Pubkey::default() {
+        Some(token::get_associated_token_address(&program_config.fee_collector, &escrow.token_y_mint, &y_token_program_id))
+    } else {
+        Some(find_fee_vault_address(&escrow.token_y_mint, &config.program_id).0)
+    };
+    let price_feed = Some(escrow.price_feed).filter(|price_feed| *price_feed != Pubkey::default());
+
+    // Without --max-payment the taker pays at most the current price plus the fee
+    let max_payment = match value_of(matches, "max_payment") {
+        Some(max_payment) => max_payment,
+        None => escrow
+            .expected_payment(amount, program_config.fee_bps, unix_timestamp())
+            .ok_or("the escrow is priced by an oracle or in USD, pass --max-payment")?,
+    };
+
+    let instruction = instruction::exchange(
+        &config.program_id,
+        instruction::ExchangeArgs {
+            taker,
+            takers_sending_token_account,
+            takers_token_to_receive_account,
+            pdas_temp_token_account: escrow.temp_token_account_pubkey,
+            initializers_main_account: escrow.initializer_pubkey,
+            initializers_token_to_receive_account: escrow.initializer_token_to_receive_account_pubkey,
+            escrow_account,
+            token_program_id: x_token_program_id,
+            token_x_mint: escrow.token_x_mint,
+            token_y_mint: escrow.token_y_mint,
+            y_token_program_id,
+            fee_vault,
+            referrer_token_account: None,
+            create_takers_token_account: !escrow.holds_lamports()
+                && !account_exists(config, &takers_token_to_receive_account)?,
+            price_feed,
+            amount,
+            max_payment,
+        },
+    )?;
+    // Fills of a rate-based escrow are recorded for the taker, see FillRecord
+    let instruction = if escrow.is_rate_based() { instruction::with_fill_record(instruction) } else { instruction };
+    // Royalties are paid into the creators' associated token accounts, which the taker creates if they don't exist yet
+    let mut instructions = vec![];
+    let creators = if escrow.holds_lamports() { None } else { nft_creators(config, &escrow.token_x_mint)? };
+    let instruction = match creators {
+        Some(creators) => {
+            let mut creators_token_accounts = vec![];
+            for creator in creators {
+                let creators_token_account =
+                    token::get_associated_token_address(&creator, &escrow.token_y_mint, &y_token_program_id);
+                if !account_exists(config, &creators_token_account)? {
+                    instructions.push(token::create_associated_token_account(
+                        &taker,
+                        &creator,
+                        &escrow.token_y_mint,
+                        &y_token_program_id,
+                    ));
+                }
+                creators_token_accounts.push(creators_token_account);
+            }
+            instruction::with_royalty_accounts(instruction, &escrow.token_x_mint, &creators_token_accounts)
+        }
+        None => instruction,
+    };
+    let instruction = match read_taker_allowlist(matches)? {
+        Some(takers) => {
+            let proof = merkle::proof(&takers, &taker).ok_or("the keypair isn't in the taker allowlist")?;
+            instruction::with_taker_proof(instruction, proof)?
+        }
+        None if escrow.has_taker_allowlist() => {
+            return Err("the escrow has a taker allowlist, pass it with --taker-allowlist".into())
+        }
+        None => instruction,
+    };
+    let instruction = if program_config.gate_program != Pubkey::default() {
+        let gate_accounts: Vec<AccountMeta> = pubkeys_of(matches, "gate_account")
+            .unwrap_or_default()
+            .iter()
+            .map(|account| AccountMeta::new_readonly(*account, false))
+            .collect();
+        instruction::with_gate_accounts(instruction, &program_config.gate_program, &gate_accounts)
+    } else {
+        instruction
+    };
+    instructions.push(with_memo(instruction, matches)?);
+    send(config, &instructions, &[])
+}
+
+fn command_cancel(config: &CliConfig, matches: &ArgMatches) -> CliResult {
+    let initializer = config.keypair.pubkey();
+    let escrow_account = pubkey_of(matches, "escrow").unwrap();
+    let escrow = fetch_escrow(config, &escrow_account)?;
+    if escrow.initializer_pubkey != initializer {
+        return Err(format!("only the initializer {} can cancel this escrow", escrow.initializer_pubkey).into());
+    }
+
+    let x_token_program_id = token_program_of(config, &escrow.token_x_mint)?;
+    let instruction = instruction::cancel(
+        &config.program_id,
+        &initializer,
+        &escrow.temp_token_account_pubkey,
+        &token::get_associated_token_address(&initializer, &escrow.token_x_mint, &x_token_program_id),
+        &escrow_account,
+        &x_token_program_id,
+        &escrow.token_x_mint,
+    )?;
+
+    // The penalty is paid like the exchange fee, only in token X
+    let config_data = config.rpc_client.get_account_data(&find_config_address(&config.program_id).0)?;
+    let program_config = Config::unpack(&config_data)?;
+    let instruction = if program_config.cancel_penalty_bps == 0 && program_config.cancel_penalty_amount == 0 {
+        instruction
+    } else {
+        let fee_authority = find_fee_authority_address(&config.program_id).0;
+        let recipient = program_config.cancel_penalty_recipient(&escrow, &fee_authority);
+        let penalty_token_account = if recipient == fee_authority {
+            find_fee_vault_address(&escrow.token_x_mint, &config.program_id).0
+        } else {
+            token::get_associated_token_address(&recipient, &escrow.token_x_mint, &x_token_program_id)
+        };
+        instruction::with_cancel_penalty_account(instruction, &penalty_token_account)
+    };
+    let instruction = with_memo(instruction, matches)?;
+    send(config, &[instruction], &[])
+}
+
+fn command_reserve(config: &CliConfig, matches: &ArgMatches) -> CliResult {
+    let escrow_account = pubkey_of(matches, "escrow").unwrap();
+    let escrow = fetch_escrow(config, &escrow_account)?;
+    if escrow.reservation_deposit == 0 {
+        return Err("this escrow can't be reserved".into());
+    }
+
+    let instruction = instruction::reserve(
+        &config.program_id,
+        &config.keypair.pubkey(),
+        &escrow_account,
+        &escrow.initializer_pubkey,
+        value_of(matches, "slots").unwrap(),
+    )?;
+    send(config, &[instruction], &[])?;
+    println!("Reserved for {} lamports", { escrow.reservation_deposit });
+    Ok(())
+}
@@ -0,0 +1,398 @@
+//! An async client for the escrow program. `EscrowClient` finds the accounts each instruction needs, builds the transaction with the builders of `solana_escrow::instruction`, and waits for it to be confirmed.
+//!
+//! ```no_run
+//! # use solana_client::nonblocking::rpc_client::RpcClient;
+//! # use solana_escrow_client::EscrowClient;
+//! # use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), solana_escrow_client::ClientError> {
+//! # let (program_id, sol, usdc, alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Keypair::new(), Keypair::new());
+//! let client = EscrowClient::new(RpcClient::new("https://api.devnet.solana.com".to_string()), program_id);
+//! // Alice offers 1 SOL for 150 USDC
+//! let escrow = client.create_escrow(&alice, &sol, 1_000_000_000, &usdc, 150_000_000, 0).await?;
+//! println!("{:?}", client.fetch_escrow(&escrow).await?);
+//! client.take_escrow(&bob, &escrow, None).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use solana_client::{
+    client_error::ClientError as RpcError,
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_escrow::{
+    find_config_address, find_fee_authority_address, find_fee_vault_address, instruction, metaplex,
+    state::{escrow_offsets, Config, Escrow, ESCROW_DISCRIMINATOR},
+    token,
+};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    /// The RPC request or the transaction failed
+    #[error(transparent)]
+    Rpc(#[from] RpcError),
+    /// Account data couldn't be decoded, or an instruction couldn't be built
+    #[error(transparent)]
+    Program(#[from] ProgramError),
+    /// The account isn't owned by the escrow program
+    #[error("{0} is not an escrow")]
+    NotAnEscrow(Pubkey),
+    /// The account isn't owned by a token program
+    #[error("{0} is not a mint")]
+    NotAMint(Pubkey),
+    /// Only the initializer can cancel an escrow
+    #[error("only the initializer can cancel escrow {0}")]
+    NotInitializer(Pubkey),
+    /// Oracle and USD priced escrows have no price to derive a max_payment from
+    #[error("escrow {0} has no known price, a max_payment is required")]
+    MaxPaymentRequired(Pubkey),
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+pub struct EscrowClient {
+    rpc_client: RpcClient,
+    program_id: Pubkey,
+}
+
+impl EscrowClient {
+    pub fn new(rpc_client: RpcClient, program_id: Pubkey) -> Self {
+        EscrowClient { rpc_client, program_id }
+    }
+
+    pub fn rpc_client(&self) -> &RpcClient {
+        &self.rpc_client
+    }
+
+    pub fn program_id(&self) -> &Pubkey {
+        &self.program_id
+    }
+
+    /// Fetches and decodes an escrow account
+    pub async fn fetch_escrow(&self, escrow_account: &Pubkey) -> Result<Escrow> {
+        let account = self.rpc_client.get_account(escrow_account).await?;
+        if account.owner != self.program_id {
+            return Err(ClientError::NotAnEscrow(*escrow_account));
+        }
+        Ok(Escrow::try_from_account_data(&account.data)?)
+    }
+
+    /// Fetches the open escrows of an initializer
+    pub async fn fetch_escrows_of_initializer(&self, initializer: &Pubkey) -> Result<Vec<(Pubkey, Escrow)>> {
+        self.fetch_escrows(vec![memcmp(escrow_offsets::INITIALIZER_PUBKEY, initializer.as_ref())])
+            .await
+    }
+
+    /// Fetches the open escrows offering token X for token Y
+    pub async fn fetch_escrows_by_mints(&self, token_x_mint: &Pubkey, token_y_mint: &Pubkey) -> Result<Vec<(Pubkey, Escrow)>> {
+        self.fetch_escrows(vec![
+            memcmp(escrow_offsets::TOKEN_X_MINT, token_x_mint.as_ref()),
+            memcmp(escrow_offsets::TOKEN_Y_MINT, token_y_mint.as_ref()),
+        ])
+        .await
+    }
+
+    /// Scans the program's escrow accounts that match all the filters. The discriminator and size filters are added here, so only escrows come back.
+    async fn fetch_escrows(&self, mut filters: Vec<RpcFilterType>) -> Result<Vec<(Pubkey, Escrow)>> {
+        filters.push(RpcFilterType::DataSize(Escrow::LEN as u64));
+        filters.push(memcmp(escrow_offsets::DISCRIMINATOR, &ESCROW_DISCRIMINATOR));
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig { commitment: Some(self.rpc_client.commitment()), ..Default::default() },
+            ..Default::default()
+        };
+        let accounts = self
+            .rpc_client
+            .get_program_accounts_with_config(&self.program_id, config)
+            .await?;
+        accounts
+            .into_iter()
+            .map(|(address, account)| Ok((address, Escrow::try_from_account_data(&account.data)?)))
+            .collect()
+    }
+
+    /// Opens an escrow offering `amount` of token X for `expected_amount` of token Y and returns its address. The tokens come from the initializer's associated token account of X, and Y will be paid into their associated token account of Y, which is created if it doesn't exist. An `expiry_unix_timestamp` of 0 uses the config's default expiry.
+    pub async fn create_escrow(
+        &self,
+        initializer: &Keypair,
+        token_x_mint: &Pubkey,
+        amount: u64,
+        token_y_mint: &Pubkey,
+        expected_amount: u64,
+        expiry_unix_timestamp: i64,
+    ) -> Result<Pubkey> {
+        let initializer_pubkey = initializer.pubkey();
+        let x_token_program_id = self.token_program_of(token_x_mint).await?;
+        let y_token_program_id = self.token_program_of(token_y_mint).await?;
+        let token_to_receive_account =
+            token::get_associated_token_address(&initializer_pubkey, token_y_mint, &y_token_program_id);
+
+        // The program creates the escrow account and creates and funds the temp token account itself, they only have to sign
+        let temp_token_account = Keypair::new();
+        let escrow_account = Keypair::new();
+        let mut instructions = vec![];
+        if !self.account_exists(&token_to_receive_account).await? {
+            instructions.push(token::create_associated_token_account(
+                &initializer_pubkey,
+                &initializer_pubkey,
+                token_y_mint,
+                &y_token_program_id,
+            ));
+        }
+        instructions.push(instruction::with_escrow_account_creation(instruction::init_escrow_with_deposit(
+            &self.program_id,
+            &initializer_pubkey,
+            &temp_token_account.pubkey(),
+            &token::get_associated_token_address(&initializer_pubkey, token_x_mint, &x_token_program_id),
+            token_x_mint,
+            &token_to_receive_account,
+            &escrow_account.pubkey(),
+            &x_token_program_id,
+            amount,
+            expected_amount,
+            expiry_unix_timestamp,
+            None,
+        )?));
+
+        self.send(initializer, &instructions, &[&temp_token_account, &escrow_account])
+            .await?;
+        Ok(escrow_account.pubkey())
+    }
+
+    /// Takes an escrow. The taker pays from their associated token account of Y and receives into their associated token account of X, which the program creates if it doesn't exist. `max_payment` caps what the taker pays, protocol fee included. Without one the taker pays at most the escrow's current price plus the fee, see Escrow::expected_payment, which oracle and USD priced escrows don't have.
+    pub async fn take_escrow(
+        &self,
+        taker: &Keypair,
+        escrow_account: &Pubkey,
+        max_payment: Option<u64>,
+    ) -> Result<Signature> {
+        let instruction = self
+            .exchange_instruction(escrow_account, &taker.pubkey(), max_payment)
+            .await?;
+        self.send(taker, &[instruction], &[]).await
+    }
+
+    /// Cancels an escrow, returning token X to the initializer's associated token account
+    pub async fn cancel_escrow(&self, initializer: &Keypair, escrow_account: &Pubkey) -> Result<Signature> {
+        let (escrow, instruction) = self.cancel_instruction(escrow_account).await?;
+        if escrow.initializer_pubkey != initializer.pubkey() {
+            return Err(ClientError::NotInitializer(*escrow_account));
+        }
+        self.send(initializer, &[instruction], &[]).await
+    }
+
+    /// Returns token X of an expired escrow to the initializer's associated token account and closes it. Anyone can do this, the payer only pays the transaction fee.
+    pub async fn reclaim_expired_escrow(&self, payer: &Keypair, escrow_account: &Pubkey) -> Result<Signature> {
+        let instruction = self.reclaim_expired_instruction(escrow_account).await?;
+        self.send(payer, &[instruction], &[]).await
+    }
+
+    /// The accounts of an Exchange of the escrow by the taker, in order, as take_escrow would send them
+    pub async fn derive_exchange_accounts(&self, escrow_account: &Pubkey, taker: &Pubkey) -> Result<Vec<AccountMeta>> {
+        // Only the accounts are used, so the max_payment doesn't matter
+        Ok(self.exchange_instruction(escrow_account, taker, Some(u64::MAX)).await?.accounts)
+    }
+
+    /// The accounts of a Cancel of the escrow, in order. Token X goes back to the initializer's associated token account.
+    pub async fn derive_cancel_accounts(&self, escrow_account: &Pubkey) -> Result<Vec<AccountMeta>> {
+        Ok(self.cancel_instruction(escrow_account).await?.1.accounts)
+    }
+
+    /// The accounts of a ReclaimExpired of the escrow, in order. Token X goes back to the initializer's associated token account.
+    pub async fn derive_reclaim_expired_accounts(&self, escrow_account: &Pubkey) -> Result<Vec<AccountMeta>> {
+        Ok(self.reclaim_expired_instruction(escrow_account).await?.accounts)
+    }
+
+    /// Builds an Exchange that takes everything the escrow offers. The PDA, the config and the fee vault are derived, everything else comes from the escrow account.
+    async fn exchange_instruction(
+        &self,
+        escrow_account: &Pubkey,
+        taker: &Pubkey,
+        max_payment: Option<u64>,
+    ) -> Result<Instruction> {
+        let escrow = self.fetch_escrow(escrow_account).await?;
+        let x_token_program_id = self.token_program_of(&escrow.token_x_mint).await?;
+        let y_token_program_id = self.token_program_of(&escrow.token_y_mint).await?;
+        let takers_token_to_receive_account =
+            token::get_associated_token_address(taker, &escrow.token_x_mint, &x_token_program_id);
+
+        // Delegated escrows leave the tokens in the initializer's account, so its balance isn't what the escrow offers
+        let amount = if escrow.delegated_amount != 0 {
+            escrow.delegated_amount
+        } else {
+            let balance = self
+                .rpc_client
+                .get_token_account_balance(&escrow.temp_token_account_pubkey)
+                .await?;
+            balance.amount.parse().map_err(|_| ProgramError::InvalidAccountData)?
+        };
+
+        let config_data = self
+            .rpc_client
+            .get_account_data(&find_config_address(&self.program_id).0)
+            .await?;
+        // A fee collector is paid into their associated token account, see SetFee
+        let config = Config::unpack(&config_data)?;
+        let fee_vault = if config.fee_bps == 0 {
+            None
+        } else if config.fee_collector != Pubkey::default() {
+            Some(token::get_associated_token_address(&config.fee_collector, &escrow.token_y_mint, &y_token_program_id))
+        } else {
+            Some(find_fee_vault_address(&escrow.token_y_mint, &self.program_id).0)
+        };
+        let price_feed = Some(escrow.price_feed).filter(|price_feed| *price_feed != Pubkey::default());
+        let max_payment = match max_payment {
+            Some(max_payment) => max_payment,
+            None => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+                escrow
+                    .expected_payment(amount, config.fee_bps, now)
+                    .ok_or(ClientError::MaxPaymentRequired(*escrow_account))?
+            }
+        };
+
+        let instruction = instruction::exchange(
+            &self.program_id,
+            instruction::ExchangeArgs {
+                taker: *taker,
+                takers_sending_token_account: token::get_associated_token_address(
+                    taker,
+                    &escrow.token_y_mint,
+                    &y_token_program_id,
+                ),
+                takers_token_to_receive_account,
+                pdas_temp_token_account: escrow.temp_token_account_pubkey,
+                initializers_main_account: escrow.initializer_pubkey,
+                initializers_token_to_receive_account: escrow.initializer_token_to_receive_account_pubkey,
+                escrow_account: *escrow_account,
+                token_program_id: x_token_program_id,
+                token_x_mint: escrow.token_x_mint,
+                token_y_mint: escrow.token_y_mint,
+                y_token_program_id,
+                fee_vault,
+                referrer_token_account: None,
+                create_takers_token_account: !self.account_exists(&takers_token_to_receive_account).await?,
+                price_feed,
+                amount,
+                max_payment,
+            },
+        )?;
+        // Fills of a rate-based escrow are recorded for the taker, see FillRecord
+        let instruction = if escrow.is_rate_based() { instruction::with_fill_record(instruction) } else { instruction };
+        // Exchanges of NFTs need the metadata account even if there is none, so it is always passed, other exchanges ignore it. Royalties go to the creators' associated token accounts, which have to exist.
+        let royalties = match self
+            .rpc_client
+            .get_account_with_commitment(&metaplex::find_metadata_address(&escrow.token_x_mint), self.rpc_client.commitment())
+            .await?
+            .value
+        {
+            Some(metadata) if metadata.owner == metaplex::id() => metaplex::unpack_royalties(&metadata.data, &escrow.token_x_mint)?,
+            _ => metaplex::Royalties::default(),
+        };
+        let creators_token_accounts: Vec<Pubkey> = royalties
+            .creators
+            .iter()
+            .filter(|creator| royalties.seller_fee_basis_points > 0 && creator.share > 0)
+            .map(|creator| token::get_associated_token_address(&creator.address, &escrow.token_y_mint, &y_token_program_id))
+            .collect();
+        let instruction = instruction::with_royalty_accounts(instruction, &escrow.token_x_mint, &creators_token_accounts);
+        // Which accounts a gate reads is up to the gate, so only gates that read none can be passed here
+        Ok(if config.gate_program != Pubkey::default() {
+            instruction::with_gate_accounts(instruction, &config.gate_program, &[])
+        } else {
+            instruction
+        })
+    }
+
+    async fn cancel_instruction(&self, escrow_account: &Pubkey) -> Result<(Escrow, Instruction)> {
+        let escrow = self.fetch_escrow(escrow_account).await?;
+        let x_token_program_id = self.token_program_of(&escrow.token_x_mint).await?;
+        let instruction = instruction::cancel(
+            &self.program_id,
+            &escrow.initializer_pubkey,
+            &escrow.temp_token_account_pubkey,
+            &token::get_associated_token_address(&escrow.initializer_pubkey, &escrow.token_x_mint, &x_token_program_id),
+            escrow_account,
+            &x_token_program_id,
+            &escrow.token_x_mint,
+        )?;
+
+        // The penalty is paid like the exchange fee, only in token X
+        let config_data = self
+            .rpc_client
+            .get_account_data(&find_config_address(&self.program_id).0)
+            .await?;
+        let config = Config::unpack(&config_data)?;
+        if config.cancel_penalty_bps == 0 && config.cancel_penalty_amount == 0 {
+            return Ok((escrow, instruction));
+        }
+        let fee_authority = find_fee_authority_address(&self.program_id).0;
+        let recipient = config.cancel_penalty_recipient(&escrow, &fee_authority);
+        let penalty_token_account = if recipient == fee_authority {
+            find_fee_vault_address(&escrow.token_x_mint, &self.program_id).0
+        } else {
+            token::get_associated_token_address(&recipient, &escrow.token_x_mint, &x_token_program_id)
+        };
+        Ok((escrow, instruction::with_cancel_penalty_account(instruction, &penalty_token_account)))
+    }
+
+    async fn reclaim_expired_instruction(&self, escrow_account: &Pubkey) -> Result<Instruction> {
+        let escrow = self.fetch_escrow(escrow_account).await?;
+        let x_token_program_id = self.token_program_of(&escrow.token_x_mint).await?;
+        Ok(instruction::reclaim_expired(
+            &self.program_id,
+            &escrow.temp_token_account_pubkey,
+            &escrow.initializer_pubkey,
+            &token::get_associated_token_address(&escrow.initializer_pubkey, &escrow.token_x_mint, &x_token_program_id),
+            escrow_account,
+            &x_token_program_id,
+            &escrow.token_x_mint,
+        )?)
+    }
+
+    /// Signs with the payer and the extra signers, sends, and waits for the transaction to be confirmed at the client's commitment
+    async fn send(&self, payer: &Keypair, instructions: &[Instruction], extra_signers: &[&Keypair]) -> Result<Signature> {
+        let mut signers = vec![payer];
+        signers.extend_from_slice(extra_signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &signers,
+            self.rpc_client.get_latest_blockhash().await?,
+        );
+        Ok(self.rpc_client.send_and_confirm_transaction(&transaction).await?)
+    }
+
+    /// The token program of a mint is whichever program owns it
+    async fn token_program_of(&self, mint: &Pubkey) -> Result<Pubkey> {
+        let owner = self.rpc_client.get_account(mint).await?.owner;
+        if !token::is_token_program(&owner) {
+            return Err(ClientError::NotAMint(*mint));
+        }
+        Ok(owner)
+    }
+
+    async fn account_exists(&self, address: &Pubkey) -> Result<bool> {
+        let response = self
+            .rpc_client
+            .get_account_with_commitment(address, self.rpc_client.commitment())
+            .await?;
+        Ok(response.value.is_some())
+    }
+}
+
+fn memcmp(offset: usize, bytes: &[u8]) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, bytes))
+}
@@ -0,0 +1,41 @@
+#!/usr/bin/env bash
+#
+# Compares the compute units InitEscrow and Exchange take in the BPF builds of a base revision and the working tree.
+# Both builds are measured by the working tree's test_compute_units, so the base revision has to understand the
+# instructions its builders produce. Any further arguments go to the working tree's build, so e.g.
+#
+#   ./scripts/compare-compute-units.sh HEAD --features no-alloc-entrypoint
+#
+# compares the default entrypoint with the one that doesn't allocate the account infos.
+#
+
+here="$(dirname "$0")"
+
+base=$1
+if [[ -z $base ]]; then
+  echo "Usage: $0 <base-revision> [cargo build-sbf arguments for the working tree]"
+  exit 1
+fi
+shift
+
+if ! command -v cargo-build-sbf > /dev/null; then
+  echo "cargo-build-sbf not found, it comes with the Solana tool suite"
+  exit 1
+fi
+
+set -e
+
+cd "$here"/..
+
+out_dir="$(mktemp -d)"
+trap 'git worktree remove --force "$out_dir/base"; rm -rf "$out_dir"' EXIT
+
+git worktree add --detach "$out_dir/base" "$base"
+(cd "$out_dir/base" && cargo build-sbf --sbf-out-dir "$out_dir/before")
+cargo build-sbf --sbf-out-dir "$out_dir/after" "$@"
+
+for build in before after; do
+  echo -n "$build: "
+  SBF_OUT_DIR="$out_dir/$build" cargo test --test integration test_compute_units -- --nocapture 2>&1 \
+    | grep "^compute units:"
+done
@@ -0,0 +1,23 @@
+#!/usr/bin/env bash
+#
+# Generates the program's IDL from the shank annotations in src/instruction.rs and src/state.rs
+#
+
+here="$(dirname "$0")"
+
+program_id=$1
+if [[ -z $program_id ]]; then
+  echo "Usage: $0 <program-id>"
+  exit 1
+fi
+
+if ! command -v shank > /dev/null; then
+  echo "shank not found, install it with: cargo install shank-cli"
+  exit 1
+fi
+
+set -e
+
+cd "$here"/..
+
+shank idl --crate-root . --out-dir idl --program-id "$program_id"
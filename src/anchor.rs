@@ -0,0 +1,3150 @@
+//! The program as an Anchor program, built with the `anchor` feature in place of the native entrypoint, so Anchor tooling can generate an IDL and clients for it. Every instruction has a handler of the same name in snake case, which hands the accounts and arguments to the same processor the native entrypoint uses. Instruction data and account layouts are the native ones: the discriminators are the ones `EscrowInstruction::unpack` already accepts, and each Accounts struct lists an instruction's accounts up to its first optional one. Optional accounts, and everything after them, go in the remaining accounts in their usual order. Instruction data that starts with our one byte tag instead goes to the fallback, so clients of the native program work unchanged.
+//!
+//! Anchor's codegen looks up the `Accounts` structs at the crate root, which is why lib.rs re-exports this module. With the `cpi` feature it also expects `crate::cpi::accounts`, and IDL generation the argument structs in `crate::instruction`, see src/cpi.rs and src/instruction.rs.
+
+// The handlers take the fields of their instruction as they are, and so do the CPI helpers Anchor generates from them
+#![allow(clippy::too_many_arguments)]
+
+use anchor_lang::{prelude::*, Bumps};
+use solana_program::{entrypoint::ProgramResult, program_error::PrintProgramError};
+use std::borrow::Cow;
+
+use crate::{compressed_escrow, error::EscrowError, instruction::EscrowInstruction, processor::Processor};
+
+// Replace with the address the program is deployed at, e.g. with `anchor keys sync`
+declare_id!("Escrow1111111111111111111111111111111111111");
+
+fn forward<'info, T: Bumps + ToAccountInfos<'info>>(
+    ctx: Context<'_, '_, '_, 'info, T>,
+    instruction: EscrowInstruction,
+) -> Result<()> {
+    let mut accounts = ctx.accounts.to_account_infos();
+    accounts.extend_from_slice(ctx.remaining_accounts);
+    log_error(Processor::process_instruction(ctx.program_id, &accounts, instruction))
+}
+
+// Logs the EscrowError behind a Custom error code, like the native entrypoint does
+fn log_error(result: ProgramResult) -> Result<()> {
+    result.map_err(|error| {
+        error.print::<EscrowError>();
+        error.into()
+    })
+}
+
+/// The argument of ExchangeCompressed and CancelCompressed. Anchor's derives implement the borsh version in scope, which for the original is the older one this crate depends on.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct CompressedEscrow {
+    pub initializer: Pubkey,
+    pub initializer_token_account: Pubkey,
+    pub initializer_token_to_receive_account: Pubkey,
+    pub token_x_mint: Pubkey,
+    pub token_y_mint: Pubkey,
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub expiry_unix_timestamp: i64,
+    pub leaf_index: u32,
+}
+
+impl From<CompressedEscrow> for compressed_escrow::CompressedEscrow {
+    fn from(escrow: CompressedEscrow) -> Self {
+        Self {
+            initializer: escrow.initializer,
+            initializer_token_account: escrow.initializer_token_account,
+            initializer_token_to_receive_account: escrow.initializer_token_to_receive_account,
+            token_x_mint: escrow.token_x_mint,
+            token_y_mint: escrow.token_y_mint,
+            amount_x: escrow.amount_x,
+            amount_y: escrow.amount_y,
+            expiry_unix_timestamp: escrow.expiry_unix_timestamp,
+            leaf_index: escrow.leaf_index,
+        }
+    }
+}
+
+#[program]
+pub mod escrow {
+    use super::*;
+
+    /// Starts the trade by creating and populating an escrow account and transferring ownership of the given temp token account to the PDA
+    pub fn init_escrow<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitEscrow<'info>>,
+        amount: u64,
+        expiry_unix_timestamp: i64,
+        allowed_taker: Pubkey,
+        deposit_amount: u64,
+        dispute_window_seconds: i64,
+        metadata: Vec<u8>,
+        escrow_id: u64,
+        reservation_deposit: u64,
+        taker_merkle_root: [u8; 32],
+    ) -> Result<()> {
+        forward(
+            ctx,
+            EscrowInstruction::InitEscrow {
+                amount,
+                expiry_unix_timestamp,
+                allowed_taker,
+                deposit_amount,
+                dispute_window_seconds,
+                metadata: Cow::Owned(metadata),
+                escrow_id,
+                reservation_deposit,
+                taker_merkle_root,
+            },
+        )
+    }
+
+    /// Accepts a trade
+    pub fn exchange<'info>(
+        ctx: Context<'_, '_, '_, 'info, Exchange<'info>>,
+        amount: u64,
+        max_payment: u64,
+        referrer_token_account: Pubkey,
+        memo: String,
+        taker_proof: Vec<[u8; 32]>,
+        relayer: Pubkey,
+        relayer_tip: u64,
+    ) -> Result<()> {
+        forward(
+            ctx,
+            EscrowInstruction::Exchange {
+                amount,
+                max_payment,
+                referrer_token_account,
+                memo: Cow::Owned(memo),
+                taker_proof: Cow::Owned(taker_proof),
+                relayer,
+                relayer_tip,
+            },
+        )
+    }
+
+    /// Cancels a trade, returning the escrowed tokens to the initializer
+    pub fn cancel<'info>(ctx: Context<'_, '_, '_, 'info, Cancel<'info>>, memo: String) -> Result<()> {
+        forward(ctx, EscrowInstruction::Cancel { memo: Cow::Owned(memo) })
+    }
+
+    /// Returns the escrowed tokens to the initializer once the escrow's expiry has passed
+    pub fn reclaim_expired<'info>(ctx: Context<'_, '_, '_, 'info, ReclaimExpired<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::ReclaimExpired)
+    }
+
+    /// Same as InitEscrow, but for a non-fungible token: the temp token account must hold exactly 1 token of a mint with 0 decimals and a supply of 1. The NFT can be priced in any token.
+    pub fn init_nft_escrow<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitNftEscrow<'info>>,
+        amount: u64,
+        expiry_unix_timestamp: i64,
+        allowed_taker: Pubkey,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitNftEscrow { amount, expiry_unix_timestamp, allowed_taker })
+    }
+
+    /// Creates the fee vault of a mint, the token account protocol fees in that token are collected into. Anyone can create a vault, the program owns it either way.
+    pub fn init_fee_vault<'info>(ctx: Context<'_, '_, '_, 'info, InitFeeVault<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitFeeVault)
+    }
+
+    /// Creates the config account. Only the program's upgrade authority can do this, otherwise anyone watching the deployment could claim the admin role first.
+    pub fn initialize_config<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitializeConfig<'info>>,
+        fee_bps: u16,
+        default_expiry_seconds: i64,
+        referral_share_bps: u16,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitializeConfig { fee_bps, default_expiry_seconds, referral_share_bps })
+    }
+
+    /// Stops new escrows from being created and open ones from being exchanged. Cancel and ReclaimExpired keep working so initializers can always get their tokens back.
+    pub fn pause<'info>(ctx: Context<'_, '_, '_, 'info, Pause<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::Pause)
+    }
+
+    /// Lifts a Pause
+    pub fn unpause<'info>(ctx: Context<'_, '_, '_, 'info, Unpause<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::Unpause)
+    }
+
+    /// Adds a mint to the allowlist, creating the allowlist account the first time
+    pub fn add_allowed_mint<'info>(ctx: Context<'_, '_, '_, 'info, AddAllowedMint<'info>>, mint: Pubkey) -> Result<()> {
+        forward(ctx, EscrowInstruction::AddAllowedMint { mint })
+    }
+
+    /// Removes a mint from the allowlist. Open escrows of that mint are not affected.
+    pub fn remove_allowed_mint<'info>(
+        ctx: Context<'_, '_, '_, 'info, RemoveAllowedMint<'info>>,
+        mint: Pubkey,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::RemoveAllowedMint { mint })
+    }
+
+    /// Turns the allowlist check in InitEscrow on or off
+    pub fn set_allowlist_enabled<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetAllowlistEnabled<'info>>,
+        enabled: bool,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::SetAllowlistEnabled { enabled })
+    }
+
+    /// Re-prices an open escrow in place, which is cheaper than cancelling it and creating a new one
+    pub fn update_escrow<'info>(
+        ctx: Context<'_, '_, '_, 'info, UpdateEscrow<'info>>,
+        expected_amount: u64,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::UpdateEscrow { expected_amount })
+    }
+
+    /// Same as InitEscrow, but the price starts at start_amount and falls linearly to floor_amount between now and auction_end_unix_timestamp. Exchange settles at the price of the moment it runs.
+    pub fn init_dutch_auction<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitDutchAuction<'info>>,
+        start_amount: u64,
+        floor_amount: u64,
+        auction_end_unix_timestamp: i64,
+        expiry_unix_timestamp: i64,
+    ) -> Result<()> {
+        forward(
+            ctx,
+            EscrowInstruction::InitDutchAuction {
+                start_amount,
+                floor_amount,
+                auction_end_unix_timestamp,
+                expiry_unix_timestamp,
+            },
+        )
+    }
+
+    /// Same as InitEscrow, but instead of being exchanged at a fixed price the escrow collects bids until auction_end_unix_timestamp and is then settled to the highest one
+    pub fn init_english_auction<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitEnglishAuction<'info>>,
+        min_bid: u64,
+        auction_end_unix_timestamp: i64,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitEnglishAuction { min_bid, auction_end_unix_timestamp })
+    }
+
+    /// Places or raises a bid on an English auction. The bid's tokens move into the bid account, a token account owned by the escrow's PDA, and stay there until the bid wins or is withdrawn.
+    pub fn place_bid<'info>(ctx: Context<'_, '_, '_, 'info, PlaceBid<'info>>, amount: u64) -> Result<()> {
+        forward(ctx, EscrowInstruction::PlaceBid { amount })
+    }
+
+    /// Settles an English auction: the highest bidder gets the escrowed tokens and the initializer gets the winning bid
+    pub fn settle_auction<'info>(ctx: Context<'_, '_, '_, 'info, SettleAuction<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::SettleAuction)
+    }
+
+    /// Returns a bid that didn't win, either because it was outbid or because the escrow is gone
+    pub fn withdraw_bid<'info>(ctx: Context<'_, '_, '_, 'info, WithdrawBid<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::WithdrawBid)
+    }
+
+    /// Same as InitEscrow, but instead of a fixed amount the taker pays whatever the escrowed tokens are worth according to a Pyth price feed at the time of the exchange, plus a premium. The feed has to quote token X in token Y, e.g. SOL/USD for an escrow selling SOL for USDC.
+    pub fn init_oracle_escrow<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitOracleEscrow<'info>>,
+        premium_bps: i16,
+        expiry_unix_timestamp: i64,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitOracleEscrow { premium_bps, expiry_unix_timestamp })
+    }
+
+    /// Same as InitEscrow, except that the tokens stay in the initializer's own token account. Instead of handing the account over to the PDA, the initializer approves the PDA as a delegate for amount_x, and Exchange transfers the tokens out of the account as that delegate. This saves creating and paying rent for a temp token account.
+    pub fn init_delegated_escrow<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitDelegatedEscrow<'info>>,
+        amount_x: u64,
+        amount: u64,
+        expiry_unix_timestamp: i64,
+        allowed_taker: Pubkey,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitDelegatedEscrow { amount_x, amount, expiry_unix_timestamp, allowed_taker })
+    }
+
+    /// The first phase of taking a two-phase escrow. The taker pays the escrow's current price into a bid account owned by the PDA, and the escrow is locked to them: it can no longer be cancelled, reclaimed, updated or accepted by anyone else.
+    pub fn accept<'info>(ctx: Context<'_, '_, '_, 'info, Accept<'info>>, max_payment: u64) -> Result<()> {
+        forward(ctx, EscrowInstruction::Accept { max_payment })
+    }
+
+    /// The second phase: the taker gets the escrowed tokens and the initializer gets the payment. Anyone can settle once the escrow's dispute window has passed since it was accepted, before that the initializer and the taker have to sign together.
+    pub fn settle<'info>(ctx: Context<'_, '_, '_, 'info, Settle<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::Settle)
+    }
+
+    /// Requires threshold of the given approvers to sign ApproveRelease before the escrow can be exchanged or settled, e.g. for escrows of a DAO treasury. Approvers can only be set once, and should be set in the same transaction as the escrow is initialized so that nobody can take the escrow in between.
+    pub fn set_approvers<'info>(ctx: Context<'_, '_, '_, 'info, SetApprovers<'info>>, threshold: u8) -> Result<()> {
+        forward(ctx, EscrowInstruction::SetApprovers { threshold })
+    }
+
+    /// Records the signer's approval to release the escrowed tokens
+    pub fn approve_release<'info>(ctx: Context<'_, '_, '_, 'info, ApproveRelease<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::ApproveRelease)
+    }
+
+    /// Same as InitEscrow, except that the taker's tokens vest over time. Exchange moves them into a vesting vault, from which ClaimVested releases them in vesting_periods equal tranches, one every vesting_period_seconds.
+    pub fn init_vesting_escrow<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitVestingEscrow<'info>>,
+        amount: u64,
+        expiry_unix_timestamp: i64,
+        vesting_period_seconds: i64,
+        vesting_periods: u16,
+    ) -> Result<()> {
+        forward(
+            ctx,
+            EscrowInstruction::InitVestingEscrow {
+                amount,
+                expiry_unix_timestamp,
+                vesting_period_seconds,
+                vesting_periods,
+            },
+        )
+    }
+
+    /// Releases the vested tokens of an exchanged vesting escrow to the taker. Anyone can call it, the tokens always go to the taker. Once everything has been claimed, the vault and the vesting account are closed and their rent goes back to the taker.
+    pub fn claim_vested<'info>(ctx: Context<'_, '_, '_, 'info, ClaimVested<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::ClaimVested)
+    }
+
+    /// Locks tokens like InitEscrow, but instead of being exchanged they stream to the recipient at a constant rate per second from now until stream_end_unix_timestamp. The recipient takes what has accrued with Withdraw, and a Cancel pays them the accrued part and returns the rest. Nothing is paid for the tokens, so no token Y is involved, and streams don't expire.
+    pub fn init_stream_escrow<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitStreamEscrow<'info>>,
+        recipient: Pubkey,
+        stream_end_unix_timestamp: i64,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitStreamEscrow { recipient, stream_end_unix_timestamp })
+    }
+
+    /// Pays the recipient of a streaming escrow everything that has accrued since their last withdrawal. Once the stream has ended and been withdrawn in full, the escrow is closed.
+    pub fn withdraw<'info>(ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::Withdraw)
+    }
+
+    /// Settles two escrows that offer each other's tokens against each other: escrow A offers X for Y, escrow B offers Y for X. It goes through if each escrow offers at least what the other one expects, and then each initializer gets everything the other escrow offered. Nobody acts as the taker and anyone can send it, so no protocol fee is charged. Auctions, two-phase, oracle, vesting and streaming escrows can't be matched.
+    pub fn match_escrows<'info>(ctx: Context<'_, '_, '_, 'info, MatchEscrows<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::MatchEscrows)
+    }
+
+    /// Creates the order book of a mint pair. Anyone can create a market, paying its rent.
+    pub fn init_market<'info>(ctx: Context<'_, '_, '_, 'info, InitMarket<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitMarket)
+    }
+
+    /// Lists an escrow on the market of its mint pair: as an ask if it offers the base mint, as a bid if it offers the quote mint. Only fixed-price escrows that anyone can take can be listed.
+    pub fn place_order<'info>(ctx: Context<'_, '_, '_, 'info, PlaceOrder<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::PlaceOrder)
+    }
+
+    /// Takes an escrow's order off its market. The initializer has to sign as long as the escrow is open. Once it has been exchanged or cancelled anyone can remove its stale order.
+    pub fn cancel_order<'info>(ctx: Context<'_, '_, '_, 'info, CancelOrder<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::CancelOrder)
+    }
+
+    /// Matches the best ask against the best bid of a market with MatchEscrows and removes both orders. Anyone can crank it. Fails if the two escrows don't match, see MatchEscrows.
+    pub fn consume_orders<'info>(ctx: Context<'_, '_, '_, 'info, ConsumeOrders<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::ConsumeOrders)
+    }
+
+    /// Creates the registry of open escrows that offer token X for token Y. Anyone can create a registry, paying its rent.
+    pub fn init_registry<'info>(ctx: Context<'_, '_, '_, 'info, InitRegistry<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitRegistry)
+    }
+
+    /// Removes escrows that have been closed without the registry being passed along, e.g. by a Settle or MatchEscrows. Escrows that are still open stay in the registry. Anyone can send it.
+    pub fn prune_registry<'info>(ctx: Context<'_, '_, '_, 'info, PruneRegistry<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::PruneRegistry)
+    }
+
+    /// Upgrades a V1 escrow to the current layout, see state::VersionedEscrow. The escrow keeps its address and terms: the account is upgraded like by UpgradeEscrowAccount, and the temp token account is handed from the legacy PDA to the escrow's own. Escrows that went through UpgradeEscrowAccount already only have their temp token account handed over. Anyone can send it, paying for the extra rent.
+    pub fn migrate_escrow<'info>(ctx: Context<'_, '_, '_, 'info, MigrateEscrow<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::MigrateEscrow)
+    }
+
+    /// Closes an escrow that can never be exchanged because its temp token account was closed, emptied or taken out of the PDA's hands, or, for a delegated escrow, because the PDA's allowance was revoked or reduced. Like ReclaimExpired anyone can send it, and the rent goes back to the initializer. A temp token account that is still the PDA's is closed along with the escrow.
+    pub fn close_stale_escrow<'info>(ctx: Context<'_, '_, '_, 'info, CloseStaleEscrow<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::CloseStaleEscrow)
+    }
+
+    /// Reports an escrow's mints, remaining amounts, expiry and status as return data, a Borsh encoded view::EscrowView. Nothing is written, so other programs can call it through CPI and clients can simulate it instead of decoding the escrow account themselves.
+    pub fn get_escrow<'info>(ctx: Context<'_, '_, '_, 'info, GetEscrow<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::GetEscrow)
+    }
+
+    /// Same as InitEscrow, but the escrow's terms are a rate rather than a total: price_numerator of token Y for every price_denominator of token X. Takers can take any part of the escrowed amount, paying the rate rounded up, and the escrow stays open until all of it is taken. Wrapped SOL can't be offered this way, as it is paid out by closing the temp token account.
+    pub fn init_rate_escrow<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitRateEscrow<'info>>,
+        price_numerator: u64,
+        price_denominator: u64,
+        expiry_unix_timestamp: i64,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitRateEscrow { price_numerator, price_denominator, expiry_unix_timestamp })
+    }
+
+    /// Changes the protocol fee and who it is paid to. Only the admin can do this.
+    pub fn set_fee<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetFee<'info>>,
+        fee_bps: u16,
+        fee_collector: Pubkey,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::SetFee { fee_bps, fee_collector })
+    }
+
+    /// Creates the stats account, which counts the escrows created, filled and cancelled and adds up the volume they moved. Anyone can create it, paying its rent, but there is only one.
+    pub fn init_stats<'info>(ctx: Context<'_, '_, '_, 'info, InitStats<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitStats)
+    }
+
+    /// Reserves an escrow for its taker, who locks the escrow's reservation_deposit in lamports to be the only one who can exchange it for the given number of slots. The initializer can't cancel or update a reserved escrow either. If the taker exchanges in time, the deposit is refunded. Otherwise it is forfeited to the initializer, when the escrow is next reserved, exchanged or closed.
+    pub fn reserve<'info>(ctx: Context<'_, '_, '_, 'info, Reserve<'info>>, slots: u64) -> Result<()> {
+        forward(ctx, EscrowInstruction::Reserve { slots })
+    }
+
+    /// Sets the penalty an initializer pays, in token X, for cancelling an escrow, to discourage posting offers only to fish for prices. It is the flat amount plus the share in basis points, capped at what the initializer would get back. Only the admin can do this.
+    pub fn set_cancel_penalty<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetCancelPenalty<'info>>,
+        penalty_bps: u16,
+        penalty_amount: u64,
+        to_counterparty: bool,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::SetCancelPenalty { penalty_bps, penalty_amount, to_counterparty })
+    }
+
+    /// Sets the gate program that has to approve every taker before Exchange, Accept or PlaceBid goes through, e.g. a KYC or credential program. See gate for the instruction it is called with. Only the admin can do this.
+    pub fn set_gate_program<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetGateProgram<'info>>,
+        gate_program: Pubkey,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::SetGateProgram { gate_program })
+    }
+
+    /// Starts a collection offer: the initializer escrows the tokens they pay and anyone holding an NFT of a verified Metaplex collection can sell it to them for those tokens with FillCollectionOffer. The escrow's token X is the payment, and it has no token Y since the NFT isn't known yet.
+    pub fn init_collection_offer<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitCollectionOffer<'info>>,
+        collection: Pubkey,
+        expiry_unix_timestamp: i64,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitCollectionOffer { collection, expiry_unix_timestamp })
+    }
+
+    /// Sells an NFT of the collection to a collection offer: the NFT goes to the initializer and the escrowed payment to the seller, who is the taker here. The NFT's metadata has to name the offer's collection and have it verified.
+    pub fn fill_collection_offer<'info>(ctx: Context<'_, '_, '_, 'info, FillCollectionOffer<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::FillCollectionOffer)
+    }
+
+    /// Same as InitEscrow, but the initializer escrows lamports rather than tokens. They are moved straight into the escrow's PDA, a plain system account, so no temp token account or wrapped SOL is involved, and Exchange moves them straight to the taker. The escrow's token X mint is left as the system program's id, which is how lamport escrows are told apart.
+    pub fn init_lamport_escrow<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitLamportEscrow<'info>>,
+        lamports: u64,
+        amount: u64,
+        expiry_unix_timestamp: i64,
+        allowed_taker: Pubkey,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitLamportEscrow { lamports, amount, expiry_unix_timestamp, allowed_taker })
+    }
+
+    /// Starts a buy escrow: the initializer escrows the tokens they pay with and names how much of the token they want for all of it. Takers holding that token can sell it to them in full or in part, getting the same share of the payment, so it works like an InitRateEscrow with price_numerator amount and price_denominator the escrowed amount. A payment in wrapped SOL can't be taken in part, so it is only sold for the whole amount.
+    pub fn init_buy_escrow<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitBuyEscrow<'info>>,
+        amount: u64,
+        expiry_unix_timestamp: i64,
+        allowed_taker: Pubkey,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitBuyEscrow { amount, expiry_unix_timestamp, allowed_taker })
+    }
+
+    /// Undoes Accept on a two-phase escrow that both sides want out of. The escrow is open again afterwards, so the taker can get their payment back with WithdrawBid and the initializer can Cancel, e.g. in the same transaction.
+    pub fn abort<'info>(ctx: Context<'_, '_, '_, 'info, Abort<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::Abort)
+    }
+
+    /// Pushes an open escrow's expiry back, which is cheaper than cancelling it and creating a new one. The expiry can only move later, and no further ahead than the config's max_expiry_seconds.
+    pub fn extend_deadline<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExtendDeadline<'info>>,
+        expiry_unix_timestamp: i64,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::ExtendDeadline { expiry_unix_timestamp })
+    }
+
+    /// Limits how far ahead escrows may expire, see ExtendDeadline. Applies to escrows initialized from then on, existing ones keep their expiry. Only the admin can do this.
+    pub fn set_max_expiry<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetMaxExpiry<'info>>,
+        max_expiry_seconds: i64,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::SetMaxExpiry { max_expiry_seconds })
+    }
+
+    /// Hands an escrow over to a new initializer, who from then on is paid when it is taken and gets the tokens and the rent back when it is cancelled. Delegated escrows can't be transferred, since their tokens stay in the current initializer's own token account.
+    pub fn transfer_escrow<'info>(ctx: Context<'_, '_, '_, 'info, TransferEscrow<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::TransferEscrow)
+    }
+
+    /// Exchanges an escrow at the price of an order its initializer signed off-chain, see signed_order. The order is for the whole escrow, and the signed price replaces the escrow's own, which still holds for Exchange. An instruction of the ed25519 program verifying the initializer's signature of signed_order::message has to come right before this one.
+    pub fn settle_signed_order<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleSignedOrder<'info>>,
+        amount: u64,
+        max_payment: u64,
+        price: u64,
+        order_taker: Pubkey,
+        order_expiry_unix_timestamp: i64,
+    ) -> Result<()> {
+        forward(
+            ctx,
+            EscrowInstruction::SettleSignedOrder {
+                amount,
+                max_payment,
+                price,
+                order_taker,
+                order_expiry_unix_timestamp,
+            },
+        )
+    }
+
+    /// Adds, updates or removes a mint the escrow accepts as payment besides token Y. A taker pays in it by passing token accounts of it, and its mint, to Exchange instead of token Y's. `amount` is the price in the option's mint: for the whole escrow, or for rate-based escrows the price_numerator of the rate, whose price_denominator stays the escrow's. 0 removes the option. Auctions, oracle, two-phase, streaming escrows and collection offers can't have payment options, and an escrow has at most MAX_PAYMENT_OPTIONS.
+    pub fn set_payment_option<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetPaymentOption<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::SetPaymentOption { amount })
+    }
+
+    /// Adds a mint to the StablecoinList that USD-priced escrows are paid in, or updates its price feed and tolerance, creating the list account the first time
+    pub fn add_stablecoin<'info>(
+        ctx: Context<'_, '_, '_, 'info, AddStablecoin<'info>>,
+        mint: Pubkey,
+        price_feed: Pubkey,
+        tolerance_bps: u16,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::AddStablecoin { mint, price_feed, tolerance_bps })
+    }
+
+    /// Removes a mint from the StablecoinList. USD-priced escrows can't be paid in it anymore.
+    pub fn remove_stablecoin<'info>(
+        ctx: Context<'_, '_, '_, 'info, RemoveStablecoin<'info>>,
+        mint: Pubkey,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::RemoveStablecoin { mint })
+    }
+
+    /// Prices an open escrow in USD, or with 0 back in token Y. Exchange then takes any mint on the StablecoinList, token Y included if it is on it, converted at the price of the mint's feed, which has to be within the mint's tolerance of one dollar. The taker passes the stablecoin's accounts in place of token Y's, and the initializer's token account of it is paid, whichever it is. Only escrows that could have payment options, but have none, and aren't rate-based can be priced in USD.
+    pub fn set_usd_price<'info>(ctx: Context<'_, '_, '_, 'info, SetUsdPrice<'info>>, usd_amount: u64) -> Result<()> {
+        forward(ctx, EscrowInstruction::SetUsdPrice { usd_amount })
+    }
+
+    /// Creates an escrow that swaps one NFT for another: the initializer escrows their NFT, like InitNftEscrow, and only takes the NFT of a given mint in return. The expected amount is 1 and token Y the wanted NFT's mint, so Exchange swaps both NFTs in one go, without a protocol fee or royalties, which round down to nothing on a single token. The wanted NFT can't be a programmable NFT, since the taker's NFT is moved by the token program.
+    pub fn init_nft_swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitNftSwap<'info>>,
+        expiry_unix_timestamp: i64,
+        allowed_taker: Pubkey,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitNftSwap { expiry_unix_timestamp, allowed_taker })
+    }
+
+    /// Lays out a swap ring, see swap_ring: each participant gives their leg's amount of its mint to the participant of the next leg, and the last participant to the first. Nothing moves until every participant has deposited their leg and the ring is executed.
+    pub fn init_ring<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitRing<'info>>,
+        ring_id: u64,
+        deadline_unix_timestamp: i64,
+        amounts: Vec<u64>,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitRing { ring_id, deadline_unix_timestamp, amounts })
+    }
+
+    /// Deposits the signing participant's leg of a swap ring into the leg's vault, which is created with the participant paying the rent. Depositing is how a participant agrees to the ring, so it can only be done before the ring's deadline.
+    pub fn deposit_ring_leg<'info>(ctx: Context<'_, '_, '_, 'info, DepositRingLeg<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::DepositRingLeg)
+    }
+
+    /// Pays every vault of a swap ring whose legs have all been deposited out to the participant of the next leg, then closes the vaults and the ring. Anyone can execute a complete ring, even after its deadline as long as nobody has withdrawn.
+    pub fn execute_ring<'info>(ctx: Context<'_, '_, '_, 'info, ExecuteRing<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::ExecuteRing)
+    }
+
+    /// Returns the signing participant's deposit from a swap ring whose deadline has passed without it being executed, and closes the leg's vault.
+    pub fn withdraw_ring_leg<'info>(ctx: Context<'_, '_, '_, 'info, WithdrawRingLeg<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::WithdrawRingLeg)
+    }
+
+    /// Closes a swap ring that holds no deposits, e.g. one that expired and was withdrawn from, and returns its rent to the creator.
+    pub fn close_ring<'info>(ctx: Context<'_, '_, '_, 'info, CloseRing<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::CloseRing)
+    }
+
+    /// Quotes a price for an open escrow, for initializers who would rather have takers compete than set the price themselves. The quoter offers price of token Y for the escrow's size of token X until the quote's expiry, and the initializer settles with whichever quote they like with AcceptQuote. The quote account is created with the quoter paying the rent, so each quoter has one quote per escrow at a time. The price is either locked in the quote's vault, or the quote PDA is approved as delegate of it on the quoter's token account, which replaces any other delegate of that account. Only escrows that MatchEscrows could settle can be quoted.
+    pub fn post_quote<'info>(
+        ctx: Context<'_, '_, '_, 'info, PostQuote<'info>>,
+        size: u64,
+        price: u64,
+        expiry_unix_timestamp: i64,
+        lock_funds: bool,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::PostQuote { size, price, expiry_unix_timestamp, lock_funds })
+    }
+
+    /// Settles the escrow with a quote on it: the quoter's price goes to the initializer and the escrowed tokens to the quoter, and the escrow, the quote and a locked quote's vault are closed. Like MatchEscrows, no protocol fee is charged.
+    pub fn accept_quote<'info>(ctx: Context<'_, '_, '_, 'info, AcceptQuote<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::AcceptQuote)
+    }
+
+    /// Withdraws a quote, returning a locked price to the quoter's token account or revoking the quote PDA's allowance, and closes the quote. Quotes on escrows that have been settled otherwise are withdrawn the same way.
+    pub fn cancel_quote<'info>(ctx: Context<'_, '_, '_, 'info, CancelQuote<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::CancelQuote)
+    }
+
+    /// Proposes a different price for an open escrow: the taker offers amount of token Y for the whole escrow instead of its expected amount, and the initializer can take them up on it with AcceptCounteroffer. The counteroffer account is created with the taker paying the rent, and the counteroffer PDA is approved as delegate of the amount on the taker's token account, which replaces any other delegate of that account. Proposing again changes the counteroffer, and an amount of 0 withdraws it, revoking the allowance and closing the account. Only escrows that MatchEscrows could settle take counteroffers.
+    pub fn propose_counteroffer<'info>(
+        ctx: Context<'_, '_, '_, 'info, ProposeCounteroffer<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::ProposeCounteroffer { amount })
+    }
+
+    /// Agrees to a counteroffer: the escrow's expected amount becomes the counteroffer's, and the escrow is settled with the taker right away, the amount going to the initializer and the escrowed tokens to the taker. The escrow and the counteroffer are closed. Like MatchEscrows, no protocol fee is charged.
+    pub fn accept_counteroffer<'info>(ctx: Context<'_, '_, '_, 'info, AcceptCounteroffer<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::AcceptCounteroffer)
+    }
+
+    /// Same as InitEnglishAuction, but the bids are sealed: bidders commit to a hash of their bid with CommitBid until commit_end_unix_timestamp, reveal it with RevealBid until reveal_end_unix_timestamp, and the escrow then settles to the best revealed bid with SettleAuction. Every commitment locks a deposit of token Y, which is slashed to the initializer if the bid isn't revealed in time. See sealed_auction.
+    pub fn init_sealed_auction<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitSealedAuction<'info>>,
+        min_bid: u64,
+        deposit: u64,
+        commit_end_unix_timestamp: i64,
+        reveal_end_unix_timestamp: i64,
+    ) -> Result<()> {
+        forward(
+            ctx,
+            EscrowInstruction::InitSealedAuction {
+                min_bid,
+                deposit,
+                commit_end_unix_timestamp,
+                reveal_end_unix_timestamp,
+            },
+        )
+    }
+
+    /// Commits to a bid on a sealed-bid auction before its commit window closes. The sealed bid account and its vault are created with the bidder paying the rent, and the auction's deposit moves into the vault. Committing again replaces the commitment without another deposit.
+    pub fn commit_bid<'info>(ctx: Context<'_, '_, '_, 'info, CommitBid<'info>>, commitment: [u8; 32]) -> Result<()> {
+        forward(ctx, EscrowInstruction::CommitBid { commitment })
+    }
+
+    /// Reveals a sealed bid during the reveal window. The deposit goes back to the bidder and the sealed bid and its vault are closed. If the escrow is still open and the bid is at least the minimum bid and beats the highest bid revealed so far, the bid moves into the bidder's bid account and becomes the highest bid, which outbid bidders withdraw with WithdrawBid as usual.
+    pub fn reveal_bid<'info>(
+        ctx: Context<'_, '_, '_, 'info, RevealBid<'info>>,
+        amount: u64,
+        salt: [u8; 32],
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::RevealBid { amount, salt })
+    }
+
+    /// Slashes a sealed bid that wasn't revealed before the reveal window closed: its deposit goes to the initializer's token account recorded in the auction, and the sealed bid and its vault are closed with their rent going back to the bidder. Anyone can slash.
+    pub fn slash_bid<'info>(ctx: Context<'_, '_, '_, 'info, SlashBid<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::SlashBid)
+    }
+
+    /// Closes a sealed-bid auction whose escrow is closed, by settlement or otherwise, once every commitment has been revealed or slashed, and returns its rent to the initializer.
+    pub fn close_sealed_auction<'info>(ctx: Context<'_, '_, '_, 'info, CloseSealedAuction<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::CloseSealedAuction)
+    }
+
+    /// Same as InitEscrow, but the escrow is raffled: takers buy tickets at ticket_price of token Y with BuyTickets until end_unix_timestamp or until max_tickets are sold, and the raffle is then drawn with DrawRaffle and settled with SettleRaffle to the buyer of a random ticket. See raffle.
+    pub fn init_raffle<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitRaffle<'info>>,
+        ticket_price: u64,
+        max_tickets: u32,
+        end_unix_timestamp: i64,
+        vrf: Pubkey,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitRaffle { ticket_price, max_tickets, end_unix_timestamp, vrf })
+    }
+
+    /// Buys count tickets of a raffle, numbered from the raffle's tickets sold so far on. Their price moves into the raffle's vault, and the ticket account recording them is created with the buyer paying the rent.
+    pub fn buy_tickets<'info>(ctx: Context<'_, '_, '_, 'info, BuyTickets<'info>>, count: u32) -> Result<()> {
+        forward(ctx, EscrowInstruction::BuyTickets { count })
+    }
+
+    /// Commits a raffle that has ended or sold out to the slot whose hash picks its winner, raffle::DRAW_DELAY_SLOTS from now. A raffle whose draw slot has dropped out of the SlotHashes sysvar before it was settled can be drawn again. Anyone can draw. Raffles drawn with VRF use RequestRaffleRandomness instead.
+    pub fn draw_raffle<'info>(ctx: Context<'_, '_, '_, 'info, DrawRaffle<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::DrawRaffle)
+    }
+
+    /// Settles a drawn raffle once its draw slot has passed: the buyer of the winning ticket gets the escrowed tokens and the initializer the ticket sales, and the escrow, the raffle, its vault and the winning ticket account are closed. Anyone can settle, and no protocol fee is charged.
+    pub fn settle_raffle<'info>(ctx: Context<'_, '_, '_, 'info, SettleRaffle<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::SettleRaffle)
+    }
+
+    /// Closes the raffle of an escrow that was closed without selling a ticket, e.g. cancelled, and returns the rent of the raffle and its vault to the initializer who created it.
+    pub fn close_raffle<'info>(ctx: Context<'_, '_, '_, 'info, CloseRaffle<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::CloseRaffle)
+    }
+
+    /// Closes a losing ticket account once its raffle has been settled and returns its rent to the buyer.
+    pub fn close_ticket<'info>(ctx: Context<'_, '_, '_, 'info, CloseTicket<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::CloseTicket)
+    }
+
+    /// Requests randomness for a raffle drawn with Switchboard VRF once it has ended or sold out, with the raffle signing as the VRF's authority. Anyone can request, paying the oracle's fee. A request that went unanswered for raffle::MAX_ENTRIES slots can be made again.
+    pub fn request_raffle_randomness<'info>(
+        ctx: Context<'_, '_, '_, 'info, RequestRaffleRandomness<'info>>,
+        permission_bump: u8,
+        state_bump: u8,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::RequestRaffleRandomness { permission_bump, state_bump })
+    }
+
+    /// Stores the verified result of a raffle's VRF request in the raffle, after which it can be settled with SettleRaffle. Meant as the VRF's callback, but anyone can send it.
+    pub fn consume_raffle_randomness<'info>(
+        ctx: Context<'_, '_, '_, 'info, ConsumeRaffleRandomness<'info>>,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::ConsumeRaffleRandomness)
+    }
+
+    /// Initializes an escrow pool, which holds many offers of token X for token Y by the same maker in one account. See escrow_pool.
+    pub fn init_escrow_pool<'info>(ctx: Context<'_, '_, '_, 'info, InitEscrowPool<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitEscrowPool)
+    }
+
+    /// Posts an offer of amount_x of token X for amount_y of token Y to an escrow pool, moving the token X into the pool's vault. The number of the entry it was posted to is set as the return data, as a little-endian u32.
+    pub fn post_pool_entry<'info>(
+        ctx: Context<'_, '_, '_, 'info, PostPoolEntry<'info>>,
+        amount_x: u64,
+        amount_y: u64,
+        expiry_unix_timestamp: i64,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::PostPoolEntry { amount_x, amount_y, expiry_unix_timestamp })
+    }
+
+    /// Takes an entry of an escrow pool whole. Like Exchange, amount_x has to be the amount of token X the entry offers, which keeps a taker from getting a different offer that was posted to the same entry, and the taker pays the protocol fee on top of the entry's amount_y, up to max_payment in total.
+    pub fn take_pool_entry<'info>(
+        ctx: Context<'_, '_, '_, 'info, TakePoolEntry<'info>>,
+        index: u32,
+        amount_x: u64,
+        max_payment: u64,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::TakePoolEntry { index, amount_x, max_payment })
+    }
+
+    /// Cancels an entry of an escrow pool and returns its token X to the maker. Expired entries have to be cancelled this way too.
+    pub fn cancel_pool_entry<'info>(ctx: Context<'_, '_, '_, 'info, CancelPoolEntry<'info>>, index: u32) -> Result<()> {
+        forward(ctx, EscrowInstruction::CancelPoolEntry { index })
+    }
+
+    /// Closes an escrow pool without open entries, returning the rent of the pool and its vault to the maker
+    pub fn close_escrow_pool<'info>(ctx: Context<'_, '_, '_, 'info, CloseEscrowPool<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::CloseEscrowPool)
+    }
+
+    /// Sets up a concurrent merkle tree of the spl-account-compression program for compressed escrows to be listed in, see compressed_escrow. The tree's config becomes its authority.
+    pub fn init_compressed_tree<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitCompressedTree<'info>>,
+        max_depth: u32,
+        max_buffer_size: u32,
+        canopy_depth: u32,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitCompressedTree { max_depth, max_buffer_size, canopy_depth })
+    }
+
+    /// Lists a compressed escrow of amount_x of token X for amount_y of token Y by appending its leaf to a tree. The token X stays in the initializer's account, and the tree's config is approved for another amount_x of it.
+    pub fn init_compressed_escrow<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitCompressedEscrow<'info>>,
+        amount_x: u64,
+        amount_y: u64,
+        expiry_unix_timestamp: i64,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::InitCompressedEscrow { amount_x, amount_y, expiry_unix_timestamp })
+    }
+
+    /// Exchanges a compressed escrow as a whole. The escrow is passed in as it was emitted when it was listed, and its leaf is cleared against root, a recent root of the tree, with the proof passed as accounts. The taker pays the protocol fee on top of amount_y, up to max_payment in total.
+    pub fn exchange_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExchangeCompressed<'info>>,
+        escrow: CompressedEscrow,
+        root: [u8; 32],
+        max_payment: u64,
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::ExchangeCompressed { escrow: escrow.into(), root, max_payment })
+    }
+
+    /// Cancels a compressed escrow by clearing its leaf, and lowers the tree config's allowance of the initializer's token X by amount_x. Expired escrows have to be cancelled this way too.
+    pub fn cancel_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, CancelCompressed<'info>>,
+        escrow: CompressedEscrow,
+        root: [u8; 32],
+    ) -> Result<()> {
+        forward(ctx, EscrowInstruction::CancelCompressed { escrow: escrow.into(), root })
+    }
+
+    /// Grows a V1 escrow account to the current layout in place with realloc, see state::VersionedEscrow. The V1 fields are moved to where the current layout has them, the mints are read from the token accounts, and the fields V1 lacked, like the expiry and the metadata, start out empty. Anyone can send it, paying for the extra rent.
+    pub fn upgrade_escrow_account<'info>(ctx: Context<'_, '_, '_, 'info, UpgradeEscrowAccount<'info>>) -> Result<()> {
+        forward(ctx, EscrowInstruction::UpgradeEscrowAccount)
+    }
+
+    /// Instructions that start with a one byte tag rather than a discriminator
+    pub fn fallback<'info>(program_id: &Pubkey, accounts: &'info [AccountInfo<'info>], data: &[u8]) -> Result<()> {
+        log_error(Processor::process(program_id, accounts, data))
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitEscrow<'info> {
+    /// The account of the person initializing the escrow
+    pub initializer: Signer<'info>,
+    /// Temporary token account holding the tokens to escrow, owned by the initializer
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account for the token they will receive
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account, it will hold all necessary info about the trade
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Exchange<'info> {
+    /// The account of the person taking the trade
+    pub taker: Signer<'info>,
+    /// The taker's token account for the token they send
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub takers_sending_token_account: UncheckedAccount<'info>,
+    /// The taker's token account for the token they will receive
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub takers_token_to_receive_account: UncheckedAccount<'info>,
+    /// The PDA's temp token account to get tokens from and eventually close
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub pdas_temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's main account to send their rent fees to
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_main_account: UncheckedAccount<'info>,
+    /// The initializer's token account that will receive tokens
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program of the token the taker receives
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The escrow's PDA
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+    /// The mint of the token the taker receives
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The mint of the token the taker sends
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of the token the taker sends
+    /// CHECK: checked by the processor
+    pub y_token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Cancel<'info> {
+    /// The account of the person who initialized the escrow
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    /// The PDA's temp token account to get tokens from and eventually close
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub pdas_temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account that will receive the returned tokens
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_token_to_return_account: UncheckedAccount<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The escrow's PDA
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+    /// The mint of the escrowed tokens
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimExpired<'info> {
+    /// The PDA's temp token account to get tokens from and eventually close
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub pdas_temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's main account to send their rent fees to
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_main_account: UncheckedAccount<'info>,
+    /// The initializer's token account that will receive the returned tokens
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_token_to_return_account: UncheckedAccount<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The escrow's PDA
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+    /// The mint of the escrowed tokens
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitNftEscrow<'info> {
+    /// The account of the person initializing the escrow
+    pub initializer: Signer<'info>,
+    /// Temporary token account holding the NFT, owned by the initializer
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account for the token they will receive
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account, it will hold all necessary info about the trade
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+    /// The NFT's mint
+    /// CHECK: checked by the processor
+    pub nft_mint: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitFeeVault<'info> {
+    /// The account paying for the fee vault's rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The fee vault of the mint
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub fee_vault: UncheckedAccount<'info>,
+    /// The mint of the fees the vault collects
+    /// CHECK: checked by the processor
+    pub mint: UncheckedAccount<'info>,
+    /// The PDA that owns every fee vault
+    /// CHECK: checked by the processor
+    pub fee_authority: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+    /// The token program that owns the mint
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The rent sysvar
+    /// CHECK: checked by the processor
+    pub rent: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    /// The admin, who has to be the program's upgrade authority
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+    /// The program's program data account
+    /// CHECK: checked by the processor
+    pub program_data: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Pause<'info> {
+    /// The admin stored in the config account
+    pub admin: Signer<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Unpause<'info> {
+    /// The admin stored in the config account
+    pub admin: Signer<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddAllowedMint<'info> {
+    /// The admin stored in the config account
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub allowlist: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAllowedMint<'info> {
+    /// The admin stored in the config account
+    pub admin: Signer<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub allowlist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAllowlistEnabled<'info> {
+    /// The admin stored in the config account
+    pub admin: Signer<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateEscrow<'info> {
+    /// The account of the person who initialized the escrow
+    pub initializer: Signer<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitDutchAuction<'info> {
+    /// The account of the person initializing the escrow
+    pub initializer: Signer<'info>,
+    /// Temporary token account holding the tokens to escrow, owned by the initializer
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account for the token they will receive
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account, it will hold all necessary info about the trade
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitEnglishAuction<'info> {
+    /// The account of the person initializing the escrow
+    pub initializer: Signer<'info>,
+    /// Temporary token account holding the tokens to escrow, owned by the initializer
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account for the token they will receive
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account, it will hold all necessary info about the trade
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    /// The bidder
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    /// The bidder's token account for the token they pay with
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub bidders_token_account: UncheckedAccount<'info>,
+    /// The bidder's bid account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub bid_account: UncheckedAccount<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The escrow's PDA
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+    /// The mint of the token the bidder pays with
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of the token the bidder pays with
+    /// CHECK: checked by the processor
+    pub y_token_program: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+    /// The rent sysvar
+    /// CHECK: checked by the processor
+    pub rent: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+    /// The account settling the auction
+    /// CHECK: checked by the processor
+    pub settler: UncheckedAccount<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The escrow's PDA
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+    /// The PDA's temp token account to get tokens from and eventually close
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub pdas_temp_token_account: UncheckedAccount<'info>,
+    /// The highest bidder's token account that will receive the escrowed tokens
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub winners_token_to_receive_account: UncheckedAccount<'info>,
+    /// The highest bidder's bid account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub bid_account: UncheckedAccount<'info>,
+    /// The highest bidder's main account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub winner: UncheckedAccount<'info>,
+    /// The initializer's token account that will receive the winning bid
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_token_to_receive_account: UncheckedAccount<'info>,
+    /// The initializer's main account to send their rent fees to
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_main_account: UncheckedAccount<'info>,
+    /// The token program of token X
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub y_token_program: UncheckedAccount<'info>,
+    /// The mint of token X
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawBid<'info> {
+    /// The bidder
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    /// The bidder's bid account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub bid_account: UncheckedAccount<'info>,
+    /// The bidder's token account that will receive the bid back
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub bidders_token_account: UncheckedAccount<'info>,
+    /// The escrow account the bid was placed on
+    /// CHECK: checked by the processor
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The escrow's PDA
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+    /// The mint of the bid's token
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of the bid's token
+    /// CHECK: checked by the processor
+    pub y_token_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitOracleEscrow<'info> {
+    /// The account of the person initializing the escrow
+    pub initializer: Signer<'info>,
+    /// Temporary token account holding the tokens to escrow, owned by the initializer
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account for the token they will receive
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account, it will hold all necessary info about the trade
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+    /// The Pyth price account quoting token X in token Y
+    /// CHECK: checked by the processor
+    pub price_feed: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitDelegatedEscrow<'info> {
+    /// The account of the person initializing the escrow
+    pub initializer: Signer<'info>,
+    /// The initializer's token account holding the tokens to escrow
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account for the token they will receive
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account, it will hold all necessary info about the trade
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the initializer's token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+    /// The escrow's PDA, which the tokens are delegated to
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Accept<'info> {
+    /// The taker
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    /// The taker's token account for the token they pay with
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub takers_token_account: UncheckedAccount<'info>,
+    /// The bid account that holds the payment
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub bid_account: UncheckedAccount<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The escrow's PDA
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+    /// The mint of the token the taker pays with
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of the token the taker pays with
+    /// CHECK: checked by the processor
+    pub y_token_program: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+    /// The rent sysvar
+    /// CHECK: checked by the processor
+    pub rent: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Settle<'info> {
+    /// The account settling the escrow
+    /// CHECK: checked by the processor
+    pub settler: UncheckedAccount<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The escrow's PDA
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+    /// The PDA's temp token account to get tokens from and eventually close
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub pdas_temp_token_account: UncheckedAccount<'info>,
+    /// The taker's token account that will receive the escrowed tokens
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub takers_token_to_receive_account: UncheckedAccount<'info>,
+    /// The taker's bid account holding the payment
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub bid_account: UncheckedAccount<'info>,
+    /// The taker's main account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub taker: UncheckedAccount<'info>,
+    /// The initializer's token account that will receive the payment
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_token_to_receive_account: UncheckedAccount<'info>,
+    /// The initializer's main account to send their rent fees to
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_main_account: UncheckedAccount<'info>,
+    /// The token program of token X
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub y_token_program: UncheckedAccount<'info>,
+    /// The mint of token X
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetApprovers<'info> {
+    /// The account of the person who initialized the escrow
+    pub initializer: Signer<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The first approver, followed by the others
+    /// CHECK: checked by the processor
+    pub approver: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveRelease<'info> {
+    /// One of the escrow's approvers
+    pub approver: Signer<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitVestingEscrow<'info> {
+    /// The account of the person initializing the escrow
+    pub initializer: Signer<'info>,
+    /// Temporary token account holding the tokens to escrow, owned by the initializer
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account for the token they will receive
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account, it will hold all necessary info about the trade
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    /// The vesting account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub vesting_account: UncheckedAccount<'info>,
+    /// The vesting vault
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    /// The taker's token account that will receive the vested tokens
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub beneficiarys_token_account: UncheckedAccount<'info>,
+    /// The taker's main account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub beneficiary: UncheckedAccount<'info>,
+    /// The token program of the vested token
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The mint of the vested token
+    /// CHECK: checked by the processor
+    pub mint: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitStreamEscrow<'info> {
+    /// The account of the person initializing the escrow
+    pub initializer: Signer<'info>,
+    /// Temporary token account holding the tokens to stream, owned by the initializer
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// Any token account of the initializer's
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account, it will hold all necessary info about the stream
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    /// The recipient of the stream
+    pub recipient: Signer<'info>,
+    /// The recipient's token account for the streamed token
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub recipients_token_account: UncheckedAccount<'info>,
+    /// The PDA's temp token account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub pdas_temp_token_account: UncheckedAccount<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The escrow's PDA
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+    /// The mint of the streamed tokens
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The initializer's main account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_main_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MatchEscrows<'info> {
+    /// Escrow A
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_a: UncheckedAccount<'info>,
+    /// A's temp token account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account_a: UncheckedAccount<'info>,
+    /// A's initializer's main account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializer_a: UncheckedAccount<'info>,
+    /// A's initializer's token account for token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub token_to_receive_account_a: UncheckedAccount<'info>,
+    /// A's PDA
+    /// CHECK: checked by the processor
+    pub pda_a: UncheckedAccount<'info>,
+    /// Escrow B
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_b: UncheckedAccount<'info>,
+    /// B's temp token account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account_b: UncheckedAccount<'info>,
+    /// B's initializer's main account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializer_b: UncheckedAccount<'info>,
+    /// B's initializer's token account for token X
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub token_to_receive_account_b: UncheckedAccount<'info>,
+    /// B's PDA
+    /// CHECK: checked by the processor
+    pub pda_b: UncheckedAccount<'info>,
+    /// The token program of token X
+    /// CHECK: checked by the processor
+    pub token_x_program: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub token_y_program: UncheckedAccount<'info>,
+    /// The mint of token X
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitMarket<'info> {
+    /// The account paying for the market's rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The market account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub market: UncheckedAccount<'info>,
+    /// The base mint
+    /// CHECK: checked by the processor
+    pub base_mint: UncheckedAccount<'info>,
+    /// The quote mint
+    /// CHECK: checked by the processor
+    pub quote_mint: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceOrder<'info> {
+    /// The escrow's initializer
+    pub initializer: Signer<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The escrow's temp token account
+    /// CHECK: checked by the processor
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The market account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub market: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelOrder<'info> {
+    /// The escrow's initializer, or anyone if the escrow is closed
+    pub authority: Signer<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The market account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub market: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConsumeOrders<'info> {
+    /// The market account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub market: UncheckedAccount<'info>,
+    /// The best ask's escrow, followed by the rest of MatchEscrows' accounts
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_a: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitRegistry<'info> {
+    /// The account paying for the registry's rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The registry account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub registry: UncheckedAccount<'info>,
+    /// The mint of token X
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PruneRegistry<'info> {
+    /// The registry account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub registry: UncheckedAccount<'info>,
+    /// The first escrow account to remove, followed by any number of others
+    /// CHECK: checked by the processor
+    pub escrow_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateEscrow<'info> {
+    /// The account paying for the rent of the larger escrow account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The escrow's temp token account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account that will receive tokens
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The token program
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The legacy PDA
+    /// CHECK: checked by the processor
+    pub legacy_pda: UncheckedAccount<'info>,
+    /// The escrow's PDA
+    /// CHECK: checked by the processor
+    pub pda: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseStaleEscrow<'info> {
+    /// The escrow's temp token account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's main account to send their rent fees to
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializer: UncheckedAccount<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The escrow's PDA
+    /// CHECK: checked by the processor
+    pub pda: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetEscrow<'info> {
+    /// The escrow account
+    /// CHECK: checked by the processor
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The escrow's temp token account
+    /// CHECK: checked by the processor
+    pub temp_token_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitRateEscrow<'info> {
+    /// The account of the person initializing the escrow
+    pub initializer: Signer<'info>,
+    /// Temporary token account holding the tokens to escrow, owned by the initializer
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account for the token they will receive
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account, it will hold all necessary info about the trade
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFee<'info> {
+    /// The admin stored in the config account
+    pub admin: Signer<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitStats<'info> {
+    /// The account paying for the stats account's rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The stats account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub stats: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Reserve<'info> {
+    /// The taker, who pays the deposit
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The initializer's main account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializer: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCancelPenalty<'info> {
+    /// The admin stored in the config account
+    pub admin: Signer<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetGateProgram<'info> {
+    /// The admin stored in the config account
+    pub admin: Signer<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitCollectionOffer<'info> {
+    /// The account of the person initializing the escrow
+    pub initializer: Signer<'info>,
+    /// Temporary token account holding the payment, owned by the initializer
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The collection's mint
+    /// CHECK: checked by the processor
+    pub collection_mint: UncheckedAccount<'info>,
+    /// The escrow account, it will hold all necessary info about the trade
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FillCollectionOffer<'info> {
+    /// The seller
+    pub seller: Signer<'info>,
+    /// The seller's token account holding the NFT
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub sellers_nft_account: UncheckedAccount<'info>,
+    /// The seller's token account for the payment
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub sellers_token_to_receive_account: UncheckedAccount<'info>,
+    /// The PDA's temp token account holding the payment
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub pdas_temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's main account to send their rent fees to
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_main_account: UncheckedAccount<'info>,
+    /// The initializer's token account for the NFT
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_nft_account: UncheckedAccount<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program of the payment
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The escrow's PDA
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+    /// The mint of the payment
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The NFT's mint
+    /// CHECK: checked by the processor
+    pub nft_mint: UncheckedAccount<'info>,
+    /// The token program of the NFT
+    /// CHECK: checked by the processor
+    pub nft_token_program: UncheckedAccount<'info>,
+    /// The Metaplex metadata account of the NFT
+    /// CHECK: checked by the processor
+    pub nft_metadata: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitLamportEscrow<'info> {
+    /// The account of the person initializing the escrow
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    /// The escrow's PDA, which holds the lamports
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub pda_account: UncheckedAccount<'info>,
+    /// The initializer's token account for the token they will receive
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account, it will hold all necessary info about the trade
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitBuyEscrow<'info> {
+    /// The account of the person initializing the escrow
+    pub initializer: Signer<'info>,
+    /// Temporary token account holding the payment, owned by the initializer
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account for the token they buy
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account, it will hold all necessary info about the trade
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Abort<'info> {
+    /// The account of the person who initialized the escrow
+    pub initializer: Signer<'info>,
+    /// The taker who accepted the escrow
+    pub taker: Signer<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendDeadline<'info> {
+    /// The account of the person who initialized the escrow
+    pub initializer: Signer<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMaxExpiry<'info> {
+    /// The admin stored in the config account
+    pub admin: Signer<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TransferEscrow<'info> {
+    /// The account of the person who initialized the escrow
+    pub initializer: Signer<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The new initializer's main account
+    /// CHECK: checked by the processor
+    pub new_initializer: UncheckedAccount<'info>,
+    /// The new initializer's token account for the token they will receive should the trade go through
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleSignedOrder<'info> {
+    /// The account of the person taking the trade
+    pub taker: Signer<'info>,
+    /// The taker's token account for the token they send
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub takers_sending_token_account: UncheckedAccount<'info>,
+    /// The taker's token account for the token they will receive
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub takers_token_to_receive_account: UncheckedAccount<'info>,
+    /// The PDA's temp token account to get tokens from and eventually close
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub pdas_temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's main account to send their rent fees to
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_main_account: UncheckedAccount<'info>,
+    /// The initializer's token account that will receive tokens
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program of the token the taker receives
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The escrow's PDA
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+    /// The mint of the token the taker receives
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The mint of the token the taker sends
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of the token the taker sends
+    /// CHECK: checked by the processor
+    pub y_token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaymentOption<'info> {
+    /// The account of the person who initialized the escrow
+    pub initializer: Signer<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The initializer's token account that will receive the option's mint
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddStablecoin<'info> {
+    /// The admin stored in the config account
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The stablecoin list
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub stablecoins: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveStablecoin<'info> {
+    /// The admin stored in the config account
+    pub admin: Signer<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The stablecoin list
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub stablecoins: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetUsdPrice<'info> {
+    /// The account of the person who initialized the escrow
+    pub initializer: Signer<'info>,
+    /// The escrow account holding the escrow info
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitNftSwap<'info> {
+    /// The account of the person initializing the escrow
+    pub initializer: Signer<'info>,
+    /// Temporary token account holding the NFT, owned by the initializer
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account of the NFT they want
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account, it will hold all necessary info about the trade
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+    /// The NFT's mint
+    /// CHECK: checked by the processor
+    pub nft_mint: UncheckedAccount<'info>,
+    /// The mint of the NFT the initializer wants
+    /// CHECK: checked by the processor
+    pub wanted_nft_mint: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitRing<'info> {
+    /// The ring's creator, who pays for the ring account
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    /// The ring account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub ring: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+    /// The participant of the first leg
+    /// CHECK: checked by the processor
+    pub participant: UncheckedAccount<'info>,
+    /// The mint the first participant gives, followed by the other legs' participants and mints
+    /// CHECK: checked by the processor
+    pub mint: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DepositRingLeg<'info> {
+    /// The participant
+    #[account(mut)]
+    pub participant: Signer<'info>,
+    /// The participant's token account the leg's tokens come from
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub participant_token_account: UncheckedAccount<'info>,
+    /// The ring account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub ring: UncheckedAccount<'info>,
+    /// The leg's vault
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    /// The mint of the leg
+    /// CHECK: checked by the processor
+    pub mint: UncheckedAccount<'info>,
+    /// The token program of the mint
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteRing<'info> {
+    /// The ring account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub ring: UncheckedAccount<'info>,
+    /// The ring's creator
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub creator: UncheckedAccount<'info>,
+    /// The first leg's vault, followed by the accounts of the other legs
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    /// The token account the second participant receives into
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub recipient_token_account: UncheckedAccount<'info>,
+    /// The mint of the first leg
+    /// CHECK: checked by the processor
+    pub mint: UncheckedAccount<'info>,
+    /// The token program of the mint
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The first participant
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub participant: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawRingLeg<'info> {
+    /// The participant
+    #[account(mut)]
+    pub participant: Signer<'info>,
+    /// The participant's token account the deposit is returned to
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub participant_token_account: UncheckedAccount<'info>,
+    /// The ring account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub ring: UncheckedAccount<'info>,
+    /// The leg's vault
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    /// The mint of the leg
+    /// CHECK: checked by the processor
+    pub mint: UncheckedAccount<'info>,
+    /// The token program of the mint
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRing<'info> {
+    /// The ring's creator
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    /// The ring account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub ring: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PostQuote<'info> {
+    /// The quoter
+    #[account(mut)]
+    pub quoter: Signer<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The quote account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub quote: UncheckedAccount<'info>,
+    /// The quoter's token account of token Y the price comes from
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub source_token_account: UncheckedAccount<'info>,
+    /// The quoter's token account of token X that receives the escrow
+    /// CHECK: checked by the processor
+    pub receive_token_account: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub token_y_program: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptQuote<'info> {
+    /// The account of the person who initialized the escrow
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The escrow's temp token account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account of token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializer_token_account: UncheckedAccount<'info>,
+    /// The PDA account
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+    /// The quote account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub quote: UncheckedAccount<'info>,
+    /// The quoter
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub quoter: UncheckedAccount<'info>,
+    /// The quote's vault, or the quoter's token account of token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub payment_source: UncheckedAccount<'info>,
+    /// The quoter's token account of token X
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub quoter_token_account: UncheckedAccount<'info>,
+    /// The token program of token X
+    /// CHECK: checked by the processor
+    pub token_x_program: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub token_y_program: UncheckedAccount<'info>,
+    /// The mint of token X
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelQuote<'info> {
+    /// The quoter
+    #[account(mut)]
+    pub quoter: Signer<'info>,
+    /// The quote account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub quote: UncheckedAccount<'info>,
+    /// The quoter's token account of token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub source_token_account: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub token_y_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeCounteroffer<'info> {
+    /// The taker
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The counteroffer account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub counteroffer: UncheckedAccount<'info>,
+    /// The taker's token account of token Y the amount comes from
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub payment_token_account: UncheckedAccount<'info>,
+    /// The taker's token account of token X that receives the escrow
+    /// CHECK: checked by the processor
+    pub receive_token_account: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub token_y_program: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptCounteroffer<'info> {
+    /// The account of the person who initialized the escrow
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The escrow's temp token account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account of token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializer_token_account: UncheckedAccount<'info>,
+    /// The PDA account
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+    /// The counteroffer account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub counteroffer: UncheckedAccount<'info>,
+    /// The taker
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub taker: UncheckedAccount<'info>,
+    /// The taker's token account of token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub payment_token_account: UncheckedAccount<'info>,
+    /// The taker's token account of token X
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub receive_token_account: UncheckedAccount<'info>,
+    /// The token program of token X
+    /// CHECK: checked by the processor
+    pub token_x_program: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub token_y_program: UncheckedAccount<'info>,
+    /// The mint of token X
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitSealedAuction<'info> {
+    /// The account of the person initializing the escrow
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    /// Temporary token account holding the tokens to escrow, owned by the initializer
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account for the token they will receive
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account, it will hold all necessary info about the trade
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+    /// The auction account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub sealed_auction: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CommitBid<'info> {
+    /// The bidder
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    /// The bidder's token account of token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub bidders_token_account: UncheckedAccount<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The auction account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub sealed_auction: UncheckedAccount<'info>,
+    /// The sealed bid account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub sealed_bid: UncheckedAccount<'info>,
+    /// The sealed bid's vault
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub sealed_bid_vault: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub token_y_program: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevealBid<'info> {
+    /// The bidder
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    /// The bidder's token account of token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub bidders_token_account: UncheckedAccount<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The auction account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub sealed_auction: UncheckedAccount<'info>,
+    /// The sealed bid account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub sealed_bid: UncheckedAccount<'info>,
+    /// The sealed bid's vault
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub sealed_bid_vault: UncheckedAccount<'info>,
+    /// The bidder's bid account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub bid_account: UncheckedAccount<'info>,
+    /// The escrow's PDA
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub token_y_program: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+    /// The rent sysvar
+    /// CHECK: checked by the processor
+    pub rent: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SlashBid<'info> {
+    /// The auction account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub sealed_auction: UncheckedAccount<'info>,
+    /// The sealed bid account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub sealed_bid: UncheckedAccount<'info>,
+    /// The bidder
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub bidder: UncheckedAccount<'info>,
+    /// The sealed bid's vault
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub sealed_bid_vault: UncheckedAccount<'info>,
+    /// The initializer's token account of token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub beneficiary_token_account: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub token_y_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSealedAuction<'info> {
+    /// The initializer who created the auction
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The auction account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub sealed_auction: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitRaffle<'info> {
+    /// The account of the person initializing the escrow
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    /// Temporary token account holding the tokens to escrow, owned by the initializer
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account for the token they will receive
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The escrow account, it will hold all necessary info about the trade
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The token program that owns the temp token account
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+    /// The raffle account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub raffle: UncheckedAccount<'info>,
+    /// The raffle's vault
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub raffle_vault: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub token_y_program: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BuyTickets<'info> {
+    /// The buyer
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    /// The buyer's token account of token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub buyers_token_account: UncheckedAccount<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The raffle account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub raffle: UncheckedAccount<'info>,
+    /// The ticket account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub ticket: UncheckedAccount<'info>,
+    /// The raffle's vault
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub raffle_vault: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub token_y_program: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DrawRaffle<'info> {
+    /// The raffle account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub raffle: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleRaffle<'info> {
+    /// The raffle account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub raffle: UncheckedAccount<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The escrow's temp token account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The escrow's PDA
+    /// CHECK: checked by the processor
+    pub pda_account: UncheckedAccount<'info>,
+    /// The raffle's vault
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub raffle_vault: UncheckedAccount<'info>,
+    /// The initializer's token account of token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub proceeds_token_account: UncheckedAccount<'info>,
+    /// The escrow's initializer
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializer: UncheckedAccount<'info>,
+    /// The ticket account holding the winning ticket
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub ticket: UncheckedAccount<'info>,
+    /// The buyer of the winning ticket
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub winner: UncheckedAccount<'info>,
+    /// The winner's token account of token X
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub winner_token_account: UncheckedAccount<'info>,
+    /// The SlotHashes sysvar
+    /// CHECK: checked by the processor
+    pub slot_hashes: UncheckedAccount<'info>,
+    /// The token program of token X
+    /// CHECK: checked by the processor
+    pub token_x_program: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub token_y_program: UncheckedAccount<'info>,
+    /// The mint of token X
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRaffle<'info> {
+    /// The initializer who created the raffle
+    #[account(mut)]
+    pub initializer: Signer<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The raffle account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub raffle: UncheckedAccount<'info>,
+    /// The raffle's vault
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub raffle_vault: UncheckedAccount<'info>,
+    /// The initializer's token account of token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub proceeds_token_account: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub token_y_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTicket<'info> {
+    /// The buyer
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    /// The ticket account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub ticket: UncheckedAccount<'info>,
+    /// The raffle account
+    /// CHECK: checked by the processor
+    pub raffle: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RequestRaffleRandomness<'info> {
+    /// The raffle account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub raffle: UncheckedAccount<'info>,
+    /// The VRF account the raffle was created with
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub vrf: UncheckedAccount<'info>,
+    /// The VRF's oracle queue
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub oracle_queue: UncheckedAccount<'info>,
+    /// The oracle queue's authority
+    /// CHECK: checked by the processor
+    pub queue_authority: UncheckedAccount<'info>,
+    /// The oracle queue's data buffer
+    /// CHECK: checked by the processor
+    pub data_buffer: UncheckedAccount<'info>,
+    /// The VRF's permission account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub permission: UncheckedAccount<'info>,
+    /// The VRF's escrow
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub vrf_escrow: UncheckedAccount<'info>,
+    /// The payer's wrapped SOL token account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub payer_wallet: UncheckedAccount<'info>,
+    /// The payer
+    pub payer: Signer<'info>,
+    /// The RecentBlockhashes sysvar
+    /// CHECK: checked by the processor
+    pub recent_blockhashes: UncheckedAccount<'info>,
+    /// The Switchboard program state
+    /// CHECK: checked by the processor
+    pub program_state: UncheckedAccount<'info>,
+    /// The token program
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The Switchboard program
+    /// CHECK: checked by the processor
+    pub switchboard_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ConsumeRaffleRandomness<'info> {
+    /// The raffle account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub raffle: UncheckedAccount<'info>,
+    /// The VRF account the raffle was created with
+    /// CHECK: checked by the processor
+    pub vrf: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitEscrowPool<'info> {
+    /// The maker
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    /// The pool account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+    /// The pool's vault
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    /// The maker's token account for token Y
+    /// CHECK: checked by the processor
+    pub makers_token_to_receive_account: UncheckedAccount<'info>,
+    /// The mint of token X
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of token X
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PostPoolEntry<'info> {
+    /// The maker
+    pub maker: Signer<'info>,
+    /// The maker's token account for token X
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub makers_token_account: UncheckedAccount<'info>,
+    /// The pool account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+    /// The pool's vault
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    /// The mint of token X
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The token program of token X
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TakePoolEntry<'info> {
+    /// The taker
+    pub taker: Signer<'info>,
+    /// The taker's token account for token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub takers_sending_token_account: UncheckedAccount<'info>,
+    /// The taker's token account for token X
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub takers_token_to_receive_account: UncheckedAccount<'info>,
+    /// The pool account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+    /// The pool's vault
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    /// The pool's authority
+    /// CHECK: checked by the processor
+    pub pool_authority: UncheckedAccount<'info>,
+    /// The maker's token account for token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub makers_token_to_receive_account: UncheckedAccount<'info>,
+    /// The mint of token X
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of token X
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub token_y_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelPoolEntry<'info> {
+    /// The maker
+    pub maker: Signer<'info>,
+    /// The maker's token account for token X
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub makers_token_account: UncheckedAccount<'info>,
+    /// The pool account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+    /// The pool's vault
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    /// The pool's authority
+    /// CHECK: checked by the processor
+    pub pool_authority: UncheckedAccount<'info>,
+    /// The mint of token X
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The token program of token X
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseEscrowPool<'info> {
+    /// The maker
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    /// The maker's token account for token X
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub makers_token_account: UncheckedAccount<'info>,
+    /// The pool account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub pool: UncheckedAccount<'info>,
+    /// The pool's vault
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+    /// The pool's authority
+    /// CHECK: checked by the processor
+    pub pool_authority: UncheckedAccount<'info>,
+    /// The mint of token X
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The token program of token X
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitCompressedTree<'info> {
+    /// The creator
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    /// The merkle tree
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    /// The tree's config
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub tree_config: UncheckedAccount<'info>,
+    /// The compression program
+    /// CHECK: checked by the processor
+    pub compression_program: UncheckedAccount<'info>,
+    /// The noop program
+    /// CHECK: checked by the processor
+    pub noop_program: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitCompressedEscrow<'info> {
+    /// The initializer
+    pub initializer: Signer<'info>,
+    /// The initializer's token account for token X
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account for token Y
+    /// CHECK: checked by the processor
+    pub initializers_token_to_receive_account: UncheckedAccount<'info>,
+    /// The tree's config
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub tree_config: UncheckedAccount<'info>,
+    /// The merkle tree
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    /// The mint of token X
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of token X
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The compression program
+    /// CHECK: checked by the processor
+    pub compression_program: UncheckedAccount<'info>,
+    /// The noop program
+    /// CHECK: checked by the processor
+    pub noop_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+    /// The mint allowlist
+    /// CHECK: checked by the processor
+    pub allowlist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExchangeCompressed<'info> {
+    /// The taker
+    pub taker: Signer<'info>,
+    /// The taker's token account for token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub takers_sending_token_account: UncheckedAccount<'info>,
+    /// The taker's token account for token X
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub takers_token_to_receive_account: UncheckedAccount<'info>,
+    /// The initializer's token account for token X
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account for token Y
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_token_to_receive_account: UncheckedAccount<'info>,
+    /// The tree's config
+    /// CHECK: checked by the processor
+    pub tree_config: UncheckedAccount<'info>,
+    /// The merkle tree
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    /// The mint of token X
+    /// CHECK: checked by the processor
+    pub token_x_mint: UncheckedAccount<'info>,
+    /// The mint of token Y
+    /// CHECK: checked by the processor
+    pub token_y_mint: UncheckedAccount<'info>,
+    /// The token program of token X
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The token program of token Y
+    /// CHECK: checked by the processor
+    pub token_y_program: UncheckedAccount<'info>,
+    /// The compression program
+    /// CHECK: checked by the processor
+    pub compression_program: UncheckedAccount<'info>,
+    /// The noop program
+    /// CHECK: checked by the processor
+    pub noop_program: UncheckedAccount<'info>,
+    /// The program's config account
+    /// CHECK: checked by the processor
+    pub config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelCompressed<'info> {
+    /// The initializer
+    pub initializer: Signer<'info>,
+    /// The initializer's token account for token X
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub initializers_token_account: UncheckedAccount<'info>,
+    /// The tree's config
+    /// CHECK: checked by the processor
+    pub tree_config: UncheckedAccount<'info>,
+    /// The merkle tree
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    /// The token program of token X
+    /// CHECK: checked by the processor
+    pub token_program: UncheckedAccount<'info>,
+    /// The compression program
+    /// CHECK: checked by the processor
+    pub compression_program: UncheckedAccount<'info>,
+    /// The noop program
+    /// CHECK: checked by the processor
+    pub noop_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpgradeEscrowAccount<'info> {
+    /// The account paying for the rent of the larger escrow account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The escrow account
+    /// CHECK: checked by the processor
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+    /// The escrow's temp token account
+    /// CHECK: checked by the processor
+    pub temp_token_account: UncheckedAccount<'info>,
+    /// The initializer's token account that will receive tokens
+    /// CHECK: checked by the processor
+    pub token_to_receive_account: UncheckedAccount<'info>,
+    /// The system program
+    /// CHECK: checked by the processor
+    pub system_program: UncheckedAccount<'info>,
+}
@@ -0,0 +1,209 @@
+//! Compressed escrows are kept as leaves of a concurrent merkle tree of the spl-account-compression program instead of in accounts of their own, so a marketplace can list millions of them for the rent of one tree. Only the leaf's hash is stored on-chain; the escrow itself is emitted in a CompressedEscrowCreated event when it is listed, and whoever exchanges or cancels it passes it back in along with a proof that its leaf is in the tree.
+//!
+//! The token X of a compressed escrow stays in the initializer's token account, which is delegated to the tree's config PDA, like a delegated escrow's is to the escrow's PDA. The PDA is also the authority of the tree, so leaves can only be changed through this program.
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    keccak::hashv,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+/// The leaf a compressed escrow is replaced with once it is exchanged or cancelled
+pub const EMPTY_LEAF: [u8; 32] = [0; 32];
+
+/// The deepest tree the compression program supports. Leaf indexes of shallower trees fit in a u32.
+pub const MAX_DEPTH: u32 = 30;
+
+/// A tree compressed escrows can be listed in, stored at the PDA derived from `[b"compressed_tree", merkle tree pubkey]`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct CompressedTree {
+    pub is_initialized: bool,
+    pub bump_seed: u8,
+    pub merkle_tree: Pubkey,
+    pub creator: Pubkey,
+    pub max_depth: u32,
+    /// How many proof nodes have to be passed, the tree's depth less the depth of its canopy
+    pub proof_len: u32,
+    /// Leaves are only ever appended, so this is also the index of the next one
+    pub num_leaves: u64,
+}
+
+impl CompressedTree {
+    pub fn is_full(&self) -> bool {
+        self.num_leaves >= 1u64 << self.max_depth
+    }
+}
+
+impl Sealed for CompressedTree {}
+
+impl IsInitialized for CompressedTree {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for CompressedTree {
+    const LEN: usize = 82;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, CompressedTree::LEN];
+        let (is_initialized, bump_seed, merkle_tree, creator, max_depth, proof_len, num_leaves) =
+            array_refs![src, 1, 1, 32, 32, 4, 4, 8];
+
+        Ok(CompressedTree {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            bump_seed: bump_seed[0],
+            merkle_tree: Pubkey::new_from_array(*merkle_tree),
+            creator: Pubkey::new_from_array(*creator),
+            max_depth: u32::from_le_bytes(*max_depth),
+            proof_len: u32::from_le_bytes(*proof_len),
+            num_leaves: u64::from_le_bytes(*num_leaves),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, CompressedTree::LEN];
+        let (
+            is_initialized_dst,
+            bump_seed_dst,
+            merkle_tree_dst,
+            creator_dst,
+            max_depth_dst,
+            proof_len_dst,
+            num_leaves_dst,
+        ) = mut_array_refs![dst, 1, 1, 32, 32, 4, 4, 8];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        merkle_tree_dst.copy_from_slice(self.merkle_tree.as_ref());
+        creator_dst.copy_from_slice(self.creator.as_ref());
+        *max_depth_dst = self.max_depth.to_le_bytes();
+        *proof_len_dst = self.proof_len.to_le_bytes();
+        *num_leaves_dst = self.num_leaves.to_le_bytes();
+    }
+}
+
+/// An escrow stored as a leaf, offering amount_x of token X for amount_y of token Y as a whole
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct CompressedEscrow {
+    pub initializer: Pubkey,
+    /// The initializer's token account of token X, delegated to the tree's config PDA
+    pub initializer_token_account: Pubkey,
+    pub initializer_token_to_receive_account: Pubkey,
+    pub token_x_mint: Pubkey,
+    pub token_y_mint: Pubkey,
+    pub amount_x: u64,
+    pub amount_y: u64,
+    /// 0 if the escrow never expires
+    pub expiry_unix_timestamp: i64,
+    /// Makes the leaves of otherwise equal escrows differ
+    pub leaf_index: u32,
+}
+
+impl CompressedEscrow {
+    /// The leaf the escrow is stored as, the keccak hash of its packed form
+    pub fn leaf(&self) -> [u8; 32] {
+        let mut data = [0u8; CompressedEscrow::LEN];
+        self.pack_into_slice(&mut data);
+        hashv(&[&data]).to_bytes()
+    }
+
+    pub fn is_expired(&self, unix_timestamp: i64) -> bool {
+        self.expiry_unix_timestamp != 0 && unix_timestamp >= self.expiry_unix_timestamp
+    }
+}
+
+impl Sealed for CompressedEscrow {}
+
+impl Pack for CompressedEscrow {
+    const LEN: usize = 188;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, CompressedEscrow::LEN];
+        let (
+            initializer,
+            initializer_token_account,
+            initializer_token_to_receive_account,
+            token_x_mint,
+            token_y_mint,
+            amount_x,
+            amount_y,
+            expiry_unix_timestamp,
+            leaf_index,
+        ) = array_refs![src, 32, 32, 32, 32, 32, 8, 8, 8, 4];
+
+        Ok(CompressedEscrow {
+            initializer: Pubkey::new_from_array(*initializer),
+            initializer_token_account: Pubkey::new_from_array(*initializer_token_account),
+            initializer_token_to_receive_account: Pubkey::new_from_array(*initializer_token_to_receive_account),
+            token_x_mint: Pubkey::new_from_array(*token_x_mint),
+            token_y_mint: Pubkey::new_from_array(*token_y_mint),
+            amount_x: u64::from_le_bytes(*amount_x),
+            amount_y: u64::from_le_bytes(*amount_y),
+            expiry_unix_timestamp: i64::from_le_bytes(*expiry_unix_timestamp),
+            leaf_index: u32::from_le_bytes(*leaf_index),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, CompressedEscrow::LEN];
+        let (
+            initializer_dst,
+            initializer_token_account_dst,
+            initializer_token_to_receive_account_dst,
+            token_x_mint_dst,
+            token_y_mint_dst,
+            amount_x_dst,
+            amount_y_dst,
+            expiry_unix_timestamp_dst,
+            leaf_index_dst,
+        ) = mut_array_refs![dst, 32, 32, 32, 32, 32, 8, 8, 8, 4];
+
+        initializer_dst.copy_from_slice(self.initializer.as_ref());
+        initializer_token_account_dst.copy_from_slice(self.initializer_token_account.as_ref());
+        initializer_token_to_receive_account_dst.copy_from_slice(self.initializer_token_to_receive_account.as_ref());
+        token_x_mint_dst.copy_from_slice(self.token_x_mint.as_ref());
+        token_y_mint_dst.copy_from_slice(self.token_y_mint.as_ref());
+        *amount_x_dst = self.amount_x.to_le_bytes();
+        *amount_y_dst = self.amount_y.to_le_bytes();
+        *expiry_unix_timestamp_dst = self.expiry_unix_timestamp.to_le_bytes();
+        *leaf_index_dst = self.leaf_index.to_le_bytes();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaf_commits_to_every_field() {
+        let escrow = CompressedEscrow {
+            initializer: Pubkey::new_unique(),
+            initializer_token_account: Pubkey::new_unique(),
+            initializer_token_to_receive_account: Pubkey::new_unique(),
+            token_x_mint: Pubkey::new_unique(),
+            token_y_mint: Pubkey::new_unique(),
+            amount_x: 10,
+            amount_y: 20,
+            expiry_unix_timestamp: 1_700_000_000,
+            leaf_index: 7,
+        };
+        let data = escrow.try_to_vec().unwrap();
+        assert_eq!(data.len(), CompressedEscrow::LEN);
+        assert_eq!(CompressedEscrow::unpack_from_slice(&data).unwrap(), escrow);
+
+        assert_ne!(escrow.leaf(), EMPTY_LEAF);
+        assert_ne!(escrow.leaf(), CompressedEscrow { leaf_index: 8, ..escrow }.leaf());
+        assert_ne!(escrow.leaf(), CompressedEscrow { amount_y: 19, ..escrow }.leaf());
+
+        let tree = CompressedTree { is_initialized: true, max_depth: 3, num_leaves: 8, ..CompressedTree::default() };
+        assert!(tree.is_full());
+        assert!(!CompressedTree { num_leaves: 7, ..tree }.is_full());
+        assert_eq!(tree.try_to_vec().unwrap().len(), CompressedTree::LEN);
+    }
+}
@@ -0,0 +1,66 @@
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+// The spl-account-compression program, which keeps the concurrent merkle trees compressed escrows are stored in. Its instructions are built by hand like the ones in `switchboard`, since the crate depends on an Anchor version that needs a newer solana-program than we build against.
+solana_program::declare_id!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+
+/// The noop program the compression program logs changes to the trees through, so indexers can follow them
+pub mod noop {
+    solana_program::declare_id!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+}
+
+/// The Anchor discriminators of the instructions we use
+const INIT_EMPTY_MERKLE_TREE_DISCRIMINATOR: [u8; 8] = [191, 11, 119, 7, 180, 107, 220, 110];
+const APPEND_DISCRIMINATOR: [u8; 8] = [149, 120, 18, 222, 236, 225, 88, 203];
+const REPLACE_LEAF_DISCRIMINATOR: [u8; 8] = [204, 165, 76, 100, 73, 147, 0, 128];
+
+/// Creates an init_empty_merkle_tree instruction. The tree account has to be created zeroed and owned by the compression program first, sized for max_depth, max_buffer_size and the canopy.
+pub fn init_empty_merkle_tree(
+    merkle_tree: &Pubkey,
+    authority: &Pubkey,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Instruction {
+    let mut data = INIT_EMPTY_MERKLE_TREE_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&max_depth.to_le_bytes());
+    data.extend_from_slice(&max_buffer_size.to_le_bytes());
+    Instruction { program_id: id(), accounts: modify_accounts(merkle_tree, authority), data }
+}
+
+/// Creates an append instruction, which adds a leaf at the tree's next index
+pub fn append(merkle_tree: &Pubkey, authority: &Pubkey, leaf: [u8; 32]) -> Instruction {
+    let mut data = APPEND_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&leaf);
+    Instruction { program_id: id(), accounts: modify_accounts(merkle_tree, authority), data }
+}
+
+/// Creates a replace_leaf instruction. `root` is the root the proof was built against, which may be any of the roots still in the tree's change log. `proof` leaves out the nodes the canopy holds.
+pub fn replace_leaf(
+    merkle_tree: &Pubkey,
+    authority: &Pubkey,
+    root: [u8; 32],
+    previous_leaf: [u8; 32],
+    new_leaf: [u8; 32],
+    index: u32,
+    proof: &[Pubkey],
+) -> Instruction {
+    let mut data = REPLACE_LEAF_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&root);
+    data.extend_from_slice(&previous_leaf);
+    data.extend_from_slice(&new_leaf);
+    data.extend_from_slice(&index.to_le_bytes());
+
+    let mut accounts = modify_accounts(merkle_tree, authority);
+    accounts.extend(proof.iter().map(|node| AccountMeta::new_readonly(*node, false)));
+    Instruction { program_id: id(), accounts, data }
+}
+
+fn modify_accounts(merkle_tree: &Pubkey, authority: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(*merkle_tree, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(noop::id(), false),
+    ]
+}
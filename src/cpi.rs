@@ -0,0 +1,281 @@
+//! Helpers for programs that invoke the escrow program. Each struct holds the accounts of one instruction, builds it with the builder of the same name in `instruction`, and lists the account infos `invoke_signed` needs, so callers never have to know the account order. Signer seeds are only needed when a PDA of the calling program signs, e.g. as the taker or the initializer; pass `&[]` otherwise.
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    program::{get_return_data, invoke, invoke_signed},
+    program_error::ProgramError,
+};
+
+use crate::{instruction, view::EscrowView};
+
+/// The accounts of the CPI helpers Anchor generates for the `anchor` feature's program, which its codegen looks up here
+#[cfg(feature = "anchor")]
+pub use crate::anchor::cpi::accounts;
+
+/// The accounts of an Exchange. The taker's token account for X has to exist already.
+pub struct Exchange<'info> {
+    pub escrow_program: AccountInfo<'info>,
+    pub taker: AccountInfo<'info>,
+    pub takers_sending_token_account: AccountInfo<'info>,
+    pub takers_token_to_receive_account: AccountInfo<'info>,
+    pub pdas_temp_token_account: AccountInfo<'info>,
+    pub initializers_main_account: AccountInfo<'info>,
+    pub initializers_token_to_receive_account: AccountInfo<'info>,
+    pub escrow_account: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub pda: AccountInfo<'info>,
+    pub token_x_mint: AccountInfo<'info>,
+    pub token_y_mint: AccountInfo<'info>,
+    pub y_token_program: AccountInfo<'info>,
+    pub config: AccountInfo<'info>,
+    /// Required when the config charges a fee
+    pub fee_vault: Option<AccountInfo<'info>>,
+    /// Required for oracle-priced escrows
+    pub price_feed: Option<AccountInfo<'info>>,
+    /// The Metaplex metadata account of token X, required when token X is an NFT
+    pub nft_metadata: Option<AccountInfo<'info>>,
+    /// The token accounts of token Y of the NFT's creators who are owed a royalty
+    pub creators_token_accounts: Vec<AccountInfo<'info>>,
+}
+
+impl<'info> Exchange<'info> {
+    pub fn instruction(&self, amount: u64, max_payment: u64) -> Result<Instruction, ProgramError> {
+        let exchange = instruction::exchange(
+            self.escrow_program.key,
+            instruction::ExchangeArgs {
+                taker: *self.taker.key,
+                takers_sending_token_account: *self.takers_sending_token_account.key,
+                takers_token_to_receive_account: *self.takers_token_to_receive_account.key,
+                pdas_temp_token_account: *self.pdas_temp_token_account.key,
+                initializers_main_account: *self.initializers_main_account.key,
+                initializers_token_to_receive_account: *self.initializers_token_to_receive_account.key,
+                escrow_account: *self.escrow_account.key,
+                token_program_id: *self.token_program.key,
+                token_x_mint: *self.token_x_mint.key,
+                token_y_mint: *self.token_y_mint.key,
+                y_token_program_id: *self.y_token_program.key,
+                fee_vault: self.fee_vault.as_ref().map(|fee_vault| *fee_vault.key),
+                referrer_token_account: None,
+                create_takers_token_account: false,
+                price_feed: self.price_feed.as_ref().map(|price_feed| *price_feed.key),
+                amount,
+                max_payment,
+            },
+        )?;
+        if self.nft_metadata.is_none() {
+            return Ok(exchange);
+        }
+        let creators_token_accounts: Vec<_> = self.creators_token_accounts.iter().map(|account| *account.key).collect();
+        Ok(instruction::with_royalty_accounts(exchange, self.token_x_mint.key, &creators_token_accounts))
+    }
+
+    pub fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        let mut account_infos = vec![
+            self.escrow_program.clone(),
+            self.taker.clone(),
+            self.takers_sending_token_account.clone(),
+            self.takers_token_to_receive_account.clone(),
+            self.pdas_temp_token_account.clone(),
+            self.initializers_main_account.clone(),
+            self.initializers_token_to_receive_account.clone(),
+            self.escrow_account.clone(),
+            self.token_program.clone(),
+            self.pda.clone(),
+            self.token_x_mint.clone(),
+            self.token_y_mint.clone(),
+            self.y_token_program.clone(),
+            self.config.clone(),
+        ];
+        account_infos.extend(self.fee_vault.clone());
+        account_infos.extend(self.price_feed.clone());
+        account_infos.extend(self.nft_metadata.clone());
+        account_infos.extend(self.creators_token_accounts.iter().cloned());
+        account_infos
+    }
+
+    pub fn invoke_signed(&self, amount: u64, max_payment: u64, signer_seeds: &[&[&[u8]]]) -> ProgramResult {
+        invoke_signed(&self.instruction(amount, max_payment)?, &self.account_infos(), signer_seeds)
+    }
+}
+
+/// The accounts of a Cancel
+pub struct Cancel<'info> {
+    pub escrow_program: AccountInfo<'info>,
+    pub initializer: AccountInfo<'info>,
+    pub pdas_temp_token_account: AccountInfo<'info>,
+    pub initializers_token_to_return_account: AccountInfo<'info>,
+    pub escrow_account: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub pda: AccountInfo<'info>,
+    pub token_x_mint: AccountInfo<'info>,
+    pub config: AccountInfo<'info>,
+    /// Required when the config charges a cancellation penalty
+    pub penalty_token_account: Option<AccountInfo<'info>>,
+}
+
+impl<'info> Cancel<'info> {
+    pub fn instruction(&self) -> Result<Instruction, ProgramError> {
+        let instruction = instruction::cancel(
+            self.escrow_program.key,
+            self.initializer.key,
+            self.pdas_temp_token_account.key,
+            self.initializers_token_to_return_account.key,
+            self.escrow_account.key,
+            self.token_program.key,
+            self.token_x_mint.key,
+        )?;
+        Ok(match &self.penalty_token_account {
+            Some(penalty_token_account) => instruction::with_cancel_penalty_account(instruction, penalty_token_account.key),
+            None => instruction,
+        })
+    }
+
+    pub fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        let mut account_infos = vec![
+            self.escrow_program.clone(),
+            self.initializer.clone(),
+            self.pdas_temp_token_account.clone(),
+            self.initializers_token_to_return_account.clone(),
+            self.escrow_account.clone(),
+            self.token_program.clone(),
+            self.pda.clone(),
+            self.token_x_mint.clone(),
+            self.config.clone(),
+        ];
+        account_infos.extend(self.penalty_token_account.clone());
+        account_infos
+    }
+
+    pub fn invoke_signed(&self, signer_seeds: &[&[&[u8]]]) -> ProgramResult {
+        invoke_signed(&self.instruction()?, &self.account_infos(), signer_seeds)
+    }
+}
+
+/// The accounts of a ReclaimExpired. Nobody signs it, so there are no signer seeds either.
+pub struct ReclaimExpired<'info> {
+    pub escrow_program: AccountInfo<'info>,
+    pub pdas_temp_token_account: AccountInfo<'info>,
+    pub initializers_main_account: AccountInfo<'info>,
+    pub initializers_token_to_return_account: AccountInfo<'info>,
+    pub escrow_account: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub pda: AccountInfo<'info>,
+    pub token_x_mint: AccountInfo<'info>,
+}
+
+impl<'info> ReclaimExpired<'info> {
+    pub fn instruction(&self) -> Result<Instruction, ProgramError> {
+        instruction::reclaim_expired(
+            self.escrow_program.key,
+            self.pdas_temp_token_account.key,
+            self.initializers_main_account.key,
+            self.initializers_token_to_return_account.key,
+            self.escrow_account.key,
+            self.token_program.key,
+            self.token_x_mint.key,
+        )
+    }
+
+    pub fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.escrow_program.clone(),
+            self.pdas_temp_token_account.clone(),
+            self.initializers_main_account.clone(),
+            self.initializers_token_to_return_account.clone(),
+            self.escrow_account.clone(),
+            self.token_program.clone(),
+            self.pda.clone(),
+            self.token_x_mint.clone(),
+        ]
+    }
+
+    pub fn invoke(&self) -> ProgramResult {
+        invoke(&self.instruction()?, &self.account_infos())
+    }
+}
+
+/// The accounts of a GetEscrow
+pub struct GetEscrow<'info> {
+    pub escrow_program: AccountInfo<'info>,
+    pub escrow_account: AccountInfo<'info>,
+    pub pdas_temp_token_account: AccountInfo<'info>,
+}
+
+impl<'info> GetEscrow<'info> {
+    pub fn instruction(&self) -> Result<Instruction, ProgramError> {
+        instruction::get_escrow(self.escrow_program.key, self.escrow_account.key, self.pdas_temp_token_account.key)
+    }
+
+    pub fn account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.escrow_program.clone(),
+            self.escrow_account.clone(),
+            self.pdas_temp_token_account.clone(),
+        ]
+    }
+
+    /// Invokes GetEscrow and decodes what it returned
+    pub fn invoke(&self) -> Result<EscrowView, ProgramError> {
+        invoke(&self.instruction()?, &self.account_infos())?;
+        match get_return_data() {
+            Some((program_id, data)) if program_id == *self.escrow_program.key => {
+                Ok(EscrowView::try_from_slice(&data)?)
+            }
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_config_address, find_escrow_authority_address};
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn account_infos_cover_the_instruction() {
+        let mut keys: Vec<Pubkey> = (0..15).map(|_| Pubkey::new_unique()).collect();
+        // The builder derives these two from the program id
+        keys[9] = find_escrow_authority_address(&keys[7], &keys[0]).0;
+        keys[13] = find_config_address(&keys[0]).0;
+        let mut lamports = vec![0u64; keys.len()];
+        let mut data = vec![Vec::<u8>::new(); keys.len()];
+        let owner = Pubkey::default();
+        let infos: Vec<AccountInfo> = keys
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|((key, lamports), data)| AccountInfo::new(key, false, false, lamports, data, &owner, false, 0))
+            .collect();
+
+        let exchange = Exchange {
+            escrow_program: infos[0].clone(),
+            taker: infos[1].clone(),
+            takers_sending_token_account: infos[2].clone(),
+            takers_token_to_receive_account: infos[3].clone(),
+            pdas_temp_token_account: infos[4].clone(),
+            initializers_main_account: infos[5].clone(),
+            initializers_token_to_receive_account: infos[6].clone(),
+            escrow_account: infos[7].clone(),
+            token_program: infos[8].clone(),
+            pda: infos[9].clone(),
+            token_x_mint: infos[10].clone(),
+            token_y_mint: infos[11].clone(),
+            y_token_program: infos[12].clone(),
+            config: infos[13].clone(),
+            fee_vault: Some(infos[14].clone()),
+            price_feed: None,
+            nft_metadata: None,
+            creators_token_accounts: vec![],
+        };
+        let instruction = exchange.instruction(10, 20).unwrap();
+        let account_infos = exchange.account_infos();
+        assert_eq!(account_infos.len(), instruction.accounts.len() + 1);
+        for meta in &instruction.accounts {
+            assert!(account_infos.iter().any(|info| *info.key == meta.pubkey));
+        }
+    }
+}
@@ -1,14 +1,242 @@
 use solana_program::{
-    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, pubkey::Pubkey,
+    account_info::AccountInfo, entrypoint::ProgramResult,
+    program_error::PrintProgramError, pubkey::Pubkey,
 };
 
-use crate::processor::Processor;
+use crate::{error::EscrowError, processor::Processor};
+
+#[cfg(not(feature = "no-alloc-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
 
-entrypoint!(process_instruction);
 fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    Processor::process(program_id, accounts, instruction_data)
-}
\ No newline at end of file
+    if let Err(error) = Processor::process(program_id, accounts, instruction_data) {
+        // Logs the EscrowError behind a Custom error code, so explorers show more than a number
+        error.print::<EscrowError>();
+        return Err(error);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "no-alloc-entrypoint")]
+pub use no_alloc::entrypoint;
+
+/// An entrypoint that reads the account infos into an array on the stack instead of the Vec the default one allocates, and never drops them, since the program is done once it returns. Each AccountInfo still keeps its lamports and data behind an Rc, which is heap allocated. Instructions with more than MAX_ACCOUNTS accounts go through the default deserialization.
+#[cfg(any(feature = "no-alloc-entrypoint", test))]
+mod no_alloc {
+    use solana_program::{
+        account_info::AccountInfo,
+        entrypoint::{BPF_ALIGN_OF_U128, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER},
+        pubkey::Pubkey,
+    };
+    use std::{cell::RefCell, mem::MaybeUninit, rc::Rc, slice};
+
+    /// Enough for every instruction but an Exchange paying a long list of NFT creators
+    pub const MAX_ACCOUNTS: usize = 32;
+
+    /// # Safety
+    #[cfg(feature = "no-alloc-entrypoint")]
+    #[no_mangle]
+    pub unsafe extern "C" fn entrypoint(input: *mut u8) -> u64 {
+        use solana_program::entrypoint::{deserialize, SUCCESS};
+
+        // An array of MaybeUninit needs no initializing
+        let mut accounts: [MaybeUninit<AccountInfo>; MAX_ACCOUNTS] = MaybeUninit::uninit().assume_init();
+        let result = match deserialize_into(input, &mut accounts) {
+            Some((program_id, accounts, instruction_data)) => {
+                super::process_instruction(program_id, accounts, instruction_data)
+            }
+            None => {
+                let (program_id, accounts, instruction_data) = deserialize(input);
+                super::process_instruction(program_id, &accounts, instruction_data)
+            }
+        };
+        match result {
+            Ok(()) => SUCCESS,
+            Err(error) => error.into(),
+        }
+    }
+
+    #[cfg(feature = "no-alloc-entrypoint")]
+    solana_program::custom_heap_default!();
+    #[cfg(feature = "no-alloc-entrypoint")]
+    solana_program::custom_panic_default!();
+
+    /// solana_program::entrypoint::deserialize, writing the account infos into `accounts`. None, having read nothing, if there are more accounts than fit.
+    ///
+    /// # Safety
+    ///
+    /// `input` has to point to parameters serialized by the runtime, aligned like the runtime aligns them
+    #[allow(clippy::cast_ptr_alignment)]
+    pub unsafe fn deserialize_into<'a>(
+        input: *mut u8,
+        accounts: &'a mut [MaybeUninit<AccountInfo<'a>>],
+    ) -> Option<(&'a Pubkey, &'a [AccountInfo<'a>], &'a [u8])> {
+        let mut offset = 0;
+
+        let num_accounts = *(input.add(offset) as *const u64) as usize;
+        offset += 8;
+        if num_accounts > accounts.len() {
+            return None;
+        }
+
+        for i in 0..num_accounts {
+            let dup_info = *input.add(offset);
+            offset += 1;
+            if dup_info != NON_DUP_MARKER {
+                offset += 7; // padding
+                let original = accounts[dup_info as usize].assume_init_ref().clone();
+                accounts[i].write(original);
+                continue;
+            }
+
+            let is_signer = *input.add(offset) != 0;
+            let is_writable = *input.add(offset + 1) != 0;
+            let executable = *input.add(offset + 2) != 0;
+            offset += 3;
+
+            // Where the runtime expects the original data length, to catch invalid reallocs
+            let original_data_len_offset = offset;
+            offset += 4;
+
+            let key = &*(input.add(offset) as *const Pubkey);
+            offset += 32;
+            let owner = &*(input.add(offset) as *const Pubkey);
+            offset += 32;
+
+            let lamports = Rc::new(RefCell::new(&mut *(input.add(offset) as *mut u64)));
+            offset += 8;
+
+            let data_len = *(input.add(offset) as *const u64) as usize;
+            offset += 8;
+            *(input.add(original_data_len_offset) as *mut u32) = data_len as u32;
+
+            let data = Rc::new(RefCell::new(slice::from_raw_parts_mut(input.add(offset), data_len)));
+            offset += data_len + MAX_PERMITTED_DATA_INCREASE;
+            offset += (offset as *const u8).align_offset(BPF_ALIGN_OF_U128);
+
+            let rent_epoch = *(input.add(offset) as *const u64);
+            offset += 8;
+
+            accounts[i].write(AccountInfo {
+                key,
+                is_signer,
+                is_writable,
+                lamports,
+                data,
+                owner,
+                executable,
+                rent_epoch,
+            });
+        }
+
+        let instruction_data_len = *(input.add(offset) as *const u64) as usize;
+        offset += 8;
+        let instruction_data = slice::from_raw_parts(input.add(offset), instruction_data_len);
+        offset += instruction_data_len;
+
+        let program_id = &*(input.add(offset) as *const Pubkey);
+
+        // The first num_accounts have been written
+        let accounts = slice::from_raw_parts(accounts.as_ptr() as *const AccountInfo, num_accounts);
+        Some((program_id, accounts, instruction_data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::no_alloc::{deserialize_into, MAX_ACCOUNTS};
+    use solana_program::{
+        account_info::AccountInfo,
+        entrypoint::{deserialize, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER},
+        pubkey::Pubkey,
+    };
+    use std::{mem::MaybeUninit, rc::Rc};
+
+    enum Entry {
+        Account { key: Pubkey, is_signer: bool, is_writable: bool, lamports: u64, data: Vec<u8> },
+        Duplicate(u8),
+    }
+
+    fn account(is_signer: bool, is_writable: bool, lamports: u64, data: Vec<u8>) -> Entry {
+        Entry::Account { key: Pubkey::new_unique(), is_signer, is_writable, lamports, data }
+    }
+
+    /// The parameters the way the runtime serializes them for a program, in u64s so they're aligned like the runtime's
+    fn serialize(entries: &[Entry], instruction_data: &[u8], program_id: &Pubkey) -> Vec<u64> {
+        let owner = Pubkey::new_unique();
+        let mut bytes = (entries.len() as u64).to_le_bytes().to_vec();
+        for entry in entries {
+            match entry {
+                Entry::Duplicate(index) => {
+                    bytes.push(*index);
+                    bytes.extend_from_slice(&[0; 7]);
+                }
+                Entry::Account { key, is_signer, is_writable, lamports, data } => {
+                    bytes.extend_from_slice(&[NON_DUP_MARKER, *is_signer as u8, *is_writable as u8, 0, 0, 0, 0, 0]);
+                    bytes.extend_from_slice(key.as_ref());
+                    bytes.extend_from_slice(owner.as_ref());
+                    bytes.extend_from_slice(&lamports.to_le_bytes());
+                    bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+                    bytes.extend_from_slice(data);
+                    bytes.resize((bytes.len() + MAX_PERMITTED_DATA_INCREASE).next_multiple_of(8), 0);
+                    bytes.extend_from_slice(&7u64.to_le_bytes());
+                }
+            }
+        }
+        bytes.extend_from_slice(&(instruction_data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(instruction_data);
+        bytes.extend_from_slice(program_id.as_ref());
+
+        let mut words = vec![0u64; bytes.len().div_ceil(8)];
+        bytemuck::cast_slice_mut::<u64, u8>(&mut words)[..bytes.len()].copy_from_slice(&bytes);
+        words
+    }
+
+    #[test]
+    fn deserialize_into_matches_deserialize() {
+        let program_id = Pubkey::new_unique();
+        let entries = [
+            account(true, true, 5, vec![1, 2, 3]),
+            account(false, false, 0, vec![]),
+            Entry::Duplicate(0),
+            // Not a multiple of 8, so the rent epoch after it needs padding
+            account(false, true, u64::MAX, vec![9; 13]),
+        ];
+        let mut buffer = serialize(&entries, &[4, 2], &program_id);
+        let mut expected_buffer = buffer.clone();
+
+        let (expected_program_id, expected_accounts, expected_data) =
+            unsafe { deserialize(expected_buffer.as_mut_ptr() as *mut u8) };
+        let mut storage: [MaybeUninit<AccountInfo>; MAX_ACCOUNTS] = unsafe { MaybeUninit::uninit().assume_init() };
+        let (program_id, accounts, data) =
+            unsafe { deserialize_into(buffer.as_mut_ptr() as *mut u8, &mut storage) }.unwrap();
+
+        assert_eq!(program_id, expected_program_id);
+        assert_eq!(data, expected_data);
+        assert_eq!(accounts.len(), expected_accounts.len());
+        for (account, expected) in accounts.iter().zip(expected_accounts.iter()) {
+            assert_eq!(
+                (account.key, account.owner, account.is_signer, account.is_writable, account.executable),
+                (expected.key, expected.owner, expected.is_signer, expected.is_writable, expected.executable)
+            );
+            assert_eq!((account.lamports(), account.rent_epoch), (expected.lamports(), expected.rent_epoch));
+            assert_eq!(*account.data.borrow(), *expected.data.borrow());
+        }
+        // A duplicate shares its original's lamports and data
+        assert!(Rc::ptr_eq(&accounts[2].data, &accounts[0].data));
+        // Including the original data lengths both write into the buffer
+        assert_eq!(buffer, expected_buffer);
+    }
+
+    #[test]
+    fn deserialize_into_leaves_too_many_accounts_to_deserialize() {
+        let entries: Vec<_> = (0..=MAX_ACCOUNTS).map(|_| account(false, false, 1, vec![])).collect();
+        let mut buffer = serialize(&entries, &[], &Pubkey::new_unique());
+        let mut storage: [MaybeUninit<AccountInfo>; MAX_ACCOUNTS] = unsafe { MaybeUninit::uninit().assume_init() };
+        assert!(unsafe { deserialize_into(buffer.as_mut_ptr() as *mut u8, &mut storage) }.is_none());
+    }
+}
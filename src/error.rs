@@ -1,8 +1,13 @@
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
 use thiserror::Error;
 
-use solana_program::program_error::ProgramError;
+use solana_program::{
+    decode_error::DecodeError,
+    program_error::{PrintProgramError, ProgramError},
+};
 
-#[derive(Error, Debug, Copy, Clone)]
+#[derive(Error, Debug, Copy, Clone, FromPrimitive, PartialEq)]
 pub enum EscrowError {
     /// Invalid instruction
     #[error("Invalid Instruction")]
@@ -16,6 +21,411 @@ pub enum EscrowError {
     /// Amount Overflow
     #[error("Amount Overflow")]
     AmountOverflow,
+    /// Expiry In The Past
+    #[error("Expiry In The Past")]
+    ExpiryInThePast,
+    /// Escrow Not Expired
+    #[error("Escrow Not Expired")]
+    EscrowNotExpired,
+    /// Escrow Expired
+    #[error("Escrow Expired")]
+    EscrowExpired,
+    /// Invalid Token Program
+    #[error("Invalid Token Program")]
+    InvalidTokenProgram,
+    /// Invalid Escrow Account
+    #[error("Invalid Escrow Account")]
+    InvalidEscrowAccount,
+    /// Token Mint Mismatch
+    #[error("Token Mint Mismatch")]
+    TokenMintMismatch,
+    /// Not A Non-Fungible Token
+    #[error("Not A Non-Fungible Token")]
+    NotNonFungible,
+    /// Invalid Fee Vault
+    #[error("Invalid Fee Vault")]
+    InvalidFeeVault,
+    /// Invalid Config Account
+    #[error("Invalid Config Account")]
+    InvalidConfigAccount,
+    /// Not The Upgrade Authority
+    #[error("Not The Upgrade Authority")]
+    NotUpgradeAuthority,
+    /// Fee Too High
+    #[error("Fee Too High")]
+    FeeTooHigh,
+    /// Unauthorized Admin
+    #[error("Unauthorized Admin")]
+    UnauthorizedAdmin,
+    /// Program Paused
+    #[error("Program Paused")]
+    ProgramPaused,
+    /// Mint Not Allowed
+    #[error("Mint Not Allowed")]
+    MintNotAllowed,
+    /// Allowlist Full
+    #[error("Allowlist Full")]
+    AllowlistFull,
+    /// Taker Not Allowed
+    #[error("Taker Not Allowed")]
+    TakerNotAllowed,
+    /// Invalid Auction Parameters
+    #[error("Invalid Auction Parameters")]
+    InvalidAuctionParameters,
+    /// Not An English Auction
+    #[error("Not An English Auction")]
+    NotAnEnglishAuction,
+    /// Escrow Only Accepts Bids
+    #[error("Escrow Only Accepts Bids")]
+    BidsOnly,
+    /// Bid Too Low
+    #[error("Bid Too Low")]
+    BidTooLow,
+    /// Auction Ended
+    #[error("Auction Ended")]
+    AuctionEnded,
+    /// Auction Not Ended
+    #[error("Auction Not Ended")]
+    AuctionNotEnded,
+    /// Auction Has Bids
+    #[error("Auction Has Bids")]
+    AuctionHasBids,
+    /// No Bids
+    #[error("No Bids")]
+    NoBids,
+    /// Winning Bid Can't Be Withdrawn
+    #[error("Winning Bid Can't Be Withdrawn")]
+    WinningBid,
+    /// Invalid Price Feed
+    #[error("Invalid Price Feed")]
+    InvalidPriceFeed,
+    /// Price Unavailable
+    #[error("Price Unavailable")]
+    PriceUnavailable,
+    /// Stale Price
+    #[error("Stale Price")]
+    StalePrice,
+    /// Invalid Premium
+    #[error("Invalid Premium")]
+    InvalidPremium,
+    /// Max Payment Exceeded
+    #[error("Max Payment Exceeded")]
+    MaxPaymentExceeded,
+    /// Native SOL Can't Be Delegated
+    #[error("Native SOL Can't Be Delegated")]
+    NativeSolNotDelegable,
+    /// Invalid Dispute Window
+    #[error("Invalid Dispute Window")]
+    InvalidDisputeWindow,
+    /// Not A Two-Phase Escrow
+    #[error("Not A Two-Phase Escrow")]
+    NotTwoPhase,
+    /// Escrow Has To Be Accepted
+    #[error("Escrow Has To Be Accepted")]
+    AcceptRequired,
+    /// Escrow Already Accepted
+    #[error("Escrow Already Accepted")]
+    AlreadyAccepted,
+    /// Escrow Not Accepted
+    #[error("Escrow Not Accepted")]
+    NotAccepted,
+    /// Dispute Window Still Open
+    #[error("Dispute Window Still Open")]
+    DisputeWindowOpen,
+    /// Invalid Approvers
+    #[error("Invalid Approvers")]
+    InvalidApprovers,
+    /// Approvers Already Set
+    #[error("Approvers Already Set")]
+    ApproversAlreadySet,
+    /// Not An Approver
+    #[error("Not An Approver")]
+    NotAnApprover,
+    /// Release Not Approved
+    #[error("Release Not Approved")]
+    ReleaseNotApproved,
+    /// Invalid Vesting Schedule
+    #[error("Invalid Vesting Schedule")]
+    InvalidVestingSchedule,
+    /// Invalid Vesting Account
+    #[error("Invalid Vesting Account")]
+    InvalidVestingAccount,
+    /// Nothing Vested Yet
+    #[error("Nothing Vested Yet")]
+    NothingVested,
+    /// Invalid Stream Parameters
+    #[error("Invalid Stream Parameters")]
+    InvalidStreamParameters,
+    /// Streaming Escrows Can Only Be Withdrawn From
+    #[error("Streaming Escrows Can Only Be Withdrawn From")]
+    StreamOnly,
+    /// Not A Streaming Escrow
+    #[error("Not A Streaming Escrow")]
+    NotAStream,
+    /// Nothing To Withdraw
+    #[error("Nothing To Withdraw")]
+    NothingToWithdraw,
+    /// Invalid Referral Share
+    #[error("Invalid Referral Share")]
+    InvalidReferralShare,
+    /// Escrows Don't Match
+    #[error("Escrows Don't Match")]
+    EscrowsDontMatch,
+    /// Market Full
+    #[error("Market Full")]
+    MarketFull,
+    /// Invalid Market
+    #[error("Invalid Market")]
+    InvalidMarket,
+    /// Escrow Can't Be Listed
+    #[error("Escrow Can't Be Listed")]
+    NotListable,
+    /// Order Already Placed
+    #[error("Order Already Placed")]
+    OrderAlreadyPlaced,
+    /// Order Not Found
+    #[error("Order Not Found")]
+    OrderNotFound,
+    /// Not The Best Orders
+    #[error("Not The Best Orders")]
+    NotBestOrders,
+    /// Registry Full
+    #[error("Registry Full")]
+    RegistryFull,
+    /// Invalid Registry
+    #[error("Invalid Registry")]
+    InvalidRegistry,
+    /// Escrow Already Migrated
+    #[error("Escrow Already Migrated")]
+    AlreadyMigrated,
+    /// Escrow Not Stale
+    #[error("Escrow Not Stale")]
+    EscrowNotStale,
+    /// Invalid Rate
+    #[error("Invalid Rate")]
+    InvalidRate,
+    /// Native SOL Can't Be Taken In Part
+    #[error("Native SOL Can't Be Taken In Part")]
+    NativeSolNotPartiallyFillable,
+    /// Net Amount Too Low
+    #[error("Net Amount Too Low")]
+    NetAmountTooLow,
+    /// Metadata Too Long
+    #[error("Metadata Too Long")]
+    MetadataTooLong,
+    /// Not Reservable
+    #[error("Not Reservable")]
+    NotReservable,
+    /// Escrow Reserved
+    #[error("Escrow Reserved")]
+    EscrowReserved,
+    /// Invalid Reservation Period
+    #[error("Invalid Reservation Period")]
+    InvalidReservationPeriod,
+    /// Invalid Taker Proof
+    #[error("Invalid Taker Proof")]
+    InvalidTakerProof,
+    /// Taker Allowlist Not Supported
+    #[error("Taker Allowlist Not Supported")]
+    TakerAllowlistNotSupported,
+    /// Invalid Gate Program
+    #[error("Invalid Gate Program")]
+    InvalidGateProgram,
+    /// Invalid Metadata Account
+    #[error("Invalid Metadata Account")]
+    InvalidMetadataAccount,
+    /// Creator Account Missing
+    #[error("Creator Account Missing")]
+    CreatorAccountMissing,
+    /// Not Programmable
+    #[error("Not Programmable")]
+    NotProgrammable,
+    /// Collection Offer Only
+    #[error("Collection Offer Only")]
+    CollectionOfferOnly,
+    /// Not A Collection Offer
+    #[error("Not A Collection Offer")]
+    NotCollectionOffer,
+    /// Collection Mismatch
+    #[error("Collection Mismatch")]
+    CollectionMismatch,
+    /// Expiry Too Far Ahead
+    #[error("Expiry Too Far Ahead")]
+    ExpiryTooFarAhead,
+    /// Deadline Not Extended
+    #[error("Deadline Not Extended")]
+    DeadlineNotExtended,
+    /// Delegated Escrow Not Transferable
+    #[error("Delegated Escrow Not Transferable")]
+    DelegatedEscrowNotTransferable,
+    /// Invalid Order Signature
+    #[error("Invalid Order Signature")]
+    InvalidOrderSignature,
+    /// Signed Order Expired
+    #[error("Signed Order Expired")]
+    SignedOrderExpired,
+    /// Account Not Writable
+    #[error("Account Not Writable")]
+    AccountNotWritable,
+    /// Too Many Payment Options
+    #[error("Too Many Payment Options")]
+    TooManyPaymentOptions,
+    /// Payment Options Not Supported
+    #[error("Payment Options Not Supported")]
+    PaymentOptionsNotSupported,
+    /// Stablecoin List Full
+    #[error("Stablecoin List Full")]
+    StablecoinListFull,
+    /// Stablecoin Not Listed
+    #[error("Stablecoin Not Listed")]
+    StablecoinNotListed,
+    /// Stablecoin Depegged
+    #[error("Stablecoin Depegged")]
+    StablecoinDepegged,
+    /// USD Pricing Not Supported
+    #[error("USD Pricing Not Supported")]
+    UsdPricingNotSupported,
+    /// Invalid Ring
+    #[error("Invalid Ring")]
+    InvalidRing,
+    /// Not A Ring Participant
+    #[error("Not A Ring Participant")]
+    NotARingParticipant,
+    /// Ring Leg Already Deposited
+    #[error("Ring Leg Already Deposited")]
+    RingLegAlreadyDeposited,
+    /// Ring Leg Not Deposited
+    #[error("Ring Leg Not Deposited")]
+    RingLegNotDeposited,
+    /// Ring Incomplete
+    #[error("Ring Incomplete")]
+    RingIncomplete,
+    /// Ring Expired
+    #[error("Ring Expired")]
+    RingExpired,
+    /// Ring Not Expired
+    #[error("Ring Not Expired")]
+    RingNotExpired,
+    /// Ring Has Deposits
+    #[error("Ring Has Deposits")]
+    RingHasDeposits,
+    /// Not Quotable
+    #[error("Not Quotable")]
+    NotQuotable,
+    /// Invalid Quote
+    #[error("Invalid Quote")]
+    InvalidQuote,
+    /// Quote Expired
+    #[error("Quote Expired")]
+    QuoteExpired,
+    /// Quote Size Mismatch
+    #[error("Quote Size Mismatch")]
+    QuoteSizeMismatch,
+    /// Not Negotiable
+    #[error("Not Negotiable")]
+    NotNegotiable,
+    /// Invalid Counteroffer
+    #[error("Invalid Counteroffer")]
+    InvalidCounteroffer,
+    /// Invalid Sealed Auction
+    #[error("Invalid Sealed Auction")]
+    InvalidSealedAuction,
+    /// Invalid Sealed Bid
+    #[error("Invalid Sealed Bid")]
+    InvalidSealedBid,
+    /// Commit Window Closed
+    #[error("Commit Window Closed")]
+    CommitWindowClosed,
+    /// Reveal Window Not Open
+    #[error("Reveal Window Not Open")]
+    RevealWindowNotOpen,
+    /// Reveal Window Closed
+    #[error("Reveal Window Closed")]
+    RevealWindowClosed,
+    /// Commitment Mismatch
+    #[error("Commitment Mismatch")]
+    CommitmentMismatch,
+    /// Reveal Window Open
+    #[error("Reveal Window Open")]
+    RevealWindowOpen,
+    /// Sealed Bids Outstanding
+    #[error("Sealed Bids Outstanding")]
+    SealedBidsOutstanding,
+    /// Invalid Raffle
+    #[error("Invalid Raffle")]
+    InvalidRaffle,
+    /// Invalid Ticket
+    #[error("Invalid Ticket")]
+    InvalidTicket,
+    /// Raffle Ended
+    #[error("Raffle Ended")]
+    RaffleEnded,
+    /// Raffle Sold Out
+    #[error("Raffle Sold Out")]
+    RaffleSoldOut,
+    /// Raffle Not Ended
+    #[error("Raffle Not Ended")]
+    RaffleNotEnded,
+    /// Raffle Already Drawn
+    #[error("Raffle Already Drawn")]
+    RaffleAlreadyDrawn,
+    /// Raffle Not Drawn
+    #[error("Raffle Not Drawn")]
+    RaffleNotDrawn,
+    /// Randomness Unavailable
+    #[error("Randomness Unavailable")]
+    RandomnessUnavailable,
+    /// Not The Winning Ticket
+    #[error("Not The Winning Ticket")]
+    NotTheWinningTicket,
+    /// Invalid Vrf Account
+    #[error("Invalid Vrf Account")]
+    InvalidVrfAccount,
+    /// Wrong Randomness Provider
+    #[error("Wrong Randomness Provider")]
+    WrongRandomnessProvider,
+    /// Invalid Escrow Pool
+    #[error("Invalid Escrow Pool")]
+    InvalidEscrowPool,
+    /// Invalid Pool Entry
+    #[error("Invalid Pool Entry")]
+    InvalidPoolEntry,
+    /// Escrow Pool Full
+    #[error("Escrow Pool Full")]
+    EscrowPoolFull,
+    /// Pool Entry Changed
+    #[error("Pool Entry Changed")]
+    PoolEntryChanged,
+    /// Escrow Pool Not Empty
+    #[error("Escrow Pool Not Empty")]
+    EscrowPoolNotEmpty,
+    /// Invalid Compressed Tree
+    #[error("Invalid Compressed Tree")]
+    InvalidCompressedTree,
+    /// Compressed Tree Full
+    #[error("Compressed Tree Full")]
+    CompressedTreeFull,
+    /// Delegation Missing
+    #[error("Delegation Missing")]
+    DelegationMissing,
+    /// Token Account Already Delegated
+    #[error("Token Account Already Delegated")]
+    TokenAccountAlreadyDelegated,
+    /// Amount Underflow
+    #[error("Amount Underflow")]
+    AmountUnderflow,
+    /// Lamport Overflow
+    #[error("Lamport Overflow")]
+    LamportOverflow,
+    /// Price Overflow
+    #[error("Price Overflow")]
+    PriceOverflow,
+    /// Fee Overflow
+    #[error("Fee Overflow")]
+    FeeOverflow,
+    /// Time Overflow
+    #[error("Time Overflow")]
+    TimeOverflow,
 }
 
 impl From<EscrowError> for ProgramError {
@@ -27,3 +437,34 @@ impl From<EscrowError> for ProgramError {
 // Let's stop for a moment to understand what is happening here. We are implementing a generic trait, specifically the From (opens new window)trait which the ? operator wants to use. To implement this trait we have to implement the from function which carries out the conversion. The ProgramError enum provides the Custom variant that allows us to convert from our program's EscrowError to a ProgramError.
 
 // The reason we do this conversion in the first place is that the entrypoint returns a Result of either nothing or a ProgramError.
+
+// DecodeError and FromPrimitive let off-chain tools (and the entrypoint) turn a Custom(n) code back into the EscrowError it came from
+impl<T> DecodeError<T> for EscrowError {
+    fn type_of() -> &'static str {
+        "EscrowError"
+    }
+}
+
+impl PrintProgramError for EscrowError {
+    fn print<E>(&self)
+    where
+        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
+    {
+        log!("Error: {}", self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_error_codes_decode() {
+        let code = match ProgramError::from(EscrowError::RegistryFull) {
+            ProgramError::Custom(code) => code,
+            error => panic!("unexpected error {:?}", error),
+        };
+        assert_eq!(EscrowError::from_u32(code), Some(EscrowError::RegistryFull));
+        assert_eq!(<EscrowError as DecodeError<EscrowError>>::type_of(), "EscrowError");
+    }
+}
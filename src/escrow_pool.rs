@@ -0,0 +1,263 @@
+//! Escrow pools let a maker post many small escrows of one token pair without paying the rent of an escrow account and a temp token account for each. The pool is a single account the maker creates, as large as they like, holding a header followed by fixed-size entries, and all of its token X sits in one vault.
+//!
+//! Entries are handed out from a free list: a cancelled or taken entry becomes the head of the list and is reused by the next PostPoolEntry, and entries past the highest one used so far are only touched once the list is empty, so a large pool doesn't have to be written in full when it is initialized. Since an entry can be reused for a different offer, TakePoolEntry names the amount of token X it expects, like Exchange does.
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+use crate::error::EscrowError;
+
+/// Marks the end of the free list
+pub const NO_ENTRY: u32 = u32::MAX;
+
+/// The header of a pool account. Its token X is held in a token account at the PDA derived from `[b"escrow_pool_vault", pool pubkey]`, owned by the PDA derived from `[b"escrow_pool", pool pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct EscrowPool {
+    pub is_initialized: bool,
+    /// The bump seed of the pool's authority PDA
+    pub authority_bump_seed: u8,
+    pub maker: Pubkey,
+    /// The maker's token account of token Y, which every entry's payment goes to
+    pub maker_token_to_receive_account: Pubkey,
+    pub token_x_mint: Pubkey,
+    pub token_y_mint: Pubkey,
+    /// How many entries fit into the account
+    pub capacity: u32,
+    pub open_entries: u32,
+    /// Entries from here on have never been used
+    pub next_unused_entry: u32,
+    /// The most recently freed entry, NO_ENTRY if there is none
+    pub free_list_head: u32,
+}
+
+impl EscrowPool {
+    /// The size of a pool account with room for `capacity` entries
+    pub fn space(capacity: u32) -> usize {
+        EscrowPool::LEN + capacity as usize * PoolEntry::LEN
+    }
+
+    /// How many entries fit into a pool account of `data_len` bytes
+    pub fn capacity_for(data_len: usize) -> u32 {
+        (data_len.saturating_sub(EscrowPool::LEN) / PoolEntry::LEN).min(NO_ENTRY as usize) as u32
+    }
+
+    /// Reads the header out of the pool account's data
+    pub fn unpack_header(data: &[u8]) -> Result<Self, ProgramError> {
+        Self::unpack_from_slice(data.get(..EscrowPool::LEN).ok_or(EscrowError::InvalidEscrowPool)?)
+    }
+
+    pub fn pack_header(self, data: &mut [u8]) {
+        self.pack_into_slice(&mut data[..EscrowPool::LEN])
+    }
+
+    pub fn load_entry(&self, data: &[u8], index: u32) -> Result<PoolEntry, ProgramError> {
+        if index >= self.capacity {
+            return Err(EscrowError::InvalidPoolEntry.into());
+        }
+        let offset = EscrowPool::space(index);
+        PoolEntry::unpack_from_slice(&data[offset..offset + PoolEntry::LEN])
+    }
+
+    pub fn store_entry(&self, data: &mut [u8], index: u32, entry: &PoolEntry) {
+        let offset = EscrowPool::space(index);
+        entry.pack_into_slice(&mut data[offset..offset + PoolEntry::LEN])
+    }
+
+    /// Takes an entry off the free list, or the next unused one if the list is empty. None if the pool is full.
+    pub fn allocate_entry(&mut self, data: &[u8]) -> Result<Option<u32>, ProgramError> {
+        let index = if self.free_list_head != NO_ENTRY {
+            let index = self.free_list_head;
+            self.free_list_head = self.load_entry(data, index)?.next_free;
+            index
+        } else if self.next_unused_entry < self.capacity {
+            // Below capacity, a u32 itself, so this can't wrap
+            self.next_unused_entry += 1;
+            self.next_unused_entry - 1
+        } else {
+            return Ok(None);
+        };
+        // Every open entry has an index below capacity, so there are never more than a u32 of them
+        self.open_entries += 1;
+        Ok(Some(index))
+    }
+
+    /// Clears an open entry and puts it at the head of the free list
+    pub fn free_entry(&mut self, data: &mut [u8], index: u32) {
+        self.store_entry(data, index, &PoolEntry { next_free: self.free_list_head, ..PoolEntry::default() });
+        self.free_list_head = index;
+        // Callers only free entries they found open, which open_entries counts
+        self.open_entries -= 1;
+    }
+}
+
+impl Sealed for EscrowPool {}
+
+impl IsInitialized for EscrowPool {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for EscrowPool {
+    const LEN: usize = 146;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, EscrowPool::LEN];
+        let (
+            is_initialized,
+            authority_bump_seed,
+            maker,
+            maker_token_to_receive_account,
+            token_x_mint,
+            token_y_mint,
+            capacity,
+            open_entries,
+            next_unused_entry,
+            free_list_head,
+        ) = array_refs![src, 1, 1, 32, 32, 32, 32, 4, 4, 4, 4];
+
+        Ok(EscrowPool {
+            is_initialized: unpack_bool(is_initialized)?,
+            authority_bump_seed: authority_bump_seed[0],
+            maker: Pubkey::new_from_array(*maker),
+            maker_token_to_receive_account: Pubkey::new_from_array(*maker_token_to_receive_account),
+            token_x_mint: Pubkey::new_from_array(*token_x_mint),
+            token_y_mint: Pubkey::new_from_array(*token_y_mint),
+            capacity: u32::from_le_bytes(*capacity),
+            open_entries: u32::from_le_bytes(*open_entries),
+            next_unused_entry: u32::from_le_bytes(*next_unused_entry),
+            free_list_head: u32::from_le_bytes(*free_list_head),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, EscrowPool::LEN];
+        let (
+            is_initialized_dst,
+            authority_bump_seed_dst,
+            maker_dst,
+            maker_token_to_receive_account_dst,
+            token_x_mint_dst,
+            token_y_mint_dst,
+            capacity_dst,
+            open_entries_dst,
+            next_unused_entry_dst,
+            free_list_head_dst,
+        ) = mut_array_refs![dst, 1, 1, 32, 32, 32, 32, 4, 4, 4, 4];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        authority_bump_seed_dst[0] = self.authority_bump_seed;
+        maker_dst.copy_from_slice(self.maker.as_ref());
+        maker_token_to_receive_account_dst.copy_from_slice(self.maker_token_to_receive_account.as_ref());
+        token_x_mint_dst.copy_from_slice(self.token_x_mint.as_ref());
+        token_y_mint_dst.copy_from_slice(self.token_y_mint.as_ref());
+        *capacity_dst = self.capacity.to_le_bytes();
+        *open_entries_dst = self.open_entries.to_le_bytes();
+        *next_unused_entry_dst = self.next_unused_entry.to_le_bytes();
+        *free_list_head_dst = self.free_list_head.to_le_bytes();
+    }
+}
+
+/// One offer in a pool: amount_x of token X for amount_y of token Y
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct PoolEntry {
+    pub is_open: bool,
+    /// The next entry of the free list while this one is free
+    pub next_free: u32,
+    pub amount_x: u64,
+    pub amount_y: u64,
+    /// 0 if the entry never expires
+    pub expiry_unix_timestamp: i64,
+}
+
+impl PoolEntry {
+    pub fn is_expired(&self, unix_timestamp: i64) -> bool {
+        self.expiry_unix_timestamp != 0 && unix_timestamp >= self.expiry_unix_timestamp
+    }
+}
+
+impl Sealed for PoolEntry {}
+
+impl Pack for PoolEntry {
+    const LEN: usize = 29;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, PoolEntry::LEN];
+        let (is_open, next_free, amount_x, amount_y, expiry_unix_timestamp) = array_refs![src, 1, 4, 8, 8, 8];
+
+        Ok(PoolEntry {
+            is_open: unpack_bool(is_open)?,
+            next_free: u32::from_le_bytes(*next_free),
+            amount_x: u64::from_le_bytes(*amount_x),
+            amount_y: u64::from_le_bytes(*amount_y),
+            expiry_unix_timestamp: i64::from_le_bytes(*expiry_unix_timestamp),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, PoolEntry::LEN];
+        let (is_open_dst, next_free_dst, amount_x_dst, amount_y_dst, expiry_dst) =
+            mut_array_refs![dst, 1, 4, 8, 8, 8];
+
+        is_open_dst[0] = self.is_open as u8;
+        *next_free_dst = self.next_free.to_le_bytes();
+        *amount_x_dst = self.amount_x.to_le_bytes();
+        *amount_y_dst = self.amount_y.to_le_bytes();
+        *expiry_dst = self.expiry_unix_timestamp.to_le_bytes();
+    }
+}
+
+fn unpack_bool(src: &[u8; 1]) -> Result<bool, ProgramError> {
+    match src {
+        [0] => Ok(false),
+        [1] => Ok(true),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn freed_entries_are_reused_before_unused_ones() {
+        let mut data = vec![0u8; EscrowPool::space(3) + PoolEntry::LEN - 1];
+        let mut pool = EscrowPool {
+            is_initialized: true,
+            authority_bump_seed: 254,
+            maker: Pubkey::new_unique(),
+            maker_token_to_receive_account: Pubkey::new_unique(),
+            token_x_mint: Pubkey::new_unique(),
+            token_y_mint: Pubkey::new_unique(),
+            capacity: EscrowPool::capacity_for(data.len()),
+            open_entries: 0,
+            next_unused_entry: 0,
+            free_list_head: NO_ENTRY,
+        };
+        assert_eq!(pool.capacity, 3);
+
+        let entry = PoolEntry { is_open: true, next_free: 0, amount_x: 10, amount_y: 20, expiry_unix_timestamp: 0 };
+        for index in 0..3 {
+            assert_eq!(pool.allocate_entry(&data).unwrap(), Some(index));
+            pool.store_entry(&mut data, index, &entry);
+        }
+        assert_eq!(pool.allocate_entry(&data).unwrap(), None);
+
+        pool.free_entry(&mut data, 0);
+        pool.free_entry(&mut data, 2);
+        assert_eq!(pool.open_entries, 1);
+        assert_eq!(pool.allocate_entry(&data).unwrap(), Some(2));
+        assert_eq!(pool.allocate_entry(&data).unwrap(), Some(0));
+        assert_eq!(pool.allocate_entry(&data).unwrap(), None);
+        assert_eq!(pool.load_entry(&data, 1).unwrap(), entry);
+        assert!(pool.load_entry(&data, 3).is_err());
+
+        pool.pack_header(&mut data);
+        assert_eq!(EscrowPool::unpack_header(&data).unwrap(), pool);
+        assert_eq!(pool.try_to_vec().unwrap(), data[..EscrowPool::LEN]);
+        assert_eq!(entry.try_to_vec().unwrap().len(), PoolEntry::LEN);
+    }
+}
@@ -0,0 +1,292 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    entrypoint::ProgramResult, log::sol_log_data, program_error::ProgramError, pubkey::Pubkey,
+};
+
+use crate::compressed_escrow::CompressedEscrow;
+
+/// Events are written to the transaction logs with sol_log_data as Borsh-encoded bytes, so indexers and UIs can follow escrows without parsing msg! strings. They show up as `Program data: <base64>` log lines, where the first byte is the variant index. The schema is part of the program's API: only ever append variants and never reorder or change fields.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum EscrowEvent {
+    EscrowInitialized {
+        escrow: Pubkey,
+        initializer: Pubkey,
+        token_x_mint: Pubkey,
+        token_y_mint: Pubkey,
+        /// The amount of token X put in the escrow
+        amount_x: u64,
+        /// The amount of token Y the initializer expects in return
+        expected_amount: u64,
+        expiry_unix_timestamp: i64,
+    },
+    EscrowExchanged {
+        escrow: Pubkey,
+        initializer: Pubkey,
+        taker: Pubkey,
+        /// The amount of token X the taker received
+        amount_x: u64,
+        /// The amount of token Y the initializer received
+        amount_y: u64,
+    },
+    EscrowCancelled {
+        escrow: Pubkey,
+        initializer: Pubkey,
+        /// The amount of token X returned to the initializer
+        amount_x: u64,
+        /// true if the escrow was reclaimed after its expiry rather than cancelled by the initializer
+        expired: bool,
+    },
+    ProtocolFeeCollected {
+        escrow: Pubkey,
+        /// The mint of the fee vault, i.e. the escrow's token Y
+        mint: Pubkey,
+        amount: u64,
+    },
+    EscrowUpdated {
+        escrow: Pubkey,
+        initializer: Pubkey,
+        /// The new amount of token Y the initializer expects in return
+        expected_amount: u64,
+    },
+    BidPlaced {
+        escrow: Pubkey,
+        bidder: Pubkey,
+        /// The bidder's total bid in token Y
+        amount: u64,
+    },
+    EscrowAccepted {
+        escrow: Pubkey,
+        taker: Pubkey,
+        /// The amount of token Y the taker paid, held until the escrow settles
+        amount_y: u64,
+    },
+    ReleaseApproved {
+        escrow: Pubkey,
+        approver: Pubkey,
+        /// How many approvers have approved so far
+        approvals: u8,
+    },
+    VestedClaimed {
+        escrow: Pubkey,
+        beneficiary: Pubkey,
+        amount: u64,
+        /// What is still left in the vault after this claim
+        remaining_amount: u64,
+    },
+    StreamWithdrawn {
+        escrow: Pubkey,
+        recipient: Pubkey,
+        amount: u64,
+        /// How much of the stream the recipient has withdrawn in total
+        withdrawn_amount: u64,
+    },
+    /// Emitted next to EscrowExchanged when the taker was referred. amount is the referrer's part of the protocol fee, ProtocolFeeCollected then has the rest.
+    ReferralFeePaid {
+        escrow: Pubkey,
+        /// The owner of the referrer's token account
+        referrer: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+    },
+    /// Emitted next to EscrowInitialized when the initializer attached metadata to the escrow
+    EscrowMetadataSet {
+        escrow: Pubkey,
+        metadata: Vec<u8>,
+    },
+    /// A taker locked the escrow's reservation deposit, see Reserve
+    EscrowReserved {
+        escrow: Pubkey,
+        taker: Pubkey,
+        deposit: u64,
+        reserved_until_slot: u64,
+    },
+    /// A reservation ended: the deposit went back to the taker if they exchanged in time, and to the initializer otherwise
+    ReservationReleased {
+        escrow: Pubkey,
+        taker: Pubkey,
+        deposit: u64,
+        forfeited: bool,
+    },
+    /// Emitted next to EscrowCancelled when the config charges a cancellation penalty. amount is in token X and EscrowCancelled's amount_x is what was left after it.
+    CancelPenaltyPaid {
+        escrow: Pubkey,
+        /// The owner of the token account the penalty was paid into
+        recipient: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+    },
+    /// Emitted by Exchange for each creator paid a royalty on an NFT, in token Y. The initializer received the price less the royalties.
+    RoyaltyPaid {
+        escrow: Pubkey,
+        creator: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+    },
+    /// The initializer and the taker undid an accepted two-phase escrow, see Abort. The taker's payment is still in their bid account until they withdraw it.
+    AcceptanceAborted {
+        escrow: Pubkey,
+        taker: Pubkey,
+    },
+    DeadlineExtended {
+        escrow: Pubkey,
+        initializer: Pubkey,
+        /// The escrow's new expiry
+        expiry_unix_timestamp: i64,
+    },
+    EscrowTransferred {
+        escrow: Pubkey,
+        previous_initializer: Pubkey,
+        new_initializer: Pubkey,
+    },
+    RelayerTipPaid {
+        escrow: Pubkey,
+        relayer: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+    },
+    /// The initializer added, updated or, with an amount of 0, removed a payment option, see SetPaymentOption
+    PaymentOptionSet {
+        escrow: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+    },
+    /// The initializer priced the escrow in USD, or with a usd_amount of 0 back in token Y, see SetUsdPrice
+    UsdPriceSet {
+        escrow: Pubkey,
+        initializer: Pubkey,
+        usd_amount: u64,
+    },
+    /// A participant deposited their leg of a swap ring, see DepositRingLeg
+    RingLegDeposited {
+        ring: Pubkey,
+        participant: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+    },
+    /// Every leg of a swap ring was paid out to the next participant, see ExecuteRing
+    RingExecuted {
+        ring: Pubkey,
+        leg_count: u8,
+    },
+    /// A participant took their deposit back from an expired swap ring, see WithdrawRingLeg
+    RingLegWithdrawn {
+        ring: Pubkey,
+        participant: Pubkey,
+        amount: u64,
+    },
+    /// A taker quoted a price for an escrow, see PostQuote
+    QuotePosted {
+        escrow: Pubkey,
+        quoter: Pubkey,
+        size: u64,
+        price: u64,
+        expiry_unix_timestamp: i64,
+    },
+    /// The initializer settled the escrow with a quote, see AcceptQuote. An EscrowExchanged event follows.
+    QuoteAccepted {
+        escrow: Pubkey,
+        quoter: Pubkey,
+    },
+    /// A taker proposed, changed or, with an amount of 0, withdrew a counteroffer, see ProposeCounteroffer
+    CounterofferProposed {
+        escrow: Pubkey,
+        taker: Pubkey,
+        amount: u64,
+    },
+    /// A bidder committed to a bid on a sealed-bid auction, or changed their commitment, see CommitBid
+    BidCommitted {
+        escrow: Pubkey,
+        bidder: Pubkey,
+    },
+    /// A bidder revealed their sealed bid and got their deposit back. is_highest_bid is set if the bid beat every bid revealed before it and was paid into the bidder's bid account.
+    BidRevealed {
+        escrow: Pubkey,
+        bidder: Pubkey,
+        amount: u64,
+        is_highest_bid: bool,
+    },
+    /// A sealed bid wasn't revealed in time and its deposit went to the initializer, see SlashBid
+    BidSlashed {
+        escrow: Pubkey,
+        bidder: Pubkey,
+        deposit: u64,
+    },
+    /// A taker bought tickets first_ticket to first_ticket + count - 1 of a raffle, see BuyTickets
+    TicketsBought {
+        escrow: Pubkey,
+        buyer: Pubkey,
+        first_ticket: u32,
+        count: u32,
+    },
+    /// A raffle committed to the slot whose hash picks its winner, see DrawRaffle
+    RaffleDrawn {
+        escrow: Pubkey,
+        draw_slot: u64,
+    },
+    /// A raffle was settled to the buyer of the winning ticket. An EscrowExchanged event follows, with the ticket sales as amount_y. For raffles drawn with VRF, draw_hash is the VRF's result.
+    RaffleSettled {
+        escrow: Pubkey,
+        winner: Pubkey,
+        winning_ticket: u32,
+        tickets_sold: u32,
+        draw_hash: [u8; 32],
+    },
+    /// Randomness was requested from the VRF a raffle is drawn with. Its result for the request numbered counter picks the winner.
+    RandomnessRequested {
+        escrow: Pubkey,
+        vrf: Pubkey,
+        counter: u128,
+    },
+    /// The VRF's result was stored in the raffle, which can be settled now
+    RandomnessFulfilled {
+        escrow: Pubkey,
+        randomness: [u8; 32],
+    },
+    /// An offer was posted to an escrow pool, at the entry numbered index
+    PoolEntryPosted {
+        pool: Pubkey,
+        index: u32,
+        amount_x: u64,
+        amount_y: u64,
+        expiry_unix_timestamp: i64,
+    },
+    /// A taker took an entry of an escrow pool, paying amount_y plus the protocol fee for amount_x
+    PoolEntryTaken {
+        pool: Pubkey,
+        index: u32,
+        taker: Pubkey,
+        amount_x: u64,
+        amount_y: u64,
+    },
+    /// The maker cancelled an entry of an escrow pool and got its token X back
+    PoolEntryCancelled {
+        pool: Pubkey,
+        index: u32,
+    },
+    /// A compressed escrow was appended to a merkle tree. This is the only place the escrow is recorded in full, so indexers have to keep it to build the proofs exchanging or cancelling it takes.
+    CompressedEscrowCreated {
+        merkle_tree: Pubkey,
+        escrow: CompressedEscrow,
+    },
+    /// A compressed escrow was exchanged and its leaf cleared
+    CompressedEscrowExchanged {
+        merkle_tree: Pubkey,
+        leaf_index: u32,
+        taker: Pubkey,
+    },
+    /// A compressed escrow was cancelled and its leaf cleared
+    CompressedEscrowCancelled {
+        merkle_tree: Pubkey,
+        leaf_index: u32,
+    },
+}
+
+impl EscrowEvent {
+    pub fn emit(&self) -> ProgramResult {
+        let data = self
+            .try_to_vec()
+            .map_err(|e| ProgramError::BorshIoError(e.to_string()))?;
+        sol_log_data(&[&data]);
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+//! Every field the escrow layout has gained so far was appended to Escrow, which made each one a new version that older escrows had to be migrated to. Extensions are the alternative for optional fields, modelled on Token-2022's: an escrow account may be longer than Escrow::LEN, and the bytes after the base hold type-length-value entries. Each entry is a little-endian u16 ExtensionType, a little-endian u16 length and that many bytes of value. The first entry of type Uninitialized ends the list, so an account is sized for its extensions up front and the rest of it stays zeroed until they are initialized.
+//!
+//! The base layout stays what VersionedEscrow reads, so an escrow without extensions is exactly what it was before, and programs that only know the base can read the first Escrow::LEN bytes of any escrow account.
+
+use bytemuck::{try_from_bytes, try_from_bytes_mut, Pod, Zeroable};
+use solana_program::{program_error::ProgramError, program_pack::Pack};
+use std::{
+    convert::{TryFrom, TryInto},
+    mem::size_of,
+};
+
+use crate::state::Escrow;
+
+const TYPE_LEN: usize = 2;
+const LENGTH_LEN: usize = 2;
+/// The size of an entry's type and length
+pub const TLV_HEADER_LEN: usize = TYPE_LEN + LENGTH_LEN;
+
+/// The kinds of extension. Values are part of the account layout: only ever append.
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExtensionType {
+    /// Unused space, which ends the list of entries
+    Uninitialized = 0,
+    /// A protocol fee for this escrow instead of the config's
+    FeeOverride = 1,
+}
+
+impl TryFrom<u16> for ExtensionType {
+    type Error = ProgramError;
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ExtensionType::Uninitialized),
+            1 => Ok(ExtensionType::FeeOverride),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+impl ExtensionType {
+    /// The size of the extension's value
+    pub fn value_len(&self) -> usize {
+        match self {
+            ExtensionType::Uninitialized => 0,
+            ExtensionType::FeeOverride => size_of::<FeeOverride>(),
+        }
+    }
+}
+
+/// A fixed-size extension value. Like Escrow, values are `repr(C, packed)` Pods, so they can be borrowed from unaligned account data.
+pub trait Extension: Pod {
+    const TYPE: ExtensionType;
+}
+
+/// A protocol fee for this escrow in place of the config's fee_bps
+#[repr(C, packed)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, Default, PartialEq)]
+pub struct FeeOverride {
+    pub fee_bps: u16,
+}
+
+impl Extension for FeeOverride {
+    const TYPE: ExtensionType = ExtensionType::FeeOverride;
+}
+
+/// The size of an escrow account with room for the given extensions
+pub fn escrow_space(extension_types: &[ExtensionType]) -> usize {
+    let tlv_len: usize = extension_types.iter().map(|extension_type| TLV_HEADER_LEN + extension_type.value_len()).sum();
+    Escrow::LEN + tlv_len
+}
+
+/// The part of an escrow account's data that holds the extensions, empty for an escrow without any
+pub fn tlv_data(account_data: &[u8]) -> &[u8] {
+    account_data.get(Escrow::LEN..).unwrap_or_default()
+}
+
+pub fn tlv_data_mut(account_data: &mut [u8]) -> &mut [u8] {
+    account_data.get_mut(Escrow::LEN..).unwrap_or_default()
+}
+
+/// The types of the initialized extensions, in order
+pub fn extension_types(tlv_data: &[u8]) -> Result<Vec<ExtensionType>, ProgramError> {
+    let mut types = vec![];
+    let mut offset = 0;
+    while let Some((extension_type, value_start, value_len)) = read_header(tlv_data, offset)? {
+        types.push(extension_type);
+        offset = value_start + value_len;
+    }
+    Ok(types)
+}
+
+/// Borrows the value of an extension. Fails if the escrow doesn't have it.
+pub fn get_extension<T: Extension>(tlv_data: &[u8]) -> Result<&T, ProgramError> {
+    let (start, end) = find_value(tlv_data, T::TYPE)?.ok_or(ProgramError::InvalidAccountData)?;
+    try_from_bytes(&tlv_data[start..end]).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+/// Like get_extension, but the value can be updated in place
+pub fn get_extension_mut<T: Extension>(tlv_data: &mut [u8]) -> Result<&mut T, ProgramError> {
+    let (start, end) = find_value(tlv_data, T::TYPE)?.ok_or(ProgramError::InvalidAccountData)?;
+    try_from_bytes_mut(&mut tlv_data[start..end]).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+/// Adds an extension in the first unused space and returns its zeroed value. Fails if the escrow already has it or there is no room left.
+pub fn init_extension<T: Extension>(tlv_data: &mut [u8]) -> Result<&mut T, ProgramError> {
+    let mut offset = 0;
+    while let Some((extension_type, value_start, value_len)) = read_header(tlv_data, offset)? {
+        if extension_type == T::TYPE {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        offset = value_start + value_len;
+    }
+
+    let value_start = offset + TLV_HEADER_LEN;
+    let value_end = value_start + size_of::<T>();
+    if value_end > tlv_data.len() {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    tlv_data[offset..offset + TYPE_LEN].copy_from_slice(&(T::TYPE as u16).to_le_bytes());
+    tlv_data[offset + TYPE_LEN..value_start].copy_from_slice(&(size_of::<T>() as u16).to_le_bytes());
+    tlv_data[value_start..value_end].fill(0);
+    try_from_bytes_mut(&mut tlv_data[value_start..value_end]).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+// The start and end of an extension's value, None if the escrow doesn't have it
+fn find_value(tlv_data: &[u8], wanted: ExtensionType) -> Result<Option<(usize, usize)>, ProgramError> {
+    let mut offset = 0;
+    while let Some((extension_type, value_start, value_len)) = read_header(tlv_data, offset)? {
+        if extension_type == wanted {
+            if value_len != wanted.value_len() {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            return Ok(Some((value_start, value_start + value_len)));
+        }
+        offset = value_start + value_len;
+    }
+    Ok(None)
+}
+
+// The entry at offset, None once the entries end. Too little space left for a header ends them too, since an account may be sized with some spare bytes.
+fn read_header(tlv_data: &[u8], offset: usize) -> Result<Option<(ExtensionType, usize, usize)>, ProgramError> {
+    let header = match tlv_data.get(offset..offset + TLV_HEADER_LEN) {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    let extension_type = ExtensionType::try_from(u16::from_le_bytes(header[..TYPE_LEN].try_into().unwrap()))?;
+    if extension_type == ExtensionType::Uninitialized {
+        return Ok(None);
+    }
+    let value_len = u16::from_le_bytes(header[TYPE_LEN..].try_into().unwrap()) as usize;
+    let value_start = offset + TLV_HEADER_LEN;
+    if value_start + value_len > tlv_data.len() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(Some((extension_type, value_start, value_len)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extensions_are_written_after_the_base() {
+        let mut data = vec![0u8; escrow_space(&[ExtensionType::FeeOverride]) + 1];
+        assert_eq!(tlv_data(&data).len(), TLV_HEADER_LEN + 2 + 1);
+        assert_eq!(extension_types(tlv_data(&data)).unwrap(), vec![]);
+        assert!(get_extension::<FeeOverride>(tlv_data(&data)).is_err());
+
+        init_extension::<FeeOverride>(tlv_data_mut(&mut data)).unwrap().fee_bps = 25;
+        assert_eq!(&data[Escrow::LEN..Escrow::LEN + TLV_HEADER_LEN], &[1, 0, 2, 0]);
+        assert_eq!({ get_extension::<FeeOverride>(tlv_data(&data)).unwrap().fee_bps }, 25);
+        assert_eq!(extension_types(tlv_data(&data)).unwrap(), vec![ExtensionType::FeeOverride]);
+        assert_eq!(
+            init_extension::<FeeOverride>(tlv_data_mut(&mut data)),
+            Err(ProgramError::AccountAlreadyInitialized)
+        );
+
+        // A length running past the end of the account is corrupt, not the end of the list
+        data[Escrow::LEN + TYPE_LEN] = 9;
+        assert!(extension_types(tlv_data(&data)).is_err());
+        assert!(tlv_data(&data[..Escrow::LEN - 1]).is_empty());
+    }
+}
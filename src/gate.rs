@@ -0,0 +1,59 @@
+//! Gate programs let a deployment decide who may take escrows, e.g. only wallets holding a KYC credential, without this program knowing about the credential. The config names the gate program, see SetGateProgram, and the program calls its Check instruction before a taker's tokens move. The gate approves by returning Ok and rejects by returning any error, which fails the whole transaction.
+//!
+//! Check's data is CHECK_DISCRIMINATOR followed by the taker's pubkey. Its accounts are the taker (a signer), the escrow account, and then every account of the instruction owned by the gate program, in the order they were passed, so the gate can be handed whatever credential accounts it reads.
+
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// The first 8 bytes of sha256("escrow-gate-interface:check")
+pub const CHECK_DISCRIMINATOR: [u8; 8] = [65, 224, 178, 179, 99, 204, 65, 198];
+
+/// The Check instruction for `gate_program_id`, with the gate's own accounts out of `gate_accounts`
+pub fn check_instruction(
+    gate_program_id: &Pubkey,
+    taker: &Pubkey,
+    escrow_account: &Pubkey,
+    gate_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    let mut data = CHECK_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(taker.as_ref());
+    let mut accounts = vec![AccountMeta::new_readonly(*taker, true), AccountMeta::new_readonly(*escrow_account, false)];
+    accounts.extend(gate_accounts);
+    Instruction { program_id: *gate_program_id, accounts, data }
+}
+
+/// Asks the gate program whether `taker` may take the escrow. The gate program is looked up by key among `accounts`, and so are its own accounts, by owner.
+pub fn check_taker<'a>(
+    gate_program_id: &Pubkey,
+    taker: &AccountInfo<'a>,
+    escrow_account: &AccountInfo<'a>,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let gate_program = accounts
+        .iter()
+        .find(|account| account.key == gate_program_id)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let gate_accounts: Vec<&AccountInfo> = accounts
+        .iter()
+        .filter(|account| account.owner == gate_program_id)
+        .collect();
+
+    let check_ix = check_instruction(
+        gate_program_id,
+        taker.key,
+        escrow_account.key,
+        gate_accounts
+            .iter()
+            .map(|account| AccountMeta { pubkey: *account.key, is_signer: false, is_writable: account.is_writable })
+            .collect(),
+    );
+    let mut account_infos = vec![taker.clone(), escrow_account.clone(), gate_program.clone()];
+    account_infos.extend(gate_accounts.into_iter().cloned());
+    invoke(&check_ix, &account_infos)
+}
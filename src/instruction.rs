@@ -1,9 +1,44 @@
-use solana_program::program_error::ProgramError;
-use std::convert::TryInto;
+use arrayref::array_ref;
+use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::try_cast_slice;
+use shank::ShankInstruction;
+use solana_program::{
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use std::{borrow::Cow, convert::TryInto};
 
-use crate::error::EscrowError::InvalidInstruction;
+use crate::{
+    compressed_escrow::CompressedEscrow,
+    compression,
+    error::EscrowError::InvalidInstruction,
+    escrow_pool::EscrowPool,
+    find_allowlist_address, find_bid_address, find_compressed_tree_address, find_config_address,
+    find_counteroffer_address, find_escrow_address, find_escrow_authority_address, find_escrow_pool_authority_address,
+    find_escrow_pool_vault_address, find_fee_authority_address, find_fee_vault_address, find_fill_record_address,
+    find_legacy_escrow_authority_address, find_market_address, find_quote_address, find_quote_vault_address,
+    find_raffle_address, find_raffle_vault_address, find_registry_address, find_ring_address, find_ring_vault_address,
+    find_sealed_auction_address, find_sealed_bid_address, find_sealed_bid_vault_address, find_stablecoins_address,
+    find_stats_address, find_ticket_address, find_vesting_address, find_vesting_vault_address, memo, metaplex,
+    raffle::Raffle,
+    swap_ring::SwapRing,
+    switchboard, token,
+};
 
-pub enum EscrowInstruction {
+/// The argument structs of the `anchor` feature's instructions, which Anchor's IDL generation looks up here
+#[cfg(feature = "idl-build")]
+pub use crate::anchor::instruction::*;
+
+/// The instruction data is a one byte tag followed by the little-endian fields of the variant, which happens to be exactly how Borsh encodes this enum. Clients can therefore either use the builders at the bottom of this file or serialize an EscrowInstruction with BorshSerialize - unpack understands both. New variants must keep that property (fixed-size fields, variants appended in tag order).
+///
+/// The `#[account]` attributes describe the same account lists as the doc comments in a form shank can read, see scripts/generate-idl.sh.
+#[derive(BorshSerialize, BorshDeserialize, ShankInstruction, Clone, Debug, PartialEq)]
+#[rustfmt::skip]
+pub enum EscrowInstruction<'a> {
   /// Starts the trade by creating and populating an escrow account and transferring ownership of the given temp token account to the PDA
   ///
   /// Although instruction.rs does not touch accounts, it is helpful to define which accounts you expect here so all the required calling info is in one place and easy to find for others.
@@ -12,9 +47,23 @@ pub enum EscrowInstruction {
   /// 0. `[signer]` The account of the person initializing the escrow
   /// 1. `[writable]` Temporary token account that should be created prior to this instruction and owned by the initializer
   /// 2. `[]` The initializer's token account for the token they will receive should the trade go through
-  /// 3. `[writable]` The escrow account, it will hold all necessary info about the trade.
-  /// 4. `[]` The rent sysvar
-  /// 5. `[]` The token program
+  /// 3. `[writable]` The escrow account, it will hold all necessary info about the trade. If it doesn't exist yet it has to sign, and the program creates it
+  /// 4. `[]` The token program that owns the temp token account, either the original token program or Token-2022
+  /// 5. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 6. `[]` The mint allowlist, derived from the seeds `[b"allowlist"]`. Only read if the config enables the allowlist
+  /// 7. `[writable]` (deposit only) The initializer's token account the escrowed tokens come from, ignored when depositing native SOL
+  /// 8. `[]` (deposit only) The mint of the escrowed tokens (X)
+  /// 9. `[]` (deposit only) The system program
+  /// 10. `[writable]` (optional) The registry of the escrow's mint pair, see InitRegistry. The escrow is added to it
+  /// 11. `[writable]` (optional) The stats account, see InitStats. The escrow is counted in it
+  ///
+  /// For Token-2022 mints with a transfer hook, the hook program, its validation account and the extra accounts the validation account lists have to be appended as well, in any order. See transfer_hook.
+  ///
+  /// When the escrow account doesn't exist yet, the initializer has to be writable as well, since they pay its rent, and the system program has to be passed. It may be anywhere after account 6. The same goes for escrows with an escrow_id, whose account the program creates at their PDA, which doesn't sign.
+  ///
+  /// The rent sysvar used to be account 4. The rent is read through a syscall now, but for older clients the sysvar is still accepted in that position and skipped. The same goes for the other instructions that create escrows.
+  ///
+  /// With a deposit_amount, account 1 has to be a new, empty account that signs the transaction. The program then creates it, initializes it as a token account and moves deposit_amount into it, so the client doesn't have to do that in separate instructions first. If the mint is the native mint, deposit_amount is in lamports and gets wrapped.
   /// 
   /// Note re: "writeable" - If the caller does not mark the account writable in their calling code but the program attempts to write to it, the transaction will fail.
   /// 
@@ -22,12 +71,40 @@ pub enum EscrowInstruction {
   /// 0. Signer: We need Account 0 and specifically Account 0 as a signer because transferring the ownership of the temporary account requires the INITIALIZER'S signature.
   /// 1. Account 1 is the temp token X account which needs to be writable. This is because changing token account ownership is a user space change which means the data field of the account will be changed
   /// 2. Account 2 is INITIALIZER'S token Y account. While it will be written to eventually, it won't happen in this transaction which is why we can leave the brackets empty (implying read-only)
-  /// 3. Account 3 is the escrow account which also needs to be writable because the program will write the escrow information into it
-  /// 4. Account 4 is explained further in PROCESSOR
-  /// 5. Account 5 is the account of the token program itself, which is explained fursther in PROCESSOR
+  /// 3. Account 3 is the escrow account which also needs to be writable because the program will write the escrow information into it. Its pubkey is also a seed of the PDA that takes over the temp token account, so each escrow has its own authority
+  /// 4. Account 4 is the account of the token program itself, which is explained fursther in PROCESSOR
+  /// 5. Account 5 is the program's config, which provides the default expiry
+  /// 6. Account 6 is the list of mints escrows may trade, should the deployment restrict them
+  #[account(0, signer, name="initializer", desc="The account of the person initializing the escrow")]
+  #[account(1, writable, name="temp_token_account", desc="Temporary token account holding the tokens to escrow, owned by the initializer")]
+  #[account(2, name="token_to_receive_account", desc="The initializer's token account for the token they will receive")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account, it will hold all necessary info about the trade")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="config", desc="The program's config account")]
+  #[account(6, name="allowlist", desc="The mint allowlist")]
+  #[account(7, optional, writable, name="initializers_token_account", desc="The initializer's token account to deposit the escrowed tokens from")]
+  #[account(8, optional, name="token_x_mint", desc="The mint of the escrowed tokens")]
+  #[account(9, optional, name="system_program", desc="The system program")]
+  #[account(10, optional, writable, name="registry", desc="The registry of the escrow's mint pair")]
   InitEscrow {
     /// The amount party A expects to receive of token Y
-    amount: u64
+    amount: u64,
+    /// Unix timestamp after which the escrow can be reclaimed by anyone via ReclaimExpired. Optional in the instruction data, 0 (the default) means the config's default expiry applies
+    expiry_unix_timestamp: i64,
+    /// The only account allowed to take the trade. Optional in the instruction data, the default pubkey (all zeroes) lets anyone take it
+    allowed_taker: Pubkey,
+    /// The amount of token X to deposit into a temp token account the program creates. Optional in the instruction data, 0 (the default) means the temp token account was created and funded beforehand
+    deposit_amount: u64,
+    /// Makes the escrow two-phase: takers Accept it instead of exchanging, and it settles this many seconds after that unless both parties sign Settle earlier. Optional in the instruction data, 0 (the default) means a regular escrow
+    dispute_window_seconds: i64,
+    /// Up to 128 bytes stored in the escrow and emitted in an EscrowMetadataSet event, e.g. a marketplace's listing id or the hash of the terms. Optional in the instruction data, empty (the default) means none
+    metadata: Cow<'a, [u8]>,
+    /// An id of the client's choosing, e.g. an off-chain order id, which is stored in the escrow. The escrow account then has to be the PDA derived from `[b"escrow", initializer, id as little-endian u64]`, see find_escrow_address, and the program creates it. Optional in the instruction data after the metadata, 0 (the default) means the escrow account is a keypair
+    escrow_id: u64,
+    /// The lamports a taker has to lock to reserve the escrow, see Reserve. Optional in the instruction data after the escrow id, 0 (the default) means the escrow can't be reserved
+    reservation_deposit: u64,
+    /// The root of a merkle tree of the takers allowed to exchange the escrow, see merkle. Optional in the instruction data after the reservation deposit, all zeroes (the default) means there is no such allowlist. Not supported for two-phase escrows
+    taker_merkle_root: [u8; 32],
   },
   // LOOK INTO FRONTRUNNING ATTACKS
 
@@ -36,50 +113,6214 @@ pub enum EscrowInstruction {
   ///
   /// Accounts expected:
   ///
-  /// 0. `[signer]` The account of the person taking the trade
+  /// 0. `[signer]` The account of the person taking the trade. Writable if they hold the escrow's reservation, so their deposit can be refunded
   /// 1. `[writable]` The taker's token account for the token they send 
-  /// 2. `[writable]` The taker's token account for the token they will receive should the trade go through
-  /// 3. `[writable]` The PDA's temp token account to get tokens from and eventually close
+  /// 2. `[writable]` The taker's token account for the token they will receive should the trade go through. If it doesn't exist yet it has to be the taker's associated token account, which is then created with the taker paying the rent. For native SOL escrows it can be any account, the SOL is unwrapped into it. For vesting escrows it is the vesting vault, derived from the seeds `[b"vesting_vault", escrow account pubkey]`, which is created with the taker paying the rent
+  /// 3. `[writable]` The PDA's temp token account to get tokens from and eventually close. For delegated escrows, the initializer's token account the PDA is a delegate of, which stays open
   /// 4. `[writable]` The initializer's main account to send their rent fees to
   /// 5. `[writable]` The initializer's token account that will receive tokens
   /// 6. `[writable]` The escrow account holding the escrow info
-  /// 7. `[]` The token program
-  /// 8. `[]` The PDA account
+  /// 7. `[]` The token program of the token the taker receives (X)
+  /// 8. `[]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 9. `[]` The mint of the token the taker receives (X)
+  /// 10. `[]` The mint of the token the taker sends (Y)
+  /// 11. `[]` The token program of the token the taker sends (Y), the same account as 7 if both tokens live in the same token program
+  /// 12. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 13. `[writable]` (optional) The fee vault of token Y, or a token account of token Y of the config's fee collector if it has one, only needed if the protocol fee is enabled
+  /// 14. `[]` (optional) The associated token account program, only needed if account 2 has to be created
+  /// 15. `[]` (optional) The system program, only needed if account 2 has to be created
+  /// 16. `[]` (optional) The rent sysvar, only needed if account 2 has to be created
+  /// 17. `[writable]` (optional) The vesting account, derived from the seeds `[b"vesting", escrow account pubkey]`, only needed for vesting escrows. Needs the system program and the rent sysvar as well, but not the associated token account program
+  /// 18. `[writable]` (optional) The referrer's token account of token Y, only needed if the instruction data names one
+  /// 19. `[]` (optional) The escrow's Pyth price account, only needed for oracle escrows
+  /// 20. `[writable]` (optional) The registry of the escrow's mint pair, see InitRegistry. The escrow is removed from it
+  /// 21. `[writable]` (optional) The stats account, see InitStats. The exchange is counted in it
+  /// 22. `[writable]` (optional) The taker's fill record, derived from the seeds `[b"fill", escrow account pubkey, taker pubkey]`, only kept for rate-based escrows. It is created on the taker's first fill, with the taker paying the rent, which needs the system program as well
+  /// 23. `[]` (optional) The Metaplex metadata account of token X, derived from the seeds `[b"metadata", metadata program id, token X mint]`, see metaplex. Required if token X is an NFT (0 decimals and a supply of 1), even if the NFT has no metadata
+  /// 24. `[writable]` (optional) A token account of token Y of each creator in the NFT's metadata who is owed a royalty
+  /// 25. `[writable, signer]` (optional) The relayer named in the instruction data
+  /// 26. `[writable]` (optional) The relayer's associated token account of token Y, only needed if the instruction data names a tip
+  /// 27. `[]` (optional) The stablecoin list, derived from the seeds `[b"stablecoins"]`, only needed for USD-priced escrows
+  /// 28. `[]` (optional) The Pyth price account quoting the stablecoin the taker pays in, only needed for USD-priced escrows
+  ///
+  /// Escrows with payment options (see SetPaymentOption) and USD-priced escrows (see SetUsdPrice) take whichever accepted mint the taker sends, and accounts 1, 5, 10, 11 and those of token Y below are that mint's. See with_stablecoin.
+  ///
+  /// The royalties of an NFT come out of the price the initializer receives, the taker pays the same. A RoyaltyPaid event records each.
+  ///
+  /// A programmable NFT is moved by Token Metadata, whose accounts with_programmable_nft_accounts appends. Account 2 has to be the taker's associated token account then, which Token Metadata creates if need be, with the taker paying.
+  ///
+  /// For Token-2022 mints with a transfer hook, the hook program, its validation account and the extra accounts the validation account lists have to be appended as well, in any order. See transfer_hook.
+  ///
+  /// Apart from the fee vault, the optional accounts are looked up by key (the registry and the stablecoin list by their owner and size), so they can come in any order. The same goes for the SPL Memo program, which is only needed if there is a memo, and for the config's gate program and the accounts of it the gate reads, see gate.
+  ///
+  /// If token Y is a Token-2022 mint with a transfer fee, the taker sends enough for the initializer to receive the expected amount after the fee, and the exchange fails with NetAmountTooLow if less arrives.
+  ///
+  /// With the protocol fee enabled the taker pays the expected amount plus the fee, which goes to the fee vault. See InitFeeVault. If a referrer is named, the config's referral_share_bps of the fee goes to them instead.
+  ///
+  /// The taker doesn't have to pay the transaction fee, so a relayer can submit Exchange on their behalf. A relayer named in the instruction data signs, pays the rent of the accounts Exchange creates for the taker in place of the taker, and receives the tip the taker agreed to. See with_relayer.
+  #[account(0, signer, name="taker", desc="The account of the person taking the trade")]
+  #[account(1, writable, name="takers_sending_token_account", desc="The taker's token account for the token they send")]
+  #[account(2, writable, name="takers_token_to_receive_account", desc="The taker's token account for the token they will receive")]
+  #[account(3, writable, name="pdas_temp_token_account", desc="The PDA's temp token account to get tokens from and eventually close")]
+  #[account(4, writable, name="initializers_main_account", desc="The initializer's main account to send their rent fees to")]
+  #[account(5, writable, name="initializers_token_to_receive_account", desc="The initializer's token account that will receive tokens")]
+  #[account(6, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  #[account(7, name="token_program", desc="The token program of the token the taker receives")]
+  #[account(8, name="pda_account", desc="The escrow's PDA")]
+  #[account(9, name="token_x_mint", desc="The mint of the token the taker receives")]
+  #[account(10, name="token_y_mint", desc="The mint of the token the taker sends")]
+  #[account(11, name="y_token_program", desc="The token program of the token the taker sends")]
+  #[account(12, name="config", desc="The program's config account")]
+  #[account(13, optional, writable, name="fee_vault", desc="The fee vault of the token the taker sends, if the protocol fee is enabled")]
+  #[account(14, optional, name="associated_token_program", desc="The associated token account program, if the taker's token account has to be created")]
+  #[account(15, optional, name="system_program", desc="The system program, if the taker's token account has to be created")]
+  #[account(16, optional, name="rent", desc="The rent sysvar, if the taker's token account has to be created")]
+  #[account(17, optional, writable, name="vesting_account", desc="The vesting account, if it is a vesting escrow")]
+  #[account(18, optional, writable, name="referrer_token_account", desc="The referrer's token account of the token the taker sends, if there is a referrer")]
+  #[account(19, optional, name="price_feed", desc="The escrow's Pyth price account, if it is an oracle escrow")]
+  #[account(20, optional, writable, name="registry", desc="The registry of the escrow's mint pair")]
   Exchange {
-      /// the amount the taker expects to be paid in the other token, as a u64 because that's the max possible supply of a token
+      /// the amount the taker expects to be paid in the other token, as a u64 because that's the max possible supply of a token. For rate-based escrows, the part of the escrowed amount the taker takes, see InitRateEscrow
       amount: u64,
-  }
-}
+      /// The most the taker is willing to pay in token Y, protocol fee and Token-2022 transfer fee included. Protects takers of Dutch auctions, oracle escrows and escrows that get updated from paying more than they signed for. Optional in the instruction data, no limit if left out.
+      max_payment: u64,
+      /// The token account of the frontend that referred the taker, which gets a share of the protocol fee. The account is looked up by this key, so it can go anywhere after account 12. Optional in the instruction data, the default pubkey means there is no referrer.
+      referrer_token_account: Pubkey,
+      /// Recorded through the SPL Memo program once the exchange has settled, signed by the taker, for accounting systems that track trades by memo. The Memo program has to be passed along, it is looked up by key. Optional in the instruction data, an empty memo (the default) isn't recorded.
+      memo: Cow<'a, str>,
+      /// The merkle proof that the taker is in the escrow's taker allowlist, see merkle::proof. Optional in the instruction data after the memo, and only needed if the escrow has an allowlist.
+      taker_proof: Cow<'a, [[u8; 32]]>,
+      /// The main account of the service submitting the transaction for the taker, looked up by this key. Optional in the instruction data after the taker proof, the default pubkey means there is no relayer.
+      relayer: Pubkey,
+      /// What the taker pays the relayer in token Y for submitting the transaction. Counts towards max_payment. Optional in the instruction data, together with the relayer.
+      relayer_tip: u64,
+  },
 
-  /// Below:
-  /// 1. unpack expects a reference (opens new window)to a slice of u8. 
-  /// 2. It looks at the first byte (=tag) to determine how to decode (using match (opens new window)) the rest (=rest) of the slice. 
-  /// 3. unpack_amount decodes the rest to get a u64 representing the amount. 
-  /// Summary: choose which instruction to build and build/return that instruction.
+  /// Cancels a trade, returning the escrowed tokens to the initializer
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The account of the person who initialized the escrow
+  /// 1. `[writable]` The PDA's temp token account to get tokens from and eventually close. For delegated escrows, the initializer's token account, whose allowance is revoked
+  /// 2. `[writable]` The initializer's token account that will receive the returned tokens, ignored for native SOL and delegated escrows
+  /// 3. `[writable]` The escrow account holding the escrow info
+  /// 4. `[]` The token program that owns the temp token account
+  /// 5. `[]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 6. `[]` The mint of the escrowed tokens
+  /// 7. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 8. `[writable]` (optional) The recipient's token account, only needed for streaming escrows. It gets the part of the stream that has accrued but wasn't withdrawn yet.
+  /// 9. `[writable]` (optional) The token account the cancellation penalty is paid into, only needed if the config sets one. See SetCancelPenalty
+  /// 10. `[writable]` (optional) The registry of the escrow's mint pair, see InitRegistry. The escrow is removed from it
+  /// 11. `[writable]` (optional) The stats account, see InitStats. The escrow is counted as cancelled in it
+  ///
+  /// For Token-2022 mints with a transfer hook, the hook program, its validation account and the extra accounts the validation account lists have to be appended as well, in any order. See transfer_hook. So does the SPL Memo program if there is a memo.
+  ///
+  /// Only the initializer can cancel, so the signer is checked against the initializer_pubkey stored in the escrow account. The rent of both the temp token account and the escrow account goes back to the initializer, and so do the escrowed tokens, less the cancellation penalty if there is one.
+  ///
+  /// A programmable NFT is returned by Token Metadata, whose accounts with_programmable_nft_accounts appends.
+  #[account(0, signer, writable, name="initializer", desc="The account of the person who initialized the escrow")]
+  #[account(1, writable, name="pdas_temp_token_account", desc="The PDA's temp token account to get tokens from and eventually close")]
+  #[account(2, writable, name="initializers_token_to_return_account", desc="The initializer's token account that will receive the returned tokens")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="pda_account", desc="The escrow's PDA")]
+  #[account(6, name="token_x_mint", desc="The mint of the escrowed tokens")]
+  #[account(7, name="config", desc="The program's config account")]
+  #[account(8, optional, writable, name="recipients_token_account", desc="The recipient's token account, for streaming escrows")]
+  #[account(9, optional, writable, name="penalty_token_account", desc="The token account the cancellation penalty is paid into")]
+  #[account(10, optional, writable, name="registry", desc="The registry of the escrow's mint pair")]
+  Cancel {
+      /// Recorded through the SPL Memo program once the tokens are back, signed by the initializer. Optional in the instruction data, see Exchange.
+      memo: Cow<'a, str>,
+  },
 
-impl EscrowInstruction {
-    /// Unpacks a byte buffer into a [EscrowInstruction](enum.EscrowInstruction.html).
-    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        let (tag, rest) = input.split_first().ok_or(InvalidInstruction)?;
+  /// Returns the escrowed tokens to the initializer once the escrow's expiry has passed
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[writable]` The PDA's temp token account to get tokens from and eventually close. For delegated escrows, the initializer's token account, which is left alone
+  /// 1. `[writable]` The initializer's main account to send their rent fees to
+  /// 2. `[writable]` The initializer's token account that will receive the returned tokens, ignored for native SOL and delegated escrows
+  /// 3. `[writable]` The escrow account holding the escrow info
+  /// 4. `[]` The token program that owns the temp token account
+  /// 5. `[]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 6. `[]` The mint of the escrowed tokens
+  /// 7. `[writable]` (optional) The registry of the escrow's mint pair, see InitRegistry. The escrow is removed from it
+  /// 8. `[writable]` (optional) The stats account, see InitStats. The escrow is counted as cancelled in it
+  ///
+  /// For Token-2022 mints with a transfer hook, the hook program, its validation account and the extra accounts the validation account lists have to be appended as well, in any order. See transfer_hook.
+  ///
+  /// No signer is required - anyone can crank an expired escrow. Because of that the program checks that account 2 really is a token account owned by the initializer, for the same mint as the temp token account.
+  ///
+  /// A programmable NFT is returned by Token Metadata, whose accounts with_programmable_nft_accounts appends. Account 2 has to be the initializer's associated token account then, and since Token Metadata may have to create it, whoever cranks the escrow has to sign and pay for it.
+  #[account(0, writable, name="pdas_temp_token_account", desc="The PDA's temp token account to get tokens from and eventually close")]
+  #[account(1, writable, name="initializers_main_account", desc="The initializer's main account to send their rent fees to")]
+  #[account(2, writable, name="initializers_token_to_return_account", desc="The initializer's token account that will receive the returned tokens")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="pda_account", desc="The escrow's PDA")]
+  #[account(6, name="token_x_mint", desc="The mint of the escrowed tokens")]
+  #[account(7, optional, writable, name="registry", desc="The registry of the escrow's mint pair")]
+  ReclaimExpired,
 
-        Ok(match tag {
-            0 => Self::InitEscrow {
-                amount: Self::unpack_amount(rest)?,
-            },
-            1 => Self::Exchange {
-                amount: Self::unpack_amount(rest)?
-            },
-            _ => return Err(InvalidInstruction.into()),
-        })
-    }
+  /// Same as InitEscrow, but for a non-fungible token: the temp token account must hold exactly 1 token of a mint with 0 decimals and a supply of 1. The NFT can be priced in any token.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The account of the person initializing the escrow
+  /// 1. `[writable]` Temporary token account holding the NFT, owned by the initializer
+  /// 2. `[]` The initializer's token account for the token they will receive should the trade go through
+  /// 3. `[writable]` The escrow account, it will hold all necessary info about the trade.
+  /// 4. `[]` The token program that owns the temp token account
+  /// 5. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 6. `[]` The mint allowlist, derived from the seeds `[b"allowlist"]`
+  /// 7. `[]` The NFT's mint
+  ///
+  /// A programmable NFT can't be moved into a temp token account by the initializer. Account 1 is the PDA's associated token account instead, the PDA being derived from the seeds `[b"escrow", escrow account pubkey]`, which must not exist yet. Token Metadata creates it and moves the NFT there from the initializer's token account, with the initializer paying, so they have to be writable. with_programmable_nft_accounts appends the initializer's token account and Token Metadata's accounts.
+  #[account(0, signer, name="initializer", desc="The account of the person initializing the escrow")]
+  #[account(1, writable, name="temp_token_account", desc="Temporary token account holding the NFT, owned by the initializer")]
+  #[account(2, name="token_to_receive_account", desc="The initializer's token account for the token they will receive")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account, it will hold all necessary info about the trade")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="config", desc="The program's config account")]
+  #[account(6, name="allowlist", desc="The mint allowlist")]
+  #[account(7, name="nft_mint", desc="The NFT's mint")]
+  InitNftEscrow {
+    /// The amount the initializer expects to receive for the NFT
+    amount: u64,
+    /// Same as for InitEscrow, 0 (the default) means the config's default expiry applies
+    expiry_unix_timestamp: i64,
+    /// Same as for InitEscrow, the default pubkey lets anyone take the trade
+    allowed_taker: Pubkey,
+  },
 
-    fn unpack_amount(input: &[u8]) -> Result<u64, ProgramError> {
-        let amount = input
-            .get(..8)
-            .and_then(|slice| slice.try_into().ok())
-            .map(u64::from_le_bytes)
-            .ok_or(InvalidInstruction)?;
-        Ok(amount)
-    }
-}
+  /// Creates the fee vault of a mint, the token account protocol fees in that token are collected into. Anyone can create a vault, the program owns it either way.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The account paying for the fee vault's rent
+  /// 1. `[writable]` The fee vault, derived from the seeds `[b"fee_vault", mint pubkey]`
+  /// 2. `[]` The mint of the fees the vault collects
+  /// 3. `[]` The fee authority, derived from the seeds `[b"fee_authority"]`
+  /// 4. `[]` The system program
+  /// 5. `[]` The token program that owns the mint
+  /// 6. `[]` The rent sysvar
+  #[account(0, signer, writable, name="payer", desc="The account paying for the fee vault's rent")]
+  #[account(1, writable, name="fee_vault", desc="The fee vault of the mint")]
+  #[account(2, name="mint", desc="The mint of the fees the vault collects")]
+  #[account(3, name="fee_authority", desc="The PDA that owns every fee vault")]
+  #[account(4, name="system_program", desc="The system program")]
+  #[account(5, name="token_program", desc="The token program that owns the mint")]
+  #[account(6, name="rent", desc="The rent sysvar")]
+  InitFeeVault,
+
+  /// Creates the config account. Only the program's upgrade authority can do this, otherwise anyone watching the deployment could claim the admin role first.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The admin, who pays for the config account and has to be the program's upgrade authority
+  /// 1. `[writable]` The config account, derived from the seeds `[b"config"]`
+  /// 2. `[]` The program's program data account, owned by the upgradeable BPF loader
+  /// 3. `[]` The system program
+  #[account(0, signer, writable, name="admin", desc="The admin, who has to be the program's upgrade authority")]
+  #[account(1, writable, name="config", desc="The program's config account")]
+  #[account(2, name="program_data", desc="The program's program data account")]
+  #[account(3, name="system_program", desc="The system program")]
+  InitializeConfig {
+    /// The protocol fee in basis points, at most MAX_FEE_BPS
+    fee_bps: u16,
+    /// How long escrows without an explicit expiry stay open, 0 for forever
+    default_expiry_seconds: i64,
+    /// The referrer's share of the protocol fee in basis points of the fee, at most 10000. Optional in the instruction data, 0 if left out.
+    referral_share_bps: u16,
+  },
+
+  /// Stops new escrows from being created and open ones from being exchanged. Cancel and ReclaimExpired keep working so initializers can always get their tokens back.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The admin stored in the config account
+  /// 1. `[writable]` The config account, derived from the seeds `[b"config"]`
+  #[account(0, signer, name="admin", desc="The admin stored in the config account")]
+  #[account(1, writable, name="config", desc="The program's config account")]
+  Pause,
+
+  /// Lifts a Pause
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The admin stored in the config account
+  /// 1. `[writable]` The config account, derived from the seeds `[b"config"]`
+  #[account(0, signer, name="admin", desc="The admin stored in the config account")]
+  #[account(1, writable, name="config", desc="The program's config account")]
+  Unpause,
+
+  /// Adds a mint to the allowlist, creating the allowlist account the first time
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The admin stored in the config account, who pays for the allowlist account
+  /// 1. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 2. `[writable]` The mint allowlist, derived from the seeds `[b"allowlist"]`
+  /// 3. `[]` The system program
+  #[account(0, signer, writable, name="admin", desc="The admin stored in the config account")]
+  #[account(1, name="config", desc="The program's config account")]
+  #[account(2, writable, name="allowlist", desc="The mint allowlist")]
+  #[account(3, name="system_program", desc="The system program")]
+  AddAllowedMint {
+    mint: Pubkey,
+  },
+
+  /// Removes a mint from the allowlist. Open escrows of that mint are not affected.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The admin stored in the config account
+  /// 1. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 2. `[writable]` The mint allowlist, derived from the seeds `[b"allowlist"]`
+  #[account(0, signer, name="admin", desc="The admin stored in the config account")]
+  #[account(1, name="config", desc="The program's config account")]
+  #[account(2, writable, name="allowlist", desc="The mint allowlist")]
+  RemoveAllowedMint {
+    mint: Pubkey,
+  },
+
+  /// Turns the allowlist check in InitEscrow on or off
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The admin stored in the config account
+  /// 1. `[writable]` The config account, derived from the seeds `[b"config"]`
+  #[account(0, signer, name="admin", desc="The admin stored in the config account")]
+  #[account(1, writable, name="config", desc="The program's config account")]
+  SetAllowlistEnabled {
+    enabled: bool,
+  },
+
+  /// Re-prices an open escrow in place, which is cheaper than cancelling it and creating a new one
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The account of the person who initialized the escrow
+  /// 1. `[writable]` The escrow account holding the escrow info
+  ///
+  /// A taker's Exchange can land after an update, so takers who care about the price should set Exchange's max_payment to protect themselves against it being raised under them.
+  #[account(0, signer, name="initializer", desc="The account of the person who initialized the escrow")]
+  #[account(1, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  UpdateEscrow {
+    /// The new amount of token Y the initializer expects to receive. For a Dutch auction this is the floor price.
+    expected_amount: u64,
+  },
+
+  /// Same as InitEscrow, but the price starts at start_amount and falls linearly to floor_amount between now and auction_end_unix_timestamp. Exchange settles at the price of the moment it runs.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// The same accounts as InitEscrow
+  #[account(0, signer, name="initializer", desc="The account of the person initializing the escrow")]
+  #[account(1, writable, name="temp_token_account", desc="Temporary token account holding the tokens to escrow, owned by the initializer")]
+  #[account(2, name="token_to_receive_account", desc="The initializer's token account for the token they will receive")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account, it will hold all necessary info about the trade")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="config", desc="The program's config account")]
+  #[account(6, name="allowlist", desc="The mint allowlist")]
+  InitDutchAuction {
+    /// The price of token Y when the auction starts, has to be higher than floor_amount
+    start_amount: u64,
+    /// The lowest price, reached at auction_end_unix_timestamp
+    floor_amount: u64,
+    auction_end_unix_timestamp: i64,
+    /// Same as for InitEscrow, 0 means the config's default expiry applies
+    expiry_unix_timestamp: i64,
+  },
+
+  /// Same as InitEscrow, but instead of being exchanged at a fixed price the escrow collects bids until auction_end_unix_timestamp and is then settled to the highest one
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// The same accounts as InitEscrow
+  #[account(0, signer, name="initializer", desc="The account of the person initializing the escrow")]
+  #[account(1, writable, name="temp_token_account", desc="Temporary token account holding the tokens to escrow, owned by the initializer")]
+  #[account(2, name="token_to_receive_account", desc="The initializer's token account for the token they will receive")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account, it will hold all necessary info about the trade")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="config", desc="The program's config account")]
+  #[account(6, name="allowlist", desc="The mint allowlist")]
+  InitEnglishAuction {
+    /// The lowest bid the initializer accepts
+    min_bid: u64,
+    auction_end_unix_timestamp: i64,
+  },
+
+  /// Places or raises a bid on an English auction. The bid's tokens move into the bid account, a token account owned by the escrow's PDA, and stay there until the bid wins or is withdrawn.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The bidder, who pays for the bid account the first time they bid
+  /// 1. `[writable]` The bidder's token account for the token they pay with (Y)
+  /// 2. `[writable]` The bid account, derived from the seeds `[b"bid", escrow account pubkey, bidder pubkey]`
+  /// 3. `[writable]` The escrow account holding the escrow info
+  /// 4. `[]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 5. `[]` The mint of the token the bidder pays with (Y)
+  /// 6. `[]` The token program of token Y
+  /// 7. `[]` The system program
+  /// 8. `[]` The rent sysvar
+  /// 9. `[]` The config account, derived from the seeds `[b"config"]`
+  ///
+  /// If the config sets a gate program, it has to be appended along with the accounts of it the gate reads. See gate.
+  #[account(0, signer, writable, name="bidder", desc="The bidder")]
+  #[account(1, writable, name="bidders_token_account", desc="The bidder's token account for the token they pay with")]
+  #[account(2, writable, name="bid_account", desc="The bidder's bid account")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  #[account(4, name="pda_account", desc="The escrow's PDA")]
+  #[account(5, name="token_y_mint", desc="The mint of the token the bidder pays with")]
+  #[account(6, name="y_token_program", desc="The token program of the token the bidder pays with")]
+  #[account(7, name="system_program", desc="The system program")]
+  #[account(8, name="rent", desc="The rent sysvar")]
+  #[account(9, name="config", desc="The program's config account")]
+  PlaceBid {
+    /// The bidder's new total bid, which has to beat the current highest bid. Only the difference to their previous bid is transferred.
+    amount: u64,
+  },
+
+  /// Settles an English auction: the highest bidder gets the escrowed tokens and the initializer gets the winning bid
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[]` The account settling the auction. Anyone can settle once the auction has ended, before that it has to be the initializer as a signer
+  /// 1. `[writable]` The escrow account holding the escrow info
+  /// 2. `[]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 3. `[writable]` The PDA's temp token account to get tokens from and eventually close
+  /// 4. `[writable]` The highest bidder's token account that will receive the escrowed tokens (X)
+  /// 5. `[writable]` The highest bidder's bid account, closed after settlement
+  /// 6. `[writable]` The highest bidder's main account, which gets the bid account's rent back
+  /// 7. `[writable]` The initializer's token account that will receive the winning bid
+  /// 8. `[writable]` The initializer's main account to send their rent fees to
+  /// 9. `[]` The token program of token X
+  /// 10. `[]` The token program of token Y
+  /// 11. `[]` The mint of token X
+  /// 12. `[]` The mint of token Y
+  #[account(0, name="settler", desc="The account settling the auction")]
+  #[account(1, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  #[account(2, name="pda_account", desc="The escrow's PDA")]
+  #[account(3, writable, name="pdas_temp_token_account", desc="The PDA's temp token account to get tokens from and eventually close")]
+  #[account(4, writable, name="winners_token_to_receive_account", desc="The highest bidder's token account that will receive the escrowed tokens")]
+  #[account(5, writable, name="bid_account", desc="The highest bidder's bid account")]
+  #[account(6, writable, name="winner", desc="The highest bidder's main account")]
+  #[account(7, writable, name="initializers_token_to_receive_account", desc="The initializer's token account that will receive the winning bid")]
+  #[account(8, writable, name="initializers_main_account", desc="The initializer's main account to send their rent fees to")]
+  #[account(9, name="token_program", desc="The token program of token X")]
+  #[account(10, name="y_token_program", desc="The token program of token Y")]
+  #[account(11, name="token_x_mint", desc="The mint of token X")]
+  #[account(12, name="token_y_mint", desc="The mint of token Y")]
+  SettleAuction,
+
+  /// Returns a bid that didn't win, either because it was outbid or because the escrow is gone
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The bidder
+  /// 1. `[writable]` The bid account, derived from the seeds `[b"bid", escrow account pubkey, bidder pubkey]`
+  /// 2. `[writable]` The bidder's token account that will receive the bid back
+  /// 3. `[]` The escrow account the bid was placed on, which may already be closed
+  /// 4. `[]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 5. `[]` The mint of the bid's token (Y)
+  /// 6. `[]` The token program of token Y
+  #[account(0, signer, writable, name="bidder", desc="The bidder")]
+  #[account(1, writable, name="bid_account", desc="The bidder's bid account")]
+  #[account(2, writable, name="bidders_token_account", desc="The bidder's token account that will receive the bid back")]
+  #[account(3, name="escrow_account", desc="The escrow account the bid was placed on")]
+  #[account(4, name="pda_account", desc="The escrow's PDA")]
+  #[account(5, name="token_y_mint", desc="The mint of the bid's token")]
+  #[account(6, name="y_token_program", desc="The token program of the bid's token")]
+  WithdrawBid,
+
+  /// Same as InitEscrow, but instead of a fixed amount the taker pays whatever the escrowed tokens are worth according to a Pyth price feed at the time of the exchange, plus a premium. The feed has to quote token X in token Y, e.g. SOL/USD for an escrow selling SOL for USDC.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0-6. The same accounts as InitEscrow
+  /// 7. `[]` The Pyth price account
+  #[account(0, signer, name="initializer", desc="The account of the person initializing the escrow")]
+  #[account(1, writable, name="temp_token_account", desc="Temporary token account holding the tokens to escrow, owned by the initializer")]
+  #[account(2, name="token_to_receive_account", desc="The initializer's token account for the token they will receive")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account, it will hold all necessary info about the trade")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="config", desc="The program's config account")]
+  #[account(6, name="allowlist", desc="The mint allowlist")]
+  #[account(7, name="price_feed", desc="The Pyth price account quoting token X in token Y")]
+  InitOracleEscrow {
+    /// Added to the oracle price in basis points, negative for a discount. Has to be above -10000.
+    premium_bps: i16,
+    /// Same as for InitEscrow, 0 means the config's default expiry applies
+    expiry_unix_timestamp: i64,
+  },
+
+  /// Same as InitEscrow, except that the tokens stay in the initializer's own token account. Instead of handing the account over to the PDA, the initializer approves the PDA as a delegate for amount_x, and Exchange transfers the tokens out of the account as that delegate. This saves creating and paying rent for a temp token account.
+  ///
+  /// The account only has room for one delegate, so approving anything else on it (e.g. a second delegated escrow) takes the allowance away from this escrow, and Exchange then fails until the initializer cancels. Cancel revokes the allowance, ReclaimExpired can't since only the initializer can revoke.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The account of the person initializing the escrow
+  /// 1. `[writable]` The initializer's token account holding the tokens to escrow
+  /// 2. `[]` The initializer's token account for the token they will receive should the trade go through
+  /// 3. `[writable]` The escrow account, it will hold all necessary info about the trade.
+  /// 4. `[]` The token program that owns the initializer's token account
+  /// 5. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 6. `[]` The mint allowlist, derived from the seeds `[b"allowlist"]`. Only read if the config enables the allowlist
+  /// 7. `[]` The PDA account the tokens are delegated to, derived from the seeds `[b"escrow", escrow account pubkey]`
+  #[account(0, signer, name="initializer", desc="The account of the person initializing the escrow")]
+  #[account(1, writable, name="initializers_token_account", desc="The initializer's token account holding the tokens to escrow")]
+  #[account(2, name="token_to_receive_account", desc="The initializer's token account for the token they will receive")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account, it will hold all necessary info about the trade")]
+  #[account(4, name="token_program", desc="The token program that owns the initializer's token account")]
+  #[account(5, name="config", desc="The program's config account")]
+  #[account(6, name="allowlist", desc="The mint allowlist")]
+  #[account(7, name="pda_account", desc="The escrow's PDA, which the tokens are delegated to")]
+  InitDelegatedEscrow {
+    /// The amount of token X the PDA may transfer out of the initializer's token account. Can't be 0.
+    amount_x: u64,
+    /// The amount party A expects to receive of token Y
+    amount: u64,
+    /// Same as for InitEscrow, 0 means the config's default expiry applies
+    expiry_unix_timestamp: i64,
+    /// Same as for InitEscrow, the default pubkey lets anyone take the trade
+    allowed_taker: Pubkey,
+  },
+
+  /// The first phase of taking a two-phase escrow. The taker pays the escrow's current price into a bid account owned by the PDA, and the escrow is locked to them: it can no longer be cancelled, reclaimed, updated or accepted by anyone else.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The taker, who pays for the bid account
+  /// 1. `[writable]` The taker's token account for the token they pay with (Y)
+  /// 2. `[writable]` The bid account that holds the payment, derived from the seeds `[b"bid", escrow account pubkey, taker pubkey]`
+  /// 3. `[writable]` The escrow account holding the escrow info
+  /// 4. `[]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 5. `[]` The mint of the token the taker pays with (Y)
+  /// 6. `[]` The token program of token Y
+  /// 7. `[]` The system program
+  /// 8. `[]` The rent sysvar
+  /// 9. `[]` The config account, derived from the seeds `[b"config"]`
+  ///
+  /// If the config sets a gate program, it has to be appended along with the accounts of it the gate reads. See gate.
+  #[account(0, signer, writable, name="taker", desc="The taker")]
+  #[account(1, writable, name="takers_token_account", desc="The taker's token account for the token they pay with")]
+  #[account(2, writable, name="bid_account", desc="The bid account that holds the payment")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  #[account(4, name="pda_account", desc="The escrow's PDA")]
+  #[account(5, name="token_y_mint", desc="The mint of the token the taker pays with")]
+  #[account(6, name="y_token_program", desc="The token program of the token the taker pays with")]
+  #[account(7, name="system_program", desc="The system program")]
+  #[account(8, name="rent", desc="The rent sysvar")]
+  #[account(9, name="config", desc="The program's config account")]
+  Accept {
+    /// The most the taker is willing to pay in token Y, which protects them from the initializer raising the price with UpdateEscrow right before the Accept lands
+    max_payment: u64,
+  },
+
+  /// The second phase: the taker gets the escrowed tokens and the initializer gets the payment. Anyone can settle once the escrow's dispute window has passed since it was accepted, before that the initializer and the taker have to sign together.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[]` The initializer, as a signer if settling within the dispute window. Anyone once the window has passed
+  /// 1. `[writable]` The escrow account holding the escrow info
+  /// 2. `[]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 3. `[writable]` The PDA's temp token account to get tokens from and eventually close
+  /// 4. `[writable]` The taker's token account that will receive the escrowed tokens (X)
+  /// 5. `[writable]` The taker's bid account holding the payment, closed after settlement
+  /// 6. `[writable]` The taker's main account, which gets the bid account's rent back. Has to sign within the dispute window
+  /// 7. `[writable]` The initializer's token account that will receive the payment
+  /// 8. `[writable]` The initializer's main account to send their rent fees to
+  /// 9. `[]` The token program of token X
+  /// 10. `[]` The token program of token Y
+  /// 11. `[]` The mint of token X
+  /// 12. `[]` The mint of token Y
+  #[account(0, name="settler", desc="The account settling the escrow")]
+  #[account(1, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  #[account(2, name="pda_account", desc="The escrow's PDA")]
+  #[account(3, writable, name="pdas_temp_token_account", desc="The PDA's temp token account to get tokens from and eventually close")]
+  #[account(4, writable, name="takers_token_to_receive_account", desc="The taker's token account that will receive the escrowed tokens")]
+  #[account(5, writable, name="bid_account", desc="The taker's bid account holding the payment")]
+  #[account(6, writable, name="taker", desc="The taker's main account")]
+  #[account(7, writable, name="initializers_token_to_receive_account", desc="The initializer's token account that will receive the payment")]
+  #[account(8, writable, name="initializers_main_account", desc="The initializer's main account to send their rent fees to")]
+  #[account(9, name="token_program", desc="The token program of token X")]
+  #[account(10, name="y_token_program", desc="The token program of token Y")]
+  #[account(11, name="token_x_mint", desc="The mint of token X")]
+  #[account(12, name="token_y_mint", desc="The mint of token Y")]
+  Settle,
+
+  /// Requires threshold of the given approvers to sign ApproveRelease before the escrow can be exchanged or settled, e.g. for escrows of a DAO treasury. Approvers can only be set once, and should be set in the same transaction as the escrow is initialized so that nobody can take the escrow in between.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The account of the person who initialized the escrow
+  /// 1. `[writable]` The escrow account holding the escrow info
+  /// 2. `[]` The first approver. Up to 8 approvers follow in the same way, they don't have to sign
+  #[account(0, signer, name="initializer", desc="The account of the person who initialized the escrow")]
+  #[account(1, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  #[account(2, name="approver", desc="The first approver, followed by the others")]
+  SetApprovers {
+    /// How many approvers have to approve, between 1 and the number of approvers
+    threshold: u8,
+  },
+
+  /// Records the signer's approval to release the escrowed tokens
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` One of the escrow's approvers
+  /// 1. `[writable]` The escrow account holding the escrow info
+  #[account(0, signer, name="approver", desc="One of the escrow's approvers")]
+  #[account(1, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  ApproveRelease,
+
+  /// Same as InitEscrow, except that the taker's tokens vest over time. Exchange moves them into a vesting vault, from which ClaimVested releases them in vesting_periods equal tranches, one every vesting_period_seconds.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The account of the person initializing the escrow
+  /// 1. `[writable]` Temporary token account that should be created prior to this instruction and owned by the initializer
+  /// 2. `[]` The initializer's token account for the token they will receive should the trade go through
+  /// 3. `[writable]` The escrow account, it will hold all necessary info about the trade.
+  /// 4. `[]` The token program that owns the temp token account, either the original token program or Token-2022
+  /// 5. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 6. `[]` The mint allowlist, derived from the seeds `[b"allowlist"]`. Only read if the config enables the allowlist
+  #[account(0, signer, name="initializer", desc="The account of the person initializing the escrow")]
+  #[account(1, writable, name="temp_token_account", desc="Temporary token account holding the tokens to escrow, owned by the initializer")]
+  #[account(2, name="token_to_receive_account", desc="The initializer's token account for the token they will receive")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account, it will hold all necessary info about the trade")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="config", desc="The program's config account")]
+  #[account(6, name="allowlist", desc="The mint allowlist")]
+  InitVestingEscrow {
+    /// The amount party A expects to receive of token Y
+    amount: u64,
+    /// Same as for InitEscrow, 0 means the config's default expiry applies
+    expiry_unix_timestamp: i64,
+    /// Has to be positive
+    vesting_period_seconds: i64,
+    /// Has to be at least 1
+    vesting_periods: u16,
+  },
+
+  /// Releases the vested tokens of an exchanged vesting escrow to the taker. Anyone can call it, the tokens always go to the taker. Once everything has been claimed, the vault and the vesting account are closed and their rent goes back to the taker.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[writable]` The vesting account, derived from the seeds `[b"vesting", escrow account pubkey]`
+  /// 1. `[writable]` The vesting vault
+  /// 2. `[writable]` The taker's token account that will receive the vested tokens
+  /// 3. `[writable]` The taker's main account, which gets the rent back after the last claim
+  /// 4. `[]` The token program of the vested token
+  /// 5. `[]` The mint of the vested token
+  #[account(0, writable, name="vesting_account", desc="The vesting account")]
+  #[account(1, writable, name="vault", desc="The vesting vault")]
+  #[account(2, writable, name="beneficiarys_token_account", desc="The taker's token account that will receive the vested tokens")]
+  #[account(3, writable, name="beneficiary", desc="The taker's main account")]
+  #[account(4, name="token_program", desc="The token program of the vested token")]
+  #[account(5, name="mint", desc="The mint of the vested token")]
+  ClaimVested,
+
+  /// Locks tokens like InitEscrow, but instead of being exchanged they stream to the recipient at a constant rate per second from now until stream_end_unix_timestamp. The recipient takes what has accrued with Withdraw, and a Cancel pays them the accrued part and returns the rest. Nothing is paid for the tokens, so no token Y is involved, and streams don't expire.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The account of the person initializing the escrow
+  /// 1. `[writable]` Temporary token account that should be created prior to this instruction and owned by the initializer
+  /// 2. `[]` Any token account of the initializer's, stored like InitEscrow's account 2 but never paid into
+  /// 3. `[writable]` The escrow account, it will hold all necessary info about the stream.
+  /// 4. `[]` The token program that owns the temp token account, either the original token program or Token-2022
+  /// 5. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 6. `[]` The mint allowlist, derived from the seeds `[b"allowlist"]`. Only read if the config enables the allowlist
+  #[account(0, signer, name="initializer", desc="The account of the person initializing the escrow")]
+  #[account(1, writable, name="temp_token_account", desc="Temporary token account holding the tokens to stream, owned by the initializer")]
+  #[account(2, name="token_to_receive_account", desc="Any token account of the initializer's")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account, it will hold all necessary info about the stream")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="config", desc="The program's config account")]
+  #[account(6, name="allowlist", desc="The mint allowlist")]
+  InitStreamEscrow {
+    /// The account the tokens stream to
+    recipient: Pubkey,
+    /// When the last of the tokens has streamed. Has to be in the future.
+    stream_end_unix_timestamp: i64,
+  },
+
+  /// Pays the recipient of a streaming escrow everything that has accrued since their last withdrawal. Once the stream has ended and been withdrawn in full, the escrow is closed.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The recipient of the stream
+  /// 1. `[writable]` The recipient's token account for the streamed token
+  /// 2. `[writable]` The PDA's temp token account
+  /// 3. `[writable]` The escrow account holding the escrow info
+  /// 4. `[]` The token program that owns the temp token account
+  /// 5. `[]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 6. `[]` The mint of the streamed tokens
+  /// 7. `[writable]` The initializer's main account, which gets the rent back once the stream is closed
+  #[account(0, signer, name="recipient", desc="The recipient of the stream")]
+  #[account(1, writable, name="recipients_token_account", desc="The recipient's token account for the streamed token")]
+  #[account(2, writable, name="pdas_temp_token_account", desc="The PDA's temp token account")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="pda_account", desc="The escrow's PDA")]
+  #[account(6, name="token_x_mint", desc="The mint of the streamed tokens")]
+  #[account(7, writable, name="initializers_main_account", desc="The initializer's main account")]
+  Withdraw,
+
+  /// Settles two escrows that offer each other's tokens against each other: escrow A offers X for Y, escrow B offers Y for X. It goes through if each escrow offers at least what the other one expects, and then each initializer gets everything the other escrow offered. Nobody acts as the taker and anyone can send it, so no protocol fee is charged. Auctions, two-phase, oracle, vesting and streaming escrows can't be matched.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[writable]` Escrow A
+  /// 1. `[writable]` A's temp token account, or for delegated escrows the initializer's token account
+  /// 2. `[writable]` A's initializer's main account, which gets A's rent back
+  /// 3. `[writable]` A's initializer's token account for token Y
+  /// 4. `[]` A's PDA, derived from the seeds `[b"escrow", escrow A pubkey]`
+  /// 5. `[writable]` Escrow B
+  /// 6. `[writable]` B's temp token account, or for delegated escrows the initializer's token account
+  /// 7. `[writable]` B's initializer's main account, which gets B's rent back
+  /// 8. `[writable]` B's initializer's token account for token X
+  /// 9. `[]` B's PDA, derived from the seeds `[b"escrow", escrow B pubkey]`
+  /// 10. `[]` The token program of token X
+  /// 11. `[]` The token program of token Y
+  /// 12. `[]` The mint of token X
+  /// 13. `[]` The mint of token Y
+  /// 14. `[]` The config account, derived from the seeds `[b"config"]`
+  #[account(0, writable, name="escrow_a", desc="Escrow A")]
+  #[account(1, writable, name="temp_token_account_a", desc="A's temp token account")]
+  #[account(2, writable, name="initializer_a", desc="A's initializer's main account")]
+  #[account(3, writable, name="token_to_receive_account_a", desc="A's initializer's token account for token Y")]
+  #[account(4, name="pda_a", desc="A's PDA")]
+  #[account(5, writable, name="escrow_b", desc="Escrow B")]
+  #[account(6, writable, name="temp_token_account_b", desc="B's temp token account")]
+  #[account(7, writable, name="initializer_b", desc="B's initializer's main account")]
+  #[account(8, writable, name="token_to_receive_account_b", desc="B's initializer's token account for token X")]
+  #[account(9, name="pda_b", desc="B's PDA")]
+  #[account(10, name="token_x_program", desc="The token program of token X")]
+  #[account(11, name="token_y_program", desc="The token program of token Y")]
+  #[account(12, name="token_x_mint", desc="The mint of token X")]
+  #[account(13, name="token_y_mint", desc="The mint of token Y")]
+  #[account(14, name="config", desc="The program's config account")]
+  MatchEscrows,
+
+  /// Creates the order book of a mint pair. Anyone can create a market, paying its rent.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The account paying for the market's rent
+  /// 1. `[writable]` The market account, derived from the seeds `[b"market", base mint, quote mint]`
+  /// 2. `[]` The base mint
+  /// 3. `[]` The quote mint
+  /// 4. `[]` The system program
+  #[account(0, signer, writable, name="payer", desc="The account paying for the market's rent")]
+  #[account(1, writable, name="market", desc="The market account")]
+  #[account(2, name="base_mint", desc="The base mint")]
+  #[account(3, name="quote_mint", desc="The quote mint")]
+  #[account(4, name="system_program", desc="The system program")]
+  InitMarket,
+
+  /// Lists an escrow on the market of its mint pair: as an ask if it offers the base mint, as a bid if it offers the quote mint. Only fixed-price escrows that anyone can take can be listed.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The escrow's initializer
+  /// 1. `[]` The escrow account
+  /// 2. `[]` The escrow's temp token account, or for delegated escrows the initializer's token account
+  /// 3. `[writable]` The market account
+  #[account(0, signer, name="initializer", desc="The escrow's initializer")]
+  #[account(1, name="escrow_account", desc="The escrow account")]
+  #[account(2, name="temp_token_account", desc="The escrow's temp token account")]
+  #[account(3, writable, name="market", desc="The market account")]
+  PlaceOrder,
+
+  /// Takes an escrow's order off its market. The initializer has to sign as long as the escrow is open. Once it has been exchanged or cancelled anyone can remove its stale order.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The escrow's initializer, or anyone if the escrow is closed
+  /// 1. `[]` The escrow account
+  /// 2. `[writable]` The market account
+  #[account(0, signer, name="authority", desc="The escrow's initializer, or anyone if the escrow is closed")]
+  #[account(1, name="escrow_account", desc="The escrow account")]
+  #[account(2, writable, name="market", desc="The market account")]
+  CancelOrder,
+
+  /// Matches the best ask against the best bid of a market with MatchEscrows and removes both orders. Anyone can crank it. Fails if the two escrows don't match, see MatchEscrows.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[writable]` The market account
+  /// 1. `[writable]` The best ask's escrow, followed by the rest of MatchEscrows' accounts with it as escrow A and the best bid's escrow as escrow B
+  #[account(0, writable, name="market", desc="The market account")]
+  #[account(1, writable, name="escrow_a", desc="The best ask's escrow, followed by the rest of MatchEscrows' accounts")]
+  ConsumeOrders,
+
+  /// Creates the registry of open escrows that offer token X for token Y. Anyone can create a registry, paying its rent.
+  ///
+  /// Once it exists, passing it to InitEscrow (or any of the other instructions that create escrows), Exchange, Cancel or ReclaimExpired keeps it up to date. The registry is recognised by its owner and size rather than its position, so it can simply be appended to those instructions' accounts.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The account paying for the registry's rent
+  /// 1. `[writable]` The registry account, derived from the seeds `[b"registry", token X mint, token Y mint]`
+  /// 2. `[]` The mint of token X
+  /// 3. `[]` The mint of token Y
+  /// 4. `[]` The system program
+  #[account(0, signer, writable, name="payer", desc="The account paying for the registry's rent")]
+  #[account(1, writable, name="registry", desc="The registry account")]
+  #[account(2, name="token_x_mint", desc="The mint of token X")]
+  #[account(3, name="token_y_mint", desc="The mint of token Y")]
+  #[account(4, name="system_program", desc="The system program")]
+  InitRegistry,
+
+  /// Removes escrows that have been closed without the registry being passed along, e.g. by a Settle or MatchEscrows. Escrows that are still open stay in the registry. Anyone can send it.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[writable]` The registry account
+  /// 1. `[]` The first escrow account to remove, followed by any number of others
+  #[account(0, writable, name="registry", desc="The registry account")]
+  #[account(1, name="escrow_account", desc="The first escrow account to remove, followed by any number of others")]
+  PruneRegistry,
+
+  /// Upgrades a V1 escrow to the current layout, see state::VersionedEscrow. The escrow keeps its address and terms: the account is upgraded like by UpgradeEscrowAccount, and the temp token account is handed from the legacy PDA to the escrow's own. Escrows that went through UpgradeEscrowAccount already only have their temp token account handed over. Anyone can send it, paying for the extra rent.
+  ///
+  /// Migrated V1 escrows don't expire and their created_unix_timestamp is the time of the migration, since V1 didn't record either.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The account paying for the rent of the larger escrow account
+  /// 1. `[writable]` The escrow account
+  /// 2. `[writable]` The escrow's temp token account
+  /// 3. `[]` The initializer's token account that will receive tokens
+  /// 4. `[]` The token program
+  /// 5. `[]` The legacy PDA, derived from the seeds `[b"escrow"]`
+  /// 6. `[]` The escrow's PDA, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 7. `[]` The system program
+  #[account(0, signer, writable, name="payer", desc="The account paying for the rent of the larger escrow account")]
+  #[account(1, writable, name="escrow_account", desc="The escrow account")]
+  #[account(2, writable, name="temp_token_account", desc="The escrow's temp token account")]
+  #[account(3, name="token_to_receive_account", desc="The initializer's token account that will receive tokens")]
+  #[account(4, name="token_program", desc="The token program")]
+  #[account(5, name="legacy_pda", desc="The legacy PDA")]
+  #[account(6, name="pda", desc="The escrow's PDA")]
+  #[account(7, name="system_program", desc="The system program")]
+  MigrateEscrow,
+
+  /// Closes an escrow that can never be exchanged because its temp token account was closed, emptied or taken out of the PDA's hands, or, for a delegated escrow, because the PDA's allowance was revoked or reduced. Like ReclaimExpired anyone can send it, and the rent goes back to the initializer. A temp token account that is still the PDA's is closed along with the escrow.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[writable]` The escrow's temp token account, which may no longer exist. For delegated escrows, the initializer's token account, which is left alone
+  /// 1. `[writable]` The initializer's main account to send their rent fees to
+  /// 2. `[writable]` The escrow account
+  /// 3. `[]` The token program
+  /// 4. `[]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 5. `[writable]` (optional) The registry of the escrow's mint pair, to remove the escrow from
+  /// 6. `[writable]` (optional) The stats account, to count the escrow as cancelled in
+  #[account(0, writable, name="temp_token_account", desc="The escrow's temp token account")]
+  #[account(1, writable, name="initializer", desc="The initializer's main account to send their rent fees to")]
+  #[account(2, writable, name="escrow_account", desc="The escrow account")]
+  #[account(3, name="token_program", desc="The token program")]
+  #[account(4, name="pda", desc="The escrow's PDA")]
+  #[account(5, optional, writable, name="registry", desc="The registry of the escrow's mint pair")]
+  CloseStaleEscrow,
+
+  /// Reports an escrow's mints, remaining amounts, expiry and status as return data, a Borsh encoded view::EscrowView. Nothing is written, so other programs can call it through CPI and clients can simulate it instead of decoding the escrow account themselves.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[]` The escrow account
+  /// 1. `[]` The escrow's temp token account, which may no longer exist. For delegated escrows, the initializer's token account
+  #[account(0, name="escrow_account", desc="The escrow account")]
+  #[account(1, name="temp_token_account", desc="The escrow's temp token account")]
+  GetEscrow,
 
+  /// Same as InitEscrow, but the escrow's terms are a rate rather than a total: price_numerator of token Y for every price_denominator of token X. Takers can take any part of the escrowed amount, paying the rate rounded up, and the escrow stays open until all of it is taken. Wrapped SOL can't be offered this way, as it is paid out by closing the temp token account.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// The same accounts as InitEscrow
+  #[account(0, signer, name="initializer", desc="The account of the person initializing the escrow")]
+  #[account(1, writable, name="temp_token_account", desc="Temporary token account holding the tokens to escrow, owned by the initializer")]
+  #[account(2, name="token_to_receive_account", desc="The initializer's token account for the token they will receive")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account, it will hold all necessary info about the trade")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="config", desc="The program's config account")]
+  #[account(6, name="allowlist", desc="The mint allowlist")]
+  InitRateEscrow {
+    /// The amount of token Y paid for every price_denominator of token X, neither can be 0
+    price_numerator: u64,
+    price_denominator: u64,
+    /// Same as for InitEscrow, 0 means the config's default expiry applies
+    expiry_unix_timestamp: i64,
+  },
+
+  /// Changes the protocol fee and who it is paid to. Only the admin can do this.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The admin stored in the config account
+  /// 1. `[writable]` The config account, derived from the seeds `[b"config"]`
+  #[account(0, signer, name="admin", desc="The admin stored in the config account")]
+  #[account(1, writable, name="config", desc="The program's config account")]
+  SetFee {
+    /// The protocol fee in basis points, at most MAX_FEE_BPS
+    fee_bps: u16,
+    /// The owner of the token accounts fees are paid into from now on, the default pubkey for the fee vaults
+    fee_collector: Pubkey,
+  },
+
+  /// Creates the stats account, which counts the escrows created, filled and cancelled and adds up the volume they moved. Anyone can create it, paying its rent, but there is only one.
+  ///
+  /// Like the registry, it is recognised by its owner and size, and only kept up to date by the instructions it is appended to: InitEscrow (or any of the other instructions that create escrows), Exchange, Cancel, ReclaimExpired and CloseStaleEscrow.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The account paying for the stats account's rent
+  /// 1. `[writable]` The stats account, derived from the seeds `[b"stats"]`
+  /// 2. `[]` The system program
+  #[account(0, signer, writable, name="payer", desc="The account paying for the stats account's rent")]
+  #[account(1, writable, name="stats", desc="The stats account")]
+  #[account(2, name="system_program", desc="The system program")]
+  InitStats,
+
+  /// Reserves an escrow for its taker, who locks the escrow's reservation_deposit in lamports to be the only one who can exchange it for the given number of slots. The initializer can't cancel or update a reserved escrow either. If the taker exchanges in time, the deposit is refunded. Otherwise it is forfeited to the initializer, when the escrow is next reserved, exchanged or closed.
+  ///
+  /// Only escrows whose initializer asked for a deposit at InitEscrow can be reserved, and only while nobody else holds a reservation. A reservation doesn't hold off the escrow's expiry.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The taker, who pays the deposit
+  /// 1. `[writable]` The escrow account, which holds the deposit
+  /// 2. `[writable]` The initializer's main account, to forfeit a lapsed reservation's deposit to
+  /// 3. `[]` The system program
+  #[account(0, signer, writable, name="taker", desc="The taker, who pays the deposit")]
+  #[account(1, writable, name="escrow_account", desc="The escrow account")]
+  #[account(2, writable, name="initializer", desc="The initializer's main account")]
+  #[account(3, name="system_program", desc="The system program")]
+  Reserve {
+    /// How long the reservation lasts, at most MAX_RESERVATION_SLOTS
+    slots: u64,
+  },
+
+  /// Sets the penalty an initializer pays, in token X, for cancelling an escrow, to discourage posting offers only to fish for prices. It is the flat amount plus the share in basis points, capped at what the initializer would get back. Only the admin can do this.
+  ///
+  /// ReclaimExpired and CloseStaleEscrow are never penalised, since the escrow was left open for its whole life.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The admin stored in the config account
+  /// 1. `[writable]` The config account, derived from the seeds `[b"config"]`
+  #[account(0, signer, name="admin", desc="The admin stored in the config account")]
+  #[account(1, writable, name="config", desc="The program's config account")]
+  SetCancelPenalty {
+    /// The share of the returned tokens forfeited, in basis points, at most MAX_FEE_BPS
+    penalty_bps: u16,
+    /// A flat amount of token X forfeited on top of the share
+    penalty_amount: u64,
+    /// Pay the penalty on private escrows to the allowed taker instead of the fee collector or the fee vault
+    to_counterparty: bool,
+  },
+
+  /// Sets the gate program that has to approve every taker before Exchange, Accept or PlaceBid goes through, e.g. a KYC or credential program. See gate for the instruction it is called with. Only the admin can do this.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The admin stored in the config account
+  /// 1. `[writable]` The config account, derived from the seeds `[b"config"]`
+  #[account(0, signer, name="admin", desc="The admin stored in the config account")]
+  #[account(1, writable, name="config", desc="The program's config account")]
+  SetGateProgram {
+    /// The default pubkey removes the gate
+    gate_program: Pubkey,
+  },
+
+  /// Starts a collection offer: the initializer escrows the tokens they pay and anyone holding an NFT of a verified Metaplex collection can sell it to them for those tokens with FillCollectionOffer. The escrow's token X is the payment, and it has no token Y since the NFT isn't known yet.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The account of the person initializing the escrow
+  /// 1. `[writable]` Temporary token account holding the payment, owned by the initializer
+  /// 2. `[]` The collection's mint, i.e. the mint of the collection NFT
+  /// 3. `[writable]` The escrow account, it will hold all necessary info about the trade. If it doesn't exist yet it has to sign, and the program creates it
+  /// 4. `[]` The token program that owns the temp token account
+  /// 5. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 6. `[]` The mint allowlist, derived from the seeds `[b"allowlist"]`. Only read if the config enables the allowlist, and only the payment's mint has to be on it
+  ///
+  /// The optional accounts of InitEscrow can be appended as well.
+  #[account(0, signer, name="initializer", desc="The account of the person initializing the escrow")]
+  #[account(1, writable, name="temp_token_account", desc="Temporary token account holding the payment, owned by the initializer")]
+  #[account(2, name="collection_mint", desc="The collection's mint")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account, it will hold all necessary info about the trade")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="config", desc="The program's config account")]
+  #[account(6, name="allowlist", desc="The mint allowlist")]
+  InitCollectionOffer {
+    /// The mint of the collection, which account 2 has to be
+    collection: Pubkey,
+    /// Same as for InitEscrow, 0 means the config's default expiry applies
+    expiry_unix_timestamp: i64,
+  },
+
+  /// Sells an NFT of the collection to a collection offer: the NFT goes to the initializer and the escrowed payment to the seller, who is the taker here. The NFT's metadata has to name the offer's collection and have it verified.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The seller
+  /// 1. `[writable]` The seller's token account holding the NFT
+  /// 2. `[writable]` The seller's token account for the payment. For native SOL escrows it can be any account, the SOL is unwrapped into it
+  /// 3. `[writable]` The PDA's temp token account holding the payment
+  /// 4. `[writable]` The initializer's main account to send their rent fees to
+  /// 5. `[writable]` The initializer's token account for the NFT, which has to exist
+  /// 6. `[writable]` The escrow account holding the escrow info
+  /// 7. `[]` The token program of the payment
+  /// 8. `[]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 9. `[]` The mint of the payment
+  /// 10. `[]` The NFT's mint
+  /// 11. `[]` The token program of the NFT
+  /// 12. `[]` The Metaplex metadata account of the NFT, derived from the seeds `[b"metadata", metadata program id, NFT mint]`
+  /// 13. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 14. `[writable]` (optional) The registry of the escrow's mint pair, see InitRegistry. The escrow is removed from it
+  /// 15. `[writable]` (optional) The stats account, see InitStats. The sale is counted in it
+  ///
+  /// For Token-2022 mints with a transfer hook, the hook program, its validation account and the extra accounts the validation account lists have to be appended as well, in any order. See transfer_hook. So do the config's gate program and the accounts of it the gate reads, see gate.
+  ///
+  /// Neither the protocol fee nor royalties are charged.
+  #[account(0, signer, name="seller", desc="The seller")]
+  #[account(1, writable, name="sellers_nft_account", desc="The seller's token account holding the NFT")]
+  #[account(2, writable, name="sellers_token_to_receive_account", desc="The seller's token account for the payment")]
+  #[account(3, writable, name="pdas_temp_token_account", desc="The PDA's temp token account holding the payment")]
+  #[account(4, writable, name="initializers_main_account", desc="The initializer's main account to send their rent fees to")]
+  #[account(5, writable, name="initializers_nft_account", desc="The initializer's token account for the NFT")]
+  #[account(6, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  #[account(7, name="token_program", desc="The token program of the payment")]
+  #[account(8, name="pda_account", desc="The escrow's PDA")]
+  #[account(9, name="token_x_mint", desc="The mint of the payment")]
+  #[account(10, name="nft_mint", desc="The NFT's mint")]
+  #[account(11, name="nft_token_program", desc="The token program of the NFT")]
+  #[account(12, name="nft_metadata", desc="The Metaplex metadata account of the NFT")]
+  #[account(13, name="config", desc="The program's config account")]
+  #[account(14, optional, writable, name="registry", desc="The registry of the escrow's mint pair")]
+  FillCollectionOffer,
+
+  /// Same as InitEscrow, but the initializer escrows lamports rather than tokens. They are moved straight into the escrow's PDA, a plain system account, so no temp token account or wrapped SOL is involved, and Exchange moves them straight to the taker. The escrow's token X mint is left as the system program's id, which is how lamport escrows are told apart.
+  ///
+  /// Exchange, Cancel and ReclaimExpired take the same accounts as for other escrows, with the PDA as the temp token account and the system program as both the token program and the mint of token X. The taker's account for token X can be any account, the lamports are sent to it as they are. A cancellation penalty is paid to the recipient's own account rather than a token account of theirs.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The account of the person initializing the escrow, which pays the lamports
+  /// 1. `[writable]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`, which holds the lamports
+  /// 2. `[]` The initializer's token account for the token they will receive should the trade go through
+  /// 3. `[writable]` The escrow account, it will hold all necessary info about the trade. If it doesn't exist yet it has to sign, and the program creates it
+  /// 4. `[]` The system program
+  /// 5. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 6. `[]` The mint allowlist, derived from the seeds `[b"allowlist"]`. Only read if the config enables the allowlist, in which case the native mint has to be on it
+  /// 7. `[writable]` (optional) The registry of the escrow's mint pair, see InitRegistry. The escrow is added to it
+  #[account(0, signer, writable, name="initializer", desc="The account of the person initializing the escrow")]
+  #[account(1, writable, name="pda_account", desc="The escrow's PDA, which holds the lamports")]
+  #[account(2, name="token_to_receive_account", desc="The initializer's token account for the token they will receive")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account, it will hold all necessary info about the trade")]
+  #[account(4, name="system_program", desc="The system program")]
+  #[account(5, name="config", desc="The program's config account")]
+  #[account(6, name="allowlist", desc="The mint allowlist")]
+  #[account(7, optional, writable, name="registry", desc="The registry of the escrow's mint pair")]
+  InitLamportEscrow {
+    /// The lamports to escrow. At least the rent-exempt minimum of an empty account, which the PDA needs to exist.
+    lamports: u64,
+    /// The amount party A expects to receive of token Y
+    amount: u64,
+    /// Same as for InitEscrow, 0 means the config's default expiry applies
+    expiry_unix_timestamp: i64,
+    /// Same as for InitEscrow, the default pubkey lets anyone take the trade
+    allowed_taker: Pubkey,
+  },
+
+  /// Starts a buy escrow: the initializer escrows the tokens they pay with and names how much of the token they want for all of it. Takers holding that token can sell it to them in full or in part, getting the same share of the payment, so it works like an InitRateEscrow with price_numerator amount and price_denominator the escrowed amount. A payment in wrapped SOL can't be taken in part, so it is only sold for the whole amount.
+  ///
+  /// Takers pass the amount of the payment they take to Exchange, state::Escrow::amount_x_for gives it for the amount of the wanted token they sell.
+  ///
+  /// Accounts expected:
+  ///
+  /// The same accounts as InitEscrow, with the temp token account holding the payment and account 2 the initializer's token account for the token they buy
+  #[account(0, signer, name="initializer", desc="The account of the person initializing the escrow")]
+  #[account(1, writable, name="temp_token_account", desc="Temporary token account holding the payment, owned by the initializer")]
+  #[account(2, name="token_to_receive_account", desc="The initializer's token account for the token they buy")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account, it will hold all necessary info about the trade")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="config", desc="The program's config account")]
+  #[account(6, name="allowlist", desc="The mint allowlist")]
+  InitBuyEscrow {
+    /// The amount of token Y the initializer wants for everything they escrowed. Can't be 0.
+    amount: u64,
+    /// Same as for InitEscrow, 0 means the config's default expiry applies
+    expiry_unix_timestamp: i64,
+    /// Same as for InitEscrow, the default pubkey lets anyone take the trade
+    allowed_taker: Pubkey,
+  },
+
+  /// Undoes Accept on a two-phase escrow that both sides want out of. The escrow is open again afterwards, so the taker can get their payment back with WithdrawBid and the initializer can Cancel, e.g. in the same transaction.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The account of the person who initialized the escrow
+  /// 1. `[signer]` The taker who accepted the escrow
+  /// 2. `[writable]` The escrow account holding the escrow info
+  #[account(0, signer, name="initializer", desc="The account of the person who initialized the escrow")]
+  #[account(1, signer, name="taker", desc="The taker who accepted the escrow")]
+  #[account(2, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  Abort,
+
+  /// Pushes an open escrow's expiry back, which is cheaper than cancelling it and creating a new one. The expiry can only move later, and no further ahead than the config's max_expiry_seconds.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The account of the person who initialized the escrow
+  /// 1. `[writable]` The escrow account holding the escrow info
+  /// 2. `[]` The config account, derived from the seeds `[b"config"]`
+  #[account(0, signer, name="initializer", desc="The account of the person who initialized the escrow")]
+  #[account(1, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  #[account(2, name="config", desc="The program's config account")]
+  ExtendDeadline {
+    /// The new expiry. Has to be later than the current one, and escrows that never expire can't be extended.
+    expiry_unix_timestamp: i64,
+  },
+
+  /// Limits how far ahead escrows may expire, see ExtendDeadline. Applies to escrows initialized from then on, existing ones keep their expiry. Only the admin can do this.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The admin stored in the config account
+  /// 1. `[writable]` The config account, derived from the seeds `[b"config"]`
+  #[account(0, signer, name="admin", desc="The admin stored in the config account")]
+  #[account(1, writable, name="config", desc="The program's config account")]
+  SetMaxExpiry {
+    /// In seconds from the time the expiry is set. 0 removes the limit, and escrows may be created without an expiry again.
+    max_expiry_seconds: i64,
+  },
+
+  /// Hands an escrow over to a new initializer, who from then on is paid when it is taken and gets the tokens and the rent back when it is cancelled. Delegated escrows can't be transferred, since their tokens stay in the current initializer's own token account.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The account of the person who initialized the escrow
+  /// 1. `[writable]` The escrow account holding the escrow info
+  /// 2. `[]` The new initializer's main account
+  /// 3. `[]` The new initializer's token account for the token they will receive should the trade go through. For collection offers, the collection's mint instead.
+  #[account(0, signer, name="initializer", desc="The account of the person who initialized the escrow")]
+  #[account(1, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  #[account(2, name="new_initializer", desc="The new initializer's main account")]
+  #[account(3, name="token_to_receive_account", desc="The new initializer's token account for the token they will receive should the trade go through")]
+  TransferEscrow,
+
+  /// Exchanges an escrow at the price of an order its initializer signed off-chain, see signed_order. The order is for the whole escrow, and the signed price replaces the escrow's own, which still holds for Exchange. An instruction of the ed25519 program verifying the initializer's signature of signed_order::message has to come right before this one.
+  ///
+  /// Accounts expected:
+  ///
+  /// The same accounts as Exchange, plus the instructions sysvar, which is looked up by key
+  #[account(0, signer, name="taker", desc="The account of the person taking the trade")]
+  #[account(1, writable, name="takers_sending_token_account", desc="The taker's token account for the token they send")]
+  #[account(2, writable, name="takers_token_to_receive_account", desc="The taker's token account for the token they will receive")]
+  #[account(3, writable, name="pdas_temp_token_account", desc="The PDA's temp token account to get tokens from and eventually close")]
+  #[account(4, writable, name="initializers_main_account", desc="The initializer's main account to send their rent fees to")]
+  #[account(5, writable, name="initializers_token_to_receive_account", desc="The initializer's token account that will receive tokens")]
+  #[account(6, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  #[account(7, name="token_program", desc="The token program of the token the taker receives")]
+  #[account(8, name="pda_account", desc="The escrow's PDA")]
+  #[account(9, name="token_x_mint", desc="The mint of the token the taker receives")]
+  #[account(10, name="token_y_mint", desc="The mint of the token the taker sends")]
+  #[account(11, name="y_token_program", desc="The token program of the token the taker sends")]
+  #[account(12, name="config", desc="The program's config account")]
+  SettleSignedOrder {
+    /// The amount of token X the order is for, the whole escrow
+    amount: u64,
+    /// Same as for Exchange, the price the initializer signed is included
+    max_payment: u64,
+    /// The price in token Y the initializer signed
+    price: u64,
+    /// The taker the initializer signed the order for, the default pubkey if anyone can settle it
+    order_taker: Pubkey,
+    /// When the order expires. Signed orders can't be revoked, so they should expire soon.
+    order_expiry_unix_timestamp: i64,
+  },
+
+  /// Adds, updates or removes a mint the escrow accepts as payment besides token Y. A taker pays in it by passing token accounts of it, and its mint, to Exchange instead of token Y's. `amount` is the price in the option's mint: for the whole escrow, or for rate-based escrows the price_numerator of the rate, whose price_denominator stays the escrow's. 0 removes the option. Auctions, oracle, two-phase, streaming escrows and collection offers can't have payment options, and an escrow has at most MAX_PAYMENT_OPTIONS.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The account of the person who initialized the escrow
+  /// 1. `[writable]` The escrow account holding the escrow info
+  /// 2. `[]` The initializer's token account that will receive the option's mint
+  /// 3. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 4. `[]` The mint allowlist, derived from the seeds `[b"allowlist"]`. Only read if the config enables the allowlist
+  #[account(0, signer, name="initializer", desc="The account of the person who initialized the escrow")]
+  #[account(1, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  #[account(2, name="token_to_receive_account", desc="The initializer's token account that will receive the option's mint")]
+  #[account(3, name="config", desc="The program's config account")]
+  #[account(4, name="allowlist", desc="The mint allowlist")]
+  SetPaymentOption {
+    /// The price in the option's mint, 0 to remove the option
+    amount: u64,
+  },
+
+  /// Adds a mint to the StablecoinList that USD-priced escrows are paid in, or updates its price feed and tolerance, creating the list account the first time
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The admin stored in the config account, who pays for the stablecoin list account
+  /// 1. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 2. `[writable]` The stablecoin list, derived from the seeds `[b"stablecoins"]`
+  /// 3. `[]` The system program
+  #[account(0, signer, writable, name="admin", desc="The admin stored in the config account")]
+  #[account(1, name="config", desc="The program's config account")]
+  #[account(2, writable, name="stablecoins", desc="The stablecoin list")]
+  #[account(3, name="system_program", desc="The system program")]
+  AddStablecoin {
+    mint: Pubkey,
+    /// The Pyth price account quoting the mint in USD
+    price_feed: Pubkey,
+    /// How far the mint's price may stray from one dollar, in basis points, before Exchange stops taking it
+    tolerance_bps: u16,
+  },
+
+  /// Removes a mint from the StablecoinList. USD-priced escrows can't be paid in it anymore.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The admin stored in the config account
+  /// 1. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 2. `[writable]` The stablecoin list, derived from the seeds `[b"stablecoins"]`
+  #[account(0, signer, name="admin", desc="The admin stored in the config account")]
+  #[account(1, name="config", desc="The program's config account")]
+  #[account(2, writable, name="stablecoins", desc="The stablecoin list")]
+  RemoveStablecoin {
+    mint: Pubkey,
+  },
+
+  /// Prices an open escrow in USD, or with 0 back in token Y. Exchange then takes any mint on the StablecoinList, token Y included if it is on it, converted at the price of the mint's feed, which has to be within the mint's tolerance of one dollar. The taker passes the stablecoin's accounts in place of token Y's, and the initializer's token account of it is paid, whichever it is. Only escrows that could have payment options, but have none, and aren't rate-based can be priced in USD.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The account of the person who initialized the escrow
+  /// 1. `[writable]` The escrow account holding the escrow info
+  #[account(0, signer, name="initializer", desc="The account of the person who initialized the escrow")]
+  #[account(1, writable, name="escrow_account", desc="The escrow account holding the escrow info")]
+  SetUsdPrice {
+    /// The price of the whole escrow in USD, with USD_DECIMALS decimals
+    usd_amount: u64,
+  },
+
+  /// Creates an escrow that swaps one NFT for another: the initializer escrows their NFT, like InitNftEscrow, and only takes the NFT of a given mint in return. The expected amount is 1 and token Y the wanted NFT's mint, so Exchange swaps both NFTs in one go, without a protocol fee or royalties, which round down to nothing on a single token. The wanted NFT can't be a programmable NFT, since the taker's NFT is moved by the token program.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// The same accounts as InitNftEscrow, with account 2 the initializer's token account of the wanted NFT, and
+  /// 8. `[]` The mint of the NFT the initializer wants
+  #[account(0, signer, name="initializer", desc="The account of the person initializing the escrow")]
+  #[account(1, writable, name="temp_token_account", desc="Temporary token account holding the NFT, owned by the initializer")]
+  #[account(2, name="token_to_receive_account", desc="The initializer's token account of the NFT they want")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account, it will hold all necessary info about the trade")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="config", desc="The program's config account")]
+  #[account(6, name="allowlist", desc="The mint allowlist")]
+  #[account(7, name="nft_mint", desc="The NFT's mint")]
+  #[account(8, name="wanted_nft_mint", desc="The mint of the NFT the initializer wants")]
+  InitNftSwap {
+    /// Same as for InitEscrow, 0 (the default) means the config's default expiry applies
+    expiry_unix_timestamp: i64,
+    /// Same as for InitEscrow, the default pubkey lets anyone take the trade
+    allowed_taker: Pubkey,
+  },
+
+  /// Lays out a swap ring, see swap_ring: each participant gives their leg's amount of its mint to the participant of the next leg, and the last participant to the first. Nothing moves until every participant has deposited their leg and the ring is executed.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The ring's creator, who pays for the ring account. They don't have to be a participant
+  /// 1. `[writable]` The ring account, derived from the seeds `[b"ring", creator pubkey, ring id as little-endian bytes]`
+  /// 2. `[]` The system program
+  /// 3. `[]` The participant of the first leg
+  /// 4. `[]` The mint the first participant gives. The participant and the mint of every further leg follow in the same way, one pair per amount
+  #[account(0, signer, writable, name="creator", desc="The ring's creator, who pays for the ring account")]
+  #[account(1, writable, name="ring", desc="The ring account")]
+  #[account(2, name="system_program", desc="The system program")]
+  #[account(3, name="participant", desc="The participant of the first leg")]
+  #[account(4, name="mint", desc="The mint the first participant gives, followed by the other legs' participants and mints")]
+  InitRing {
+    /// Tells apart the rings of one creator
+    ring_id: u64,
+    /// Until when the participants can deposit. After it they can withdraw their deposits unless the ring was executed
+    deadline_unix_timestamp: i64,
+    /// How much each participant gives, one amount per leg. A ring has between MIN_RING_LEGS and MAX_RING_LEGS legs
+    amounts: Vec<u64>,
+  },
+
+  /// Deposits the signing participant's leg of a swap ring into the leg's vault, which is created with the participant paying the rent. Depositing is how a participant agrees to the ring, so it can only be done before the ring's deadline.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The participant
+  /// 1. `[writable]` The participant's token account the leg's tokens come from
+  /// 2. `[writable]` The ring account
+  /// 3. `[writable]` The leg's vault, derived from the seeds `[b"ring_vault", ring pubkey, leg index]`
+  /// 4. `[]` The mint of the leg
+  /// 5. `[]` The token program of the mint, either the original token program or Token-2022
+  /// 6. `[]` The system program
+  #[account(0, signer, writable, name="participant", desc="The participant")]
+  #[account(1, writable, name="participant_token_account", desc="The participant's token account the leg's tokens come from")]
+  #[account(2, writable, name="ring", desc="The ring account")]
+  #[account(3, writable, name="vault", desc="The leg's vault")]
+  #[account(4, name="mint", desc="The mint of the leg")]
+  #[account(5, name="token_program", desc="The token program of the mint")]
+  #[account(6, name="system_program", desc="The system program")]
+  DepositRingLeg,
+
+  /// Pays every vault of a swap ring whose legs have all been deposited out to the participant of the next leg, then closes the vaults and the ring. Anyone can execute a complete ring, even after its deadline as long as nobody has withdrawn.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[writable]` The ring account
+  /// 1. `[writable]` The ring's creator, who gets the ring account's rent back
+  /// 2. `[writable]` The first leg's vault
+  /// 3. `[writable]` The token account of the first leg's mint that the second participant receives into
+  /// 4. `[]` The mint of the first leg
+  /// 5. `[]` The token program of the mint
+  /// 6. `[writable]` The first participant, who gets the vault's rent back. Accounts 2 to 6 of every further leg follow in the same way, the last leg paying the first participant
+  #[account(0, writable, name="ring", desc="The ring account")]
+  #[account(1, writable, name="creator", desc="The ring's creator")]
+  #[account(2, writable, name="vault", desc="The first leg's vault, followed by the accounts of the other legs")]
+  #[account(3, writable, name="recipient_token_account", desc="The token account the second participant receives into")]
+  #[account(4, name="mint", desc="The mint of the first leg")]
+  #[account(5, name="token_program", desc="The token program of the mint")]
+  #[account(6, writable, name="participant", desc="The first participant")]
+  ExecuteRing,
+
+  /// Returns the signing participant's deposit from a swap ring whose deadline has passed without it being executed, and closes the leg's vault.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The participant, who gets the vault's rent back
+  /// 1. `[writable]` The participant's token account the deposit is returned to
+  /// 2. `[writable]` The ring account
+  /// 3. `[writable]` The leg's vault
+  /// 4. `[]` The mint of the leg
+  /// 5. `[]` The token program of the mint
+  #[account(0, signer, writable, name="participant", desc="The participant")]
+  #[account(1, writable, name="participant_token_account", desc="The participant's token account the deposit is returned to")]
+  #[account(2, writable, name="ring", desc="The ring account")]
+  #[account(3, writable, name="vault", desc="The leg's vault")]
+  #[account(4, name="mint", desc="The mint of the leg")]
+  #[account(5, name="token_program", desc="The token program of the mint")]
+  WithdrawRingLeg,
+
+  /// Closes a swap ring that holds no deposits, e.g. one that expired and was withdrawn from, and returns its rent to the creator.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The ring's creator
+  /// 1. `[writable]` The ring account
+  #[account(0, signer, writable, name="creator", desc="The ring's creator")]
+  #[account(1, writable, name="ring", desc="The ring account")]
+  CloseRing,
+
+  /// Quotes a price for an open escrow, for initializers who would rather have takers compete than set the price themselves. The quoter offers price of token Y for the escrow's size of token X until the quote's expiry, and the initializer settles with whichever quote they like with AcceptQuote. The quote account is created with the quoter paying the rent, so each quoter has one quote per escrow at a time. The price is either locked in the quote's vault, or the quote PDA is approved as delegate of it on the quoter's token account, which replaces any other delegate of that account. Only escrows that MatchEscrows could settle can be quoted.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The quoter
+  /// 1. `[]` The escrow account
+  /// 2. `[writable]` The quote account, derived from the seeds `[b"quote", escrow account pubkey, quoter pubkey]`
+  /// 3. `[writable]` The quoter's token account of token Y the price comes from
+  /// 4. `[]` The quoter's token account of token X that receives the escrow
+  /// 5. `[]` The mint of token Y
+  /// 6. `[]` The token program of token Y
+  /// 7. `[]` The system program
+  /// 8. `[writable]` (locked quotes only) The quote's vault, derived from the seeds `[b"quote_vault", quote pubkey]`
+  #[account(0, signer, writable, name="quoter", desc="The quoter")]
+  #[account(1, name="escrow_account", desc="The escrow account")]
+  #[account(2, writable, name="quote", desc="The quote account")]
+  #[account(3, writable, name="source_token_account", desc="The quoter's token account of token Y the price comes from")]
+  #[account(4, name="receive_token_account", desc="The quoter's token account of token X that receives the escrow")]
+  #[account(5, name="token_y_mint", desc="The mint of token Y")]
+  #[account(6, name="token_y_program", desc="The token program of token Y")]
+  #[account(7, name="system_program", desc="The system program")]
+  #[account(8, optional, writable, name="quote_vault", desc="The quote's vault, for locked quotes")]
+  PostQuote {
+    /// The amount of token X quoted for, which has to be what the escrow offers when the quote is accepted
+    size: u64,
+    /// The amount of token Y offered for it
+    price: u64,
+    /// When the quote lapses, 0 for never
+    expiry_unix_timestamp: i64,
+    /// Moves the price into the quote's vault instead of approving the quote PDA as its delegate
+    lock_funds: bool,
+  },
+
+  /// Settles the escrow with a quote on it: the quoter's price goes to the initializer and the escrowed tokens to the quoter, and the escrow, the quote and a locked quote's vault are closed. Like MatchEscrows, no protocol fee is charged.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The account of the person who initialized the escrow
+  /// 1. `[writable]` The escrow account
+  /// 2. `[writable]` The escrow's temp token account
+  /// 3. `[writable]` The initializer's token account of token Y that receives the price
+  /// 4. `[]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 5. `[writable]` The quote account
+  /// 6. `[writable]` The quoter, who gets the rent of the quote and its vault back
+  /// 7. `[writable]` The quote's vault if the quote is locked, the quoter's token account of token Y otherwise
+  /// 8. `[writable]` The quoter's token account of token X
+  /// 9. `[]` The token program of token X
+  /// 10. `[]` The token program of token Y
+  /// 11. `[]` The mint of token X
+  /// 12. `[]` The mint of token Y
+  /// 13. `[]` The config account, derived from the seeds `[b"config"]`
+  #[account(0, signer, writable, name="initializer", desc="The account of the person who initialized the escrow")]
+  #[account(1, writable, name="escrow_account", desc="The escrow account")]
+  #[account(2, writable, name="temp_token_account", desc="The escrow's temp token account")]
+  #[account(3, writable, name="initializer_token_account", desc="The initializer's token account of token Y")]
+  #[account(4, name="pda_account", desc="The PDA account")]
+  #[account(5, writable, name="quote", desc="The quote account")]
+  #[account(6, writable, name="quoter", desc="The quoter")]
+  #[account(7, writable, name="payment_source", desc="The quote's vault, or the quoter's token account of token Y")]
+  #[account(8, writable, name="quoter_token_account", desc="The quoter's token account of token X")]
+  #[account(9, name="token_x_program", desc="The token program of token X")]
+  #[account(10, name="token_y_program", desc="The token program of token Y")]
+  #[account(11, name="token_x_mint", desc="The mint of token X")]
+  #[account(12, name="token_y_mint", desc="The mint of token Y")]
+  #[account(13, name="config", desc="The program's config account")]
+  AcceptQuote,
+
+  /// Withdraws a quote, returning a locked price to the quoter's token account or revoking the quote PDA's allowance, and closes the quote. Quotes on escrows that have been settled otherwise are withdrawn the same way.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The quoter
+  /// 1. `[writable]` The quote account
+  /// 2. `[writable]` The quoter's token account of token Y the quote was posted with
+  /// 3. `[]` The mint of token Y
+  /// 4. `[]` The token program of token Y
+  /// 5. `[writable]` (locked quotes only) The quote's vault
+  #[account(0, signer, writable, name="quoter", desc="The quoter")]
+  #[account(1, writable, name="quote", desc="The quote account")]
+  #[account(2, writable, name="source_token_account", desc="The quoter's token account of token Y")]
+  #[account(3, name="token_y_mint", desc="The mint of token Y")]
+  #[account(4, name="token_y_program", desc="The token program of token Y")]
+  #[account(5, optional, writable, name="quote_vault", desc="The quote's vault, for locked quotes")]
+  CancelQuote,
+
+  /// Proposes a different price for an open escrow: the taker offers amount of token Y for the whole escrow instead of its expected amount, and the initializer can take them up on it with AcceptCounteroffer. The counteroffer account is created with the taker paying the rent, and the counteroffer PDA is approved as delegate of the amount on the taker's token account, which replaces any other delegate of that account. Proposing again changes the counteroffer, and an amount of 0 withdraws it, revoking the allowance and closing the account. Only escrows that MatchEscrows could settle take counteroffers.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The taker
+  /// 1. `[]` The escrow account
+  /// 2. `[writable]` The counteroffer account, derived from the seeds `[b"counteroffer", escrow account pubkey, taker pubkey]`
+  /// 3. `[writable]` The taker's token account of token Y the amount comes from
+  /// 4. `[]` The taker's token account of token X that receives the escrow
+  /// 5. `[]` The token program of token Y
+  /// 6. `[]` The system program
+  #[account(0, signer, writable, name="taker", desc="The taker")]
+  #[account(1, name="escrow_account", desc="The escrow account")]
+  #[account(2, writable, name="counteroffer", desc="The counteroffer account")]
+  #[account(3, writable, name="payment_token_account", desc="The taker's token account of token Y the amount comes from")]
+  #[account(4, name="receive_token_account", desc="The taker's token account of token X that receives the escrow")]
+  #[account(5, name="token_y_program", desc="The token program of token Y")]
+  #[account(6, name="system_program", desc="The system program")]
+  ProposeCounteroffer {
+    /// The amount of token Y offered for the whole escrow, 0 to withdraw the counteroffer
+    amount: u64,
+  },
+
+  /// Agrees to a counteroffer: the escrow's expected amount becomes the counteroffer's, and the escrow is settled with the taker right away, the amount going to the initializer and the escrowed tokens to the taker. The escrow and the counteroffer are closed. Like MatchEscrows, no protocol fee is charged.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The account of the person who initialized the escrow
+  /// 1. `[writable]` The escrow account
+  /// 2. `[writable]` The escrow's temp token account
+  /// 3. `[writable]` The initializer's token account of token Y that receives the amount
+  /// 4. `[]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 5. `[writable]` The counteroffer account
+  /// 6. `[writable]` The taker, who gets the counteroffer's rent back
+  /// 7. `[writable]` The taker's token account of token Y named in the counteroffer
+  /// 8. `[writable]` The taker's token account of token X named in the counteroffer
+  /// 9. `[]` The token program of token X
+  /// 10. `[]` The token program of token Y
+  /// 11. `[]` The mint of token X
+  /// 12. `[]` The mint of token Y
+  /// 13. `[]` The config account, derived from the seeds `[b"config"]`
+  #[account(0, signer, writable, name="initializer", desc="The account of the person who initialized the escrow")]
+  #[account(1, writable, name="escrow_account", desc="The escrow account")]
+  #[account(2, writable, name="temp_token_account", desc="The escrow's temp token account")]
+  #[account(3, writable, name="initializer_token_account", desc="The initializer's token account of token Y")]
+  #[account(4, name="pda_account", desc="The PDA account")]
+  #[account(5, writable, name="counteroffer", desc="The counteroffer account")]
+  #[account(6, writable, name="taker", desc="The taker")]
+  #[account(7, writable, name="payment_token_account", desc="The taker's token account of token Y")]
+  #[account(8, writable, name="receive_token_account", desc="The taker's token account of token X")]
+  #[account(9, name="token_x_program", desc="The token program of token X")]
+  #[account(10, name="token_y_program", desc="The token program of token Y")]
+  #[account(11, name="token_x_mint", desc="The mint of token X")]
+  #[account(12, name="token_y_mint", desc="The mint of token Y")]
+  #[account(13, name="config", desc="The program's config account")]
+  AcceptCounteroffer,
+
+  /// Same as InitEnglishAuction, but the bids are sealed: bidders commit to a hash of their bid with CommitBid until commit_end_unix_timestamp, reveal it with RevealBid until reveal_end_unix_timestamp, and the escrow then settles to the best revealed bid with SettleAuction. Every commitment locks a deposit of token Y, which is slashed to the initializer if the bid isn't revealed in time. See sealed_auction.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// The same accounts as InitEscrow, with the initializer writable since they pay the auction's rent. The auction account, derived from the seeds `[b"sealed_auction", escrow account pubkey]`, and the system program have to be passed as well, anywhere after account 6.
+  #[account(0, signer, writable, name="initializer", desc="The account of the person initializing the escrow")]
+  #[account(1, writable, name="temp_token_account", desc="Temporary token account holding the tokens to escrow, owned by the initializer")]
+  #[account(2, name="token_to_receive_account", desc="The initializer's token account for the token they will receive")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account, it will hold all necessary info about the trade")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="config", desc="The program's config account")]
+  #[account(6, name="allowlist", desc="The mint allowlist")]
+  #[account(7, writable, name="sealed_auction", desc="The auction account")]
+  #[account(8, name="system_program", desc="The system program")]
+  InitSealedAuction {
+    /// The lowest bid the initializer accepts
+    min_bid: u64,
+    /// The amount of token Y every bidder locks with their commitment
+    deposit: u64,
+    commit_end_unix_timestamp: i64,
+    /// Has to be after commit_end_unix_timestamp. The auction ends with it.
+    reveal_end_unix_timestamp: i64,
+  },
+
+  /// Commits to a bid on a sealed-bid auction before its commit window closes. The sealed bid account and its vault are created with the bidder paying the rent, and the auction's deposit moves into the vault. Committing again replaces the commitment without another deposit.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The bidder
+  /// 1. `[writable]` The bidder's token account of token Y the deposit comes from
+  /// 2. `[]` The escrow account
+  /// 3. `[writable]` The auction account
+  /// 4. `[writable]` The sealed bid account, derived from the seeds `[b"sealed_bid", auction pubkey, bidder pubkey]`
+  /// 5. `[writable]` The sealed bid's vault, derived from the seeds `[b"sealed_bid_vault", sealed bid pubkey]`
+  /// 6. `[]` The mint of token Y
+  /// 7. `[]` The token program of token Y
+  /// 8. `[]` The system program
+  /// 9. `[]` The config account, derived from the seeds `[b"config"]`
+  ///
+  /// If the config sets a gate program, it has to be appended along with the accounts of it the gate reads. See gate.
+  #[account(0, signer, writable, name="bidder", desc="The bidder")]
+  #[account(1, writable, name="bidders_token_account", desc="The bidder's token account of token Y")]
+  #[account(2, name="escrow_account", desc="The escrow account")]
+  #[account(3, writable, name="sealed_auction", desc="The auction account")]
+  #[account(4, writable, name="sealed_bid", desc="The sealed bid account")]
+  #[account(5, writable, name="sealed_bid_vault", desc="The sealed bid's vault")]
+  #[account(6, name="token_y_mint", desc="The mint of token Y")]
+  #[account(7, name="token_y_program", desc="The token program of token Y")]
+  #[account(8, name="system_program", desc="The system program")]
+  #[account(9, name="config", desc="The program's config account")]
+  CommitBid {
+    /// See sealed_auction::commitment
+    commitment: [u8; 32],
+  },
+
+  /// Reveals a sealed bid during the reveal window. The deposit goes back to the bidder and the sealed bid and its vault are closed. If the escrow is still open and the bid is at least the minimum bid and beats the highest bid revealed so far, the bid moves into the bidder's bid account and becomes the highest bid, which outbid bidders withdraw with WithdrawBid as usual.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The bidder, who gets the rent of the sealed bid and its vault back
+  /// 1. `[writable]` The bidder's token account of token Y
+  /// 2. `[writable]` The escrow account
+  /// 3. `[writable]` The auction account
+  /// 4. `[writable]` The sealed bid account
+  /// 5. `[writable]` The sealed bid's vault
+  /// 6. `[writable]` The bid account, derived from the seeds `[b"bid", escrow account pubkey, bidder pubkey]`
+  /// 7. `[]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 8. `[]` The mint of token Y
+  /// 9. `[]` The token program of token Y
+  /// 10. `[]` The system program
+  /// 11. `[]` The rent sysvar
+  #[account(0, signer, writable, name="bidder", desc="The bidder")]
+  #[account(1, writable, name="bidders_token_account", desc="The bidder's token account of token Y")]
+  #[account(2, writable, name="escrow_account", desc="The escrow account")]
+  #[account(3, writable, name="sealed_auction", desc="The auction account")]
+  #[account(4, writable, name="sealed_bid", desc="The sealed bid account")]
+  #[account(5, writable, name="sealed_bid_vault", desc="The sealed bid's vault")]
+  #[account(6, writable, name="bid_account", desc="The bidder's bid account")]
+  #[account(7, name="pda_account", desc="The escrow's PDA")]
+  #[account(8, name="token_y_mint", desc="The mint of token Y")]
+  #[account(9, name="token_y_program", desc="The token program of token Y")]
+  #[account(10, name="system_program", desc="The system program")]
+  #[account(11, name="rent", desc="The rent sysvar")]
+  RevealBid {
+    amount: u64,
+    /// The salt the commitment was made with
+    salt: [u8; 32],
+  },
+
+  /// Slashes a sealed bid that wasn't revealed before the reveal window closed: its deposit goes to the initializer's token account recorded in the auction, and the sealed bid and its vault are closed with their rent going back to the bidder. Anyone can slash.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[writable]` The auction account
+  /// 1. `[writable]` The sealed bid account
+  /// 2. `[writable]` The bidder
+  /// 3. `[writable]` The sealed bid's vault
+  /// 4. `[writable]` The initializer's token account of token Y the auction was created with
+  /// 5. `[]` The mint of token Y
+  /// 6. `[]` The token program of token Y
+  #[account(0, writable, name="sealed_auction", desc="The auction account")]
+  #[account(1, writable, name="sealed_bid", desc="The sealed bid account")]
+  #[account(2, writable, name="bidder", desc="The bidder")]
+  #[account(3, writable, name="sealed_bid_vault", desc="The sealed bid's vault")]
+  #[account(4, writable, name="beneficiary_token_account", desc="The initializer's token account of token Y")]
+  #[account(5, name="token_y_mint", desc="The mint of token Y")]
+  #[account(6, name="token_y_program", desc="The token program of token Y")]
+  SlashBid,
+
+  /// Closes a sealed-bid auction whose escrow is closed, by settlement or otherwise, once every commitment has been revealed or slashed, and returns its rent to the initializer.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The initializer who created the auction
+  /// 1. `[]` The escrow account
+  /// 2. `[writable]` The auction account
+  #[account(0, signer, writable, name="initializer", desc="The initializer who created the auction")]
+  #[account(1, name="escrow_account", desc="The escrow account")]
+  #[account(2, writable, name="sealed_auction", desc="The auction account")]
+  CloseSealedAuction,
+
+  /// Same as InitEscrow, but the escrow is raffled: takers buy tickets at ticket_price of token Y with BuyTickets until end_unix_timestamp or until max_tickets are sold, and the raffle is then drawn with DrawRaffle and settled with SettleRaffle to the buyer of a random ticket. See raffle.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// The same accounts as InitEscrow, with the initializer writable since they pay the rent of the raffle and its vault. The raffle account, derived from the seeds `[b"raffle", escrow account pubkey]`, its vault, derived from the seeds `[b"raffle_vault", raffle pubkey]`, the mint of token Y, its token program and the system program have to be passed as well, anywhere after account 6.
+  #[account(0, signer, writable, name="initializer", desc="The account of the person initializing the escrow")]
+  #[account(1, writable, name="temp_token_account", desc="Temporary token account holding the tokens to escrow, owned by the initializer")]
+  #[account(2, name="token_to_receive_account", desc="The initializer's token account for the token they will receive")]
+  #[account(3, writable, name="escrow_account", desc="The escrow account, it will hold all necessary info about the trade")]
+  #[account(4, name="token_program", desc="The token program that owns the temp token account")]
+  #[account(5, name="config", desc="The program's config account")]
+  #[account(6, name="allowlist", desc="The mint allowlist")]
+  #[account(7, writable, name="raffle", desc="The raffle account")]
+  #[account(8, writable, name="raffle_vault", desc="The raffle's vault")]
+  #[account(9, name="token_y_mint", desc="The mint of token Y")]
+  #[account(10, name="token_y_program", desc="The token program of token Y")]
+  #[account(11, name="system_program", desc="The system program")]
+  InitRaffle {
+    /// The price of one ticket in token Y
+    ticket_price: u64,
+    max_tickets: u32,
+    end_unix_timestamp: i64,
+    /// The Switchboard VRF account to draw the raffle with, whose authority has to be the raffle. The default pubkey (all zeroes) draws it with DrawRaffle instead. See raffle.
+    vrf: Pubkey,
+  },
+
+  /// Buys count tickets of a raffle, numbered from the raffle's tickets sold so far on. Their price moves into the raffle's vault, and the ticket account recording them is created with the buyer paying the rent.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The buyer
+  /// 1. `[writable]` The buyer's token account of token Y
+  /// 2. `[writable]` The escrow account
+  /// 3. `[writable]` The raffle account
+  /// 4. `[writable]` The ticket account, derived from the seeds `[b"ticket", raffle pubkey, tickets sold so far as little-endian u32]`
+  /// 5. `[writable]` The raffle's vault
+  /// 6. `[]` The mint of token Y
+  /// 7. `[]` The token program of token Y
+  /// 8. `[]` The system program
+  /// 9. `[]` The config account, derived from the seeds `[b"config"]`
+  ///
+  /// If the config sets a gate program, it has to be appended along with the accounts of it the gate reads. See gate.
+  #[account(0, signer, writable, name="buyer", desc="The buyer")]
+  #[account(1, writable, name="buyers_token_account", desc="The buyer's token account of token Y")]
+  #[account(2, writable, name="escrow_account", desc="The escrow account")]
+  #[account(3, writable, name="raffle", desc="The raffle account")]
+  #[account(4, writable, name="ticket", desc="The ticket account")]
+  #[account(5, writable, name="raffle_vault", desc="The raffle's vault")]
+  #[account(6, name="token_y_mint", desc="The mint of token Y")]
+  #[account(7, name="token_y_program", desc="The token program of token Y")]
+  #[account(8, name="system_program", desc="The system program")]
+  #[account(9, name="config", desc="The program's config account")]
+  BuyTickets {
+    count: u32,
+  },
+
+  /// Commits a raffle that has ended or sold out to the slot whose hash picks its winner, raffle::DRAW_DELAY_SLOTS from now. A raffle whose draw slot has dropped out of the SlotHashes sysvar before it was settled can be drawn again. Anyone can draw. Raffles drawn with VRF use RequestRaffleRandomness instead.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[writable]` The raffle account
+  #[account(0, writable, name="raffle", desc="The raffle account")]
+  DrawRaffle,
+
+  /// Settles a drawn raffle once its draw slot has passed: the buyer of the winning ticket gets the escrowed tokens and the initializer the ticket sales, and the escrow, the raffle, its vault and the winning ticket account are closed. Anyone can settle, and no protocol fee is charged.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[writable]` The raffle account
+  /// 1. `[writable]` The escrow account
+  /// 2. `[writable]` The escrow's temp token account
+  /// 3. `[]` The PDA account, derived from the seeds `[b"escrow", escrow account pubkey]`
+  /// 4. `[writable]` The raffle's vault
+  /// 5. `[writable]` The initializer's token account of token Y the raffle was created with
+  /// 6. `[writable]` The escrow's initializer, who gets the rent of the escrow, the raffle and its vault back
+  /// 7. `[writable]` The ticket account holding the winning ticket
+  /// 8. `[writable]` The buyer of the winning ticket, who gets its rent back
+  /// 9. `[writable]` The winner's token account of token X
+  /// 10. `[]` The SlotHashes sysvar, which isn't read for raffles drawn with VRF
+  /// 11. `[]` The token program of token X
+  /// 12. `[]` The token program of token Y
+  /// 13. `[]` The mint of token X
+  /// 14. `[]` The mint of token Y
+  #[account(0, writable, name="raffle", desc="The raffle account")]
+  #[account(1, writable, name="escrow_account", desc="The escrow account")]
+  #[account(2, writable, name="temp_token_account", desc="The escrow's temp token account")]
+  #[account(3, name="pda_account", desc="The escrow's PDA")]
+  #[account(4, writable, name="raffle_vault", desc="The raffle's vault")]
+  #[account(5, writable, name="proceeds_token_account", desc="The initializer's token account of token Y")]
+  #[account(6, writable, name="initializer", desc="The escrow's initializer")]
+  #[account(7, writable, name="ticket", desc="The ticket account holding the winning ticket")]
+  #[account(8, writable, name="winner", desc="The buyer of the winning ticket")]
+  #[account(9, writable, name="winner_token_account", desc="The winner's token account of token X")]
+  #[account(10, name="slot_hashes", desc="The SlotHashes sysvar")]
+  #[account(11, name="token_x_program", desc="The token program of token X")]
+  #[account(12, name="token_y_program", desc="The token program of token Y")]
+  #[account(13, name="token_x_mint", desc="The mint of token X")]
+  #[account(14, name="token_y_mint", desc="The mint of token Y")]
+  SettleRaffle,
+
+  /// Closes the raffle of an escrow that was closed without selling a ticket, e.g. cancelled, and returns the rent of the raffle and its vault to the initializer who created it.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The initializer who created the raffle
+  /// 1. `[]` The escrow account
+  /// 2. `[writable]` The raffle account
+  /// 3. `[writable]` The raffle's vault
+  /// 4. `[writable]` The initializer's token account of token Y the raffle was created with
+  /// 5. `[]` The mint of token Y
+  /// 6. `[]` The token program of token Y
+  #[account(0, signer, writable, name="initializer", desc="The initializer who created the raffle")]
+  #[account(1, name="escrow_account", desc="The escrow account")]
+  #[account(2, writable, name="raffle", desc="The raffle account")]
+  #[account(3, writable, name="raffle_vault", desc="The raffle's vault")]
+  #[account(4, writable, name="proceeds_token_account", desc="The initializer's token account of token Y")]
+  #[account(5, name="token_y_mint", desc="The mint of token Y")]
+  #[account(6, name="token_y_program", desc="The token program of token Y")]
+  CloseRaffle,
+
+  /// Closes a losing ticket account once its raffle has been settled and returns its rent to the buyer.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The buyer
+  /// 1. `[writable]` The ticket account
+  /// 2. `[]` The raffle account
+  #[account(0, signer, writable, name="buyer", desc="The buyer")]
+  #[account(1, writable, name="ticket", desc="The ticket account")]
+  #[account(2, name="raffle", desc="The raffle account")]
+  CloseTicket,
+
+  /// Requests randomness for a raffle drawn with Switchboard VRF once it has ended or sold out, with the raffle signing as the VRF's authority. Anyone can request, paying the oracle's fee. A request that went unanswered for raffle::MAX_ENTRIES slots can be made again.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[writable]` The raffle account
+  /// 1. `[writable]` The VRF account the raffle was created with
+  /// 2. `[writable]` The VRF's oracle queue
+  /// 3. `[]` The oracle queue's authority
+  /// 4. `[]` The oracle queue's data buffer
+  /// 5. `[writable]` The VRF's permission account
+  /// 6. `[writable]` The VRF's escrow
+  /// 7. `[writable]` The payer's wrapped SOL token account, which pays the oracle's fee
+  /// 8. `[signer]` The payer
+  /// 9. `[]` The RecentBlockhashes sysvar
+  /// 10. `[]` The Switchboard program state
+  /// 11. `[]` The token program
+  /// 12. `[]` The Switchboard program
+  #[account(0, writable, name="raffle", desc="The raffle account")]
+  #[account(1, writable, name="vrf", desc="The VRF account the raffle was created with")]
+  #[account(2, writable, name="oracle_queue", desc="The VRF's oracle queue")]
+  #[account(3, name="queue_authority", desc="The oracle queue's authority")]
+  #[account(4, name="data_buffer", desc="The oracle queue's data buffer")]
+  #[account(5, writable, name="permission", desc="The VRF's permission account")]
+  #[account(6, writable, name="vrf_escrow", desc="The VRF's escrow")]
+  #[account(7, writable, name="payer_wallet", desc="The payer's wrapped SOL token account")]
+  #[account(8, signer, name="payer", desc="The payer")]
+  #[account(9, name="recent_blockhashes", desc="The RecentBlockhashes sysvar")]
+  #[account(10, name="program_state", desc="The Switchboard program state")]
+  #[account(11, name="token_program", desc="The token program")]
+  #[account(12, name="switchboard_program", desc="The Switchboard program")]
+  RequestRaffleRandomness {
+    permission_bump: u8,
+    state_bump: u8,
+  },
+
+  /// Stores the verified result of a raffle's VRF request in the raffle, after which it can be settled with SettleRaffle. Meant as the VRF's callback, but anyone can send it.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[writable]` The raffle account
+  /// 1. `[]` The VRF account the raffle was created with
+  #[account(0, writable, name="raffle", desc="The raffle account")]
+  #[account(1, name="vrf", desc="The VRF account the raffle was created with")]
+  ConsumeRaffleRandomness,
+
+  /// Initializes an escrow pool, which holds many offers of token X for token Y by the same maker in one account. See escrow_pool.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The maker, who pays the rent of the vault
+  /// 1. `[writable]` The pool account, created beforehand with the program as its owner and `escrow_pool::EscrowPool::space(capacity)` bytes, and rent exempt
+  /// 2. `[writable]` The pool's vault, derived from the seeds `[b"escrow_pool_vault", pool pubkey]`
+  /// 3. `[]` The maker's token account for token Y, which every payment goes to
+  /// 4. `[]` The mint of token X
+  /// 5. `[]` The mint of token Y
+  /// 6. `[]` The token program of token X
+  /// 7. `[]` The system program
+  /// 8. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 9. `[]` The mint allowlist, derived from the seeds `[b"allowlist"]`. Only read if the config enables it
+  #[account(0, signer, writable, name="maker", desc="The maker")]
+  #[account(1, writable, name="pool", desc="The pool account")]
+  #[account(2, writable, name="vault", desc="The pool's vault")]
+  #[account(3, name="makers_token_to_receive_account", desc="The maker's token account for token Y")]
+  #[account(4, name="token_x_mint", desc="The mint of token X")]
+  #[account(5, name="token_y_mint", desc="The mint of token Y")]
+  #[account(6, name="token_program", desc="The token program of token X")]
+  #[account(7, name="system_program", desc="The system program")]
+  #[account(8, name="config", desc="The program's config account")]
+  #[account(9, name="allowlist", desc="The mint allowlist")]
+  InitEscrowPool,
+
+  /// Posts an offer of amount_x of token X for amount_y of token Y to an escrow pool, moving the token X into the pool's vault. The number of the entry it was posted to is set as the return data, as a little-endian u32.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The maker
+  /// 1. `[writable]` The maker's token account for token X
+  /// 2. `[writable]` The pool account
+  /// 3. `[writable]` The pool's vault
+  /// 4. `[]` The mint of token X
+  /// 5. `[]` The token program of token X
+  /// 6. `[]` The config account, derived from the seeds `[b"config"]`
+  #[account(0, signer, name="maker", desc="The maker")]
+  #[account(1, writable, name="makers_token_account", desc="The maker's token account for token X")]
+  #[account(2, writable, name="pool", desc="The pool account")]
+  #[account(3, writable, name="vault", desc="The pool's vault")]
+  #[account(4, name="token_x_mint", desc="The mint of token X")]
+  #[account(5, name="token_program", desc="The token program of token X")]
+  #[account(6, name="config", desc="The program's config account")]
+  PostPoolEntry {
+    amount_x: u64,
+    amount_y: u64,
+    /// 0 means the config's default expiry applies, like for InitEscrow
+    expiry_unix_timestamp: i64,
+  },
+
+  /// Takes an entry of an escrow pool whole. Like Exchange, amount_x has to be the amount of token X the entry offers, which keeps a taker from getting a different offer that was posted to the same entry, and the taker pays the protocol fee on top of the entry's amount_y, up to max_payment in total.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The taker
+  /// 1. `[writable]` The taker's token account for token Y
+  /// 2. `[writable]` The taker's token account for token X
+  /// 3. `[writable]` The pool account
+  /// 4. `[writable]` The pool's vault
+  /// 5. `[]` The pool's authority, derived from the seeds `[b"escrow_pool", pool pubkey]`
+  /// 6. `[writable]` The maker's token account for token Y the pool was created with
+  /// 7. `[]` The mint of token X
+  /// 8. `[]` The mint of token Y
+  /// 9. `[]` The token program of token X
+  /// 10. `[]` The token program of token Y
+  /// 11. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 12. `[writable]` The fee vault, see Exchange. Only needed if there is a fee
+  ///
+  /// If the config sets a gate program, it has to be appended along with the accounts of it the gate reads. See gate.
+  #[account(0, signer, name="taker", desc="The taker")]
+  #[account(1, writable, name="takers_sending_token_account", desc="The taker's token account for token Y")]
+  #[account(2, writable, name="takers_token_to_receive_account", desc="The taker's token account for token X")]
+  #[account(3, writable, name="pool", desc="The pool account")]
+  #[account(4, writable, name="vault", desc="The pool's vault")]
+  #[account(5, name="pool_authority", desc="The pool's authority")]
+  #[account(6, writable, name="makers_token_to_receive_account", desc="The maker's token account for token Y")]
+  #[account(7, name="token_x_mint", desc="The mint of token X")]
+  #[account(8, name="token_y_mint", desc="The mint of token Y")]
+  #[account(9, name="token_program", desc="The token program of token X")]
+  #[account(10, name="token_y_program", desc="The token program of token Y")]
+  #[account(11, name="config", desc="The program's config account")]
+  #[account(12, optional, writable, name="fee_vault", desc="The fee vault, only needed if there is a fee")]
+  TakePoolEntry {
+    index: u32,
+    /// The amount of token X the entry is expected to offer
+    amount_x: u64,
+    max_payment: u64,
+  },
+
+  /// Cancels an entry of an escrow pool and returns its token X to the maker. Expired entries have to be cancelled this way too.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The maker
+  /// 1. `[writable]` The maker's token account for token X
+  /// 2. `[writable]` The pool account
+  /// 3. `[writable]` The pool's vault
+  /// 4. `[]` The pool's authority, derived from the seeds `[b"escrow_pool", pool pubkey]`
+  /// 5. `[]` The mint of token X
+  /// 6. `[]` The token program of token X
+  #[account(0, signer, name="maker", desc="The maker")]
+  #[account(1, writable, name="makers_token_account", desc="The maker's token account for token X")]
+  #[account(2, writable, name="pool", desc="The pool account")]
+  #[account(3, writable, name="vault", desc="The pool's vault")]
+  #[account(4, name="pool_authority", desc="The pool's authority")]
+  #[account(5, name="token_x_mint", desc="The mint of token X")]
+  #[account(6, name="token_program", desc="The token program of token X")]
+  CancelPoolEntry {
+    index: u32,
+  },
+
+  /// Closes an escrow pool without open entries, returning the rent of the pool and its vault to the maker
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The maker
+  /// 1. `[writable]` The maker's token account for token X, which gets anything left in the vault
+  /// 2. `[writable]` The pool account
+  /// 3. `[writable]` The pool's vault
+  /// 4. `[]` The pool's authority, derived from the seeds `[b"escrow_pool", pool pubkey]`
+  /// 5. `[]` The mint of token X
+  /// 6. `[]` The token program of token X
+  #[account(0, signer, writable, name="maker", desc="The maker")]
+  #[account(1, writable, name="makers_token_account", desc="The maker's token account for token X")]
+  #[account(2, writable, name="pool", desc="The pool account")]
+  #[account(3, writable, name="vault", desc="The pool's vault")]
+  #[account(4, name="pool_authority", desc="The pool's authority")]
+  #[account(5, name="token_x_mint", desc="The mint of token X")]
+  #[account(6, name="token_program", desc="The token program of token X")]
+  CloseEscrowPool,
+
+  /// Sets up a concurrent merkle tree of the spl-account-compression program for compressed escrows to be listed in, see compressed_escrow. The tree's config becomes its authority.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The creator, who pays for the config
+  /// 1. `[writable]` The merkle tree, created zeroed and owned by the compression program, sized for max_depth, max_buffer_size and canopy_depth
+  /// 2. `[writable]` The tree's config, derived from the seeds `[b"compressed_tree", merkle tree pubkey]`
+  /// 3. `[]` The compression program
+  /// 4. `[]` The noop program
+  /// 5. `[]` The system program
+  #[account(0, signer, writable, name="creator", desc="The creator")]
+  #[account(1, writable, name="merkle_tree", desc="The merkle tree")]
+  #[account(2, writable, name="tree_config", desc="The tree's config")]
+  #[account(3, name="compression_program", desc="The compression program")]
+  #[account(4, name="noop_program", desc="The noop program")]
+  #[account(5, name="system_program", desc="The system program")]
+  InitCompressedTree {
+    /// At most compressed_escrow::MAX_DEPTH
+    max_depth: u32,
+    max_buffer_size: u32,
+    /// How many levels of the tree above the leaves are kept in the account, which saves passing that many proof nodes
+    canopy_depth: u32,
+  },
+
+  /// Lists a compressed escrow of amount_x of token X for amount_y of token Y by appending its leaf to a tree. The token X stays in the initializer's account, and the tree's config is approved for another amount_x of it.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The initializer
+  /// 1. `[writable]` The initializer's token account for token X
+  /// 2. `[]` The initializer's token account for token Y
+  /// 3. `[writable]` The tree's config, derived from the seeds `[b"compressed_tree", merkle tree pubkey]`
+  /// 4. `[writable]` The merkle tree
+  /// 5. `[]` The mint of token X
+  /// 6. `[]` The mint of token Y
+  /// 7. `[]` The token program of token X
+  /// 8. `[]` The compression program
+  /// 9. `[]` The noop program
+  /// 10. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 11. `[]` The mint allowlist, derived from the seeds `[b"allowlist"]`. Only read if the config enables it
+  #[account(0, signer, name="initializer", desc="The initializer")]
+  #[account(1, writable, name="initializers_token_account", desc="The initializer's token account for token X")]
+  #[account(2, name="initializers_token_to_receive_account", desc="The initializer's token account for token Y")]
+  #[account(3, writable, name="tree_config", desc="The tree's config")]
+  #[account(4, writable, name="merkle_tree", desc="The merkle tree")]
+  #[account(5, name="token_x_mint", desc="The mint of token X")]
+  #[account(6, name="token_y_mint", desc="The mint of token Y")]
+  #[account(7, name="token_program", desc="The token program of token X")]
+  #[account(8, name="compression_program", desc="The compression program")]
+  #[account(9, name="noop_program", desc="The noop program")]
+  #[account(10, name="config", desc="The program's config account")]
+  #[account(11, name="allowlist", desc="The mint allowlist")]
+  InitCompressedEscrow {
+    amount_x: u64,
+    amount_y: u64,
+    /// 0 means the config's default expiry applies, like for InitEscrow
+    expiry_unix_timestamp: i64,
+  },
+
+  /// Exchanges a compressed escrow as a whole. The escrow is passed in as it was emitted when it was listed, and its leaf is cleared against root, a recent root of the tree, with the proof passed as accounts. The taker pays the protocol fee on top of amount_y, up to max_payment in total.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The taker
+  /// 1. `[writable]` The taker's token account for token Y
+  /// 2. `[writable]` The taker's token account for token X
+  /// 3. `[writable]` The initializer's token account for token X
+  /// 4. `[writable]` The initializer's token account for token Y
+  /// 5. `[]` The tree's config, derived from the seeds `[b"compressed_tree", merkle tree pubkey]`
+  /// 6. `[writable]` The merkle tree
+  /// 7. `[]` The mint of token X
+  /// 8. `[]` The mint of token Y
+  /// 9. `[]` The token program of token X
+  /// 10. `[]` The token program of token Y
+  /// 11. `[]` The compression program
+  /// 12. `[]` The noop program
+  /// 13. `[]` The config account, derived from the seeds `[b"config"]`
+  /// 14. `[writable]` The fee vault, see Exchange. Only needed if there is a fee
+  ///
+  /// The proof nodes follow, as many as the tree's config asks for. If the config sets a gate program, it has to be appended along with the accounts of it the gate reads. See gate.
+  #[account(0, signer, name="taker", desc="The taker")]
+  #[account(1, writable, name="takers_sending_token_account", desc="The taker's token account for token Y")]
+  #[account(2, writable, name="takers_token_to_receive_account", desc="The taker's token account for token X")]
+  #[account(3, writable, name="initializers_token_account", desc="The initializer's token account for token X")]
+  #[account(4, writable, name="initializers_token_to_receive_account", desc="The initializer's token account for token Y")]
+  #[account(5, name="tree_config", desc="The tree's config")]
+  #[account(6, writable, name="merkle_tree", desc="The merkle tree")]
+  #[account(7, name="token_x_mint", desc="The mint of token X")]
+  #[account(8, name="token_y_mint", desc="The mint of token Y")]
+  #[account(9, name="token_program", desc="The token program of token X")]
+  #[account(10, name="token_y_program", desc="The token program of token Y")]
+  #[account(11, name="compression_program", desc="The compression program")]
+  #[account(12, name="noop_program", desc="The noop program")]
+  #[account(13, name="config", desc="The program's config account")]
+  #[account(14, optional, writable, name="fee_vault", desc="The fee vault, only needed if there is a fee")]
+  ExchangeCompressed {
+    escrow: CompressedEscrow,
+    root: [u8; 32],
+    max_payment: u64,
+  },
+
+  /// Cancels a compressed escrow by clearing its leaf, and lowers the tree config's allowance of the initializer's token X by amount_x. Expired escrows have to be cancelled this way too.
+  ///
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer]` The initializer
+  /// 1. `[writable]` The initializer's token account for token X
+  /// 2. `[]` The tree's config, derived from the seeds `[b"compressed_tree", merkle tree pubkey]`
+  /// 3. `[writable]` The merkle tree
+  /// 4. `[]` The token program of token X
+  /// 5. `[]` The compression program
+  /// 6. `[]` The noop program
+  ///
+  /// The proof nodes follow, as many as the tree's config asks for.
+  #[account(0, signer, name="initializer", desc="The initializer")]
+  #[account(1, writable, name="initializers_token_account", desc="The initializer's token account for token X")]
+  #[account(2, name="tree_config", desc="The tree's config")]
+  #[account(3, writable, name="merkle_tree", desc="The merkle tree")]
+  #[account(4, name="token_program", desc="The token program of token X")]
+  #[account(5, name="compression_program", desc="The compression program")]
+  #[account(6, name="noop_program", desc="The noop program")]
+  CancelCompressed {
+    escrow: CompressedEscrow,
+    root: [u8; 32],
+  },
+
+  /// Grows a V1 escrow account to the current layout in place with realloc, see state::VersionedEscrow. The V1 fields are moved to where the current layout has them, the mints are read from the token accounts, and the fields V1 lacked, like the expiry and the metadata, start out empty. Anyone can send it, paying for the extra rent.
+  ///
+  /// The temp token account stays with the legacy PDA, so the escrow can't be exchanged or cancelled until MigrateEscrow hands it to the escrow's own PDA.
+  ///
+  /// Accounts expected:
+  ///
+  /// 0. `[signer, writable]` The account paying for the rent of the larger escrow account
+  /// 1. `[writable]` The escrow account
+  /// 2. `[]` The escrow's temp token account
+  /// 3. `[]` The initializer's token account that will receive tokens
+  /// 4. `[]` The system program
+  #[account(0, signer, writable, name="payer", desc="The account paying for the rent of the larger escrow account")]
+  #[account(1, writable, name="escrow_account", desc="The escrow account")]
+  #[account(2, name="temp_token_account", desc="The escrow's temp token account")]
+  #[account(3, name="token_to_receive_account", desc="The initializer's token account that will receive tokens")]
+  #[account(4, name="system_program", desc="The system program")]
+  UpgradeEscrowAccount,
+}
+
+/// Anchor-generated clients start the instruction data with an 8 byte discriminator, the first 8 bytes of sha256("global:<instruction name in snake case>"), instead of our 1 byte tag. The arguments that follow are Borsh encoded, which matches our own layout, so only the discriminator needs translating. The values are hardcoded to avoid hashing on-chain.
+const ANCHOR_DISCRIMINATORS: [([u8; 8], u8); 91] = [
+    ([70, 46, 40, 23, 6, 11, 81, 139], 0),      // init_escrow
+    ([47, 3, 27, 97, 215, 236, 219, 144], 1),   // exchange
+    ([232, 219, 223, 41, 219, 236, 220, 190], 2), // cancel
+    ([125, 185, 48, 75, 0, 71, 93, 98], 3),     // reclaim_expired
+    ([17, 123, 234, 123, 133, 126, 74, 235], 4), // init_nft_escrow
+    ([141, 17, 88, 209, 137, 84, 89, 235], 5),  // init_fee_vault
+    ([208, 127, 21, 1, 194, 190, 196, 70], 6),  // initialize_config
+    ([211, 22, 221, 251, 74, 121, 193, 47], 7), // pause
+    ([169, 144, 4, 38, 10, 141, 188, 255], 8),  // unpause
+    ([114, 83, 166, 247, 86, 17, 220, 147], 9), // add_allowed_mint
+    ([53, 133, 46, 51, 25, 228, 27, 73], 10),   // remove_allowed_mint
+    ([204, 179, 188, 165, 225, 141, 118, 243], 11), // set_allowlist_enabled
+    ([252, 228, 127, 1, 60, 43, 54, 28], 12),   // update_escrow
+    ([11, 249, 209, 235, 199, 26, 99, 76], 13), // init_dutch_auction
+    ([91, 171, 201, 83, 217, 3, 204, 163], 14), // init_english_auction
+    ([238, 77, 148, 91, 200, 151, 92, 146], 15), // place_bid
+    ([246, 196, 183, 98, 222, 139, 46, 133], 16), // settle_auction
+    ([110, 53, 157, 195, 147, 100, 110, 73], 17), // withdraw_bid
+    ([199, 121, 218, 64, 192, 48, 195, 136], 18), // init_oracle_escrow
+    ([244, 6, 184, 245, 56, 188, 95, 68], 19),  // init_delegated_escrow
+    ([65, 150, 70, 216, 133, 6, 107, 4], 20),   // accept
+    ([175, 42, 185, 87, 144, 131, 102, 212], 21), // settle
+    ([200, 243, 4, 34, 150, 154, 148, 93], 22), // set_approvers
+    ([110, 173, 58, 175, 146, 128, 138, 255], 23), // approve_release
+    ([99, 173, 31, 157, 77, 123, 176, 193], 24), // init_vesting_escrow
+    ([208, 190, 166, 114, 203, 225, 140, 208], 25), // claim_vested
+    ([129, 203, 110, 25, 135, 44, 136, 208], 26), // init_stream_escrow
+    ([183, 18, 70, 156, 148, 109, 161, 34], 27), // withdraw
+    ([111, 194, 75, 71, 16, 71, 63, 211], 28),  // match_escrows
+    ([33, 253, 15, 116, 89, 25, 127, 236], 29), // init_market
+    ([51, 194, 155, 175, 109, 130, 96, 106], 30), // place_order
+    ([95, 129, 237, 240, 8, 49, 223, 132], 31), // cancel_order
+    ([20, 67, 44, 130, 171, 124, 221, 106], 32), // consume_orders
+    ([131, 22, 4, 103, 24, 94, 163, 239], 33),  // init_registry
+    ([107, 28, 24, 254, 214, 63, 17, 2], 34),   // prune_registry
+    ([65, 111, 186, 119, 58, 11, 81, 209], 35), // migrate_escrow
+    ([206, 8, 171, 68, 253, 194, 209, 21], 36), // close_stale_escrow
+    ([237, 216, 159, 100, 172, 119, 114, 2], 37), // get_escrow
+    ([37, 216, 51, 235, 68, 149, 201, 10], 38), // init_rate_escrow
+    ([18, 154, 24, 18, 237, 214, 19, 80], 39),  // set_fee
+    ([203, 189, 229, 174, 240, 190, 158, 110], 40), // init_stats
+    ([92, 99, 244, 209, 28, 65, 213, 157], 41), // reserve
+    ([17, 235, 179, 142, 123, 53, 194, 104], 42), // set_cancel_penalty
+    ([51, 139, 128, 29, 175, 240, 164, 13], 43), // set_gate_program
+    ([42, 79, 51, 36, 170, 27, 216, 218], 44),  // init_collection_offer
+    ([244, 149, 206, 235, 225, 223, 253, 242], 45), // fill_collection_offer
+    ([164, 173, 235, 42, 110, 205, 36, 126], 46), // init_lamport_escrow
+    ([110, 40, 123, 194, 116, 225, 177, 111], 47), // init_buy_escrow
+    ([73, 205, 102, 177, 241, 200, 145, 80], 48), // abort
+    ([18, 209, 27, 175, 87, 13, 213, 188], 49), // extend_deadline
+    ([160, 189, 97, 86, 209, 226, 190, 104], 50), // set_max_expiry
+    ([65, 227, 52, 119, 95, 174, 237, 55], 51), // transfer_escrow
+    ([203, 191, 39, 138, 40, 158, 89, 243], 52), // settle_signed_order
+    ([177, 72, 116, 50, 24, 90, 149, 9], 53),   // set_payment_option
+    ([184, 160, 218, 168, 41, 5, 46, 54], 54),  // add_stablecoin
+    ([54, 94, 28, 239, 237, 124, 255, 28], 55), // remove_stablecoin
+    ([79, 196, 139, 62, 52, 254, 61, 39], 56),  // set_usd_price
+    ([246, 132, 246, 62, 209, 117, 143, 229], 57), // init_nft_swap
+    ([98, 219, 142, 204, 237, 131, 110, 215], 58), // init_ring
+    ([121, 83, 177, 216, 66, 235, 70, 187], 59), // deposit_ring_leg
+    ([208, 72, 124, 220, 244, 76, 126, 31], 60), // execute_ring
+    ([234, 82, 45, 7, 81, 111, 209, 11], 61),   // withdraw_ring_leg
+    ([42, 47, 49, 65, 241, 134, 138, 134], 62), // close_ring
+    ([68, 231, 88, 224, 13, 116, 27, 84], 63), // post_quote
+    ([129, 61, 5, 81, 46, 253, 210, 152], 64), // accept_quote
+    ([41, 51, 237, 236, 153, 159, 210, 185], 65), // cancel_quote
+    ([254, 176, 196, 139, 131, 227, 74, 240], 66), // propose_counteroffer
+    ([12, 24, 73, 178, 62, 229, 106, 37], 67),  // accept_counteroffer
+    ([72, 127, 83, 87, 172, 178, 127, 233], 68), // init_sealed_auction
+    ([149, 237, 198, 113, 53, 66, 70, 76], 69), // commit_bid
+    ([48, 73, 28, 255, 202, 126, 236, 196], 70), // reveal_bid
+    ([251, 123, 171, 238, 218, 50, 251, 93], 71), // slash_bid
+    ([74, 123, 152, 60, 99, 20, 147, 149], 72), // close_sealed_auction
+    ([202, 48, 109, 16, 128, 29, 52, 109], 73), // init_raffle
+    ([48, 16, 122, 137, 24, 214, 198, 58], 74), // buy_tickets
+    ([117, 70, 132, 142, 127, 14, 224, 160], 75), // draw_raffle
+    ([136, 98, 44, 133, 40, 36, 190, 185], 76), // settle_raffle
+    ([220, 129, 128, 51, 70, 66, 209, 124], 77), // close_raffle
+    ([66, 209, 114, 197, 75, 27, 182, 117], 78), // close_ticket
+    ([113, 36, 171, 69, 9, 3, 88, 125], 79), // request_raffle_randomness
+    ([24, 88, 8, 180, 172, 14, 218, 82], 80), // consume_raffle_randomness
+    ([154, 0, 197, 100, 66, 127, 81, 255], 81), // init_escrow_pool
+    ([243, 145, 89, 205, 36, 253, 141, 229], 82), // post_pool_entry
+    ([165, 224, 144, 24, 177, 251, 171, 79], 83), // take_pool_entry
+    ([10, 245, 153, 177, 39, 152, 23, 57], 84), // cancel_pool_entry
+    ([89, 232, 7, 26, 44, 207, 227, 248], 85), // close_escrow_pool
+    ([197, 166, 237, 57, 246, 56, 85, 8], 86), // init_compressed_tree
+    ([59, 174, 75, 158, 100, 250, 154, 49], 87), // init_compressed_escrow
+    ([183, 193, 225, 241, 130, 192, 170, 228], 88), // exchange_compressed
+    ([171, 237, 45, 215, 10, 178, 243, 114], 89), // cancel_compressed
+    ([195, 27, 121, 146, 37, 43, 34, 179], 90), // upgrade_escrow_account
+];
+
+  /// Below:
+  /// 1. unpack expects a reference (opens new window)to a slice of u8. 
+  /// 2. It looks at the first byte (=tag) to determine how to decode (using match (opens new window)) the rest (=rest) of the slice. 
+  /// 3. unpack_amount decodes the rest to get a u64 representing the amount. 
+  /// Summary: choose which instruction to build and build/return that instruction.
+
+impl<'a> EscrowInstruction<'a> {
+    /// Unpacks a byte buffer into a [EscrowInstruction](enum.EscrowInstruction.html). Metadata, memos and taker proofs are borrowed from the buffer rather than copied.
+    pub fn unpack(input: &'a [u8]) -> Result<Self, ProgramError> {
+        let (tag, rest) = match Self::unpack_anchor_discriminator(input) {
+            Some((tag, rest)) => (tag, rest),
+            None => input.split_first().ok_or(InvalidInstruction)?,
+        };
+
+        Ok(match tag {
+            0 => Self::InitEscrow {
+                amount: Self::unpack_amount(rest)?,
+                expiry_unix_timestamp: Self::unpack_expiry(rest)?,
+                allowed_taker: Self::unpack_allowed_taker(rest)?,
+                deposit_amount: Self::unpack_deposit_amount(rest)?,
+                dispute_window_seconds: Self::unpack_dispute_window(rest)?,
+                metadata: Cow::Borrowed(Self::unpack_bytes(rest.get(64..).unwrap_or_default())?),
+                escrow_id: Self::unpack_escrow_id(rest.get(64..).unwrap_or_default())?,
+                reservation_deposit: Self::unpack_reservation_deposit(rest.get(64..).unwrap_or_default())?,
+                taker_merkle_root: Self::unpack_taker_merkle_root(rest.get(64..).unwrap_or_default())?,
+            },
+            1 => {
+                let (relayer, relayer_tip) = Self::unpack_relayer(rest.get(48..).unwrap_or_default())?;
+                Self::Exchange {
+                    amount: Self::unpack_amount(rest)?,
+                    max_payment: Self::unpack_max_payment(rest)?,
+                    referrer_token_account: Self::unpack_referrer(rest)?,
+                    memo: Cow::Borrowed(Self::unpack_memo(rest.get(48..).unwrap_or_default())?),
+                    taker_proof: Cow::Borrowed(Self::unpack_taker_proof(rest.get(48..).unwrap_or_default())?),
+                    relayer,
+                    relayer_tip,
+                }
+            }
+            2 => Self::Cancel { memo: Cow::Borrowed(Self::unpack_memo(rest)?) },
+            3 => Self::ReclaimExpired,
+            4 => Self::InitNftEscrow {
+                amount: Self::unpack_amount(rest)?,
+                expiry_unix_timestamp: Self::unpack_expiry(rest)?,
+                allowed_taker: Self::unpack_allowed_taker(rest)?,
+            },
+            5 => Self::InitFeeVault,
+            6 => Self::InitializeConfig {
+                fee_bps: Self::unpack_fee_bps(rest)?,
+                default_expiry_seconds: Self::unpack_default_expiry(rest)?,
+                referral_share_bps: Self::unpack_referral_share(rest)?,
+            },
+            7 => Self::Pause,
+            8 => Self::Unpause,
+            9 => Self::AddAllowedMint { mint: Self::unpack_pubkey(rest)? },
+            10 => Self::RemoveAllowedMint { mint: Self::unpack_pubkey(rest)? },
+            11 => Self::SetAllowlistEnabled { enabled: Self::unpack_bool(rest)? },
+            12 => Self::UpdateEscrow { expected_amount: Self::unpack_amount(rest)? },
+            13 => Self::InitDutchAuction {
+                start_amount: Self::unpack_amount(rest)?,
+                floor_amount: Self::unpack_amount(rest.get(8..).unwrap_or_default())?,
+                auction_end_unix_timestamp: Self::unpack_i64(rest.get(16..).unwrap_or_default())?,
+                expiry_unix_timestamp: Self::unpack_i64(rest.get(24..).unwrap_or_default())?,
+            },
+            14 => Self::InitEnglishAuction {
+                min_bid: Self::unpack_amount(rest)?,
+                auction_end_unix_timestamp: Self::unpack_i64(rest.get(8..).unwrap_or_default())?,
+            },
+            15 => Self::PlaceBid { amount: Self::unpack_amount(rest)? },
+            16 => Self::SettleAuction,
+            17 => Self::WithdrawBid,
+            18 => Self::InitOracleEscrow {
+                premium_bps: Self::unpack_premium_bps(rest)?,
+                expiry_unix_timestamp: Self::unpack_i64(rest.get(2..).unwrap_or_default())?,
+            },
+            19 => Self::InitDelegatedEscrow {
+                amount_x: Self::unpack_amount(rest)?,
+                amount: Self::unpack_amount(rest.get(8..).unwrap_or_default())?,
+                expiry_unix_timestamp: Self::unpack_i64(rest.get(16..).unwrap_or_default())?,
+                allowed_taker: Self::unpack_pubkey(rest.get(24..).unwrap_or_default())?,
+            },
+            20 => Self::Accept { max_payment: Self::unpack_amount(rest)? },
+            21 => Self::Settle,
+            22 => Self::SetApprovers { threshold: *rest.first().ok_or(InvalidInstruction)? },
+            23 => Self::ApproveRelease,
+            24 => Self::InitVestingEscrow {
+                amount: Self::unpack_amount(rest)?,
+                expiry_unix_timestamp: Self::unpack_i64(rest.get(8..).unwrap_or_default())?,
+                vesting_period_seconds: Self::unpack_i64(rest.get(16..).unwrap_or_default())?,
+                vesting_periods: rest
+                    .get(24..26)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u16::from_le_bytes)
+                    .ok_or(InvalidInstruction)?,
+            },
+            25 => Self::ClaimVested,
+            26 => Self::InitStreamEscrow {
+                recipient: Self::unpack_pubkey(rest)?,
+                stream_end_unix_timestamp: Self::unpack_i64(rest.get(32..).unwrap_or_default())?,
+            },
+            27 => Self::Withdraw,
+            28 => Self::MatchEscrows,
+            29 => Self::InitMarket,
+            30 => Self::PlaceOrder,
+            31 => Self::CancelOrder,
+            32 => Self::ConsumeOrders,
+            33 => Self::InitRegistry,
+            34 => Self::PruneRegistry,
+            35 => Self::MigrateEscrow,
+            36 => Self::CloseStaleEscrow,
+            37 => Self::GetEscrow,
+            38 => Self::InitRateEscrow {
+                price_numerator: Self::unpack_amount(rest)?,
+                price_denominator: Self::unpack_amount(rest.get(8..).unwrap_or_default())?,
+                expiry_unix_timestamp: Self::unpack_i64(rest.get(16..).unwrap_or_default())?,
+            },
+            39 => Self::SetFee {
+                fee_bps: Self::unpack_fee_bps(rest)?,
+                fee_collector: Self::unpack_pubkey(rest.get(2..).unwrap_or_default())?,
+            },
+            40 => Self::InitStats,
+            41 => Self::Reserve { slots: Self::unpack_amount(rest)? },
+            42 => Self::SetCancelPenalty {
+                penalty_bps: Self::unpack_fee_bps(rest)?,
+                penalty_amount: Self::unpack_amount(rest.get(2..).unwrap_or_default())?,
+                to_counterparty: Self::unpack_bool(rest.get(10..).unwrap_or_default())?,
+            },
+            43 => Self::SetGateProgram { gate_program: Self::unpack_pubkey(rest)? },
+            44 => Self::InitCollectionOffer {
+                collection: Self::unpack_pubkey(rest)?,
+                expiry_unix_timestamp: Self::unpack_i64(rest.get(32..).unwrap_or_default())?,
+            },
+            45 => Self::FillCollectionOffer,
+            46 => Self::InitLamportEscrow {
+                lamports: Self::unpack_amount(rest)?,
+                amount: Self::unpack_amount(rest.get(8..).unwrap_or_default())?,
+                expiry_unix_timestamp: Self::unpack_i64(rest.get(16..).unwrap_or_default())?,
+                allowed_taker: Self::unpack_pubkey(rest.get(24..).unwrap_or_default())?,
+            },
+            47 => Self::InitBuyEscrow {
+                amount: Self::unpack_amount(rest)?,
+                expiry_unix_timestamp: Self::unpack_expiry(rest)?,
+                allowed_taker: Self::unpack_allowed_taker(rest)?,
+            },
+            48 => Self::Abort,
+            49 => Self::ExtendDeadline { expiry_unix_timestamp: Self::unpack_i64(rest)? },
+            50 => Self::SetMaxExpiry { max_expiry_seconds: Self::unpack_i64(rest)? },
+            51 => Self::TransferEscrow,
+            52 => Self::SettleSignedOrder {
+                amount: Self::unpack_amount(rest)?,
+                max_payment: Self::unpack_amount(rest.get(8..).unwrap_or_default())?,
+                price: Self::unpack_amount(rest.get(16..).unwrap_or_default())?,
+                order_taker: Self::unpack_pubkey(rest.get(24..).unwrap_or_default())?,
+                order_expiry_unix_timestamp: Self::unpack_i64(rest.get(56..).unwrap_or_default())?,
+            },
+            53 => Self::SetPaymentOption { amount: Self::unpack_amount(rest)? },
+            54 => Self::AddStablecoin {
+                mint: Self::unpack_pubkey(rest)?,
+                price_feed: Self::unpack_pubkey(rest.get(32..).unwrap_or_default())?,
+                tolerance_bps: Self::unpack_fee_bps(rest.get(64..).unwrap_or_default())?,
+            },
+            55 => Self::RemoveStablecoin { mint: Self::unpack_pubkey(rest)? },
+            56 => Self::SetUsdPrice { usd_amount: Self::unpack_amount(rest)? },
+            57 => Self::InitNftSwap {
+                expiry_unix_timestamp: Self::unpack_i64(rest)?,
+                allowed_taker: Self::unpack_pubkey(rest.get(8..).unwrap_or_default())?,
+            },
+            58 => Self::InitRing {
+                ring_id: Self::unpack_amount(rest)?,
+                deadline_unix_timestamp: Self::unpack_i64(rest.get(8..).unwrap_or_default())?,
+                amounts: Self::unpack_ring_amounts(rest.get(16..).unwrap_or_default())?,
+            },
+            59 => Self::DepositRingLeg,
+            60 => Self::ExecuteRing,
+            61 => Self::WithdrawRingLeg,
+            62 => Self::CloseRing,
+            63 => Self::PostQuote {
+                size: Self::unpack_amount(rest)?,
+                price: Self::unpack_amount(rest.get(8..).unwrap_or_default())?,
+                expiry_unix_timestamp: Self::unpack_i64(rest.get(16..).unwrap_or_default())?,
+                lock_funds: Self::unpack_bool(rest.get(24..).unwrap_or_default())?,
+            },
+            64 => Self::AcceptQuote,
+            65 => Self::CancelQuote,
+            66 => Self::ProposeCounteroffer { amount: Self::unpack_amount(rest)? },
+            67 => Self::AcceptCounteroffer,
+            68 => Self::InitSealedAuction {
+                min_bid: Self::unpack_amount(rest)?,
+                deposit: Self::unpack_amount(rest.get(8..).unwrap_or_default())?,
+                commit_end_unix_timestamp: Self::unpack_i64(rest.get(16..).unwrap_or_default())?,
+                reveal_end_unix_timestamp: Self::unpack_i64(rest.get(24..).unwrap_or_default())?,
+            },
+            69 => Self::CommitBid { commitment: Self::unpack_hash(rest)? },
+            70 => Self::RevealBid {
+                amount: Self::unpack_amount(rest)?,
+                salt: Self::unpack_hash(rest.get(8..).unwrap_or_default())?,
+            },
+            71 => Self::SlashBid,
+            72 => Self::CloseSealedAuction,
+            73 => Self::InitRaffle {
+                ticket_price: Self::unpack_amount(rest)?,
+                max_tickets: Self::unpack_u32(rest.get(8..).unwrap_or_default())?,
+                end_unix_timestamp: Self::unpack_i64(rest.get(12..).unwrap_or_default())?,
+                vrf: Self::unpack_pubkey(rest.get(20..).unwrap_or_default())?,
+            },
+            74 => Self::BuyTickets { count: Self::unpack_u32(rest)? },
+            75 => Self::DrawRaffle,
+            76 => Self::SettleRaffle,
+            77 => Self::CloseRaffle,
+            78 => Self::CloseTicket,
+            79 => Self::RequestRaffleRandomness {
+                permission_bump: *rest.first().ok_or(InvalidInstruction)?,
+                state_bump: *rest.get(1).ok_or(InvalidInstruction)?,
+            },
+            80 => Self::ConsumeRaffleRandomness,
+            81 => Self::InitEscrowPool,
+            82 => Self::PostPoolEntry {
+                amount_x: Self::unpack_amount(rest)?,
+                amount_y: Self::unpack_amount(rest.get(8..).unwrap_or_default())?,
+                expiry_unix_timestamp: Self::unpack_i64(rest.get(16..).unwrap_or_default())?,
+            },
+            83 => Self::TakePoolEntry {
+                index: Self::unpack_u32(rest)?,
+                amount_x: Self::unpack_amount(rest.get(4..).unwrap_or_default())?,
+                max_payment: Self::unpack_amount(rest.get(12..).unwrap_or_default())?,
+            },
+            84 => Self::CancelPoolEntry { index: Self::unpack_u32(rest)? },
+            85 => Self::CloseEscrowPool,
+            86 => Self::InitCompressedTree {
+                max_depth: Self::unpack_u32(rest)?,
+                max_buffer_size: Self::unpack_u32(rest.get(4..).unwrap_or_default())?,
+                canopy_depth: Self::unpack_u32(rest.get(8..).unwrap_or_default())?,
+            },
+            87 => Self::InitCompressedEscrow {
+                amount_x: Self::unpack_amount(rest)?,
+                amount_y: Self::unpack_amount(rest.get(8..).unwrap_or_default())?,
+                expiry_unix_timestamp: Self::unpack_i64(rest.get(16..).unwrap_or_default())?,
+            },
+            88 => Self::ExchangeCompressed {
+                escrow: Self::unpack_compressed_escrow(rest)?,
+                root: Self::unpack_hash(rest.get(CompressedEscrow::LEN..).unwrap_or_default())?,
+                max_payment: Self::unpack_amount(rest.get(CompressedEscrow::LEN + 32..).unwrap_or_default())?,
+            },
+            89 => Self::CancelCompressed {
+                escrow: Self::unpack_compressed_escrow(rest)?,
+                root: Self::unpack_hash(rest.get(CompressedEscrow::LEN..).unwrap_or_default())?,
+            },
+            90 => Self::UpgradeEscrowAccount,
+            _ => return Err(InvalidInstruction.into()),
+        })
+    }
+
+    // Discriminators are checked first. A 1 byte tag instruction would only be mistaken for an Anchor one if its tag and the first 7 bytes of its arguments happened to equal a full discriminator.
+    fn unpack_anchor_discriminator(input: &[u8]) -> Option<(&u8, &[u8])> {
+        let discriminator = input.get(..8)?;
+        ANCHOR_DISCRIMINATORS
+            .iter()
+            .find(|(anchor_discriminator, _)| anchor_discriminator == discriminator)
+            .map(|(_, tag)| (tag, &input[8..]))
+    }
+
+    fn unpack_amount(input: &[u8]) -> Result<u64, ProgramError> {
+        let amount = input
+            .get(..8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(InvalidInstruction)?;
+        Ok(amount)
+    }
+
+    // The expiry follows the amount and is optional so clients that only send an amount keep working
+    fn unpack_expiry(input: &[u8]) -> Result<i64, ProgramError> {
+        match input.get(8..) {
+            None | Some([]) => Ok(0),
+            Some(rest) => rest
+                .get(..8)
+                .and_then(|slice| slice.try_into().ok())
+                .map(i64::from_le_bytes)
+                .ok_or_else(|| InvalidInstruction.into()),
+        }
+    }
+
+    // Optional for the same reason as the expiry
+    fn unpack_max_payment(input: &[u8]) -> Result<u64, ProgramError> {
+        match input.get(8..) {
+            None | Some([]) => Ok(u64::MAX),
+            Some(rest) => Self::unpack_amount(rest),
+        }
+    }
+
+    fn unpack_referrer(input: &[u8]) -> Result<Pubkey, ProgramError> {
+        match input.get(16..) {
+            None | Some([]) => Ok(Pubkey::default()),
+            Some(rest) => Self::unpack_pubkey(rest),
+        }
+    }
+
+    // Borsh bytes: a u32 length and that many bytes. They always come last, so leaving them out means there are none.
+    fn unpack_bytes(input: &[u8]) -> Result<&[u8], ProgramError> {
+        if input.is_empty() {
+            return Ok(&[]);
+        }
+        let length = input
+            .get(..4)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u32::from_le_bytes)
+            .ok_or(InvalidInstruction)? as usize;
+        input.get(4..4 + length).ok_or_else(|| InvalidInstruction.into())
+    }
+
+    // The escrow id comes after the metadata, so clients that give an id without metadata send empty metadata first
+    fn unpack_escrow_id(input: &[u8]) -> Result<u64, ProgramError> {
+        let metadata_len = Self::unpack_bytes(input)?.len();
+        match input.get(4 + metadata_len..) {
+            None | Some([]) => Ok(0),
+            Some(rest) => Self::unpack_amount(rest),
+        }
+    }
+
+    // Comes after the escrow id, which has to be given along with it
+    fn unpack_reservation_deposit(input: &[u8]) -> Result<u64, ProgramError> {
+        let metadata_len = Self::unpack_bytes(input)?.len();
+        match input.get(4 + metadata_len + 8..) {
+            None | Some([]) => Ok(0),
+            Some(rest) => Self::unpack_amount(rest),
+        }
+    }
+
+    // Comes after the reservation deposit
+    fn unpack_taker_merkle_root(input: &[u8]) -> Result<[u8; 32], ProgramError> {
+        let metadata_len = Self::unpack_bytes(input)?.len();
+        match input.get(4 + metadata_len + 16..) {
+            None | Some([]) => Ok([0; 32]),
+            Some(rest) => rest
+                .get(..32)
+                .and_then(|slice| slice.try_into().ok())
+                .ok_or_else(|| InvalidInstruction.into()),
+        }
+    }
+
+    // Comes after the memo, as a Borsh Vec of 32 byte arrays. The nodes are borrowed from the input, so skipping over them doesn't allocate.
+    fn taker_proof_nodes(input: &[u8]) -> Result<&[u8], ProgramError> {
+        let memo_len = Self::unpack_bytes(input)?.len();
+        match input.get(4 + memo_len..) {
+            None | Some([]) => Ok(&[]),
+            Some(rest) => {
+                let count = rest
+                    .get(..4)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u32::from_le_bytes)
+                    .ok_or(InvalidInstruction)? as usize;
+                rest.get(4..)
+                    .and_then(|nodes| nodes.get(..count.checked_mul(32)?))
+                    .ok_or_else(|| InvalidInstruction.into())
+            }
+        }
+    }
+
+    fn unpack_taker_proof(input: &[u8]) -> Result<&[[u8; 32]], ProgramError> {
+        try_cast_slice(Self::taker_proof_nodes(input)?).map_err(|_| InvalidInstruction.into())
+    }
+
+    // A commitment or a salt, 32 bytes without a length prefix
+    fn unpack_hash(input: &[u8]) -> Result<[u8; 32], ProgramError> {
+        input
+            .get(..32)
+            .and_then(|slice| slice.try_into().ok())
+            .ok_or_else(|| InvalidInstruction.into())
+    }
+
+    // A Borsh Vec of u64s. How many legs a ring may have is checked by InitRing, not here.
+    fn unpack_ring_amounts(input: &[u8]) -> Result<Vec<u64>, ProgramError> {
+        let count = input
+            .get(..4)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u32::from_le_bytes)
+            .ok_or(InvalidInstruction)? as usize;
+        let amounts = input
+            .get(4..)
+            .and_then(|amounts| amounts.get(..count.checked_mul(8)?))
+            .ok_or(InvalidInstruction)?;
+        Ok(amounts.chunks_exact(8).map(|amount| u64::from_le_bytes(*array_ref![amount, 0, 8])).collect())
+    }
+
+    // The relayer and the tip follow the taker proof, so both have to be skipped first
+    fn unpack_relayer(input: &[u8]) -> Result<(Pubkey, u64), ProgramError> {
+        let memo_len = Self::unpack_bytes(input)?.len();
+        let proof_len = Self::taker_proof_nodes(input)?.len();
+        match input.get(4 + memo_len + 4 + proof_len..) {
+            None | Some([]) => Ok((Pubkey::default(), 0)),
+            Some(rest) => Ok((Self::unpack_pubkey(rest)?, Self::unpack_amount(rest.get(32..).unwrap_or_default())?)),
+        }
+    }
+
+    // A Borsh string is encoded like bytes that are UTF-8
+    fn unpack_memo(input: &[u8]) -> Result<&str, ProgramError> {
+        std::str::from_utf8(Self::unpack_bytes(input)?).map_err(|_| InvalidInstruction.into())
+    }
+
+    fn unpack_i64(input: &[u8]) -> Result<i64, ProgramError> {
+        let value = input
+            .get(..8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(i64::from_le_bytes)
+            .ok_or(InvalidInstruction)?;
+        Ok(value)
+    }
+
+    fn unpack_compressed_escrow(input: &[u8]) -> Result<CompressedEscrow, ProgramError> {
+        let escrow = input.get(..CompressedEscrow::LEN).ok_or(InvalidInstruction)?;
+        CompressedEscrow::unpack_from_slice(escrow)
+    }
+
+    fn unpack_u32(input: &[u8]) -> Result<u32, ProgramError> {
+        let value = input
+            .get(..4)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u32::from_le_bytes)
+            .ok_or(InvalidInstruction)?;
+        Ok(value)
+    }
+
+    // Optional as well, it can only be given together with the expiry
+    fn unpack_allowed_taker(input: &[u8]) -> Result<Pubkey, ProgramError> {
+        match input.get(16..) {
+            None | Some([]) => Ok(Pubkey::default()),
+            Some(rest) => Self::unpack_pubkey(rest),
+        }
+    }
+
+    // And the deposit amount can only be given together with the allowed taker
+    fn unpack_deposit_amount(input: &[u8]) -> Result<u64, ProgramError> {
+        match input.get(48..) {
+            None | Some([]) => Ok(0),
+            Some(rest) => Self::unpack_amount(rest),
+        }
+    }
+
+    // Same for the dispute window, which comes after the deposit amount
+    fn unpack_dispute_window(input: &[u8]) -> Result<i64, ProgramError> {
+        match input.get(56..) {
+            None | Some([]) => Ok(0),
+            Some(rest) => Self::unpack_i64(rest),
+        }
+    }
+
+    fn unpack_fee_bps(input: &[u8]) -> Result<u16, ProgramError> {
+        let fee_bps = input
+            .get(..2)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u16::from_le_bytes)
+            .ok_or(InvalidInstruction)?;
+        Ok(fee_bps)
+    }
+
+    fn unpack_premium_bps(input: &[u8]) -> Result<i16, ProgramError> {
+        let premium_bps = input
+            .get(..2)
+            .and_then(|slice| slice.try_into().ok())
+            .map(i16::from_le_bytes)
+            .ok_or(InvalidInstruction)?;
+        Ok(premium_bps)
+    }
+
+    fn unpack_default_expiry(input: &[u8]) -> Result<i64, ProgramError> {
+        let default_expiry_seconds = input
+            .get(2..10)
+            .and_then(|slice| slice.try_into().ok())
+            .map(i64::from_le_bytes)
+            .ok_or(InvalidInstruction)?;
+        Ok(default_expiry_seconds)
+    }
+
+    fn unpack_referral_share(input: &[u8]) -> Result<u16, ProgramError> {
+        match input.get(10..) {
+            None | Some([]) => Ok(0),
+            Some(rest) => Self::unpack_fee_bps(rest),
+        }
+    }
+
+    fn unpack_pubkey(input: &[u8]) -> Result<Pubkey, ProgramError> {
+        let pubkey = input
+            .get(..32)
+            .and_then(|slice| slice.try_into().ok())
+            .map(Pubkey::new_from_array)
+            .ok_or(InvalidInstruction)?;
+        Ok(pubkey)
+    }
+
+    // Borsh only accepts 0 and 1 for a bool, so anything else is rejected here too
+    fn unpack_bool(input: &[u8]) -> Result<bool, ProgramError> {
+        match input.first() {
+            Some(0) => Ok(false),
+            Some(1) => Ok(true),
+            _ => Err(InvalidInstruction.into()),
+        }
+    }
+
+    /// Packs a [EscrowInstruction](enum.EscrowInstruction.html) into a byte buffer, the reverse of unpack.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(65);
+        match self {
+            Self::InitEscrow {
+                amount,
+                expiry_unix_timestamp,
+                allowed_taker,
+                deposit_amount,
+                dispute_window_seconds,
+                metadata,
+                escrow_id,
+                reservation_deposit,
+                taker_merkle_root,
+            } => {
+                buf.push(0);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+                buf.extend_from_slice(allowed_taker.as_ref());
+                buf.extend_from_slice(&deposit_amount.to_le_bytes());
+                buf.extend_from_slice(&dispute_window_seconds.to_le_bytes());
+                Self::pack_bytes(metadata, &mut buf);
+                buf.extend_from_slice(&escrow_id.to_le_bytes());
+                buf.extend_from_slice(&reservation_deposit.to_le_bytes());
+                buf.extend_from_slice(taker_merkle_root);
+            }
+            Self::Exchange { amount, max_payment, referrer_token_account, memo, taker_proof, relayer, relayer_tip } => {
+                buf.push(1);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&max_payment.to_le_bytes());
+                buf.extend_from_slice(referrer_token_account.as_ref());
+                Self::pack_bytes(memo.as_bytes(), &mut buf);
+                buf.extend_from_slice(&(taker_proof.len() as u32).to_le_bytes());
+                taker_proof.iter().for_each(|node| buf.extend_from_slice(node));
+                buf.extend_from_slice(relayer.as_ref());
+                buf.extend_from_slice(&relayer_tip.to_le_bytes());
+            }
+            Self::Cancel { memo } => {
+                buf.push(2);
+                Self::pack_bytes(memo.as_bytes(), &mut buf);
+            }
+            Self::ReclaimExpired => buf.push(3),
+            Self::InitNftEscrow { amount, expiry_unix_timestamp, allowed_taker } => {
+                buf.push(4);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+                buf.extend_from_slice(allowed_taker.as_ref());
+            }
+            Self::InitFeeVault => buf.push(5),
+            Self::InitializeConfig { fee_bps, default_expiry_seconds, referral_share_bps } => {
+                buf.push(6);
+                buf.extend_from_slice(&fee_bps.to_le_bytes());
+                buf.extend_from_slice(&default_expiry_seconds.to_le_bytes());
+                buf.extend_from_slice(&referral_share_bps.to_le_bytes());
+            }
+            Self::Pause => buf.push(7),
+            Self::Unpause => buf.push(8),
+            Self::AddAllowedMint { mint } => {
+                buf.push(9);
+                buf.extend_from_slice(mint.as_ref());
+            }
+            Self::RemoveAllowedMint { mint } => {
+                buf.push(10);
+                buf.extend_from_slice(mint.as_ref());
+            }
+            Self::SetAllowlistEnabled { enabled } => {
+                buf.push(11);
+                buf.push(*enabled as u8);
+            }
+            Self::UpdateEscrow { expected_amount } => {
+                buf.push(12);
+                buf.extend_from_slice(&expected_amount.to_le_bytes());
+            }
+            Self::InitDutchAuction {
+                start_amount,
+                floor_amount,
+                auction_end_unix_timestamp,
+                expiry_unix_timestamp,
+            } => {
+                buf.push(13);
+                buf.extend_from_slice(&start_amount.to_le_bytes());
+                buf.extend_from_slice(&floor_amount.to_le_bytes());
+                buf.extend_from_slice(&auction_end_unix_timestamp.to_le_bytes());
+                buf.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+            }
+            Self::InitEnglishAuction { min_bid, auction_end_unix_timestamp } => {
+                buf.push(14);
+                buf.extend_from_slice(&min_bid.to_le_bytes());
+                buf.extend_from_slice(&auction_end_unix_timestamp.to_le_bytes());
+            }
+            Self::PlaceBid { amount } => {
+                buf.push(15);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::SettleAuction => buf.push(16),
+            Self::WithdrawBid => buf.push(17),
+            Self::InitOracleEscrow { premium_bps, expiry_unix_timestamp } => {
+                buf.push(18);
+                buf.extend_from_slice(&premium_bps.to_le_bytes());
+                buf.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+            }
+            Self::InitDelegatedEscrow { amount_x, amount, expiry_unix_timestamp, allowed_taker } => {
+                buf.push(19);
+                buf.extend_from_slice(&amount_x.to_le_bytes());
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+                buf.extend_from_slice(allowed_taker.as_ref());
+            }
+            Self::Accept { max_payment } => {
+                buf.push(20);
+                buf.extend_from_slice(&max_payment.to_le_bytes());
+            }
+            Self::Settle => buf.push(21),
+            Self::SetApprovers { threshold } => {
+                buf.push(22);
+                buf.push(*threshold);
+            }
+            Self::ApproveRelease => buf.push(23),
+            Self::InitVestingEscrow { amount, expiry_unix_timestamp, vesting_period_seconds, vesting_periods } => {
+                buf.push(24);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+                buf.extend_from_slice(&vesting_period_seconds.to_le_bytes());
+                buf.extend_from_slice(&vesting_periods.to_le_bytes());
+            }
+            Self::ClaimVested => buf.push(25),
+            Self::InitStreamEscrow { recipient, stream_end_unix_timestamp } => {
+                buf.push(26);
+                buf.extend_from_slice(recipient.as_ref());
+                buf.extend_from_slice(&stream_end_unix_timestamp.to_le_bytes());
+            }
+            Self::Withdraw => buf.push(27),
+            Self::MatchEscrows => buf.push(28),
+            Self::InitMarket => buf.push(29),
+            Self::PlaceOrder => buf.push(30),
+            Self::CancelOrder => buf.push(31),
+            Self::ConsumeOrders => buf.push(32),
+            Self::InitRegistry => buf.push(33),
+            Self::PruneRegistry => buf.push(34),
+            Self::MigrateEscrow => buf.push(35),
+            Self::CloseStaleEscrow => buf.push(36),
+            Self::GetEscrow => buf.push(37),
+            Self::InitRateEscrow { price_numerator, price_denominator, expiry_unix_timestamp } => {
+                buf.push(38);
+                buf.extend_from_slice(&price_numerator.to_le_bytes());
+                buf.extend_from_slice(&price_denominator.to_le_bytes());
+                buf.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+            }
+            Self::SetFee { fee_bps, fee_collector } => {
+                buf.push(39);
+                buf.extend_from_slice(&fee_bps.to_le_bytes());
+                buf.extend_from_slice(fee_collector.as_ref());
+            }
+            Self::InitStats => buf.push(40),
+            Self::Reserve { slots } => {
+                buf.push(41);
+                buf.extend_from_slice(&slots.to_le_bytes());
+            }
+            Self::SetCancelPenalty { penalty_bps, penalty_amount, to_counterparty } => {
+                buf.push(42);
+                buf.extend_from_slice(&penalty_bps.to_le_bytes());
+                buf.extend_from_slice(&penalty_amount.to_le_bytes());
+                buf.push(*to_counterparty as u8);
+            }
+            Self::SetGateProgram { gate_program } => {
+                buf.push(43);
+                buf.extend_from_slice(gate_program.as_ref());
+            }
+            Self::InitCollectionOffer { collection, expiry_unix_timestamp } => {
+                buf.push(44);
+                buf.extend_from_slice(collection.as_ref());
+                buf.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+            }
+            Self::FillCollectionOffer => buf.push(45),
+            Self::InitLamportEscrow { lamports, amount, expiry_unix_timestamp, allowed_taker } => {
+                buf.push(46);
+                buf.extend_from_slice(&lamports.to_le_bytes());
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+                buf.extend_from_slice(allowed_taker.as_ref());
+            }
+            Self::InitBuyEscrow { amount, expiry_unix_timestamp, allowed_taker } => {
+                buf.push(47);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+                buf.extend_from_slice(allowed_taker.as_ref());
+            }
+            Self::Abort => buf.push(48),
+            Self::ExtendDeadline { expiry_unix_timestamp } => {
+                buf.push(49);
+                buf.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+            }
+            Self::SetMaxExpiry { max_expiry_seconds } => {
+                buf.push(50);
+                buf.extend_from_slice(&max_expiry_seconds.to_le_bytes());
+            }
+            Self::TransferEscrow => buf.push(51),
+            Self::SettleSignedOrder { amount, max_payment, price, order_taker, order_expiry_unix_timestamp } => {
+                buf.push(52);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&max_payment.to_le_bytes());
+                buf.extend_from_slice(&price.to_le_bytes());
+                buf.extend_from_slice(order_taker.as_ref());
+                buf.extend_from_slice(&order_expiry_unix_timestamp.to_le_bytes());
+            }
+            Self::SetPaymentOption { amount } => {
+                buf.push(53);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::AddStablecoin { mint, price_feed, tolerance_bps } => {
+                buf.push(54);
+                buf.extend_from_slice(mint.as_ref());
+                buf.extend_from_slice(price_feed.as_ref());
+                buf.extend_from_slice(&tolerance_bps.to_le_bytes());
+            }
+            Self::RemoveStablecoin { mint } => {
+                buf.push(55);
+                buf.extend_from_slice(mint.as_ref());
+            }
+            Self::SetUsdPrice { usd_amount } => {
+                buf.push(56);
+                buf.extend_from_slice(&usd_amount.to_le_bytes());
+            }
+            Self::InitNftSwap { expiry_unix_timestamp, allowed_taker } => {
+                buf.push(57);
+                buf.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+                buf.extend_from_slice(allowed_taker.as_ref());
+            }
+            Self::InitRing { ring_id, deadline_unix_timestamp, amounts } => {
+                buf.push(58);
+                buf.extend_from_slice(&ring_id.to_le_bytes());
+                buf.extend_from_slice(&deadline_unix_timestamp.to_le_bytes());
+                buf.extend_from_slice(&(amounts.len() as u32).to_le_bytes());
+                for amount in amounts {
+                    buf.extend_from_slice(&amount.to_le_bytes());
+                }
+            }
+            Self::DepositRingLeg => buf.push(59),
+            Self::ExecuteRing => buf.push(60),
+            Self::WithdrawRingLeg => buf.push(61),
+            Self::CloseRing => buf.push(62),
+            Self::PostQuote { size, price, expiry_unix_timestamp, lock_funds } => {
+                buf.push(63);
+                buf.extend_from_slice(&size.to_le_bytes());
+                buf.extend_from_slice(&price.to_le_bytes());
+                buf.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+                buf.push(*lock_funds as u8);
+            }
+            Self::AcceptQuote => buf.push(64),
+            Self::CancelQuote => buf.push(65),
+            Self::ProposeCounteroffer { amount } => {
+                buf.push(66);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::AcceptCounteroffer => buf.push(67),
+            Self::InitSealedAuction { min_bid, deposit, commit_end_unix_timestamp, reveal_end_unix_timestamp } => {
+                buf.push(68);
+                buf.extend_from_slice(&min_bid.to_le_bytes());
+                buf.extend_from_slice(&deposit.to_le_bytes());
+                buf.extend_from_slice(&commit_end_unix_timestamp.to_le_bytes());
+                buf.extend_from_slice(&reveal_end_unix_timestamp.to_le_bytes());
+            }
+            Self::CommitBid { commitment } => {
+                buf.push(69);
+                buf.extend_from_slice(commitment);
+            }
+            Self::RevealBid { amount, salt } => {
+                buf.push(70);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(salt);
+            }
+            Self::SlashBid => buf.push(71),
+            Self::CloseSealedAuction => buf.push(72),
+            Self::InitRaffle { ticket_price, max_tickets, end_unix_timestamp, vrf } => {
+                buf.push(73);
+                buf.extend_from_slice(&ticket_price.to_le_bytes());
+                buf.extend_from_slice(&max_tickets.to_le_bytes());
+                buf.extend_from_slice(&end_unix_timestamp.to_le_bytes());
+                buf.extend_from_slice(vrf.as_ref());
+            }
+            Self::BuyTickets { count } => {
+                buf.push(74);
+                buf.extend_from_slice(&count.to_le_bytes());
+            }
+            Self::DrawRaffle => buf.push(75),
+            Self::SettleRaffle => buf.push(76),
+            Self::CloseRaffle => buf.push(77),
+            Self::CloseTicket => buf.push(78),
+            Self::RequestRaffleRandomness { permission_bump, state_bump } => {
+                buf.push(79);
+                buf.push(*permission_bump);
+                buf.push(*state_bump);
+            }
+            Self::ConsumeRaffleRandomness => buf.push(80),
+            Self::InitEscrowPool => buf.push(81),
+            Self::PostPoolEntry { amount_x, amount_y, expiry_unix_timestamp } => {
+                buf.push(82);
+                buf.extend_from_slice(&amount_x.to_le_bytes());
+                buf.extend_from_slice(&amount_y.to_le_bytes());
+                buf.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+            }
+            Self::TakePoolEntry { index, amount_x, max_payment } => {
+                buf.push(83);
+                buf.extend_from_slice(&index.to_le_bytes());
+                buf.extend_from_slice(&amount_x.to_le_bytes());
+                buf.extend_from_slice(&max_payment.to_le_bytes());
+            }
+            Self::CancelPoolEntry { index } => {
+                buf.push(84);
+                buf.extend_from_slice(&index.to_le_bytes());
+            }
+            Self::CloseEscrowPool => buf.push(85),
+            Self::InitCompressedTree { max_depth, max_buffer_size, canopy_depth } => {
+                buf.push(86);
+                buf.extend_from_slice(&max_depth.to_le_bytes());
+                buf.extend_from_slice(&max_buffer_size.to_le_bytes());
+                buf.extend_from_slice(&canopy_depth.to_le_bytes());
+            }
+            Self::InitCompressedEscrow { amount_x, amount_y, expiry_unix_timestamp } => {
+                buf.push(87);
+                buf.extend_from_slice(&amount_x.to_le_bytes());
+                buf.extend_from_slice(&amount_y.to_le_bytes());
+                buf.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+            }
+            Self::ExchangeCompressed { escrow, root, max_payment } => {
+                buf.push(88);
+                Self::pack_compressed_escrow(escrow, &mut buf);
+                buf.extend_from_slice(root);
+                buf.extend_from_slice(&max_payment.to_le_bytes());
+            }
+            Self::CancelCompressed { escrow, root } => {
+                buf.push(89);
+                Self::pack_compressed_escrow(escrow, &mut buf);
+                buf.extend_from_slice(root);
+            }
+            Self::UpgradeEscrowAccount => buf.push(90),
+        }
+        buf
+    }
+
+    fn pack_compressed_escrow(escrow: &CompressedEscrow, buf: &mut Vec<u8>) {
+        let start = buf.len();
+        buf.resize(start + CompressedEscrow::LEN, 0);
+        escrow.pack_into_slice(&mut buf[start..]);
+    }
+
+    fn pack_bytes(bytes: &[u8], buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        buf.extend_from_slice(bytes);
+    }
+}
+
+// The functions below build complete instructions for off-chain (or cross-program) callers, with the accounts in the order the processor expects them. Depend on this crate with the `no-entrypoint` feature to use them.
+
+/// Creates an `InitEscrow` instruction. Pass an `allowed_taker` to make the escrow private to that account.
+#[allow(clippy::too_many_arguments)]
+pub fn init_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    amount: u64,
+    expiry_unix_timestamp: i64,
+    allowed_taker: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let allowed_taker = allowed_taker.copied().unwrap_or_default();
+    let data = EscrowInstruction::InitEscrow {
+        amount,
+        expiry_unix_timestamp,
+        allowed_taker,
+        deposit_amount: 0,
+        dispute_window_seconds: 0,
+        metadata: Cow::Borrowed(&[]),
+        escrow_id: 0,
+        reservation_deposit: 0,
+        taker_merkle_root: [0; 32],
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*initializer, true),
+        AccountMeta::new(*temp_token_account, false),
+        AccountMeta::new_readonly(*token_to_receive_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        AccountMeta::new_readonly(find_allowlist_address(program_id).0, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `InitEscrow` instruction that also creates the temp token account and deposits `deposit_amount` of token X into it from `initializers_token_account`. `temp_token_account` has to be a new keypair that signs the transaction.
+#[allow(clippy::too_many_arguments)]
+pub fn init_escrow_with_deposit(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    initializers_token_account: &Pubkey,
+    token_x_mint: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    deposit_amount: u64,
+    amount: u64,
+    expiry_unix_timestamp: i64,
+    allowed_taker: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = init_escrow(
+        program_id,
+        initializer,
+        temp_token_account,
+        token_to_receive_account,
+        escrow_account,
+        token_program_id,
+        amount,
+        expiry_unix_timestamp,
+        allowed_taker,
+    )?;
+    instruction.data = EscrowInstruction::InitEscrow {
+        amount,
+        expiry_unix_timestamp,
+        allowed_taker: allowed_taker.copied().unwrap_or_default(),
+        deposit_amount,
+        dispute_window_seconds: 0,
+        metadata: Cow::Borrowed(&[]),
+        escrow_id: 0,
+        reservation_deposit: 0,
+        taker_merkle_root: [0; 32],
+    }
+    .pack();
+    // The initializer pays for the temp token account
+    instruction.accounts[0] = AccountMeta::new(*initializer, true);
+    instruction.accounts[1] = AccountMeta::new(*temp_token_account, true);
+    instruction.accounts.push(AccountMeta::new(*initializers_token_account, false));
+    instruction.accounts.push(AccountMeta::new_readonly(*token_x_mint, false));
+    instruction.accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+    Ok(instruction)
+}
+
+/// Creates an `InitEscrow` instruction for a two-phase escrow, which takers `accept` and which can be `settle`d `dispute_window_seconds` after that.
+#[allow(clippy::too_many_arguments)]
+pub fn init_two_phase_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    amount: u64,
+    expiry_unix_timestamp: i64,
+    allowed_taker: Option<&Pubkey>,
+    dispute_window_seconds: i64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = init_escrow(
+        program_id,
+        initializer,
+        temp_token_account,
+        token_to_receive_account,
+        escrow_account,
+        token_program_id,
+        amount,
+        expiry_unix_timestamp,
+        allowed_taker,
+    )?;
+    instruction.data = EscrowInstruction::InitEscrow {
+        amount,
+        expiry_unix_timestamp,
+        allowed_taker: allowed_taker.copied().unwrap_or_default(),
+        deposit_amount: 0,
+        dispute_window_seconds,
+        metadata: Cow::Borrowed(&[]),
+        escrow_id: 0,
+        reservation_deposit: 0,
+        taker_merkle_root: [0; 32],
+    }
+    .pack();
+    Ok(instruction)
+}
+
+/// Creates an `InitEscrow` instruction for an escrow of native SOL. The program wraps `lamports` into the new `temp_token_account`, which has to sign the transaction, and unwraps it again when the escrow is exchanged, cancelled or reclaimed.
+#[allow(clippy::too_many_arguments)]
+pub fn init_native_sol_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    lamports: u64,
+    amount: u64,
+    expiry_unix_timestamp: i64,
+    allowed_taker: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    init_escrow_with_deposit(
+        program_id,
+        initializer,
+        temp_token_account,
+        // There is no token account to deposit from, the lamports come from the initializer directly
+        initializer,
+        &spl_token::native_mint::id(),
+        token_to_receive_account,
+        escrow_account,
+        &spl_token::id(),
+        lamports,
+        amount,
+        expiry_unix_timestamp,
+        allowed_taker,
+    )
+}
+
+/// Creates an `InitNftEscrow` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn init_nft_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    nft_mint: &Pubkey,
+    amount: u64,
+    expiry_unix_timestamp: i64,
+    allowed_taker: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = init_escrow(
+        program_id,
+        initializer,
+        temp_token_account,
+        token_to_receive_account,
+        escrow_account,
+        token_program_id,
+        amount,
+        expiry_unix_timestamp,
+        allowed_taker,
+    )?;
+    let allowed_taker = allowed_taker.copied().unwrap_or_default();
+    instruction.data =
+        EscrowInstruction::InitNftEscrow { amount, expiry_unix_timestamp, allowed_taker }.pack();
+    instruction.accounts.push(AccountMeta::new_readonly(*nft_mint, false));
+    Ok(instruction)
+}
+
+/// Creates an `InitNftSwap` instruction. `token_to_receive_account` is the initializer's token account of `wanted_nft_mint`, which has to exist.
+#[allow(clippy::too_many_arguments)]
+pub fn init_nft_swap(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    nft_mint: &Pubkey,
+    wanted_nft_mint: &Pubkey,
+    expiry_unix_timestamp: i64,
+    allowed_taker: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = init_nft_escrow(
+        program_id,
+        initializer,
+        temp_token_account,
+        token_to_receive_account,
+        escrow_account,
+        token_program_id,
+        nft_mint,
+        1,
+        expiry_unix_timestamp,
+        allowed_taker,
+    )?;
+    let allowed_taker = allowed_taker.copied().unwrap_or_default();
+    instruction.data = EscrowInstruction::InitNftSwap { expiry_unix_timestamp, allowed_taker }.pack();
+    instruction.accounts.push(AccountMeta::new_readonly(*wanted_nft_mint, false));
+    Ok(instruction)
+}
+
+/// The accounts and arguments of an `Exchange`, for `exchange`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExchangeArgs {
+    pub taker: Pubkey,
+    pub takers_sending_token_account: Pubkey,
+    pub takers_token_to_receive_account: Pubkey,
+    pub pdas_temp_token_account: Pubkey,
+    pub initializers_main_account: Pubkey,
+    pub initializers_token_to_receive_account: Pubkey,
+    pub escrow_account: Pubkey,
+    pub token_program_id: Pubkey,
+    pub token_x_mint: Pubkey,
+    pub token_y_mint: Pubkey,
+    /// The token program of the tokens the taker sends
+    pub y_token_program_id: Pubkey,
+    /// The fee vault of token Y, only needed while the protocol fee is enabled
+    pub fee_vault: Option<Pubkey>,
+    pub referrer_token_account: Option<Pubkey>,
+    /// Set if `takers_token_to_receive_account` is the taker's associated token account and doesn't exist yet
+    pub create_takers_token_account: bool,
+    /// Required for oracle escrows
+    pub price_feed: Option<Pubkey>,
+    pub amount: u64,
+    /// The most the taker pays in token Y, protocol fee included. `Escrow::expected_payment` is a sensible value for escrows with a known price.
+    pub max_payment: u64,
+}
+
+/// Creates an `Exchange` instruction
+pub fn exchange(program_id: &Pubkey, args: ExchangeArgs) -> Result<Instruction, ProgramError> {
+    let ExchangeArgs {
+        taker,
+        takers_sending_token_account,
+        takers_token_to_receive_account,
+        pdas_temp_token_account,
+        initializers_main_account,
+        initializers_token_to_receive_account,
+        escrow_account,
+        token_program_id,
+        token_x_mint,
+        token_y_mint,
+        y_token_program_id,
+        fee_vault,
+        referrer_token_account,
+        create_takers_token_account,
+        price_feed,
+        amount,
+        max_payment,
+    } = args;
+    let data = EscrowInstruction::Exchange {
+        amount,
+        max_payment,
+        referrer_token_account: referrer_token_account.unwrap_or_default(),
+        memo: Cow::Borrowed(""),
+        taker_proof: Cow::Borrowed(&[]),
+        relayer: Pubkey::default(),
+        relayer_tip: 0,
+    }
+    .pack();
+    let (pda, _bump_seed) = find_escrow_authority_address(&escrow_account, program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(taker, true),
+        AccountMeta::new(takers_sending_token_account, false),
+        AccountMeta::new(takers_token_to_receive_account, false),
+        AccountMeta::new(pdas_temp_token_account, false),
+        AccountMeta::new(initializers_main_account, false),
+        AccountMeta::new(initializers_token_to_receive_account, false),
+        AccountMeta::new(escrow_account, false),
+        AccountMeta::new_readonly(token_program_id, false),
+        AccountMeta::new_readonly(pda, false),
+        AccountMeta::new_readonly(token_x_mint, false),
+        AccountMeta::new_readonly(token_y_mint, false),
+        AccountMeta::new_readonly(y_token_program_id, false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+    ];
+    if let Some(fee_vault) = fee_vault {
+        accounts.push(AccountMeta::new(fee_vault, false));
+    }
+    if let Some(referrer_token_account) = referrer_token_account {
+        accounts.push(AccountMeta::new(referrer_token_account, false));
+    }
+    if create_takers_token_account {
+        // The taker pays for their new token account
+        accounts[0] = AccountMeta::new(taker, true);
+        accounts.push(AccountMeta::new_readonly(token::associated_token::id(), false));
+        accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+        accounts.push(AccountMeta::new_readonly(sysvar::rent::id(), false));
+    }
+    if let Some(price_feed) = price_feed {
+        accounts.push(AccountMeta::new_readonly(price_feed, false));
+    }
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `Exchange` instruction for a vesting escrow, which pays the taker into the escrow's vesting vault instead of a token account of theirs.
+#[allow(clippy::too_many_arguments)]
+pub fn exchange_into_vesting(
+    program_id: &Pubkey,
+    taker: &Pubkey,
+    takers_sending_token_account: &Pubkey,
+    pdas_temp_token_account: &Pubkey,
+    initializers_main_account: &Pubkey,
+    initializers_token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    token_x_mint: &Pubkey,
+    token_y_mint: &Pubkey,
+    y_token_program_id: &Pubkey,
+    fee_vault: Option<&Pubkey>,
+    amount: u64,
+    max_payment: u64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = exchange(
+        program_id,
+        ExchangeArgs {
+            taker: *taker,
+            takers_sending_token_account: *takers_sending_token_account,
+            takers_token_to_receive_account: find_vesting_vault_address(escrow_account, program_id).0,
+            pdas_temp_token_account: *pdas_temp_token_account,
+            initializers_main_account: *initializers_main_account,
+            initializers_token_to_receive_account: *initializers_token_to_receive_account,
+            escrow_account: *escrow_account,
+            token_program_id: *token_program_id,
+            token_x_mint: *token_x_mint,
+            token_y_mint: *token_y_mint,
+            y_token_program_id: *y_token_program_id,
+            fee_vault: fee_vault.copied(),
+            referrer_token_account: None,
+            create_takers_token_account: false,
+            price_feed: None,
+            amount,
+            max_payment,
+        },
+    )?;
+    // The taker pays for the vesting account and the vault
+    instruction.accounts[0] = AccountMeta::new(*taker, true);
+    instruction.accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+    instruction.accounts.push(AccountMeta::new_readonly(sysvar::rent::id(), false));
+    instruction.accounts.push(AccountMeta::new(find_vesting_address(escrow_account, program_id).0, false));
+    Ok(instruction)
+}
+
+/// Creates a `Cancel` instruction.
+pub fn cancel(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    pdas_temp_token_account: &Pubkey,
+    initializers_token_to_return_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    token_x_mint: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::Cancel { memo: Cow::Borrowed("") }.pack();
+    let (pda, _bump_seed) = find_escrow_authority_address(escrow_account, program_id);
+    let (config, _bump_seed) = find_config_address(program_id);
+
+    let accounts = vec![
+        AccountMeta::new(*initializer, true),
+        AccountMeta::new(*pdas_temp_token_account, false),
+        AccountMeta::new(*initializers_token_to_return_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(pda, false),
+        AccountMeta::new_readonly(*token_x_mint, false),
+        AccountMeta::new_readonly(config, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `Cancel` instruction for a streaming escrow, which also pays the recipient what has accrued.
+#[allow(clippy::too_many_arguments)]
+pub fn cancel_stream(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    pdas_temp_token_account: &Pubkey,
+    initializers_token_to_return_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    token_x_mint: &Pubkey,
+    recipients_token_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = cancel(
+        program_id,
+        initializer,
+        pdas_temp_token_account,
+        initializers_token_to_return_account,
+        escrow_account,
+        token_program_id,
+        token_x_mint,
+    )?;
+    instruction.accounts.push(AccountMeta::new(*recipients_token_account, false));
+    Ok(instruction)
+}
+
+/// Creates a `ReclaimExpired` instruction.
+pub fn reclaim_expired(
+    program_id: &Pubkey,
+    pdas_temp_token_account: &Pubkey,
+    initializers_main_account: &Pubkey,
+    initializers_token_to_return_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    token_x_mint: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::ReclaimExpired.pack();
+    let (pda, _bump_seed) = find_escrow_authority_address(escrow_account, program_id);
+
+    let accounts = vec![
+        AccountMeta::new(*pdas_temp_token_account, false),
+        AccountMeta::new(*initializers_main_account, false),
+        AccountMeta::new(*initializers_token_to_return_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(pda, false),
+        AccountMeta::new_readonly(*token_x_mint, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `InitFeeVault` instruction for the fee vault of `mint`.
+pub fn init_fee_vault(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::InitFeeVault.pack();
+    let (fee_vault, _bump_seed) = find_fee_vault_address(mint, program_id);
+    let (fee_authority, _bump_seed) = find_fee_authority_address(program_id);
+
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(fee_vault, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(fee_authority, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `InitializeConfig` instruction. `admin` has to be the program's upgrade authority.
+pub fn initialize_config(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    fee_bps: u16,
+    default_expiry_seconds: i64,
+    referral_share_bps: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::InitializeConfig { fee_bps, default_expiry_seconds, referral_share_bps }.pack();
+    let (config, _bump_seed) = find_config_address(program_id);
+    let (program_data, _bump_seed) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+
+    let accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new(config, false),
+        AccountMeta::new_readonly(program_data, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `Pause` instruction.
+pub fn pause(program_id: &Pubkey, admin: &Pubkey) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::Pause.pack();
+    let (config, _bump_seed) = find_config_address(program_id);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(config, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `Unpause` instruction.
+pub fn unpause(program_id: &Pubkey, admin: &Pubkey) -> Result<Instruction, ProgramError> {
+    let mut instruction = pause(program_id, admin)?;
+    instruction.data = EscrowInstruction::Unpause.pack();
+    Ok(instruction)
+}
+
+/// Creates an `AddAllowedMint` instruction.
+pub fn add_allowed_mint(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::AddAllowedMint { mint: *mint }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        AccountMeta::new(find_allowlist_address(program_id).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `RemoveAllowedMint` instruction.
+pub fn remove_allowed_mint(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::RemoveAllowedMint { mint: *mint }.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        AccountMeta::new(find_allowlist_address(program_id).0, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `AddStablecoin` instruction.
+pub fn add_stablecoin(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    price_feed: &Pubkey,
+    tolerance_bps: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::AddStablecoin { mint: *mint, price_feed: *price_feed, tolerance_bps }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        AccountMeta::new(find_stablecoins_address(program_id).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `RemoveStablecoin` instruction.
+pub fn remove_stablecoin(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::RemoveStablecoin { mint: *mint }.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        AccountMeta::new(find_stablecoins_address(program_id).0, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `SetAllowlistEnabled` instruction.
+pub fn set_allowlist_enabled(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    enabled: bool,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = pause(program_id, admin)?;
+    instruction.data = EscrowInstruction::SetAllowlistEnabled { enabled }.pack();
+    Ok(instruction)
+}
+
+/// Creates a `SetFee` instruction. Pass `Pubkey::default()` as the fee collector to have fees paid into the fee vaults.
+pub fn set_fee(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    fee_bps: u16,
+    fee_collector: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = pause(program_id, admin)?;
+    instruction.data = EscrowInstruction::SetFee { fee_bps, fee_collector: *fee_collector }.pack();
+    Ok(instruction)
+}
+
+/// Creates an `InitDutchAuction` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn init_dutch_auction(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    start_amount: u64,
+    floor_amount: u64,
+    auction_end_unix_timestamp: i64,
+    expiry_unix_timestamp: i64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = init_escrow(
+        program_id,
+        initializer,
+        temp_token_account,
+        token_to_receive_account,
+        escrow_account,
+        token_program_id,
+        floor_amount,
+        expiry_unix_timestamp,
+        None,
+    )?;
+    instruction.data = EscrowInstruction::InitDutchAuction {
+        start_amount,
+        floor_amount,
+        auction_end_unix_timestamp,
+        expiry_unix_timestamp,
+    }
+    .pack();
+    Ok(instruction)
+}
+
+/// Creates an `InitRateEscrow` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn init_rate_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    price_numerator: u64,
+    price_denominator: u64,
+    expiry_unix_timestamp: i64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = init_escrow(
+        program_id,
+        initializer,
+        temp_token_account,
+        token_to_receive_account,
+        escrow_account,
+        token_program_id,
+        0,
+        expiry_unix_timestamp,
+        None,
+    )?;
+    instruction.data = EscrowInstruction::InitRateEscrow {
+        price_numerator,
+        price_denominator,
+        expiry_unix_timestamp,
+    }
+    .pack();
+    Ok(instruction)
+}
+
+/// Creates an `InitEnglishAuction` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn init_english_auction(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    min_bid: u64,
+    auction_end_unix_timestamp: i64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = init_escrow(
+        program_id,
+        initializer,
+        temp_token_account,
+        token_to_receive_account,
+        escrow_account,
+        token_program_id,
+        min_bid,
+        0,
+        None,
+    )?;
+    instruction.data = EscrowInstruction::InitEnglishAuction { min_bid, auction_end_unix_timestamp }.pack();
+    Ok(instruction)
+}
+
+/// Creates a `PlaceBid` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn place_bid(
+    program_id: &Pubkey,
+    bidder: &Pubkey,
+    bidders_token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_y_mint: &Pubkey,
+    y_token_program_id: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::PlaceBid { amount }.pack();
+    let (bid_account, _bump_seed) = find_bid_address(escrow_account, bidder, program_id);
+    let (pda, _bump_seed) = find_escrow_authority_address(escrow_account, program_id);
+
+    let accounts = vec![
+        AccountMeta::new(*bidder, true),
+        AccountMeta::new(*bidders_token_account, false),
+        AccountMeta::new(bid_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda, false),
+        AccountMeta::new_readonly(*token_y_mint, false),
+        AccountMeta::new_readonly(*y_token_program_id, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `SettleAuction` instruction. `settler` only signs if it is the initializer settling before the auction has ended.
+#[allow(clippy::too_many_arguments)]
+pub fn settle_auction(
+    program_id: &Pubkey,
+    settler: &Pubkey,
+    settler_is_signer: bool,
+    escrow_account: &Pubkey,
+    pdas_temp_token_account: &Pubkey,
+    winners_token_to_receive_account: &Pubkey,
+    winner: &Pubkey,
+    initializers_token_to_receive_account: &Pubkey,
+    initializers_main_account: &Pubkey,
+    token_program_id: &Pubkey,
+    y_token_program_id: &Pubkey,
+    token_x_mint: &Pubkey,
+    token_y_mint: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::SettleAuction.pack();
+    let (pda, _bump_seed) = find_escrow_authority_address(escrow_account, program_id);
+    let (bid_account, _bump_seed) = find_bid_address(escrow_account, winner, program_id);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*settler, settler_is_signer),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(pda, false),
+        AccountMeta::new(*pdas_temp_token_account, false),
+        AccountMeta::new(*winners_token_to_receive_account, false),
+        AccountMeta::new(bid_account, false),
+        AccountMeta::new(*winner, false),
+        AccountMeta::new(*initializers_token_to_receive_account, false),
+        AccountMeta::new(*initializers_main_account, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(*y_token_program_id, false),
+        AccountMeta::new_readonly(*token_x_mint, false),
+        AccountMeta::new_readonly(*token_y_mint, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `WithdrawBid` instruction.
+pub fn withdraw_bid(
+    program_id: &Pubkey,
+    bidder: &Pubkey,
+    bidders_token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_y_mint: &Pubkey,
+    y_token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::WithdrawBid.pack();
+    let (bid_account, _bump_seed) = find_bid_address(escrow_account, bidder, program_id);
+    let (pda, _bump_seed) = find_escrow_authority_address(escrow_account, program_id);
+
+    let accounts = vec![
+        AccountMeta::new(*bidder, true),
+        AccountMeta::new(bid_account, false),
+        AccountMeta::new(*bidders_token_account, false),
+        AccountMeta::new_readonly(*escrow_account, false),
+        AccountMeta::new_readonly(pda, false),
+        AccountMeta::new_readonly(*token_y_mint, false),
+        AccountMeta::new_readonly(*y_token_program_id, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `InitOracleEscrow` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn init_oracle_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    price_feed: &Pubkey,
+    premium_bps: i16,
+    expiry_unix_timestamp: i64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = init_escrow(
+        program_id,
+        initializer,
+        temp_token_account,
+        token_to_receive_account,
+        escrow_account,
+        token_program_id,
+        0,
+        expiry_unix_timestamp,
+        None,
+    )?;
+    instruction.data = EscrowInstruction::InitOracleEscrow { premium_bps, expiry_unix_timestamp }.pack();
+    instruction.accounts.push(AccountMeta::new_readonly(*price_feed, false));
+    Ok(instruction)
+}
+
+/// Creates an `InitDelegatedEscrow` instruction. The initializer's token account is approved for `amount_x` instead of being handed over to the PDA.
+#[allow(clippy::too_many_arguments)]
+pub fn init_delegated_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    initializers_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    amount_x: u64,
+    amount: u64,
+    expiry_unix_timestamp: i64,
+    allowed_taker: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = init_escrow(
+        program_id,
+        initializer,
+        initializers_token_account,
+        token_to_receive_account,
+        escrow_account,
+        token_program_id,
+        amount,
+        expiry_unix_timestamp,
+        allowed_taker,
+    )?;
+    instruction.data = EscrowInstruction::InitDelegatedEscrow {
+        amount_x,
+        amount,
+        expiry_unix_timestamp,
+        allowed_taker: allowed_taker.copied().unwrap_or_default(),
+    }
+    .pack();
+    let (pda, _bump_seed) = find_escrow_authority_address(escrow_account, program_id);
+    instruction.accounts.push(AccountMeta::new_readonly(pda, false));
+    Ok(instruction)
+}
+
+/// Creates an `Accept` instruction.
+pub fn accept(
+    program_id: &Pubkey,
+    taker: &Pubkey,
+    takers_token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_y_mint: &Pubkey,
+    y_token_program_id: &Pubkey,
+    max_payment: u64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = place_bid(
+        program_id,
+        taker,
+        takers_token_account,
+        escrow_account,
+        token_y_mint,
+        y_token_program_id,
+        0,
+    )?;
+    instruction.data = EscrowInstruction::Accept { max_payment }.pack();
+    Ok(instruction)
+}
+
+/// Creates an `Abort` instruction. Both the initializer and the taker sign it.
+pub fn abort(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    taker: &Pubkey,
+    escrow_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::Abort.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*initializer, true),
+        AccountMeta::new_readonly(*taker, true),
+        AccountMeta::new(*escrow_account, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `Settle` instruction. Within the dispute window, both the initializer (as `settler`) and the taker have to sign, so pass `signed: true`.
+#[allow(clippy::too_many_arguments)]
+pub fn settle(
+    program_id: &Pubkey,
+    settler: &Pubkey,
+    signed: bool,
+    escrow_account: &Pubkey,
+    pdas_temp_token_account: &Pubkey,
+    takers_token_to_receive_account: &Pubkey,
+    taker: &Pubkey,
+    initializers_token_to_receive_account: &Pubkey,
+    initializers_main_account: &Pubkey,
+    token_program_id: &Pubkey,
+    y_token_program_id: &Pubkey,
+    token_x_mint: &Pubkey,
+    token_y_mint: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = settle_auction(
+        program_id,
+        settler,
+        signed,
+        escrow_account,
+        pdas_temp_token_account,
+        takers_token_to_receive_account,
+        taker,
+        initializers_token_to_receive_account,
+        initializers_main_account,
+        token_program_id,
+        y_token_program_id,
+        token_x_mint,
+        token_y_mint,
+    )?;
+    instruction.data = EscrowInstruction::Settle.pack();
+    instruction.accounts[6].is_signer = signed;
+    Ok(instruction)
+}
+
+/// Creates a `SetApprovers` instruction.
+pub fn set_approvers(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    escrow_account: &Pubkey,
+    approvers: &[Pubkey],
+    threshold: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::SetApprovers { threshold }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*initializer, true),
+        AccountMeta::new(*escrow_account, false),
+    ];
+    accounts.extend(approvers.iter().map(|approver| AccountMeta::new_readonly(*approver, false)));
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `ApproveRelease` instruction.
+pub fn approve_release(
+    program_id: &Pubkey,
+    approver: &Pubkey,
+    escrow_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::ApproveRelease.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*approver, true),
+        AccountMeta::new(*escrow_account, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `InitVestingEscrow` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn init_vesting_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    amount: u64,
+    expiry_unix_timestamp: i64,
+    vesting_period_seconds: i64,
+    vesting_periods: u16,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = init_escrow(
+        program_id,
+        initializer,
+        temp_token_account,
+        token_to_receive_account,
+        escrow_account,
+        token_program_id,
+        amount,
+        expiry_unix_timestamp,
+        None,
+    )?;
+    instruction.data = EscrowInstruction::InitVestingEscrow {
+        amount,
+        expiry_unix_timestamp,
+        vesting_period_seconds,
+        vesting_periods,
+    }
+    .pack();
+    Ok(instruction)
+}
+
+/// Creates a `ClaimVested` instruction.
+pub fn claim_vested(
+    program_id: &Pubkey,
+    escrow_account: &Pubkey,
+    beneficiarys_token_account: &Pubkey,
+    beneficiary: &Pubkey,
+    token_program_id: &Pubkey,
+    mint: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::ClaimVested.pack();
+
+    let accounts = vec![
+        AccountMeta::new(find_vesting_address(escrow_account, program_id).0, false),
+        AccountMeta::new(find_vesting_vault_address(escrow_account, program_id).0, false),
+        AccountMeta::new(*beneficiarys_token_account, false),
+        AccountMeta::new(*beneficiary, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(*mint, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `InitStreamEscrow` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn init_stream_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    initializers_token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    recipient: &Pubkey,
+    stream_end_unix_timestamp: i64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = init_escrow(
+        program_id,
+        initializer,
+        temp_token_account,
+        initializers_token_account,
+        escrow_account,
+        token_program_id,
+        0,
+        0,
+        None,
+    )?;
+    instruction.data = EscrowInstruction::InitStreamEscrow {
+        recipient: *recipient,
+        stream_end_unix_timestamp,
+    }
+    .pack();
+    Ok(instruction)
+}
+
+/// Creates a `Withdraw` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw(
+    program_id: &Pubkey,
+    recipient: &Pubkey,
+    recipients_token_account: &Pubkey,
+    pdas_temp_token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    token_x_mint: &Pubkey,
+    initializers_main_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::Withdraw.pack();
+    let (pda, _bump_seed) = find_escrow_authority_address(escrow_account, program_id);
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*recipient, true),
+        AccountMeta::new(*recipients_token_account, false),
+        AccountMeta::new(*pdas_temp_token_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(pda, false),
+        AccountMeta::new_readonly(*token_x_mint, false),
+        AccountMeta::new(*initializers_main_account, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `MatchEscrows` instruction. Escrow A offers token X, escrow B offers token Y.
+#[allow(clippy::too_many_arguments)]
+pub fn match_escrows(
+    program_id: &Pubkey,
+    escrow_a: &Pubkey,
+    temp_token_account_a: &Pubkey,
+    initializer_a: &Pubkey,
+    token_to_receive_account_a: &Pubkey,
+    escrow_b: &Pubkey,
+    temp_token_account_b: &Pubkey,
+    initializer_b: &Pubkey,
+    token_to_receive_account_b: &Pubkey,
+    token_x_program_id: &Pubkey,
+    token_y_program_id: &Pubkey,
+    token_x_mint: &Pubkey,
+    token_y_mint: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::MatchEscrows.pack();
+    let (pda_a, _bump_seed) = find_escrow_authority_address(escrow_a, program_id);
+    let (pda_b, _bump_seed) = find_escrow_authority_address(escrow_b, program_id);
+
+    let accounts = vec![
+        AccountMeta::new(*escrow_a, false),
+        AccountMeta::new(*temp_token_account_a, false),
+        AccountMeta::new(*initializer_a, false),
+        AccountMeta::new(*token_to_receive_account_a, false),
+        AccountMeta::new_readonly(pda_a, false),
+        AccountMeta::new(*escrow_b, false),
+        AccountMeta::new(*temp_token_account_b, false),
+        AccountMeta::new(*initializer_b, false),
+        AccountMeta::new(*token_to_receive_account_b, false),
+        AccountMeta::new_readonly(pda_b, false),
+        AccountMeta::new_readonly(*token_x_program_id, false),
+        AccountMeta::new_readonly(*token_y_program_id, false),
+        AccountMeta::new_readonly(*token_x_mint, false),
+        AccountMeta::new_readonly(*token_y_mint, false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `InitMarket` instruction.
+pub fn init_market(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::InitMarket.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(find_market_address(base_mint, quote_mint, program_id).0, false),
+        AccountMeta::new_readonly(*base_mint, false),
+        AccountMeta::new_readonly(*quote_mint, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `PlaceOrder` instruction.
+pub fn place_order(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    escrow_account: &Pubkey,
+    temp_token_account: &Pubkey,
+    market: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::PlaceOrder.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*initializer, true),
+        AccountMeta::new_readonly(*escrow_account, false),
+        AccountMeta::new_readonly(*temp_token_account, false),
+        AccountMeta::new(*market, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `CancelOrder` instruction.
+pub fn cancel_order(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    escrow_account: &Pubkey,
+    market: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::CancelOrder.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(*escrow_account, false),
+        AccountMeta::new(*market, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `ConsumeOrders` instruction. The arguments after the market are those of match_escrows, with the best ask as escrow A and the best bid as escrow B.
+#[allow(clippy::too_many_arguments)]
+pub fn consume_orders(
+    program_id: &Pubkey,
+    market: &Pubkey,
+    ask_escrow: &Pubkey,
+    ask_temp_token_account: &Pubkey,
+    ask_initializer: &Pubkey,
+    ask_token_to_receive_account: &Pubkey,
+    bid_escrow: &Pubkey,
+    bid_temp_token_account: &Pubkey,
+    bid_initializer: &Pubkey,
+    bid_token_to_receive_account: &Pubkey,
+    base_token_program_id: &Pubkey,
+    quote_token_program_id: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = match_escrows(
+        program_id,
+        ask_escrow,
+        ask_temp_token_account,
+        ask_initializer,
+        ask_token_to_receive_account,
+        bid_escrow,
+        bid_temp_token_account,
+        bid_initializer,
+        bid_token_to_receive_account,
+        base_token_program_id,
+        quote_token_program_id,
+        base_mint,
+        quote_mint,
+    )?;
+    instruction.data = EscrowInstruction::ConsumeOrders.pack();
+    instruction.accounts.insert(0, AccountMeta::new(*market, false));
+    Ok(instruction)
+}
+
+/// Creates an `InitRegistry` instruction.
+pub fn init_registry(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    token_x_mint: &Pubkey,
+    token_y_mint: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::InitRegistry.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(find_registry_address(token_x_mint, token_y_mint, program_id).0, false),
+        AccountMeta::new_readonly(*token_x_mint, false),
+        AccountMeta::new_readonly(*token_y_mint, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `InitStats` instruction.
+pub fn init_stats(program_id: &Pubkey, payer: &Pubkey) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::InitStats.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(find_stats_address(program_id).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `PruneRegistry` instruction.
+pub fn prune_registry(
+    program_id: &Pubkey,
+    token_x_mint: &Pubkey,
+    token_y_mint: &Pubkey,
+    escrow_accounts: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::PruneRegistry.pack();
+
+    let mut accounts = vec![AccountMeta::new(find_registry_address(token_x_mint, token_y_mint, program_id).0, false)];
+    accounts.extend(escrow_accounts.iter().map(|escrow_account| AccountMeta::new_readonly(*escrow_account, false)));
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `MigrateEscrow` instruction for an escrow of an older layout. The temp token account and the initializer's token account are the ones stored in the escrow.
+pub fn migrate_escrow(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    escrow_account: &Pubkey,
+    pdas_temp_token_account: &Pubkey,
+    initializers_token_to_receive_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::MigrateEscrow.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new(*pdas_temp_token_account, false),
+        AccountMeta::new_readonly(*initializers_token_to_receive_account, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(find_legacy_escrow_authority_address(program_id).0, false),
+        AccountMeta::new_readonly(find_escrow_authority_address(escrow_account, program_id).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `UpgradeEscrowAccount` instruction for a V1 escrow. The temp token account and the initializer's token account are the ones stored in the escrow.
+pub fn upgrade_escrow_account(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    escrow_account: &Pubkey,
+    pdas_temp_token_account: &Pubkey,
+    initializers_token_to_receive_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::UpgradeEscrowAccount.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(*pdas_temp_token_account, false),
+        AccountMeta::new_readonly(*initializers_token_to_receive_account, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `CloseStaleEscrow` instruction. The temp token account and the initializer are the ones stored in the escrow.
+pub fn close_stale_escrow(
+    program_id: &Pubkey,
+    pdas_temp_token_account: &Pubkey,
+    initializers_main_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::CloseStaleEscrow.pack();
+    let (pda, _bump_seed) = find_escrow_authority_address(escrow_account, program_id);
+
+    let accounts = vec![
+        AccountMeta::new(*pdas_temp_token_account, false),
+        AccountMeta::new(*initializers_main_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(pda, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `GetEscrow` instruction. The temp token account is the one stored in the escrow.
+pub fn get_escrow(
+    program_id: &Pubkey,
+    escrow_account: &Pubkey,
+    pdas_temp_token_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::GetEscrow.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*escrow_account, false),
+        AccountMeta::new_readonly(*pdas_temp_token_account, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Has the program create the escrow account of an instruction built by one of the init functions above, so no create_account instruction has to come first. The initializer pays the rent, and the escrow account has to be a new keypair that signs the transaction.
+pub fn with_escrow_account_creation(mut instruction: Instruction) -> Instruction {
+    instruction.accounts[0].is_writable = true;
+    instruction.accounts[3].is_signer = true;
+    if !instruction.accounts.iter().any(|account| account.pubkey == system_program::id()) {
+        instruction.accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+    }
+    instruction
+}
+
+/// Appends the registry of the given mint pair to an instruction built by one of the functions above, e.g. init_escrow, exchange, cancel, reclaim_expired or close_stale_escrow, so the instruction keeps the registry up to date.
+pub fn with_registry(mut instruction: Instruction, token_x_mint: &Pubkey, token_y_mint: &Pubkey) -> Instruction {
+    let registry = find_registry_address(token_x_mint, token_y_mint, &instruction.program_id).0;
+    instruction.accounts.push(AccountMeta::new(registry, false));
+    instruction
+}
+
+/// Appends the taker's fill record and the system program to an Exchange built by exchange, so a fill of a rate-based escrow is recorded in it. Other escrows ignore it.
+pub fn with_fill_record(mut instruction: Instruction) -> Instruction {
+    let taker = instruction.accounts[0].pubkey;
+    let escrow_account = instruction.accounts[6].pubkey;
+    let (fill_record, _bump_seed) = find_fill_record_address(&escrow_account, &taker, &instruction.program_id);
+    instruction.accounts.push(AccountMeta::new(fill_record, false));
+    instruction.accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+    instruction
+}
+
+/// Appends the stats account to an instruction built by one of the functions above, e.g. init_escrow, exchange, cancel, reclaim_expired or close_stale_escrow, so the instruction is counted in it.
+pub fn with_stats(mut instruction: Instruction) -> Instruction {
+    let stats = find_stats_address(&instruction.program_id).0;
+    instruction.accounts.push(AccountMeta::new(stats, false));
+    instruction
+}
+
+/// Attaches metadata to an escrow created by an instruction built by init_escrow, init_escrow_with_deposit or init_two_phase_escrow
+pub fn with_metadata(mut instruction: Instruction, metadata: &[u8]) -> Result<Instruction, ProgramError> {
+    let instruction_data = std::mem::take(&mut instruction.data);
+    let mut data = EscrowInstruction::unpack(&instruction_data)?;
+    match &mut data {
+        EscrowInstruction::InitEscrow { metadata: data_metadata, .. } => *data_metadata = Cow::Borrowed(metadata),
+        _ => return Err(InvalidInstruction.into()),
+    }
+    instruction.data = data.pack();
+    Ok(instruction)
+}
+
+/// Gives the escrow of an instruction built by init_escrow, init_escrow_with_deposit or init_two_phase_escrow an id, which moves it to the PDA find_escrow_address derives from the initializer and the id. Whatever escrow account the instruction was built with is replaced. The program creates the account, with the initializer paying the rent.
+pub fn with_escrow_id(mut instruction: Instruction, escrow_id: u64) -> Result<Instruction, ProgramError> {
+    // 0 is no id
+    if escrow_id == 0 {
+        return Err(InvalidInstruction.into());
+    }
+    let instruction_data = std::mem::take(&mut instruction.data);
+    let mut data = EscrowInstruction::unpack(&instruction_data)?;
+    match &mut data {
+        EscrowInstruction::InitEscrow { escrow_id: data_escrow_id, .. } => *data_escrow_id = escrow_id,
+        _ => return Err(InvalidInstruction.into()),
+    }
+    instruction.data = data.pack();
+    let initializer = instruction.accounts[0].pubkey;
+    instruction.accounts[0].is_writable = true;
+    instruction.accounts[3] = AccountMeta::new(find_escrow_address(&initializer, escrow_id, &instruction.program_id).0, false);
+    if !instruction.accounts.iter().any(|account| account.pubkey == system_program::id()) {
+        instruction.accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+    }
+    Ok(instruction)
+}
+
+/// Lets takers reserve the escrow of an instruction built by init_escrow, init_escrow_with_deposit or init_two_phase_escrow for the given deposit in lamports. Like the escrow id, it is appended to the instruction data, and an escrow without an id gets an id of 0.
+pub fn with_reservation_deposit(mut instruction: Instruction, reservation_deposit: u64) -> Result<Instruction, ProgramError> {
+    let instruction_data = std::mem::take(&mut instruction.data);
+    let mut data = EscrowInstruction::unpack(&instruction_data)?;
+    match &mut data {
+        EscrowInstruction::InitEscrow { reservation_deposit: data_reservation_deposit, .. } => {
+            *data_reservation_deposit = reservation_deposit
+        }
+        _ => return Err(InvalidInstruction.into()),
+    }
+    instruction.data = data.pack();
+    Ok(instruction)
+}
+
+/// Restricts the takers of the escrow of an instruction built by init_escrow, init_escrow_with_deposit or one of their variants to the tree with the given root, see merkle::root. Takers then have to add their proof to Exchange with with_taker_proof.
+pub fn with_taker_merkle_root(mut instruction: Instruction, root: [u8; 32]) -> Result<Instruction, ProgramError> {
+    let instruction_data = std::mem::take(&mut instruction.data);
+    let mut data = EscrowInstruction::unpack(&instruction_data)?;
+    match &mut data {
+        EscrowInstruction::InitEscrow { taker_merkle_root, .. } => *taker_merkle_root = root,
+        _ => return Err(InvalidInstruction.into()),
+    }
+    instruction.data = data.pack();
+    Ok(instruction)
+}
+
+/// Adds the taker's merkle proof, see merkle::proof, to an instruction built by exchange or one of its variants.
+pub fn with_taker_proof(mut instruction: Instruction, proof: Vec<[u8; 32]>) -> Result<Instruction, ProgramError> {
+    let instruction_data = std::mem::take(&mut instruction.data);
+    let mut data = EscrowInstruction::unpack(&instruction_data)?;
+    match &mut data {
+        EscrowInstruction::Exchange { taker_proof, .. } => *taker_proof = Cow::Owned(proof),
+        _ => return Err(InvalidInstruction.into()),
+    }
+    instruction.data = data.pack();
+    Ok(instruction)
+}
+
+/// Names the relayer who submits an instruction built by exchange or one of its variants for the taker, and the tip the taker pays them in token Y. Appends the relayer, who has to sign, and, if there is a tip, their associated token account of token Y.
+pub fn with_relayer(mut instruction: Instruction, relayer: &Pubkey, tip: u64) -> Result<Instruction, ProgramError> {
+    let instruction_data = std::mem::take(&mut instruction.data);
+    let mut data = EscrowInstruction::unpack(&instruction_data)?;
+    match &mut data {
+        EscrowInstruction::Exchange { relayer: data_relayer, relayer_tip, .. } => {
+            *data_relayer = *relayer;
+            *relayer_tip = tip;
+        }
+        _ => return Err(InvalidInstruction.into()),
+    }
+    instruction.data = data.pack();
+    instruction.accounts.push(AccountMeta::new(*relayer, true));
+    if tip > 0 {
+        // Exchange passes the Y mint and the Y token program as accounts 10 and 11
+        let token_y_mint = instruction.accounts.get(10).ok_or(ProgramError::NotEnoughAccountKeys)?.pubkey;
+        let y_token_program = instruction.accounts.get(11).ok_or(ProgramError::NotEnoughAccountKeys)?.pubkey;
+        instruction.accounts.push(AccountMeta::new(
+            token::get_associated_token_address(relayer, &token_y_mint, &y_token_program),
+            false,
+        ));
+    }
+    Ok(instruction)
+}
+
+/// Turns an instruction built by exchange or one of its variants into a SettleSignedOrder of the order the initializer signed, see signed_order, and appends the instructions sysvar. signed_order::verify_instruction has to come right before it in the transaction.
+pub fn with_signed_order(
+    mut instruction: Instruction,
+    order_taker: &Pubkey,
+    price: u64,
+    order_expiry_unix_timestamp: i64,
+) -> Result<Instruction, ProgramError> {
+    let (amount, max_payment) = match EscrowInstruction::unpack(&instruction.data)? {
+        EscrowInstruction::Exchange { amount, max_payment, .. } => (amount, max_payment),
+        _ => return Err(InvalidInstruction.into()),
+    };
+    instruction.data = EscrowInstruction::SettleSignedOrder {
+        amount,
+        max_payment,
+        price,
+        order_taker: *order_taker,
+        order_expiry_unix_timestamp,
+    }
+    .pack();
+    instruction.accounts.push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+    Ok(instruction)
+}
+
+/// Appends the Metaplex metadata account of the NFT an instruction built by exchange takes, and the creators' token accounts of token Y its royalties are paid into, see metaplex::Royalties::shares.
+pub fn with_royalty_accounts(mut instruction: Instruction, nft_mint: &Pubkey, creators_token_accounts: &[Pubkey]) -> Instruction {
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(metaplex::find_metadata_address(nft_mint), false));
+    instruction
+        .accounts
+        .extend(creators_token_accounts.iter().map(|account| AccountMeta::new(*account, false)));
+    instruction
+}
+
+/// Appends the accounts Token Metadata needs to move a programmable NFT from `source_token_account` to `destination_token_account` to an instruction built by init_nft_escrow, exchange, cancel or reclaim_expired. `payer` pays for the accounts Token Metadata creates and signs. `rule_set` is the NFT's rule set, if it has one.
+pub fn with_programmable_nft_accounts(
+    mut instruction: Instruction,
+    payer: &Pubkey,
+    nft_mint: &Pubkey,
+    source_token_account: &Pubkey,
+    destination_token_account: &Pubkey,
+    rule_set: Option<&Pubkey>,
+) -> Instruction {
+    // The owners, the mint and the token program are already among the instruction's accounts
+    instruction.accounts.extend([
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*source_token_account, false),
+        AccountMeta::new(*destination_token_account, false),
+        AccountMeta::new(metaplex::find_metadata_address(nft_mint), false),
+        AccountMeta::new_readonly(metaplex::find_edition_address(nft_mint), false),
+        AccountMeta::new(metaplex::find_token_record_address(nft_mint, source_token_account), false),
+        AccountMeta::new(metaplex::find_token_record_address(nft_mint, destination_token_account), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(token::associated_token::id(), false),
+        AccountMeta::new_readonly(metaplex::id(), false),
+    ]);
+    if let Some(rule_set) = rule_set {
+        instruction.accounts.push(AccountMeta::new_readonly(metaplex::token_auth_rules::id(), false));
+        instruction.accounts.push(AccountMeta::new_readonly(*rule_set, false));
+    }
+    instruction
+}
+
+/// Adds a memo to an instruction built by exchange, cancel or one of their variants and appends the SPL Memo program, which records it once the instruction has gone through.
+pub fn with_memo(mut instruction: Instruction, memo: &str) -> Result<Instruction, ProgramError> {
+    let instruction_data = std::mem::take(&mut instruction.data);
+    let mut data = EscrowInstruction::unpack(&instruction_data)?;
+    match &mut data {
+        EscrowInstruction::Exchange { memo: data_memo, .. } | EscrowInstruction::Cancel { memo: data_memo } => {
+            *data_memo = Cow::Borrowed(memo)
+        }
+        _ => return Err(InvalidInstruction.into()),
+    }
+    instruction.data = data.pack();
+    instruction.accounts.push(AccountMeta::new_readonly(memo::id(), false));
+    Ok(instruction)
+}
+
+/// Creates an `UpdateEscrow` instruction.
+pub fn update_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    escrow_account: &Pubkey,
+    expected_amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::UpdateEscrow { expected_amount }.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*initializer, true),
+        AccountMeta::new(*escrow_account, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `ExtendDeadline` instruction.
+pub fn extend_deadline(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    escrow_account: &Pubkey,
+    expiry_unix_timestamp: i64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = update_escrow(program_id, initializer, escrow_account, 0)?;
+    instruction.data = EscrowInstruction::ExtendDeadline { expiry_unix_timestamp }.pack();
+    instruction.accounts.push(AccountMeta::new_readonly(find_config_address(program_id).0, false));
+    Ok(instruction)
+}
+
+/// Adds the token account the penalty is paid into to a Cancel instruction, for when the config charges one. It has to be a token account of the escrow's token X, owned by whoever Config::cancel_penalty_recipient names.
+pub fn with_cancel_penalty_account(mut instruction: Instruction, penalty_token_account: &Pubkey) -> Instruction {
+    instruction.accounts.push(AccountMeta::new(*penalty_token_account, false));
+    instruction
+}
+
+/// Creates a `Reserve` instruction.
+pub fn reserve(
+    program_id: &Pubkey,
+    taker: &Pubkey,
+    escrow_account: &Pubkey,
+    initializer: &Pubkey,
+    slots: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::Reserve { slots }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*taker, true),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new(*initializer, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `SetCancelPenalty` instruction.
+pub fn set_cancel_penalty(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    penalty_bps: u16,
+    penalty_amount: u64,
+    to_counterparty: bool,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = pause(program_id, admin)?;
+    instruction.data = EscrowInstruction::SetCancelPenalty { penalty_bps, penalty_amount, to_counterparty }.pack();
+    Ok(instruction)
+}
+
+/// Creates a `SetGateProgram` instruction.
+pub fn set_gate_program(program_id: &Pubkey, admin: &Pubkey, gate_program: &Pubkey) -> Result<Instruction, ProgramError> {
+    let mut instruction = pause(program_id, admin)?;
+    instruction.data = EscrowInstruction::SetGateProgram { gate_program: *gate_program }.pack();
+    Ok(instruction)
+}
+
+/// Creates a `TransferEscrow` instruction.
+pub fn transfer_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    escrow_account: &Pubkey,
+    new_initializer: &Pubkey,
+    token_to_receive_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::TransferEscrow.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*initializer, true),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(*new_initializer, false),
+        AccountMeta::new_readonly(*token_to_receive_account, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `SetPaymentOption` instruction. The option's mint is that of `token_to_receive_account`.
+pub fn set_payment_option(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    escrow_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::SetPaymentOption { amount }.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*initializer, true),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(*token_to_receive_account, false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        AccountMeta::new_readonly(find_allowlist_address(program_id).0, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `SetUsdPrice` instruction.
+pub fn set_usd_price(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    escrow_account: &Pubkey,
+    usd_amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = update_escrow(program_id, initializer, escrow_account, 0)?;
+    instruction.data = EscrowInstruction::SetUsdPrice { usd_amount }.pack();
+    Ok(instruction)
+}
+
+/// Appends what Exchange needs to pay a USD-priced escrow in a stablecoin to an instruction built by exchange or one of its variants: the stablecoin list and the stablecoin's `price_feed`. The instruction's token Y accounts have to be the stablecoin's, including the initializer's token account that receives it.
+pub fn with_stablecoin(mut instruction: Instruction, price_feed: &Pubkey) -> Instruction {
+    let stablecoins = find_stablecoins_address(&instruction.program_id).0;
+    instruction.accounts.push(AccountMeta::new_readonly(stablecoins, false));
+    instruction.accounts.push(AccountMeta::new_readonly(*price_feed, false));
+    instruction
+}
+
+/// Creates a `SetMaxExpiry` instruction.
+pub fn set_max_expiry(program_id: &Pubkey, admin: &Pubkey, max_expiry_seconds: i64) -> Result<Instruction, ProgramError> {
+    let mut instruction = pause(program_id, admin)?;
+    instruction.data = EscrowInstruction::SetMaxExpiry { max_expiry_seconds }.pack();
+    Ok(instruction)
+}
+
+/// Creates an `InitCollectionOffer` instruction. `temp_token_account` holds the payment.
+#[allow(clippy::too_many_arguments)]
+pub fn init_collection_offer(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    collection: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    expiry_unix_timestamp: i64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = init_escrow(
+        program_id,
+        initializer,
+        temp_token_account,
+        collection,
+        escrow_account,
+        token_program_id,
+        0,
+        expiry_unix_timestamp,
+        None,
+    )?;
+    instruction.data = EscrowInstruction::InitCollectionOffer { collection: *collection, expiry_unix_timestamp }.pack();
+    Ok(instruction)
+}
+
+/// Creates a `FillCollectionOffer` instruction. `token_program_id` and `token_x_mint` are those of the payment. `initializers_nft_account` has to exist, e.g. the initializer's associated token account created earlier in the same transaction.
+#[allow(clippy::too_many_arguments)]
+pub fn fill_collection_offer(
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    sellers_nft_account: &Pubkey,
+    sellers_token_to_receive_account: &Pubkey,
+    pdas_temp_token_account: &Pubkey,
+    initializers_main_account: &Pubkey,
+    initializers_nft_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    token_x_mint: &Pubkey,
+    nft_mint: &Pubkey,
+    nft_token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::FillCollectionOffer.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*seller, true),
+        AccountMeta::new(*sellers_nft_account, false),
+        AccountMeta::new(*sellers_token_to_receive_account, false),
+        AccountMeta::new(*pdas_temp_token_account, false),
+        AccountMeta::new(*initializers_main_account, false),
+        AccountMeta::new(*initializers_nft_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(find_escrow_authority_address(escrow_account, program_id).0, false),
+        AccountMeta::new_readonly(*token_x_mint, false),
+        AccountMeta::new_readonly(*nft_mint, false),
+        AccountMeta::new_readonly(*nft_token_program_id, false),
+        AccountMeta::new_readonly(metaplex::find_metadata_address(nft_mint), false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `InitLamportEscrow` instruction. The escrow's PDA, which receives the lamports, is derived from `escrow_account`.
+#[allow(clippy::too_many_arguments)]
+pub fn init_lamport_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    lamports: u64,
+    amount: u64,
+    expiry_unix_timestamp: i64,
+    allowed_taker: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::InitLamportEscrow {
+        lamports,
+        amount,
+        expiry_unix_timestamp,
+        allowed_taker: allowed_taker.copied().unwrap_or_default(),
+    }
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*initializer, true),
+        AccountMeta::new(find_escrow_authority_address(escrow_account, program_id).0, false),
+        AccountMeta::new_readonly(*token_to_receive_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        AccountMeta::new_readonly(find_allowlist_address(program_id).0, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `InitBuyEscrow` instruction. `temp_token_account` holds the payment and `token_to_receive_account` is the initializer's token account for the token they buy.
+#[allow(clippy::too_many_arguments)]
+pub fn init_buy_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    amount: u64,
+    expiry_unix_timestamp: i64,
+    allowed_taker: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = init_escrow(
+        program_id,
+        initializer,
+        temp_token_account,
+        token_to_receive_account,
+        escrow_account,
+        token_program_id,
+        amount,
+        expiry_unix_timestamp,
+        allowed_taker,
+    )?;
+    let allowed_taker = allowed_taker.copied().unwrap_or_default();
+    instruction.data = EscrowInstruction::InitBuyEscrow { amount, expiry_unix_timestamp, allowed_taker }.pack();
+    Ok(instruction)
+}
+
+/// Appends the config's gate program and the accounts of it the gate reads, see gate, to an instruction built by exchange, accept or place_bid.
+pub fn with_gate_accounts(mut instruction: Instruction, gate_program: &Pubkey, gate_accounts: &[AccountMeta]) -> Instruction {
+    instruction.accounts.push(AccountMeta::new_readonly(*gate_program, false));
+    instruction.accounts.extend_from_slice(gate_accounts);
+    instruction
+}
+
+/// Creates an `InitRing` instruction. `legs` are the participant, the mint they give and the amount of every leg, in ring order: each participant pays the next one, the last one the first.
+pub fn init_ring(
+    program_id: &Pubkey,
+    creator: &Pubkey,
+    ring_id: u64,
+    deadline_unix_timestamp: i64,
+    legs: &[(Pubkey, Pubkey, u64)],
+) -> Result<Instruction, ProgramError> {
+    let amounts = legs.iter().map(|(_, _, amount)| *amount).collect();
+    let data = EscrowInstruction::InitRing { ring_id, deadline_unix_timestamp, amounts }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*creator, true),
+        AccountMeta::new(find_ring_address(creator, ring_id, program_id).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    for (participant, mint, _) in legs {
+        accounts.push(AccountMeta::new_readonly(*participant, false));
+        accounts.push(AccountMeta::new_readonly(*mint, false));
+    }
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `DepositRingLeg` instruction for the leg at `leg_index`.
+pub fn deposit_ring_leg(
+    program_id: &Pubkey,
+    participant: &Pubkey,
+    participant_token_account: &Pubkey,
+    ring_account: &Pubkey,
+    leg_index: u8,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::DepositRingLeg.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*participant, true),
+        AccountMeta::new(*participant_token_account, false),
+        AccountMeta::new(*ring_account, false),
+        AccountMeta::new(find_ring_vault_address(ring_account, leg_index, program_id).0, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `ExecuteRing` instruction that pays every leg into the next participant's associated token account of the leg's mint, which has to exist. `token_program_ids` are the token programs of the legs' mints, in the order of the legs.
+pub fn execute_ring(
+    program_id: &Pubkey,
+    ring_account: &Pubkey,
+    ring: &SwapRing,
+    token_program_ids: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    if token_program_ids.len() != ring.legs().len() {
+        return Err(InvalidInstruction.into());
+    }
+    let data = EscrowInstruction::ExecuteRing.pack();
+
+    let mut accounts = vec![AccountMeta::new(*ring_account, false), AccountMeta::new(ring.creator, false)];
+    for (index, (leg, token_program_id)) in ring.legs().iter().zip(token_program_ids).enumerate() {
+        let recipient = ring.recipient_of(index);
+        accounts.extend([
+            AccountMeta::new(find_ring_vault_address(ring_account, index as u8, program_id).0, false),
+            AccountMeta::new(token::get_associated_token_address(&recipient, &leg.mint, token_program_id), false),
+            AccountMeta::new_readonly(leg.mint, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new(leg.participant, false),
+        ]);
+    }
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `WithdrawRingLeg` instruction for the leg at `leg_index`.
+pub fn withdraw_ring_leg(
+    program_id: &Pubkey,
+    participant: &Pubkey,
+    participant_token_account: &Pubkey,
+    ring_account: &Pubkey,
+    leg_index: u8,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = deposit_ring_leg(
+        program_id,
+        participant,
+        participant_token_account,
+        ring_account,
+        leg_index,
+        mint,
+        token_program_id,
+    )?;
+    instruction.accounts.pop();
+    instruction.data = EscrowInstruction::WithdrawRingLeg.pack();
+    Ok(instruction)
+}
+
+/// Creates a `CloseRing` instruction.
+pub fn close_ring(program_id: &Pubkey, creator: &Pubkey, ring_account: &Pubkey) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::CloseRing.pack();
+
+    let accounts = vec![AccountMeta::new(*creator, true), AccountMeta::new(*ring_account, false)];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `PostQuote` instruction. Set `lock_funds` to move the price into the quote's vault.
+#[allow(clippy::too_many_arguments)]
+pub fn post_quote(
+    program_id: &Pubkey,
+    quoter: &Pubkey,
+    escrow_account: &Pubkey,
+    source_token_account: &Pubkey,
+    receive_token_account: &Pubkey,
+    token_y_mint: &Pubkey,
+    token_y_program_id: &Pubkey,
+    size: u64,
+    price: u64,
+    expiry_unix_timestamp: i64,
+    lock_funds: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::PostQuote { size, price, expiry_unix_timestamp, lock_funds }.pack();
+    let (quote, _bump_seed) = find_quote_address(escrow_account, quoter, program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(*quoter, true),
+        AccountMeta::new_readonly(*escrow_account, false),
+        AccountMeta::new(quote, false),
+        AccountMeta::new(*source_token_account, false),
+        AccountMeta::new_readonly(*receive_token_account, false),
+        AccountMeta::new_readonly(*token_y_mint, false),
+        AccountMeta::new_readonly(*token_y_program_id, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    if lock_funds {
+        accounts.push(AccountMeta::new(find_quote_vault_address(&quote, program_id).0, false));
+    }
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `AcceptQuote` instruction for `quoter`'s quote on the escrow. `is_locked` tells whether the quote was posted with its funds locked.
+#[allow(clippy::too_many_arguments)]
+pub fn accept_quote(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    escrow_account: &Pubkey,
+    temp_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    quoter: &Pubkey,
+    quoters_token_y_account: &Pubkey,
+    quoters_token_x_account: &Pubkey,
+    is_locked: bool,
+    token_x_program_id: &Pubkey,
+    token_y_program_id: &Pubkey,
+    token_x_mint: &Pubkey,
+    token_y_mint: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::AcceptQuote.pack();
+    let (pda, _bump_seed) = find_escrow_authority_address(escrow_account, program_id);
+    let (quote, _bump_seed) = find_quote_address(escrow_account, quoter, program_id);
+    let payment_source = if is_locked {
+        find_quote_vault_address(&quote, program_id).0
+    } else {
+        *quoters_token_y_account
+    };
+
+    let accounts = vec![
+        AccountMeta::new(*initializer, true),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new(*temp_token_account, false),
+        AccountMeta::new(*token_to_receive_account, false),
+        AccountMeta::new_readonly(pda, false),
+        AccountMeta::new(quote, false),
+        AccountMeta::new(*quoter, false),
+        AccountMeta::new(payment_source, false),
+        AccountMeta::new(*quoters_token_x_account, false),
+        AccountMeta::new_readonly(*token_x_program_id, false),
+        AccountMeta::new_readonly(*token_y_program_id, false),
+        AccountMeta::new_readonly(*token_x_mint, false),
+        AccountMeta::new_readonly(*token_y_mint, false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `CancelQuote` instruction.
+pub fn cancel_quote(
+    program_id: &Pubkey,
+    quoter: &Pubkey,
+    escrow_account: &Pubkey,
+    source_token_account: &Pubkey,
+    token_y_mint: &Pubkey,
+    token_y_program_id: &Pubkey,
+    is_locked: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::CancelQuote.pack();
+    let (quote, _bump_seed) = find_quote_address(escrow_account, quoter, program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(*quoter, true),
+        AccountMeta::new(quote, false),
+        AccountMeta::new(*source_token_account, false),
+        AccountMeta::new_readonly(*token_y_mint, false),
+        AccountMeta::new_readonly(*token_y_program_id, false),
+    ];
+    if is_locked {
+        accounts.push(AccountMeta::new(find_quote_vault_address(&quote, program_id).0, false));
+    }
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `ProposeCounteroffer` instruction. An `amount` of 0 withdraws the taker's counteroffer.
+#[allow(clippy::too_many_arguments)]
+pub fn propose_counteroffer(
+    program_id: &Pubkey,
+    taker: &Pubkey,
+    escrow_account: &Pubkey,
+    payment_token_account: &Pubkey,
+    receive_token_account: &Pubkey,
+    token_y_program_id: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::ProposeCounteroffer { amount }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*taker, true),
+        AccountMeta::new_readonly(*escrow_account, false),
+        AccountMeta::new(find_counteroffer_address(escrow_account, taker, program_id).0, false),
+        AccountMeta::new(*payment_token_account, false),
+        AccountMeta::new_readonly(*receive_token_account, false),
+        AccountMeta::new_readonly(*token_y_program_id, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `AcceptCounteroffer` instruction for `taker`'s counteroffer, whose token accounts are `takers_token_y_account` and `takers_token_x_account`.
+#[allow(clippy::too_many_arguments)]
+pub fn accept_counteroffer(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    escrow_account: &Pubkey,
+    temp_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    taker: &Pubkey,
+    takers_token_y_account: &Pubkey,
+    takers_token_x_account: &Pubkey,
+    token_x_program_id: &Pubkey,
+    token_y_program_id: &Pubkey,
+    token_x_mint: &Pubkey,
+    token_y_mint: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = accept_quote(
+        program_id,
+        initializer,
+        escrow_account,
+        temp_token_account,
+        token_to_receive_account,
+        taker,
+        takers_token_y_account,
+        takers_token_x_account,
+        false,
+        token_x_program_id,
+        token_y_program_id,
+        token_x_mint,
+        token_y_mint,
+    )?;
+    instruction.accounts[5] = AccountMeta::new(find_counteroffer_address(escrow_account, taker, program_id).0, false);
+    instruction.data = EscrowInstruction::AcceptCounteroffer.pack();
+    Ok(instruction)
+}
+
+/// Creates an `InitSealedAuction` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn init_sealed_auction(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    min_bid: u64,
+    deposit: u64,
+    commit_end_unix_timestamp: i64,
+    reveal_end_unix_timestamp: i64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = init_escrow(
+        program_id,
+        initializer,
+        temp_token_account,
+        token_to_receive_account,
+        escrow_account,
+        token_program_id,
+        min_bid,
+        0,
+        None,
+    )?;
+    instruction.accounts[0] = AccountMeta::new(*initializer, true);
+    instruction.accounts.extend([
+        AccountMeta::new(find_sealed_auction_address(escrow_account, program_id).0, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ]);
+    instruction.data = EscrowInstruction::InitSealedAuction {
+        min_bid,
+        deposit,
+        commit_end_unix_timestamp,
+        reveal_end_unix_timestamp,
+    }
+    .pack();
+    Ok(instruction)
+}
+
+/// Creates a `CommitBid` instruction. Build the commitment with `sealed_auction::commitment` and keep the salt, which has to be random, for `reveal_bid`.
+pub fn commit_bid(
+    program_id: &Pubkey,
+    bidder: &Pubkey,
+    bidders_token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_y_mint: &Pubkey,
+    token_y_program_id: &Pubkey,
+    commitment: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::CommitBid { commitment }.pack();
+    let (auction, _bump_seed) = find_sealed_auction_address(escrow_account, program_id);
+    let (sealed_bid, _bump_seed) = find_sealed_bid_address(&auction, bidder, program_id);
+
+    let accounts = vec![
+        AccountMeta::new(*bidder, true),
+        AccountMeta::new(*bidders_token_account, false),
+        AccountMeta::new_readonly(*escrow_account, false),
+        AccountMeta::new(auction, false),
+        AccountMeta::new(sealed_bid, false),
+        AccountMeta::new(find_sealed_bid_vault_address(&sealed_bid, program_id).0, false),
+        AccountMeta::new_readonly(*token_y_mint, false),
+        AccountMeta::new_readonly(*token_y_program_id, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `RevealBid` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn reveal_bid(
+    program_id: &Pubkey,
+    bidder: &Pubkey,
+    bidders_token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_y_mint: &Pubkey,
+    token_y_program_id: &Pubkey,
+    amount: u64,
+    salt: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::RevealBid { amount, salt }.pack();
+    let (auction, _bump_seed) = find_sealed_auction_address(escrow_account, program_id);
+    let (sealed_bid, _bump_seed) = find_sealed_bid_address(&auction, bidder, program_id);
+
+    let accounts = vec![
+        AccountMeta::new(*bidder, true),
+        AccountMeta::new(*bidders_token_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new(auction, false),
+        AccountMeta::new(sealed_bid, false),
+        AccountMeta::new(find_sealed_bid_vault_address(&sealed_bid, program_id).0, false),
+        AccountMeta::new(find_bid_address(escrow_account, bidder, program_id).0, false),
+        AccountMeta::new_readonly(find_escrow_authority_address(escrow_account, program_id).0, false),
+        AccountMeta::new_readonly(*token_y_mint, false),
+        AccountMeta::new_readonly(*token_y_program_id, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `SlashBid` instruction. `beneficiary_token_account` is the auction's, see `SealedAuction::beneficiary_token_account`.
+pub fn slash_bid(
+    program_id: &Pubkey,
+    escrow_account: &Pubkey,
+    bidder: &Pubkey,
+    beneficiary_token_account: &Pubkey,
+    token_y_mint: &Pubkey,
+    token_y_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::SlashBid.pack();
+    let (auction, _bump_seed) = find_sealed_auction_address(escrow_account, program_id);
+    let (sealed_bid, _bump_seed) = find_sealed_bid_address(&auction, bidder, program_id);
+
+    let accounts = vec![
+        AccountMeta::new(auction, false),
+        AccountMeta::new(sealed_bid, false),
+        AccountMeta::new(*bidder, false),
+        AccountMeta::new(find_sealed_bid_vault_address(&sealed_bid, program_id).0, false),
+        AccountMeta::new(*beneficiary_token_account, false),
+        AccountMeta::new_readonly(*token_y_mint, false),
+        AccountMeta::new_readonly(*token_y_program_id, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `CloseSealedAuction` instruction.
+pub fn close_sealed_auction(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    escrow_account: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::CloseSealedAuction.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*initializer, true),
+        AccountMeta::new_readonly(*escrow_account, false),
+        AccountMeta::new(find_sealed_auction_address(escrow_account, program_id).0, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `InitRaffle` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn init_raffle(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    temp_token_account: &Pubkey,
+    token_to_receive_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program_id: &Pubkey,
+    token_y_mint: &Pubkey,
+    token_y_program_id: &Pubkey,
+    ticket_price: u64,
+    max_tickets: u32,
+    end_unix_timestamp: i64,
+    vrf: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = init_escrow(
+        program_id,
+        initializer,
+        temp_token_account,
+        token_to_receive_account,
+        escrow_account,
+        token_program_id,
+        ticket_price,
+        0,
+        None,
+    )?;
+    let (raffle, _bump_seed) = find_raffle_address(escrow_account, program_id);
+    instruction.accounts[0] = AccountMeta::new(*initializer, true);
+    instruction.accounts.extend([
+        AccountMeta::new(raffle, false),
+        AccountMeta::new(find_raffle_vault_address(&raffle, program_id).0, false),
+        AccountMeta::new_readonly(*token_y_mint, false),
+        AccountMeta::new_readonly(*token_y_program_id, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ]);
+    instruction.data = EscrowInstruction::InitRaffle {
+        ticket_price,
+        max_tickets,
+        end_unix_timestamp,
+        vrf: vrf.copied().unwrap_or_default(),
+    }
+    .pack();
+    Ok(instruction)
+}
+
+/// Creates a `BuyTickets` instruction. `tickets_sold` is the raffle's, which numbers the new ticket account, so the purchase fails if someone else buys first.
+#[allow(clippy::too_many_arguments)]
+pub fn buy_tickets(
+    program_id: &Pubkey,
+    buyer: &Pubkey,
+    buyers_token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_y_mint: &Pubkey,
+    token_y_program_id: &Pubkey,
+    tickets_sold: u32,
+    count: u32,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::BuyTickets { count }.pack();
+    let (raffle, _bump_seed) = find_raffle_address(escrow_account, program_id);
+
+    let accounts = vec![
+        AccountMeta::new(*buyer, true),
+        AccountMeta::new(*buyers_token_account, false),
+        AccountMeta::new(*escrow_account, false),
+        AccountMeta::new(raffle, false),
+        AccountMeta::new(find_ticket_address(&raffle, tickets_sold, program_id).0, false),
+        AccountMeta::new(find_raffle_vault_address(&raffle, program_id).0, false),
+        AccountMeta::new_readonly(*token_y_mint, false),
+        AccountMeta::new_readonly(*token_y_program_id, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `DrawRaffle` instruction.
+pub fn draw_raffle(program_id: &Pubkey, escrow_account: &Pubkey) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::DrawRaffle.pack();
+    let accounts = vec![AccountMeta::new(find_raffle_address(escrow_account, program_id).0, false)];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `SettleRaffle` instruction. The winning ticket is found off-chain with `raffle::draw_hash` and `raffle::winning_ticket`, and `winning_ticket_account` is the ticket account whose range holds it.
+#[allow(clippy::too_many_arguments)]
+pub fn settle_raffle(
+    program_id: &Pubkey,
+    raffle: &Raffle,
+    temp_token_account: &Pubkey,
+    initializer: &Pubkey,
+    winning_ticket_account: &Pubkey,
+    winner: &Pubkey,
+    winners_token_account: &Pubkey,
+    token_x_program_id: &Pubkey,
+    token_y_program_id: &Pubkey,
+    token_x_mint: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::SettleRaffle.pack();
+    let (raffle_account, _bump_seed) = find_raffle_address(&raffle.escrow, program_id);
+
+    let accounts = vec![
+        AccountMeta::new(raffle_account, false),
+        AccountMeta::new(raffle.escrow, false),
+        AccountMeta::new(*temp_token_account, false),
+        AccountMeta::new_readonly(find_escrow_authority_address(&raffle.escrow, program_id).0, false),
+        AccountMeta::new(find_raffle_vault_address(&raffle_account, program_id).0, false),
+        AccountMeta::new(raffle.proceeds_token_account, false),
+        AccountMeta::new(*initializer, false),
+        AccountMeta::new(*winning_ticket_account, false),
+        AccountMeta::new(*winner, false),
+        AccountMeta::new(*winners_token_account, false),
+        AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        AccountMeta::new_readonly(*token_x_program_id, false),
+        AccountMeta::new_readonly(*token_y_program_id, false),
+        AccountMeta::new_readonly(*token_x_mint, false),
+        AccountMeta::new_readonly(raffle.token_y_mint, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `CloseRaffle` instruction.
+pub fn close_raffle(
+    program_id: &Pubkey,
+    raffle: &Raffle,
+    token_y_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::CloseRaffle.pack();
+    let (raffle_account, _bump_seed) = find_raffle_address(&raffle.escrow, program_id);
+
+    let accounts = vec![
+        AccountMeta::new(raffle.initializer, true),
+        AccountMeta::new_readonly(raffle.escrow, false),
+        AccountMeta::new(raffle_account, false),
+        AccountMeta::new(find_raffle_vault_address(&raffle_account, program_id).0, false),
+        AccountMeta::new(raffle.proceeds_token_account, false),
+        AccountMeta::new_readonly(raffle.token_y_mint, false),
+        AccountMeta::new_readonly(*token_y_program_id, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `RequestRaffleRandomness` instruction for a raffle drawn with VRF. The Switchboard accounts are the ones `switchboard::request_randomness` takes.
+#[allow(clippy::too_many_arguments)]
+pub fn request_raffle_randomness(
+    program_id: &Pubkey,
+    raffle: &Raffle,
+    oracle_queue: &Pubkey,
+    queue_authority: &Pubkey,
+    data_buffer: &Pubkey,
+    permission: &Pubkey,
+    vrf_escrow: &Pubkey,
+    payer_wallet: &Pubkey,
+    payer: &Pubkey,
+    program_state: &Pubkey,
+    permission_bump: u8,
+    state_bump: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::RequestRaffleRandomness { permission_bump, state_bump }.pack();
+    let (raffle_account, _bump_seed) = find_raffle_address(&raffle.escrow, program_id);
+    let mut accounts = switchboard::request_randomness(
+        &raffle_account,
+        &raffle.vrf,
+        oracle_queue,
+        queue_authority,
+        data_buffer,
+        permission,
+        vrf_escrow,
+        payer_wallet,
+        payer,
+        program_state,
+        permission_bump,
+        state_bump,
+    )
+    .accounts;
+    // The raffle signs the request itself
+    accounts[0] = AccountMeta::new(raffle_account, false);
+    accounts.push(AccountMeta::new_readonly(switchboard::id(), false));
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `ConsumeRaffleRandomness` instruction. Its accounts are also what the VRF's callback has to be set up with.
+pub fn consume_raffle_randomness(program_id: &Pubkey, raffle: &Raffle) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::ConsumeRaffleRandomness.pack();
+    let accounts = vec![
+        AccountMeta::new(find_raffle_address(&raffle.escrow, program_id).0, false),
+        AccountMeta::new_readonly(raffle.vrf, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `InitEscrowPool` instruction. The pool account has to be created first, e.g. with `system_instruction::create_account` for `escrow_pool::EscrowPool::space(capacity)` bytes owned by the program, in the same transaction.
+#[allow(clippy::too_many_arguments)]
+pub fn init_escrow_pool(
+    program_id: &Pubkey,
+    maker: &Pubkey,
+    pool_account: &Pubkey,
+    makers_token_to_receive_account: &Pubkey,
+    token_x_mint: &Pubkey,
+    token_y_mint: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::InitEscrowPool.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*maker, true),
+        AccountMeta::new(*pool_account, false),
+        AccountMeta::new(find_escrow_pool_vault_address(pool_account, program_id).0, false),
+        AccountMeta::new_readonly(*makers_token_to_receive_account, false),
+        AccountMeta::new_readonly(*token_x_mint, false),
+        AccountMeta::new_readonly(*token_y_mint, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        AccountMeta::new_readonly(find_allowlist_address(program_id).0, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `PostPoolEntry` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn post_pool_entry(
+    program_id: &Pubkey,
+    pool_account: &Pubkey,
+    pool: &EscrowPool,
+    makers_token_account: &Pubkey,
+    token_program_id: &Pubkey,
+    amount_x: u64,
+    amount_y: u64,
+    expiry_unix_timestamp: i64,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::PostPoolEntry { amount_x, amount_y, expiry_unix_timestamp }.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(pool.maker, true),
+        AccountMeta::new(*makers_token_account, false),
+        AccountMeta::new(*pool_account, false),
+        AccountMeta::new(find_escrow_pool_vault_address(pool_account, program_id).0, false),
+        AccountMeta::new_readonly(pool.token_x_mint, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `TakePoolEntry` instruction. `fee_vault` is only needed if the config charges a fee.
+#[allow(clippy::too_many_arguments)]
+pub fn take_pool_entry(
+    program_id: &Pubkey,
+    taker: &Pubkey,
+    takers_sending_token_account: &Pubkey,
+    takers_token_to_receive_account: &Pubkey,
+    pool_account: &Pubkey,
+    pool: &EscrowPool,
+    token_x_program_id: &Pubkey,
+    token_y_program_id: &Pubkey,
+    fee_vault: Option<&Pubkey>,
+    index: u32,
+    amount_x: u64,
+    max_payment: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::TakePoolEntry { index, amount_x, max_payment }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*taker, true),
+        AccountMeta::new(*takers_sending_token_account, false),
+        AccountMeta::new(*takers_token_to_receive_account, false),
+        AccountMeta::new(*pool_account, false),
+        AccountMeta::new(find_escrow_pool_vault_address(pool_account, program_id).0, false),
+        AccountMeta::new_readonly(find_escrow_pool_authority_address(pool_account, program_id).0, false),
+        AccountMeta::new(pool.maker_token_to_receive_account, false),
+        AccountMeta::new_readonly(pool.token_x_mint, false),
+        AccountMeta::new_readonly(pool.token_y_mint, false),
+        AccountMeta::new_readonly(*token_x_program_id, false),
+        AccountMeta::new_readonly(*token_y_program_id, false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+    ];
+    if let Some(fee_vault) = fee_vault {
+        accounts.push(AccountMeta::new(*fee_vault, false));
+    }
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `CancelPoolEntry` instruction.
+pub fn cancel_pool_entry(
+    program_id: &Pubkey,
+    pool_account: &Pubkey,
+    pool: &EscrowPool,
+    makers_token_account: &Pubkey,
+    token_program_id: &Pubkey,
+    index: u32,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::CancelPoolEntry { index }.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(pool.maker, true),
+        AccountMeta::new(*makers_token_account, false),
+        AccountMeta::new(*pool_account, false),
+        AccountMeta::new(find_escrow_pool_vault_address(pool_account, program_id).0, false),
+        AccountMeta::new_readonly(find_escrow_pool_authority_address(pool_account, program_id).0, false),
+        AccountMeta::new_readonly(pool.token_x_mint, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `CloseEscrowPool` instruction.
+pub fn close_escrow_pool(
+    program_id: &Pubkey,
+    pool_account: &Pubkey,
+    pool: &EscrowPool,
+    makers_token_account: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::CloseEscrowPool.pack();
+
+    let accounts = vec![
+        AccountMeta::new(pool.maker, true),
+        AccountMeta::new(*makers_token_account, false),
+        AccountMeta::new(*pool_account, false),
+        AccountMeta::new(find_escrow_pool_vault_address(pool_account, program_id).0, false),
+        AccountMeta::new_readonly(find_escrow_pool_authority_address(pool_account, program_id).0, false),
+        AccountMeta::new_readonly(pool.token_x_mint, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `InitCompressedTree` instruction. The merkle tree account has to be created first, zeroed and owned by `compression::id()`, in the same transaction.
+pub fn init_compressed_tree(
+    program_id: &Pubkey,
+    creator: &Pubkey,
+    merkle_tree: &Pubkey,
+    max_depth: u32,
+    max_buffer_size: u32,
+    canopy_depth: u32,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::InitCompressedTree { max_depth, max_buffer_size, canopy_depth }.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*creator, true),
+        AccountMeta::new(*merkle_tree, false),
+        AccountMeta::new(find_compressed_tree_address(merkle_tree, program_id).0, false),
+        AccountMeta::new_readonly(compression::id(), false),
+        AccountMeta::new_readonly(compression::noop::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `InitCompressedEscrow` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn init_compressed_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    initializers_token_account: &Pubkey,
+    initializers_token_to_receive_account: &Pubkey,
+    merkle_tree: &Pubkey,
+    token_x_mint: &Pubkey,
+    token_y_mint: &Pubkey,
+    token_program_id: &Pubkey,
+    amount_x: u64,
+    amount_y: u64,
+    expiry_unix_timestamp: i64,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::InitCompressedEscrow { amount_x, amount_y, expiry_unix_timestamp }.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*initializer, true),
+        AccountMeta::new(*initializers_token_account, false),
+        AccountMeta::new_readonly(*initializers_token_to_receive_account, false),
+        AccountMeta::new(find_compressed_tree_address(merkle_tree, program_id).0, false),
+        AccountMeta::new(*merkle_tree, false),
+        AccountMeta::new_readonly(*token_x_mint, false),
+        AccountMeta::new_readonly(*token_y_mint, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(compression::id(), false),
+        AccountMeta::new_readonly(compression::noop::id(), false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+        AccountMeta::new_readonly(find_allowlist_address(program_id).0, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates an `ExchangeCompressed` instruction. `proof` are the nodes from the escrow's leaf up to the tree's canopy, and `fee_vault` is only needed if the config charges a fee.
+#[allow(clippy::too_many_arguments)]
+pub fn exchange_compressed(
+    program_id: &Pubkey,
+    taker: &Pubkey,
+    takers_sending_token_account: &Pubkey,
+    takers_token_to_receive_account: &Pubkey,
+    merkle_tree: &Pubkey,
+    escrow: &CompressedEscrow,
+    token_x_program_id: &Pubkey,
+    token_y_program_id: &Pubkey,
+    fee_vault: Option<&Pubkey>,
+    root: [u8; 32],
+    proof: &[Pubkey],
+    max_payment: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::ExchangeCompressed { escrow: *escrow, root, max_payment }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*taker, true),
+        AccountMeta::new(*takers_sending_token_account, false),
+        AccountMeta::new(*takers_token_to_receive_account, false),
+        AccountMeta::new(escrow.initializer_token_account, false),
+        AccountMeta::new(escrow.initializer_token_to_receive_account, false),
+        AccountMeta::new_readonly(find_compressed_tree_address(merkle_tree, program_id).0, false),
+        AccountMeta::new(*merkle_tree, false),
+        AccountMeta::new_readonly(escrow.token_x_mint, false),
+        AccountMeta::new_readonly(escrow.token_y_mint, false),
+        AccountMeta::new_readonly(*token_x_program_id, false),
+        AccountMeta::new_readonly(*token_y_program_id, false),
+        AccountMeta::new_readonly(compression::id(), false),
+        AccountMeta::new_readonly(compression::noop::id(), false),
+        AccountMeta::new_readonly(find_config_address(program_id).0, false),
+    ];
+    if let Some(fee_vault) = fee_vault {
+        accounts.push(AccountMeta::new(*fee_vault, false));
+    }
+    accounts.extend(proof.iter().map(|node| AccountMeta::new_readonly(*node, false)));
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `CancelCompressed` instruction. `proof` is the same as for `exchange_compressed`.
+pub fn cancel_compressed(
+    program_id: &Pubkey,
+    merkle_tree: &Pubkey,
+    escrow: &CompressedEscrow,
+    token_program_id: &Pubkey,
+    root: [u8; 32],
+    proof: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::CancelCompressed { escrow: *escrow, root }.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(escrow.initializer, true),
+        AccountMeta::new(escrow.initializer_token_account, false),
+        AccountMeta::new_readonly(find_compressed_tree_address(merkle_tree, program_id).0, false),
+        AccountMeta::new(*merkle_tree, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(compression::id(), false),
+        AccountMeta::new_readonly(compression::noop::id(), false),
+    ];
+    accounts.extend(proof.iter().map(|node| AccountMeta::new_readonly(*node, false)));
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+/// Creates a `CloseTicket` instruction for the ticket account starting at `first_ticket`.
+pub fn close_ticket(
+    program_id: &Pubkey,
+    buyer: &Pubkey,
+    escrow_account: &Pubkey,
+    first_ticket: u32,
+) -> Result<Instruction, ProgramError> {
+    let data = EscrowInstruction::CloseTicket.pack();
+    let (raffle, _bump_seed) = find_raffle_address(escrow_account, program_id);
+
+    let accounts = vec![
+        AccountMeta::new(*buyer, true),
+        AccountMeta::new(find_ticket_address(&raffle, first_ticket, program_id).0, false),
+        AccountMeta::new_readonly(raffle, false),
+    ];
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borsh_encoding_matches_pack() {
+        let instructions = vec![
+            EscrowInstruction::InitEscrow {
+                amount: 42,
+                expiry_unix_timestamp: 1_700_000_000,
+                allowed_taker: Pubkey::new_unique(),
+                deposit_amount: 1_000,
+                dispute_window_seconds: 86_400,
+                metadata: Cow::Borrowed(b"listing 42"),
+                escrow_id: 7,
+                reservation_deposit: 5_000_000,
+                taker_merkle_root: [3; 32],
+            },
+            EscrowInstruction::Exchange {
+                amount: 42,
+                max_payment: 1_000,
+                referrer_token_account: Pubkey::new_unique(),
+                memo: Cow::Borrowed("order 42"),
+                taker_proof: Cow::Borrowed(&[[1; 32], [2; 32]]),
+                relayer: Pubkey::new_unique(),
+                relayer_tip: 10,
+            },
+            EscrowInstruction::Cancel { memo: Cow::Borrowed("") },
+            EscrowInstruction::ReclaimExpired,
+            EscrowInstruction::InitNftEscrow {
+                amount: 42,
+                expiry_unix_timestamp: 0,
+                allowed_taker: Pubkey::default(),
+            },
+            EscrowInstruction::InitFeeVault,
+            EscrowInstruction::InitializeConfig {
+                fee_bps: 30,
+                default_expiry_seconds: 86_400,
+                referral_share_bps: 2_000,
+            },
+            EscrowInstruction::Pause,
+            EscrowInstruction::Unpause,
+            EscrowInstruction::AddAllowedMint { mint: Pubkey::new_unique() },
+            EscrowInstruction::RemoveAllowedMint { mint: Pubkey::new_unique() },
+            EscrowInstruction::SetAllowlistEnabled { enabled: true },
+            EscrowInstruction::UpdateEscrow { expected_amount: 42 },
+            EscrowInstruction::InitDutchAuction {
+                start_amount: 1_000,
+                floor_amount: 100,
+                auction_end_unix_timestamp: 1_700_000_000,
+                expiry_unix_timestamp: 0,
+            },
+            EscrowInstruction::InitEnglishAuction { min_bid: 100, auction_end_unix_timestamp: 1_700_000_000 },
+            EscrowInstruction::PlaceBid { amount: 42 },
+            EscrowInstruction::SettleAuction,
+            EscrowInstruction::WithdrawBid,
+            EscrowInstruction::InitOracleEscrow { premium_bps: -50, expiry_unix_timestamp: 0 },
+            EscrowInstruction::InitDelegatedEscrow {
+                amount_x: 1_000,
+                amount: 42,
+                expiry_unix_timestamp: 0,
+                allowed_taker: Pubkey::new_unique(),
+            },
+            EscrowInstruction::Accept { max_payment: 42 },
+            EscrowInstruction::Settle,
+            EscrowInstruction::SetApprovers { threshold: 2 },
+            EscrowInstruction::ApproveRelease,
+            EscrowInstruction::InitVestingEscrow {
+                amount: 42,
+                expiry_unix_timestamp: 0,
+                vesting_period_seconds: 86_400,
+                vesting_periods: 12,
+            },
+            EscrowInstruction::ClaimVested,
+            EscrowInstruction::InitStreamEscrow {
+                recipient: Pubkey::new_unique(),
+                stream_end_unix_timestamp: 1_700_000_000,
+            },
+            EscrowInstruction::Withdraw,
+            EscrowInstruction::MatchEscrows,
+            EscrowInstruction::InitMarket,
+            EscrowInstruction::PlaceOrder,
+            EscrowInstruction::CancelOrder,
+            EscrowInstruction::ConsumeOrders,
+            EscrowInstruction::InitRegistry,
+            EscrowInstruction::PruneRegistry,
+            EscrowInstruction::MigrateEscrow,
+            EscrowInstruction::CloseStaleEscrow,
+            EscrowInstruction::GetEscrow,
+            EscrowInstruction::InitRateEscrow {
+                price_numerator: 3,
+                price_denominator: 2,
+                expiry_unix_timestamp: 0,
+            },
+            EscrowInstruction::SetFee { fee_bps: 50, fee_collector: Pubkey::new_unique() },
+            EscrowInstruction::InitStats,
+            EscrowInstruction::Reserve { slots: 150 },
+            EscrowInstruction::SetCancelPenalty { penalty_bps: 100, penalty_amount: 5_000, to_counterparty: true },
+            EscrowInstruction::SetGateProgram { gate_program: Pubkey::new_unique() },
+            EscrowInstruction::InitCollectionOffer { collection: Pubkey::new_unique(), expiry_unix_timestamp: 0 },
+            EscrowInstruction::FillCollectionOffer,
+            EscrowInstruction::InitLamportEscrow {
+                lamports: 1_000_000_000,
+                amount: 42,
+                expiry_unix_timestamp: 0,
+                allowed_taker: Pubkey::new_unique(),
+            },
+            EscrowInstruction::InitBuyEscrow { amount: 42, expiry_unix_timestamp: 0, allowed_taker: Pubkey::new_unique() },
+            EscrowInstruction::Abort,
+            EscrowInstruction::ExtendDeadline { expiry_unix_timestamp: 1_700_000_000 },
+            EscrowInstruction::SetMaxExpiry { max_expiry_seconds: 30 * 24 * 60 * 60 },
+            EscrowInstruction::TransferEscrow,
+            EscrowInstruction::SettleSignedOrder {
+                amount: 42,
+                max_payment: 1_000,
+                price: 900,
+                order_taker: Pubkey::new_unique(),
+                order_expiry_unix_timestamp: 1_700_000_000,
+            },
+            EscrowInstruction::SetPaymentOption { amount: 99 },
+            EscrowInstruction::AddStablecoin {
+                mint: Pubkey::new_unique(),
+                price_feed: Pubkey::new_unique(),
+                tolerance_bps: 50,
+            },
+            EscrowInstruction::RemoveStablecoin { mint: Pubkey::new_unique() },
+            EscrowInstruction::SetUsdPrice { usd_amount: 150_000_000 },
+            EscrowInstruction::InitNftSwap { expiry_unix_timestamp: 0, allowed_taker: Pubkey::new_unique() },
+            EscrowInstruction::InitRing {
+                ring_id: 3,
+                deadline_unix_timestamp: 1_700_000_000,
+                amounts: vec![10, 20, 30],
+            },
+            EscrowInstruction::DepositRingLeg,
+            EscrowInstruction::ExecuteRing,
+            EscrowInstruction::WithdrawRingLeg,
+            EscrowInstruction::CloseRing,
+            EscrowInstruction::PostQuote {
+                size: 100,
+                price: 250,
+                expiry_unix_timestamp: 1_700_000_000,
+                lock_funds: true,
+            },
+            EscrowInstruction::AcceptQuote,
+            EscrowInstruction::CancelQuote,
+            EscrowInstruction::ProposeCounteroffer { amount: 900 },
+            EscrowInstruction::AcceptCounteroffer,
+            EscrowInstruction::InitSealedAuction {
+                min_bid: 500,
+                deposit: 50,
+                commit_end_unix_timestamp: 1_700_000_000,
+                reveal_end_unix_timestamp: 1_700_003_600,
+            },
+            EscrowInstruction::CommitBid { commitment: [3; 32] },
+            EscrowInstruction::RevealBid { amount: 750, salt: [4; 32] },
+            EscrowInstruction::SlashBid,
+            EscrowInstruction::CloseSealedAuction,
+            EscrowInstruction::InitRaffle {
+                ticket_price: 25,
+                max_tickets: 100,
+                end_unix_timestamp: 1_700_000_000,
+                vrf: Pubkey::new_unique(),
+            },
+            EscrowInstruction::BuyTickets { count: 4 },
+            EscrowInstruction::DrawRaffle,
+            EscrowInstruction::SettleRaffle,
+            EscrowInstruction::CloseRaffle,
+            EscrowInstruction::CloseTicket,
+            EscrowInstruction::RequestRaffleRandomness { permission_bump: 254, state_bump: 255 },
+            EscrowInstruction::ConsumeRaffleRandomness,
+            EscrowInstruction::InitEscrowPool,
+            EscrowInstruction::PostPoolEntry { amount_x: 10, amount_y: 20, expiry_unix_timestamp: 1_700_000_000 },
+            EscrowInstruction::TakePoolEntry { index: 3, amount_x: 10, max_payment: 21 },
+            EscrowInstruction::CancelPoolEntry { index: 3 },
+            EscrowInstruction::CloseEscrowPool,
+            EscrowInstruction::InitCompressedTree { max_depth: 20, max_buffer_size: 64, canopy_depth: 10 },
+            EscrowInstruction::InitCompressedEscrow { amount_x: 10, amount_y: 20, expiry_unix_timestamp: 0 },
+            EscrowInstruction::ExchangeCompressed {
+                escrow: CompressedEscrow {
+                    initializer: Pubkey::new_unique(),
+                    amount_x: 10,
+                    amount_y: 20,
+                    leaf_index: 5,
+                    ..CompressedEscrow::default()
+                },
+                root: [6; 32],
+                max_payment: 21,
+            },
+            EscrowInstruction::CancelCompressed {
+                escrow: CompressedEscrow { token_x_mint: Pubkey::new_unique(), ..CompressedEscrow::default() },
+                root: [7; 32],
+            },
+            EscrowInstruction::UpgradeEscrowAccount,
+        ];
+
+        for instruction in instructions {
+            let borsh_data = instruction.try_to_vec().unwrap();
+            assert_eq!(borsh_data, instruction.pack());
+            assert_eq!(EscrowInstruction::unpack(&borsh_data).unwrap(), instruction);
+        }
+    }
+
+    #[test]
+    fn memo_is_optional() {
+        // What clients sent before there were memos
+        assert_eq!(EscrowInstruction::unpack(&[2]).unwrap(), EscrowInstruction::Cancel { memo: Cow::Borrowed("") });
+        let exchange = EscrowInstruction::Exchange {
+            amount: 42,
+            max_payment: 1_000,
+            referrer_token_account: Pubkey::new_unique(),
+            memo: Cow::Borrowed(""),
+            taker_proof: Cow::Borrowed(&[]),
+            relayer: Pubkey::default(),
+            relayer_tip: 0,
+        };
+        assert_eq!(EscrowInstruction::unpack(&exchange.pack()[..49]).unwrap(), exchange);
+        assert_eq!(EscrowInstruction::unpack(&exchange.pack()[..53]).unwrap(), exchange);
+        assert_eq!(EscrowInstruction::unpack(&exchange.pack()[..57]).unwrap(), exchange);
+
+        let mut data = vec![2];
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&[0xff, 0xfe]);
+        assert_eq!(EscrowInstruction::unpack(&data), Err(InvalidInstruction.into()));
+    }
+
+    #[test]
+    fn relayer_follows_the_taker_proof() {
+        let exchange = EscrowInstruction::Exchange {
+            amount: 42,
+            max_payment: 1_000,
+            referrer_token_account: Pubkey::default(),
+            memo: Cow::Borrowed("order 42"),
+            taker_proof: Cow::Borrowed(&[[1; 32]]),
+            relayer: Pubkey::new_unique(),
+            relayer_tip: 10,
+        };
+        let data = exchange.pack();
+        assert_eq!(EscrowInstruction::unpack(&data).unwrap(), exchange);
+        assert!(matches!(
+            EscrowInstruction::unpack(&data),
+            Ok(EscrowInstruction::Exchange { memo: Cow::Borrowed(_), taker_proof: Cow::Borrowed(_), .. })
+        ));
+        // A relayer without the tip is cut short
+        assert_eq!(EscrowInstruction::unpack(&data[..data.len() - 8]), Err(InvalidInstruction.into()));
+        assert!(matches!(
+            EscrowInstruction::unpack(&data[..data.len() - 40]),
+            Ok(EscrowInstruction::Exchange { relayer, relayer_tip: 0, .. }) if relayer == Pubkey::default()
+        ));
+    }
+
+    #[test]
+    fn escrow_id_follows_the_metadata() {
+        let mut data = EscrowInstruction::InitEscrow {
+            amount: 42,
+            expiry_unix_timestamp: 0,
+            allowed_taker: Pubkey::default(),
+            deposit_amount: 0,
+            dispute_window_seconds: 0,
+            metadata: Cow::Borrowed(&[]),
+            escrow_id: 0,
+            reservation_deposit: 0,
+            taker_merkle_root: [0; 32],
+        }
+        .pack();
+        // Without an id, a reservation deposit or a taker merkle root
+        data.truncate(data.len() - 48);
+        assert!(matches!(EscrowInstruction::unpack(&data), Ok(EscrowInstruction::InitEscrow { escrow_id: 0, .. })));
+        data.extend_from_slice(&7u64.to_le_bytes());
+        assert!(matches!(
+            EscrowInstruction::unpack(&data),
+            Ok(EscrowInstruction::InitEscrow { escrow_id: 7, reservation_deposit: 0, .. })
+        ));
+        data.extend_from_slice(&9u64.to_le_bytes());
+        assert!(matches!(
+            EscrowInstruction::unpack(&data),
+            Ok(EscrowInstruction::InitEscrow { escrow_id: 7, reservation_deposit: 9, .. })
+        ));
+        data.extend_from_slice(&[5; 32]);
+        assert!(matches!(
+            EscrowInstruction::unpack(&data),
+            Ok(EscrowInstruction::InitEscrow { reservation_deposit: 9, taker_merkle_root: [5, ..], .. })
+        ));
+        data.pop();
+        assert_eq!(EscrowInstruction::unpack(&data), Err(InvalidInstruction.into()));
+    }
+
+    #[test]
+    fn anchor_discriminators() {
+        let names = [
+            "init_escrow",
+            "exchange",
+            "cancel",
+            "reclaim_expired",
+            "init_nft_escrow",
+            "init_fee_vault",
+            "initialize_config",
+            "pause",
+            "unpause",
+            "add_allowed_mint",
+            "remove_allowed_mint",
+            "set_allowlist_enabled",
+            "update_escrow",
+            "init_dutch_auction",
+            "init_english_auction",
+            "place_bid",
+            "settle_auction",
+            "withdraw_bid",
+            "init_oracle_escrow",
+            "init_delegated_escrow",
+            "accept",
+            "settle",
+            "set_approvers",
+            "approve_release",
+            "init_vesting_escrow",
+            "claim_vested",
+            "init_stream_escrow",
+            "withdraw",
+            "match_escrows",
+            "init_market",
+            "place_order",
+            "cancel_order",
+            "consume_orders",
+            "init_registry",
+            "prune_registry",
+            "migrate_escrow",
+            "close_stale_escrow",
+            "get_escrow",
+            "init_rate_escrow",
+            "set_fee",
+            "init_stats",
+            "reserve",
+            "set_cancel_penalty",
+            "set_gate_program",
+            "init_collection_offer",
+            "fill_collection_offer",
+            "init_lamport_escrow",
+            "init_buy_escrow",
+            "abort",
+            "extend_deadline",
+            "set_max_expiry",
+            "transfer_escrow",
+            "settle_signed_order",
+            "set_payment_option",
+            "add_stablecoin",
+            "remove_stablecoin",
+            "set_usd_price",
+            "init_nft_swap",
+            "init_ring",
+            "deposit_ring_leg",
+            "execute_ring",
+            "withdraw_ring_leg",
+            "close_ring",
+            "post_quote",
+            "accept_quote",
+            "cancel_quote",
+            "propose_counteroffer",
+            "accept_counteroffer",
+            "init_sealed_auction",
+            "commit_bid",
+            "reveal_bid",
+            "slash_bid",
+            "close_sealed_auction",
+            "init_raffle",
+            "buy_tickets",
+            "draw_raffle",
+            "settle_raffle",
+            "close_raffle",
+            "close_ticket",
+            "request_raffle_randomness",
+            "consume_raffle_randomness",
+            "init_escrow_pool",
+            "post_pool_entry",
+            "take_pool_entry",
+            "cancel_pool_entry",
+            "close_escrow_pool",
+            "init_compressed_tree",
+            "init_compressed_escrow",
+            "exchange_compressed",
+            "cancel_compressed",
+            "upgrade_escrow_account",
+        ];
+        for (name, (discriminator, tag)) in names.iter().zip(ANCHOR_DISCRIMINATORS.iter()) {
+            let sighash = solana_program::hash::hash(format!("global:{}", name).as_bytes());
+            assert_eq!(&sighash.to_bytes()[..8], discriminator);
+
+            // Long enough for the arguments of every instruction. A 1 for SetAllowlistEnabled's bool, one byte long memos for Cancel and Exchange, and an empty taker proof for Exchange, which is followed by the relayer and its tip. AddStablecoin ends in a tolerance after its two pubkeys, InitRing gets a single amount, and the compressed escrow instructions start with a whole escrow.
+            let mut args = vec![1u8; match tag { 1 => 97, 54 => 66, 88 | 89 => 228, _ => 64 }];
+            args[1..4].fill(0);
+            args[49..52].fill(0);
+            args[53..57].fill(0);
+            if *tag == 58 {
+                args[17..20].fill(0);
+            }
+            let mut data = discriminator.to_vec();
+            data.extend_from_slice(&args);
+            let mut tagged = vec![*tag];
+            tagged.extend_from_slice(&args);
+            assert_eq!(
+                EscrowInstruction::unpack(&data).unwrap(),
+                EscrowInstruction::unpack(&tagged).unwrap()
+            );
+        }
+    }
+}
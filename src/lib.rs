@@ -1,11 +1,201 @@
+/// Logs through msg! when the `log` feature is enabled (it is by default). Every msg! call costs compute units, so production deployments can build with `--no-default-features` to compile the logging out entirely.
+macro_rules! log {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        solana_program::msg!($($arg)*);
+    };
+}
+
+#[cfg(feature = "anchor")]
+pub mod anchor;
+#[cfg(feature = "cpi")]
+pub mod cpi;
+pub mod compressed_escrow;
+pub mod compression;
 pub mod error;
+pub mod escrow_pool;
+pub mod event;
+pub mod extension;
+pub mod gate;
 pub mod instruction;
+pub mod math;
+pub mod memo;
+pub mod merkle;
+pub mod metaplex;
+pub mod oracle;
+pub mod order_book;
 pub mod processor;
+pub mod raffle;
+pub mod sealed_auction;
+pub mod signed_order;
 pub mod state;
+pub mod strict;
+pub mod swap_ring;
+pub mod switchboard;
+pub mod token;
+pub mod transfer_hook;
+pub mod validation;
+pub mod vesting;
+pub mod view;
 
-#[cfg(not(feature = "no-entrypoint"))]
+#[cfg(not(any(feature = "no-entrypoint", feature = "anchor")))]
 pub mod entrypoint;
 
+#[cfg(feature = "anchor")]
+pub use anchor::*;
+
+use solana_program::pubkey::Pubkey;
+
+/// Derives the PDA that becomes the authority of an escrow's temp token account
+pub fn find_escrow_authority_address(escrow_account: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow", escrow_account.as_ref()], program_id)
+}
+
+/// Derives the address of an escrow that InitEscrow was given an id for. Each initializer can have one open escrow per id.
+pub fn find_escrow_address(initializer: &Pubkey, escrow_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow", initializer.as_ref(), &escrow_id.to_le_bytes()], program_id)
+}
+
+/// The protocol fee for a given amount of token Y at the config's fee_bps, rounded down. None on overflow.
+pub fn protocol_fee(amount: u64, fee_bps: u16) -> Option<u64> {
+    math::bps_of(amount, fee_bps, math::Rounding::Down)
+}
+
+/// Derives the address of a bidder's bid on an English auction escrow. The bid is a token account owned by the escrow's PDA.
+pub fn find_bid_address(escrow_account: &Pubkey, bidder: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"bid", escrow_account.as_ref(), bidder.as_ref()], program_id)
+}
+
+/// Derives the address of the config account
+pub fn find_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"config"], program_id)
+}
+
+/// Derives the address of the stats account
+pub fn find_stats_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"stats"], program_id)
+}
+
+/// Derives the address of a taker's fill record of a rate-based escrow
+pub fn find_fill_record_address(escrow_account: &Pubkey, taker: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"fill", escrow_account.as_ref(), taker.as_ref()], program_id)
+}
+
+/// Derives the address of the mint allowlist
+pub fn find_allowlist_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"allowlist"], program_id)
+}
+
+/// Derives the address of the stablecoin list
+pub fn find_stablecoins_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"stablecoins"], program_id)
+}
+
+/// Derives the PDA that owns every fee vault
+pub fn find_fee_authority_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"fee_authority"], program_id)
+}
+
+/// Derives the address of the fee vault, a token account owned by the fee authority, for the given mint
+pub fn find_fee_vault_address(mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"fee_vault", mint.as_ref()], program_id)
+}
+
+/// Derives the address of a vesting escrow's vesting account, which holds the schedule once the escrow has been exchanged
+pub fn find_vesting_address(escrow_account: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vesting", escrow_account.as_ref()], program_id)
+}
+
+/// Derives the address of the token account that holds a vesting escrow's tokens until they are claimed. It is owned by the vesting account.
+pub fn find_vesting_vault_address(escrow_account: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vesting_vault", escrow_account.as_ref()], program_id)
+}
+
+/// Derives the address of the order book of a mint pair
+pub fn find_market_address(base_mint: &Pubkey, quote_mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"market", base_mint.as_ref(), quote_mint.as_ref()], program_id)
+}
+
+/// Derives the address of the registry of open escrows trading token X for token Y
+pub fn find_registry_address(token_x_mint: &Pubkey, token_y_mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"registry", token_x_mint.as_ref(), token_y_mint.as_ref()], program_id)
+}
+
+/// Derives the address of a quoter's quote on an escrow. Each quoter can have one quote per escrow.
+pub fn find_quote_address(escrow_account: &Pubkey, quoter: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"quote", escrow_account.as_ref(), quoter.as_ref()], program_id)
+}
+
+/// Derives the address of the token account that holds a locked quote's price. It is owned by the quote.
+pub fn find_quote_vault_address(quote_account: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"quote_vault", quote_account.as_ref()], program_id)
+}
+
+/// Derives the address of a taker's counteroffer on an escrow. Each taker can have one counteroffer per escrow.
+pub fn find_counteroffer_address(escrow_account: &Pubkey, taker: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"counteroffer", escrow_account.as_ref(), taker.as_ref()], program_id)
+}
+
+/// Derives the address of a swap ring. Each creator can have one ring per id.
+pub fn find_ring_address(creator: &Pubkey, ring_id: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"ring", creator.as_ref(), &ring_id.to_le_bytes()], program_id)
+}
+
+/// Derives the address of the vault of a swap ring's leg, a token account owned by the ring
+pub fn find_ring_vault_address(ring_account: &Pubkey, leg_index: u8, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"ring_vault", ring_account.as_ref(), &[leg_index]], program_id)
+}
+
+/// Derives the address of the settings of a sealed-bid auction. Each escrow can be one auction.
+pub fn find_sealed_auction_address(escrow_account: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"sealed_auction", escrow_account.as_ref()], program_id)
+}
+
+/// Derives the address of a bidder's commitment in a sealed-bid auction. Each bidder can commit to one bid per auction.
+pub fn find_sealed_bid_address(auction_account: &Pubkey, bidder: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"sealed_bid", auction_account.as_ref(), bidder.as_ref()], program_id)
+}
+
+/// Derives the address of the token account that holds a sealed bid's deposit. It is owned by the sealed bid.
+pub fn find_sealed_bid_vault_address(sealed_bid_account: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"sealed_bid_vault", sealed_bid_account.as_ref()], program_id)
+}
+
+/// Derives the address of the raffle of an escrow
+pub fn find_raffle_address(escrow_account: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"raffle", escrow_account.as_ref()], program_id)
+}
+
+/// Derives the address of the token account that holds a raffle's ticket sales. It is owned by the raffle.
+pub fn find_raffle_vault_address(raffle_account: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"raffle_vault", raffle_account.as_ref()], program_id)
+}
+
+/// Derives the address of the tickets bought by one purchase, which is told apart by the number of its first ticket
+pub fn find_ticket_address(raffle_account: &Pubkey, first_ticket: u32, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"ticket", raffle_account.as_ref(), &first_ticket.to_le_bytes()], program_id)
+}
+
+/// Derives the PDA that owns an escrow pool's vault
+pub fn find_escrow_pool_authority_address(pool_account: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow_pool", pool_account.as_ref()], program_id)
+}
+
+/// Derives the address of the token account that holds the token X of all of an escrow pool's entries
+pub fn find_escrow_pool_vault_address(pool_account: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow_pool_vault", pool_account.as_ref()], program_id)
+}
+
+/// Derives the config of a merkle tree compressed escrows are listed in. It is the tree's authority and the delegate of the escrows' token X.
+pub fn find_compressed_tree_address(merkle_tree: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"compressed_tree", merkle_tree.as_ref()], program_id)
+}
+
+/// Derives the PDA that owned the temp token accounts of all V1 escrows, before every escrow got its own. Only MigrateEscrow still signs for it.
+pub fn find_legacy_escrow_authority_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow"], program_id)
+}
+
 // GO BACK AND LEARN ABOUT SLICE
 
 // Flow of the file structure:
@@ -0,0 +1,201 @@
+//! Multiply-then-divide for amounts, rates and fees. The product is taken in a u128, so `value * numerator` never overflows before the division brings it back down, and every helper returns None instead of wrapping or panicking when the result doesn't fit or the denominator is zero.
+//!
+//! The caller picks the rounding. The convention in this program is to round in the initializer's favour: what a taker pays rounds up, what a taker receives rounds down, and fees the protocol takes round down.
+
+/// Basis points in a whole
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Which way a division that doesn't come out even is rounded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// numerator / denominator, rounded. None if denominator is zero.
+pub fn div(numerator: u128, denominator: u128, rounding: Rounding) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    match rounding {
+        Rounding::Down => Some(numerator / denominator),
+        Rounding::Up => Some(numerator.div_ceil(denominator)),
+    }
+}
+
+/// value * numerator / denominator, rounded. None if denominator is zero or the result doesn't fit in a u64.
+pub fn mul_div(value: u64, numerator: u64, denominator: u64, rounding: Rounding) -> Option<u64> {
+    // Two u64s always multiply into a u128 without overflow
+    let product = value as u128 * numerator as u128;
+    div(product, denominator as u128, rounding)?.try_into().ok()
+}
+
+/// bps basis points of amount, rounded. None if the result doesn't fit in a u64, which needs bps over 10_000.
+pub fn bps_of(amount: u64, bps: u16, rounding: Rounding) -> Option<u64> {
+    mul_div(amount, bps as u64, BPS_DENOMINATOR, rounding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn mul_div_rounds_in_the_requested_direction() {
+        assert_eq!(mul_div(10, 1, 3, Rounding::Down), Some(3));
+        assert_eq!(mul_div(10, 1, 3, Rounding::Up), Some(4));
+        // Even divisions are the same either way
+        assert_eq!(mul_div(9, 1, 3, Rounding::Down), Some(3));
+        assert_eq!(mul_div(9, 1, 3, Rounding::Up), Some(3));
+        assert_eq!(mul_div(0, 7, 3, Rounding::Up), Some(0));
+        assert_eq!(mul_div(1, 1, u64::MAX, Rounding::Down), Some(0));
+        assert_eq!(mul_div(1, 1, u64::MAX, Rounding::Up), Some(1));
+    }
+
+    #[test]
+    fn mul_div_handles_overflow_and_zero() {
+        // The product overflows a u64, the result doesn't
+        assert_eq!(mul_div(u64::MAX, u64::MAX, u64::MAX, Rounding::Down), Some(u64::MAX));
+        assert_eq!(mul_div(u64::MAX, 3, 4, Rounding::Up), Some((u64::MAX / 4) * 3 + 3));
+        assert_eq!(mul_div(u64::MAX, 2, 1, Rounding::Down), None);
+        assert_eq!(mul_div(u64::MAX, u64::MAX, u64::MAX - 1, Rounding::Down), None);
+        assert_eq!(mul_div(u64::MAX - 1, u64::MAX, u64::MAX - 1, Rounding::Up), Some(u64::MAX));
+        // (2^33 - 1) * (2^33 + 1) / 4 is just under 2^64, so rounding it up is what overflows
+        assert_eq!(mul_div(8_589_934_591, 8_589_934_593, 4, Rounding::Down), Some(u64::MAX));
+        assert_eq!(mul_div(8_589_934_591, 8_589_934_593, 4, Rounding::Up), None);
+        assert_eq!(mul_div(1, 1, 0, Rounding::Down), None);
+        assert_eq!(mul_div(0, 0, 0, Rounding::Up), None);
+
+        assert_eq!(div(u128::MAX, 2, Rounding::Up), Some(u128::MAX / 2 + 1));
+        assert_eq!(div(u128::MAX, u128::MAX, Rounding::Up), Some(1));
+        assert_eq!(div(1, 0, Rounding::Up), None);
+    }
+
+    #[test]
+    fn bps_of_amounts() {
+        assert_eq!(bps_of(1_000_000, 25, Rounding::Down), Some(2_500));
+        assert_eq!(bps_of(399, 25, Rounding::Down), Some(0));
+        assert_eq!(bps_of(399, 25, Rounding::Up), Some(1));
+        assert_eq!(bps_of(u64::MAX, 10_000, Rounding::Down), Some(u64::MAX));
+        assert_eq!(bps_of(u64::MAX, 10_001, Rounding::Down), None);
+        assert_eq!(bps_of(u64::MAX, u16::MAX, Rounding::Down), None);
+    }
+
+    #[test]
+    fn div_table() {
+        // numerator, denominator, rounded down, rounded up
+        let cases = [
+            (0, 1, Some(0), Some(0)),
+            (0, u128::MAX, Some(0), Some(0)),
+            (0, 0, None, None),
+            (u128::MAX, 0, None, None),
+            (4, 5, Some(0), Some(1)),
+            (5, 5, Some(1), Some(1)),
+            (6, 5, Some(1), Some(2)),
+            (u128::MAX, 1, Some(u128::MAX), Some(u128::MAX)),
+            (u128::MAX, u128::MAX, Some(1), Some(1)),
+            (u128::MAX - 1, u128::MAX, Some(0), Some(1)),
+            (u128::MAX, u128::MAX - 1, Some(1), Some(2)),
+        ];
+        for (numerator, denominator, down, up) in cases {
+            assert_eq!(div(numerator, denominator, Rounding::Down), down, "{numerator} / {denominator}");
+            assert_eq!(div(numerator, denominator, Rounding::Up), up, "{numerator} / {denominator}");
+        }
+    }
+
+    #[test]
+    fn mul_div_table() {
+        // value, numerator, denominator, rounded down, rounded up
+        let cases = [
+            (0, 0, 1, Some(0), Some(0)),
+            (0, u64::MAX, 1, Some(0), Some(0)),
+            (u64::MAX, 0, u64::MAX, Some(0), Some(0)),
+            (0, 0, 0, None, None),
+            (u64::MAX, u64::MAX, 0, None, None),
+            (8, 1, 3, Some(2), Some(3)),
+            (9, 1, 3, Some(3), Some(3)),
+            (10, 1, 3, Some(3), Some(4)),
+            (u64::MAX - 1, 1, u64::MAX, Some(0), Some(1)),
+            (u64::MAX, 1, 1, Some(u64::MAX), Some(u64::MAX)),
+            (u64::MAX, u64::MAX - 1, u64::MAX, Some(u64::MAX - 1), Some(u64::MAX - 1)),
+            (u64::MAX, 3, 4, Some(u64::MAX / 4 * 3 + 2), Some(u64::MAX / 4 * 3 + 3)),
+            // 2^64 is one past u64::MAX, half of it fits
+            (1 << 32, 1 << 32, 1, None, None),
+            (1 << 32, 1 << 32, 2, Some(1 << 63), Some(1 << 63)),
+            (8_589_934_591, 8_589_934_593, 4, Some(u64::MAX), None),
+        ];
+        for (value, numerator, denominator, down, up) in cases {
+            let case = format!("{value} * {numerator} / {denominator}");
+            assert_eq!(mul_div(value, numerator, denominator, Rounding::Down), down, "{case}");
+            assert_eq!(mul_div(value, numerator, denominator, Rounding::Up), up, "{case}");
+        }
+    }
+
+    #[test]
+    fn bps_of_table() {
+        // amount, bps, rounded down, rounded up
+        let cases = [
+            (0, 0, Some(0), Some(0)),
+            (0, u16::MAX, Some(0), Some(0)),
+            (u64::MAX, 0, Some(0), Some(0)),
+            (1, 1, Some(0), Some(1)),
+            (9_999, 1, Some(0), Some(1)),
+            (10_000, 1, Some(1), Some(1)),
+            (10_001, 9_999, Some(9_999), Some(10_000)),
+            (399, 25, Some(0), Some(1)),
+            (400, 25, Some(1), Some(1)),
+            (401, 25, Some(1), Some(2)),
+            (u64::MAX, 1, Some(u64::MAX / 10_000), Some(u64::MAX / 10_000 + 1)),
+            (u64::MAX, 9_999, Some(u64::MAX - u64::MAX / 10_000 - 1), Some(u64::MAX - u64::MAX / 10_000)),
+            (u64::MAX, 10_000, Some(u64::MAX), Some(u64::MAX)),
+            (u64::MAX, 10_001, None, None),
+            (u64::MAX, u16::MAX, None, None),
+            // Over 10_000 bps, the largest amount that still fits
+            (u64::MAX / 2, 20_000, Some(u64::MAX - 1), Some(u64::MAX - 1)),
+            (u64::MAX / 2 + 1, 20_000, None, None),
+        ];
+        for (amount, bps, down, up) in cases {
+            assert_eq!(bps_of(amount, bps, Rounding::Down), down, "{bps} bps of {amount}");
+            assert_eq!(bps_of(amount, bps, Rounding::Up), up, "{bps} bps of {amount}");
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn mul_div_brackets_the_exact_quotient(value: u64, numerator: u64, denominator in 1..=u64::MAX) {
+            let product = value as u128 * numerator as u128;
+            let (d, max) = (denominator as u128, u64::MAX as u128);
+            // Rounded down, the result is the largest q with q * d <= product
+            match mul_div(value, numerator, denominator, Rounding::Down) {
+                Some(q) => prop_assert!(q as u128 * d <= product && product < (q as u128 + 1) * d),
+                None => prop_assert!(product >= (max + 1) * d),
+            }
+            // Rounded up, the smallest q with q * d >= product
+            match mul_div(value, numerator, denominator, Rounding::Up) {
+                Some(q) => prop_assert!(q as u128 * d >= product && (q == 0 || (q as u128 - 1) * d < product)),
+                None => prop_assert!(product > max * d),
+            }
+        }
+
+        #[test]
+        fn mul_div_by_zero_is_none(value: u64, numerator: u64) {
+            prop_assert_eq!(mul_div(value, numerator, 0, Rounding::Down), None);
+            prop_assert_eq!(mul_div(value, numerator, 0, Rounding::Up), None);
+        }
+
+        #[test]
+        fn div_rounds_up_only_when_uneven(numerator: u128, denominator in 1..=u128::MAX) {
+            let down = div(numerator, denominator, Rounding::Down).unwrap();
+            let up = div(numerator, denominator, Rounding::Up).unwrap();
+            prop_assert_eq!(up - down, u128::from(numerator % denominator != 0));
+        }
+
+        #[test]
+        fn bps_of_at_most_a_whole_never_exceeds_the_amount(amount: u64, bps in 0..=BPS_DENOMINATOR as u16) {
+            let down = bps_of(amount, bps, Rounding::Down).unwrap();
+            let up = bps_of(amount, bps, Rounding::Up).unwrap();
+            prop_assert!(down <= up && up <= amount);
+            prop_assert!(up - down <= 1);
+        }
+    }
+}
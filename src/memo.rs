@@ -0,0 +1,27 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke,
+    program_error::ProgramError,
+};
+
+// The SPL Memo program. Its instruction is nothing but the memo's UTF-8 bytes, with the accounts that have to sign it, so like the associated token account program in `token` we build it ourselves instead of depending on spl-memo.
+solana_program::declare_id!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+/// Records `memo` in the transaction, signed by `signer`. The Memo program is looked up by key among `accounts`. Empty memos are skipped, so instructions that weren't given one don't need the program.
+pub fn log_memo<'a>(memo: &str, signer: &AccountInfo<'a>, accounts: &[AccountInfo<'a>]) -> ProgramResult {
+    if memo.is_empty() {
+        return Ok(());
+    }
+    let memo_program = accounts
+        .iter()
+        .find(|account| *account.key == id())
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let memo_ix = Instruction {
+        program_id: id(),
+        accounts: vec![AccountMeta::new_readonly(*signer.key, true)],
+        data: memo.as_bytes().to_vec(),
+    };
+    invoke(&memo_ix, &[signer.clone(), memo_program.clone()])
+}
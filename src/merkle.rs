@@ -0,0 +1,79 @@
+//! Merkle trees of taker pubkeys, which let an escrow be private to more takers than it could list. The escrow stores the root and the taker proves they are a leaf.
+//!
+//! Leaves and inner nodes are sha256 hashes with a one byte prefix each, so an inner node can't pass for a leaf. The two children of a node are hashed in sorted order, which means proofs don't have to say which side each sibling is on. A tree of one taker has that taker's leaf as its root and an empty proof.
+
+use solana_program::{hash::hashv, pubkey::Pubkey};
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// The leaf of a taker
+pub fn leaf(taker: &Pubkey) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, taker.as_ref()]).to_bytes()
+}
+
+fn node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Whether `proof` leads from the taker's leaf to `root`
+pub fn verify(root: &[u8; 32], taker: &Pubkey, proof: &[[u8; 32]]) -> bool {
+    proof.iter().fold(leaf(taker), |hash, sibling| node(&hash, sibling)) == *root
+}
+
+// The rest is for clients building the tree. A node without a sibling moves up a level unchanged.
+fn levels(takers: &[Pubkey]) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![takers.iter().map(leaf).collect::<Vec<_>>()];
+    while levels[levels.len() - 1].len() > 1 {
+        let next = levels[levels.len() - 1]
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => node(a, b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// The root of a tree of takers, to give InitEscrow. None if there are no takers.
+pub fn root(takers: &[Pubkey]) -> Option<[u8; 32]> {
+    levels(takers).last()?.first().copied()
+}
+
+/// The proof that `taker` is in the tree of `takers`, to give Exchange. None if they aren't.
+pub fn proof(takers: &[Pubkey], taker: &Pubkey) -> Option<Vec<[u8; 32]>> {
+    let mut index = takers.iter().position(|candidate| candidate == taker)?;
+    let mut proof = vec![];
+    for level in levels(takers).iter().filter(|level| level.len() > 1) {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        index /= 2;
+    }
+    Some(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proofs_verify_against_the_root() {
+        for count in 1..=9 {
+            let takers: Vec<Pubkey> = (0..count).map(|_| Pubkey::new_unique()).collect();
+            let root = root(&takers).unwrap();
+            for taker in &takers {
+                assert!(verify(&root, taker, &proof(&takers, taker).unwrap()));
+            }
+
+            let outsider = Pubkey::new_unique();
+            assert_eq!(proof(&takers, &outsider), None);
+            assert!(!verify(&root, &outsider, &proof(&takers, &takers[0]).unwrap()));
+        }
+        assert_eq!(root(&[]), None);
+    }
+}
@@ -0,0 +1,345 @@
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use std::convert::TryInto;
+
+use crate::{
+    error::EscrowError,
+    math::{self, Rounding},
+    token,
+};
+
+// The Metaplex Token Metadata program. Like the Pyth accounts in `oracle`, its metadata accounts are read by hand, since mpl-token-metadata needs a newer solana-program than we build against. Only the royalty fields are read.
+solana_program::declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// The Token Auth Rules program, which checks transfers of programmable NFTs against their rule set
+pub mod token_auth_rules {
+    solana_program::declare_id!("auth9SigNpDKz4sJJ1DfCTuZrZNSAgh9sFD3rboVmgg");
+}
+
+/// The first byte of a metadata account, Key::MetadataV1
+const KEY_METADATA_V1: u8 = 4;
+/// Metaplex caps the creators of an NFT at 5
+const MAX_CREATORS: usize = 5;
+/// TokenStandard::ProgrammableNonFungible
+const TOKEN_STANDARD_PROGRAMMABLE_NON_FUNGIBLE: u8 = 4;
+/// MetadataInstruction::Transfer, which is followed by TransferArgs::V1
+const TRANSFER_INSTRUCTION: u8 = 49;
+const TRANSFER_ARGS_V1: u8 = 0;
+
+/// A creator entitled to `share` percent of the royalties
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Creator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+/// The royalty terms of an NFT
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Royalties {
+    pub seller_fee_basis_points: u16,
+    pub creators: Vec<Creator>,
+}
+
+/// The collection an NFT belongs to. It is only verified once the collection's update authority has confirmed the NFT is part of it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Collection {
+    pub verified: bool,
+    pub key: Pubkey,
+}
+
+impl Royalties {
+    /// What each creator gets out of a sale for `price`, rounded down, leaving out creators who'd get nothing. The rounding dust stays with the seller.
+    pub fn shares(&self, price: u64) -> Vec<(Pubkey, u64)> {
+        // Both are checked against their maximums when the metadata is read, so neither can overflow
+        let royalty = math::bps_of(price, self.seller_fee_basis_points, Rounding::Down).unwrap_or(0);
+        self.creators
+            .iter()
+            .map(|creator| {
+                let amount = math::mul_div(royalty, creator.share as u64, 100, Rounding::Down).unwrap_or(0);
+                (creator.address, amount)
+            })
+            .filter(|(_, amount)| *amount > 0)
+            .collect()
+    }
+}
+
+/// The metadata account of a mint, derived from `[b"metadata", metadata program id, mint]`
+pub fn find_metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"metadata", id().as_ref(), mint.as_ref()], &id()).0
+}
+
+/// The master edition account of a mint, derived from `[b"metadata", metadata program id, mint, b"edition"]`
+pub fn find_edition_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"metadata", id().as_ref(), mint.as_ref(), b"edition"], &id()).0
+}
+
+/// The token record of a programmable NFT's token account, derived from `[b"metadata", metadata program id, mint, b"token_record", token account]`
+pub fn find_token_record_address(mint: &Pubkey, token_account: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", id().as_ref(), mint.as_ref(), b"token_record", token_account.as_ref()],
+        &id(),
+    )
+    .0
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ProgramError> {
+        if self.data.len() < len {
+            return Err(EscrowError::InvalidMetadataAccount.into());
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, ProgramError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ProgramError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey, ProgramError> {
+        Ok(Pubkey::new_from_array(self.take(32)?.try_into().unwrap()))
+    }
+
+    // Borsh strings and vecs start with a u32 length. Metaplex pads the strings, so their length is their padded length.
+    fn len(&mut self) -> Result<usize, ProgramError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()) as usize)
+    }
+
+    fn skip_string(&mut self) -> Result<(), ProgramError> {
+        let len = self.len()?;
+        self.take(len).map(|_| ())
+    }
+
+    // A borsh Option whose value is `len` bytes long
+    fn option(&mut self, len: usize) -> Result<Option<&'a [u8]>, ProgramError> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => self.take(len).map(Some),
+            _ => Err(EscrowError::InvalidMetadataAccount.into()),
+        }
+    }
+}
+
+/// Unpacks the royalties out of the data of `mint`'s metadata account. Fails if the data isn't the metadata of `mint`.
+pub fn unpack_royalties(data: &[u8], mint: &Pubkey) -> Result<Royalties, ProgramError> {
+    read_royalties(&mut Reader { data }, mint)
+}
+
+fn read_royalties(reader: &mut Reader, mint: &Pubkey) -> Result<Royalties, ProgramError> {
+    if reader.u8()? != KEY_METADATA_V1 {
+        return Err(EscrowError::InvalidMetadataAccount.into());
+    }
+    let _update_authority = reader.pubkey()?;
+    if reader.pubkey()? != *mint {
+        return Err(EscrowError::InvalidMetadataAccount.into());
+    }
+    // name, symbol and uri
+    for _ in 0..3 {
+        reader.skip_string()?;
+    }
+    let seller_fee_basis_points = reader.u16()?;
+    let creators = match reader.u8()? {
+        0 => vec![],
+        1 => {
+            let count = reader.len()?;
+            if count > MAX_CREATORS {
+                return Err(EscrowError::InvalidMetadataAccount.into());
+            }
+            (0..count)
+                .map(|_| {
+                    Ok(Creator {
+                        address: reader.pubkey()?,
+                        verified: reader.u8()? != 0,
+                        share: reader.u8()?,
+                    })
+                })
+                .collect::<Result<_, ProgramError>>()?
+        }
+        _ => return Err(EscrowError::InvalidMetadataAccount.into()),
+    };
+    if seller_fee_basis_points > 10_000 || creators.iter().map(|creator| creator.share as u32).sum::<u32>() > 100 {
+        return Err(EscrowError::InvalidMetadataAccount.into());
+    }
+    Ok(Royalties { seller_fee_basis_points, creators })
+}
+
+// Reads on from the royalties to the token standard and the collection
+fn read_collection(reader: &mut Reader, mint: &Pubkey) -> Result<(Option<u8>, Option<Collection>), ProgramError> {
+    read_royalties(reader, mint)?;
+    // primary_sale_happened, is_mutable and edition_nonce
+    reader.take(2)?;
+    reader.option(1)?;
+    let token_standard = reader.option(1)?.map(|token_standard| token_standard[0]);
+    let collection = reader.option(33)?.map(|collection| Collection {
+        verified: collection[0] != 0,
+        key: Pubkey::new_from_array(collection[1..].try_into().unwrap()),
+    });
+    Ok((token_standard, collection))
+}
+
+/// Unpacks the collection out of the data of `mint`'s metadata account, None if the NFT isn't part of one. Fails if the data isn't the metadata of `mint`.
+pub fn unpack_collection(data: &[u8], mint: &Pubkey) -> Result<Option<Collection>, ProgramError> {
+    read_collection(&mut Reader { data }, mint).map(|(_, collection)| collection)
+}
+
+/// Unpacks the rule set out of the data of a programmable NFT's metadata account, None if the NFT has none. Fails with NotProgrammable if the NFT isn't a programmable one.
+pub fn unpack_rule_set(data: &[u8], mint: &Pubkey) -> Result<Option<Pubkey>, ProgramError> {
+    let mut reader = Reader { data };
+    let (token_standard, _) = read_collection(&mut reader, mint)?;
+    if token_standard != Some(TOKEN_STANDARD_PROGRAMMABLE_NON_FUNGIBLE) {
+        return Err(EscrowError::NotProgrammable.into());
+    }
+    // uses and collection_details
+    reader.option(17)?;
+    reader.option(9)?;
+    // programmable_config is an optional enum whose only variant, V1, holds an optional rule set
+    if reader.option(0)?.is_none() {
+        return Ok(None);
+    }
+    if reader.u8()? != 0 {
+        return Err(EscrowError::InvalidMetadataAccount.into());
+    }
+    reader.option(32).map(|rule_set| rule_set.map(|rule_set| Pubkey::new_from_array(rule_set.try_into().unwrap())))
+}
+
+/// Token Metadata's Transfer instruction moving `amount` of a programmable NFT from `token`, owned by `token_owner`, to `destination`, owned by `destination_owner`. Token Metadata creates `destination` if it is `destination_owner`'s associated token account and doesn't exist yet, with `payer` paying the rent. `authority` has to be `token_owner` and sign.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_instruction(
+    token: &Pubkey,
+    token_owner: &Pubkey,
+    destination: &Pubkey,
+    destination_owner: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    rule_set: Option<&Pubkey>,
+    amount: u64,
+) -> Instruction {
+    let mut data = vec![TRANSFER_INSTRUCTION, TRANSFER_ARGS_V1];
+    data.extend_from_slice(&amount.to_le_bytes());
+    // No authorization data
+    data.push(0);
+    // Optional accounts that are left out are passed as the Token Metadata program itself
+    let (rules_program, rules) = match rule_set {
+        Some(rule_set) => (token_auth_rules::id(), *rule_set),
+        None => (id(), id()),
+    };
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*token, false),
+            AccountMeta::new_readonly(*token_owner, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*destination_owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(find_metadata_address(mint), false),
+            AccountMeta::new_readonly(find_edition_address(mint), false),
+            AccountMeta::new(find_token_record_address(mint, token), false),
+            AccountMeta::new(find_token_record_address(mint, destination), false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(token::associated_token::id(), false),
+            AccountMeta::new_readonly(rules_program, false),
+            AccountMeta::new_readonly(rules, false),
+        ],
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(mint: &Pubkey, seller_fee_basis_points: u16, creators: &[Creator]) -> Vec<u8> {
+        let mut data = vec![KEY_METADATA_V1];
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(mint.as_ref());
+        for (text, padded_len) in [("Escrowed", 32), ("ESC", 10), ("https://example.com", 200)] {
+            data.extend_from_slice(&(padded_len as u32).to_le_bytes());
+            let mut padded = text.as_bytes().to_vec();
+            padded.resize(padded_len, 0);
+            data.extend_from_slice(&padded);
+        }
+        data.extend_from_slice(&seller_fee_basis_points.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(&(creators.len() as u32).to_le_bytes());
+        for creator in creators {
+            data.extend_from_slice(creator.address.as_ref());
+            data.push(creator.verified as u8);
+            data.push(creator.share);
+        }
+        // What comes after the creators isn't read
+        data.extend_from_slice(&[1, 0, 1, 255]);
+        data
+    }
+
+    #[test]
+    fn royalties_are_split_between_creators() {
+        let mint = Pubkey::new_unique();
+        let creators = [
+            Creator { address: Pubkey::new_unique(), verified: true, share: 70 },
+            Creator { address: Pubkey::new_unique(), verified: false, share: 30 },
+            Creator { address: Pubkey::new_unique(), verified: true, share: 0 },
+        ];
+        let royalties = unpack_royalties(&metadata(&mint, 500, &creators), &mint).unwrap();
+        assert_eq!(royalties, Royalties { seller_fee_basis_points: 500, creators: creators.to_vec() });
+        // 5% of 1001 is 50, split 35 and 15
+        assert_eq!(royalties.shares(1_001), vec![(creators[0].address, 35), (creators[1].address, 15)]);
+
+        assert_eq!(
+            unpack_royalties(&metadata(&mint, 500, &creators), &Pubkey::new_unique()),
+            Err(EscrowError::InvalidMetadataAccount.into())
+        );
+        let data = metadata(&mint, 500, &creators);
+        assert_eq!(
+            unpack_royalties(&data[..data.len() - 40], &mint),
+            Err(EscrowError::InvalidMetadataAccount.into())
+        );
+    }
+
+    #[test]
+    fn rule_set_of_programmable_nfts() {
+        let mint = Pubkey::new_unique();
+        let rule_set = Pubkey::new_unique();
+        let metadata = |token_standard: u8, programmable_config: &[u8]| {
+            let mut data = metadata(&mint, 500, &[]);
+            data.extend_from_slice(&[1, token_standard]);
+            // A collection, no uses and no collection details
+            data.push(1);
+            data.extend_from_slice(&[1; 33]);
+            data.extend_from_slice(&[0, 0]);
+            data.extend_from_slice(programmable_config);
+            data
+        };
+
+        let mut with_rule_set = vec![1, 0, 1];
+        with_rule_set.extend_from_slice(rule_set.as_ref());
+        assert_eq!(unpack_rule_set(&metadata(4, &with_rule_set), &mint), Ok(Some(rule_set)));
+        assert_eq!(unpack_rule_set(&metadata(4, &[1, 0, 0]), &mint), Ok(None));
+        assert_eq!(
+            unpack_collection(&metadata(4, &[0]), &mint),
+            Ok(Some(Collection { verified: true, key: Pubkey::new_from_array([1; 32]) }))
+        );
+        assert_eq!(unpack_rule_set(&metadata(4, &[0]), &mint), Ok(None));
+        assert_eq!(
+            unpack_rule_set(&metadata(0, &[0]), &mint),
+            Err(EscrowError::NotProgrammable.into())
+        );
+    }
+}
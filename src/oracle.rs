@@ -0,0 +1,158 @@
+use solana_program::{account_info::AccountInfo, program_error::ProgramError};
+use std::convert::TryInto;
+
+use crate::{
+    error::EscrowError,
+    math::{self, Rounding},
+    state::USD_DECIMALS,
+};
+
+/// Pyth price accounts are read by hand instead of through pyth-sdk-solana, which needs a newer solana-program than we build against. We only need a handful of fields of the v2 price account, at the offsets below. The Pyth program id differs between clusters, so the account's owner isn't checked - the initializer picks the feed, and takers can see which one it is before they take the escrow.
+const MAGIC: u32 = 0xa1b2_c3d4;
+const VERSION_2: u32 = 2;
+const ACCOUNT_TYPE_PRICE: u32 = 3;
+const STATUS_TRADING: u32 = 1;
+
+const VERSION_OFFSET: usize = 4;
+const ACCOUNT_TYPE_OFFSET: usize = 8;
+const EXPONENT_OFFSET: usize = 20;
+const TIMESTAMP_OFFSET: usize = 96;
+const AGGREGATE_PRICE_OFFSET: usize = 208;
+const AGGREGATE_STATUS_OFFSET: usize = 224;
+
+/// Prices published longer ago than this are rejected
+pub const MAX_PRICE_AGE_SECONDS: i64 = 60;
+
+/// The aggregate price of a feed: one unit of the base token is worth `price * 10^exponent` units of the quote token
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Price {
+    pub price: i64,
+    pub exponent: i32,
+    pub publish_unix_timestamp: i64,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, ProgramError> {
+    data.get(offset..offset + 4)
+        .and_then(|slice| slice.try_into().ok())
+        .map(u32::from_le_bytes)
+        .ok_or_else(|| EscrowError::InvalidPriceFeed.into())
+}
+
+fn read_i64(data: &[u8], offset: usize) -> Result<i64, ProgramError> {
+    data.get(offset..offset + 8)
+        .and_then(|slice| slice.try_into().ok())
+        .map(i64::from_le_bytes)
+        .ok_or_else(|| EscrowError::InvalidPriceFeed.into())
+}
+
+/// Unpacks the aggregate price of a Pyth price account. Fails if the account isn't a price account or the feed isn't currently trading.
+pub fn unpack_price(account: &AccountInfo) -> Result<Price, ProgramError> {
+    let data = account.try_borrow_data()?;
+    if read_u32(&data, 0)? != MAGIC
+        || read_u32(&data, VERSION_OFFSET)? != VERSION_2
+        || read_u32(&data, ACCOUNT_TYPE_OFFSET)? != ACCOUNT_TYPE_PRICE
+    {
+        return Err(EscrowError::InvalidPriceFeed.into());
+    }
+
+    let price = read_i64(&data, AGGREGATE_PRICE_OFFSET)?;
+    if read_u32(&data, AGGREGATE_STATUS_OFFSET)? != STATUS_TRADING || price <= 0 {
+        return Err(EscrowError::PriceUnavailable.into());
+    }
+
+    Ok(Price {
+        price,
+        exponent: read_u32(&data, EXPONENT_OFFSET)? as i32,
+        publish_unix_timestamp: read_i64(&data, TIMESTAMP_OFFSET)?,
+    })
+}
+
+/// The amount of token Y worth amount_x of token X at the given price, adjusted by premium_bps (negative for a discount) and rounded up in the initializer's favour. The feed has to quote X in Y. None on overflow.
+pub fn payment_amount(
+    price: &Price,
+    premium_bps: i16,
+    amount_x: u64,
+    token_x_decimals: u8,
+    token_y_decimals: u8,
+) -> Option<u64> {
+    let premium_factor = 10_000i32.checked_add(premium_bps as i32)?.try_into().ok()?;
+    let numerator = (amount_x as u128)
+        .checked_mul(price.price as u128)?
+        .checked_mul(premium_factor)?;
+
+    let scale = price
+        .exponent
+        .checked_add(token_y_decimals as i32)?
+        .checked_sub(token_x_decimals as i32)?;
+    let (numerator, denominator) = if scale >= 0 {
+        (numerator.checked_mul(10u128.checked_pow(scale as u32)?)?, 10_000u128)
+    } else {
+        (numerator, 10_000u128.checked_mul(10u128.checked_pow(scale.unsigned_abs())?)?)
+    };
+
+    math::div(numerator, denominator, Rounding::Up)?.try_into().ok()
+}
+
+/// The amount of a stablecoin worth usd_amount (with USD_DECIMALS decimals) at the given price, rounded up in the initializer's favour. The feed has to quote the stablecoin in USD. None on overflow.
+pub fn usd_payment_amount(price: &Price, usd_amount: u64, token_decimals: u8) -> Option<u64> {
+    let numerator = (usd_amount as u128).checked_mul(10u128.checked_pow(token_decimals as u32)?)?;
+    let scale = price.exponent.checked_add(USD_DECIMALS as i32)?;
+    let (numerator, denominator) = if scale >= 0 {
+        (numerator, (price.price as u128).checked_mul(10u128.checked_pow(scale as u32)?)?)
+    } else {
+        (numerator.checked_mul(10u128.checked_pow(scale.unsigned_abs())?)?, price.price as u128)
+    };
+
+    math::div(numerator, denominator, Rounding::Up)?.try_into().ok()
+}
+
+/// Whether a price quoted in USD is within tolerance_bps of one dollar, i.e. the stablecoin holds its peg
+pub fn is_pegged(price: &Price, tolerance_bps: u16) -> bool {
+    // Both sides are scaled by 10^-exponent for the usual negative exponents, so one dollar is 10^-exponent
+    let (value, one) = match 10u128.checked_pow(price.exponent.unsigned_abs()) {
+        Some(scale) if price.exponent < 0 => (price.price as u128, scale),
+        Some(scale) => ((price.price as u128).saturating_mul(scale), 1),
+        None => return false,
+    };
+    value.abs_diff(one).saturating_mul(10_000) <= one.saturating_mul(tolerance_bps as u128)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payment_amount_scales_by_decimals_and_premium() {
+        // 1 SOL = 150.12345678 USDC
+        let price = Price { price: 15_012_345_678, exponent: -8, publish_unix_timestamp: 0 };
+
+        // 2 SOL (9 decimals) for USDC (6 decimals)
+        assert_eq!(payment_amount(&price, 0, 2_000_000_000, 9, 6), Some(300_246_914));
+        // 1% premium
+        assert_eq!(payment_amount(&price, 100, 2_000_000_000, 9, 6), Some(303_249_383));
+        // 1% discount
+        assert_eq!(payment_amount(&price, -100, 2_000_000_000, 9, 6), Some(297_244_445));
+        // Rounded up rather than giving away a fraction of a unit
+        assert_eq!(payment_amount(&price, 0, 1, 9, 6), Some(1));
+        assert_eq!(payment_amount(&price, 0, 0, 9, 6), Some(0));
+        assert_eq!(payment_amount(&price, 0, u64::MAX, 0, 18), None);
+    }
+
+    #[test]
+    fn usd_payment_amount_converts_at_the_stablecoin_price() {
+        // 1 USDC = 0.9998 USD
+        let usdc = Price { price: 99_980_000, exponent: -8, publish_unix_timestamp: 0 };
+
+        // $150 in USDC (6 decimals), rounded up
+        assert_eq!(usd_payment_amount(&usdc, 150_000_000, 6), Some(150_030_007));
+        // and in a stablecoin with 9 decimals at exactly one dollar
+        let one_dollar = Price { price: 100_000_000, exponent: -8, publish_unix_timestamp: 0 };
+        assert_eq!(usd_payment_amount(&one_dollar, 150_000_000, 9), Some(150_000_000_000));
+        assert_eq!(usd_payment_amount(&usdc, u64::MAX, 30), None);
+
+        assert!(is_pegged(&usdc, 2));
+        assert!(!is_pegged(&usdc, 1));
+        assert!(is_pegged(&Price { price: 1, exponent: 0, publish_unix_timestamp: 0 }, 0));
+        assert!(!is_pegged(&Price { price: 2, exponent: 0, publish_unix_timestamp: 0 }, 50));
+    }
+}
@@ -0,0 +1,257 @@
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+use std::cmp::Ordering;
+
+use crate::error::EscrowError;
+
+/// How many orders each side of a market holds. Markets are a fixed-size account, so a full side has to be consumed or cancelled from before new orders fit.
+pub const MAX_ORDERS: usize = 16;
+
+const ORDER_LEN: usize = 48;
+
+/// An escrow listed on a market. Every order is a whole escrow, so both amounts are the escrow's: asks offer base_amount of the base mint for quote_amount of the quote mint, bids offer quote_amount for base_amount.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Order {
+    pub escrow: Pubkey,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+}
+
+impl Order {
+    /// Compares the prices, in quote per base, of two orders without dividing
+    pub fn cmp_price(&self, other: &Order) -> Ordering {
+        (self.quote_amount as u128 * other.base_amount as u128)
+            .cmp(&(other.quote_amount as u128 * self.base_amount as u128))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+    /// Escrows offering the base mint
+    Ask,
+    /// Escrows offering the quote mint
+    Bid,
+}
+
+/// The order book of one mint pair, stored at the PDA derived from `[b"market", base mint, quote mint]`. Asks are sorted by ascending price and bids by descending price, so the best order of each side comes first. Orders at the same price keep the order they were placed in.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Market {
+    pub is_initialized: bool,
+    /// The bump seed of the market PDA
+    pub bump_seed: u8,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub ask_count: u8,
+    pub bid_count: u8,
+    /// The first ask_count entries are in use
+    pub asks: [Order; 16],
+    /// The first bid_count entries are in use
+    pub bids: [Order; 16],
+}
+
+impl Market {
+    pub fn new(base_mint: Pubkey, quote_mint: Pubkey, bump_seed: u8) -> Self {
+        Market {
+            is_initialized: true,
+            bump_seed,
+            base_mint,
+            quote_mint,
+            ask_count: 0,
+            bid_count: 0,
+            asks: [Order::default(); MAX_ORDERS],
+            bids: [Order::default(); MAX_ORDERS],
+        }
+    }
+
+    /// Which side an escrow trading token_x_mint for token_y_mint belongs on, None if it trades another pair
+    pub fn side_of(&self, token_x_mint: &Pubkey, token_y_mint: &Pubkey) -> Option<Side> {
+        if (*token_x_mint, *token_y_mint) == (self.base_mint, self.quote_mint) {
+            Some(Side::Ask)
+        } else if (*token_x_mint, *token_y_mint) == (self.quote_mint, self.base_mint) {
+            Some(Side::Bid)
+        } else {
+            None
+        }
+    }
+
+    pub fn orders(&self, side: Side) -> &[Order] {
+        match side {
+            Side::Ask => &self.asks[..self.ask_count as usize],
+            Side::Bid => &self.bids[..self.bid_count as usize],
+        }
+    }
+
+    pub fn contains(&self, escrow: &Pubkey) -> bool {
+        self.find(escrow).is_some()
+    }
+
+    fn find(&self, escrow: &Pubkey) -> Option<(Side, usize)> {
+        [Side::Ask, Side::Bid].iter().find_map(|side| {
+            self.orders(*side)
+                .iter()
+                .position(|order| order.escrow == *escrow)
+                .map(|index| (*side, index))
+        })
+    }
+
+    fn side_mut(&mut self, side: Side) -> (&mut [Order; MAX_ORDERS], &mut u8) {
+        match side {
+            Side::Ask => (&mut self.asks, &mut self.ask_count),
+            Side::Bid => (&mut self.bids, &mut self.bid_count),
+        }
+    }
+
+    /// Inserts the order behind every order on its side with the same or a better price
+    pub fn insert(&mut self, side: Side, order: Order) -> Result<(), ProgramError> {
+        let index = self
+            .orders(side)
+            .iter()
+            .position(|existing| match side {
+                Side::Ask => order.cmp_price(existing) == Ordering::Less,
+                Side::Bid => order.cmp_price(existing) == Ordering::Greater,
+            })
+            .unwrap_or_else(|| self.orders(side).len());
+
+        let (orders, count) = self.side_mut(side);
+        if *count as usize == MAX_ORDERS {
+            return Err(EscrowError::MarketFull.into());
+        }
+        orders.copy_within(index..*count as usize, index + 1);
+        orders[index] = order;
+        *count += 1;
+        Ok(())
+    }
+
+    /// Removes the escrow's order from whichever side it is on
+    pub fn remove(&mut self, escrow: &Pubkey) -> Option<Order> {
+        let (side, index) = self.find(escrow)?;
+        let (orders, count) = self.side_mut(side);
+        let order = orders[index];
+        orders.copy_within(index + 1..*count as usize, index);
+        *count -= 1;
+        orders[*count as usize] = Order::default();
+        Some(order)
+    }
+}
+
+impl Sealed for Market {}
+
+impl IsInitialized for Market {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+fn unpack_orders(src: &[u8]) -> [Order; MAX_ORDERS] {
+    let mut orders = [Order::default(); MAX_ORDERS];
+    for (order, order_src) in orders.iter_mut().zip(src.chunks_exact(ORDER_LEN)) {
+        let (escrow, base_amount, quote_amount) = array_refs![array_ref![order_src, 0, ORDER_LEN], 32, 8, 8];
+        *order = Order {
+            escrow: Pubkey::new_from_array(*escrow),
+            base_amount: u64::from_le_bytes(*base_amount),
+            quote_amount: u64::from_le_bytes(*quote_amount),
+        };
+    }
+    orders
+}
+
+fn pack_orders(orders: &[Order; MAX_ORDERS], dst: &mut [u8]) {
+    for (order, order_dst) in orders.iter().zip(dst.chunks_exact_mut(ORDER_LEN)) {
+        let (escrow_dst, base_amount_dst, quote_amount_dst) =
+            mut_array_refs![array_mut_ref![order_dst, 0, ORDER_LEN], 32, 8, 8];
+        escrow_dst.copy_from_slice(order.escrow.as_ref());
+        *base_amount_dst = order.base_amount.to_le_bytes();
+        *quote_amount_dst = order.quote_amount.to_le_bytes();
+    }
+}
+
+impl Pack for Market {
+    const LEN: usize = 68 + 2 * ORDER_LEN * MAX_ORDERS;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Market::LEN];
+        let (is_initialized, bump_seed, base_mint, quote_mint, ask_count, bid_count, asks, bids) =
+            array_refs![src, 1, 1, 32, 32, 1, 1, ORDER_LEN * MAX_ORDERS, ORDER_LEN * MAX_ORDERS];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        if ask_count[0] as usize > MAX_ORDERS || bid_count[0] as usize > MAX_ORDERS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Market {
+            is_initialized,
+            bump_seed: bump_seed[0],
+            base_mint: Pubkey::new_from_array(*base_mint),
+            quote_mint: Pubkey::new_from_array(*quote_mint),
+            ask_count: ask_count[0],
+            bid_count: bid_count[0],
+            asks: unpack_orders(asks),
+            bids: unpack_orders(bids),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Market::LEN];
+        let (is_initialized_dst, bump_seed_dst, base_mint_dst, quote_mint_dst, ask_count_dst, bid_count_dst, asks_dst, bids_dst) =
+            mut_array_refs![dst, 1, 1, 32, 32, 1, 1, ORDER_LEN * MAX_ORDERS, ORDER_LEN * MAX_ORDERS];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        base_mint_dst.copy_from_slice(self.base_mint.as_ref());
+        quote_mint_dst.copy_from_slice(self.quote_mint.as_ref());
+        ask_count_dst[0] = self.ask_count;
+        bid_count_dst[0] = self.bid_count;
+        pack_orders(&self.asks, asks_dst);
+        pack_orders(&self.bids, bids_dst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(base_amount: u64, quote_amount: u64) -> Order {
+        Order { escrow: Pubkey::new_unique(), base_amount, quote_amount }
+    }
+
+    #[test]
+    fn orders_are_sorted_by_price_then_time() {
+        let mut market = Market::new(Pubkey::new_unique(), Pubkey::new_unique(), 255);
+        let (cheap, dear, also_cheap) = (order(10, 20), order(10, 30), order(20, 40));
+        for ask in [dear, cheap, also_cheap] {
+            market.insert(Side::Ask, ask).unwrap();
+            market.insert(Side::Bid, ask).unwrap();
+        }
+
+        assert_eq!(market.orders(Side::Ask), &[cheap, also_cheap, dear]);
+        assert_eq!(market.orders(Side::Bid), &[dear, cheap, also_cheap]);
+
+        assert_eq!(market.remove(&cheap.escrow), Some(cheap));
+        assert_eq!(market.orders(Side::Ask), &[also_cheap, dear]);
+        assert!(market.contains(&cheap.escrow));
+        assert_eq!(market.remove(&cheap.escrow), Some(cheap));
+        assert!(!market.contains(&cheap.escrow));
+
+        let mut packed = vec![0u8; Market::LEN];
+        Market::pack(market.clone(), &mut packed).unwrap();
+        assert_eq!(Market::unpack(&packed).unwrap(), market);
+        assert_eq!(market.try_to_vec().unwrap(), packed);
+    }
+
+    #[test]
+    fn full_side_is_rejected() {
+        let mut market = Market::new(Pubkey::new_unique(), Pubkey::new_unique(), 255);
+        for _ in 0..MAX_ORDERS {
+            market.insert(Side::Bid, order(1, 1)).unwrap();
+        }
+        assert_eq!(market.insert(Side::Bid, order(1, 1)), Err(EscrowError::MarketFull.into()));
+        market.insert(Side::Ask, order(1, 1)).unwrap();
+    }
+}
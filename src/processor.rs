@@ -1,90 +1,665 @@
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    bpf_loader_upgradeable,
     entrypoint::ProgramResult,
-    msg,
-    program::{invoke, invoke_signed},
+    program::{invoke, invoke_signed, set_return_data},
     program_error::ProgramError,
+    program_option::COption,
     program_pack::{IsInitialized, Pack},
     pubkey::Pubkey,
-    sysvar::{rent::Rent, Sysvar},
+    system_instruction, system_program,
+    sysvar::{self, clock::Clock, rent::Rent, Sysvar},
 };
+use borsh::BorshSerialize;
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+use std::borrow::Cow;
 
-use spl_token::state::Account as TokenAccount;
-
-use crate::{error::EscrowError, instruction::EscrowInstruction, state::Escrow};
+use crate::{
+    compressed_escrow::{self, CompressedEscrow, CompressedTree},
+    compression,
+    error::EscrowError,
+    escrow_pool::{self, EscrowPool, PoolEntry},
+    event::EscrowEvent,
+    find_allowlist_address, find_bid_address, find_compressed_tree_address, find_config_address,
+    find_counteroffer_address, find_escrow_address, find_escrow_authority_address, find_escrow_pool_authority_address,
+    find_escrow_pool_vault_address, find_fee_authority_address, find_fee_vault_address, find_fill_record_address,
+    find_legacy_escrow_authority_address, find_market_address, find_quote_address, find_quote_vault_address,
+    find_raffle_address, find_raffle_vault_address, find_registry_address, find_ring_address, find_ring_vault_address,
+    find_sealed_auction_address, find_sealed_bid_address, find_sealed_bid_vault_address, find_stablecoins_address,
+    find_stats_address, find_ticket_address, find_vesting_address, find_vesting_vault_address, gate,
+    instruction::EscrowInstruction,
+    memo, metaplex,
+    oracle::{self, MAX_PRICE_AGE_SECONDS},
+    order_book::{Market, Order, Side},
+    protocol_fee,
+    raffle::{self, Raffle, Ticket},
+    sealed_auction::{self, SealedAuction, SealedBid},
+    signed_order, strict,
+    state::{
+        Config, Counteroffer, Escrow, EscrowRegistry, EscrowV1, FillRecord, MintAllowlist, Quote, StablecoinList,
+        Stats, VersionedEscrow, Vesting, ESCROW_DISCRIMINATOR, ESCROW_VERSION, MAX_ALLOWED_MINTS, MAX_APPROVERS,
+        MAX_FEE_BPS, MAX_METADATA_LEN, MAX_PAYMENT_OPTIONS, MAX_RESERVATION_SLOTS, MAX_STABLECOINS,
+    },
+    swap_ring::{RingLeg, SwapRing, MAX_RING_LEGS, MIN_RING_LEGS},
+    switchboard,
+    token,
+    transfer_hook,
+    validation::{KnownAccount, ProgramOwned, Signer, TokenProgram, WritableTokenAccount},
+    view::EscrowView,
+};
 
 pub struct Processor;
 impl Processor {
   pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
-    let instruction = EscrowInstruction::unpack(instruction_data)?;
+    Self::process_instruction(program_id, accounts, EscrowInstruction::unpack(instruction_data)?)
+  }
 
+  // Processes an instruction that has already been decoded, e.g. by the Anchor program's handlers
+  pub fn process_instruction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction: EscrowInstruction,
+    ) -> ProgramResult {
     match instruction {
-      EscrowInstruction::InitEscrow { amount } => {
-        msg!("Instruction: InitEscrow");
-        Self::process_init_escrow(accounts, amount, program_id)
+      EscrowInstruction::InitEscrow {
+        amount,
+        expiry_unix_timestamp,
+        allowed_taker,
+        deposit_amount,
+        dispute_window_seconds,
+        metadata,
+        escrow_id,
+        reservation_deposit,
+        taker_merkle_root,
+      } => {
+        log!("Instruction: InitEscrow");
+        let accounts = &Self::without_rent_sysvar(accounts);
+        if deposit_amount != 0 {
+          Self::create_temp_token_account(accounts, deposit_amount)?;
+        }
+        if escrow_id != 0 {
+          Self::create_escrow_account_with_id(accounts, escrow_id, program_id)?;
+        }
+        Self::process_init_escrow(accounts, amount, expiry_unix_timestamp, allowed_taker, 0, dispute_window_seconds, &Pubkey::default(), program_id)?;
+        Self::set_escrow_options(&accounts[3], &metadata, escrow_id, reservation_deposit, taker_merkle_root)
+      },
+      EscrowInstruction::Exchange { amount, max_payment, referrer_token_account, memo, taker_proof, relayer, relayer_tip } => {
+        log!("Instruction: Exchange");
+        Self::process_exchange(
+          accounts,
+          amount,
+          max_payment,
+          &referrer_token_account,
+          &taker_proof,
+          &relayer,
+          relayer_tip,
+          None,
+          program_id,
+        )?;
+        memo::log_memo(&memo, &accounts[0], accounts)
+      },
+      EscrowInstruction::Cancel { memo } => {
+        log!("Instruction: Cancel");
+        Self::process_cancel(accounts, program_id)?;
+        memo::log_memo(&memo, &accounts[0], accounts)
+      },
+      EscrowInstruction::ReclaimExpired => {
+        log!("Instruction: ReclaimExpired");
+        Self::process_reclaim_expired(accounts, program_id)
+      },
+      EscrowInstruction::InitNftEscrow { amount, expiry_unix_timestamp, allowed_taker } => {
+        log!("Instruction: InitNftEscrow");
+        let accounts = &Self::without_rent_sysvar(accounts);
+        Self::process_init_nft_escrow(accounts, amount, expiry_unix_timestamp, allowed_taker, program_id)
+      },
+      EscrowInstruction::InitFeeVault => {
+        log!("Instruction: InitFeeVault");
+        Self::process_init_fee_vault(accounts, program_id)
+      },
+      EscrowInstruction::InitializeConfig { fee_bps, default_expiry_seconds, referral_share_bps } => {
+        log!("Instruction: InitializeConfig");
+        Self::process_initialize_config(accounts, fee_bps, default_expiry_seconds, referral_share_bps, program_id)
+      },
+      EscrowInstruction::Pause => {
+        log!("Instruction: Pause");
+        Self::process_set_paused(accounts, true, program_id)
+      },
+      EscrowInstruction::Unpause => {
+        log!("Instruction: Unpause");
+        Self::process_set_paused(accounts, false, program_id)
+      },
+      EscrowInstruction::AddAllowedMint { mint } => {
+        log!("Instruction: AddAllowedMint");
+        Self::process_add_allowed_mint(accounts, &mint, program_id)
+      },
+      EscrowInstruction::RemoveAllowedMint { mint } => {
+        log!("Instruction: RemoveAllowedMint");
+        Self::process_remove_allowed_mint(accounts, &mint, program_id)
+      },
+      EscrowInstruction::SetAllowlistEnabled { enabled } => {
+        log!("Instruction: SetAllowlistEnabled");
+        Self::process_set_allowlist_enabled(accounts, enabled, program_id)
+      },
+      EscrowInstruction::UpdateEscrow { expected_amount } => {
+        log!("Instruction: UpdateEscrow");
+        Self::process_update_escrow(accounts, expected_amount, program_id)
+      },
+      EscrowInstruction::InitDutchAuction {
+        start_amount,
+        floor_amount,
+        auction_end_unix_timestamp,
+        expiry_unix_timestamp,
+      } => {
+        log!("Instruction: InitDutchAuction");
+        let accounts = &Self::without_rent_sysvar(accounts);
+        Self::process_init_dutch_auction(
+          accounts,
+          start_amount,
+          floor_amount,
+          auction_end_unix_timestamp,
+          expiry_unix_timestamp,
+          program_id,
+        )
+      },
+      EscrowInstruction::InitEnglishAuction { min_bid, auction_end_unix_timestamp } => {
+        log!("Instruction: InitEnglishAuction");
+        let accounts = &Self::without_rent_sysvar(accounts);
+        Self::process_init_english_auction(accounts, min_bid, auction_end_unix_timestamp, program_id)
+      },
+      EscrowInstruction::PlaceBid { amount } => {
+        log!("Instruction: PlaceBid");
+        Self::process_place_bid(accounts, amount, program_id)
+      },
+      EscrowInstruction::SettleAuction => {
+        log!("Instruction: SettleAuction");
+        Self::process_settle(accounts, false, program_id)
+      },
+      EscrowInstruction::WithdrawBid => {
+        log!("Instruction: WithdrawBid");
+        Self::process_withdraw_bid(accounts, program_id)
+      },
+      EscrowInstruction::InitDelegatedEscrow { amount_x, amount, expiry_unix_timestamp, allowed_taker } => {
+        log!("Instruction: InitDelegatedEscrow");
+        let accounts = &Self::without_rent_sysvar(accounts);
+        // A delegated_amount of 0 would be read back as an escrow that isn't delegated
+        if amount_x == 0 {
+          return Err(EscrowError::InvalidInstruction.into());
+        }
+        Self::process_init_escrow(accounts, amount, expiry_unix_timestamp, allowed_taker, amount_x, 0, &Pubkey::default(), program_id)
+      },
+      EscrowInstruction::Accept { max_payment } => {
+        log!("Instruction: Accept");
+        Self::process_accept(accounts, max_payment, program_id)
+      },
+      EscrowInstruction::Settle => {
+        log!("Instruction: Settle");
+        Self::process_settle(accounts, true, program_id)
+      },
+      EscrowInstruction::SetApprovers { threshold } => {
+        log!("Instruction: SetApprovers");
+        Self::process_set_approvers(accounts, threshold, program_id)
+      },
+      EscrowInstruction::ApproveRelease => {
+        log!("Instruction: ApproveRelease");
+        Self::process_approve_release(accounts, program_id)
+      },
+      EscrowInstruction::InitOracleEscrow { premium_bps, expiry_unix_timestamp } => {
+        log!("Instruction: InitOracleEscrow");
+        let accounts = &Self::without_rent_sysvar(accounts);
+        Self::process_init_oracle_escrow(accounts, premium_bps, expiry_unix_timestamp, program_id)
+      },
+      EscrowInstruction::InitVestingEscrow {
+        amount,
+        expiry_unix_timestamp,
+        vesting_period_seconds,
+        vesting_periods,
+      } => {
+        log!("Instruction: InitVestingEscrow");
+        let accounts = &Self::without_rent_sysvar(accounts);
+        Self::process_init_vesting_escrow(
+          accounts,
+          amount,
+          expiry_unix_timestamp,
+          vesting_period_seconds,
+          vesting_periods,
+          program_id,
+        )
+      },
+      EscrowInstruction::ClaimVested => {
+        log!("Instruction: ClaimVested");
+        Self::process_claim_vested(accounts, program_id)
+      },
+      EscrowInstruction::InitStreamEscrow { recipient, stream_end_unix_timestamp } => {
+        log!("Instruction: InitStreamEscrow");
+        let accounts = &Self::without_rent_sysvar(accounts);
+        Self::process_init_stream_escrow(accounts, recipient, stream_end_unix_timestamp, program_id)
+      },
+      EscrowInstruction::Withdraw => {
+        log!("Instruction: Withdraw");
+        Self::process_withdraw(accounts, program_id)
+      },
+      EscrowInstruction::MatchEscrows => {
+        log!("Instruction: MatchEscrows");
+        Self::process_match_escrows(accounts, program_id)
+      },
+      EscrowInstruction::InitMarket => {
+        log!("Instruction: InitMarket");
+        Self::process_init_market(accounts, program_id)
+      },
+      EscrowInstruction::PlaceOrder => {
+        log!("Instruction: PlaceOrder");
+        Self::process_place_order(accounts, program_id)
+      },
+      EscrowInstruction::CancelOrder => {
+        log!("Instruction: CancelOrder");
+        Self::process_cancel_order(accounts, program_id)
+      },
+      EscrowInstruction::ConsumeOrders => {
+        log!("Instruction: ConsumeOrders");
+        Self::process_consume_orders(accounts, program_id)
+      },
+      EscrowInstruction::InitRegistry => {
+        log!("Instruction: InitRegistry");
+        Self::process_init_registry(accounts, program_id)
+      },
+      EscrowInstruction::PruneRegistry => {
+        log!("Instruction: PruneRegistry");
+        Self::process_prune_registry(accounts, program_id)
+      },
+      EscrowInstruction::MigrateEscrow => {
+        log!("Instruction: MigrateEscrow");
+        Self::process_migrate_escrow(accounts, program_id)
+      },
+      EscrowInstruction::CloseStaleEscrow => {
+        log!("Instruction: CloseStaleEscrow");
+        Self::process_close_stale_escrow(accounts, program_id)
+      },
+      EscrowInstruction::GetEscrow => {
+        log!("Instruction: GetEscrow");
+        Self::process_get_escrow(accounts, program_id)
+      }
+      EscrowInstruction::InitRateEscrow { price_numerator, price_denominator, expiry_unix_timestamp } => {
+        log!("Instruction: InitRateEscrow");
+        let accounts = &Self::without_rent_sysvar(accounts);
+        Self::process_init_rate_escrow(
+          accounts,
+          price_numerator,
+          price_denominator,
+          expiry_unix_timestamp,
+          Pubkey::default(),
+          program_id,
+        )
+      }
+      EscrowInstruction::SetFee { fee_bps, fee_collector } => {
+        log!("Instruction: SetFee");
+        Self::process_set_fee(accounts, fee_bps, fee_collector, program_id)
+      }
+      EscrowInstruction::InitStats => {
+        log!("Instruction: InitStats");
+        Self::process_init_stats(accounts, program_id)
+      }
+      EscrowInstruction::Reserve { slots } => {
+        log!("Instruction: Reserve");
+        Self::process_reserve(accounts, slots, program_id)
+      }
+      EscrowInstruction::SetCancelPenalty { penalty_bps, penalty_amount, to_counterparty } => {
+        log!("Instruction: SetCancelPenalty");
+        Self::process_set_cancel_penalty(accounts, penalty_bps, penalty_amount, to_counterparty, program_id)
+      }
+      EscrowInstruction::SetGateProgram { gate_program } => {
+        log!("Instruction: SetGateProgram");
+        Self::process_set_gate_program(accounts, &gate_program, program_id)
+      }
+      EscrowInstruction::InitCollectionOffer { collection, expiry_unix_timestamp } => {
+        log!("Instruction: InitCollectionOffer");
+        let accounts = &Self::without_rent_sysvar(accounts);
+        Self::process_init_escrow(accounts, 1, expiry_unix_timestamp, Pubkey::default(), 0, 0, &collection, program_id)
+      }
+      EscrowInstruction::FillCollectionOffer => {
+        log!("Instruction: FillCollectionOffer");
+        Self::process_fill_collection_offer(accounts, program_id)
+      },
+      EscrowInstruction::InitLamportEscrow { lamports, amount, expiry_unix_timestamp, allowed_taker } => {
+        log!("Instruction: InitLamportEscrow");
+        Self::process_init_lamport_escrow(accounts, lamports, amount, expiry_unix_timestamp, allowed_taker, program_id)
+      },
+      EscrowInstruction::InitBuyEscrow { amount, expiry_unix_timestamp, allowed_taker } => {
+        log!("Instruction: InitBuyEscrow");
+        let accounts = &Self::without_rent_sysvar(accounts);
+        Self::process_init_buy_escrow(accounts, amount, expiry_unix_timestamp, allowed_taker, program_id)
+      },
+      EscrowInstruction::Abort => {
+        log!("Instruction: Abort");
+        Self::process_abort(accounts, program_id)
+      },
+      EscrowInstruction::ExtendDeadline { expiry_unix_timestamp } => {
+        log!("Instruction: ExtendDeadline");
+        Self::process_extend_deadline(accounts, expiry_unix_timestamp, program_id)
+      },
+      EscrowInstruction::SetMaxExpiry { max_expiry_seconds } => {
+        log!("Instruction: SetMaxExpiry");
+        Self::process_set_max_expiry(accounts, max_expiry_seconds, program_id)
+      },
+      EscrowInstruction::TransferEscrow => {
+        log!("Instruction: TransferEscrow");
+        Self::process_transfer_escrow(accounts, program_id)
+      },
+      EscrowInstruction::SettleSignedOrder { amount, max_payment, price, order_taker, order_expiry_unix_timestamp } => {
+        log!("Instruction: SettleSignedOrder");
+        Self::process_settle_signed_order(
+          accounts,
+          amount,
+          max_payment,
+          price,
+          &order_taker,
+          order_expiry_unix_timestamp,
+          program_id,
+        )
+      },
+      EscrowInstruction::SetPaymentOption { amount } => {
+        log!("Instruction: SetPaymentOption");
+        Self::process_set_payment_option(accounts, amount, program_id)
+      },
+      EscrowInstruction::AddStablecoin { mint, price_feed, tolerance_bps } => {
+        log!("Instruction: AddStablecoin");
+        Self::process_add_stablecoin(accounts, &mint, &price_feed, tolerance_bps, program_id)
       },
-      EscrowInstruction::Exchange { amount } => {
-        msg!("Instruction: Exchange");
-        Self::process_exchange(accounts, amount, program_id)
+      EscrowInstruction::RemoveStablecoin { mint } => {
+        log!("Instruction: RemoveStablecoin");
+        Self::process_remove_stablecoin(accounts, &mint, program_id)
+      },
+      EscrowInstruction::SetUsdPrice { usd_amount } => {
+        log!("Instruction: SetUsdPrice");
+        Self::process_set_usd_price(accounts, usd_amount, program_id)
+      },
+      EscrowInstruction::InitNftSwap { expiry_unix_timestamp, allowed_taker } => {
+        log!("Instruction: InitNftSwap");
+        let accounts = &Self::without_rent_sysvar(accounts);
+        Self::process_init_nft_swap(accounts, expiry_unix_timestamp, allowed_taker, program_id)
+      },
+      EscrowInstruction::InitRing { ring_id, deadline_unix_timestamp, amounts } => {
+        log!("Instruction: InitRing");
+        Self::process_init_ring(accounts, ring_id, deadline_unix_timestamp, &amounts, program_id)
+      },
+      EscrowInstruction::DepositRingLeg => {
+        log!("Instruction: DepositRingLeg");
+        Self::process_deposit_ring_leg(accounts, program_id)
+      },
+      EscrowInstruction::ExecuteRing => {
+        log!("Instruction: ExecuteRing");
+        Self::process_execute_ring(accounts, program_id)
+      },
+      EscrowInstruction::WithdrawRingLeg => {
+        log!("Instruction: WithdrawRingLeg");
+        Self::process_withdraw_ring_leg(accounts, program_id)
+      },
+      EscrowInstruction::CloseRing => {
+        log!("Instruction: CloseRing");
+        Self::process_close_ring(accounts, program_id)
+      },
+      EscrowInstruction::PostQuote { size, price, expiry_unix_timestamp, lock_funds } => {
+        log!("Instruction: PostQuote");
+        Self::process_post_quote(accounts, size, price, expiry_unix_timestamp, lock_funds, program_id)
+      },
+      EscrowInstruction::AcceptQuote => {
+        log!("Instruction: AcceptQuote");
+        Self::process_accept_quote(accounts, program_id)
+      },
+      EscrowInstruction::CancelQuote => {
+        log!("Instruction: CancelQuote");
+        Self::process_cancel_quote(accounts, program_id)
+      },
+      EscrowInstruction::ProposeCounteroffer { amount } => {
+        log!("Instruction: ProposeCounteroffer");
+        Self::process_propose_counteroffer(accounts, amount, program_id)
+      },
+      EscrowInstruction::AcceptCounteroffer => {
+        log!("Instruction: AcceptCounteroffer");
+        Self::process_accept_counteroffer(accounts, program_id)
+      },
+      EscrowInstruction::InitSealedAuction {
+        min_bid,
+        deposit,
+        commit_end_unix_timestamp,
+        reveal_end_unix_timestamp,
+      } => {
+        log!("Instruction: InitSealedAuction");
+        let accounts = &Self::without_rent_sysvar(accounts);
+        Self::process_init_sealed_auction(
+          accounts,
+          min_bid,
+          deposit,
+          commit_end_unix_timestamp,
+          reveal_end_unix_timestamp,
+          program_id,
+        )
+      },
+      EscrowInstruction::CommitBid { commitment } => {
+        log!("Instruction: CommitBid");
+        Self::process_commit_bid(accounts, commitment, program_id)
+      },
+      EscrowInstruction::RevealBid { amount, salt } => {
+        log!("Instruction: RevealBid");
+        Self::process_reveal_bid(accounts, amount, &salt, program_id)
+      },
+      EscrowInstruction::SlashBid => {
+        log!("Instruction: SlashBid");
+        Self::process_slash_bid(accounts, program_id)
+      },
+      EscrowInstruction::CloseSealedAuction => {
+        log!("Instruction: CloseSealedAuction");
+        Self::process_close_sealed_auction(accounts, program_id)
+      },
+      EscrowInstruction::InitRaffle { ticket_price, max_tickets, end_unix_timestamp, vrf } => {
+        log!("Instruction: InitRaffle");
+        let accounts = &Self::without_rent_sysvar(accounts);
+        Self::process_init_raffle(accounts, ticket_price, max_tickets, end_unix_timestamp, &vrf, program_id)
+      },
+      EscrowInstruction::BuyTickets { count } => {
+        log!("Instruction: BuyTickets");
+        Self::process_buy_tickets(accounts, count, program_id)
+      },
+      EscrowInstruction::DrawRaffle => {
+        log!("Instruction: DrawRaffle");
+        Self::process_draw_raffle(accounts, program_id)
+      },
+      EscrowInstruction::SettleRaffle => {
+        log!("Instruction: SettleRaffle");
+        Self::process_settle_raffle(accounts, program_id)
+      },
+      EscrowInstruction::CloseRaffle => {
+        log!("Instruction: CloseRaffle");
+        Self::process_close_raffle(accounts, program_id)
+      },
+      EscrowInstruction::CloseTicket => {
+        log!("Instruction: CloseTicket");
+        Self::process_close_ticket(accounts, program_id)
+      },
+      EscrowInstruction::RequestRaffleRandomness { permission_bump, state_bump } => {
+        log!("Instruction: RequestRaffleRandomness");
+        Self::process_request_raffle_randomness(accounts, permission_bump, state_bump, program_id)
+      },
+      EscrowInstruction::ConsumeRaffleRandomness => {
+        log!("Instruction: ConsumeRaffleRandomness");
+        Self::process_consume_raffle_randomness(accounts, program_id)
+      },
+      EscrowInstruction::InitEscrowPool => {
+        log!("Instruction: InitEscrowPool");
+        Self::process_init_escrow_pool(accounts, program_id)
+      },
+      EscrowInstruction::PostPoolEntry { amount_x, amount_y, expiry_unix_timestamp } => {
+        log!("Instruction: PostPoolEntry");
+        Self::process_post_pool_entry(accounts, amount_x, amount_y, expiry_unix_timestamp, program_id)
+      },
+      EscrowInstruction::TakePoolEntry { index, amount_x, max_payment } => {
+        log!("Instruction: TakePoolEntry");
+        Self::process_take_pool_entry(accounts, index, amount_x, max_payment, program_id)
+      },
+      EscrowInstruction::CancelPoolEntry { index } => {
+        log!("Instruction: CancelPoolEntry");
+        Self::process_cancel_pool_entry(accounts, index, program_id)
+      },
+      EscrowInstruction::CloseEscrowPool => {
+        log!("Instruction: CloseEscrowPool");
+        Self::process_close_escrow_pool(accounts, program_id)
+      },
+      EscrowInstruction::InitCompressedTree { max_depth, max_buffer_size, canopy_depth } => {
+        log!("Instruction: InitCompressedTree");
+        Self::process_init_compressed_tree(accounts, max_depth, max_buffer_size, canopy_depth, program_id)
+      },
+      EscrowInstruction::InitCompressedEscrow { amount_x, amount_y, expiry_unix_timestamp } => {
+        log!("Instruction: InitCompressedEscrow");
+        Self::process_init_compressed_escrow(accounts, amount_x, amount_y, expiry_unix_timestamp, program_id)
+      },
+      EscrowInstruction::ExchangeCompressed { escrow, root, max_payment } => {
+        log!("Instruction: ExchangeCompressed");
+        Self::process_exchange_compressed(accounts, escrow, root, max_payment, program_id)
+      },
+      EscrowInstruction::CancelCompressed { escrow, root } => {
+        log!("Instruction: CancelCompressed");
+        Self::process_cancel_compressed(accounts, escrow, root, program_id)
+      },
+      EscrowInstruction::UpgradeEscrowAccount => {
+        log!("Instruction: UpgradeEscrowAccount");
+        Self::process_upgrade_escrow_account(accounts, program_id)
       }
     }
   }
 
+  // The instructions that create escrows used to take the rent sysvar as account 4. Rent::get() made it unnecessary, but older clients still send it, so it's taken out before the accounts are read by position.
+  fn without_rent_sysvar<'a, 'b>(accounts: &'a [AccountInfo<'b>]) -> Cow<'a, [AccountInfo<'b>]> {
+        match accounts.get(4) {
+            Some(account) if *account.key == sysvar::rent::id() => {
+                Cow::Owned(accounts[..4].iter().chain(&accounts[5..]).cloned().collect())
+            }
+            _ => Cow::Borrowed(accounts),
+        }
+    }
+
+  #[allow(clippy::too_many_arguments)]
   fn process_init_escrow(
         accounts: &[AccountInfo],
         amount: u64,
+        expiry_unix_timestamp: i64,
+        allowed_taker: Pubkey,
+        delegated_amount: u64,
+        dispute_window_seconds: i64,
+        collection: &Pubkey,
         program_id: &Pubkey,
     ) -> ProgramResult {
         // needs to be mutable so we can take elements out of it.
         // The first account we expect - as defined in instruction.rs - is the escrow's initializer, i.e. INITIALIZER's main account. They need to be a signer which we check right away. It's just a boolean field on AccountInfo.
         let account_info_iter = &mut accounts.iter();
-        let initializer = next_account_info(account_info_iter)?;
+        let initializer = Signer::new(next_account_info(account_info_iter)?)?;
 
-        if !initializer.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+        //  The temporary token account needs to be writable. The transaction would fail anyway should INITIALIZER not mark the account as writable, but WritableTokenAccount fails earlier and says why.
+        let temp_token_account = WritableTokenAccount::new(next_account_info(account_info_iter)?)?;
+        let temp_token_account_info = temp_token_account.state;
+        if delegated_amount != 0 {
+            // Closing the temp token account is what pays out native SOL, and the initializer's own account can't be closed by the PDA
+            if temp_token_account_info.mint == spl_token::native_mint::id() {
+                return Err(EscrowError::NativeSolNotDelegable.into());
+            }
+            if temp_token_account_info.amount < delegated_amount {
+                return Err(ProgramError::InsufficientFunds);
+            }
         }
 
-        //  The temporary token account needs to be writable but there is no need to explicitly check this. The transaction will fail automatically should INITIALIZER not mark the account as writable.
-        let temp_token_account = next_account_info(account_info_iter)?;
-
+        // The token account has to be owned by one of the token programs, which unpack_token_account checks for us. Collection offers don't know which NFT they get until they are filled, so they pass the collection's mint instead and have no token Y.
         let token_to_receive_account = next_account_info(account_info_iter)?;
-        if *token_to_receive_account.owner != spl_token::id() {
-            return Err(ProgramError::IncorrectProgramId);
-        }
+        let token_y_mint = if *collection == Pubkey::default() {
+            token::unpack_token_account(token_to_receive_account)?.mint
+        } else if token_to_receive_account.key == collection {
+            Pubkey::default()
+        } else {
+            return Err(EscrowError::CollectionMismatch.into());
+        };
         
         let escrow_account = next_account_info(account_info_iter)?;
-        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let rent = Rent::get()?;
+
+        // Clients used to create the escrow account in an instruction of their own, which still works. One that doesn't exist yet is created here instead.
+        if *escrow_account.owner == system_program::id() {
+            Self::create_escrow_account(accounts, &initializer, escrow_account, &rent, program_id)?;
+        }
+        let escrow_account = ProgramOwned::<Escrow>::new_uninitialized(escrow_account, program_id)?;
 
         if !rent.is_exempt(escrow_account.lamports(), escrow_account.data_len()) {
             return Err(EscrowError::NotRentExempt.into());
         }
 
-        let mut escrow_info = Escrow::unpack_unchecked(&escrow_account.try_borrow_data()?)?;
-        if escrow_info.is_initialized() {
-            return Err(ProgramError::AccountAlreadyInitialized);
+        let mut escrow_info = escrow_account.state;
+        if dispute_window_seconds < 0 {
+            return Err(EscrowError::InvalidDisputeWindow.into());
+        }
+
+        // The config comes after the token program, but its default expiry is needed before the escrow is written
+        let config = Self::unpack_config(
+            accounts.get(5).ok_or(ProgramError::NotEnoughAccountKeys)?,
+            program_id,
+        )?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        if config.allowlist_enabled {
+            let allowlist = Self::unpack_allowlist(
+                accounts.get(6).ok_or(ProgramError::NotEnoughAccountKeys)?,
+                program_id,
+            )?;
+            if !allowlist.contains(&temp_token_account_info.mint)
+                || (*collection == Pubkey::default() && !allowlist.contains(&token_y_mint))
+            {
+                return Err(EscrowError::MintNotAllowed.into());
+            }
         }
 
+        // Like Rent::get() above, Clock::get() reads the sysvar through a syscall, so there is no sysvar account to pass in
+        let now = Clock::get()?.unix_timestamp;
+        let expiry_unix_timestamp = Self::expiry_with_default(&config, expiry_unix_timestamp, now)?;
+
         // With Escrow struct instance created and and checked that it was not previously initialized, we now populate the struct's fields
-        escrow_info.is_initialized = true;
+        escrow_info.discriminator = ESCROW_DISCRIMINATOR;
+        escrow_info.version = ESCROW_VERSION;
+        escrow_info.is_initialized = true.into();
         escrow_info.initializer_pubkey = *initializer.key;
         escrow_info.temp_token_account_pubkey = *temp_token_account.key;
-        escrow_info.initializer_token_to_receive_account_pubkey = *token_to_receive_account.key;
+        if *collection == Pubkey::default() {
+            escrow_info.initializer_token_to_receive_account_pubkey = *token_to_receive_account.key;
+        }
         escrow_info.expected_amount = amount;
-        // Pack will call pack_into_slice
-        Escrow::pack(escrow_info, &mut escrow_account.try_borrow_mut_data()?)?;
+        escrow_info.expiry_unix_timestamp = expiry_unix_timestamp;
+        escrow_info.allowed_taker_pubkey = allowed_taker;
+        escrow_info.delegated_amount = delegated_amount;
+        escrow_info.dispute_window_seconds = dispute_window_seconds;
+        escrow_info.created_unix_timestamp = now;
+        // Remembering the mints lets Exchange make sure the taker pays with, and receives, the right tokens
+        escrow_info.token_x_mint = temp_token_account_info.mint;
+        escrow_info.token_y_mint = token_y_mint;
+        escrow_info.collection = *collection;
         // Create PDA by passing in an array of seeds, plus the program_id
         //
-        // In our case the seeds can be static. There are cases such as in the Associated Token Account program where they aren't (because different users should own different associated token accounts). We just need 1 PDA that can own N temporary token accounts for different escrows occuring at any and possibly the same point in time.
+        // The escrow account's pubkey is part of the seeds, so every escrow gets its own PDA - similar to the Associated Token Account program where different users own different associated token accounts. A single static seed would work too, but then one PDA would be the authority of every temp token account and a bug in any path that signs for it would put the tokens of all escrows at risk.
         //
         // PDAs are public keys that are derived from the program_id and the seeds as well as having been pushed off the curve by the bump seed. Hence, Program Derived Addresses do not lie on the ed25519 curve and therefore have no private key associated with them.
         //
         // A PDA is just a random array of bytes with the only defining feature being that they are not on that curve. That said, they can still be used as normal addresses most of the time. 
-        let (pda, _bump_seed) = Pubkey::find_program_address(&[b"escrow"], program_id);
+        let (pda, bump_seed) = find_escrow_authority_address(escrow_account.key, program_id);
+
+        // find_program_address loops over bump seeds until it finds one that pushes the address off the curve, which costs a lot of compute. We only pay for it once here and store the bump so the other instructions can use the much cheaper create_program_address.
+        escrow_info.bump_seed = bump_seed;
+        Self::register_escrow(accounts, escrow_account.key, &escrow_info, program_id)?;
+        let escrowed_amount = escrow_info.escrowed_amount(&temp_token_account_info);
+        Self::update_stats(accounts, program_id, |stats| stats.record_created(escrowed_amount))?;
+        // Pack will call pack_into_slice
+        Escrow::pack(escrow_info, &mut escrow_account.try_borrow_mut_data()?)?;
 
         // Invoke CPI to transfer the (user space) ownership of the temporary token account to the PDA. 
 
         // First, create the token_program account. The program being called through a CPI must be ingcluded in the 2nd argument as an account. 
         let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || temp_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
         // set_authority is a builder function that creates the instruction for the token program
         //
         // We pass in: 
@@ -100,159 +675,8197 @@ impl Processor {
         //  When including a signed account in a program call, in all CPIs including that account made by that program inside the current instruction, the account will also be signed, i.e. the signature is extended to the CPIs.
         //
         //  In our case this means that because INITIALIZER signed the InitEscrow transaction, the program can make the token program set_authority CPI and include their pubkey as a signer pubkey. This is necessary because changing a token account's authority should of course require the approval of the current authority.
-        let owner_change_ix = spl_token::instruction::set_authority(
-            token_program.key,
-            temp_token_account.key,
-            Some(&pda),
-            spl_token::instruction::AuthorityType::AccountOwner,
-            initializer.key,
-            &[&initializer.key],
-        )?;
+        //
+        // The temp token account can belong to either the original token program or Token-2022, so the instruction is pointed at whichever one owns it. Note that Token-2022 accounts with the ImmutableOwner extension (e.g. associated token accounts) can't be used as temp token accounts.
+        if delegated_amount != 0 {
+            // A delegated escrow leaves the initializer as the owner and only lets the PDA transfer delegated_amount out of the account. approve needs the owner's signature, which is extended to the CPI in the same way as for set_authority below.
+            let pda_account = accounts.get(7).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if *pda_account.key != pda {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            let approve_ix = token::for_token_program(
+                spl_token::instruction::approve(
+                    &spl_token::id(),
+                    temp_token_account.key,
+                    &pda,
+                    initializer.key,
+                    &[initializer.key],
+                    delegated_amount,
+                ),
+                token_program.key,
+            )?;
+            log!("Calling the token program to delegate the tokens to the PDA...");
+            invoke(
+                &approve_ix,
+                &[
+                    temp_token_account.clone(),
+                    pda_account.clone(),
+                    initializer.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+        } else if temp_token_account_info.owner != pda {
+            // A programmable NFT escrow's temp token account is the PDA's already, see process_init_nft_escrow
+            let owner_change_ix = token::for_token_program(
+                spl_token::instruction::set_authority(
+                    &spl_token::id(),
+                    temp_token_account.key,
+                    Some(&pda),
+                    spl_token::instruction::AuthorityType::AccountOwner,
+                    initializer.key,
+                    &[initializer.key],
+                ),
+                token_program.key,
+            )?;
 
-        // Note that before making a CPI, we should add another check that the token_program is truly the account of the token program. Otherwise, we might be calling a rogue program. If you're using the spl-token crate above version 3.1.1 (which I do in this guide), you don't have to do this if you use their instruction builder functions. They do it for you.
+            // Note that before making a CPI, we should check that the token_program is truly the account of a token program. Otherwise, we might be calling a rogue program. We check it explicitly right after taking the account out of the iterator, since for_token_program bypasses the check the spl-token instruction builders would otherwise do for us.
 
-        msg!("Calling the token program to transfer token account ownership...");
-        invoke(
-            &owner_change_ix,
-            &[
-                temp_token_account.clone(),
-                initializer.clone(),
-                token_program.clone(),
-            ],
-        )?;
+            log!("Calling the token program to transfer token account ownership...");
+            invoke(
+                &owner_change_ix,
+                &[
+                    temp_token_account.clone(),
+                    initializer.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+        }
+
+        EscrowEvent::EscrowInitialized {
+            escrow: *escrow_account.key,
+            initializer: *initializer.key,
+            token_x_mint: temp_token_account_info.mint,
+            token_y_mint,
+            amount_x: escrow_info.escrowed_amount(&temp_token_account_info),
+            expected_amount: amount,
+            expiry_unix_timestamp,
+        }
+        .emit()?;
 
         Ok(())
     }
 
-    // INITIALLY SIMILAR TO PROCESS INIT ESCROW
-    fn process_exchange(
-        accounts: &[AccountInfo],
-        amount_expected_by_taker: u64,
+    // An expiry of 0 falls back to the config's default expiry, if it has one
+    fn expiry_with_default(config: &Config, expiry_unix_timestamp: i64, now: i64) -> Result<i64, ProgramError> {
+        let expiry_unix_timestamp = if expiry_unix_timestamp == 0 && config.default_expiry_seconds != 0 {
+            now.checked_add(config.default_expiry_seconds).ok_or(EscrowError::TimeOverflow)?
+        } else {
+            expiry_unix_timestamp
+        };
+        if expiry_unix_timestamp != 0 && expiry_unix_timestamp <= now {
+            return Err(EscrowError::ExpiryInThePast.into());
+        }
+        Self::check_max_expiry(config, expiry_unix_timestamp, now)?;
+        Ok(expiry_unix_timestamp)
+    }
+
+    // With a max_expiry_seconds, never expiring is too far ahead as well
+    fn check_max_expiry(config: &Config, expiry_unix_timestamp: i64, now: i64) -> ProgramResult {
+        if config.max_expiry_seconds != 0
+            && (expiry_unix_timestamp == 0 || expiry_unix_timestamp > now.saturating_add(config.max_expiry_seconds))
+        {
+            return Err(EscrowError::ExpiryTooFarAhead.into());
+        }
+        Ok(())
+    }
+
+    // Like the temp token account, the escrow account is a new keypair that signs the transaction and the initializer pays for it. The system program is looked up rather than read by position because the init instructions have different optional accounts.
+    fn create_escrow_account<'a>(
+        accounts: &[AccountInfo<'a>],
+        initializer: &AccountInfo<'a>,
+        escrow_account: &AccountInfo<'a>,
+        rent: &Rent,
         program_id: &Pubkey,
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let taker = next_account_info(account_info_iter)?;
+        let system_program_account = accounts
+            .iter()
+            .find(|account| *account.key == system_program::id())
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        log!("Calling the system program to create the escrow account...");
+        invoke(
+            &system_instruction::create_account(
+                initializer.key,
+                escrow_account.key,
+                rent.minimum_balance(Escrow::LEN),
+                Escrow::LEN as u64,
+                program_id,
+            ),
+            &[initializer.clone(), escrow_account.clone(), system_program_account.clone()],
+        )
+    }
 
-        if !taker.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+    // Escrows with an id live at a PDA, which can't sign its own creation like a keypair escrow account does in create_escrow_account, so the program signs for it. The initializer pays. If the account exists already, the id is taken by an open escrow of the initializer's, which process_init_escrow rejects as already initialized.
+    fn create_escrow_account_with_id(accounts: &[AccountInfo], escrow_id: u64, program_id: &Pubkey) -> ProgramResult {
+        let get = |index: usize| accounts.get(index).ok_or(ProgramError::NotEnoughAccountKeys);
+        let initializer = get(0)?;
+        let escrow_account = get(3)?;
+        let (escrow_address, bump_seed) = find_escrow_address(initializer.key, escrow_id, program_id);
+        if *escrow_account.key != escrow_address {
+            return Err(ProgramError::InvalidSeeds);
         }
+        if *escrow_account.owner != system_program::id() {
+            return Ok(());
+        }
+        let system_program_account = accounts
+            .iter()
+            .find(|account| *account.key == system_program::id())
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        log!("Calling the system program to create the escrow account...");
+        invoke_signed(
+            &system_instruction::create_account(
+                initializer.key,
+                escrow_account.key,
+                Rent::get()?.minimum_balance(Escrow::LEN),
+                Escrow::LEN as u64,
+                program_id,
+            ),
+            &[initializer.clone(), escrow_account.clone(), system_program_account.clone()],
+            &[&[b"escrow", initializer.key.as_ref(), &escrow_id.to_le_bytes(), &[bump_seed]]],
+        )
+    }
 
-        let takers_sending_token_account = next_account_info(account_info_iter)?;
+    // Does what clients otherwise do in the instructions before InitEscrow: create the temp token account, initialize it with the initializer as its owner and fund it. process_init_escrow then hands it over to the PDA like any other temp token account.
+    fn create_temp_token_account(accounts: &[AccountInfo], deposit_amount: u64) -> ProgramResult {
+        let get = |index: usize| accounts.get(index).ok_or(ProgramError::NotEnoughAccountKeys);
+        let initializer = get(0)?;
+        let temp_token_account = get(1)?;
+        let token_program = get(4)?;
+        let token_x_mint_account = get(8)?;
+        let system_program_account = get(9)?;
 
-        let takers_token_to_receive_account = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let token_x_mint_info = token::unpack_mint(token_x_mint_account)?;
+        let rent = Rent::get()?;
 
-        let pdas_temp_token_account = next_account_info(account_info_iter)?;
-        let pdas_temp_token_account_info =
-            TokenAccount::unpack(&pdas_temp_token_account.try_borrow_data()?)?;
-        let (pda, nonce) = Pubkey::find_program_address(&[b"escrow"], program_id);
+        // Only the base token account is allocated, so this doesn't work for Token-2022 mints whose accounts need extensions
+        log!("Calling the system program to create the temp token account...");
+        invoke(
+            &system_instruction::create_account(
+                initializer.key,
+                temp_token_account.key,
+                rent.minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                token_program.key,
+            ),
+            &[initializer.clone(), temp_token_account.clone(), system_program_account.clone()],
+        )?;
 
-        if amount_expected_by_taker != pdas_temp_token_account_info.amount {
-            return Err(EscrowError::ExpectedAmountMismatch.into());
+        // InitializeAccount3 takes the owner as an argument rather than an account and doesn't need the rent sysvar
+        let init_temp_account_ix = token::for_token_program(
+            spl_token::instruction::initialize_account3(
+                &spl_token::id(),
+                temp_token_account.key,
+                token_x_mint_account.key,
+                initializer.key,
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to initialize the temp token account...");
+        invoke(
+            &init_temp_account_ix,
+            &[temp_token_account.clone(), token_x_mint_account.clone(), token_program.clone()],
+        )?;
+
+        // Native SOL is wrapped by sending the lamports to the new account and having the token program sync its balance. The initializer's token account isn't needed for that.
+        if *token_x_mint_account.key == spl_token::native_mint::id() {
+            log!("Calling the system program to deposit SOL into the temp token account...");
+            invoke(
+                &system_instruction::transfer(initializer.key, temp_token_account.key, deposit_amount),
+                &[initializer.clone(), temp_token_account.clone(), system_program_account.clone()],
+            )?;
+            log!("Calling the token program to wrap the deposited SOL...");
+            return invoke(
+                &spl_token::instruction::sync_native(&spl_token::id(), temp_token_account.key)?,
+                &[temp_token_account.clone(), token_program.clone()],
+            );
         }
 
-        let initializers_main_account = next_account_info(account_info_iter)?;
-        let initializers_token_to_receive_account = next_account_info(account_info_iter)?;
+        let initializers_token_account = get(7)?;
+        let mut deposit_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                initializers_token_account.key,
+                token_x_mint_account.key,
+                temp_token_account.key,
+                initializer.key,
+                &[initializer.key],
+                deposit_amount,
+                token_x_mint_info.decimals,
+            ),
+            token_program.key,
+        )?;
+        let mut account_infos = vec![
+            initializers_token_account.clone(),
+            token_x_mint_account.clone(),
+            temp_token_account.clone(),
+            initializer.clone(),
+            token_program.clone(),
+        ];
+        transfer_hook::add_hook_accounts(&mut deposit_ix, &mut account_infos, token_x_mint_account, deposit_amount, accounts)?;
+        log!("Calling the token program to deposit the tokens into the temp token account...");
+        invoke(&deposit_ix, &account_infos)
+    }
+
+    // An NFT escrow is a regular escrow with an amount of 1, so after the mint checks everything is handed over to process_init_escrow. The NFT mint is the last account, which process_init_escrow never looks at.
+    // The lamports go straight into the PDA, so unlike process_init_escrow there is no temp token account to hand over
+    fn process_init_lamport_escrow(
+        accounts: &[AccountInfo],
+        lamports: u64,
+        amount: u64,
+        expiry_unix_timestamp: i64,
+        allowed_taker: Pubkey,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let pda_account = next_account_info(account_info_iter)?;
+        let token_to_receive_account = next_account_info(account_info_iter)?;
+        let token_y_mint = token::unpack_token_account(token_to_receive_account)?.mint;
         let escrow_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let rent = Rent::get()?;
 
-        let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+        if *escrow_account.owner == system_program::id() {
+            Self::create_escrow_account(accounts, initializer, escrow_account, &rent, program_id)?;
+        }
 
-        if escrow_info.temp_token_account_pubkey != *pdas_temp_token_account.key {
-            return Err(ProgramError::InvalidAccountData);
+        if !rent.is_exempt(escrow_account.lamports(), escrow_account.data_len()) {
+            return Err(EscrowError::NotRentExempt.into());
         }
 
-        if escrow_info.initializer_pubkey != *initializers_main_account.key {
-            return Err(ProgramError::InvalidAccountData);
+        let mut escrow_info = Escrow::unpack_unchecked(&escrow_account.try_borrow_data()?)?;
+        if escrow_info.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
         }
 
-        if escrow_info.initializer_token_to_receive_account_pubkey
-            != *initializers_token_to_receive_account.key
-        {
-            return Err(ProgramError::InvalidAccountData);
+        let (pda, bump_seed) = find_escrow_authority_address(escrow_account.key, program_id);
+        if *pda_account.key != pda {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        // The PDA is a system account without data, which only exists while it holds its rent
+        if lamports < rent.minimum_balance(0) {
+            return Err(EscrowError::NotRentExempt.into());
         }
 
-        let token_program = next_account_info(account_info_iter)?;
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        if config.allowlist_enabled {
+            let allowlist = Self::unpack_allowlist(next_account_info(account_info_iter)?, program_id)?;
+            if !allowlist.contains(&spl_token::native_mint::id()) || !allowlist.contains(&token_y_mint) {
+                return Err(EscrowError::MintNotAllowed.into());
+            }
+        }
 
-        let transfer_to_initializer_ix = spl_token::instruction::transfer(
-            token_program.key,
-            takers_sending_token_account.key,
-            initializers_token_to_receive_account.key,
-            taker.key,
-            &[&taker.key],
-            escrow_info.expected_amount,
-        )?;
-        msg!("Calling the token program to transfer tokens to the escrow's initializer...");
+        let now = Clock::get()?.unix_timestamp;
+        let expiry_unix_timestamp = Self::expiry_with_default(&config, expiry_unix_timestamp, now)?;
+
+        escrow_info.discriminator = ESCROW_DISCRIMINATOR;
+        escrow_info.version = ESCROW_VERSION;
+        escrow_info.is_initialized = true.into();
+        escrow_info.initializer_pubkey = *initializer.key;
+        escrow_info.temp_token_account_pubkey = pda;
+        escrow_info.initializer_token_to_receive_account_pubkey = *token_to_receive_account.key;
+        escrow_info.expected_amount = amount;
+        escrow_info.expiry_unix_timestamp = expiry_unix_timestamp;
+        escrow_info.allowed_taker_pubkey = allowed_taker;
+        escrow_info.created_unix_timestamp = now;
+        escrow_info.token_x_mint = system_program::id();
+        escrow_info.token_y_mint = token_y_mint;
+        escrow_info.bump_seed = bump_seed;
+        Self::register_escrow(accounts, escrow_account.key, &escrow_info, program_id)?;
+        Self::update_stats(accounts, program_id, |stats| stats.record_created(lamports))?;
+        Escrow::pack(escrow_info, &mut escrow_account.try_borrow_mut_data()?)?;
+
+        log!("Calling the system program to transfer the lamports to the PDA...");
         invoke(
-            &transfer_to_initializer_ix,
-            &[
-                takers_sending_token_account.clone(),
-                initializers_token_to_receive_account.clone(),
-                taker.clone(),
-                token_program.clone(),
-            ],
+            &system_instruction::transfer(initializer.key, &pda, lamports),
+            &[initializer.clone(), pda_account.clone(), system_program_account.clone()],
         )?;
 
-        // SOMETHING NEW
-        // 
-        let pda_account = next_account_info(account_info_iter)?;
+        EscrowEvent::EscrowInitialized {
+            escrow: *escrow_account.key,
+            initializer: *initializer.key,
+            token_x_mint: system_program::id(),
+            token_y_mint,
+            amount_x: lamports,
+            expected_amount: amount,
+            expiry_unix_timestamp,
+        }
+        .emit()?;
 
-        let transfer_to_taker_ix = spl_token::instruction::transfer(
-            token_program.key,
-            pdas_temp_token_account.key,
-            takers_token_to_receive_account.key,
-            &pda,
-            &[&pda],
-            pdas_temp_token_account_info.amount,
-        )?;
-        // INVOKE SIGNED - allows the PDA to sign
-        //
-        // By providing the seeds and program_id of the calling program, the runtime can recreate the PDA and match it against the accounts provided inside INVOKE_SIGNED's arguments. If there is a match, then the "signed" property of that account will be set to "true"
-        //
-        // Because only the Escrow program will have the programId that results in a matching PDA, this validation cannot be faked as long as the program is built properly. 
-        msg!("Calling the token program to transfer tokens to the taker...");
-        // the first invoke_signed call transfers the tokens from the temp X token account to RECEIVER's main X token account. 
-        invoke_signed(
-            &transfer_to_taker_ix,
-            &[
-                pdas_temp_token_account.clone(),
-                takers_token_to_receive_account.clone(),
-                pda_account.clone(),
-                token_program.clone(),
-            ],
-            &[&[&b"escrow"[..], &[nonce]]],
-        )?;
+        Ok(())
+    }
 
-        let close_pdas_temp_acc_ix = spl_token::instruction::close_account(
-            token_program.key,
-            pdas_temp_token_account.key,
-            initializers_main_account.key,
-            &pda,
-            &[&pda],
-        )?;
-        msg!("Calling the token program to close pda's temp account...");
-        // the first invoke_signed call closes the account - aka drain the balance, allowing it to be purged from memory by the runtime after the transaction
-        invoke_signed(
+    fn process_init_nft_escrow(
+        accounts: &[AccountInfo],
+        amount: u64,
+        expiry_unix_timestamp: i64,
+        allowed_taker: Pubkey,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let temp_token_account = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let nft_mint_account = accounts.get(7).ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+        // The initializer can't move a programmable NFT into a temp token account of their own, so Token Metadata moves it from their token account into the associated token account of the escrow's PDA instead, which it creates. That account is then the temp token account.
+        if temp_token_account.data_is_empty() {
+            let initializer = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let escrow_account = accounts.get(3).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let (pda, _) = find_escrow_authority_address(escrow_account.key, program_id);
+            if *temp_token_account.key
+                != token::get_associated_token_address(&pda, nft_mint_account.key, &spl_token::id())
+            {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            let initializers_token_account = accounts
+                .iter()
+                .find(|account| {
+                    token::unpack_token_account(account).is_ok_and(|info| {
+                        info.owner == *initializer.key && info.mint == *nft_mint_account.key && info.amount == 1
+                    })
+                })
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            Self::transfer_programmable(
+                accounts,
+                initializers_token_account.key,
+                initializer.key,
+                temp_token_account.key,
+                &pda,
+                nft_mint_account.key,
+                initializer.key,
+                1,
+                &[],
+            )?;
+        }
+
+        let temp_token_account_info = token::unpack_token_account(temp_token_account)?;
+        if temp_token_account_info.mint != *nft_mint_account.key {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+
+        let nft_mint_info = token::unpack_mint(nft_mint_account)?;
+        if nft_mint_info.decimals != 0 || nft_mint_info.supply != 1 || temp_token_account_info.amount != 1 {
+            return Err(EscrowError::NotNonFungible.into());
+        }
+
+        Self::process_init_escrow(accounts, amount, expiry_unix_timestamp, allowed_taker, 0, 0, &Pubkey::default(), program_id)
+    }
+
+    // An NFT swap is an NFT escrow priced at exactly one of the wanted NFT, which only its holder can pay
+    fn process_init_nft_swap(
+        accounts: &[AccountInfo],
+        expiry_unix_timestamp: i64,
+        allowed_taker: Pubkey,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let token_to_receive_account = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let wanted_nft_mint_account = accounts.get(8).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if token::unpack_token_account(token_to_receive_account)?.mint != *wanted_nft_mint_account.key {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let wanted_nft_mint_info = token::unpack_mint(wanted_nft_mint_account)?;
+        if wanted_nft_mint_info.decimals != 0 || wanted_nft_mint_info.supply != 1 {
+            return Err(EscrowError::NotNonFungible.into());
+        }
+
+        Self::process_init_nft_escrow(accounts, 1, expiry_unix_timestamp, allowed_taker, program_id)
+    }
+
+    // A Dutch auction is a regular escrow whose expected_amount is the floor price, with the auction fields added on top once process_init_escrow has written it
+    fn process_init_dutch_auction(
+        accounts: &[AccountInfo],
+        start_amount: u64,
+        floor_amount: u64,
+        auction_end_unix_timestamp: i64,
+        expiry_unix_timestamp: i64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let now = Clock::get()?.unix_timestamp;
+        if start_amount <= floor_amount || auction_end_unix_timestamp <= now {
+            return Err(EscrowError::InvalidAuctionParameters.into());
+        }
+
+        Self::process_init_escrow(accounts, floor_amount, expiry_unix_timestamp, Pubkey::default(), 0, 0, &Pubkey::default(), program_id)?;
+
+        let escrow_account = &accounts[3];
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        escrow_info.auction_start_amount = start_amount;
+        escrow_info.auction_start_unix_timestamp = now;
+        escrow_info.auction_end_unix_timestamp = auction_end_unix_timestamp;
+
+        Ok(())
+    }
+
+    // Like the auctions, an oracle escrow is written by process_init_escrow first. Its expected_amount stays 0, the price comes from the feed at exchange time.
+    fn process_init_oracle_escrow(
+        accounts: &[AccountInfo],
+        premium_bps: i16,
+        expiry_unix_timestamp: i64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        if premium_bps <= -10_000 {
+            return Err(EscrowError::InvalidPremium.into());
+        }
+
+        // Checking the feed up front means a typo in the feed's address fails now rather than on every exchange
+        let price_feed = accounts.get(7).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        oracle::unpack_price(price_feed)?;
+
+        Self::process_init_escrow(accounts, 0, expiry_unix_timestamp, Pubkey::default(), 0, 0, &Pubkey::default(), program_id)?;
+
+        let escrow_account = &accounts[3];
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        escrow_info.price_feed = *price_feed.key;
+        escrow_info.premium_bps = premium_bps;
+
+        Ok(())
+    }
+
+    // Rate-based escrows are written by process_init_escrow like the oracle escrows, their expected_amount stays 0 as well. The price of the whole escrowed amount is checked once here, every part of it is cheaper.
+    fn process_init_rate_escrow(
+        accounts: &[AccountInfo],
+        price_numerator: u64,
+        price_denominator: u64,
+        expiry_unix_timestamp: i64,
+        allowed_taker: Pubkey,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        if price_numerator == 0 || price_denominator == 0 {
+            return Err(EscrowError::InvalidRate.into());
+        }
+
+        Self::process_init_escrow(accounts, 0, expiry_unix_timestamp, allowed_taker, 0, 0, &Pubkey::default(), program_id)?;
+
+        let temp_token_account_info = token::unpack_token_account(&accounts[1])?;
+        let escrow_account = &accounts[3];
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        if escrow_info.holds_native_sol() {
+            return Err(EscrowError::NativeSolNotPartiallyFillable.into());
+        }
+        escrow_info.price_numerator = price_numerator;
+        escrow_info.price_denominator = price_denominator;
+        escrow_info
+            .price_of(escrow_info.escrowed_amount(&temp_token_account_info), 0)
+            .ok_or(EscrowError::PriceOverflow)?;
+
+        Ok(())
+    }
+
+    // The rate of a buy escrow is the amount wanted for everything escrowed, so every taker gets the payment's share of what they sell
+    fn process_init_buy_escrow(
+        accounts: &[AccountInfo],
+        amount: u64,
+        expiry_unix_timestamp: i64,
+        allowed_taker: Pubkey,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let temp_token_account_info =
+            token::unpack_token_account(accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?)?;
+        if temp_token_account_info.mint == spl_token::native_mint::id() {
+            if amount == 0 {
+                return Err(EscrowError::InvalidRate.into());
+            }
+            return Self::process_init_escrow(accounts, amount, expiry_unix_timestamp, allowed_taker, 0, 0, &Pubkey::default(), program_id);
+        }
+        Self::process_init_rate_escrow(accounts, amount, temp_token_account_info.amount, expiry_unix_timestamp, allowed_taker, program_id)
+    }
+
+    // A vesting escrow is a regular escrow with a release schedule for the taker's side, which only kicks in once it is taken
+    fn process_init_vesting_escrow(
+        accounts: &[AccountInfo],
+        amount: u64,
+        expiry_unix_timestamp: i64,
+        vesting_period_seconds: i64,
+        vesting_periods: u16,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        if vesting_period_seconds <= 0 || vesting_periods == 0 {
+            return Err(EscrowError::InvalidVestingSchedule.into());
+        }
+
+        Self::process_init_escrow(accounts, amount, expiry_unix_timestamp, Pubkey::default(), 0, 0, &Pubkey::default(), program_id)?;
+
+        let escrow_account = &accounts[3];
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        escrow_info.vesting_period_seconds = vesting_period_seconds;
+        escrow_info.vesting_periods = vesting_periods;
+
+        Ok(())
+    }
+
+    // A stream is an escrow nobody pays for, with the recipient as its allowed taker. It must not expire, or anyone could crank the tokens back to the initializer in the middle of it.
+    fn process_init_stream_escrow(
+        accounts: &[AccountInfo],
+        recipient: Pubkey,
+        stream_end_unix_timestamp: i64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let now = Clock::get()?.unix_timestamp;
+        if recipient == Pubkey::default() || stream_end_unix_timestamp <= now {
+            return Err(EscrowError::InvalidStreamParameters.into());
+        }
+
+        Self::process_init_escrow(accounts, 0, 0, recipient, 0, 0, &Pubkey::default(), program_id)?;
+
+        let escrow_account = &accounts[3];
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        escrow_info.expiry_unix_timestamp = 0;
+        escrow_info.stream_start_unix_timestamp = now;
+        escrow_info.stream_end_unix_timestamp = stream_end_unix_timestamp;
+
+        Ok(())
+    }
+
+    // Same approach as process_init_dutch_auction, expected_amount doubles as the minimum bid
+    fn process_init_english_auction(
+        accounts: &[AccountInfo],
+        min_bid: u64,
+        auction_end_unix_timestamp: i64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let now = Clock::get()?.unix_timestamp;
+        if min_bid == 0 || auction_end_unix_timestamp <= now {
+            return Err(EscrowError::InvalidAuctionParameters.into());
+        }
+
+        Self::process_init_escrow(accounts, min_bid, 0, Pubkey::default(), 0, 0, &Pubkey::default(), program_id)?;
+
+        let escrow_account = &accounts[3];
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        escrow_info.is_english_auction = true.into();
+        escrow_info.auction_start_unix_timestamp = now;
+        escrow_info.auction_end_unix_timestamp = auction_end_unix_timestamp;
+
+        Ok(())
+    }
+
+    // Every bidder gets their own bid account, a token account at a PDA of this program whose owner is the escrow's PDA. That way outbid bidders can take their tokens back on their own schedule, without the auction having to refund anyone.
+    fn process_place_bid(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let bidder = next_account_info(account_info_iter)?;
+
+        if !bidder.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let bidders_token_account = next_account_info(account_info_iter)?;
+        let bid_account = next_account_info(account_info_iter)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+
+        let mut escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+        let pda = Pubkey::create_program_address(
+            &[b"escrow", escrow_account.key.as_ref(), &[escrow_info.bump_seed]],
+            program_id,
+        )?;
+
+        if !bool::from(escrow_info.is_english_auction) {
+            return Err(EscrowError::NotAnEnglishAuction.into());
+        }
+        if !escrow_info.can_be_taken_by(bidder.key) {
+            return Err(EscrowError::TakerNotAllowed.into());
+        }
+
+        let pda_account = next_account_info(account_info_iter)?;
+        if *pda_account.key != pda {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+        if *token_y_mint_account.key != escrow_info.token_y_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_y_mint_info = token::unpack_mint(token_y_mint_account)?;
+
+        let y_token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(y_token_program.key) || token_y_mint_account.owner != y_token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+
+        let system_program_account = next_account_info(account_info_iter)?;
+        let rent_account = next_account_info(account_info_iter)?;
+
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        // Bidding is how an auction is taken, so the gate sees bidders as takers
+        if config.gate_program != Pubkey::default() {
+            gate::check_taker(&config.gate_program, bidder, escrow_account, accounts)?;
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        if now >= escrow_info.auction_end_unix_timestamp {
+            return Err(EscrowError::AuctionEnded.into());
+        }
+        if escrow_info.is_expired(now) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+        if amount < escrow_info.expected_amount || amount <= escrow_info.highest_bid_amount {
+            return Err(EscrowError::BidTooLow.into());
+        }
+
+        let (bid_address, bid_bump_seed) = find_bid_address(escrow_account.key, bidder.key, program_id);
+        if *bid_account.key != bid_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let previous_bid = if bid_account.data_is_empty() {
+            Self::create_bid_account(
+                bidder,
+                bid_account,
+                escrow_account,
+                pda_account,
+                token_y_mint_account,
+                y_token_program,
+                system_program_account,
+                rent_account,
+                bid_bump_seed,
+            )?;
+            0
+        } else {
+            token::unpack_token_amount(bid_account)?
+        };
+
+        let transfer_bid_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                bidders_token_account.key,
+                token_y_mint_account.key,
+                bid_account.key,
+                bidder.key,
+                &[bidder.key],
+                amount.checked_sub(previous_bid).ok_or(EscrowError::BidTooLow)?,
+                token_y_mint_info.decimals,
+            ),
+            y_token_program.key,
+        )?;
+        log!("Calling the token program to transfer the bid...");
+        invoke(
+            &transfer_bid_ix,
+            &[
+                bidders_token_account.clone(),
+                token_y_mint_account.clone(),
+                bid_account.clone(),
+                bidder.clone(),
+                y_token_program.clone(),
+            ],
+        )?;
+
+        escrow_info.highest_bidder = *bidder.key;
+        escrow_info.highest_bid_amount = amount;
+        Escrow::pack(escrow_info, &mut escrow_account.try_borrow_mut_data()?)?;
+
+        EscrowEvent::BidPlaced {
+            escrow: *escrow_account.key,
+            bidder: *bidder.key,
+            amount,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    // A bid account is a token account of token Y owned by the escrow's PDA, at an address derived from the escrow and the bidder so the program can sign for its creation
+    #[allow(clippy::too_many_arguments)]
+    fn create_bid_account<'a>(
+        bidder: &AccountInfo<'a>,
+        bid_account: &AccountInfo<'a>,
+        escrow_account: &AccountInfo<'a>,
+        pda_account: &AccountInfo<'a>,
+        token_y_mint_account: &AccountInfo<'a>,
+        y_token_program: &AccountInfo<'a>,
+        system_program_account: &AccountInfo<'a>,
+        rent_account: &AccountInfo<'a>,
+        bid_bump_seed: u8,
+    ) -> ProgramResult {
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let rent = Rent::get()?;
+
+        log!("Calling the system program to create the bid account...");
+        invoke_signed(
+            &system_instruction::create_account(
+                bidder.key,
+                bid_account.key,
+                rent.minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                y_token_program.key,
+            ),
+            &[bidder.clone(), bid_account.clone(), system_program_account.clone()],
+            &[&[&b"bid"[..], escrow_account.key.as_ref(), bidder.key.as_ref(), &[bid_bump_seed]]],
+        )?;
+
+        let init_bid_account_ix = token::for_token_program(
+            spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                bid_account.key,
+                token_y_mint_account.key,
+                pda_account.key,
+            ),
+            y_token_program.key,
+        )?;
+        log!("Calling the token program to initialize the bid account...");
+        invoke(
+            &init_bid_account_ix,
+            &[
+                bid_account.clone(),
+                token_y_mint_account.clone(),
+                pda_account.clone(),
+                rent_account.clone(),
+                y_token_program.clone(),
+            ],
+        )
+    }
+
+    // Accepting works like a bid that can't be outbid: the payment goes into a bid account, and process_settle later pays it out exactly like a winning bid
+    fn process_accept(accounts: &[AccountInfo], max_payment: u64, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker = next_account_info(account_info_iter)?;
+
+        if !taker.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let takers_token_account = next_account_info(account_info_iter)?;
+        let bid_account = next_account_info(account_info_iter)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+
+        let mut escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+        let pda = Pubkey::create_program_address(
+            &[b"escrow", escrow_account.key.as_ref(), &[escrow_info.bump_seed]],
+            program_id,
+        )?;
+
+        if !escrow_info.is_two_phase() {
+            return Err(EscrowError::NotTwoPhase.into());
+        }
+        if escrow_info.is_accepted() {
+            return Err(EscrowError::AlreadyAccepted.into());
+        }
+        if !escrow_info.can_be_taken_by(taker.key) {
+            return Err(EscrowError::TakerNotAllowed.into());
+        }
+
+        let pda_account = next_account_info(account_info_iter)?;
+        if *pda_account.key != pda {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+        if *token_y_mint_account.key != escrow_info.token_y_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_y_mint_info = token::unpack_mint(token_y_mint_account)?;
+
+        let y_token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(y_token_program.key) || token_y_mint_account.owner != y_token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+
+        let system_program_account = next_account_info(account_info_iter)?;
+        let rent_account = next_account_info(account_info_iter)?;
+
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        if config.gate_program != Pubkey::default() {
+            gate::check_taker(&config.gate_program, taker, escrow_account, accounts)?;
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        if escrow_info.is_expired(now) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+        let price = escrow_info.current_price(now).ok_or(EscrowError::PriceOverflow)?;
+        if price > max_payment {
+            return Err(EscrowError::MaxPaymentExceeded.into());
+        }
+
+        let (bid_address, bid_bump_seed) = find_bid_address(escrow_account.key, taker.key, program_id);
+        if *bid_account.key != bid_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        Self::create_bid_account(
+            taker,
+            bid_account,
+            escrow_account,
+            pda_account,
+            token_y_mint_account,
+            y_token_program,
+            system_program_account,
+            rent_account,
+            bid_bump_seed,
+        )?;
+
+        let transfer_payment_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                takers_token_account.key,
+                token_y_mint_account.key,
+                bid_account.key,
+                taker.key,
+                &[taker.key],
+                price,
+                token_y_mint_info.decimals,
+            ),
+            y_token_program.key,
+        )?;
+        log!("Calling the token program to transfer the taker's payment...");
+        invoke(
+            &transfer_payment_ix,
+            &[
+                takers_token_account.clone(),
+                token_y_mint_account.clone(),
+                bid_account.clone(),
+                taker.clone(),
+                y_token_program.clone(),
+            ],
+        )?;
+
+        escrow_info.highest_bidder = *taker.key;
+        escrow_info.highest_bid_amount = price;
+        escrow_info.accepted_unix_timestamp = now;
+        Escrow::pack(escrow_info, &mut escrow_account.try_borrow_mut_data()?)?;
+
+        EscrowEvent::EscrowAccepted {
+            escrow: *escrow_account.key,
+            taker: *taker.key,
+            amount_y: price,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    // Either side backing out alone would break the deal the other one locked their tokens for, so both have to sign. Once the taker is no longer the highest bidder, WithdrawBid returns their payment like an outbid bid.
+    fn process_abort(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+        let taker = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer || !taker.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        if !escrow_info.is_two_phase() {
+            return Err(EscrowError::NotTwoPhase.into());
+        }
+        if !escrow_info.is_accepted() {
+            return Err(EscrowError::NotAccepted.into());
+        }
+        if escrow_info.initializer_pubkey != *initializer.key || escrow_info.highest_bidder != *taker.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        escrow_info.highest_bidder = Pubkey::default();
+        escrow_info.highest_bid_amount = 0;
+        escrow_info.accepted_unix_timestamp = 0;
+
+        EscrowEvent::AcceptanceAborted {
+            escrow: *escrow_account.key,
+            taker: *taker.key,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    // The settlement half of process_exchange, except that both sides' tokens are already in accounts the PDA controls, so the PDA signs for both transfers
+    //
+    // Settles both English auctions (SettleAuction) and accepted two-phase escrows (Settle). The accepting taker is stored as the highest bidder, so the only difference is when settling is allowed.
+    fn process_settle(accounts: &[AccountInfo], two_phase: bool, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let settler = next_account_info(account_info_iter)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+
+        let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+        let pda = Pubkey::create_program_address(
+            &[b"escrow", escrow_account.key.as_ref(), &[escrow_info.bump_seed]],
+            program_id,
+        )?;
+
+        let settled_by_initializer = settler.is_signer && *settler.key == escrow_info.initializer_pubkey;
+        if two_phase {
+            if !escrow_info.is_two_phase() {
+                return Err(EscrowError::NotTwoPhase.into());
+            }
+            if !escrow_info.is_accepted() {
+                return Err(EscrowError::NotAccepted.into());
+            }
+        } else {
+            if !bool::from(escrow_info.is_english_auction) {
+                return Err(EscrowError::NotAnEnglishAuction.into());
+            }
+            if escrow_info.highest_bid_amount == 0 {
+                return Err(EscrowError::NoBids.into());
+            }
+            // The initializer may accept the highest bid early, everyone else has to wait for the auction to end
+            if Clock::get()?.unix_timestamp < escrow_info.auction_end_unix_timestamp && !settled_by_initializer {
+                return Err(EscrowError::AuctionNotEnded.into());
+            }
+        }
+        if !escrow_info.is_release_approved() {
+            return Err(EscrowError::ReleaseNotApproved.into());
+        }
+
+        let pda_account = next_account_info(account_info_iter)?;
+        if *pda_account.key != pda {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let pdas_temp_token_account = next_account_info(account_info_iter)?;
+        if escrow_info.temp_token_account_pubkey != *pdas_temp_token_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let pdas_temp_token_account_info = token::unpack_token_account(pdas_temp_token_account)?;
+
+        // Nobody signed for the winner, so make sure the tokens really end up with them
+        let winners_token_to_receive_account = next_account_info(account_info_iter)?;
+        let winners_token_to_receive_account_info =
+            token::unpack_token_account(winners_token_to_receive_account)?;
+        if winners_token_to_receive_account_info.owner != escrow_info.highest_bidder
+            || winners_token_to_receive_account_info.mint != escrow_info.token_x_mint
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let bid_account = next_account_info(account_info_iter)?;
+        let (bid_address, _bump_seed) =
+            find_bid_address(escrow_account.key, &escrow_info.highest_bidder, program_id);
+        if *bid_account.key != bid_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let bid_account_info = token::unpack_token_account(bid_account)?;
+
+        let winner = next_account_info(account_info_iter)?;
+        if *winner.key != escrow_info.highest_bidder {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Within the dispute window the trade only goes through if both sides agree to it
+        if two_phase {
+            let dispute_window_end = escrow_info
+                .accepted_unix_timestamp
+                .saturating_add(escrow_info.dispute_window_seconds);
+            if Clock::get()?.unix_timestamp < dispute_window_end && !(settled_by_initializer && winner.is_signer) {
+                return Err(EscrowError::DisputeWindowOpen.into());
+            }
+        }
+
+        let initializers_token_to_receive_account = next_account_info(account_info_iter)?;
+        if escrow_info.initializer_token_to_receive_account_pubkey
+            != *initializers_token_to_receive_account.key
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let initializers_main_account = next_account_info(account_info_iter)?;
+        if escrow_info.initializer_pubkey != *initializers_main_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || pdas_temp_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let y_token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(y_token_program.key) || bid_account.owner != y_token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+        if *token_x_mint_account.key != escrow_info.token_x_mint
+            || *token_y_mint_account.key != escrow_info.token_y_mint
+        {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_x_mint_info = token::unpack_mint(token_x_mint_account)?;
+        let token_y_mint_info = token::unpack_mint(token_y_mint_account)?;
+
+        let escrow_seeds: &[&[u8]] = &[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]];
+
+        let transfer_to_initializer_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                bid_account.key,
+                token_y_mint_account.key,
+                initializers_token_to_receive_account.key,
+                &pda,
+                &[&pda],
+                bid_account_info.amount,
+                token_y_mint_info.decimals,
+            ),
+            y_token_program.key,
+        )?;
+        log!("Calling the token program to transfer the winning bid to the escrow's initializer...");
+        invoke_signed(
+            &transfer_to_initializer_ix,
+            &[
+                bid_account.clone(),
+                token_y_mint_account.clone(),
+                initializers_token_to_receive_account.clone(),
+                pda_account.clone(),
+                y_token_program.clone(),
+            ],
+            &[escrow_seeds],
+        )?;
+
+        let close_bid_account_ix = token::for_token_program(
+            spl_token::instruction::close_account(&spl_token::id(), bid_account.key, winner.key, &pda, &[&pda]),
+            y_token_program.key,
+        )?;
+        log!("Calling the token program to close the bid account...");
+        invoke_signed(
+            &close_bid_account_ix,
+            &[bid_account.clone(), winner.clone(), pda_account.clone(), y_token_program.clone()],
+            &[escrow_seeds],
+        )?;
+
+        let transfer_to_winner_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                pdas_temp_token_account.key,
+                token_x_mint_account.key,
+                winners_token_to_receive_account.key,
+                &pda,
+                &[&pda],
+                pdas_temp_token_account_info.amount,
+                token_x_mint_info.decimals,
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to transfer tokens to the highest bidder...");
+        invoke_signed(
+            &transfer_to_winner_ix,
+            &[
+                pdas_temp_token_account.clone(),
+                token_x_mint_account.clone(),
+                winners_token_to_receive_account.clone(),
+                pda_account.clone(),
+                token_program.clone(),
+            ],
+            &[escrow_seeds],
+        )?;
+
+        let close_pdas_temp_acc_ix = token::for_token_program(
+            spl_token::instruction::close_account(
+                &spl_token::id(),
+                pdas_temp_token_account.key,
+                initializers_main_account.key,
+                &pda,
+                &[&pda],
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to close pda's temp account...");
+        invoke_signed(
+            &close_pdas_temp_acc_ix,
+            &[
+                pdas_temp_token_account.clone(),
+                initializers_main_account.clone(),
+                pda_account.clone(),
+                token_program.clone(),
+            ],
+            &[escrow_seeds],
+        )?;
+
+        log!("Closing the escrow account...");
+        **initializers_main_account.try_borrow_mut_lamports()? = initializers_main_account
+            .lamports()
+            .checked_add(escrow_account.lamports())
+            .ok_or(EscrowError::LamportOverflow)?;
+        **escrow_account.try_borrow_mut_lamports()? = 0;
+        *escrow_account.try_borrow_mut_data()? = &mut [];
+
+        EscrowEvent::EscrowExchanged {
+            escrow: *escrow_account.key,
+            initializer: escrow_info.initializer_pubkey,
+            taker: escrow_info.highest_bidder,
+            amount_x: pdas_temp_token_account_info.amount,
+            amount_y: bid_account_info.amount,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    // Takers of long-tail tokens often don't have a token account for them yet, so Exchange can create their associated token account instead of making them send a setup transaction first. The X mint and token program are taken from where Exchange expects them and checked against the escrow later on. The extra programs are looked up by key since it depends on the fee and the escrow which other optional accounts come before them.
+    fn create_takers_token_account<'a>(
+        accounts: &[AccountInfo<'a>],
+        payer: &AccountInfo<'a>,
+        taker: &AccountInfo<'a>,
+        takers_token_to_receive_account: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        let token_program = accounts.get(7).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let token_x_mint_account = accounts.get(9).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if !token::is_token_program(token_program.key) {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        if *takers_token_to_receive_account.key
+            != token::get_associated_token_address(taker.key, token_x_mint_account.key, token_program.key)
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let find_account = |key: Pubkey| {
+            accounts
+                .iter()
+                .find(|account| *account.key == key)
+                .ok_or(ProgramError::NotEnoughAccountKeys)
+        };
+        let associated_token_program = find_account(token::associated_token::id())?;
+        let system_program_account = find_account(system_program::id())?;
+        let rent_account = find_account(sysvar::rent::id())?;
+
+        log!("Calling the associated token account program to create the taker's token account...");
+        invoke(
+            &token::create_associated_token_account(
+                payer.key,
+                taker.key,
+                token_x_mint_account.key,
+                token_program.key,
+            ),
+            &[
+                payer.clone(),
+                takers_token_to_receive_account.clone(),
+                taker.clone(),
+                token_x_mint_account.clone(),
+                system_program_account.clone(),
+                token_program.clone(),
+                rent_account.clone(),
+                associated_token_program.clone(),
+            ],
+        )
+    }
+
+    // The taker of a vesting escrow gets a vesting account instead of the tokens: a vault at a PDA of the escrow, owned by the vesting account's own PDA, which releases the tokens as the schedule allows. Exchange then transfers X into the vault as if it were the taker's token account. The vesting account and the extra programs are looked up by key, like in create_takers_token_account.
+    fn create_vesting<'a>(
+        accounts: &[AccountInfo<'a>],
+        payer: &AccountInfo<'a>,
+        escrow_info: &Escrow,
+        amount_x: u64,
+        now: i64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let taker = &accounts[0];
+        let vault = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let escrow_account = accounts.get(6).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let token_program = accounts.get(7).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let token_x_mint_account = accounts.get(9).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if !token::is_token_program(token_program.key) {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+
+        let (vault_address, vault_bump_seed) = find_vesting_vault_address(escrow_account.key, program_id);
+        if *vault.key != vault_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let (vesting_address, vesting_bump_seed) = find_vesting_address(escrow_account.key, program_id);
+
+        let find_account = |key: Pubkey| {
+            accounts
+                .iter()
+                .find(|account| *account.key == key)
+                .ok_or(ProgramError::NotEnoughAccountKeys)
+        };
+        let vesting_account = find_account(vesting_address)?;
+        let system_program_account = find_account(system_program::id())?;
+        let rent_account = find_account(sysvar::rent::id())?;
+        let rent = Rent::get()?;
+
+        log!("Calling the system program to create the vesting account...");
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                vesting_account.key,
+                rent.minimum_balance(Vesting::LEN),
+                Vesting::LEN as u64,
+                program_id,
+            ),
+            &[payer.clone(), vesting_account.clone(), system_program_account.clone()],
+            &[&[&b"vesting"[..], escrow_account.key.as_ref(), &[vesting_bump_seed]]],
+        )?;
+
+        log!("Calling the system program to create the vesting vault...");
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                vault.key,
+                rent.minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                token_program.key,
+            ),
+            &[payer.clone(), vault.clone(), system_program_account.clone()],
+            &[&[&b"vesting_vault"[..], escrow_account.key.as_ref(), &[vault_bump_seed]]],
+        )?;
+
+        let init_vault_ix = token::for_token_program(
+            spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                vault.key,
+                token_x_mint_account.key,
+                vesting_account.key,
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to initialize the vesting vault...");
+        invoke(
+            &init_vault_ix,
+            &[
+                vault.clone(),
+                token_x_mint_account.clone(),
+                vesting_account.clone(),
+                rent_account.clone(),
+                token_program.clone(),
+            ],
+        )?;
+
+        Vesting::pack(
+            Vesting {
+                is_initialized: true,
+                bump_seed: vesting_bump_seed,
+                escrow: *escrow_account.key,
+                beneficiary: *taker.key,
+                vault: *vault.key,
+                mint: escrow_info.token_x_mint,
+                total_amount: amount_x,
+                claimed_amount: 0,
+                start_unix_timestamp: now,
+                period_seconds: escrow_info.vesting_period_seconds,
+                periods: escrow_info.vesting_periods,
+            },
+            &mut vesting_account.try_borrow_mut_data()?,
+        )
+    }
+
+    // The beneficiary can claim whatever has vested so far, as often as they like. The last claim empties the vault, so both the vault and the vesting account are closed and their rent goes back to the beneficiary, who paid it.
+    fn process_claim_vested(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let vesting_account = next_account_info(account_info_iter)?;
+        if vesting_account.owner != program_id || vesting_account.data_len() != Vesting::LEN {
+            return Err(EscrowError::InvalidVestingAccount.into());
+        }
+        let mut vesting_info = Vesting::unpack(&vesting_account.try_borrow_data()?)?;
+
+        let vault = next_account_info(account_info_iter)?;
+        if *vault.key != vesting_info.vault {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Anyone may send the transaction, the tokens can only ever go to the beneficiary
+        let beneficiarys_token_account = next_account_info(account_info_iter)?;
+        let beneficiary = next_account_info(account_info_iter)?;
+        if *beneficiary.key != vesting_info.beneficiary {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let beneficiarys_token_account_info = token::unpack_token_account(beneficiarys_token_account)?;
+        if beneficiarys_token_account_info.owner != vesting_info.beneficiary {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if beneficiarys_token_account_info.mint != vesting_info.mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || vault.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+
+        let mint_account = next_account_info(account_info_iter)?;
+        if *mint_account.key != vesting_info.mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let mint_info = token::unpack_mint(mint_account)?;
+
+        let amount = vesting_info.claimable_amount(Clock::get()?.unix_timestamp);
+        if amount == 0 {
+            return Err(EscrowError::NothingVested.into());
+        }
+
+        let vesting_seeds: &[&[u8]] = &[
+            &b"vesting"[..],
+            vesting_info.escrow.as_ref(),
+            &[vesting_info.bump_seed],
+        ];
+        let transfer_to_beneficiary_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                vault.key,
+                mint_account.key,
+                beneficiarys_token_account.key,
+                vesting_account.key,
+                &[vesting_account.key],
+                amount,
+                mint_info.decimals,
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to transfer the vested tokens to the beneficiary...");
+        invoke_signed(
+            &transfer_to_beneficiary_ix,
+            &[
+                vault.clone(),
+                mint_account.clone(),
+                beneficiarys_token_account.clone(),
+                vesting_account.clone(),
+                token_program.clone(),
+            ],
+            &[vesting_seeds],
+        )?;
+
+        vesting_info.claimed_amount = vesting_info
+            .claimed_amount
+            .checked_add(amount)
+            .ok_or(EscrowError::AmountOverflow)?;
+        let remaining_amount = vesting_info
+            .total_amount
+            .checked_sub(vesting_info.claimed_amount)
+            .ok_or(EscrowError::AmountUnderflow)?;
+
+        EscrowEvent::VestedClaimed {
+            escrow: vesting_info.escrow,
+            beneficiary: vesting_info.beneficiary,
+            amount,
+            remaining_amount,
+        }
+        .emit()?;
+
+        if remaining_amount > 0 {
+            return Vesting::pack(vesting_info, &mut vesting_account.try_borrow_mut_data()?);
+        }
+
+        let close_vault_ix = token::for_token_program(
+            spl_token::instruction::close_account(
+                &spl_token::id(),
+                vault.key,
+                beneficiary.key,
+                vesting_account.key,
+                &[vesting_account.key],
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to close the vesting vault...");
+        invoke_signed(
+            &close_vault_ix,
+            &[
+                vault.clone(),
+                beneficiary.clone(),
+                vesting_account.clone(),
+                token_program.clone(),
+            ],
+            &[vesting_seeds],
+        )?;
+
+        log!("Closing the vesting account...");
+        **beneficiary.try_borrow_mut_lamports()? = beneficiary
+            .lamports()
+            .checked_add(vesting_account.lamports())
+            .ok_or(EscrowError::LamportOverflow)?;
+        **vesting_account.try_borrow_mut_lamports()? = 0;
+        *vesting_account.try_borrow_mut_data()? = &mut [];
+
+        Ok(())
+    }
+
+    // A bid can be withdrawn as long as it isn't the one the auction would settle to. Once the escrow is closed, by settlement or cancellation, every remaining bid is a losing one.
+    fn process_withdraw_bid(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let bidder = next_account_info(account_info_iter)?;
+
+        if !bidder.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let bid_account = next_account_info(account_info_iter)?;
+        let bidders_token_account = next_account_info(account_info_iter)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+
+        let (bid_address, _bump_seed) = find_bid_address(escrow_account.key, bidder.key, program_id);
+        if *bid_account.key != bid_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if escrow_account.owner == program_id && escrow_account.data_len() == Escrow::LEN {
+            let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+            if escrow_info.highest_bidder == *bidder.key {
+                return Err(EscrowError::WinningBid.into());
+            }
+        }
+
+        // The escrow may be gone, and its bump seed with it, so the PDA has to be searched for
+        let (pda, bump_seed) = find_escrow_authority_address(escrow_account.key, program_id);
+        let pda_account = next_account_info(account_info_iter)?;
+        if *pda_account.key != pda {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let bid_account_info = token::unpack_token_account(bid_account)?;
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+        if *token_y_mint_account.key != bid_account_info.mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_y_mint_info = token::unpack_mint(token_y_mint_account)?;
+
+        let y_token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(y_token_program.key) || bid_account.owner != y_token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+
+        let escrow_seeds: &[&[u8]] = &[&b"escrow"[..], escrow_account.key.as_ref(), &[bump_seed]];
+
+        let transfer_to_bidder_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                bid_account.key,
+                token_y_mint_account.key,
+                bidders_token_account.key,
+                &pda,
+                &[&pda],
+                bid_account_info.amount,
+                token_y_mint_info.decimals,
+            ),
+            y_token_program.key,
+        )?;
+        log!("Calling the token program to return the bid...");
+        invoke_signed(
+            &transfer_to_bidder_ix,
+            &[
+                bid_account.clone(),
+                token_y_mint_account.clone(),
+                bidders_token_account.clone(),
+                pda_account.clone(),
+                y_token_program.clone(),
+            ],
+            &[escrow_seeds],
+        )?;
+
+        let close_bid_account_ix = token::for_token_program(
+            spl_token::instruction::close_account(&spl_token::id(), bid_account.key, bidder.key, &pda, &[&pda]),
+            y_token_program.key,
+        )?;
+        log!("Calling the token program to close the bid account...");
+        invoke_signed(
+            &close_bid_account_ix,
+            &[bid_account.clone(), bidder.clone(), pda_account.clone(), y_token_program.clone()],
+            &[escrow_seeds],
+        )?;
+
+        Ok(())
+    }
+
+    // INITIALLY SIMILAR TO PROCESS INIT ESCROW
+    #[allow(clippy::too_many_arguments)]
+    fn process_exchange(
+        accounts: &[AccountInfo],
+        amount_expected_by_taker: u64,
+        max_payment: u64,
+        referrer_token_account: &Pubkey,
+        taker_proof: &[[u8; 32]],
+        relayer: &Pubkey,
+        relayer_tip: u64,
+        signed_price: Option<u64>,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker = Signer::new(next_account_info(account_info_iter)?)?;
+
+        // Takers of a relayed exchange may not hold any SOL, so the relayer pays the rent of the accounts created for them. It is looked up by key, like the referrer's token account.
+        let payer = if *relayer != Pubkey::default() {
+            let relayer_account = accounts
+                .iter()
+                .find(|account| account.key == relayer)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if !relayer_account.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            relayer_account
+        } else if relayer_tip > 0 {
+            return Err(EscrowError::InvalidInstruction.into());
+        } else {
+            taker.info()
+        };
+
+        let takers_sending_token_account = WritableTokenAccount::new(next_account_info(account_info_iter)?)?;
+        let takers_sending_token_account_info = takers_sending_token_account.state;
+
+        // May not exist yet, what it has to be depends on the kind of escrow, see below
+        let takers_token_to_receive_account = next_account_info(account_info_iter)?;
+
+        let pdas_temp_token_account = next_account_info(account_info_iter)?;
+
+        let initializers_main_account = next_account_info(account_info_iter)?;
+        let initializers_token_to_receive_account = next_account_info(account_info_iter)?;
+        let escrow_account = ProgramOwned::<Escrow>::new(next_account_info(account_info_iter)?, program_id)?;
+
+        let escrow_info = escrow_account.state;
+        // Escrows that are taken some other way are turned away first, whatever the taker pays with
+        if bool::from(escrow_info.is_english_auction) {
+            return Err(EscrowError::BidsOnly.into());
+        }
+        if escrow_info.is_two_phase() {
+            return Err(EscrowError::AcceptRequired.into());
+        }
+        if escrow_info.is_stream() {
+            return Err(EscrowError::StreamOnly.into());
+        }
+        if escrow_info.is_collection_offer() {
+            return Err(EscrowError::CollectionOfferOnly.into());
+        }
+        // The mint the taker sends picks the payment option they pay with, see SetPaymentOption, or the stablecoin a USD-priced escrow is paid in. From here on the escrow reads as if it were priced in that mint.
+        let pays_with_option = takers_sending_token_account_info.mint != escrow_info.token_y_mint;
+        let escrow_info = if escrow_info.is_usd_priced() {
+            // The initializer can't name a token account for every stablecoin up front, so any of theirs will do
+            let initializers_token_to_receive_account_info =
+                token::unpack_token_account(initializers_token_to_receive_account)?;
+            if initializers_token_to_receive_account_info.owner != escrow_info.initializer_pubkey {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            if initializers_token_to_receive_account_info.mint != takers_sending_token_account_info.mint {
+                return Err(EscrowError::TokenMintMismatch.into());
+            }
+            Escrow {
+                token_y_mint: takers_sending_token_account_info.mint,
+                initializer_token_to_receive_account_pubkey: *initializers_token_to_receive_account.key,
+                ..escrow_info
+            }
+        } else {
+            escrow_info
+                .paid_in(&takers_sending_token_account_info.mint)
+                .ok_or(EscrowError::TokenMintMismatch)?
+        };
+        // The initializer signed a price in token Y
+        if signed_price.is_some() && pays_with_option {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let pdas_temp_token_account = KnownAccount::new(
+            pdas_temp_token_account,
+            &escrow_info.temp_token_account_pubkey,
+            ProgramError::InvalidAccountData,
+        )?;
+        let initializers_main_account = KnownAccount::new(
+            initializers_main_account,
+            &escrow_info.initializer_pubkey,
+            ProgramError::InvalidAccountData,
+        )?;
+        let initializers_token_to_receive_account = KnownAccount::new(
+            initializers_token_to_receive_account,
+            &escrow_info.initializer_token_to_receive_account_pubkey,
+            ProgramError::InvalidAccountData,
+        )?;
+        let pda = Pubkey::create_program_address(
+            &[b"escrow", escrow_account.key.as_ref(), &[escrow_info.bump_seed]],
+            program_id,
+        )?;
+        let pdas_temp_token_account_info = Self::unpack_escrowed_tokens(&pdas_temp_token_account, &escrow_info, &pda)?;
+
+        if !escrow_info.is_release_approved() {
+            return Err(EscrowError::ReleaseNotApproved.into());
+        }
+
+        // Rate-based escrows can be taken in part, all others are taken whole. A partly taken escrow stays open for the rest.
+        let escrowed_amount = escrow_info.escrowed_amount(&pdas_temp_token_account_info);
+        let amount_is_valid = if escrow_info.is_rate_based() {
+            amount_expected_by_taker > 0 && amount_expected_by_taker <= escrowed_amount
+        } else {
+            amount_expected_by_taker == escrowed_amount
+        };
+        if !amount_is_valid {
+            return Err(EscrowError::ExpectedAmountMismatch.into());
+        }
+        let amount_x = amount_expected_by_taker;
+        let is_partial_fill = amount_x < escrowed_amount;
+        // A signed order could be settled again for the rest of a rate-based escrow
+        if signed_price.is_some() && is_partial_fill {
+            return Err(EscrowError::ExpectedAmountMismatch.into());
+        }
+        // Token Metadata keeps programmable NFTs frozen, which is how their escrows are told apart
+        let is_programmable = pdas_temp_token_account_info.is_frozen();
+
+        // Private escrows name their taker up front, so nobody can snipe the deal between the parties agreeing on it and the taker's transaction landing
+        if !escrow_info.can_be_taken_by(taker.key) {
+            return Err(EscrowError::TakerNotAllowed.into());
+        }
+        // A merkle allowlist does the same for more takers than fit in the escrow
+        if !escrow_info.is_taker_in_allowlist(taker.key, taker_proof) {
+            return Err(EscrowError::InvalidTakerProof.into());
+        }
+        // A reservation does the same for a while, for whoever paid its deposit
+        let slot = Clock::get()?.slot;
+        if escrow_info.is_reserved(slot) && escrow_info.reserved_by != *taker.key {
+            return Err(EscrowError::EscrowReserved.into());
+        }
+
+        // Once the deadline has passed the offer is off the table, the tokens can only be reclaimed
+        let now = Clock::get()?.unix_timestamp;
+        if escrow_info.is_expired(now) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+
+        // The taker pays in Y and gets X, the reverse of the initializer
+        if takers_sending_token_account_info.mint != escrow_info.token_y_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+
+        // The token program of the X tokens, i.e. the one that owns the temp token account. The system program for lamport escrows.
+        let token_program = next_account_info(account_info_iter)?;
+        if !Self::is_x_program(&escrow_info, token_program.key) || pdas_temp_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+
+        let pda_account = KnownAccount::new(next_account_info(account_info_iter)?, &pda, ProgramError::InvalidSeeds)?;
+
+        // transfer_checked needs the mints so Token-2022 can apply their extensions
+        let token_x_mint_account = KnownAccount::new(
+            next_account_info(account_info_iter)?,
+            &escrow_info.token_x_mint,
+            EscrowError::TokenMintMismatch,
+        )?;
+        let token_y_mint_account = KnownAccount::new(
+            next_account_info(account_info_iter)?,
+            &escrow_info.token_y_mint,
+            EscrowError::TokenMintMismatch,
+        )?;
+        let token_x_mint_info = Self::unpack_x_mint(&token_x_mint_account, &escrow_info)?;
+        let token_y_mint_info = token::unpack_mint(&token_y_mint_account)?;
+
+        // X and Y don't have to live in the same token program, so the Y token program is passed in separately
+        let y_token_program = TokenProgram::new(next_account_info(account_info_iter)?)?;
+        if takers_sending_token_account.owner != y_token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+
+        let config = ProgramOwned::<Config>::new(next_account_info(account_info_iter)?, program_id)?.state;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        // Deployments that vet their takers, e.g. for KYC, do it in the gate program, before any tokens move
+        if config.gate_program != Pubkey::default() {
+            gate::check_taker(&config.gate_program, &taker, &escrow_account, accounts)?;
+        }
+
+        // Oracle escrows are priced from their feed, which is looked up by key so it doesn't matter which of the other optional accounts are there. Everything else pays expected_amount, the current price of a Dutch auction, or the rate of a rate-based escrow.
+        let price = if let Some(signed_price) = signed_price {
+            signed_price
+        } else if escrow_info.price_feed != Pubkey::default() {
+            let price_feed = accounts
+                .iter()
+                .find(|account| *account.key == escrow_info.price_feed)
+                .ok_or(EscrowError::InvalidPriceFeed)?;
+            let oracle_price = oracle::unpack_price(price_feed)?;
+            if now.saturating_sub(oracle_price.publish_unix_timestamp) > MAX_PRICE_AGE_SECONDS {
+                return Err(EscrowError::StalePrice.into());
+            }
+            oracle::payment_amount(
+                &oracle_price,
+                escrow_info.premium_bps,
+                amount_x,
+                token_x_mint_info.decimals,
+                token_y_mint_info.decimals,
+            )
+            .ok_or(EscrowError::PriceOverflow)?
+        } else if escrow_info.is_usd_priced() {
+            // Only stablecoins that hold their peg are taken, at their feed's price. The list is looked up by its owner and size, the feed by key.
+            let stablecoins_account = accounts
+                .iter()
+                .find(|account| account.owner == program_id && account.data_len() == StablecoinList::LEN)
+                .ok_or(EscrowError::StablecoinNotListed)?;
+            let (price_feed, tolerance_bps) = Self::unpack_stablecoins(stablecoins_account, program_id)?
+                .get(&escrow_info.token_y_mint)
+                .ok_or(EscrowError::StablecoinNotListed)?;
+            let price_feed = accounts
+                .iter()
+                .find(|account| *account.key == price_feed)
+                .ok_or(EscrowError::InvalidPriceFeed)?;
+            let oracle_price = oracle::unpack_price(price_feed)?;
+            if now.saturating_sub(oracle_price.publish_unix_timestamp) > MAX_PRICE_AGE_SECONDS {
+                return Err(EscrowError::StalePrice.into());
+            }
+            if !oracle::is_pegged(&oracle_price, tolerance_bps) {
+                return Err(EscrowError::StablecoinDepegged.into());
+            }
+            oracle::usd_payment_amount(&oracle_price, escrow_info.usd_amount, token_y_mint_info.decimals)
+                .ok_or(EscrowError::PriceOverflow)?
+        } else {
+            escrow_info.price_of(amount_x, now).ok_or(EscrowError::PriceOverflow)?
+        };
+
+        // Only with the strict-checks feature, see strict
+        strict::check_writable(&[
+            &takers_sending_token_account,
+            takers_token_to_receive_account,
+            &pdas_temp_token_account,
+            &initializers_main_account,
+            &initializers_token_to_receive_account,
+            &escrow_account,
+        ])?;
+        strict::check_canonical_pda(&pda, &[b"escrow", escrow_account.key.as_ref()], program_id)?;
+        strict::check_data_len(&pda_account, 0)?;
+        strict::check_token_account_mint(&initializers_token_to_receive_account, &escrow_info.token_y_mint)?;
+        strict::check_owner(&token_y_mint_account, y_token_program.key)?;
+        if !escrow_info.holds_lamports() {
+            strict::check_owner(&token_x_mint_account, token_program.key)?;
+        }
+
+        // Marketplaces have to honor NFT royalties, so the creators' cut comes out of what the initializer receives
+        let royalties = Self::nft_royalties(accounts, &token_x_mint_account, &token_x_mint_info, price)?;
+        let royalty = royalties
+            .iter()
+            .try_fold(0u64, |royalty, (_, amount)| royalty.checked_add(*amount))
+            .ok_or(EscrowError::FeeOverflow)?;
+        let proceeds = price.checked_sub(royalty).ok_or(EscrowError::AmountUnderflow)?;
+
+        // Token-2022 mints can withhold a fee from every transfer, so the taker sends enough for the initializer to receive the whole price. What arrives is checked as well, in case the fee isn't what the mint said it would be.
+        let payment = match token::unpack_transfer_fee(&token_y_mint_account, Clock::get()?.epoch)? {
+            Some(transfer_fee) => transfer_fee.amount_before_fee(proceeds).ok_or(EscrowError::FeeOverflow)?,
+            None => proceeds,
+        };
+        // The protocol fee is paid by the taker on top of what the initializer asked for. Any token account owned by the fee authority will do as the vault, since only this program can move tokens out of it, or once the admin has named a fee collector, any token account of theirs.
+        let fee = protocol_fee(price, config.fee_bps)
+            .ok_or(EscrowError::FeeOverflow)?;
+        let total_payment = payment
+            .checked_add(royalty)
+            .and_then(|total_payment| total_payment.checked_add(fee))
+            .and_then(|total_payment| total_payment.checked_add(relayer_tip))
+            .ok_or(EscrowError::AmountOverflow)?;
+        if total_payment > max_payment {
+            return Err(EscrowError::MaxPaymentExceeded.into());
+        }
+
+        // Native SOL is unwrapped, and lamports are sent, straight into whichever account the taker names, so there is no token account to check. Vesting escrows pay into a vault that is created here.
+        if escrow_info.is_vesting() {
+            Self::create_vesting(accounts, payer, &escrow_info, amount_x, now, program_id)?;
+        } else if is_programmable {
+            // Token Metadata creates the taker's associated token account itself if need be
+            if *takers_token_to_receive_account.key
+                != token::get_associated_token_address(taker.key, &escrow_info.token_x_mint, &spl_token::id())
+            {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        } else if !escrow_info.holds_native_sol() && !escrow_info.holds_lamports() {
+            if takers_token_to_receive_account.data_is_empty() {
+                Self::create_takers_token_account(accounts, payer, &taker, takers_token_to_receive_account)?;
+            }
+            let takers_token_to_receive_account_info =
+                token::unpack_token_account(takers_token_to_receive_account)?;
+            if takers_token_to_receive_account_info.mint != escrow_info.token_x_mint {
+                return Err(EscrowError::TokenMintMismatch.into());
+            }
+            // Takers may have token X sent to someone else's account, unless the deployment only lets them receive it themselves
+            strict::check_token_account_owner(takers_token_to_receive_account, taker.key)?;
+        }
+
+        let initializers_balance_before = token::unpack_token_amount(&initializers_token_to_receive_account)?;
+
+        let mut transfer_to_initializer_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                takers_sending_token_account.key,
+                token_y_mint_account.key,
+                initializers_token_to_receive_account.key,
+                taker.key,
+                &[taker.key],
+                payment,
+                token_y_mint_info.decimals,
+            ),
+            y_token_program.key,
+        )?;
+        let mut account_infos = vec![
+            takers_sending_token_account.clone(),
+            token_y_mint_account.clone(),
+            initializers_token_to_receive_account.clone(),
+            taker.clone(),
+            y_token_program.clone(),
+        ];
+        transfer_hook::add_hook_accounts(
+            &mut transfer_to_initializer_ix,
+            &mut account_infos,
+            &token_y_mint_account,
+            payment,
+            accounts,
+        )?;
+        log!("Calling the token program to transfer tokens to the escrow's initializer...");
+        invoke(&transfer_to_initializer_ix, &account_infos)?;
+        let initializers_balance_after = token::unpack_token_amount(&initializers_token_to_receive_account)?;
+        if initializers_balance_after.saturating_sub(initializers_balance_before) < proceeds {
+            return Err(EscrowError::NetAmountTooLow.into());
+        }
+
+        // Creators bear the transfer fee on their royalty, like the fee vault does on the protocol fee
+        for (creator, amount) in royalties {
+            let creators_token_account = accounts
+                .iter()
+                .find(|account| {
+                    token::unpack_token_account(account)
+                        .is_ok_and(|info| info.owner == creator && info.mint == escrow_info.token_y_mint)
+                })
+                .ok_or(EscrowError::CreatorAccountMissing)?;
+            log!("Calling the token program to transfer the royalty to a creator...");
+            Self::transfer_fee(
+                &taker,
+                &takers_sending_token_account,
+                &token_y_mint_account,
+                creators_token_account,
+                &y_token_program,
+                amount,
+                token_y_mint_info.decimals,
+                accounts,
+            )?;
+
+            EscrowEvent::RoyaltyPaid {
+                escrow: *escrow_account.key,
+                creator,
+                mint: escrow_info.token_y_mint,
+                amount,
+            }
+            .emit()?;
+        }
+
+        // The tip goes to the relayer's associated token account, so it doesn't have to be named in the instruction data as well
+        if relayer_tip > 0 {
+            let relayers_token_account_address =
+                token::get_associated_token_address(relayer, &escrow_info.token_y_mint, y_token_program.key);
+            let relayers_token_account = accounts
+                .iter()
+                .find(|account| *account.key == relayers_token_account_address)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            log!("Calling the token program to transfer the tip to the relayer...");
+            Self::transfer_fee(
+                &taker,
+                &takers_sending_token_account,
+                &token_y_mint_account,
+                relayers_token_account,
+                &y_token_program,
+                relayer_tip,
+                token_y_mint_info.decimals,
+                accounts,
+            )?;
+
+            EscrowEvent::RelayerTipPaid {
+                escrow: *escrow_account.key,
+                relayer: *relayer,
+                mint: escrow_info.token_y_mint,
+                amount: relayer_tip,
+            }
+            .emit()?;
+        }
+        if fee > 0 {
+            let fee_vault = next_account_info(account_info_iter)?;
+            let fee_vault_info = token::unpack_token_account(fee_vault)?;
+            let fee_collector = if config.fee_collector != Pubkey::default() {
+                config.fee_collector
+            } else {
+                find_fee_authority_address(program_id).0
+            };
+            if fee_vault_info.owner != fee_collector || fee_vault_info.mint != escrow_info.token_y_mint {
+                return Err(EscrowError::InvalidFeeVault.into());
+            }
+
+            // The referrer's cut comes out of the fee, the taker doesn't pay more for having been referred. Like the extra programs of create_takers_token_account, the referrer's token account is looked up by key.
+            let referral_fee = if *referrer_token_account != Pubkey::default() {
+                let referrer_account = accounts
+                    .iter()
+                    .find(|account| account.key == referrer_token_account)
+                    .ok_or(ProgramError::NotEnoughAccountKeys)?;
+                let referrer_account_info = token::unpack_token_account(referrer_account)?;
+                if referrer_account_info.mint != escrow_info.token_y_mint {
+                    return Err(EscrowError::TokenMintMismatch.into());
+                }
+
+                let referral_fee = protocol_fee(fee, config.referral_share_bps)
+                    .ok_or(EscrowError::FeeOverflow)?;
+                if referral_fee > 0 {
+                    log!("Calling the token program to transfer the referral fee to the referrer...");
+                    Self::transfer_fee(
+                        &taker,
+                        &takers_sending_token_account,
+                        &token_y_mint_account,
+                        referrer_account,
+                        &y_token_program,
+                        referral_fee,
+                        token_y_mint_info.decimals,
+                        accounts,
+                    )?;
+
+                    EscrowEvent::ReferralFeePaid {
+                        escrow: *escrow_account.key,
+                        referrer: referrer_account_info.owner,
+                        mint: escrow_info.token_y_mint,
+                        amount: referral_fee,
+                    }
+                    .emit()?;
+                }
+                referral_fee
+            } else {
+                0
+            };
+
+            let protocol_share = fee.checked_sub(referral_fee).ok_or(EscrowError::AmountUnderflow)?;
+            if protocol_share > 0 {
+                log!("Calling the token program to transfer the protocol fee to the fee vault...");
+                Self::transfer_fee(
+                    &taker,
+                    &takers_sending_token_account,
+                    &token_y_mint_account,
+                    fee_vault,
+                    &y_token_program,
+                    protocol_share,
+                    token_y_mint_info.decimals,
+                    accounts,
+                )?;
+
+                EscrowEvent::ProtocolFeeCollected {
+                    escrow: *escrow_account.key,
+                    mint: escrow_info.token_y_mint,
+                    amount: protocol_share,
+                }
+                .emit()?;
+            }
+        }
+
+        if escrow_info.holds_lamports() {
+            log!("Calling the system program to transfer the lamports to the taker...");
+            Self::transfer_escrowed_lamports(
+                &pdas_temp_token_account,
+                takers_token_to_receive_account,
+                token_program,
+                &escrow_account,
+                &escrow_info,
+            )?;
+        } else if escrow_info.holds_native_sol() && !escrow_info.is_vesting() {
+            // Closing a wrapped SOL account unwraps it. The temp account is closed into the escrow account, which this program owns and can therefore split between the taker (the SOL) and the initializer (the rent) when it is closed below.
+            let close_pdas_temp_acc_ix = token::for_token_program(
+                spl_token::instruction::close_account(
+                    &spl_token::id(),
+                    pdas_temp_token_account.key,
+                    escrow_account.key,
+                    &pda,
+                    &[&pda],
+                ),
+                token_program.key,
+            )?;
+            log!("Calling the token program to unwrap the escrowed SOL...");
+            invoke_signed(
+                &close_pdas_temp_acc_ix,
+                &[
+                    pdas_temp_token_account.clone(),
+                    escrow_account.clone(),
+                    pda_account.clone(),
+                    token_program.clone(),
+                ],
+                &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]],
+            )?;
+
+            **takers_token_to_receive_account.try_borrow_mut_lamports()? = takers_token_to_receive_account
+                .lamports()
+                .checked_add(amount_x)
+                .ok_or(EscrowError::LamportOverflow)?;
+            **escrow_account.try_borrow_mut_lamports()? = escrow_account
+                .lamports()
+                .checked_sub(amount_x)
+                .ok_or(EscrowError::AmountUnderflow)?;
+        } else {
+            if is_programmable {
+                Self::transfer_programmable(
+                    accounts,
+                    pdas_temp_token_account.key,
+                    &pda,
+                    takers_token_to_receive_account.key,
+                    taker.key,
+                    token_x_mint_account.key,
+                    payer.key,
+                    amount_x,
+                    &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]],
+                )?;
+            } else {
+                // SOMETHING NEW
+                // 
+                let mut transfer_to_taker_ix = token::for_token_program(
+                    spl_token::instruction::transfer_checked(
+                        &spl_token::id(),
+                        pdas_temp_token_account.key,
+                        token_x_mint_account.key,
+                        takers_token_to_receive_account.key,
+                        &pda,
+                        &[&pda],
+                        amount_x,
+                        token_x_mint_info.decimals,
+                    ),
+                    token_program.key,
+                )?;
+                let mut account_infos = vec![
+                    pdas_temp_token_account.clone(),
+                    token_x_mint_account.clone(),
+                    takers_token_to_receive_account.clone(),
+                    pda_account.clone(),
+                    token_program.clone(),
+                ];
+                transfer_hook::add_hook_accounts(
+                    &mut transfer_to_taker_ix,
+                    &mut account_infos,
+                    &token_x_mint_account,
+                    amount_x,
+                    accounts,
+                )?;
+                // INVOKE SIGNED - allows the PDA to sign
+                //
+                // By providing the seeds and program_id of the calling program, the runtime can recreate the PDA and match it against the accounts provided inside INVOKE_SIGNED's arguments. If there is a match, then the "signed" property of that account will be set to "true"
+                //
+                // Because only the Escrow program will have the programId that results in a matching PDA, this validation cannot be faked as long as the program is built properly. 
+                log!("Calling the token program to transfer tokens to the taker...");
+                // the first invoke_signed call transfers the tokens from the temp X token account to RECEIVER's main X token account. 
+                invoke_signed(
+                    &transfer_to_taker_ix,
+                    &account_infos,
+                    &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]],
+                )?;
+            }
+
+            // The initializer's own token account stays open if the tokens were only delegated, and the temp token account while there is something left to take. Token Metadata may have closed a programmable NFT's already.
+            if !escrow_info.is_delegated()
+                && !is_partial_fill
+                && Self::unpack_temp_token_account(&pdas_temp_token_account)?.is_some()
+            {
+                let close_pdas_temp_acc_ix = token::for_token_program(
+                    spl_token::instruction::close_account(
+                        &spl_token::id(),
+                        pdas_temp_token_account.key,
+                        initializers_main_account.key,
+                        &pda,
+                        &[&pda],
+                    ),
+                    token_program.key,
+                )?;
+                log!("Calling the token program to close pda's temp account...");
+                // the first invoke_signed call closes the account - aka drain the balance, allowing it to be purged from memory by the runtime after the transaction
+                invoke_signed(
+                    &close_pdas_temp_acc_ix,
+                    &[
+                        pdas_temp_token_account.clone(),
+                        initializers_main_account.clone(),
+                        pda_account.clone(),
+                        token_program.clone(),
+                    ],
+                    &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]],
+                )?;
+            }
+        }
+
+        Self::release_reservation(&escrow_account, &taker, &initializers_main_account, slot)?;
+        if !is_partial_fill {
+            Self::deregister_escrow(accounts, escrow_account.key, &escrow_info, program_id)?;
+            log!("Closing the escrow account...");
+            // Must clear the data for security purposes, even if the intention is to have the account purged after the transaction.
+            //
+            // Specifically:
+            // In any call to a program that is of the "close" kind, i.e. where you set an account's lamports to zero so it's removed from memory after the transaction, make sure to either clear the data field or leave the data in a state that would be OK to be recovered by a subsequent transaction.
+            **initializers_main_account.try_borrow_mut_lamports()? = initializers_main_account
+                .lamports()
+                .checked_add(escrow_account.lamports())
+                .ok_or(EscrowError::LamportOverflow)?;
+            **escrow_account.try_borrow_mut_lamports()? = 0;
+            *escrow_account.try_borrow_mut_data()? = &mut [];
+        }
+        Self::update_stats(accounts, program_id, |stats| stats.record_exchange(amount_x, price, !is_partial_fill))?;
+        if escrow_info.is_rate_based() {
+            Self::record_fill(accounts, &escrow_account, payer, &taker, amount_x, price, slot, program_id)?;
+        }
+
+        EscrowEvent::EscrowExchanged {
+            escrow: *escrow_account.key,
+            initializer: escrow_info.initializer_pubkey,
+            taker: *taker.key,
+            amount_x,
+            amount_y: price,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    // The initializer's signature stands in for the escrow's price, the rest is an ordinary exchange
+    #[allow(clippy::too_many_arguments)]
+    fn process_settle_signed_order(
+        accounts: &[AccountInfo],
+        amount: u64,
+        max_payment: u64,
+        price: u64,
+        order_taker: &Pubkey,
+        order_expiry_unix_timestamp: i64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let taker = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if *order_taker != Pubkey::default() && order_taker != taker.key {
+            return Err(EscrowError::TakerNotAllowed.into());
+        }
+        if Clock::get()?.unix_timestamp > order_expiry_unix_timestamp {
+            return Err(EscrowError::SignedOrderExpired.into());
+        }
+
+        let escrow_account = accounts.get(6).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+        let initializer = Escrow::unpack(&escrow_account.try_borrow_data()?)?.initializer_pubkey;
+        let instructions_sysvar = accounts
+            .iter()
+            .find(|account| *account.key == sysvar::instructions::id())
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        signed_order::check_signature(
+            instructions_sysvar,
+            &initializer,
+            &signed_order::message(escrow_account.key, order_taker, amount, price, order_expiry_unix_timestamp),
+        )?;
+
+        Self::process_exchange(
+            accounts,
+            amount,
+            max_payment,
+            &Pubkey::default(),
+            &[],
+            &Pubkey::default(),
+            0,
+            Some(price),
+            program_id,
+        )
+    }
+
+    // A collection offer is an exchange the other way around: the initializer escrowed the payment, and the seller of an NFT of the collection takes it. The NFT goes straight from the seller to the initializer.
+    fn process_fill_collection_offer(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let seller = next_account_info(account_info_iter)?;
+
+        if !seller.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let sellers_nft_account = next_account_info(account_info_iter)?;
+        let sellers_token_to_receive_account = next_account_info(account_info_iter)?;
+        let pdas_temp_token_account = next_account_info(account_info_iter)?;
+        let pdas_temp_token_account_info = token::unpack_token_account(pdas_temp_token_account)?;
+        let initializers_main_account = next_account_info(account_info_iter)?;
+        let initializers_nft_account = next_account_info(account_info_iter)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+
+        let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+        let pda = Pubkey::create_program_address(
+            &[b"escrow", escrow_account.key.as_ref(), &[escrow_info.bump_seed]],
+            program_id,
+        )?;
+
+        if !escrow_info.is_collection_offer() {
+            return Err(EscrowError::NotCollectionOffer.into());
+        }
+        if !escrow_info.is_release_approved() {
+            return Err(EscrowError::ReleaseNotApproved.into());
+        }
+        if !escrow_info.can_be_taken_by(seller.key) {
+            return Err(EscrowError::TakerNotAllowed.into());
+        }
+        if escrow_info.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+        if escrow_info.temp_token_account_pubkey != *pdas_temp_token_account.key
+            || escrow_info.initializer_pubkey != *initializers_main_account.key
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || pdas_temp_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let pda_account = next_account_info(account_info_iter)?;
+        if *pda_account.key != pda {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        if *token_x_mint_account.key != escrow_info.token_x_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_x_mint_info = token::unpack_mint(token_x_mint_account)?;
+
+        let nft_mint_account = next_account_info(account_info_iter)?;
+        let nft_token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(nft_token_program.key) || nft_mint_account.owner != nft_token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let nft_mint_info = token::unpack_mint(nft_mint_account)?;
+        if nft_mint_info.decimals != 0 || nft_mint_info.supply != 1 {
+            return Err(EscrowError::NotNonFungible.into());
+        }
+        let initializers_nft_account_info = token::unpack_token_account(initializers_nft_account)?;
+        if initializers_nft_account_info.owner != escrow_info.initializer_pubkey
+            || initializers_nft_account_info.mint != *nft_mint_account.key
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Anyone can call their collection whatever they like, only a verified collection has been signed off by the collection's update authority
+        let nft_metadata_account = next_account_info(account_info_iter)?;
+        if *nft_metadata_account.key != metaplex::find_metadata_address(nft_mint_account.key)
+            || *nft_metadata_account.owner != metaplex::id()
+        {
+            return Err(EscrowError::InvalidMetadataAccount.into());
+        }
+        let collection = metaplex::unpack_collection(&nft_metadata_account.try_borrow_data()?, nft_mint_account.key)?;
+        if !collection.is_some_and(|collection| collection.verified && collection.key == escrow_info.collection) {
+            return Err(EscrowError::CollectionMismatch.into());
+        }
+
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        if config.gate_program != Pubkey::default() {
+            gate::check_taker(&config.gate_program, seller, escrow_account, accounts)?;
+        }
+
+        let mut transfer_to_initializer_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                sellers_nft_account.key,
+                nft_mint_account.key,
+                initializers_nft_account.key,
+                seller.key,
+                &[seller.key],
+                1,
+                0,
+            ),
+            nft_token_program.key,
+        )?;
+        let mut account_infos = vec![
+            sellers_nft_account.clone(),
+            nft_mint_account.clone(),
+            initializers_nft_account.clone(),
+            seller.clone(),
+            nft_token_program.clone(),
+        ];
+        transfer_hook::add_hook_accounts(&mut transfer_to_initializer_ix, &mut account_infos, nft_mint_account, 1, accounts)?;
+        log!("Calling the token program to transfer the NFT to the escrow's initializer...");
+        invoke(&transfer_to_initializer_ix, &account_infos)?;
+
+        let amount_x = pdas_temp_token_account_info.amount;
+        let signer_seeds: &[&[&[u8]]] = &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]];
+        if escrow_info.holds_native_sol() {
+            // Unwrapped the same way as in process_exchange
+            let close_pdas_temp_acc_ix = token::for_token_program(
+                spl_token::instruction::close_account(
+                    &spl_token::id(),
+                    pdas_temp_token_account.key,
+                    escrow_account.key,
+                    &pda,
+                    &[&pda],
+                ),
+                token_program.key,
+            )?;
+            log!("Calling the token program to unwrap the escrowed SOL...");
+            invoke_signed(
+                &close_pdas_temp_acc_ix,
+                &[
+                    pdas_temp_token_account.clone(),
+                    escrow_account.clone(),
+                    pda_account.clone(),
+                    token_program.clone(),
+                ],
+                signer_seeds,
+            )?;
+
+            **sellers_token_to_receive_account.try_borrow_mut_lamports()? = sellers_token_to_receive_account
+                .lamports()
+                .checked_add(amount_x)
+                .ok_or(EscrowError::LamportOverflow)?;
+            **escrow_account.try_borrow_mut_lamports()? = escrow_account
+                .lamports()
+                .checked_sub(amount_x)
+                .ok_or(EscrowError::AmountUnderflow)?;
+        } else {
+            if token::unpack_token_account(sellers_token_to_receive_account)?.mint != escrow_info.token_x_mint {
+                return Err(EscrowError::TokenMintMismatch.into());
+            }
+            let mut transfer_to_seller_ix = token::for_token_program(
+                spl_token::instruction::transfer_checked(
+                    &spl_token::id(),
+                    pdas_temp_token_account.key,
+                    token_x_mint_account.key,
+                    sellers_token_to_receive_account.key,
+                    &pda,
+                    &[&pda],
+                    amount_x,
+                    token_x_mint_info.decimals,
+                ),
+                token_program.key,
+            )?;
+            let mut account_infos = vec![
+                pdas_temp_token_account.clone(),
+                token_x_mint_account.clone(),
+                sellers_token_to_receive_account.clone(),
+                pda_account.clone(),
+                token_program.clone(),
+            ];
+            transfer_hook::add_hook_accounts(&mut transfer_to_seller_ix, &mut account_infos, token_x_mint_account, amount_x, accounts)?;
+            log!("Calling the token program to transfer the payment to the seller...");
+            invoke_signed(&transfer_to_seller_ix, &account_infos, signer_seeds)?;
+
+            let close_pdas_temp_acc_ix = token::for_token_program(
+                spl_token::instruction::close_account(
+                    &spl_token::id(),
+                    pdas_temp_token_account.key,
+                    initializers_main_account.key,
+                    &pda,
+                    &[&pda],
+                ),
+                token_program.key,
+            )?;
+            log!("Calling the token program to close pda's temp account...");
+            invoke_signed(
+                &close_pdas_temp_acc_ix,
+                &[
+                    pdas_temp_token_account.clone(),
+                    initializers_main_account.clone(),
+                    pda_account.clone(),
+                    token_program.clone(),
+                ],
+                signer_seeds,
+            )?;
+        }
+
+        Self::deregister_escrow(accounts, escrow_account.key, &escrow_info, program_id)?;
+        log!("Closing the escrow account...");
+        **initializers_main_account.try_borrow_mut_lamports()? = initializers_main_account
+            .lamports()
+            .checked_add(escrow_account.lamports())
+            .ok_or(EscrowError::LamportOverflow)?;
+        **escrow_account.try_borrow_mut_lamports()? = 0;
+        *escrow_account.try_borrow_mut_data()? = &mut [];
+        Self::update_stats(accounts, program_id, |stats| stats.record_exchange(amount_x, 1, true))?;
+
+        EscrowEvent::EscrowExchanged {
+            escrow: *escrow_account.key,
+            initializer: escrow_info.initializer_pubkey,
+            taker: *seller.key,
+            amount_x,
+            amount_y: 1,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    // Matching is a pair of exchanges where each escrow's initializer is the other one's taker. Each side gets everything the other escrow offers, so a side that offers more than the other asks for gives the surplus away rather than having it stuck in a half-settled escrow.
+    fn process_match_escrows(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let escrow_a = next_account_info(account_info_iter)?;
+        let temp_token_account_a = next_account_info(account_info_iter)?;
+        let initializer_a = next_account_info(account_info_iter)?;
+        let token_to_receive_account_a = next_account_info(account_info_iter)?;
+        let pda_a = next_account_info(account_info_iter)?;
+        let escrow_b = next_account_info(account_info_iter)?;
+        let temp_token_account_b = next_account_info(account_info_iter)?;
+        let initializer_b = next_account_info(account_info_iter)?;
+        let token_to_receive_account_b = next_account_info(account_info_iter)?;
+        let pda_b = next_account_info(account_info_iter)?;
+        let token_x_program = next_account_info(account_info_iter)?;
+        let token_y_program = next_account_info(account_info_iter)?;
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+
+        // Both escrows would be closed below, so matching one with itself has to be ruled out explicitly
+        if escrow_a.key == escrow_b.key {
+            return Err(EscrowError::EscrowsDontMatch.into());
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let (escrow_info_a, temp_token_account_info_a) = Self::unpack_matchable_escrow(
+            escrow_a,
+            temp_token_account_a,
+            initializer_a,
+            token_to_receive_account_a,
+            pda_a,
+            token_x_program,
+            now,
+            program_id,
+        )?;
+        let (escrow_info_b, temp_token_account_info_b) = Self::unpack_matchable_escrow(
+            escrow_b,
+            temp_token_account_b,
+            initializer_b,
+            token_to_receive_account_b,
+            pda_b,
+            token_y_program,
+            now,
+            program_id,
+        )?;
+
+        if escrow_info_a.token_x_mint != escrow_info_b.token_y_mint
+            || escrow_info_a.token_y_mint != escrow_info_b.token_x_mint
+        {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        if *token_x_mint_account.key != escrow_info_a.token_x_mint
+            || *token_y_mint_account.key != escrow_info_a.token_y_mint
+        {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+
+        // Private escrows can only be matched with the escrow of their allowed taker
+        if !escrow_info_a.can_be_taken_by(initializer_b.key) || !escrow_info_b.can_be_taken_by(initializer_a.key) {
+            return Err(EscrowError::TakerNotAllowed.into());
+        }
+
+        let amount_x = escrow_info_a.escrowed_amount(&temp_token_account_info_a);
+        let amount_y = escrow_info_b.escrowed_amount(&temp_token_account_info_b);
+        let price_a = escrow_info_a.current_price(now).ok_or(EscrowError::PriceOverflow)?;
+        let price_b = escrow_info_b.current_price(now).ok_or(EscrowError::PriceOverflow)?;
+        if amount_y < price_a || amount_x < price_b {
+            return Err(EscrowError::EscrowsDontMatch.into());
+        }
+
+        let token_x_mint_info = token::unpack_mint(token_x_mint_account)?;
+        let token_y_mint_info = token::unpack_mint(token_y_mint_account)?;
+        Self::settle_matched_escrow(
+            &escrow_info_a,
+            escrow_a,
+            temp_token_account_a,
+            initializer_a,
+            pda_a,
+            token_to_receive_account_b,
+            token_x_program,
+            token_x_mint_account,
+            amount_x,
+            token_x_mint_info.decimals,
+        )?;
+        Self::settle_matched_escrow(
+            &escrow_info_b,
+            escrow_b,
+            temp_token_account_b,
+            initializer_b,
+            pda_b,
+            token_to_receive_account_a,
+            token_y_program,
+            token_y_mint_account,
+            amount_y,
+            token_y_mint_info.decimals,
+        )?;
+
+        EscrowEvent::EscrowExchanged {
+            escrow: *escrow_a.key,
+            initializer: escrow_info_a.initializer_pubkey,
+            taker: escrow_info_b.initializer_pubkey,
+            amount_x,
+            amount_y,
+        }
+        .emit()?;
+        EscrowEvent::EscrowExchanged {
+            escrow: *escrow_b.key,
+            initializer: escrow_info_b.initializer_pubkey,
+            taker: escrow_info_a.initializer_pubkey,
+            amount_x: amount_y,
+            amount_y: amount_x,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    // The same checks process_exchange makes on its escrow, for one side of a match. Only escrows that Exchange would settle straight away qualify.
+    #[allow(clippy::too_many_arguments)]
+    fn unpack_matchable_escrow(
+        escrow_account: &AccountInfo,
+        temp_token_account: &AccountInfo,
+        initializers_main_account: &AccountInfo,
+        token_to_receive_account: &AccountInfo,
+        pda_account: &AccountInfo,
+        token_program: &AccountInfo,
+        now: i64,
+        program_id: &Pubkey,
+    ) -> Result<(Escrow, TokenAccount), ProgramError> {
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+        let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+
+        if !escrow_info.can_be_matched() {
+            return Err(EscrowError::EscrowsDontMatch.into());
+        }
+        if escrow_info.is_reserved(Clock::get()?.slot) {
+            return Err(EscrowError::EscrowReserved.into());
+        }
+        if !escrow_info.is_release_approved() {
+            return Err(EscrowError::ReleaseNotApproved.into());
+        }
+        if escrow_info.is_expired(now) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+
+        if escrow_info.temp_token_account_pubkey != *temp_token_account.key
+            || escrow_info.initializer_pubkey != *initializers_main_account.key
+            || escrow_info.initializer_token_to_receive_account_pubkey != *token_to_receive_account.key
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if !token::is_token_program(token_program.key) || temp_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let pda = Pubkey::create_program_address(
+            &[b"escrow", escrow_account.key.as_ref(), &[escrow_info.bump_seed]],
+            program_id,
+        )?;
+        if *pda_account.key != pda {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let temp_token_account_info = token::unpack_token_account(temp_token_account)?;
+        Ok((escrow_info, temp_token_account_info))
+    }
+
+    // Pays out one side of a match to the other initializer and closes it, like the end of process_exchange
+    #[allow(clippy::too_many_arguments)]
+    fn settle_matched_escrow<'a>(
+        escrow_info: &Escrow,
+        escrow_account: &AccountInfo<'a>,
+        temp_token_account: &AccountInfo<'a>,
+        initializers_main_account: &AccountInfo<'a>,
+        pda_account: &AccountInfo<'a>,
+        counterpartys_token_account: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+        mint_account: &AccountInfo<'a>,
+        amount: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        let escrow_seeds: &[&[u8]] = &[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]];
+
+        let transfer_to_counterparty_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                temp_token_account.key,
+                mint_account.key,
+                counterpartys_token_account.key,
+                pda_account.key,
+                &[pda_account.key],
+                amount,
+                decimals,
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to transfer tokens to the other escrow's initializer...");
+        invoke_signed(
+            &transfer_to_counterparty_ix,
+            &[
+                temp_token_account.clone(),
+                mint_account.clone(),
+                counterpartys_token_account.clone(),
+                pda_account.clone(),
+                token_program.clone(),
+            ],
+            &[escrow_seeds],
+        )?;
+
+        if !escrow_info.is_delegated() {
+            let close_temp_token_account_ix = token::for_token_program(
+                spl_token::instruction::close_account(
+                    &spl_token::id(),
+                    temp_token_account.key,
+                    initializers_main_account.key,
+                    pda_account.key,
+                    &[pda_account.key],
+                ),
+                token_program.key,
+            )?;
+            log!("Calling the token program to close pda's temp account...");
+            invoke_signed(
+                &close_temp_token_account_ix,
+                &[
+                    temp_token_account.clone(),
+                    initializers_main_account.clone(),
+                    pda_account.clone(),
+                    token_program.clone(),
+                ],
+                &[escrow_seeds],
+            )?;
+        }
+
+        log!("Closing the escrow account...");
+        **initializers_main_account.try_borrow_mut_lamports()? = initializers_main_account
+            .lamports()
+            .checked_add(escrow_account.lamports())
+            .ok_or(EscrowError::LamportOverflow)?;
+        **escrow_account.try_borrow_mut_lamports()? = 0;
+        *escrow_account.try_borrow_mut_data()? = &mut [];
+
+        Ok(())
+    }
+
+    // Markets are created at a PDA of the mint pair so there is exactly one order book per pair and direction
+    fn process_init_market(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let base_mint = next_account_info(account_info_iter)?;
+        let quote_mint = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        if base_mint.key == quote_mint.key {
+            return Err(EscrowError::InvalidMarket.into());
+        }
+        // Checking the mints up front means a market can't be created for an account that isn't one
+        token::unpack_mint(base_mint)?;
+        token::unpack_mint(quote_mint)?;
+
+        let (market_address, bump_seed) = find_market_address(base_mint.key, quote_mint.key, program_id);
+        if *market_account.key != market_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        // create_account fails if the account already exists, so each market can only be initialized once
+        log!("Calling the system program to create the market account...");
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                market_account.key,
+                Rent::get()?.minimum_balance(Market::LEN),
+                Market::LEN as u64,
+                program_id,
+            ),
+            &[payer.clone(), market_account.clone(), system_program_account.clone()],
+            &[&[&b"market"[..], base_mint.key.as_ref(), quote_mint.key.as_ref(), &[bump_seed]]],
+        )?;
+
+        Market::pack(
+            Market::new(*base_mint.key, *quote_mint.key, bump_seed),
+            &mut market_account.try_borrow_mut_data()?,
+        )
+    }
+
+    // An order is a snapshot of the escrow's amounts at the time it was placed. Escrows whose price can change - Dutch auctions, oracle escrows, or escrows that get updated - would leave the book out of order, so updating an escrow on a market means cancelling and placing its order again.
+    fn process_place_order(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+        let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+        if escrow_info.initializer_pubkey != *initializer.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let temp_token_account = next_account_info(account_info_iter)?;
+        if escrow_info.temp_token_account_pubkey != *temp_token_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let temp_token_account_info = token::unpack_token_account(temp_token_account)?;
+
+        let offered_amount = escrow_info.escrowed_amount(&temp_token_account_info);
+        if !escrow_info.can_be_matched()
+            || escrow_info.auction_start_amount != 0
+            || escrow_info.allowed_taker_pubkey != Pubkey::default()
+            || offered_amount == 0
+            || escrow_info.expected_amount == 0
+        {
+            return Err(EscrowError::NotListable.into());
+        }
+
+        let market_account = next_account_info(account_info_iter)?;
+        let mut market = Self::unpack_market(market_account, program_id)?;
+        if market.contains(escrow_account.key) {
+            return Err(EscrowError::OrderAlreadyPlaced.into());
+        }
+
+        let side = market
+            .side_of(&escrow_info.token_x_mint, &escrow_info.token_y_mint)
+            .ok_or(EscrowError::TokenMintMismatch)?;
+        let (base_amount, quote_amount) = match side {
+            Side::Ask => (offered_amount, escrow_info.expected_amount),
+            Side::Bid => (escrow_info.expected_amount, offered_amount),
+        };
+        market.insert(side, Order { escrow: *escrow_account.key, base_amount, quote_amount })?;
+        Market::pack(market, &mut market_account.try_borrow_mut_data()?)
+    }
+
+    // An escrow that has been exchanged or cancelled is closed, which is how stale orders are told apart from live ones
+    fn process_cancel_order(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+
+        if escrow_account.owner == program_id && escrow_account.data_len() == Escrow::LEN {
+            let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+            if !authority.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if escrow_info.initializer_pubkey != *authority.key {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        }
+
+        let mut market = Self::unpack_market(market_account, program_id)?;
+        market.remove(escrow_account.key).ok_or(EscrowError::OrderNotFound)?;
+        Market::pack(market, &mut market_account.try_borrow_mut_data()?)
+    }
+
+    // Only the top of the book is ever consumed, so cranks can't skip an order in favour of a worse one
+    fn process_consume_orders(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let market_account = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let mut market = Self::unpack_market(market_account, program_id)?;
+
+        let best_ask = market.orders(Side::Ask).first().ok_or(EscrowError::OrderNotFound)?.escrow;
+        let best_bid = market.orders(Side::Bid).first().ok_or(EscrowError::OrderNotFound)?.escrow;
+        let ask_escrow = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let bid_escrow = accounts.get(6).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if *ask_escrow.key != best_ask || *bid_escrow.key != best_bid {
+            return Err(EscrowError::NotBestOrders.into());
+        }
+
+        Self::process_match_escrows(&accounts[1..], program_id)?;
+
+        market.remove(&best_ask);
+        market.remove(&best_bid);
+        Market::pack(market, &mut market_account.try_borrow_mut_data()?)
+    }
+
+    fn unpack_market(market_account: &AccountInfo, program_id: &Pubkey) -> Result<Market, ProgramError> {
+        if market_account.owner != program_id || market_account.data_len() != Market::LEN {
+            return Err(EscrowError::InvalidMarket.into());
+        }
+        Market::unpack(&market_account.try_borrow_data()?)
+    }
+
+    fn process_init_registry(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer = next_account_info(account_info_iter)?;
+        let registry_account = next_account_info(account_info_iter)?;
+        let token_x_mint = next_account_info(account_info_iter)?;
+        let token_y_mint = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        if token_x_mint.key == token_y_mint.key {
+            return Err(EscrowError::InvalidRegistry.into());
+        }
+        token::unpack_mint(token_x_mint)?;
+        token::unpack_mint(token_y_mint)?;
+
+        let (registry_address, bump_seed) = find_registry_address(token_x_mint.key, token_y_mint.key, program_id);
+        if *registry_account.key != registry_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        log!("Calling the system program to create the registry account...");
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                registry_account.key,
+                Rent::get()?.minimum_balance(EscrowRegistry::LEN),
+                EscrowRegistry::LEN as u64,
+                program_id,
+            ),
+            &[payer.clone(), registry_account.clone(), system_program_account.clone()],
+            &[&[&b"registry"[..], token_x_mint.key.as_ref(), token_y_mint.key.as_ref(), &[bump_seed]]],
+        )?;
+
+        EscrowRegistry::pack(
+            EscrowRegistry::new(*token_x_mint.key, *token_y_mint.key, bump_seed),
+            &mut registry_account.try_borrow_mut_data()?,
+        )
+    }
+
+    fn process_init_stats(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer = next_account_info(account_info_iter)?;
+        let stats_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        let (stats_address, bump_seed) = find_stats_address(program_id);
+        if *stats_account.key != stats_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        log!("Calling the system program to create the stats account...");
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                stats_account.key,
+                Rent::get()?.minimum_balance(Stats::LEN),
+                Stats::LEN as u64,
+                program_id,
+            ),
+            &[payer.clone(), stats_account.clone(), system_program_account.clone()],
+            &[&[&b"stats"[..], &[bump_seed]]],
+        )?;
+
+        Stats::pack(
+            Stats { is_initialized: true, bump_seed, ..Stats::default() },
+            &mut stats_account.try_borrow_mut_data()?,
+        )
+    }
+
+    // Closed escrows have had their data emptied, so an account that isn't a program owned account of escrow size anymore can go
+    fn process_prune_registry(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let registry_account = next_account_info(account_info_iter)?;
+        if registry_account.owner != program_id || registry_account.data_len() != EscrowRegistry::LEN {
+            return Err(EscrowError::InvalidRegistry.into());
+        }
+        let mut registry = EscrowRegistry::unpack(&registry_account.try_borrow_data()?)?;
+
+        for escrow_account in account_info_iter {
+            if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+                registry.remove(escrow_account.key);
+            }
+        }
+        EscrowRegistry::pack(registry, &mut registry_account.try_borrow_mut_data()?)
+    }
+
+    // V1 escrows share the legacy PDA and know neither their mints nor their PDA's bump. The account is upgraded the way UpgradeEscrowAccount does it, unless that happened already, and the temp token account is handed to the escrow's own PDA, after which the escrow looks like one created by InitEscrow.
+    fn process_migrate_escrow(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer = next_account_info(account_info_iter)?;
+
+        if !payer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        if escrow_account.owner != program_id {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+        let versioned_escrow = VersionedEscrow::unpack(&escrow_account.try_borrow_data()?)?;
+
+        let temp_token_account = next_account_info(account_info_iter)?;
+        let token_to_receive_account = next_account_info(account_info_iter)?;
+
+        // V1 predates Token-2022 support
+        let token_program = next_account_info(account_info_iter)?;
+        if *token_program.key != spl_token::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let legacy_pda = next_account_info(account_info_iter)?;
+        let (legacy_pda_key, legacy_bump_seed) = find_legacy_escrow_authority_address(program_id);
+        if *legacy_pda.key != legacy_pda_key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let pda = next_account_info(account_info_iter)?;
+        let (pda_key, _bump_seed) = find_escrow_authority_address(escrow_account.key, program_id);
+        if *pda.key != pda_key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        match versioned_escrow {
+            VersionedEscrow::V1(escrow_v1) => Self::upgrade_escrow_v1(
+                payer,
+                escrow_account,
+                &escrow_v1,
+                temp_token_account,
+                token_to_receive_account,
+                system_program_account,
+                program_id,
+            )?,
+            // Escrows created as V2 have their own PDA from the start, as do V1 escrows that were migrated already
+            VersionedEscrow::V2(escrow_info) => {
+                if *temp_token_account.key != escrow_info.temp_token_account_pubkey {
+                    return Err(ProgramError::InvalidAccountData);
+                }
+                if token::unpack_token_account(temp_token_account)?.owner != legacy_pda_key {
+                    return Err(EscrowError::AlreadyMigrated.into());
+                }
+            }
+        }
+
+        let owner_change_ix = spl_token::instruction::set_authority(
+            token_program.key,
+            temp_token_account.key,
+            Some(pda.key),
+            spl_token::instruction::AuthorityType::AccountOwner,
+            legacy_pda.key,
+            &[legacy_pda.key],
+        )?;
+        log!("Calling the token program to hand the temp token account to the escrow's PDA...");
+        invoke_signed(
+            &owner_change_ix,
+            &[temp_token_account.clone(), legacy_pda.clone(), token_program.clone()],
+            &[&[&b"escrow"[..], &[legacy_bump_seed]]],
+        )
+    }
+
+    // Only the escrow account is upgraded. Its temp token account stays with the legacy PDA until MigrateEscrow hands it over.
+    fn process_upgrade_escrow_account(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer = next_account_info(account_info_iter)?;
+
+        if !payer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        if escrow_account.owner != program_id {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+        let escrow_v1 = match VersionedEscrow::unpack(&escrow_account.try_borrow_data()?)? {
+            VersionedEscrow::V1(escrow_v1) => escrow_v1,
+            VersionedEscrow::V2(_) => return Err(EscrowError::AlreadyMigrated.into()),
+        };
+
+        let temp_token_account = next_account_info(account_info_iter)?;
+        let token_to_receive_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        Self::upgrade_escrow_v1(
+            payer,
+            escrow_account,
+            &escrow_v1,
+            temp_token_account,
+            token_to_receive_account,
+            system_program_account,
+            program_id,
+        )
+    }
+
+    // Rewrites a V1 escrow in the current layout, reading the mints from its token accounts. The bump is the one of the escrow's own PDA, which the temp token account still has to be handed to.
+    fn upgrade_escrow_v1<'a>(
+        payer: &AccountInfo<'a>,
+        escrow_account: &AccountInfo<'a>,
+        escrow_v1: &EscrowV1,
+        temp_token_account: &AccountInfo<'a>,
+        token_to_receive_account: &AccountInfo<'a>,
+        system_program_account: &AccountInfo<'a>,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        if *temp_token_account.key != escrow_v1.temp_token_account_pubkey
+            || *token_to_receive_account.key != escrow_v1.initializer_token_to_receive_account_pubkey
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if *temp_token_account.owner != spl_token::id() || *token_to_receive_account.owner != spl_token::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let temp_token_account_info = token::unpack_token_account(temp_token_account)?;
+        let token_to_receive_account_info = token::unpack_token_account(token_to_receive_account)?;
+        let (legacy_pda_key, _legacy_bump_seed) = find_legacy_escrow_authority_address(program_id);
+        if temp_token_account_info.owner != legacy_pda_key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (_pda_key, bump_seed) = find_escrow_authority_address(escrow_account.key, program_id);
+
+        Self::grow_escrow_account(payer, escrow_account, system_program_account)?;
+        // realloc only zeroes the new bytes, and the old layout mustn't be misread as the new one
+        escrow_account.try_borrow_mut_data()?.fill(0);
+
+        let mut escrow_info = Escrow::unpack_unchecked(&escrow_account.try_borrow_data()?)?;
+        escrow_info.discriminator = ESCROW_DISCRIMINATOR;
+        escrow_info.version = ESCROW_VERSION;
+        escrow_info.is_initialized = true.into();
+        escrow_info.initializer_pubkey = escrow_v1.initializer_pubkey;
+        escrow_info.temp_token_account_pubkey = escrow_v1.temp_token_account_pubkey;
+        escrow_info.initializer_token_to_receive_account_pubkey = escrow_v1.initializer_token_to_receive_account_pubkey;
+        escrow_info.expected_amount = escrow_v1.expected_amount;
+        escrow_info.bump_seed = bump_seed;
+        escrow_info.token_x_mint = temp_token_account_info.mint;
+        escrow_info.token_y_mint = token_to_receive_account_info.mint;
+        escrow_info.created_unix_timestamp = Clock::get()?.unix_timestamp;
+        Escrow::pack(escrow_info, &mut escrow_account.try_borrow_mut_data()?)
+    }
+
+    // Resizes an escrow account of an older layout to Escrow::LEN, the payer topping up its rent. The new bytes are zeroed.
+    fn grow_escrow_account<'a>(
+        payer: &AccountInfo<'a>,
+        escrow_account: &AccountInfo<'a>,
+        system_program_account: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        let lamports_needed = Rent::get()?
+            .minimum_balance(Escrow::LEN)
+            .saturating_sub(escrow_account.lamports());
+        if lamports_needed > 0 {
+            log!("Calling the system program to pay for the larger escrow account...");
+            invoke(
+                &system_instruction::transfer(payer.key, escrow_account.key, lamports_needed),
+                &[payer.clone(), escrow_account.clone(), system_program_account.clone()],
+            )?;
+        }
+        escrow_account.realloc(Escrow::LEN, true)
+    }
+
+    // A closed temp token account belongs to the system program again, which is how it is told apart from one that is still open
+    fn unpack_temp_token_account(pdas_temp_token_account: &AccountInfo) -> Result<Option<TokenAccount>, ProgramError> {
+        if token::is_token_program(pdas_temp_token_account.owner) {
+            Ok(Some(token::unpack_token_account(pdas_temp_token_account)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Nothing is left to return here, so unlike ReclaimExpired no token account of the initializer is needed.
+    fn process_close_stale_escrow(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let pdas_temp_token_account = next_account_info(account_info_iter)?;
+        let initializers_main_account = next_account_info(account_info_iter)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+
+        let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+        if escrow_info.temp_token_account_pubkey != *pdas_temp_token_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if escrow_info.initializer_pubkey != *initializers_main_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let pda_account = next_account_info(account_info_iter)?;
+        let pda = Pubkey::create_program_address(
+            &[b"escrow", escrow_account.key.as_ref(), &[escrow_info.bump_seed]],
+            program_id,
+        )?;
+        if *pda_account.key != pda {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if token::is_token_program(pdas_temp_token_account.owner) && pdas_temp_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let pdas_temp_token_account_info = if escrow_info.holds_lamports() {
+            Some(Self::unpack_escrowed_tokens(pdas_temp_token_account, &escrow_info, &pda)?)
+        } else {
+            Self::unpack_temp_token_account(pdas_temp_token_account)?
+        };
+        if !escrow_info.is_stale(pdas_temp_token_account_info.as_ref(), &pda) {
+            return Err(EscrowError::EscrowNotStale.into());
+        }
+
+        // An empty temp token account the PDA still owns would otherwise keep its rent forever
+        if matches!(&pdas_temp_token_account_info, Some(info) if info.owner == pda) {
+            let close_pdas_temp_acc_ix = token::for_token_program(
+                spl_token::instruction::close_account(
+                    &spl_token::id(),
+                    pdas_temp_token_account.key,
+                    initializers_main_account.key,
+                    &pda,
+                    &[&pda],
+                ),
+                token_program.key,
+            )?;
+            log!("Calling the token program to close pda's temp account...");
+            invoke_signed(
+                &close_pdas_temp_acc_ix,
+                &[
+                    pdas_temp_token_account.clone(),
+                    initializers_main_account.clone(),
+                    pda_account.clone(),
+                    token_program.clone(),
+                ],
+                &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]],
+            )?;
+        }
+
+        Self::deregister_escrow(accounts, escrow_account.key, &escrow_info, program_id)?;
+        log!("Closing the escrow account...");
+        **initializers_main_account.try_borrow_mut_lamports()? = initializers_main_account
+            .lamports()
+            .checked_add(escrow_account.lamports())
+            .ok_or(EscrowError::LamportOverflow)?;
+        **escrow_account.try_borrow_mut_lamports()? = 0;
+        *escrow_account.try_borrow_mut_data()? = &mut [];
+
+        Self::update_stats(accounts, program_id, |stats| stats.record_cancelled(0))?;
+
+        EscrowEvent::EscrowCancelled {
+            escrow: *escrow_account.key,
+            initializer: escrow_info.initializer_pubkey,
+            amount_x: 0,
+            expired: false,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    // Read-only, so the escrow doesn't have to be writable and the instruction can be simulated or called by other programs
+    fn process_get_escrow(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let escrow_account = next_account_info(account_info_iter)?;
+        let pdas_temp_token_account = next_account_info(account_info_iter)?;
+
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+
+        let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+        if escrow_info.temp_token_account_pubkey != *pdas_temp_token_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let pda = Pubkey::create_program_address(
+            &[b"escrow", escrow_account.key.as_ref(), &[escrow_info.bump_seed]],
+            program_id,
+        )?;
+
+        let pdas_temp_token_account_info = if escrow_info.holds_lamports() {
+            Some(Self::unpack_escrowed_tokens(pdas_temp_token_account, &escrow_info, &pda)?)
+        } else {
+            Self::unpack_temp_token_account(pdas_temp_token_account)?
+        };
+        let view = EscrowView::new(
+            &escrow_info,
+            pdas_temp_token_account_info.as_ref(),
+            &pda,
+            Clock::get()?.unix_timestamp,
+        )?;
+        set_return_data(&view.try_to_vec()?);
+        Ok(())
+    }
+
+    // The registry is optional and may be anywhere among the accounts, so it is recognised by its owner and size. No other account of this program has that size.
+    fn find_registry<'a, 'b>(
+        accounts: &'a [AccountInfo<'b>],
+        escrow_info: &Escrow,
+        program_id: &Pubkey,
+    ) -> Result<Option<(&'a AccountInfo<'b>, EscrowRegistry)>, ProgramError> {
+        let registry_account = match accounts
+            .iter()
+            .find(|account| account.owner == program_id && account.data_len() == EscrowRegistry::LEN)
+        {
+            Some(registry_account) => registry_account,
+            None => return Ok(None),
+        };
+        let registry = EscrowRegistry::unpack(&registry_account.try_borrow_data()?)?;
+        if registry.token_x_mint != escrow_info.token_x_mint || registry.token_y_mint != escrow_info.token_y_mint {
+            return Err(EscrowError::InvalidRegistry.into());
+        }
+        Ok(Some((registry_account, registry)))
+    }
+
+    fn register_escrow(
+        accounts: &[AccountInfo],
+        escrow_account: &Pubkey,
+        escrow_info: &Escrow,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        if let Some((registry_account, mut registry)) = Self::find_registry(accounts, escrow_info, program_id)? {
+            registry.insert(*escrow_account)?;
+            EscrowRegistry::pack(registry, &mut registry_account.try_borrow_mut_data()?)?;
+        }
+        Ok(())
+    }
+
+    // Escrows created before the registry existed, or without it, aren't in it, which is fine
+    fn deregister_escrow(
+        accounts: &[AccountInfo],
+        escrow_account: &Pubkey,
+        escrow_info: &Escrow,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        if let Some((registry_account, mut registry)) = Self::find_registry(accounts, escrow_info, program_id)? {
+            if registry.remove(escrow_account) {
+                EscrowRegistry::pack(registry, &mut registry_account.try_borrow_mut_data()?)?;
+            }
+        }
+        Ok(())
+    }
+
+    // The deposit is held by the escrow account itself, on top of its rent, so whichever way the escrow is closed it leaves with the account's lamports, which go to the initializer
+    fn process_reserve(accounts: &[AccountInfo], slots: u64, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker = next_account_info(account_info_iter)?;
+
+        if !taker.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+        let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+
+        let initializers_main_account = next_account_info(account_info_iter)?;
+        if escrow_info.initializer_pubkey != *initializers_main_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let system_program_account = next_account_info(account_info_iter)?;
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        if !escrow_info.is_reservable() {
+            return Err(EscrowError::NotReservable.into());
+        }
+        if !escrow_info.can_be_taken_by(taker.key) {
+            return Err(EscrowError::TakerNotAllowed.into());
+        }
+        let clock = Clock::get()?;
+        if escrow_info.is_expired(clock.unix_timestamp) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+        if slots == 0 || slots > MAX_RESERVATION_SLOTS {
+            return Err(EscrowError::InvalidReservationPeriod.into());
+        }
+        if escrow_info.is_reserved(clock.slot) {
+            return Err(EscrowError::EscrowReserved.into());
+        }
+        // Whoever held a lapsed reservation forfeits their deposit
+        Self::release_reservation(escrow_account, taker, initializers_main_account, clock.slot)?;
+
+        log!("Calling the system program to lock the reservation deposit...");
+        invoke(
+            &system_instruction::transfer(taker.key, escrow_account.key, escrow_info.reservation_deposit),
+            &[taker.clone(), escrow_account.clone(), system_program_account.clone()],
+        )?;
+
+        let reserved_until_slot = clock.slot.checked_add(slots).ok_or(EscrowError::TimeOverflow)?;
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        escrow_info.reserved_by = *taker.key;
+        escrow_info.reserved_until_slot = reserved_until_slot;
+
+        EscrowEvent::EscrowReserved {
+            escrow: *escrow_account.key,
+            taker: *taker.key,
+            deposit: escrow_info.reservation_deposit,
+            reserved_until_slot,
+        }
+        .emit()
+    }
+
+    // Ends the escrow's reservation, if it has one. The deposit goes back to the taker if the reservation is theirs and still running, and to the initializer otherwise.
+    fn release_reservation<'a>(
+        escrow_account: &AccountInfo<'a>,
+        taker: &AccountInfo<'a>,
+        initializers_main_account: &AccountInfo<'a>,
+        slot: u64,
+    ) -> ProgramResult {
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        let reserved_by = escrow_info.reserved_by;
+        if reserved_by == Pubkey::default() {
+            return Ok(());
+        }
+        let forfeited = reserved_by != *taker.key || !escrow_info.is_reserved(slot);
+        let deposit = escrow_info.reservation_deposit;
+        escrow_info.reserved_by = Pubkey::default();
+        escrow_info.reserved_until_slot = 0;
+        drop(escrow_data);
+
+        let recipient = if forfeited { initializers_main_account } else { taker };
+        **recipient.try_borrow_mut_lamports()? = recipient
+            .lamports()
+            .checked_add(deposit)
+            .ok_or(EscrowError::LamportOverflow)?;
+        **escrow_account.try_borrow_mut_lamports()? = escrow_account
+            .lamports()
+            .checked_sub(deposit)
+            .ok_or(EscrowError::AmountUnderflow)?;
+
+        EscrowEvent::ReservationReleased {
+            escrow: *escrow_account.key,
+            taker: reserved_by,
+            deposit,
+            forfeited,
+        }
+        .emit()
+    }
+
+    // The fill record is optional as well, but looked up by key, since a taker's record only belongs to one escrow. The first fill creates it.
+    #[allow(clippy::too_many_arguments)]
+    fn record_fill<'a>(
+        accounts: &[AccountInfo<'a>],
+        escrow_account: &AccountInfo<'a>,
+        payer: &AccountInfo<'a>,
+        taker: &AccountInfo<'a>,
+        amount_x: u64,
+        amount_y: u64,
+        slot: u64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let (fill_record_address, bump_seed) = find_fill_record_address(escrow_account.key, taker.key, program_id);
+        let fill_record_account = match accounts.iter().find(|account| *account.key == fill_record_address) {
+            Some(fill_record_account) => fill_record_account,
+            None => return Ok(()),
+        };
+
+        let mut fill_record = if fill_record_account.owner == program_id {
+            FillRecord::unpack(&fill_record_account.try_borrow_data()?)?
+        } else {
+            let system_program_account = accounts
+                .iter()
+                .find(|account| *account.key == system_program::id())
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            log!("Calling the system program to create the fill record...");
+            invoke_signed(
+                &system_instruction::create_account(
+                    payer.key,
+                    fill_record_account.key,
+                    Rent::get()?.minimum_balance(FillRecord::LEN),
+                    FillRecord::LEN as u64,
+                    program_id,
+                ),
+                &[payer.clone(), fill_record_account.clone(), system_program_account.clone()],
+                &[&[&b"fill"[..], escrow_account.key.as_ref(), taker.key.as_ref(), &[bump_seed]]],
+            )?;
+            FillRecord {
+                is_initialized: true,
+                bump_seed,
+                escrow: *escrow_account.key,
+                taker: *taker.key,
+                ..FillRecord::default()
+            }
+        };
+        fill_record.record(amount_x, amount_y, slot);
+        FillRecord::pack(fill_record, &mut fill_record_account.try_borrow_mut_data()?)
+    }
+
+    // Like the registry, the stats account is optional and recognised by its owner and size. Instructions sent without it simply aren't counted.
+    fn update_stats(accounts: &[AccountInfo], program_id: &Pubkey, update: impl FnOnce(&mut Stats)) -> ProgramResult {
+        if let Some(stats_account) = accounts
+            .iter()
+            .find(|account| account.owner == program_id && account.data_len() == Stats::LEN)
+        {
+            let mut stats = Stats::unpack(&stats_account.try_borrow_data()?)?;
+            update(&mut stats);
+            Stats::pack(stats, &mut stats_account.try_borrow_mut_data()?)?;
+        }
+        Ok(())
+    }
+
+    // Mirror image of the second half of process_exchange: the PDA signs to move the tokens out of the temp account and to close it, only this time everything goes back to the initializer.
+    fn process_cancel(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let pdas_temp_token_account = next_account_info(account_info_iter)?;
+
+        let initializers_token_to_return_account = next_account_info(account_info_iter)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+
+        let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+        let pda = Pubkey::create_program_address(
+            &[b"escrow", escrow_account.key.as_ref(), &[escrow_info.bump_seed]],
+            program_id,
+        )?;
+        let pdas_temp_token_account_info = Self::unpack_escrowed_tokens(pdas_temp_token_account, &escrow_info, &pda)?;
+
+        // The highest bidder's tokens are committed to the escrow, so once there is a bid the auction has to be settled. The same goes for an accepted escrow, and a reserved one until the reservation lapses.
+        if escrow_info.is_accepted() {
+            return Err(EscrowError::AlreadyAccepted.into());
+        }
+        if escrow_info.is_reserved(Clock::get()?.slot) {
+            return Err(EscrowError::EscrowReserved.into());
+        }
+        if escrow_info.highest_bid_amount != 0 {
+            return Err(EscrowError::AuctionHasBids.into());
+        }
+
+        // Without this check anyone could cancel someone else's escrow and send the tokens to an account of their choosing
+        if escrow_info.initializer_pubkey != *initializer.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if escrow_info.temp_token_account_pubkey != *pdas_temp_token_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let token_program = next_account_info(account_info_iter)?;
+        if !Self::is_x_program(&escrow_info, token_program.key) || pdas_temp_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let pda_account = next_account_info(account_info_iter)?;
+        if *pda_account.key != pda {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        if *token_x_mint_account.key != escrow_info.token_x_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_x_mint_info = Self::unpack_x_mint(token_x_mint_account, &escrow_info)?;
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+
+        // Only with the strict-checks feature, see strict. The initializer signed for where their tokens go, and the token program would reject an account of another mint anyway.
+        strict::check_writable(&[pdas_temp_token_account, escrow_account])?;
+        strict::check_canonical_pda(&pda, &[b"escrow", escrow_account.key.as_ref()], program_id)?;
+        strict::check_data_len(pda_account, 0)?;
+        if !escrow_info.holds_native_sol() && !escrow_info.holds_lamports() && !escrow_info.is_delegated() {
+            strict::check_writable(&[initializers_token_to_return_account])?;
+            strict::check_token_account_mint(initializers_token_to_return_account, &escrow_info.token_x_mint)?;
+        }
+
+        // Cancelling a stream doesn't take back what the recipient has already earned. That part goes to them, the rest back to the initializer.
+        let mut returned_amount = escrow_info.escrowed_amount(&pdas_temp_token_account_info);
+        if escrow_info.is_stream() {
+            let recipients_token_account = next_account_info(account_info_iter)?;
+            let recipients_token_account_info = token::unpack_token_account(recipients_token_account)?;
+            if recipients_token_account_info.owner != escrow_info.allowed_taker_pubkey {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            if recipients_token_account_info.mint != escrow_info.token_x_mint {
+                return Err(EscrowError::TokenMintMismatch.into());
+            }
+
+            let accrued_amount = escrow_info.withdrawable_amount(&pdas_temp_token_account_info, Clock::get()?.unix_timestamp);
+            if accrued_amount > 0 {
+                Self::transfer_from_stream(
+                    &escrow_info,
+                    escrow_account,
+                    pdas_temp_token_account,
+                    recipients_token_account,
+                    pda_account,
+                    token_program,
+                    token_x_mint_account,
+                    accrued_amount,
+                    token_x_mint_info.decimals,
+                )?;
+            }
+            returned_amount = returned_amount.checked_sub(accrued_amount).ok_or(EscrowError::AmountUnderflow)?;
+        }
+
+        // The PDA of a lamport escrow can't be left with less than its rent, so all of it goes back at once and any penalty below is paid out of it by the initializer
+        if escrow_info.holds_lamports() {
+            log!("Calling the system program to return the lamports to the escrow's initializer...");
+            Self::transfer_escrowed_lamports(pdas_temp_token_account, initializer, token_program, escrow_account, &escrow_info)?;
+        }
+
+        // Pulling an offer costs the initializer part of what they get back, so posting offers only to see who bites isn't free. Like the fee vault in process_exchange, any token account of the recipient will do, and it is looked up by owner.
+        let penalty = config.cancel_penalty(returned_amount);
+        if penalty > 0 {
+            let recipient = config.cancel_penalty_recipient(&escrow_info, &find_fee_authority_address(program_id).0);
+            if escrow_info.holds_lamports() {
+                let penalty_account = accounts
+                    .iter()
+                    .find(|account| *account.key == recipient)
+                    .ok_or(EscrowError::InvalidFeeVault)?;
+                log!("Calling the system program to transfer the cancellation penalty...");
+                invoke(
+                    &system_instruction::transfer(initializer.key, &recipient, penalty),
+                    &[initializer.clone(), penalty_account.clone(), token_program.clone()],
+                )?;
+            } else {
+                let penalty_account = accounts
+                    .iter()
+                    .find(|account| {
+                        token::unpack_token_account(account)
+                            .is_ok_and(|info| info.owner == recipient && info.mint == escrow_info.token_x_mint)
+                    })
+                    .ok_or(EscrowError::InvalidFeeVault)?;
+
+                let mut transfer_penalty_ix = token::for_token_program(
+                    spl_token::instruction::transfer_checked(
+                        &spl_token::id(),
+                        pdas_temp_token_account.key,
+                        token_x_mint_account.key,
+                        penalty_account.key,
+                        &pda,
+                        &[&pda],
+                        penalty,
+                        token_x_mint_info.decimals,
+                    ),
+                    token_program.key,
+                )?;
+                let mut account_infos = vec![
+                    pdas_temp_token_account.clone(),
+                    token_x_mint_account.clone(),
+                    penalty_account.clone(),
+                    pda_account.clone(),
+                    token_program.clone(),
+                ];
+                transfer_hook::add_hook_accounts(
+                    &mut transfer_penalty_ix,
+                    &mut account_infos,
+                    token_x_mint_account,
+                    penalty,
+                    accounts,
+                )?;
+                log!("Calling the token program to transfer the cancellation penalty...");
+                invoke_signed(
+                    &transfer_penalty_ix,
+                    &account_infos,
+                    &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]],
+                )?;
+            }
+            returned_amount = returned_amount.checked_sub(penalty).ok_or(EscrowError::AmountUnderflow)?;
+
+            EscrowEvent::CancelPenaltyPaid {
+                escrow: *escrow_account.key,
+                recipient,
+                mint: escrow_info.token_x_mint,
+                amount: penalty,
+            }
+            .emit()?;
+        }
+
+        // Closing the temp account unwraps native SOL, so it doesn't have to be transferred first. Delegated tokens are still in the initializer's account.
+        if pdas_temp_token_account_info.is_frozen() {
+            Self::transfer_programmable(
+                accounts,
+                pdas_temp_token_account.key,
+                &pda,
+                initializers_token_to_return_account.key,
+                initializer.key,
+                token_x_mint_account.key,
+                initializer.key,
+                returned_amount,
+                &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]],
+            )?;
+        } else if !escrow_info.holds_native_sol() && !escrow_info.holds_lamports() && !escrow_info.is_delegated() {
+            let mut transfer_to_initializer_ix = token::for_token_program(
+                spl_token::instruction::transfer_checked(
+                    &spl_token::id(),
+                    pdas_temp_token_account.key,
+                    token_x_mint_account.key,
+                    initializers_token_to_return_account.key,
+                    &pda,
+                    &[&pda],
+                    returned_amount,
+                    token_x_mint_info.decimals,
+                ),
+                token_program.key,
+            )?;
+            let mut account_infos = vec![
+                pdas_temp_token_account.clone(),
+                token_x_mint_account.clone(),
+                initializers_token_to_return_account.clone(),
+                pda_account.clone(),
+                token_program.clone(),
+            ];
+            transfer_hook::add_hook_accounts(
+                &mut transfer_to_initializer_ix,
+                &mut account_infos,
+                token_x_mint_account,
+                returned_amount,
+                accounts,
+            )?;
+            log!("Calling the token program to return tokens to the escrow's initializer...");
+            invoke_signed(
+                &transfer_to_initializer_ix,
+                &account_infos,
+                &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]],
+            )?;
+        }
+
+        if escrow_info.is_delegated() {
+            // The tokens never left the initializer's account, so taking away the PDA's allowance is all there is to undo
+            let revoke_ix = token::for_token_program(
+                spl_token::instruction::revoke(
+                    &spl_token::id(),
+                    pdas_temp_token_account.key,
+                    initializer.key,
+                    &[initializer.key],
+                ),
+                token_program.key,
+            )?;
+            log!("Calling the token program to revoke the PDA's allowance...");
+            invoke(
+                &revoke_ix,
+                &[pdas_temp_token_account.clone(), initializer.clone(), token_program.clone()],
+            )?;
+        } else if Self::unpack_temp_token_account(pdas_temp_token_account)?.is_some() {
+            let close_pdas_temp_acc_ix = token::for_token_program(
+                spl_token::instruction::close_account(
+                    &spl_token::id(),
+                    pdas_temp_token_account.key,
+                    initializer.key,
+                    &pda,
+                    &[&pda],
+                ),
+                token_program.key,
+            )?;
+            log!("Calling the token program to close pda's temp account...");
+            invoke_signed(
+                &close_pdas_temp_acc_ix,
+                &[
+                    pdas_temp_token_account.clone(),
+                    initializer.clone(),
+                    pda_account.clone(),
+                    token_program.clone(),
+                ],
+                &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]],
+            )?;
+        }
+
+        Self::deregister_escrow(accounts, escrow_account.key, &escrow_info, program_id)?;
+        log!("Closing the escrow account...");
+        // Same as in process_exchange - clear the data so the account can't be revived with stale escrow info.
+        **initializer.try_borrow_mut_lamports()? = initializer
+            .lamports()
+            .checked_add(escrow_account.lamports())
+            .ok_or(EscrowError::LamportOverflow)?;
+        **escrow_account.try_borrow_mut_lamports()? = 0;
+        *escrow_account.try_borrow_mut_data()? = &mut [];
+
+        Self::update_stats(accounts, program_id, |stats| stats.record_cancelled(returned_amount))?;
+
+        EscrowEvent::EscrowCancelled {
+            escrow: *escrow_account.key,
+            initializer: escrow_info.initializer_pubkey,
+            amount_x: returned_amount,
+            expired: false,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    // The recipient can withdraw as often as they like. The escrow stays open until the stream has ended and everything in it has been withdrawn, at which point the temp token account and the escrow account are closed like after an exchange.
+    fn process_withdraw(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let recipient = next_account_info(account_info_iter)?;
+
+        if !recipient.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let recipients_token_account = next_account_info(account_info_iter)?;
+        let pdas_temp_token_account = next_account_info(account_info_iter)?;
+        let pdas_temp_token_account_info =
+            token::unpack_token_account(pdas_temp_token_account)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+
+        let mut escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+        if !escrow_info.is_stream() {
+            return Err(EscrowError::NotAStream.into());
+        }
+        if escrow_info.allowed_taker_pubkey != *recipient.key {
+            return Err(EscrowError::TakerNotAllowed.into());
+        }
+        if escrow_info.temp_token_account_pubkey != *pdas_temp_token_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || pdas_temp_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let pda_account = next_account_info(account_info_iter)?;
+        let pda = Pubkey::create_program_address(
+            &[b"escrow", escrow_account.key.as_ref(), &[escrow_info.bump_seed]],
+            program_id,
+        )?;
+        if *pda_account.key != pda {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        if *token_x_mint_account.key != escrow_info.token_x_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_x_mint_info = token::unpack_mint(token_x_mint_account)?;
+
+        let initializers_main_account = next_account_info(account_info_iter)?;
+        if escrow_info.initializer_pubkey != *initializers_main_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let amount = escrow_info.withdrawable_amount(&pdas_temp_token_account_info, now);
+        if amount == 0 {
+            return Err(EscrowError::NothingToWithdraw.into());
+        }
+        Self::transfer_from_stream(
+            &escrow_info,
+            escrow_account,
+            pdas_temp_token_account,
+            recipients_token_account,
+            pda_account,
+            token_program,
+            token_x_mint_account,
+            amount,
+            token_x_mint_info.decimals,
+        )?;
+
+        escrow_info.stream_withdrawn_amount = escrow_info
+            .stream_withdrawn_amount
+            .checked_add(amount)
+            .ok_or(EscrowError::AmountOverflow)?;
+
+        EscrowEvent::StreamWithdrawn {
+            escrow: *escrow_account.key,
+            recipient: *recipient.key,
+            amount,
+            withdrawn_amount: escrow_info.stream_withdrawn_amount,
+        }
+        .emit()?;
+
+        if amount < pdas_temp_token_account_info.amount {
+            return Escrow::pack(escrow_info, &mut escrow_account.try_borrow_mut_data()?);
+        }
+
+        let close_pdas_temp_acc_ix = token::for_token_program(
+            spl_token::instruction::close_account(
+                &spl_token::id(),
+                pdas_temp_token_account.key,
+                initializers_main_account.key,
+                &pda,
+                &[&pda],
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to close pda's temp account...");
+        invoke_signed(
             &close_pdas_temp_acc_ix,
             &[
-                pdas_temp_token_account.clone(),
-                initializers_main_account.clone(),
-                pda_account.clone(),
+                pdas_temp_token_account.clone(),
+                initializers_main_account.clone(),
+                pda_account.clone(),
+                token_program.clone(),
+            ],
+            &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]],
+        )?;
+
+        log!("Closing the escrow account...");
+        **initializers_main_account.try_borrow_mut_lamports()? = initializers_main_account
+            .lamports()
+            .checked_add(escrow_account.lamports())
+            .ok_or(EscrowError::LamportOverflow)?;
+        **escrow_account.try_borrow_mut_lamports()? = 0;
+        *escrow_account.try_borrow_mut_data()? = &mut [];
+
+        Ok(())
+    }
+
+    // Runs after process_init_escrow, which checked the escrow account and left it initialized
+    fn set_escrow_options(
+        escrow_account: &AccountInfo,
+        metadata: &[u8],
+        escrow_id: u64,
+        reservation_deposit: u64,
+        taker_merkle_root: [u8; 32],
+    ) -> ProgramResult {
+        if metadata.len() > MAX_METADATA_LEN {
+            return Err(EscrowError::MetadataTooLong.into());
+        }
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        // Two-phase escrows are taken with Accept, which has no room for a proof
+        if taker_merkle_root != [0; 32] && escrow_info.is_two_phase() {
+            return Err(EscrowError::TakerAllowlistNotSupported.into());
+        }
+        escrow_info.escrow_id = escrow_id;
+        escrow_info.reservation_deposit = reservation_deposit;
+        escrow_info.taker_merkle_root = taker_merkle_root;
+        if metadata.is_empty() {
+            return Ok(());
+        }
+        escrow_info.metadata[..metadata.len()].copy_from_slice(metadata);
+        escrow_info.metadata_len = metadata.len() as u8;
+
+        EscrowEvent::EscrowMetadataSet {
+            escrow: *escrow_account.key,
+            metadata: metadata.to_vec(),
+        }
+        .emit()
+    }
+
+    // A lamport escrow keeps its lamports in the PDA itself. They are read as a token account of the PDA's holding that many, so the amounts of all escrows are checked the same way.
+    fn unpack_escrowed_tokens(
+        pdas_temp_token_account: &AccountInfo,
+        escrow_info: &Escrow,
+        pda: &Pubkey,
+    ) -> Result<TokenAccount, ProgramError> {
+        if escrow_info.holds_lamports() {
+            Ok(TokenAccount {
+                mint: escrow_info.token_x_mint,
+                owner: *pda,
+                amount: pdas_temp_token_account.lamports(),
+                state: AccountState::Initialized,
+                ..TokenAccount::default()
+            })
+        } else {
+            token::unpack_token_account(pdas_temp_token_account)
+        }
+    }
+
+    // Lamports have no mint, they count like native SOL. Without a supply they are never mistaken for an NFT.
+    fn unpack_x_mint(token_x_mint_account: &AccountInfo, escrow_info: &Escrow) -> Result<Mint, ProgramError> {
+        if escrow_info.holds_lamports() {
+            Ok(Mint { decimals: spl_token::native_mint::DECIMALS, is_initialized: true, ..Mint::default() })
+        } else {
+            token::unpack_mint(token_x_mint_account)
+        }
+    }
+
+    fn is_x_program(escrow_info: &Escrow, program: &Pubkey) -> bool {
+        if escrow_info.holds_lamports() {
+            *program == system_program::id()
+        } else {
+            token::is_token_program(program)
+        }
+    }
+
+    // The system program owns the PDA, so only it can take lamports out, and only this program can sign for the PDA. All of them go, the PDA can't be left with less than its rent.
+    fn transfer_escrowed_lamports<'a>(
+        pda_account: &AccountInfo<'a>,
+        destination: &AccountInfo<'a>,
+        system_program_account: &AccountInfo<'a>,
+        escrow_account: &AccountInfo,
+        escrow_info: &Escrow,
+    ) -> ProgramResult {
+        invoke_signed(
+            &system_instruction::transfer(pda_account.key, destination.key, pda_account.lamports()),
+            &[pda_account.clone(), destination.clone(), system_program_account.clone()],
+            &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]],
+        )
+    }
+
+    // Token Metadata keeps programmable NFTs frozen and only moves them itself, once the transfer has passed the NFT's rule set, so they can't be moved with the token program like other tokens. What Token Metadata needs is looked up by key, since it depends on the instruction which other optional accounts come before.
+    #[allow(clippy::too_many_arguments)]
+    fn transfer_programmable(
+        accounts: &[AccountInfo],
+        source: &Pubkey,
+        source_owner: &Pubkey,
+        destination: &Pubkey,
+        destination_owner: &Pubkey,
+        mint: &Pubkey,
+        payer: &Pubkey,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let find_account = |key: &Pubkey| {
+            accounts
+                .iter()
+                .find(|account| account.key == key)
+                .ok_or(ProgramError::NotEnoughAccountKeys)
+        };
+        let metadata_account = find_account(&metaplex::find_metadata_address(mint))?;
+        if *metadata_account.owner != metaplex::id() {
+            return Err(EscrowError::InvalidMetadataAccount.into());
+        }
+        let rule_set = metaplex::unpack_rule_set(&metadata_account.try_borrow_data()?, mint)?;
+
+        let transfer_ix = metaplex::transfer_instruction(
+            source,
+            source_owner,
+            destination,
+            destination_owner,
+            mint,
+            source_owner,
+            payer,
+            rule_set.as_ref(),
+            amount,
+        );
+        let account_infos = transfer_ix
+            .accounts
+            .iter()
+            .map(|account| &account.pubkey)
+            .chain([&metaplex::id()])
+            .map(|key| find_account(key).cloned())
+            .collect::<Result<Vec<_>, _>>()?;
+        log!("Calling the token metadata program to transfer the programmable NFT...");
+        invoke_signed(&transfer_ix, &account_infos, signer_seeds)
+    }
+
+    // An NFT's metadata account has to be passed even if the NFT has none, so takers can't skip the royalties by leaving it out. An account Metaplex doesn't own holds no metadata.
+    fn nft_royalties(
+        accounts: &[AccountInfo],
+        token_x_mint_account: &AccountInfo,
+        token_x_mint_info: &Mint,
+        price: u64,
+    ) -> Result<Vec<(Pubkey, u64)>, ProgramError> {
+        if token_x_mint_info.decimals != 0 || token_x_mint_info.supply != 1 {
+            return Ok(vec![]);
+        }
+        let metadata_address = metaplex::find_metadata_address(token_x_mint_account.key);
+        let metadata_account = accounts
+            .iter()
+            .find(|account| *account.key == metadata_address)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if *metadata_account.owner != metaplex::id() {
+            return Ok(vec![]);
+        }
+        let royalties = metaplex::unpack_royalties(&metadata_account.try_borrow_data()?, token_x_mint_account.key)?;
+        Ok(royalties.shares(price))
+    }
+
+    // The taker signed the Exchange, so their signature extends to the fee transfers
+    #[allow(clippy::too_many_arguments)]
+    fn transfer_fee<'a>(
+        taker: &AccountInfo<'a>,
+        takers_sending_token_account: &AccountInfo<'a>,
+        token_y_mint_account: &AccountInfo<'a>,
+        destination: &AccountInfo<'a>,
+        y_token_program: &AccountInfo<'a>,
+        amount: u64,
+        decimals: u8,
+        accounts: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+        let mut transfer_fee_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                takers_sending_token_account.key,
+                token_y_mint_account.key,
+                destination.key,
+                taker.key,
+                &[taker.key],
+                amount,
+                decimals,
+            ),
+            y_token_program.key,
+        )?;
+        let mut account_infos = vec![
+            takers_sending_token_account.clone(),
+            token_y_mint_account.clone(),
+            destination.clone(),
+            taker.clone(),
+            y_token_program.clone(),
+        ];
+        transfer_hook::add_hook_accounts(&mut transfer_fee_ix, &mut account_infos, token_y_mint_account, amount, accounts)?;
+        invoke(&transfer_fee_ix, &account_infos)
+    }
+
+    // Pays part of a stream out of the temp token account, signed by the escrow's PDA
+    #[allow(clippy::too_many_arguments)]
+    fn transfer_from_stream<'a>(
+        escrow_info: &Escrow,
+        escrow_account: &AccountInfo<'a>,
+        pdas_temp_token_account: &AccountInfo<'a>,
+        recipients_token_account: &AccountInfo<'a>,
+        pda_account: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+        token_x_mint_account: &AccountInfo<'a>,
+        amount: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        let transfer_to_recipient_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                pdas_temp_token_account.key,
+                token_x_mint_account.key,
+                recipients_token_account.key,
+                pda_account.key,
+                &[pda_account.key],
+                amount,
+                decimals,
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to transfer the streamed tokens to the recipient...");
+        invoke_signed(
+            &transfer_to_recipient_ix,
+            &[
+                pdas_temp_token_account.clone(),
+                token_x_mint_account.clone(),
+                recipients_token_account.clone(),
+                pda_account.clone(),
+                token_program.clone(),
+            ],
+            &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]],
+        )
+    }
+
+    // Same flow as process_cancel, but instead of the initializer's signature the Clock sysvar is what authorizes it
+    fn process_reclaim_expired(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+
+        let pdas_temp_token_account = next_account_info(account_info_iter)?;
+
+        let initializers_main_account = next_account_info(account_info_iter)?;
+        let initializers_token_to_return_account = next_account_info(account_info_iter)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+
+        let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+        let pda = Pubkey::create_program_address(
+            &[b"escrow", escrow_account.key.as_ref(), &[escrow_info.bump_seed]],
+            program_id,
+        )?;
+        let pdas_temp_token_account_info = Self::unpack_escrowed_tokens(pdas_temp_token_account, &escrow_info, &pda)?;
+
+        if !escrow_info.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::EscrowNotExpired.into());
+        }
+        if escrow_info.is_accepted() {
+            return Err(EscrowError::AlreadyAccepted.into());
+        }
+        if escrow_info.is_reserved(Clock::get()?.slot) {
+            return Err(EscrowError::EscrowReserved.into());
+        }
+        if escrow_info.highest_bid_amount != 0 {
+            return Err(EscrowError::AuctionHasBids.into());
+        }
+
+        if escrow_info.temp_token_account_pubkey != *pdas_temp_token_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if escrow_info.initializer_pubkey != *initializers_main_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // Nobody signed for the initializer here, so make sure the tokens really end up with them. Native SOL and lamports go to their main account instead, and delegated tokens never left it. A programmable NFT goes to their associated token account, which Token Metadata creates if need be.
+        let is_programmable = pdas_temp_token_account_info.is_frozen();
+        if is_programmable {
+            if *initializers_token_to_return_account.key
+                != token::get_associated_token_address(&escrow_info.initializer_pubkey, &escrow_info.token_x_mint, &spl_token::id())
+            {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        } else if !escrow_info.holds_native_sol() && !escrow_info.holds_lamports() && !escrow_info.is_delegated() {
+            let initializers_token_to_return_account_info =
+                token::unpack_token_account(initializers_token_to_return_account)?;
+            if initializers_token_to_return_account_info.owner != escrow_info.initializer_pubkey
+                || initializers_token_to_return_account_info.mint != pdas_temp_token_account_info.mint
+            {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        }
+
+        let token_program = next_account_info(account_info_iter)?;
+        if !Self::is_x_program(&escrow_info, token_program.key) || pdas_temp_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let pda_account = next_account_info(account_info_iter)?;
+        if *pda_account.key != pda {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        if *token_x_mint_account.key != escrow_info.token_x_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_x_mint_info = Self::unpack_x_mint(token_x_mint_account, &escrow_info)?;
+
+        // Only with the strict-checks feature, see strict
+        strict::check_writable(&[pdas_temp_token_account, initializers_main_account, escrow_account])?;
+        strict::check_canonical_pda(&pda, &[b"escrow", escrow_account.key.as_ref()], program_id)?;
+        strict::check_data_len(pda_account, 0)?;
+
+        if is_programmable {
+            // Token Metadata needs someone to pay for the accounts it creates. Whoever sends the reclaim signs it anyway.
+            let payer = accounts
+                .iter()
+                .find(|account| account.is_signer)
+                .ok_or(ProgramError::MissingRequiredSignature)?;
+            Self::transfer_programmable(
+                accounts,
+                pdas_temp_token_account.key,
+                &pda,
+                initializers_token_to_return_account.key,
+                initializers_main_account.key,
+                token_x_mint_account.key,
+                payer.key,
+                pdas_temp_token_account_info.amount,
+                &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]],
+            )?;
+        } else if escrow_info.holds_lamports() {
+            log!("Calling the system program to return the lamports to the escrow's initializer...");
+            Self::transfer_escrowed_lamports(
+                pdas_temp_token_account,
+                initializers_main_account,
+                token_program,
+                escrow_account,
+                &escrow_info,
+            )?;
+        } else if !escrow_info.holds_native_sol() && !escrow_info.is_delegated() {
+            let mut transfer_to_initializer_ix = token::for_token_program(
+                spl_token::instruction::transfer_checked(
+                    &spl_token::id(),
+                    pdas_temp_token_account.key,
+                    token_x_mint_account.key,
+                    initializers_token_to_return_account.key,
+                    &pda,
+                    &[&pda],
+                    pdas_temp_token_account_info.amount,
+                    token_x_mint_info.decimals,
+                ),
+                token_program.key,
+            )?;
+            let mut account_infos = vec![
+                pdas_temp_token_account.clone(),
+                token_x_mint_account.clone(),
+                initializers_token_to_return_account.clone(),
+                pda_account.clone(),
+                token_program.clone(),
+            ];
+            transfer_hook::add_hook_accounts(
+                &mut transfer_to_initializer_ix,
+                &mut account_infos,
+                token_x_mint_account,
+                pdas_temp_token_account_info.amount,
+                accounts,
+            )?;
+            log!("Calling the token program to return tokens to the escrow's initializer...");
+            invoke_signed(
+                &transfer_to_initializer_ix,
+                &account_infos,
+                &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]],
+            )?;
+        }
+
+        // Revoking needs the initializer's signature, so a delegated escrow's allowance outlives it until they revoke it themselves. Without the escrow the PDA never signs for it again.
+        if !escrow_info.is_delegated() && Self::unpack_temp_token_account(pdas_temp_token_account)?.is_some() {
+            let close_pdas_temp_acc_ix = token::for_token_program(
+                spl_token::instruction::close_account(
+                    &spl_token::id(),
+                    pdas_temp_token_account.key,
+                    initializers_main_account.key,
+                    &pda,
+                    &[&pda],
+                ),
+                token_program.key,
+            )?;
+            log!("Calling the token program to close pda's temp account...");
+            invoke_signed(
+                &close_pdas_temp_acc_ix,
+                &[
+                    pdas_temp_token_account.clone(),
+                    initializers_main_account.clone(),
+                    pda_account.clone(),
+                    token_program.clone(),
+                ],
+                &[&[&b"escrow"[..], escrow_account.key.as_ref(), &[escrow_info.bump_seed]]],
+            )?;
+        }
+
+        Self::deregister_escrow(accounts, escrow_account.key, &escrow_info, program_id)?;
+        log!("Closing the escrow account...");
+        **initializers_main_account.try_borrow_mut_lamports()? = initializers_main_account
+            .lamports()
+            .checked_add(escrow_account.lamports())
+            .ok_or(EscrowError::LamportOverflow)?;
+        **escrow_account.try_borrow_mut_lamports()? = 0;
+        *escrow_account.try_borrow_mut_data()? = &mut [];
+
+        let escrowed_amount = escrow_info.escrowed_amount(&pdas_temp_token_account_info);
+        Self::update_stats(accounts, program_id, |stats| stats.record_cancelled(escrowed_amount))?;
+
+        EscrowEvent::EscrowCancelled {
+            escrow: *escrow_account.key,
+            initializer: escrow_info.initializer_pubkey,
+            amount_x: escrowed_amount,
+            expired: true,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    // Like process_update_escrow, but for the expiry. Takers can only gain from an offer staying open longer, so unlike the price it may change while the escrow is reserved
+    fn process_extend_deadline(accounts: &[AccountInfo], expiry_unix_timestamp: i64, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        if escrow_info.initializer_pubkey != *initializer.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let now = Clock::get()?.unix_timestamp;
+        if escrow_info.is_expired(now) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+        if escrow_info.is_accepted() {
+            return Err(EscrowError::AlreadyAccepted.into());
+        }
+        if escrow_info.expiry_unix_timestamp == 0 || expiry_unix_timestamp <= escrow_info.expiry_unix_timestamp {
+            return Err(EscrowError::DeadlineNotExtended.into());
+        }
+        Self::check_max_expiry(&config, expiry_unix_timestamp, now)?;
+
+        escrow_info.expiry_unix_timestamp = expiry_unix_timestamp;
+
+        EscrowEvent::DeadlineExtended {
+            escrow: *escrow_account.key,
+            initializer: *initializer.key,
+            expiry_unix_timestamp,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    // The tokens stay in the temp token account, which the PDA owns whoever the initializer is. Escrows with an id keep their address, even though it was derived from the previous initializer.
+    fn process_transfer_escrow(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+        let new_initializer = next_account_info(account_info_iter)?;
+        let token_to_receive_account = next_account_info(account_info_iter)?;
+
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        if escrow_info.initializer_pubkey != *initializer.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if escrow_info.is_delegated() {
+            return Err(EscrowError::DelegatedEscrowNotTransferable.into());
+        }
+
+        if escrow_info.is_collection_offer() {
+            if *token_to_receive_account.key != escrow_info.collection {
+                return Err(EscrowError::CollectionMismatch.into());
+            }
+        } else {
+            if token::unpack_token_account(token_to_receive_account)?.mint != escrow_info.token_y_mint {
+                return Err(EscrowError::TokenMintMismatch.into());
+            }
+            escrow_info.initializer_token_to_receive_account_pubkey = *token_to_receive_account.key;
+        }
+        escrow_info.initializer_pubkey = *new_initializer.key;
+
+        EscrowEvent::EscrowTransferred {
+            escrow: *escrow_account.key,
+            previous_initializer: *initializer.key,
+            new_initializer: *new_initializer.key,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    // The options are kept packed at the front of the arrays, so removing one moves the last into its place
+    fn process_set_payment_option(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+        let token_to_receive_account = next_account_info(account_info_iter)?;
+        let mint = token::unpack_token_account(token_to_receive_account)?.mint;
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+        if amount > 0 && config.allowlist_enabled {
+            let allowlist = Self::unpack_allowlist(next_account_info(account_info_iter)?, program_id)?;
+            if !allowlist.contains(&mint) {
+                return Err(EscrowError::MintNotAllowed.into());
+            }
+        }
+
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        if escrow_info.initializer_pubkey != *initializer.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if !escrow_info.can_have_payment_options() {
+            return Err(EscrowError::PaymentOptionsNotSupported.into());
+        }
+        if escrow_info.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+        // Same as for UpdateEscrow, the price a reserving taker relies on may be in any of the options
+        if escrow_info.is_accepted() {
+            return Err(EscrowError::AlreadyAccepted.into());
+        }
+        if escrow_info.is_reserved(Clock::get()?.slot) {
+            return Err(EscrowError::EscrowReserved.into());
+        }
+        if mint == escrow_info.token_y_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+
+        let count = escrow_info.payment_option_count as usize;
+        let mut mints = escrow_info.payment_option_mints;
+        let mut token_accounts = escrow_info.payment_option_token_accounts;
+        let mut amounts = escrow_info.payment_option_amounts;
+        let index = escrow_info.payment_option_mints().iter().position(|option_mint| *option_mint == mint);
+        match (index, amount) {
+            (Some(index), 0) => {
+                mints[index] = mints[count - 1];
+                token_accounts[index] = token_accounts[count - 1];
+                amounts[index] = amounts[count - 1];
+                mints[count - 1] = Pubkey::default();
+                token_accounts[count - 1] = Pubkey::default();
+                amounts[count - 1] = 0;
+                escrow_info.payment_option_count -= 1;
+            }
+            (None, 0) => return Err(EscrowError::TokenMintMismatch.into()),
+            (Some(index), _) => {
+                token_accounts[index] = *token_to_receive_account.key;
+                amounts[index] = amount;
+            }
+            (None, _) => {
+                if count == MAX_PAYMENT_OPTIONS {
+                    return Err(EscrowError::TooManyPaymentOptions.into());
+                }
+                mints[count] = mint;
+                token_accounts[count] = *token_to_receive_account.key;
+                amounts[count] = amount;
+                escrow_info.payment_option_count += 1;
+            }
+        }
+        escrow_info.payment_option_mints = mints;
+        escrow_info.payment_option_token_accounts = token_accounts;
+        escrow_info.payment_option_amounts = amounts;
+
+        EscrowEvent::PaymentOptionSet { escrow: *escrow_account.key, mint, amount }.emit()?;
+
+        Ok(())
+    }
+
+    // The token Y price stays, so pricing the escrow back in token Y restores it
+    fn process_set_usd_price(accounts: &[AccountInfo], usd_amount: u64, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        if escrow_info.initializer_pubkey != *initializer.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if !escrow_info.can_be_usd_priced() {
+            return Err(EscrowError::UsdPricingNotSupported.into());
+        }
+        if escrow_info.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+        if escrow_info.is_accepted() {
+            return Err(EscrowError::AlreadyAccepted.into());
+        }
+        if escrow_info.is_reserved(Clock::get()?.slot) {
+            return Err(EscrowError::EscrowReserved.into());
+        }
+
+        escrow_info.usd_amount = usd_amount;
+
+        EscrowEvent::UsdPriceSet {
+            escrow: *escrow_account.key,
+            initializer: *initializer.key,
+            usd_amount,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    // Only the price changes, the tokens stay where they are
+    fn process_update_escrow(accounts: &[AccountInfo], expected_amount: u64, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        if escrow_info.initializer_pubkey != *initializer.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if escrow_info.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+        // The taker has already paid the price they accepted, and a reserving taker has paid to rely on the price
+        if escrow_info.is_accepted() {
+            return Err(EscrowError::AlreadyAccepted.into());
+        }
+        if escrow_info.is_reserved(Clock::get()?.slot) {
+            return Err(EscrowError::EscrowReserved.into());
+        }
+
+        escrow_info.expected_amount = expected_amount;
+
+        EscrowEvent::EscrowUpdated {
+            escrow: *escrow_account.key,
+            initializer: *initializer.key,
+            expected_amount,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    // The approvers are passed as accounts rather than in the instruction data, which keeps the data fixed-size
+    fn process_set_approvers(accounts: &[AccountInfo], threshold: u8, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        if escrow_info.initializer_pubkey != *initializer.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        // Otherwise the initializer could swap out approvers who haven't approved yet
+        if escrow_info.approval_threshold != 0 {
+            return Err(EscrowError::ApproversAlreadySet.into());
+        }
+        // Once a taker has committed their tokens, new approvers could hold them hostage
+        if escrow_info.is_accepted() {
+            return Err(EscrowError::AlreadyAccepted.into());
+        }
+        if escrow_info.highest_bid_amount != 0 {
+            return Err(EscrowError::AuctionHasBids.into());
+        }
+
+        let approvers: Vec<&Pubkey> = account_info_iter.map(|approver| approver.key).collect();
+        if approvers.len() > MAX_APPROVERS || threshold == 0 || threshold as usize > approvers.len() {
+            return Err(EscrowError::InvalidApprovers.into());
+        }
+        for (index, approver) in approvers.iter().enumerate() {
+            // A duplicate would let a single approver count twice
+            if approvers[..index].contains(approver) {
+                return Err(EscrowError::InvalidApprovers.into());
+            }
+            escrow_info.approvers[index] = **approver;
+        }
+        escrow_info.approver_count = approvers.len() as u8;
+        escrow_info.approval_threshold = threshold;
+
+        Ok(())
+    }
+
+    fn process_approve_release(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let approver = next_account_info(account_info_iter)?;
+
+        if !approver.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+        let index = escrow_info
+            .approver_index(approver.key)
+            .ok_or(EscrowError::NotAnApprover)?;
+        escrow_info.approvals |= 1 << index;
+
+        EscrowEvent::ReleaseApproved {
+            escrow: *escrow_account.key,
+            approver: *approver.key,
+            approvals: escrow_info.approvals.count_ones() as u8,
+        }
+        .emit()?;
+
+        Ok(())
+    }
+
+    // The fee vault is created at a PDA so clients can find it from the mint alone. The program signs for the vault's address to create it, and the fee authority becomes the owner of the token account.
+    fn process_init_fee_vault(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let payer = next_account_info(account_info_iter)?;
+
+        if !payer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let fee_vault = next_account_info(account_info_iter)?;
+        let mint = next_account_info(account_info_iter)?;
+        let fee_authority = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let rent_account = next_account_info(account_info_iter)?;
+        let rent = Rent::get()?;
+
+        let (fee_vault_address, bump_seed) = find_fee_vault_address(mint.key, program_id);
+        if *fee_vault.key != fee_vault_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let (fee_authority_address, _bump_seed) = find_fee_authority_address(program_id);
+        if *fee_authority.key != fee_authority_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if !token::is_token_program(token_program.key) || mint.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        token::unpack_mint(mint)?;
+
+        log!("Calling the system program to create the fee vault...");
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                fee_vault.key,
+                rent.minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                token_program.key,
+            ),
+            &[payer.clone(), fee_vault.clone(), system_program_account.clone()],
+            &[&[&b"fee_vault"[..], mint.key.as_ref(), &[bump_seed]]],
+        )?;
+
+        let init_fee_vault_ix = token::for_token_program(
+            spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                fee_vault.key,
+                mint.key,
+                fee_authority.key,
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to initialize the fee vault...");
+        invoke(
+            &init_fee_vault_ix,
+            &[
+                fee_vault.clone(),
+                mint.clone(),
+                fee_authority.clone(),
+                rent_account.clone(),
+                token_program.clone(),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    // The upgrade authority is the one account that provably controls the deployment, so it is the natural first admin
+    fn process_initialize_config(
+        accounts: &[AccountInfo],
+        fee_bps: u16,
+        default_expiry_seconds: i64,
+        referral_share_bps: u16,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+
+        if !admin.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let config_account = next_account_info(account_info_iter)?;
+        let program_data = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        let (config_address, bump_seed) = find_config_address(program_id);
+        if *config_account.key != config_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let (program_data_address, _bump_seed) =
+            Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+        if *program_data.key != program_data_address
+            || *program_data.owner != bpf_loader_upgradeable::id()
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        // The program data account starts with the bincode encoded UpgradeableLoaderState::ProgramData: a u32 variant index (3), the slot it was deployed at, then the upgrade authority as an Option<Pubkey>
+        let upgrade_authority = {
+            let data = program_data.try_borrow_data()?;
+            match data.get(..45) {
+                Some([3, 0, 0, 0, _, _, _, _, _, _, _, _, 1, authority @ ..]) => {
+                    authority.try_into().ok().map(Pubkey::new_from_array)
+                }
+                _ => None,
+            }
+        };
+        if upgrade_authority != Some(*admin.key) {
+            return Err(EscrowError::NotUpgradeAuthority.into());
+        }
+
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if fee_bps > MAX_FEE_BPS {
+            return Err(EscrowError::FeeTooHigh.into());
+        }
+        if default_expiry_seconds < 0 {
+            return Err(EscrowError::ExpiryInThePast.into());
+        }
+        if referral_share_bps > 10_000 {
+            return Err(EscrowError::InvalidReferralShare.into());
+        }
+
+        // create_account fails if the account already exists, so the config can only be initialized once
+        log!("Calling the system program to create the config account...");
+        invoke_signed(
+            &system_instruction::create_account(
+                admin.key,
+                config_account.key,
+                Rent::get()?.minimum_balance(Config::LEN),
+                Config::LEN as u64,
+                program_id,
+            ),
+            &[admin.clone(), config_account.clone(), system_program_account.clone()],
+            &[&[&b"config"[..], &[bump_seed]]],
+        )?;
+
+        let config = Config {
+            is_initialized: true,
+            admin: *admin.key,
+            fee_bps,
+            paused: false,
+            default_expiry_seconds,
+            bump_seed,
+            allowlist_enabled: false,
+            referral_share_bps,
+            fee_collector: Pubkey::default(),
+            cancel_penalty_bps: 0,
+            cancel_penalty_amount: 0,
+            cancel_penalty_to_counterparty: false,
+            gate_program: Pubkey::default(),
+            max_expiry_seconds: 0,
+        };
+        Config::pack(config, &mut config_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    fn process_set_paused(accounts: &[AccountInfo], paused: bool, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        let mut config = Self::unpack_config_as_admin(admin, config_account, program_id)?;
+
+        config.paused = paused;
+        Config::pack(config, &mut config_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    // Exchanges already in flight pay the new fee, but takers who set max_payment never pay more than they signed for
+    fn process_set_fee(
+        accounts: &[AccountInfo],
+        fee_bps: u16,
+        fee_collector: Pubkey,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        let mut config = Self::unpack_config_as_admin(admin, config_account, program_id)?;
+
+        if fee_bps > MAX_FEE_BPS {
+            return Err(EscrowError::FeeTooHigh.into());
+        }
+        config.fee_bps = fee_bps;
+        config.fee_collector = fee_collector;
+        Config::pack(config, &mut config_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    fn process_set_cancel_penalty(
+        accounts: &[AccountInfo],
+        penalty_bps: u16,
+        penalty_amount: u64,
+        to_counterparty: bool,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        let mut config = Self::unpack_config_as_admin(admin, config_account, program_id)?;
+
+        if penalty_bps > MAX_FEE_BPS {
+            return Err(EscrowError::FeeTooHigh.into());
+        }
+        config.cancel_penalty_bps = penalty_bps;
+        config.cancel_penalty_amount = penalty_amount;
+        config.cancel_penalty_to_counterparty = to_counterparty;
+        Config::pack(config, &mut config_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    fn process_set_max_expiry(accounts: &[AccountInfo], max_expiry_seconds: i64, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        let mut config = Self::unpack_config_as_admin(admin, config_account, program_id)?;
+
+        if max_expiry_seconds < 0 {
+            return Err(EscrowError::ExpiryInThePast.into());
+        }
+        config.max_expiry_seconds = max_expiry_seconds;
+        Config::pack(config, &mut config_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    // The gate is called on every take, so a gate calling back into this program would only fail them all
+    fn process_set_gate_program(accounts: &[AccountInfo], gate_program: &Pubkey, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        let mut config = Self::unpack_config_as_admin(admin, config_account, program_id)?;
+
+        if gate_program == program_id {
+            return Err(EscrowError::InvalidGateProgram.into());
+        }
+        config.gate_program = *gate_program;
+        Config::pack(config, &mut config_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    fn process_set_allowlist_enabled(
+        accounts: &[AccountInfo],
+        enabled: bool,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        let mut config = Self::unpack_config_as_admin(admin, config_account, program_id)?;
+
+        config.allowlist_enabled = enabled;
+        Config::pack(config, &mut config_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    // The allowlist account is created on the first add, so deployments that never use it don't pay its rent
+    fn process_add_allowed_mint(accounts: &[AccountInfo], mint: &Pubkey, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        Self::unpack_config_as_admin(admin, config_account, program_id)?;
+
+        let allowlist_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        let mut allowlist = if allowlist_account.data_is_empty() {
+            let (allowlist_address, bump_seed) = find_allowlist_address(program_id);
+            if *allowlist_account.key != allowlist_address {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            if *system_program_account.key != system_program::id() {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+
+            log!("Calling the system program to create the allowlist account...");
+            invoke_signed(
+                &system_instruction::create_account(
+                    admin.key,
+                    allowlist_account.key,
+                    Rent::get()?.minimum_balance(MintAllowlist::LEN),
+                    MintAllowlist::LEN as u64,
+                    program_id,
+                ),
+                &[admin.clone(), allowlist_account.clone(), system_program_account.clone()],
+                &[&[&b"allowlist"[..], &[bump_seed]]],
+            )?;
+
+            MintAllowlist {
+                is_initialized: true,
+                bump_seed,
+                count: 0,
+                mints: [Pubkey::default(); MAX_ALLOWED_MINTS],
+            }
+        } else {
+            Self::unpack_allowlist(allowlist_account, program_id)?
+        };
+
+        if !allowlist.contains(mint) {
+            if allowlist.count as usize == MAX_ALLOWED_MINTS {
+                return Err(EscrowError::AllowlistFull.into());
+            }
+            allowlist.mints[allowlist.count as usize] = *mint;
+            allowlist.count += 1;
+        }
+        MintAllowlist::pack(allowlist, &mut allowlist_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    fn process_remove_allowed_mint(accounts: &[AccountInfo], mint: &Pubkey, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        Self::unpack_config_as_admin(admin, config_account, program_id)?;
+
+        let allowlist_account = next_account_info(account_info_iter)?;
+        let mut allowlist = Self::unpack_allowlist(allowlist_account, program_id)?;
+
+        // The order of the mints doesn't matter, so the last one takes the removed one's place
+        let count = allowlist.count as usize;
+        let index = allowlist.mints[..count]
+            .iter()
+            .position(|allowed_mint| allowed_mint == mint)
+            .ok_or(EscrowError::MintNotAllowed)?;
+        allowlist.mints[index] = allowlist.mints[count - 1];
+        allowlist.mints[count - 1] = Pubkey::default();
+        allowlist.count -= 1;
+        MintAllowlist::pack(allowlist, &mut allowlist_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    // Like the allowlist, the stablecoin list is created on the first add
+    fn process_add_stablecoin(
+        accounts: &[AccountInfo],
+        mint: &Pubkey,
+        price_feed: &Pubkey,
+        tolerance_bps: u16,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        Self::unpack_config_as_admin(admin, config_account, program_id)?;
+
+        let stablecoins_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        if *price_feed == Pubkey::default() || tolerance_bps > MAX_FEE_BPS {
+            return Err(EscrowError::InvalidInstruction.into());
+        }
+
+        let mut stablecoins = if stablecoins_account.data_is_empty() {
+            let (stablecoins_address, bump_seed) = find_stablecoins_address(program_id);
+            if *stablecoins_account.key != stablecoins_address {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            if *system_program_account.key != system_program::id() {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+
+            log!("Calling the system program to create the stablecoin list account...");
+            invoke_signed(
+                &system_instruction::create_account(
+                    admin.key,
+                    stablecoins_account.key,
+                    Rent::get()?.minimum_balance(StablecoinList::LEN),
+                    StablecoinList::LEN as u64,
+                    program_id,
+                ),
+                &[admin.clone(), stablecoins_account.clone(), system_program_account.clone()],
+                &[&[&b"stablecoins"[..], &[bump_seed]]],
+            )?;
+
+            StablecoinList {
+                is_initialized: true,
+                bump_seed,
+                count: 0,
+                mints: [Pubkey::default(); MAX_STABLECOINS],
+                price_feeds: [Pubkey::default(); MAX_STABLECOINS],
+                tolerance_bps: [0; MAX_STABLECOINS],
+            }
+        } else {
+            Self::unpack_stablecoins(stablecoins_account, program_id)?
+        };
+
+        let count = stablecoins.count as usize;
+        let index = match stablecoins.mints[..count].iter().position(|listed_mint| listed_mint == mint) {
+            Some(index) => index,
+            None if count == MAX_STABLECOINS => return Err(EscrowError::StablecoinListFull.into()),
+            None => {
+                stablecoins.mints[count] = *mint;
+                stablecoins.count += 1;
+                count
+            }
+        };
+        stablecoins.price_feeds[index] = *price_feed;
+        stablecoins.tolerance_bps[index] = tolerance_bps;
+        StablecoinList::pack(stablecoins, &mut stablecoins_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    fn process_remove_stablecoin(accounts: &[AccountInfo], mint: &Pubkey, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        Self::unpack_config_as_admin(admin, config_account, program_id)?;
+
+        let stablecoins_account = next_account_info(account_info_iter)?;
+        let mut stablecoins = Self::unpack_stablecoins(stablecoins_account, program_id)?;
+
+        let count = stablecoins.count as usize;
+        let index = stablecoins.mints[..count]
+            .iter()
+            .position(|listed_mint| listed_mint == mint)
+            .ok_or(EscrowError::StablecoinNotListed)?;
+        stablecoins.mints[index] = stablecoins.mints[count - 1];
+        stablecoins.price_feeds[index] = stablecoins.price_feeds[count - 1];
+        stablecoins.tolerance_bps[index] = stablecoins.tolerance_bps[count - 1];
+        stablecoins.mints[count - 1] = Pubkey::default();
+        stablecoins.price_feeds[count - 1] = Pubkey::default();
+        stablecoins.tolerance_bps[count - 1] = 0;
+        stablecoins.count -= 1;
+        StablecoinList::pack(stablecoins, &mut stablecoins_account.try_borrow_mut_data()?)?;
+
+        Ok(())
+    }
+
+    // The participants and mints come as accounts rather than instruction data so the mints can be checked to be mints. The participants don't sign, they agree to the ring by depositing.
+    fn process_init_ring(
+        accounts: &[AccountInfo],
+        ring_id: u64,
+        deadline_unix_timestamp: i64,
+        amounts: &[u64],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let creator = next_account_info(account_info_iter)?;
+
+        if !creator.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let ring_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        if amounts.len() < MIN_RING_LEGS || amounts.len() > MAX_RING_LEGS || amounts.contains(&0) {
+            return Err(EscrowError::InvalidRing.into());
+        }
+        if deadline_unix_timestamp <= Clock::get()?.unix_timestamp {
+            return Err(EscrowError::ExpiryInThePast.into());
+        }
+
+        let mut legs = [RingLeg::default(); MAX_RING_LEGS];
+        for (index, amount) in amounts.iter().enumerate() {
+            let participant = next_account_info(account_info_iter)?;
+            let mint_account = next_account_info(account_info_iter)?;
+            token::unpack_mint(mint_account)?;
+            // Each participant has one leg, which is how DepositRingLeg and WithdrawRingLeg find theirs
+            if legs[..index].iter().any(|leg| leg.participant == *participant.key) {
+                return Err(EscrowError::InvalidRing.into());
+            }
+            legs[index] = RingLeg {
+                participant: *participant.key,
+                mint: *mint_account.key,
+                amount: *amount,
+                is_deposited: false,
+            };
+        }
+
+        let (ring_address, bump_seed) = find_ring_address(creator.key, ring_id, program_id);
+        if *ring_account.key != ring_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        log!("Calling the system program to create the ring account...");
+        invoke_signed(
+            &system_instruction::create_account(
+                creator.key,
+                ring_account.key,
+                Rent::get()?.minimum_balance(SwapRing::LEN),
+                SwapRing::LEN as u64,
+                program_id,
+            ),
+            &[creator.clone(), ring_account.clone(), system_program_account.clone()],
+            &[&[&b"ring"[..], creator.key.as_ref(), &ring_id.to_le_bytes(), &[bump_seed]]],
+        )?;
+
+        SwapRing::pack(
+            SwapRing {
+                is_initialized: true,
+                bump_seed,
+                creator: *creator.key,
+                ring_id,
+                deadline_unix_timestamp,
+                leg_count: amounts.len() as u8,
+                legs,
+            },
+            &mut ring_account.try_borrow_mut_data()?,
+        )
+    }
+
+    // The vault is owned by the ring itself rather than a separate authority PDA, so the ring's seeds sign for every vault when it is executed
+    fn process_deposit_ring_leg(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let participant = next_account_info(account_info_iter)?;
+
+        if !participant.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let participants_token_account = next_account_info(account_info_iter)?;
+        let ring_account = next_account_info(account_info_iter)?;
+        let mut ring = Self::unpack_ring(ring_account, program_id)?;
+        let index = ring.leg_of(participant.key).ok_or(EscrowError::NotARingParticipant)?;
+        let leg = ring.legs[index];
+        if leg.is_deposited {
+            return Err(EscrowError::RingLegAlreadyDeposited.into());
+        }
+        if ring.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::RingExpired.into());
+        }
+
+        let vault = next_account_info(account_info_iter)?;
+        let (vault_address, vault_bump_seed) = find_ring_vault_address(ring_account.key, index as u8, program_id);
+        if *vault.key != vault_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let mint_account = next_account_info(account_info_iter)?;
+        if *mint_account.key != leg.mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let mint_info = token::unpack_mint(mint_account)?;
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || mint_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let system_program_account = next_account_info(account_info_iter)?;
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        // A vault left over from a withdrawn deposit was closed, so create_account works for every deposit
+        log!("Calling the system program to create the ring vault...");
+        invoke_signed(
+            &system_instruction::create_account(
+                participant.key,
+                vault.key,
+                Rent::get()?.minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                token_program.key,
+            ),
+            &[participant.clone(), vault.clone(), system_program_account.clone()],
+            &[&[&b"ring_vault"[..], ring_account.key.as_ref(), &[index as u8], &[vault_bump_seed]]],
+        )?;
+
+        let init_vault_ix = token::for_token_program(
+            spl_token::instruction::initialize_account3(&spl_token::id(), vault.key, mint_account.key, ring_account.key),
+            token_program.key,
+        )?;
+        log!("Calling the token program to initialize the ring vault...");
+        invoke(&init_vault_ix, &[vault.clone(), mint_account.clone(), token_program.clone()])?;
+
+        let deposit_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                participants_token_account.key,
+                mint_account.key,
+                vault.key,
+                participant.key,
+                &[participant.key],
+                leg.amount,
+                mint_info.decimals,
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to deposit the leg...");
+        invoke(
+            &deposit_ix,
+            &[
+                participants_token_account.clone(),
+                mint_account.clone(),
+                vault.clone(),
+                participant.clone(),
+                token_program.clone(),
+            ],
+        )?;
+        // A transfer fee would leave the next participant with less than the ring promised them
+        if token::unpack_token_amount(vault)? != leg.amount {
+            return Err(EscrowError::NetAmountTooLow.into());
+        }
+
+        ring.legs[index].is_deposited = true;
+        SwapRing::pack(ring, &mut ring_account.try_borrow_mut_data()?)?;
+
+        EscrowEvent::RingLegDeposited {
+            ring: *ring_account.key,
+            participant: *participant.key,
+            mint: leg.mint,
+            amount: leg.amount,
+        }
+        .emit()
+    }
+
+    // Every leg is paid out in this one instruction, so the ring settles completely or, if any transfer fails, not at all
+    fn process_execute_ring(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let ring_account = next_account_info(account_info_iter)?;
+        let ring = Self::unpack_ring(ring_account, program_id)?;
+        let creator = next_account_info(account_info_iter)?;
+        if *creator.key != ring.creator {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if !ring.is_complete() {
+            return Err(EscrowError::RingIncomplete.into());
+        }
+
+        let ring_id = ring.ring_id.to_le_bytes();
+        let ring_seeds: &[&[u8]] = &[&b"ring"[..], ring.creator.as_ref(), &ring_id, &[ring.bump_seed]];
+        for (index, leg) in ring.legs().iter().enumerate() {
+            let vault = next_account_info(account_info_iter)?;
+            if *vault.key != find_ring_vault_address(ring_account.key, index as u8, program_id).0 {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            // Whoever executes the ring picks the recipients' token accounts, so they have to belong to the right participants
+            let recipients_token_account = next_account_info(account_info_iter)?;
+            let recipients_token_account_info = token::unpack_token_account(recipients_token_account)?;
+            if recipients_token_account_info.owner != ring.recipient_of(index) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            if recipients_token_account_info.mint != leg.mint {
+                return Err(EscrowError::TokenMintMismatch.into());
+            }
+            let mint_account = next_account_info(account_info_iter)?;
+            let token_program = next_account_info(account_info_iter)?;
+            let participant = next_account_info(account_info_iter)?;
+            if *participant.key != leg.participant {
+                return Err(EscrowError::NotARingParticipant.into());
+            }
+
+            Self::empty_vault(
+                vault,
+                recipients_token_account,
+                mint_account,
+                token_program,
+                participant,
+                ring_account,
+                &leg.mint,
+                ring_seeds,
+            )?;
+        }
+
+        EscrowEvent::RingExecuted { ring: *ring_account.key, leg_count: ring.leg_count }.emit()?;
+
+        log!("Closing the ring account...");
+        Self::close_program_account(ring_account, creator)
+    }
+
+    fn process_withdraw_ring_leg(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let participant = next_account_info(account_info_iter)?;
+
+        if !participant.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let participants_token_account = next_account_info(account_info_iter)?;
+        let ring_account = next_account_info(account_info_iter)?;
+        let mut ring = Self::unpack_ring(ring_account, program_id)?;
+        let index = ring.leg_of(participant.key).ok_or(EscrowError::NotARingParticipant)?;
+        if !ring.legs[index].is_deposited {
+            return Err(EscrowError::RingLegNotDeposited.into());
+        }
+        if !ring.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::RingNotExpired.into());
+        }
+
+        let vault = next_account_info(account_info_iter)?;
+        if *vault.key != find_ring_vault_address(ring_account.key, index as u8, program_id).0 {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let mint_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        let ring_id = ring.ring_id.to_le_bytes();
+        let ring_seeds: &[&[u8]] = &[&b"ring"[..], ring.creator.as_ref(), &ring_id, &[ring.bump_seed]];
+        let amount = Self::empty_vault(
+            vault,
+            participants_token_account,
+            mint_account,
+            token_program,
+            participant,
+            ring_account,
+            &ring.legs[index].mint,
+            ring_seeds,
+        )?;
+
+        ring.legs[index].is_deposited = false;
+        SwapRing::pack(ring, &mut ring_account.try_borrow_mut_data()?)?;
+
+        EscrowEvent::RingLegWithdrawn { ring: *ring_account.key, participant: *participant.key, amount }.emit()
+    }
+
+    // Rings that were never deposited into, or whose deposits were all withdrawn, would otherwise hold the creator's rent forever
+    fn process_close_ring(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let creator = next_account_info(account_info_iter)?;
+
+        if !creator.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let ring_account = next_account_info(account_info_iter)?;
+        let ring = Self::unpack_ring(ring_account, program_id)?;
+        if ring.creator != *creator.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if ring.has_deposits() {
+            return Err(EscrowError::RingHasDeposits.into());
+        }
+
+        log!("Closing the ring account...");
+        Self::close_program_account(ring_account, creator)
+    }
+
+    // Moves everything in a vault owned by one of the program's accounts, such as a ring or a sealed bid, including anything sent to it on top of the deposit, to `destination` and closes the vault, with its rent going to `rent_destination` who paid it. Returns how much was moved.
+    #[allow(clippy::too_many_arguments)]
+    fn empty_vault<'a>(
+        vault: &AccountInfo<'a>,
+        destination: &AccountInfo<'a>,
+        mint_account: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+        rent_destination: &AccountInfo<'a>,
+        authority: &AccountInfo<'a>,
+        mint: &Pubkey,
+        authority_seeds: &[&[u8]],
+    ) -> Result<u64, ProgramError> {
+        if *mint_account.key != *mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let mint_info = token::unpack_mint(mint_account)?;
+        if !token::is_token_program(token_program.key) || vault.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let amount = token::unpack_token_amount(vault)?;
+
+        let transfer_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                vault.key,
+                mint_account.key,
+                destination.key,
+                authority.key,
+                &[authority.key],
+                amount,
+                mint_info.decimals,
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to pay out the vault...");
+        invoke_signed(
+            &transfer_ix,
+            &[
+                vault.clone(),
+                mint_account.clone(),
+                destination.clone(),
+                authority.clone(),
+                token_program.clone(),
+            ],
+            &[authority_seeds],
+        )?;
+
+        let close_vault_ix = token::for_token_program(
+            spl_token::instruction::close_account(
+                &spl_token::id(),
+                vault.key,
+                rent_destination.key,
+                authority.key,
+                &[authority.key],
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to close the vault...");
+        invoke_signed(
+            &close_vault_ix,
+            &[vault.clone(), rent_destination.clone(), authority.clone(), token_program.clone()],
+            &[authority_seeds],
+        )?;
+
+        Ok(amount)
+    }
+
+    // Hands a program-owned account's rent to `destination` and empties it, so the runtime removes it
+    fn close_program_account(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+        **destination.try_borrow_mut_lamports()? = destination
+            .lamports()
+            .checked_add(account.lamports())
+            .ok_or(EscrowError::LamportOverflow)?;
+        **account.try_borrow_mut_lamports()? = 0;
+        *account.try_borrow_mut_data()? = &mut [];
+        Ok(())
+    }
+
+    // Checks that the account really is a ring of this program, at the address its own seeds give
+    fn unpack_ring(ring_account: &AccountInfo, program_id: &Pubkey) -> Result<SwapRing, ProgramError> {
+        if ring_account.owner != program_id || ring_account.data_len() != SwapRing::LEN {
+            return Err(EscrowError::InvalidRing.into());
+        }
+        let ring = SwapRing::unpack(&ring_account.try_borrow_data()?)?;
+        let ring_address = Pubkey::create_program_address(
+            &[b"ring", ring.creator.as_ref(), &ring.ring_id.to_le_bytes(), &[ring.bump_seed]],
+            program_id,
+        )?;
+        if *ring_account.key != ring_address {
+            return Err(EscrowError::InvalidRing.into());
+        }
+        Ok(ring)
+    }
+
+    // Posting a quote doesn't touch the escrow, so any number of takers can quote on it at once
+    #[allow(clippy::too_many_arguments)]
+    fn process_post_quote(
+        accounts: &[AccountInfo],
+        size: u64,
+        price: u64,
+        expiry_unix_timestamp: i64,
+        lock_funds: bool,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let quoter = next_account_info(account_info_iter)?;
+
+        if !quoter.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+        let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+        if !escrow_info.can_be_matched() {
+            return Err(EscrowError::NotQuotable.into());
+        }
+        let now = Clock::get()?.unix_timestamp;
+        if escrow_info.is_expired(now) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+        if !escrow_info.can_be_taken_by(quoter.key) {
+            return Err(EscrowError::TakerNotAllowed.into());
+        }
+        if size == 0 || price == 0 || (expiry_unix_timestamp != 0 && expiry_unix_timestamp <= now) {
+            return Err(EscrowError::InvalidQuote.into());
+        }
+
+        let quote_account = next_account_info(account_info_iter)?;
+        let (quote_address, bump_seed) = find_quote_address(escrow_account.key, quoter.key, program_id);
+        if *quote_account.key != quote_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let source_token_account = next_account_info(account_info_iter)?;
+        let receive_token_account = next_account_info(account_info_iter)?;
+        if token::unpack_token_account(source_token_account)?.mint != escrow_info.token_y_mint
+            || token::unpack_token_account(receive_token_account)?.mint != escrow_info.token_x_mint
+        {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+        if *token_y_mint_account.key != escrow_info.token_y_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_y_mint_info = token::unpack_mint(token_y_mint_account)?;
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || source_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let system_program_account = next_account_info(account_info_iter)?;
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let rent = Rent::get()?;
+
+        // create_account fails if the quoter already has a quote on the escrow, which they have to cancel first
+        log!("Calling the system program to create the quote account...");
+        invoke_signed(
+            &system_instruction::create_account(
+                quoter.key,
+                quote_account.key,
+                rent.minimum_balance(Quote::LEN),
+                Quote::LEN as u64,
+                program_id,
+            ),
+            &[quoter.clone(), quote_account.clone(), system_program_account.clone()],
+            &[&[&b"quote"[..], escrow_account.key.as_ref(), quoter.key.as_ref(), &[bump_seed]]],
+        )?;
+
+        if lock_funds {
+            let vault = next_account_info(account_info_iter)?;
+            let (vault_address, vault_bump_seed) = find_quote_vault_address(quote_account.key, program_id);
+            if *vault.key != vault_address {
+                return Err(ProgramError::InvalidSeeds);
+            }
+
+            log!("Calling the system program to create the quote vault...");
+            invoke_signed(
+                &system_instruction::create_account(
+                    quoter.key,
+                    vault.key,
+                    rent.minimum_balance(TokenAccount::LEN),
+                    TokenAccount::LEN as u64,
+                    token_program.key,
+                ),
+                &[quoter.clone(), vault.clone(), system_program_account.clone()],
+                &[&[&b"quote_vault"[..], quote_account.key.as_ref(), &[vault_bump_seed]]],
+            )?;
+
+            let init_vault_ix = token::for_token_program(
+                spl_token::instruction::initialize_account3(
+                    &spl_token::id(),
+                    vault.key,
+                    token_y_mint_account.key,
+                    quote_account.key,
+                ),
+                token_program.key,
+            )?;
+            log!("Calling the token program to initialize the quote vault...");
+            invoke(&init_vault_ix, &[vault.clone(), token_y_mint_account.clone(), token_program.clone()])?;
+
+            let lock_ix = token::for_token_program(
+                spl_token::instruction::transfer_checked(
+                    &spl_token::id(),
+                    source_token_account.key,
+                    token_y_mint_account.key,
+                    vault.key,
+                    quoter.key,
+                    &[quoter.key],
+                    price,
+                    token_y_mint_info.decimals,
+                ),
+                token_program.key,
+            )?;
+            log!("Calling the token program to lock the quote's price...");
+            invoke(
+                &lock_ix,
+                &[
+                    source_token_account.clone(),
+                    token_y_mint_account.clone(),
+                    vault.clone(),
+                    quoter.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+            // The initializer is promised the whole price, so a transfer fee can't come out of it
+            if token::unpack_token_amount(vault)? != price {
+                return Err(EscrowError::NetAmountTooLow.into());
+            }
+        } else {
+            let approve_ix = token::for_token_program(
+                spl_token::instruction::approve(
+                    &spl_token::id(),
+                    source_token_account.key,
+                    quote_account.key,
+                    quoter.key,
+                    &[quoter.key],
+                    price,
+                ),
+                token_program.key,
+            )?;
+            log!("Calling the token program to delegate the quote's price to the quote...");
+            invoke(
+                &approve_ix,
+                &[source_token_account.clone(), quote_account.clone(), quoter.clone(), token_program.clone()],
+            )?;
+        }
+
+        Quote::pack(
+            Quote {
+                is_initialized: true,
+                bump_seed,
+                escrow: *escrow_account.key,
+                quoter: *quoter.key,
+                size,
+                price,
+                expiry_unix_timestamp,
+                is_locked: lock_funds,
+                source_token_account: *source_token_account.key,
+                receive_token_account: *receive_token_account.key,
+            },
+            &mut quote_account.try_borrow_mut_data()?,
+        )?;
+
+        EscrowEvent::QuotePosted {
+            escrow: *escrow_account.key,
+            quoter: *quoter.key,
+            size,
+            price,
+            expiry_unix_timestamp,
+        }
+        .emit()
+    }
+
+    // The quoter takes the initializer's side of a match: their price pays the initializer, then the escrow is settled to them like one side of MatchEscrows
+    fn process_accept_quote(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        let temp_token_account = next_account_info(account_info_iter)?;
+        let token_to_receive_account = next_account_info(account_info_iter)?;
+        let pda_account = next_account_info(account_info_iter)?;
+        let quote_account = next_account_info(account_info_iter)?;
+        let quoter = next_account_info(account_info_iter)?;
+        let payment_source = next_account_info(account_info_iter)?;
+        let quoters_token_account = next_account_info(account_info_iter)?;
+        let token_x_program = next_account_info(account_info_iter)?;
+        let token_y_program = next_account_info(account_info_iter)?;
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let (escrow_info, temp_token_account_info) = Self::unpack_matchable_escrow(
+            escrow_account,
+            temp_token_account,
+            initializer,
+            token_to_receive_account,
+            pda_account,
+            token_x_program,
+            now,
+            program_id,
+        )?;
+
+        let quote = Self::unpack_quote(quote_account, program_id)?;
+        if quote.escrow != *escrow_account.key || quote.quoter != *quoter.key {
+            return Err(EscrowError::InvalidQuote.into());
+        }
+        if quote.is_expired(now) {
+            return Err(EscrowError::QuoteExpired.into());
+        }
+        // The escrow's own price may have changed since the quote was posted, but what it offers must not have
+        let amount_x = escrow_info.escrowed_amount(&temp_token_account_info);
+        if amount_x != quote.size {
+            return Err(EscrowError::QuoteSizeMismatch.into());
+        }
+        if *quoters_token_account.key != quote.receive_token_account {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if *token_x_mint_account.key != escrow_info.token_x_mint
+            || *token_y_mint_account.key != escrow_info.token_y_mint
+        {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        if quote.is_locked {
+            if *payment_source.key != find_quote_vault_address(quote_account.key, program_id).0 {
+                return Err(ProgramError::InvalidSeeds);
+            }
+        } else if *payment_source.key != quote.source_token_account {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if !token::is_token_program(token_y_program.key) || payment_source.owner != token_y_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+
+        let token_x_mint_info = token::unpack_mint(token_x_mint_account)?;
+        let token_y_mint_info = token::unpack_mint(token_y_mint_account)?;
+        let quote_seeds: &[&[u8]] =
+            &[&b"quote"[..], escrow_account.key.as_ref(), quoter.key.as_ref(), &[quote.bump_seed]];
+
+        let transfer_to_initializer_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                payment_source.key,
+                token_y_mint_account.key,
+                token_to_receive_account.key,
+                quote_account.key,
+                &[quote_account.key],
+                quote.price,
+                token_y_mint_info.decimals,
+            ),
+            token_y_program.key,
+        )?;
+        log!("Calling the token program to pay the quote's price to the initializer...");
+        invoke_signed(
+            &transfer_to_initializer_ix,
+            &[
+                payment_source.clone(),
+                token_y_mint_account.clone(),
+                token_to_receive_account.clone(),
+                quote_account.clone(),
+                token_y_program.clone(),
+            ],
+            &[quote_seeds],
+        )?;
+
+        if quote.is_locked {
+            let close_vault_ix = token::for_token_program(
+                spl_token::instruction::close_account(
+                    &spl_token::id(),
+                    payment_source.key,
+                    quoter.key,
+                    quote_account.key,
+                    &[quote_account.key],
+                ),
+                token_y_program.key,
+            )?;
+            log!("Calling the token program to close the quote vault...");
+            invoke_signed(
+                &close_vault_ix,
+                &[payment_source.clone(), quoter.clone(), quote_account.clone(), token_y_program.clone()],
+                &[quote_seeds],
+            )?;
+        }
+
+        Self::settle_matched_escrow(
+            &escrow_info,
+            escrow_account,
+            temp_token_account,
+            initializer,
+            pda_account,
+            quoters_token_account,
+            token_x_program,
+            token_x_mint_account,
+            amount_x,
+            token_x_mint_info.decimals,
+        )?;
+
+        log!("Closing the quote account...");
+        Self::close_program_account(quote_account, quoter)?;
+
+        EscrowEvent::QuoteAccepted { escrow: *escrow_account.key, quoter: *quoter.key }.emit()?;
+        EscrowEvent::EscrowExchanged {
+            escrow: *escrow_account.key,
+            initializer: escrow_info.initializer_pubkey,
+            taker: *quoter.key,
+            amount_x,
+            amount_y: quote.price,
+        }
+        .emit()
+    }
+
+    fn process_cancel_quote(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let quoter = next_account_info(account_info_iter)?;
+
+        if !quoter.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let quote_account = next_account_info(account_info_iter)?;
+        let quote = Self::unpack_quote(quote_account, program_id)?;
+        if quote.quoter != *quoter.key {
+            return Err(EscrowError::InvalidQuote.into());
+        }
+        let source_token_account = next_account_info(account_info_iter)?;
+        if *source_token_account.key != quote.source_token_account {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || source_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+
+        if quote.is_locked {
+            let vault = next_account_info(account_info_iter)?;
+            if *vault.key != find_quote_vault_address(quote_account.key, program_id).0 {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            let token_y_mint_info = token::unpack_mint(token_y_mint_account)?;
+            let quote_seeds: &[&[u8]] =
+                &[&b"quote"[..], quote.escrow.as_ref(), quoter.key.as_ref(), &[quote.bump_seed]];
+
+            let unlock_ix = token::for_token_program(
+                spl_token::instruction::transfer_checked(
+                    &spl_token::id(),
+                    vault.key,
+                    token_y_mint_account.key,
+                    source_token_account.key,
+                    quote_account.key,
+                    &[quote_account.key],
+                    token::unpack_token_amount(vault)?,
+                    token_y_mint_info.decimals,
+                ),
+                token_program.key,
+            )?;
+            log!("Calling the token program to return the quote's price...");
+            invoke_signed(
+                &unlock_ix,
+                &[
+                    vault.clone(),
+                    token_y_mint_account.clone(),
+                    source_token_account.clone(),
+                    quote_account.clone(),
+                    token_program.clone(),
+                ],
+                &[quote_seeds],
+            )?;
+
+            let close_vault_ix = token::for_token_program(
+                spl_token::instruction::close_account(
+                    &spl_token::id(),
+                    vault.key,
+                    quoter.key,
+                    quote_account.key,
+                    &[quote_account.key],
+                ),
+                token_program.key,
+            )?;
+            log!("Calling the token program to close the quote vault...");
+            invoke_signed(
+                &close_vault_ix,
+                &[vault.clone(), quoter.clone(), quote_account.clone(), token_program.clone()],
+                &[quote_seeds],
+            )?;
+        } else if token::unpack_token_account(source_token_account)?.delegate == COption::Some(*quote_account.key) {
+            // The quoter may have approved someone else since, whose allowance mustn't be revoked along with the quote's
+            let revoke_ix = token::for_token_program(
+                spl_token::instruction::revoke(&spl_token::id(), source_token_account.key, quoter.key, &[quoter.key]),
+                token_program.key,
+            )?;
+            log!("Calling the token program to revoke the quote's allowance...");
+            invoke(&revoke_ix, &[source_token_account.clone(), quoter.clone(), token_program.clone()])?;
+        }
+
+        log!("Closing the quote account...");
+        Self::close_program_account(quote_account, quoter)
+    }
+
+    // Withdrawing only needs the counteroffer, since the escrow may be gone by then
+    fn process_propose_counteroffer(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker = next_account_info(account_info_iter)?;
+
+        if !taker.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        let counteroffer_account = next_account_info(account_info_iter)?;
+        let (counteroffer_address, bump_seed) = find_counteroffer_address(escrow_account.key, taker.key, program_id);
+        if *counteroffer_account.key != counteroffer_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let payment_token_account = next_account_info(account_info_iter)?;
+        let receive_token_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || payment_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let system_program_account = next_account_info(account_info_iter)?;
+        let exists = counteroffer_account.owner == program_id && counteroffer_account.data_len() == Counteroffer::LEN;
+
+        if amount == 0 {
+            if !exists {
+                return Err(EscrowError::InvalidCounteroffer.into());
+            }
+            let counteroffer = Counteroffer::unpack(&counteroffer_account.try_borrow_data()?)?;
+            if *payment_token_account.key != counteroffer.payment_token_account {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            if token::unpack_token_account(payment_token_account)?.delegate == COption::Some(counteroffer_address) {
+                let revoke_ix = token::for_token_program(
+                    spl_token::instruction::revoke(
+                        &spl_token::id(),
+                        payment_token_account.key,
+                        taker.key,
+                        &[taker.key],
+                    ),
+                    token_program.key,
+                )?;
+                log!("Calling the token program to revoke the counteroffer's allowance...");
+                invoke(&revoke_ix, &[payment_token_account.clone(), taker.clone(), token_program.clone()])?;
+            }
+
+            log!("Closing the counteroffer account...");
+            Self::close_program_account(counteroffer_account, taker)?;
+            return EscrowEvent::CounterofferProposed { escrow: *escrow_account.key, taker: *taker.key, amount }.emit();
+        }
+
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+        let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+        if !escrow_info.can_be_matched() {
+            return Err(EscrowError::NotNegotiable.into());
+        }
+        if escrow_info.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+        if !escrow_info.can_be_taken_by(taker.key) {
+            return Err(EscrowError::TakerNotAllowed.into());
+        }
+        if token::unpack_token_account(payment_token_account)?.mint != escrow_info.token_y_mint
+            || token::unpack_token_account(receive_token_account)?.mint != escrow_info.token_x_mint
+        {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+
+        if !exists {
+            if *system_program_account.key != system_program::id() {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            log!("Calling the system program to create the counteroffer account...");
+            invoke_signed(
+                &system_instruction::create_account(
+                    taker.key,
+                    counteroffer_account.key,
+                    Rent::get()?.minimum_balance(Counteroffer::LEN),
+                    Counteroffer::LEN as u64,
+                    program_id,
+                ),
+                &[taker.clone(), counteroffer_account.clone(), system_program_account.clone()],
+                &[&[&b"counteroffer"[..], escrow_account.key.as_ref(), taker.key.as_ref(), &[bump_seed]]],
+            )?;
+        }
+
+        let approve_ix = token::for_token_program(
+            spl_token::instruction::approve(
+                &spl_token::id(),
+                payment_token_account.key,
+                counteroffer_account.key,
+                taker.key,
+                &[taker.key],
+                amount,
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to delegate the counteroffer's amount to the counteroffer...");
+        invoke(
+            &approve_ix,
+            &[payment_token_account.clone(), counteroffer_account.clone(), taker.clone(), token_program.clone()],
+        )?;
+
+        Counteroffer::pack(
+            Counteroffer {
+                is_initialized: true,
+                bump_seed,
+                escrow: *escrow_account.key,
+                taker: *taker.key,
+                amount,
+                payment_token_account: *payment_token_account.key,
+                receive_token_account: *receive_token_account.key,
+            },
+            &mut counteroffer_account.try_borrow_mut_data()?,
+        )?;
+
+        EscrowEvent::CounterofferProposed { escrow: *escrow_account.key, taker: *taker.key, amount }.emit()
+    }
+
+    // Accepting updates the escrow's price to the counteroffer's and settles it in the same instruction, so the taker can't back out in between. The escrow is closed by the settlement, so the new price only shows in the EscrowUpdated event.
+    fn process_accept_counteroffer(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        let temp_token_account = next_account_info(account_info_iter)?;
+        let token_to_receive_account = next_account_info(account_info_iter)?;
+        let pda_account = next_account_info(account_info_iter)?;
+        let counteroffer_account = next_account_info(account_info_iter)?;
+        let taker = next_account_info(account_info_iter)?;
+        let payment_token_account = next_account_info(account_info_iter)?;
+        let takers_token_account = next_account_info(account_info_iter)?;
+        let token_x_program = next_account_info(account_info_iter)?;
+        let token_y_program = next_account_info(account_info_iter)?;
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+
+        let (escrow_info, temp_token_account_info) = Self::unpack_matchable_escrow(
+            escrow_account,
+            temp_token_account,
+            initializer,
+            token_to_receive_account,
+            pda_account,
+            token_x_program,
+            Clock::get()?.unix_timestamp,
+            program_id,
+        )?;
+
+        if counteroffer_account.owner != program_id || counteroffer_account.data_len() != Counteroffer::LEN {
+            return Err(EscrowError::InvalidCounteroffer.into());
+        }
+        let counteroffer = Counteroffer::unpack(&counteroffer_account.try_borrow_data()?)?;
+        let counteroffer_seeds: &[&[u8]] =
+            &[&b"counteroffer"[..], escrow_account.key.as_ref(), taker.key.as_ref(), &[counteroffer.bump_seed]];
+        if *counteroffer_account.key != Pubkey::create_program_address(counteroffer_seeds, program_id)? {
+            return Err(EscrowError::InvalidCounteroffer.into());
+        }
+        if *payment_token_account.key != counteroffer.payment_token_account
+            || *takers_token_account.key != counteroffer.receive_token_account
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if *token_x_mint_account.key != escrow_info.token_x_mint
+            || *token_y_mint_account.key != escrow_info.token_y_mint
+        {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        if !token::is_token_program(token_y_program.key) || payment_token_account.owner != token_y_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+
+        let token_x_mint_info = token::unpack_mint(token_x_mint_account)?;
+        let token_y_mint_info = token::unpack_mint(token_y_mint_account)?;
+
+        let transfer_to_initializer_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                payment_token_account.key,
+                token_y_mint_account.key,
+                token_to_receive_account.key,
+                counteroffer_account.key,
+                &[counteroffer_account.key],
+                counteroffer.amount,
+                token_y_mint_info.decimals,
+            ),
+            token_y_program.key,
+        )?;
+        log!("Calling the token program to pay the counteroffer's amount to the initializer...");
+        invoke_signed(
+            &transfer_to_initializer_ix,
+            &[
+                payment_token_account.clone(),
+                token_y_mint_account.clone(),
+                token_to_receive_account.clone(),
+                counteroffer_account.clone(),
+                token_y_program.clone(),
+            ],
+            &[counteroffer_seeds],
+        )?;
+
+        let amount_x = escrow_info.escrowed_amount(&temp_token_account_info);
+        Self::settle_matched_escrow(
+            &escrow_info,
+            escrow_account,
+            temp_token_account,
+            initializer,
+            pda_account,
+            takers_token_account,
+            token_x_program,
+            token_x_mint_account,
+            amount_x,
+            token_x_mint_info.decimals,
+        )?;
+
+        log!("Closing the counteroffer account...");
+        Self::close_program_account(counteroffer_account, taker)?;
+
+        EscrowEvent::EscrowUpdated {
+            escrow: *escrow_account.key,
+            initializer: *initializer.key,
+            expected_amount: counteroffer.amount,
+        }
+        .emit()?;
+        EscrowEvent::EscrowExchanged {
+            escrow: *escrow_account.key,
+            initializer: escrow_info.initializer_pubkey,
+            taker: *taker.key,
+            amount_x,
+            amount_y: counteroffer.amount,
+        }
+        .emit()
+    }
+
+    // The escrow is an English auction made private to the auction account, which never signs, so PlaceBid turns every bidder away and bids only come in through RevealBid. Its auction ends with the reveal window, which is when SettleAuction can settle it.
+    fn process_init_sealed_auction(
+        accounts: &[AccountInfo],
+        min_bid: u64,
+        deposit: u64,
+        commit_end_unix_timestamp: i64,
+        reveal_end_unix_timestamp: i64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let now = Clock::get()?.unix_timestamp;
+        if min_bid == 0
+            || deposit == 0
+            || commit_end_unix_timestamp <= now
+            || reveal_end_unix_timestamp <= commit_end_unix_timestamp
+        {
+            return Err(EscrowError::InvalidAuctionParameters.into());
+        }
+
+        let get = |index: usize| accounts.get(index).ok_or(ProgramError::NotEnoughAccountKeys);
+        let initializer = get(0)?;
+        let escrow_account = get(3)?;
+        let (auction_address, bump_seed) = find_sealed_auction_address(escrow_account.key, program_id);
+        let auction_account = accounts
+            .iter()
+            .find(|account| *account.key == auction_address)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+        Self::process_init_escrow(accounts, min_bid, 0, auction_address, 0, 0, &Pubkey::default(), program_id)?;
+
+        let (beneficiary_token_account, token_y_mint) = {
+            let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+            let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+            escrow_info.is_english_auction = true.into();
+            escrow_info.auction_start_unix_timestamp = now;
+            escrow_info.auction_end_unix_timestamp = reveal_end_unix_timestamp;
+            (escrow_info.initializer_token_to_receive_account_pubkey, escrow_info.token_y_mint)
+        };
+
+        let system_program_account = accounts
+            .iter()
+            .find(|account| *account.key == system_program::id())
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        log!("Calling the system program to create the auction account...");
+        invoke_signed(
+            &system_instruction::create_account(
+                initializer.key,
+                auction_account.key,
+                Rent::get()?.minimum_balance(SealedAuction::LEN),
+                SealedAuction::LEN as u64,
+                program_id,
+            ),
+            &[initializer.clone(), auction_account.clone(), system_program_account.clone()],
+            &[&[&b"sealed_auction"[..], escrow_account.key.as_ref(), &[bump_seed]]],
+        )?;
+
+        SealedAuction::pack(
+            SealedAuction {
+                is_initialized: true,
+                bump_seed,
+                escrow: *escrow_account.key,
+                initializer: *initializer.key,
+                beneficiary_token_account,
+                token_y_mint,
+                min_bid,
+                deposit,
+                commit_end_unix_timestamp,
+                reveal_end_unix_timestamp,
+                open_commitments: 0,
+            },
+            &mut auction_account.try_borrow_mut_data()?,
+        )
+    }
+
+    // Like the ring vaults, the deposit's vault is owned by the sealed bid itself, whose seeds then sign for returning or slashing it
+    fn process_commit_bid(accounts: &[AccountInfo], commitment: [u8; 32], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let bidder = next_account_info(account_info_iter)?;
+
+        if !bidder.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let bidders_token_account = next_account_info(account_info_iter)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+        let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+
+        let auction_account = next_account_info(account_info_iter)?;
+        let mut auction = Self::unpack_sealed_auction(auction_account, program_id)?;
+        if auction.escrow != *escrow_account.key {
+            return Err(EscrowError::InvalidSealedAuction.into());
+        }
+        let now = Clock::get()?.unix_timestamp;
+        if !auction.is_commit_open(now) {
+            return Err(EscrowError::CommitWindowClosed.into());
+        }
+        if escrow_info.is_expired(now) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+
+        let sealed_bid_account = next_account_info(account_info_iter)?;
+        let (sealed_bid_address, bump_seed) = find_sealed_bid_address(auction_account.key, bidder.key, program_id);
+        if *sealed_bid_account.key != sealed_bid_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let vault = next_account_info(account_info_iter)?;
+        let (vault_address, vault_bump_seed) = find_sealed_bid_vault_address(sealed_bid_account.key, program_id);
+        if *vault.key != vault_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+        if *token_y_mint_account.key != auction.token_y_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_y_mint_info = token::unpack_mint(token_y_mint_account)?;
+        let y_token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(y_token_program.key) || token_y_mint_account.owner != y_token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let system_program_account = next_account_info(account_info_iter)?;
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        if config.gate_program != Pubkey::default() {
+            gate::check_taker(&config.gate_program, bidder, escrow_account, accounts)?;
+        }
+
+        // Changing the commitment keeps the deposit that is already in the vault
+        if !sealed_bid_account.data_is_empty() {
+            let mut sealed_bid = Self::unpack_sealed_bid(sealed_bid_account, program_id)?;
+            sealed_bid.commitment = commitment;
+            SealedBid::pack(sealed_bid, &mut sealed_bid_account.try_borrow_mut_data()?)?;
+            return EscrowEvent::BidCommitted { escrow: *escrow_account.key, bidder: *bidder.key }.emit();
+        }
+
+        let rent = Rent::get()?;
+        log!("Calling the system program to create the sealed bid account...");
+        invoke_signed(
+            &system_instruction::create_account(
+                bidder.key,
+                sealed_bid_account.key,
+                rent.minimum_balance(SealedBid::LEN),
+                SealedBid::LEN as u64,
+                program_id,
+            ),
+            &[bidder.clone(), sealed_bid_account.clone(), system_program_account.clone()],
+            &[&[&b"sealed_bid"[..], auction_account.key.as_ref(), bidder.key.as_ref(), &[bump_seed]]],
+        )?;
+
+        log!("Calling the system program to create the sealed bid's vault...");
+        invoke_signed(
+            &system_instruction::create_account(
+                bidder.key,
+                vault.key,
+                rent.minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                y_token_program.key,
+            ),
+            &[bidder.clone(), vault.clone(), system_program_account.clone()],
+            &[&[&b"sealed_bid_vault"[..], sealed_bid_account.key.as_ref(), &[vault_bump_seed]]],
+        )?;
+
+        let init_vault_ix = token::for_token_program(
+            spl_token::instruction::initialize_account3(
+                &spl_token::id(),
+                vault.key,
+                token_y_mint_account.key,
+                sealed_bid_account.key,
+            ),
+            y_token_program.key,
+        )?;
+        log!("Calling the token program to initialize the sealed bid's vault...");
+        invoke(&init_vault_ix, &[vault.clone(), token_y_mint_account.clone(), y_token_program.clone()])?;
+
+        let deposit_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                bidders_token_account.key,
+                token_y_mint_account.key,
+                vault.key,
+                bidder.key,
+                &[bidder.key],
+                auction.deposit,
+                token_y_mint_info.decimals,
+            ),
+            y_token_program.key,
+        )?;
+        log!("Calling the token program to transfer the deposit...");
+        invoke(
+            &deposit_ix,
+            &[
+                bidders_token_account.clone(),
+                token_y_mint_account.clone(),
+                vault.clone(),
+                bidder.clone(),
+                y_token_program.clone(),
+            ],
+        )?;
+
+        SealedBid::pack(
+            SealedBid {
+                is_initialized: true,
+                bump_seed,
+                auction: *auction_account.key,
+                bidder: *bidder.key,
+                commitment,
+            },
+            &mut sealed_bid_account.try_borrow_mut_data()?,
+        )?;
+        auction.open_commitments = auction.open_commitments.checked_add(1).ok_or(EscrowError::AmountOverflow)?;
+        SealedAuction::pack(auction, &mut auction_account.try_borrow_mut_data()?)?;
+
+        EscrowEvent::BidCommitted { escrow: *escrow_account.key, bidder: *bidder.key }.emit()
+    }
+
+    // The deposit is returned first, so it can pay for part of the bid. Once the escrow is gone, e.g. because the initializer settled early or cancelled before anyone revealed, revealing only returns the deposit.
+    fn process_reveal_bid(
+        accounts: &[AccountInfo],
+        amount: u64,
+        salt: &[u8; 32],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let bidder = next_account_info(account_info_iter)?;
+
+        if !bidder.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let bidders_token_account = next_account_info(account_info_iter)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+        let auction_account = next_account_info(account_info_iter)?;
+        let mut auction = Self::unpack_sealed_auction(auction_account, program_id)?;
+        if auction.escrow != *escrow_account.key {
+            return Err(EscrowError::InvalidSealedAuction.into());
+        }
+        let sealed_bid_account = next_account_info(account_info_iter)?;
+        let sealed_bid = Self::unpack_sealed_bid(sealed_bid_account, program_id)?;
+        if sealed_bid.auction != *auction_account.key || sealed_bid.bidder != *bidder.key {
+            return Err(EscrowError::InvalidSealedBid.into());
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        if auction.is_commit_open(now) {
+            return Err(EscrowError::RevealWindowNotOpen.into());
+        }
+        if !auction.is_reveal_open(now) {
+            return Err(EscrowError::RevealWindowClosed.into());
+        }
+        if sealed_auction::commitment(bidder.key, amount, salt) != sealed_bid.commitment {
+            return Err(EscrowError::CommitmentMismatch.into());
+        }
+
+        let vault = next_account_info(account_info_iter)?;
+        if *vault.key != find_sealed_bid_vault_address(sealed_bid_account.key, program_id).0 {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let bid_account = next_account_info(account_info_iter)?;
+        let pda_account = next_account_info(account_info_iter)?;
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+        let y_token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+        let rent_account = next_account_info(account_info_iter)?;
+
+        let sealed_bid_seeds: &[&[u8]] = &[
+            &b"sealed_bid"[..],
+            auction_account.key.as_ref(),
+            bidder.key.as_ref(),
+            &[sealed_bid.bump_seed],
+        ];
+        Self::empty_vault(
+            vault,
+            bidders_token_account,
+            token_y_mint_account,
+            y_token_program,
+            bidder,
+            sealed_bid_account,
+            &auction.token_y_mint,
+            sealed_bid_seeds,
+        )?;
+        auction.open_commitments = auction.open_commitments.saturating_sub(1);
+        SealedAuction::pack(auction, &mut auction_account.try_borrow_mut_data()?)?;
+
+        // The escrow's address could have been reused for an escrow that isn't this auction's
+        let mut escrow_info = if escrow_account.owner == program_id && escrow_account.data_len() == Escrow::LEN {
+            Some(Escrow::unpack(&escrow_account.try_borrow_data()?)?)
+                .filter(|escrow_info| escrow_info.allowed_taker_pubkey == *auction_account.key)
+        } else {
+            None
+        };
+        let is_highest_bid = match &escrow_info {
+            Some(escrow_info) => {
+                amount >= auction.min_bid && amount > escrow_info.highest_bid_amount && !escrow_info.is_expired(now)
+            }
+            None => false,
+        };
+
+        if let (true, Some(escrow_info)) = (is_highest_bid, escrow_info.as_mut()) {
+            let pda = Pubkey::create_program_address(
+                &[b"escrow", escrow_account.key.as_ref(), &[escrow_info.bump_seed]],
+                program_id,
+            )?;
+            if *pda_account.key != pda {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            let (bid_address, bid_bump_seed) = find_bid_address(escrow_account.key, bidder.key, program_id);
+            if *bid_account.key != bid_address {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            let token_y_mint_info = token::unpack_mint(token_y_mint_account)?;
+
+            let previous_bid = if bid_account.data_is_empty() {
+                Self::create_bid_account(
+                    bidder,
+                    bid_account,
+                    escrow_account,
+                    pda_account,
+                    token_y_mint_account,
+                    y_token_program,
+                    system_program_account,
+                    rent_account,
+                    bid_bump_seed,
+                )?;
+                0
+            } else {
+                token::unpack_token_amount(bid_account)?
+            };
+
+            let transfer_bid_ix = token::for_token_program(
+                spl_token::instruction::transfer_checked(
+                    &spl_token::id(),
+                    bidders_token_account.key,
+                    token_y_mint_account.key,
+                    bid_account.key,
+                    bidder.key,
+                    &[bidder.key],
+                    amount.saturating_sub(previous_bid),
+                    token_y_mint_info.decimals,
+                ),
+                y_token_program.key,
+            )?;
+            log!("Calling the token program to transfer the bid...");
+            invoke(
+                &transfer_bid_ix,
+                &[
+                    bidders_token_account.clone(),
+                    token_y_mint_account.clone(),
+                    bid_account.clone(),
+                    bidder.clone(),
+                    y_token_program.clone(),
+                ],
+            )?;
+
+            escrow_info.highest_bidder = *bidder.key;
+            escrow_info.highest_bid_amount = amount;
+            Escrow::pack(*escrow_info, &mut escrow_account.try_borrow_mut_data()?)?;
+        }
+
+        // Closed last, as the bid account's rent comes out of the bidder's lamports in a CPI that doesn't include the sealed bid account
+        log!("Closing the sealed bid account...");
+        Self::close_program_account(sealed_bid_account, bidder)?;
+
+        EscrowEvent::BidRevealed {
+            escrow: *escrow_account.key,
+            bidder: *bidder.key,
+            amount,
+            is_highest_bid,
+        }
+        .emit()
+    }
+
+    // Whether the escrow is still open doesn't matter: a bidder who didn't reveal in time loses the deposit either way
+    fn process_slash_bid(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let auction_account = next_account_info(account_info_iter)?;
+        let mut auction = Self::unpack_sealed_auction(auction_account, program_id)?;
+        let sealed_bid_account = next_account_info(account_info_iter)?;
+        let sealed_bid = Self::unpack_sealed_bid(sealed_bid_account, program_id)?;
+        if sealed_bid.auction != *auction_account.key {
+            return Err(EscrowError::InvalidSealedBid.into());
+        }
+        let bidder = next_account_info(account_info_iter)?;
+        if *bidder.key != sealed_bid.bidder {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if Clock::get()?.unix_timestamp < auction.reveal_end_unix_timestamp {
+            return Err(EscrowError::RevealWindowOpen.into());
+        }
+
+        let vault = next_account_info(account_info_iter)?;
+        if *vault.key != find_sealed_bid_vault_address(sealed_bid_account.key, program_id).0 {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let beneficiary_token_account = next_account_info(account_info_iter)?;
+        if *beneficiary_token_account.key != auction.beneficiary_token_account {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+        let y_token_program = next_account_info(account_info_iter)?;
+
+        let sealed_bid_seeds: &[&[u8]] = &[
+            &b"sealed_bid"[..],
+            auction_account.key.as_ref(),
+            bidder.key.as_ref(),
+            &[sealed_bid.bump_seed],
+        ];
+        let deposit = Self::empty_vault(
+            vault,
+            beneficiary_token_account,
+            token_y_mint_account,
+            y_token_program,
+            bidder,
+            sealed_bid_account,
+            &auction.token_y_mint,
+            sealed_bid_seeds,
+        )?;
+        log!("Closing the sealed bid account...");
+        Self::close_program_account(sealed_bid_account, bidder)?;
+        auction.open_commitments = auction.open_commitments.saturating_sub(1);
+        SealedAuction::pack(auction, &mut auction_account.try_borrow_mut_data()?)?;
+
+        EscrowEvent::BidSlashed { escrow: auction.escrow, bidder: *bidder.key, deposit }.emit()
+    }
+
+    // The auction has to outlive its escrow, since deposits can still be returned or slashed after the escrow settles
+    fn process_close_sealed_auction(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        let auction_account = next_account_info(account_info_iter)?;
+        let auction = Self::unpack_sealed_auction(auction_account, program_id)?;
+        if auction.initializer != *initializer.key || auction.escrow != *escrow_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if escrow_account.owner == program_id && escrow_account.data_len() == Escrow::LEN {
+            return Err(EscrowError::AuctionNotEnded.into());
+        }
+        if auction.open_commitments != 0 {
+            return Err(EscrowError::SealedBidsOutstanding.into());
+        }
+
+        log!("Closing the auction account...");
+        Self::close_program_account(auction_account, initializer)
+    }
+
+    // Like a sealed-bid auction's, the escrow is an English auction private to the raffle, which never signs, so neither Exchange nor PlaceBid can take it. Its highest bid tracks the ticket sales, which keeps the initializer from cancelling once a ticket is sold.
+    fn process_init_raffle(
+        accounts: &[AccountInfo],
+        ticket_price: u64,
+        max_tickets: u32,
+        end_unix_timestamp: i64,
+        vrf: &Pubkey,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let now = Clock::get()?.unix_timestamp;
+        if ticket_price == 0 || max_tickets == 0 || end_unix_timestamp <= now {
+            return Err(EscrowError::InvalidAuctionParameters.into());
+        }
+
+        let get = |index: usize| accounts.get(index).ok_or(ProgramError::NotEnoughAccountKeys);
+        let find = |key: &Pubkey| {
+            accounts
+                .iter()
+                .skip(7)
+                .find(|account| account.key == key)
+                .ok_or(ProgramError::NotEnoughAccountKeys)
+        };
+        let initializer = get(0)?;
+        let escrow_account = get(3)?;
+        let (raffle_address, bump_seed) = find_raffle_address(escrow_account.key, program_id);
+        let raffle_account = find(&raffle_address)?;
+        let (vault_address, vault_bump_seed) = find_raffle_vault_address(&raffle_address, program_id);
+        let vault = find(&vault_address)?;
+        let system_program_account = find(&system_program::id())?;
+
+        Self::process_init_escrow(accounts, ticket_price, 0, raffle_address, 0, 0, &Pubkey::default(), program_id)?;
+
+        let (proceeds_token_account, token_y_mint) = {
+            let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+            let escrow_info = Escrow::load_mut(&mut escrow_data)?;
+            escrow_info.is_english_auction = true.into();
+            escrow_info.auction_start_unix_timestamp = now;
+            escrow_info.auction_end_unix_timestamp = end_unix_timestamp;
+            (escrow_info.initializer_token_to_receive_account_pubkey, escrow_info.token_y_mint)
+        };
+        let token_y_mint_account = find(&token_y_mint)?;
+        let y_token_program = find(token_y_mint_account.owner)?;
+        if !token::is_token_program(y_token_program.key) {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+
+        let rent = Rent::get()?;
+        log!("Calling the system program to create the raffle account...");
+        invoke_signed(
+            &system_instruction::create_account(
+                initializer.key,
+                raffle_account.key,
+                rent.minimum_balance(Raffle::LEN),
+                Raffle::LEN as u64,
+                program_id,
+            ),
+            &[initializer.clone(), raffle_account.clone(), system_program_account.clone()],
+            &[&[&b"raffle"[..], escrow_account.key.as_ref(), &[bump_seed]]],
+        )?;
+
+        log!("Calling the system program to create the raffle's vault...");
+        invoke_signed(
+            &system_instruction::create_account(
+                initializer.key,
+                vault.key,
+                rent.minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                y_token_program.key,
+            ),
+            &[initializer.clone(), vault.clone(), system_program_account.clone()],
+            &[&[&b"raffle_vault"[..], raffle_account.key.as_ref(), &[vault_bump_seed]]],
+        )?;
+
+        let init_vault_ix = token::for_token_program(
+            spl_token::instruction::initialize_account3(
+                &spl_token::id(),
+                vault.key,
+                token_y_mint_account.key,
+                raffle_account.key,
+            ),
+            y_token_program.key,
+        )?;
+        log!("Calling the token program to initialize the raffle's vault...");
+        invoke(&init_vault_ix, &[vault.clone(), token_y_mint_account.clone(), y_token_program.clone()])?;
+
+        Raffle::pack(
+            Raffle {
+                is_initialized: true,
+                bump_seed,
+                escrow: *escrow_account.key,
+                initializer: *initializer.key,
+                proceeds_token_account,
+                token_y_mint,
+                ticket_price,
+                max_tickets,
+                tickets_sold: 0,
+                end_unix_timestamp,
+                draw_slot: 0,
+                vrf: *vrf,
+                vrf_counter: 0,
+                randomness: [0; 32],
+            },
+            &mut raffle_account.try_borrow_mut_data()?,
+        )
+    }
+
+    // Ticket accounts are numbered by their first ticket, so a purchase never needs more than the one account it creates, however many tickets were sold before it
+    fn process_buy_tickets(accounts: &[AccountInfo], count: u32, program_id: &Pubkey) -> ProgramResult {
+        if count == 0 {
+            return Err(EscrowError::InvalidInstruction.into());
+        }
+
+        let account_info_iter = &mut accounts.iter();
+        let buyer = next_account_info(account_info_iter)?;
+
+        if !buyer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let buyers_token_account = next_account_info(account_info_iter)?;
+        let escrow_account = next_account_info(account_info_iter)?;
+        if escrow_account.owner != program_id || escrow_account.data_len() != Escrow::LEN {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+        let mut escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+
+        let raffle_account = next_account_info(account_info_iter)?;
+        let mut raffle = Self::unpack_raffle(raffle_account, program_id)?;
+        if raffle.escrow != *escrow_account.key {
+            return Err(EscrowError::InvalidRaffle.into());
+        }
+        let now = Clock::get()?.unix_timestamp;
+        if now >= raffle.end_unix_timestamp {
+            return Err(EscrowError::RaffleEnded.into());
+        }
+        if count > raffle.max_tickets.saturating_sub(raffle.tickets_sold) {
+            return Err(EscrowError::RaffleSoldOut.into());
+        }
+        if escrow_info.is_expired(now) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+
+        let ticket_account = next_account_info(account_info_iter)?;
+        let (ticket_address, bump_seed) = find_ticket_address(raffle_account.key, raffle.tickets_sold, program_id);
+        if *ticket_account.key != ticket_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let vault = next_account_info(account_info_iter)?;
+        if *vault.key != find_raffle_vault_address(raffle_account.key, program_id).0 {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+        if *token_y_mint_account.key != raffle.token_y_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_y_mint_info = token::unpack_mint(token_y_mint_account)?;
+        let y_token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(y_token_program.key) || token_y_mint_account.owner != y_token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let system_program_account = next_account_info(account_info_iter)?;
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        if config.gate_program != Pubkey::default() {
+            gate::check_taker(&config.gate_program, buyer, escrow_account, accounts)?;
+        }
+
+        let price = raffle.ticket_price.checked_mul(count as u64).ok_or(EscrowError::AmountOverflow)?;
+        let transfer_price_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                buyers_token_account.key,
+                token_y_mint_account.key,
+                vault.key,
+                buyer.key,
+                &[buyer.key],
+                price,
+                token_y_mint_info.decimals,
+            ),
+            y_token_program.key,
+        )?;
+        log!("Calling the token program to transfer the tickets' price...");
+        invoke(
+            &transfer_price_ix,
+            &[
+                buyers_token_account.clone(),
+                token_y_mint_account.clone(),
+                vault.clone(),
+                buyer.clone(),
+                y_token_program.clone(),
+            ],
+        )?;
+
+        let first_ticket = raffle.tickets_sold;
+        log!("Calling the system program to create the ticket account...");
+        invoke_signed(
+            &system_instruction::create_account(
+                buyer.key,
+                ticket_account.key,
+                Rent::get()?.minimum_balance(Ticket::LEN),
+                Ticket::LEN as u64,
+                program_id,
+            ),
+            &[buyer.clone(), ticket_account.clone(), system_program_account.clone()],
+            &[&[&b"ticket"[..], raffle_account.key.as_ref(), &first_ticket.to_le_bytes(), &[bump_seed]]],
+        )?;
+        Ticket::pack(
+            Ticket {
+                is_initialized: true,
+                bump_seed,
+                raffle: *raffle_account.key,
+                buyer: *buyer.key,
+                first_ticket,
+                count,
+            },
+            &mut ticket_account.try_borrow_mut_data()?,
+        )?;
+
+        raffle.tickets_sold = raffle.tickets_sold.checked_add(count).ok_or(EscrowError::AmountOverflow)?;
+        Raffle::pack(raffle, &mut raffle_account.try_borrow_mut_data()?)?;
+        escrow_info.highest_bid_amount =
+            escrow_info.highest_bid_amount.checked_add(price).ok_or(EscrowError::AmountOverflow)?;
+        Escrow::pack(escrow_info, &mut escrow_account.try_borrow_mut_data()?)?;
+
+        EscrowEvent::TicketsBought {
+            escrow: *escrow_account.key,
+            buyer: *buyer.key,
+            first_ticket,
+            count,
+        }
+        .emit()
+    }
+
+    // Nobody, including whoever draws, knows the draw slot's hash yet, and the winner only depends on it and the raffle's address
+    fn process_draw_raffle(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let raffle_account = next_account_info(account_info_iter)?;
+        let mut raffle = Self::unpack_raffle(raffle_account, program_id)?;
+        if raffle.uses_vrf() {
+            return Err(EscrowError::WrongRandomnessProvider.into());
+        }
+
+        let clock = Clock::get()?;
+        if raffle.tickets_sold == 0 {
+            return Err(EscrowError::NoBids.into());
+        }
+        if !raffle.has_ended(clock.unix_timestamp) {
+            return Err(EscrowError::RaffleNotEnded.into());
+        }
+        if raffle.is_drawn() && !raffle.is_draw_stale(clock.slot) {
+            return Err(EscrowError::RaffleAlreadyDrawn.into());
+        }
+
+        raffle.draw_slot = clock.slot.saturating_add(raffle::DRAW_DELAY_SLOTS);
+        Raffle::pack(raffle, &mut raffle_account.try_borrow_mut_data()?)?;
+
+        EscrowEvent::RaffleDrawn { escrow: raffle.escrow, draw_slot: raffle.draw_slot }.emit()
+    }
+
+    // The winner doesn't sign, so like SettleAuction this checks that the escrowed tokens really go to them. SlotHashes is read from the account, since the sysvar can't be fetched with a syscall.
+    fn process_settle_raffle(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let raffle_account = next_account_info(account_info_iter)?;
+        let raffle = Self::unpack_raffle(raffle_account, program_id)?;
+        if !raffle.is_drawn() {
+            return Err(EscrowError::RaffleNotDrawn.into());
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        if *escrow_account.key != raffle.escrow
+            || escrow_account.owner != program_id
+            || escrow_account.data_len() != Escrow::LEN
+        {
+            return Err(EscrowError::InvalidEscrowAccount.into());
+        }
+        let escrow_info = Escrow::unpack(&escrow_account.try_borrow_data()?)?;
+        if !escrow_info.is_release_approved() {
+            return Err(EscrowError::ReleaseNotApproved.into());
+        }
+
+        let temp_token_account = next_account_info(account_info_iter)?;
+        if escrow_info.temp_token_account_pubkey != *temp_token_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let temp_token_account_info = token::unpack_token_account(temp_token_account)?;
+        let pda_account = next_account_info(account_info_iter)?;
+        let pda = Pubkey::create_program_address(
+            &[b"escrow", escrow_account.key.as_ref(), &[escrow_info.bump_seed]],
+            program_id,
+        )?;
+        if *pda_account.key != pda {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let vault = next_account_info(account_info_iter)?;
+        if *vault.key != find_raffle_vault_address(raffle_account.key, program_id).0 {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let proceeds_token_account = next_account_info(account_info_iter)?;
+        if *proceeds_token_account.key != raffle.proceeds_token_account {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let initializer = next_account_info(account_info_iter)?;
+        if *initializer.key != escrow_info.initializer_pubkey {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let ticket_account = next_account_info(account_info_iter)?;
+        let ticket = Self::unpack_ticket(ticket_account, program_id)?;
+        let winner = next_account_info(account_info_iter)?;
+        if ticket.raffle != *raffle_account.key || *winner.key != ticket.buyer {
+            return Err(EscrowError::InvalidTicket.into());
+        }
+        let winners_token_account = next_account_info(account_info_iter)?;
+        let winners_token_account_info = token::unpack_token_account(winners_token_account)?;
+        if winners_token_account_info.owner != ticket.buyer
+            || winners_token_account_info.mint != escrow_info.token_x_mint
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let slot_hashes = next_account_info(account_info_iter)?;
+        if *slot_hashes.key != sysvar::slot_hashes::id() {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let draw_hash = if raffle.uses_vrf() {
+            raffle.randomness()
+        } else {
+            raffle::draw_hash(&slot_hashes.try_borrow_data()?, raffle.draw_slot)
+        }
+        .ok_or(EscrowError::RandomnessUnavailable)?;
+        let winning_ticket = raffle::winning_ticket(&draw_hash, raffle_account.key, raffle.tickets_sold);
+        if !ticket.contains(winning_ticket) {
+            return Err(EscrowError::NotTheWinningTicket.into());
+        }
+
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || temp_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let y_token_program = next_account_info(account_info_iter)?;
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        if *token_x_mint_account.key != escrow_info.token_x_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_x_mint_info = token::unpack_mint(token_x_mint_account)?;
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+
+        // The vault is emptied first. settle_matched_escrow moves the escrow's lamports to the initializer directly, and any CPI after that which leaves the escrow account out fails as unbalanced.
+        let raffle_seeds: &[&[u8]] = &[&b"raffle"[..], raffle.escrow.as_ref(), &[raffle.bump_seed]];
+        let amount_y = Self::empty_vault(
+            vault,
+            proceeds_token_account,
+            token_y_mint_account,
+            y_token_program,
+            initializer,
+            raffle_account,
+            &raffle.token_y_mint,
+            raffle_seeds,
+        )?;
+
+        let amount_x = escrow_info.escrowed_amount(&temp_token_account_info);
+        Self::settle_matched_escrow(
+            &escrow_info,
+            escrow_account,
+            temp_token_account,
+            initializer,
+            pda_account,
+            winners_token_account,
+            token_program,
+            token_x_mint_account,
+            amount_x,
+            token_x_mint_info.decimals,
+        )?;
+        log!("Closing the raffle and the winning ticket account...");
+        Self::close_program_account(raffle_account, initializer)?;
+        Self::close_program_account(ticket_account, winner)?;
+
+        EscrowEvent::RaffleSettled {
+            escrow: *escrow_account.key,
+            winner: ticket.buyer,
+            winning_ticket,
+            tickets_sold: raffle.tickets_sold,
+            draw_hash,
+        }
+        .emit()?;
+        EscrowEvent::EscrowExchanged {
+            escrow: *escrow_account.key,
+            initializer: escrow_info.initializer_pubkey,
+            taker: ticket.buyer,
+            amount_x,
+            amount_y,
+        }
+        .emit()
+    }
+
+    // Only an escrow without tickets sold can be closed other than by SettleRaffle, which closes the raffle itself, so the vault is normally empty by now
+    fn process_close_raffle(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let escrow_account = next_account_info(account_info_iter)?;
+        let raffle_account = next_account_info(account_info_iter)?;
+        let raffle = Self::unpack_raffle(raffle_account, program_id)?;
+        if raffle.initializer != *initializer.key || raffle.escrow != *escrow_account.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if escrow_account.owner == program_id && escrow_account.data_len() == Escrow::LEN {
+            return Err(EscrowError::RaffleNotEnded.into());
+        }
+
+        let vault = next_account_info(account_info_iter)?;
+        if *vault.key != find_raffle_vault_address(raffle_account.key, program_id).0 {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let proceeds_token_account = next_account_info(account_info_iter)?;
+        if *proceeds_token_account.key != raffle.proceeds_token_account {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+        let y_token_program = next_account_info(account_info_iter)?;
+
+        let raffle_seeds: &[&[u8]] = &[&b"raffle"[..], raffle.escrow.as_ref(), &[raffle.bump_seed]];
+        Self::empty_vault(
+            vault,
+            proceeds_token_account,
+            token_y_mint_account,
+            y_token_program,
+            initializer,
+            raffle_account,
+            &raffle.token_y_mint,
+            raffle_seeds,
+        )?;
+        log!("Closing the raffle account...");
+        Self::close_program_account(raffle_account, initializer)
+    }
+
+    // Which result picks the winner is fixed here, before it exists: the raffle only ever consumes the result of the request numbered with the counter stored now
+    fn process_request_raffle_randomness(
+        accounts: &[AccountInfo],
+        permission_bump: u8,
+        state_bump: u8,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let raffle_account = next_account_info(account_info_iter)?;
+        let mut raffle = Self::unpack_raffle(raffle_account, program_id)?;
+        let vrf_account = next_account_info(account_info_iter)?;
+        if !raffle.uses_vrf() {
+            return Err(EscrowError::WrongRandomnessProvider.into());
+        }
+        if *vrf_account.key != raffle.vrf || switchboard::unpack_vrf(vrf_account)?.authority != *raffle_account.key {
+            return Err(EscrowError::InvalidVrfAccount.into());
+        }
+
+        let clock = Clock::get()?;
+        if raffle.tickets_sold == 0 {
+            return Err(EscrowError::NoBids.into());
+        }
+        if !raffle.has_ended(clock.unix_timestamp) {
+            return Err(EscrowError::RaffleNotEnded.into());
+        }
+        if raffle.is_drawn() && !raffle.is_draw_stale(clock.slot) {
+            return Err(EscrowError::RaffleAlreadyDrawn.into());
+        }
+
+        let oracle_queue = next_account_info(account_info_iter)?;
+        let queue_authority = next_account_info(account_info_iter)?;
+        let data_buffer = next_account_info(account_info_iter)?;
+        let permission = next_account_info(account_info_iter)?;
+        let vrf_escrow = next_account_info(account_info_iter)?;
+        let payer_wallet = next_account_info(account_info_iter)?;
+        let payer = next_account_info(account_info_iter)?;
+        let recent_blockhashes = next_account_info(account_info_iter)?;
+        let program_state = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let switchboard_program = next_account_info(account_info_iter)?;
+        if *switchboard_program.key != switchboard::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let request_randomness_ix = switchboard::request_randomness(
+            raffle_account.key,
+            vrf_account.key,
+            oracle_queue.key,
+            queue_authority.key,
+            data_buffer.key,
+            permission.key,
+            vrf_escrow.key,
+            payer_wallet.key,
+            payer.key,
+            program_state.key,
+            permission_bump,
+            state_bump,
+        );
+        log!("Calling Switchboard to request randomness...");
+        invoke_signed(
+            &request_randomness_ix,
+            &[
+                raffle_account.clone(),
+                vrf_account.clone(),
+                oracle_queue.clone(),
+                queue_authority.clone(),
+                data_buffer.clone(),
+                permission.clone(),
+                vrf_escrow.clone(),
+                payer_wallet.clone(),
+                payer.clone(),
+                recent_blockhashes.clone(),
+                program_state.clone(),
+                token_program.clone(),
+                switchboard_program.clone(),
+            ],
+            &[&[&b"raffle"[..], raffle.escrow.as_ref(), &[raffle.bump_seed]]],
+        )?;
+
+        raffle.vrf_counter = switchboard::unpack_vrf(vrf_account)?.counter;
+        raffle.draw_slot = clock.slot;
+        Raffle::pack(raffle, &mut raffle_account.try_borrow_mut_data()?)?;
+
+        EscrowEvent::RandomnessRequested { escrow: raffle.escrow, vrf: raffle.vrf, counter: raffle.vrf_counter }.emit()
+    }
+
+    // Switchboard calls this once the proof is verified, but nothing depends on who sends it, since the result is the same either way
+    fn process_consume_raffle_randomness(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let raffle_account = next_account_info(account_info_iter)?;
+        let mut raffle = Self::unpack_raffle(raffle_account, program_id)?;
+        let vrf_account = next_account_info(account_info_iter)?;
+        if !raffle.uses_vrf() || *vrf_account.key != raffle.vrf {
+            return Err(EscrowError::InvalidVrfAccount.into());
+        }
+        if !raffle.is_drawn() {
+            return Err(EscrowError::RaffleNotDrawn.into());
+        }
+        if raffle.randomness().is_some() {
+            return Err(EscrowError::RaffleAlreadyDrawn.into());
+        }
+
+        let vrf = switchboard::unpack_vrf(vrf_account)?;
+        raffle.randomness = match vrf.result {
+            Some(result) if vrf.counter == raffle.vrf_counter => result,
+            _ => return Err(EscrowError::RandomnessUnavailable.into()),
+        };
+        Raffle::pack(raffle, &mut raffle_account.try_borrow_mut_data()?)?;
+
+        EscrowEvent::RandomnessFulfilled { escrow: raffle.escrow, randomness: raffle.randomness }.emit()
+    }
+
+    // Losing tickets stay around until the raffle is gone, so the winning one can't be closed before it's settled
+    fn process_close_ticket(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let buyer = next_account_info(account_info_iter)?;
+
+        if !buyer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let ticket_account = next_account_info(account_info_iter)?;
+        let ticket = Self::unpack_ticket(ticket_account, program_id)?;
+        let raffle_account = next_account_info(account_info_iter)?;
+        if ticket.buyer != *buyer.key || ticket.raffle != *raffle_account.key {
+            return Err(EscrowError::InvalidTicket.into());
+        }
+        if raffle_account.owner == program_id && raffle_account.data_len() == Raffle::LEN {
+            return Err(EscrowError::RaffleNotEnded.into());
+        }
+
+        log!("Closing the ticket account...");
+        Self::close_program_account(ticket_account, buyer)
+    }
+
+    // The pool account is created by the maker like escrow accounts used to be, since a pool of any real size is larger than an account created through a CPI can be. Its entries are left as they are, see escrow_pool.
+    fn process_init_escrow_pool(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker = next_account_info(account_info_iter)?;
+
+        if !maker.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let pool_account = next_account_info(account_info_iter)?;
+        if pool_account.owner != program_id {
+            return Err(EscrowError::InvalidEscrowPool.into());
+        }
+        if !Rent::get()?.is_exempt(pool_account.lamports(), pool_account.data_len()) {
+            return Err(EscrowError::NotRentExempt.into());
+        }
+        let capacity = EscrowPool::capacity_for(pool_account.data_len());
+        if capacity == 0 {
+            return Err(EscrowError::InvalidEscrowPool.into());
+        }
+        if EscrowPool::unpack_header(&pool_account.try_borrow_data()?)?.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        let vault = next_account_info(account_info_iter)?;
+        let (vault_address, vault_bump_seed) = find_escrow_pool_vault_address(pool_account.key, program_id);
+        if *vault.key != vault_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let makers_token_to_receive_account = next_account_info(account_info_iter)?;
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+        if token::unpack_token_account(makers_token_to_receive_account)?.mint != *token_y_mint_account.key {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || token_x_mint_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let system_program_account = next_account_info(account_info_iter)?;
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        if config.allowlist_enabled {
+            let allowlist = Self::unpack_allowlist(next_account_info(account_info_iter)?, program_id)?;
+            if !allowlist.contains(token_x_mint_account.key) || !allowlist.contains(token_y_mint_account.key) {
+                return Err(EscrowError::MintNotAllowed.into());
+            }
+        }
+
+        let (authority, authority_bump_seed) = find_escrow_pool_authority_address(pool_account.key, program_id);
+        log!("Calling the system program to create the pool's vault...");
+        invoke_signed(
+            &system_instruction::create_account(
+                maker.key,
+                vault.key,
+                Rent::get()?.minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                token_program.key,
+            ),
+            &[maker.clone(), vault.clone(), system_program_account.clone()],
+            &[&[&b"escrow_pool_vault"[..], pool_account.key.as_ref(), &[vault_bump_seed]]],
+        )?;
+
+        let init_vault_ix = token::for_token_program(
+            spl_token::instruction::initialize_account3(
+                &spl_token::id(),
+                vault.key,
+                token_x_mint_account.key,
+                &authority,
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to initialize the pool's vault...");
+        invoke(&init_vault_ix, &[vault.clone(), token_x_mint_account.clone(), token_program.clone()])?;
+
+        EscrowPool {
+            is_initialized: true,
+            authority_bump_seed,
+            maker: *maker.key,
+            maker_token_to_receive_account: *makers_token_to_receive_account.key,
+            token_x_mint: *token_x_mint_account.key,
+            token_y_mint: *token_y_mint_account.key,
+            capacity,
+            open_entries: 0,
+            next_unused_entry: 0,
+            free_list_head: escrow_pool::NO_ENTRY,
+        }
+        .pack_header(&mut pool_account.try_borrow_mut_data()?);
+        Ok(())
+    }
+
+    // Posting only writes the header and one entry, so it costs the same however large the pool is
+    fn process_post_pool_entry(
+        accounts: &[AccountInfo],
+        amount_x: u64,
+        amount_y: u64,
+        expiry_unix_timestamp: i64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        if amount_x == 0 || amount_y == 0 {
+            return Err(EscrowError::InvalidInstruction.into());
+        }
+
+        let account_info_iter = &mut accounts.iter();
+        let maker = next_account_info(account_info_iter)?;
+
+        if !maker.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let makers_token_account = next_account_info(account_info_iter)?;
+        let pool_account = next_account_info(account_info_iter)?;
+        let mut pool = Self::unpack_escrow_pool(pool_account, program_id)?;
+        if pool.maker != *maker.key {
+            return Err(EscrowError::InvalidEscrowPool.into());
+        }
+        let vault = next_account_info(account_info_iter)?;
+        if *vault.key != find_escrow_pool_vault_address(pool_account.key, program_id).0 {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        if *token_x_mint_account.key != pool.token_x_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_x_mint_info = token::unpack_mint(token_x_mint_account)?;
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || vault.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        let expiry_unix_timestamp =
+            Self::expiry_with_default(&config, expiry_unix_timestamp, Clock::get()?.unix_timestamp)?;
+
+        let transfer_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                makers_token_account.key,
+                token_x_mint_account.key,
+                vault.key,
+                maker.key,
+                &[maker.key],
+                amount_x,
+                token_x_mint_info.decimals,
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to transfer token X into the pool's vault...");
+        invoke(
+            &transfer_ix,
+            &[
+                makers_token_account.clone(),
+                token_x_mint_account.clone(),
+                vault.clone(),
+                maker.clone(),
+                token_program.clone(),
+            ],
+        )?;
+
+        let mut pool_data = pool_account.try_borrow_mut_data()?;
+        let index = pool.allocate_entry(&pool_data)?.ok_or(EscrowError::EscrowPoolFull)?;
+        let entry = PoolEntry { is_open: true, next_free: 0, amount_x, amount_y, expiry_unix_timestamp };
+        pool.store_entry(&mut pool_data, index, &entry);
+        pool.pack_header(&mut pool_data);
+        set_return_data(&index.to_le_bytes());
+
+        EscrowEvent::PoolEntryPosted {
+            pool: *pool_account.key,
+            index,
+            amount_x,
+            amount_y,
+            expiry_unix_timestamp,
+        }
+        .emit()
+    }
+
+    // A pared-down Exchange: an entry is always taken whole, for a fixed price, and only the protocol fee is charged on top
+    fn process_take_pool_entry(
+        accounts: &[AccountInfo],
+        index: u32,
+        amount_x: u64,
+        max_payment: u64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker = next_account_info(account_info_iter)?;
+
+        if !taker.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let takers_sending_token_account = next_account_info(account_info_iter)?;
+        let takers_token_to_receive_account = next_account_info(account_info_iter)?;
+        let pool_account = next_account_info(account_info_iter)?;
+        let mut pool = Self::unpack_escrow_pool(pool_account, program_id)?;
+        let entry = pool.load_entry(&pool_account.try_borrow_data()?, index)?;
+        if !entry.is_open {
+            return Err(EscrowError::InvalidPoolEntry.into());
+        }
+        if entry.amount_x != amount_x {
+            return Err(EscrowError::PoolEntryChanged.into());
+        }
+        if entry.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+
+        let vault = next_account_info(account_info_iter)?;
+        if *vault.key != find_escrow_pool_vault_address(pool_account.key, program_id).0 {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let authority = next_account_info(account_info_iter)?;
+        let makers_token_to_receive_account = next_account_info(account_info_iter)?;
+        if *makers_token_to_receive_account.key != pool.maker_token_to_receive_account {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+        if *token_x_mint_account.key != pool.token_x_mint || *token_y_mint_account.key != pool.token_y_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_x_mint_info = token::unpack_mint(token_x_mint_account)?;
+        let token_y_mint_info = token::unpack_mint(token_y_mint_account)?;
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || vault.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let y_token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(y_token_program.key) || token_y_mint_account.owner != y_token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        if config.gate_program != Pubkey::default() {
+            gate::check_taker(&config.gate_program, taker, pool_account, accounts)?;
+        }
+
+        let fee = protocol_fee(entry.amount_y, config.fee_bps).ok_or(EscrowError::FeeOverflow)?;
+        if entry.amount_y.checked_add(fee).ok_or(EscrowError::AmountOverflow)? > max_payment {
+            return Err(EscrowError::MaxPaymentExceeded.into());
+        }
+        if fee > 0 {
+            let fee_vault = next_account_info(account_info_iter)?;
+            let fee_vault_info = token::unpack_token_account(fee_vault)?;
+            let fee_collector = if config.fee_collector != Pubkey::default() {
+                config.fee_collector
+            } else {
+                find_fee_authority_address(program_id).0
+            };
+            if fee_vault_info.owner != fee_collector || fee_vault_info.mint != pool.token_y_mint {
+                return Err(EscrowError::InvalidFeeVault.into());
+            }
+            log!("Calling the token program to transfer the protocol fee to the fee vault...");
+            Self::transfer_fee(
+                taker,
+                takers_sending_token_account,
+                token_y_mint_account,
+                fee_vault,
+                y_token_program,
+                fee,
+                token_y_mint_info.decimals,
+                accounts,
+            )?;
+
+            EscrowEvent::ProtocolFeeCollected { escrow: *pool_account.key, mint: pool.token_y_mint, amount: fee }
+                .emit()?;
+        }
+
+        log!("Calling the token program to transfer the payment to the maker...");
+        Self::transfer_fee(
+            taker,
+            takers_sending_token_account,
+            token_y_mint_account,
+            makers_token_to_receive_account,
+            y_token_program,
+            entry.amount_y,
+            token_y_mint_info.decimals,
+            accounts,
+        )?;
+        Self::transfer_from_pool_vault(
+            pool_account,
+            &pool,
+            vault,
+            authority,
+            takers_token_to_receive_account,
+            token_x_mint_account,
+            token_program,
+            entry.amount_x,
+            token_x_mint_info.decimals,
+        )?;
+
+        pool.free_entry(&mut pool_account.try_borrow_mut_data()?, index);
+        pool.pack_header(&mut pool_account.try_borrow_mut_data()?);
+
+        EscrowEvent::PoolEntryTaken {
+            pool: *pool_account.key,
+            index,
+            taker: *taker.key,
+            amount_x: entry.amount_x,
+            amount_y: entry.amount_y,
+        }
+        .emit()
+    }
+
+    fn process_cancel_pool_entry(accounts: &[AccountInfo], index: u32, program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker = next_account_info(account_info_iter)?;
+
+        if !maker.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let makers_token_account = next_account_info(account_info_iter)?;
+        let pool_account = next_account_info(account_info_iter)?;
+        let mut pool = Self::unpack_escrow_pool(pool_account, program_id)?;
+        if pool.maker != *maker.key {
+            return Err(EscrowError::InvalidEscrowPool.into());
+        }
+        let entry = pool.load_entry(&pool_account.try_borrow_data()?, index)?;
+        if !entry.is_open {
+            return Err(EscrowError::InvalidPoolEntry.into());
+        }
+
+        let vault = next_account_info(account_info_iter)?;
+        if *vault.key != find_escrow_pool_vault_address(pool_account.key, program_id).0 {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let authority = next_account_info(account_info_iter)?;
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        if *token_x_mint_account.key != pool.token_x_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_x_mint_info = token::unpack_mint(token_x_mint_account)?;
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || vault.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+
+        Self::transfer_from_pool_vault(
+            pool_account,
+            &pool,
+            vault,
+            authority,
+            makers_token_account,
+            token_x_mint_account,
+            token_program,
+            entry.amount_x,
+            token_x_mint_info.decimals,
+        )?;
+
+        pool.free_entry(&mut pool_account.try_borrow_mut_data()?, index);
+        pool.pack_header(&mut pool_account.try_borrow_mut_data()?);
+
+        EscrowEvent::PoolEntryCancelled { pool: *pool_account.key, index }.emit()
+    }
+
+    fn process_close_escrow_pool(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let maker = next_account_info(account_info_iter)?;
+
+        if !maker.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let makers_token_account = next_account_info(account_info_iter)?;
+        let pool_account = next_account_info(account_info_iter)?;
+        let pool = Self::unpack_escrow_pool(pool_account, program_id)?;
+        if pool.maker != *maker.key {
+            return Err(EscrowError::InvalidEscrowPool.into());
+        }
+        if pool.open_entries != 0 {
+            return Err(EscrowError::EscrowPoolNotEmpty.into());
+        }
+
+        let vault = next_account_info(account_info_iter)?;
+        if *vault.key != find_escrow_pool_vault_address(pool_account.key, program_id).0 {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let authority = next_account_info(account_info_iter)?;
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        let authority_seeds: &[&[u8]] = &[&b"escrow_pool"[..], pool_account.key.as_ref(), &[pool.authority_bump_seed]];
+        Self::empty_vault(
+            vault,
+            makers_token_account,
+            token_x_mint_account,
+            token_program,
+            maker,
+            authority,
+            &pool.token_x_mint,
+            authority_seeds,
+        )?;
+        log!("Closing the pool account...");
+        Self::close_program_account(pool_account, maker)
+    }
+
+    // Moves one entry's token X out of a pool's vault, signed by the pool's authority
+    #[allow(clippy::too_many_arguments)]
+    fn transfer_from_pool_vault<'a>(
+        pool_account: &AccountInfo<'a>,
+        pool: &EscrowPool,
+        vault: &AccountInfo<'a>,
+        authority: &AccountInfo<'a>,
+        destination: &AccountInfo<'a>,
+        token_x_mint_account: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+        amount: u64,
+        decimals: u8,
+    ) -> ProgramResult {
+        let transfer_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                vault.key,
+                token_x_mint_account.key,
+                destination.key,
+                authority.key,
+                &[authority.key],
+                amount,
+                decimals,
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to transfer token X out of the pool's vault...");
+        invoke_signed(
+            &transfer_ix,
+            &[
+                vault.clone(),
+                token_x_mint_account.clone(),
+                destination.clone(),
+                authority.clone(),
                 token_program.clone(),
             ],
-            &[&[&b"escrow"[..], &[nonce]]],
+            &[&[&b"escrow_pool"[..], pool_account.key.as_ref(), &[pool.authority_bump_seed]]],
+        )
+    }
+
+    // Checks that the account is an initialized escrow pool of this program. Pools aren't PDAs, so there is no address to check.
+    fn unpack_escrow_pool(pool_account: &AccountInfo, program_id: &Pubkey) -> Result<EscrowPool, ProgramError> {
+        if pool_account.owner != program_id || pool_account.data_len() < EscrowPool::space(1) {
+            return Err(EscrowError::InvalidEscrowPool.into());
+        }
+        let pool = EscrowPool::unpack_header(&pool_account.try_borrow_data()?)?;
+        if !pool.is_initialized() || pool.capacity != EscrowPool::capacity_for(pool_account.data_len()) {
+            return Err(EscrowError::InvalidEscrowPool.into());
+        }
+        Ok(pool)
+    }
+
+    fn process_init_compressed_tree(
+        accounts: &[AccountInfo],
+        max_depth: u32,
+        max_buffer_size: u32,
+        canopy_depth: u32,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        if max_depth > compressed_escrow::MAX_DEPTH || canopy_depth > max_depth {
+            return Err(EscrowError::InvalidInstruction.into());
+        }
+
+        let account_info_iter = &mut accounts.iter();
+        let creator = next_account_info(account_info_iter)?;
+
+        if !creator.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let merkle_tree = next_account_info(account_info_iter)?;
+        if *merkle_tree.owner != compression::id() {
+            return Err(EscrowError::InvalidCompressedTree.into());
+        }
+        let tree_config_account = next_account_info(account_info_iter)?;
+        let (tree_config_address, bump_seed) = find_compressed_tree_address(merkle_tree.key, program_id);
+        if *tree_config_account.key != tree_config_address {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let compression_program = next_account_info(account_info_iter)?;
+        let noop_program = next_account_info(account_info_iter)?;
+        if *compression_program.key != compression::id() || *noop_program.key != compression::noop::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let system_program_account = next_account_info(account_info_iter)?;
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        // create_account fails if the config already exists, so a tree can't be set up twice
+        let tree_config_seeds: &[&[u8]] = &[b"compressed_tree", merkle_tree.key.as_ref(), &[bump_seed]];
+        log!("Calling the system program to create the tree's config...");
+        invoke_signed(
+            &system_instruction::create_account(
+                creator.key,
+                tree_config_account.key,
+                Rent::get()?.minimum_balance(CompressedTree::LEN),
+                CompressedTree::LEN as u64,
+                program_id,
+            ),
+            &[creator.clone(), tree_config_account.clone(), system_program_account.clone()],
+            &[tree_config_seeds],
         )?;
 
-        msg!("Closing the escrow account...");
-        // Must clear the data for security purposes, even if the intention is to have the account purged after the transaction.
-        //
-        // Specifically:
-        // In any call to a program that is of the "close" kind, i.e. where you set an account's lamports to zero so it's removed from memory after the transaction, make sure to either clear the data field or leave the data in a state that would be OK to be recovered by a subsequent transaction.
-        **initializers_main_account.try_borrow_mut_lamports()? = initializers_main_account
-            .lamports()
-            .checked_add(escrow_account.lamports())
-            .ok_or(EscrowError::AmountOverflow)?;
-        **escrow_account.try_borrow_mut_lamports()? = 0;
-        *escrow_account.try_borrow_mut_data()? = &mut [];
+        log!("Calling the compression program to initialize the merkle tree...");
+        invoke_signed(
+            &compression::init_empty_merkle_tree(merkle_tree.key, tree_config_account.key, max_depth, max_buffer_size),
+            &[merkle_tree.clone(), tree_config_account.clone(), noop_program.clone(), compression_program.clone()],
+            &[tree_config_seeds],
+        )?;
 
+        CompressedTree::pack(
+            CompressedTree {
+                is_initialized: true,
+                bump_seed,
+                merkle_tree: *merkle_tree.key,
+                creator: *creator.key,
+                max_depth,
+                proof_len: max_depth - canopy_depth,
+                num_leaves: 0,
+            },
+            &mut tree_config_account.try_borrow_mut_data()?,
+        )?;
         Ok(())
     }
-}
\ No newline at end of file
+
+    // The escrow only exists as the leaf and the event, so everything the leaf commits to is checked here, before it becomes unchangeable
+    fn process_init_compressed_escrow(
+        accounts: &[AccountInfo],
+        amount_x: u64,
+        amount_y: u64,
+        expiry_unix_timestamp: i64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        if amount_x == 0 || amount_y == 0 {
+            return Err(EscrowError::InvalidInstruction.into());
+        }
+
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        let initializers_token_account = next_account_info(account_info_iter)?;
+        let initializers_token_account_info = token::unpack_token_account(initializers_token_account)?;
+        if initializers_token_account_info.owner != *initializer.key {
+            return Err(ProgramError::IllegalOwner);
+        }
+        if initializers_token_account_info.amount < amount_x {
+            return Err(ProgramError::InsufficientFunds);
+        }
+        let initializers_token_to_receive_account = next_account_info(account_info_iter)?;
+        let tree_config_account = next_account_info(account_info_iter)?;
+        let merkle_tree = next_account_info(account_info_iter)?;
+        let mut tree = Self::unpack_compressed_tree(tree_config_account, merkle_tree, program_id)?;
+        if tree.is_full() {
+            return Err(EscrowError::CompressedTreeFull.into());
+        }
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+        if initializers_token_account_info.mint != *token_x_mint_account.key
+            || token::unpack_token_account(initializers_token_to_receive_account)?.mint != *token_y_mint_account.key
+        {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        token::unpack_mint(token_y_mint_account)?;
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || initializers_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let compression_program = next_account_info(account_info_iter)?;
+        let noop_program = next_account_info(account_info_iter)?;
+        if *compression_program.key != compression::id() || *noop_program.key != compression::noop::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        if config.allowlist_enabled {
+            let allowlist = Self::unpack_allowlist(next_account_info(account_info_iter)?, program_id)?;
+            if !allowlist.contains(token_x_mint_account.key) || !allowlist.contains(token_y_mint_account.key) {
+                return Err(EscrowError::MintNotAllowed.into());
+            }
+        }
+        let expiry_unix_timestamp =
+            Self::expiry_with_default(&config, expiry_unix_timestamp, Clock::get()?.unix_timestamp)?;
+
+        // Every compressed escrow of the account in this tree shares one allowance, so it is raised rather than replaced. Replacing another program's or escrow's delegation would break whatever relies on it.
+        let allowance = match initializers_token_account_info.delegate {
+            COption::Some(delegate) if delegate == *tree_config_account.key => initializers_token_account_info
+                .delegated_amount
+                .checked_add(amount_x)
+                .ok_or(EscrowError::AmountOverflow)?,
+            COption::Some(_) if initializers_token_account_info.delegated_amount != 0 => {
+                return Err(EscrowError::TokenAccountAlreadyDelegated.into())
+            }
+            _ => amount_x,
+        };
+        let approve_ix = token::for_token_program(
+            spl_token::instruction::approve(
+                &spl_token::id(),
+                initializers_token_account.key,
+                tree_config_account.key,
+                initializer.key,
+                &[initializer.key],
+                allowance,
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to delegate the escrow's token X to the tree's config...");
+        invoke(
+            &approve_ix,
+            &[
+                initializers_token_account.clone(),
+                tree_config_account.clone(),
+                initializer.clone(),
+                token_program.clone(),
+            ],
+        )?;
+
+        let escrow = CompressedEscrow {
+            initializer: *initializer.key,
+            initializer_token_account: *initializers_token_account.key,
+            initializer_token_to_receive_account: *initializers_token_to_receive_account.key,
+            token_x_mint: *token_x_mint_account.key,
+            token_y_mint: *token_y_mint_account.key,
+            amount_x,
+            amount_y,
+            expiry_unix_timestamp,
+            leaf_index: u32::try_from(tree.num_leaves).map_err(|_| EscrowError::CompressedTreeFull)?,
+        };
+        log!("Calling the compression program to append the escrow's leaf...");
+        invoke_signed(
+            &compression::append(merkle_tree.key, tree_config_account.key, escrow.leaf()),
+            &[merkle_tree.clone(), tree_config_account.clone(), noop_program.clone(), compression_program.clone()],
+            &[&[&b"compressed_tree"[..], merkle_tree.key.as_ref(), &[tree.bump_seed]]],
+        )?;
+
+        tree.num_leaves = tree.num_leaves.checked_add(1).ok_or(EscrowError::CompressedTreeFull)?;
+        CompressedTree::pack(tree, &mut tree_config_account.try_borrow_mut_data()?)?;
+
+        EscrowEvent::CompressedEscrowCreated { merkle_tree: *merkle_tree.key, escrow }.emit()
+    }
+
+    fn process_exchange_compressed(
+        accounts: &[AccountInfo],
+        escrow: CompressedEscrow,
+        root: [u8; 32],
+        max_payment: u64,
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let taker = next_account_info(account_info_iter)?;
+
+        if !taker.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if escrow.is_expired(Clock::get()?.unix_timestamp) {
+            return Err(EscrowError::EscrowExpired.into());
+        }
+
+        let takers_sending_token_account = next_account_info(account_info_iter)?;
+        let takers_token_to_receive_account = next_account_info(account_info_iter)?;
+        let initializers_token_account = next_account_info(account_info_iter)?;
+        let initializers_token_to_receive_account = next_account_info(account_info_iter)?;
+        if *initializers_token_account.key != escrow.initializer_token_account
+            || *initializers_token_to_receive_account.key != escrow.initializer_token_to_receive_account
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let tree_config_account = next_account_info(account_info_iter)?;
+        let merkle_tree = next_account_info(account_info_iter)?;
+        let tree = Self::unpack_compressed_tree(tree_config_account, merkle_tree, program_id)?;
+        let token_x_mint_account = next_account_info(account_info_iter)?;
+        let token_y_mint_account = next_account_info(account_info_iter)?;
+        if *token_x_mint_account.key != escrow.token_x_mint || *token_y_mint_account.key != escrow.token_y_mint {
+            return Err(EscrowError::TokenMintMismatch.into());
+        }
+        let token_x_mint_info = token::unpack_mint(token_x_mint_account)?;
+        let token_y_mint_info = token::unpack_mint(token_y_mint_account)?;
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || initializers_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let y_token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(y_token_program.key) || token_y_mint_account.owner != y_token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        // The token program would refuse the transfer too, but not in a way that tells the taker the listing is stale
+        let initializers_token_account_info = token::unpack_token_account(initializers_token_account)?;
+        if initializers_token_account_info.delegate != COption::Some(*tree_config_account.key)
+            || initializers_token_account_info.delegated_amount < escrow.amount_x
+        {
+            return Err(EscrowError::DelegationMissing.into());
+        }
+        let compression_program = next_account_info(account_info_iter)?;
+        let noop_program = next_account_info(account_info_iter)?;
+
+        let config = Self::unpack_config(next_account_info(account_info_iter)?, program_id)?;
+        if config.paused {
+            return Err(EscrowError::ProgramPaused.into());
+        }
+        if config.gate_program != Pubkey::default() {
+            gate::check_taker(&config.gate_program, taker, merkle_tree, accounts)?;
+        }
+
+        let fee = protocol_fee(escrow.amount_y, config.fee_bps).ok_or(EscrowError::FeeOverflow)?;
+        if escrow.amount_y.checked_add(fee).ok_or(EscrowError::AmountOverflow)? > max_payment {
+            return Err(EscrowError::MaxPaymentExceeded.into());
+        }
+        if fee > 0 {
+            let fee_vault = next_account_info(account_info_iter)?;
+            let fee_vault_info = token::unpack_token_account(fee_vault)?;
+            let fee_collector = if config.fee_collector != Pubkey::default() {
+                config.fee_collector
+            } else {
+                find_fee_authority_address(program_id).0
+            };
+            if fee_vault_info.owner != fee_collector || fee_vault_info.mint != escrow.token_y_mint {
+                return Err(EscrowError::InvalidFeeVault.into());
+            }
+            log!("Calling the token program to transfer the protocol fee to the fee vault...");
+            Self::transfer_fee(
+                taker,
+                takers_sending_token_account,
+                token_y_mint_account,
+                fee_vault,
+                y_token_program,
+                fee,
+                token_y_mint_info.decimals,
+                accounts,
+            )?;
+
+            EscrowEvent::ProtocolFeeCollected { escrow: *merkle_tree.key, mint: escrow.token_y_mint, amount: fee }
+                .emit()?;
+        }
+
+        let proof = Self::next_proof_accounts(account_info_iter, &tree)?;
+        Self::clear_compressed_leaf(
+            &escrow,
+            &tree,
+            tree_config_account,
+            merkle_tree,
+            compression_program,
+            noop_program,
+            root,
+            &proof,
+        )?;
+
+        log!("Calling the token program to transfer the payment to the initializer...");
+        Self::transfer_fee(
+            taker,
+            takers_sending_token_account,
+            token_y_mint_account,
+            initializers_token_to_receive_account,
+            y_token_program,
+            escrow.amount_y,
+            token_y_mint_info.decimals,
+            accounts,
+        )?;
+
+        let transfer_ix = token::for_token_program(
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                initializers_token_account.key,
+                token_x_mint_account.key,
+                takers_token_to_receive_account.key,
+                tree_config_account.key,
+                &[tree_config_account.key],
+                escrow.amount_x,
+                token_x_mint_info.decimals,
+            ),
+            token_program.key,
+        )?;
+        log!("Calling the token program to transfer token X to the taker as the delegate...");
+        invoke_signed(
+            &transfer_ix,
+            &[
+                initializers_token_account.clone(),
+                token_x_mint_account.clone(),
+                takers_token_to_receive_account.clone(),
+                tree_config_account.clone(),
+                token_program.clone(),
+            ],
+            &[&[&b"compressed_tree"[..], merkle_tree.key.as_ref(), &[tree.bump_seed]]],
+        )?;
+
+        EscrowEvent::CompressedEscrowExchanged {
+            merkle_tree: *merkle_tree.key,
+            leaf_index: escrow.leaf_index,
+            taker: *taker.key,
+        }
+        .emit()
+    }
+
+    fn process_cancel_compressed(
+        accounts: &[AccountInfo],
+        escrow: CompressedEscrow,
+        root: [u8; 32],
+        program_id: &Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let initializer = next_account_info(account_info_iter)?;
+
+        if !initializer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if *initializer.key != escrow.initializer {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let initializers_token_account = next_account_info(account_info_iter)?;
+        if *initializers_token_account.key != escrow.initializer_token_account {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let tree_config_account = next_account_info(account_info_iter)?;
+        let merkle_tree = next_account_info(account_info_iter)?;
+        let tree = Self::unpack_compressed_tree(tree_config_account, merkle_tree, program_id)?;
+        let token_program = next_account_info(account_info_iter)?;
+        if !token::is_token_program(token_program.key) || initializers_token_account.owner != token_program.key {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        let compression_program = next_account_info(account_info_iter)?;
+        let noop_program = next_account_info(account_info_iter)?;
+
+        let proof = Self::next_proof_accounts(account_info_iter, &tree)?;
+        Self::clear_compressed_leaf(
+            &escrow,
+            &tree,
+            tree_config_account,
+            merkle_tree,
+            compression_program,
+            noop_program,
+            root,
+            &proof,
+        )?;
+
+        // The allowance may have been lowered or moved to someone else since, in which case there is nothing to give back
+        let initializers_token_account_info = token::unpack_token_account(initializers_token_account)?;
+        if initializers_token_account_info.delegate == COption::Some(*tree_config_account.key) {
+            let approve_ix = token::for_token_program(
+                spl_token::instruction::approve(
+                    &spl_token::id(),
+                    initializers_token_account.key,
+                    tree_config_account.key,
+                    initializer.key,
+                    &[initializer.key],
+                    initializers_token_account_info.delegated_amount.saturating_sub(escrow.amount_x),
+                ),
+                token_program.key,
+            )?;
+            log!("Calling the token program to lower the tree config's allowance...");
+            invoke(
+                &approve_ix,
+                &[
+                    initializers_token_account.clone(),
+                    tree_config_account.clone(),
+                    initializer.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+        }
+
+        EscrowEvent::CompressedEscrowCancelled { merkle_tree: *merkle_tree.key, leaf_index: escrow.leaf_index }.emit()
+    }
+
+    // The proof nodes come after an instruction's own accounts, as many as the tree's canopy leaves to be passed
+    fn next_proof_accounts<'a, 'b>(
+        account_info_iter: &mut std::slice::Iter<'b, AccountInfo<'a>>,
+        tree: &CompressedTree,
+    ) -> Result<Vec<AccountInfo<'a>>, ProgramError> {
+        (0..tree.proof_len).map(|_| next_account_info(account_info_iter).cloned()).collect()
+    }
+
+    // replace_leaf fails unless the escrow's leaf is in the tree at its index, which is what proves the escrow passed in was really listed and hasn't been exchanged or cancelled yet
+    #[allow(clippy::too_many_arguments)]
+    fn clear_compressed_leaf<'a>(
+        escrow: &CompressedEscrow,
+        tree: &CompressedTree,
+        tree_config_account: &AccountInfo<'a>,
+        merkle_tree: &AccountInfo<'a>,
+        compression_program: &AccountInfo<'a>,
+        noop_program: &AccountInfo<'a>,
+        root: [u8; 32],
+        proof: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+        if *compression_program.key != compression::id() || *noop_program.key != compression::noop::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let proof_keys: Vec<Pubkey> = proof.iter().map(|node| *node.key).collect();
+        let replace_ix = compression::replace_leaf(
+            merkle_tree.key,
+            tree_config_account.key,
+            root,
+            escrow.leaf(),
+            compressed_escrow::EMPTY_LEAF,
+            escrow.leaf_index,
+            &proof_keys,
+        );
+        let mut account_infos =
+            vec![merkle_tree.clone(), tree_config_account.clone(), noop_program.clone(), compression_program.clone()];
+        account_infos.extend_from_slice(proof);
+        log!("Calling the compression program to clear the escrow's leaf...");
+        invoke_signed(
+            &replace_ix,
+            &account_infos,
+            &[&[&b"compressed_tree"[..], merkle_tree.key.as_ref(), &[tree.bump_seed]]],
+        )
+    }
+
+    // Checks that the account is the config of the merkle tree, at the address its own seeds give
+    fn unpack_compressed_tree(
+        tree_config_account: &AccountInfo,
+        merkle_tree: &AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<CompressedTree, ProgramError> {
+        if tree_config_account.owner != program_id || tree_config_account.data_len() != CompressedTree::LEN {
+            return Err(EscrowError::InvalidCompressedTree.into());
+        }
+        let tree = CompressedTree::unpack(&tree_config_account.try_borrow_data()?)?;
+        let tree_config_address = Pubkey::create_program_address(
+            &[b"compressed_tree", merkle_tree.key.as_ref(), &[tree.bump_seed]],
+            program_id,
+        )?;
+        if *tree_config_account.key != tree_config_address || tree.merkle_tree != *merkle_tree.key {
+            return Err(EscrowError::InvalidCompressedTree.into());
+        }
+        Ok(tree)
+    }
+
+    // Checks that the account really is a quote of this program, at the address its own seeds give
+    fn unpack_quote(quote_account: &AccountInfo, program_id: &Pubkey) -> Result<Quote, ProgramError> {
+        if quote_account.owner != program_id || quote_account.data_len() != Quote::LEN {
+            return Err(EscrowError::InvalidQuote.into());
+        }
+        let quote = Quote::unpack(&quote_account.try_borrow_data()?)?;
+        let quote_address = Pubkey::create_program_address(
+            &[b"quote", quote.escrow.as_ref(), quote.quoter.as_ref(), &[quote.bump_seed]],
+            program_id,
+        )?;
+        if *quote_account.key != quote_address {
+            return Err(EscrowError::InvalidQuote.into());
+        }
+        Ok(quote)
+    }
+
+    // Checks that the account really is a sealed-bid auction of this program, at the address its own seeds give
+    fn unpack_sealed_auction(
+        auction_account: &AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<SealedAuction, ProgramError> {
+        if auction_account.owner != program_id || auction_account.data_len() != SealedAuction::LEN {
+            return Err(EscrowError::InvalidSealedAuction.into());
+        }
+        let auction = SealedAuction::unpack(&auction_account.try_borrow_data()?)?;
+        let auction_address = Pubkey::create_program_address(
+            &[b"sealed_auction", auction.escrow.as_ref(), &[auction.bump_seed]],
+            program_id,
+        )?;
+        if *auction_account.key != auction_address {
+            return Err(EscrowError::InvalidSealedAuction.into());
+        }
+        Ok(auction)
+    }
+
+    fn unpack_sealed_bid(sealed_bid_account: &AccountInfo, program_id: &Pubkey) -> Result<SealedBid, ProgramError> {
+        if sealed_bid_account.owner != program_id || sealed_bid_account.data_len() != SealedBid::LEN {
+            return Err(EscrowError::InvalidSealedBid.into());
+        }
+        let sealed_bid = SealedBid::unpack(&sealed_bid_account.try_borrow_data()?)?;
+        let sealed_bid_address = Pubkey::create_program_address(
+            &[b"sealed_bid", sealed_bid.auction.as_ref(), sealed_bid.bidder.as_ref(), &[sealed_bid.bump_seed]],
+            program_id,
+        )?;
+        if *sealed_bid_account.key != sealed_bid_address {
+            return Err(EscrowError::InvalidSealedBid.into());
+        }
+        Ok(sealed_bid)
+    }
+
+    // Checks that the account really is a raffle of this program, at the address its own seeds give
+    fn unpack_raffle(raffle_account: &AccountInfo, program_id: &Pubkey) -> Result<Raffle, ProgramError> {
+        if raffle_account.owner != program_id || raffle_account.data_len() != Raffle::LEN {
+            return Err(EscrowError::InvalidRaffle.into());
+        }
+        let raffle = Raffle::unpack(&raffle_account.try_borrow_data()?)?;
+        let raffle_address =
+            Pubkey::create_program_address(&[b"raffle", raffle.escrow.as_ref(), &[raffle.bump_seed]], program_id)?;
+        if *raffle_account.key != raffle_address {
+            return Err(EscrowError::InvalidRaffle.into());
+        }
+        Ok(raffle)
+    }
+
+    fn unpack_ticket(ticket_account: &AccountInfo, program_id: &Pubkey) -> Result<Ticket, ProgramError> {
+        if ticket_account.owner != program_id || ticket_account.data_len() != Ticket::LEN {
+            return Err(EscrowError::InvalidTicket.into());
+        }
+        let ticket = Ticket::unpack(&ticket_account.try_borrow_data()?)?;
+        let ticket_address = Pubkey::create_program_address(
+            &[b"ticket", ticket.raffle.as_ref(), &ticket.first_ticket.to_le_bytes(), &[ticket.bump_seed]],
+            program_id,
+        )?;
+        if *ticket_account.key != ticket_address {
+            return Err(EscrowError::InvalidTicket.into());
+        }
+        Ok(ticket)
+    }
+
+    fn unpack_stablecoins(
+        stablecoins_account: &AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<StablecoinList, ProgramError> {
+        if stablecoins_account.owner != program_id || stablecoins_account.data_len() != StablecoinList::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let stablecoins = StablecoinList::unpack(&stablecoins_account.try_borrow_data()?)?;
+        let stablecoins_address =
+            Pubkey::create_program_address(&[b"stablecoins", &[stablecoins.bump_seed]], program_id)?;
+        if *stablecoins_account.key != stablecoins_address {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(stablecoins)
+    }
+
+    fn unpack_allowlist(allowlist_account: &AccountInfo, program_id: &Pubkey) -> Result<MintAllowlist, ProgramError> {
+        if allowlist_account.owner != program_id || allowlist_account.data_len() != MintAllowlist::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let allowlist = MintAllowlist::unpack(&allowlist_account.try_borrow_data()?)?;
+        let allowlist_address =
+            Pubkey::create_program_address(&[b"allowlist", &[allowlist.bump_seed]], program_id)?;
+        if *allowlist_account.key != allowlist_address {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(allowlist)
+    }
+
+    // Every admin instruction needs the admin's signature and the config account naming them as admin
+    fn unpack_config_as_admin(
+        admin: &AccountInfo,
+        config_account: &AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<Config, ProgramError> {
+        if !admin.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let config = Self::unpack_config(config_account, program_id)?;
+        if config.admin != *admin.key {
+            return Err(EscrowError::UnauthorizedAdmin.into());
+        }
+        Ok(config)
+    }
+
+    // Checks that the account really is the config account before trusting what it says
+    fn unpack_config(config_account: &AccountInfo, program_id: &Pubkey) -> Result<Config, ProgramError> {
+        Ok(ProgramOwned::<Config>::new(config_account, program_id)?.state)
+    }
+}
@@ -0,0 +1,319 @@
+//! Raffles settle an escrow to one of many takers at random instead of to whoever pays most. Takers buy numbered tickets at a fixed price until the raffle ends or sells out, and the winning ticket gets the escrowed tokens while the initializer gets the ticket sales.
+//!
+//! The randomness comes from the SlotHashes sysvar, so anyone can check a draw with nothing but the chain's history. DrawRaffle commits to a slot a few slots in the future, before anyone can know its hash, and SettleRaffle then derives the winning ticket from the hash of that slot, or of the first slot after it if it was skipped. SlotHashes only keeps the last MAX_ENTRIES slots, so a raffle that isn't settled in time has to be drawn again.
+//!
+//! A slot's leader has some say over its hash, so raffles worth more than a block reward can draw with Switchboard VRF instead, see switchboard. The VRF account is fixed at InitRaffle, RequestRaffleRandomness requests a result once the raffle is over, and the VRF's callback, ConsumeRaffleRandomness, stores it in the raffle for SettleRaffle.
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    hash::hashv,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+pub use solana_program::slot_hashes::MAX_ENTRIES;
+
+/// How many slots after DrawRaffle the draw slot is. Its hash is unknown until then, including to whoever sent DrawRaffle.
+pub const DRAW_DELAY_SLOTS: u64 = 10;
+
+/// The hash a raffle drawn at `draw_slot` is settled with, read from the SlotHashes sysvar's data: the hash of `draw_slot`, or of the earliest slot after it if `draw_slot` was skipped. None if no such slot has passed yet, or if SlotHashes no longer reaches back to `draw_slot`, since a later slot's hash could then be picked.
+pub fn draw_hash(slot_hashes: &[u8], draw_slot: u64) -> Option<[u8; 32]> {
+    let len = u64::from_le_bytes(*array_ref![slot_hashes.get(..8)?, 0, 8]) as usize;
+    let entries = slot_hashes.get(8..8 + len.checked_mul(40)?)?;
+    // Entries are ordered from the newest slot to the oldest
+    let mut draw_hash = None;
+    for entry in entries.chunks_exact(40) {
+        let (slot, hash) = array_refs![array_ref![entry, 0, 40], 8, 32];
+        if u64::from_le_bytes(*slot) < draw_slot {
+            return draw_hash;
+        }
+        draw_hash = Some(*hash);
+    }
+    // Without an entry before draw_slot there may have been an earlier one at or after it that was dropped
+    entries.chunks_exact(40).last().filter(|entry| entry[..8] == draw_slot.to_le_bytes())?;
+    draw_hash
+}
+
+/// The number of the winning ticket, given the hash the raffle is settled with
+pub fn winning_ticket(draw_hash: &[u8; 32], raffle: &Pubkey, tickets_sold: u32) -> u32 {
+    let seed = hashv(&[draw_hash, raffle.as_ref()]).to_bytes();
+    (u64::from_le_bytes(*array_ref![seed, 0, 8]) % tickets_sold as u64) as u32
+}
+
+/// A raffle, stored at the PDA derived from `[b"raffle", escrow pubkey]`. The escrow is an English auction that is private to this account, so it can't be exchanged or bid on, and its highest bid is what the tickets sold so far paid, so it can't be cancelled once a ticket is sold. The ticket sales are held in a token account owned by the raffle, at the PDA derived from `[b"raffle_vault", raffle pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Raffle {
+    pub is_initialized: bool,
+    /// The bump seed of the raffle PDA
+    pub bump_seed: u8,
+    pub escrow: Pubkey,
+    /// Who initialized the escrow and paid the rent of the raffle and its vault
+    pub initializer: Pubkey,
+    /// The initializer's token account of token Y at InitRaffle, which the ticket sales are paid into
+    pub proceeds_token_account: Pubkey,
+    pub token_y_mint: Pubkey,
+    /// The price of one ticket in token Y
+    pub ticket_price: u64,
+    pub max_tickets: u32,
+    /// Tickets are numbered from 0, so this is also the number of the next ticket
+    pub tickets_sold: u32,
+    /// Tickets can be bought until then, and the raffle can be drawn from then on, or as soon as it sells out
+    pub end_unix_timestamp: i64,
+    /// The slot whose hash picks the winner, see DrawRaffle, or the slot randomness was requested in for raffles drawn with VRF. 0 until the raffle is drawn.
+    pub draw_slot: u64,
+    /// The Switchboard VRF account the raffle is drawn with, or the default pubkey if it's drawn with SlotHashes
+    pub vrf: Pubkey,
+    /// The VRF's counter after RequestRaffleRandomness. Only a result of that request is consumed.
+    pub vrf_counter: u128,
+    /// The VRF's result once ConsumeRaffleRandomness stored it, all zeroes until then
+    pub randomness: [u8; 32],
+}
+
+impl Raffle {
+    pub fn is_sold_out(&self) -> bool {
+        self.tickets_sold >= self.max_tickets
+    }
+
+    pub fn has_ended(&self, unix_timestamp: i64) -> bool {
+        unix_timestamp >= self.end_unix_timestamp || self.is_sold_out()
+    }
+
+    pub fn is_drawn(&self) -> bool {
+        self.draw_slot != 0
+    }
+
+    /// Whether the draw slot's hash has surely dropped out of SlotHashes, so the raffle can be drawn again. A VRF request that went unanswered for as long can be made again too.
+    pub fn is_draw_stale(&self, slot: u64) -> bool {
+        slot > self.draw_slot.saturating_add(MAX_ENTRIES as u64) && self.randomness().is_none()
+    }
+
+    pub fn uses_vrf(&self) -> bool {
+        self.vrf != Pubkey::default()
+    }
+
+    /// The consumed VRF result
+    pub fn randomness(&self) -> Option<[u8; 32]> {
+        Some(self.randomness).filter(|randomness| *randomness != [0; 32])
+    }
+}
+
+impl Sealed for Raffle {}
+
+impl IsInitialized for Raffle {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Raffle {
+    const LEN: usize = 242;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Raffle::LEN];
+        let (
+            is_initialized,
+            bump_seed,
+            escrow,
+            initializer,
+            proceeds_token_account,
+            token_y_mint,
+            ticket_price,
+            max_tickets,
+            tickets_sold,
+            end_unix_timestamp,
+            draw_slot,
+            vrf,
+            vrf_counter,
+            randomness,
+        ) = array_refs![src, 1, 1, 32, 32, 32, 32, 8, 4, 4, 8, 8, 32, 16, 32];
+
+        Ok(Raffle {
+            is_initialized: unpack_bool(is_initialized)?,
+            bump_seed: bump_seed[0],
+            escrow: Pubkey::new_from_array(*escrow),
+            initializer: Pubkey::new_from_array(*initializer),
+            proceeds_token_account: Pubkey::new_from_array(*proceeds_token_account),
+            token_y_mint: Pubkey::new_from_array(*token_y_mint),
+            ticket_price: u64::from_le_bytes(*ticket_price),
+            max_tickets: u32::from_le_bytes(*max_tickets),
+            tickets_sold: u32::from_le_bytes(*tickets_sold),
+            end_unix_timestamp: i64::from_le_bytes(*end_unix_timestamp),
+            draw_slot: u64::from_le_bytes(*draw_slot),
+            vrf: Pubkey::new_from_array(*vrf),
+            vrf_counter: u128::from_le_bytes(*vrf_counter),
+            randomness: *randomness,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Raffle::LEN];
+        let (
+            is_initialized_dst,
+            bump_seed_dst,
+            escrow_dst,
+            initializer_dst,
+            proceeds_token_account_dst,
+            token_y_mint_dst,
+            ticket_price_dst,
+            max_tickets_dst,
+            tickets_sold_dst,
+            end_unix_timestamp_dst,
+            draw_slot_dst,
+            vrf_dst,
+            vrf_counter_dst,
+            randomness_dst,
+        ) = mut_array_refs![dst, 1, 1, 32, 32, 32, 32, 8, 4, 4, 8, 8, 32, 16, 32];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        escrow_dst.copy_from_slice(self.escrow.as_ref());
+        initializer_dst.copy_from_slice(self.initializer.as_ref());
+        proceeds_token_account_dst.copy_from_slice(self.proceeds_token_account.as_ref());
+        token_y_mint_dst.copy_from_slice(self.token_y_mint.as_ref());
+        *ticket_price_dst = self.ticket_price.to_le_bytes();
+        *max_tickets_dst = self.max_tickets.to_le_bytes();
+        *tickets_sold_dst = self.tickets_sold.to_le_bytes();
+        *end_unix_timestamp_dst = self.end_unix_timestamp.to_le_bytes();
+        *draw_slot_dst = self.draw_slot.to_le_bytes();
+        vrf_dst.copy_from_slice(self.vrf.as_ref());
+        *vrf_counter_dst = self.vrf_counter.to_le_bytes();
+        *randomness_dst = self.randomness;
+    }
+}
+
+/// The tickets one purchase bought, numbered first_ticket to first_ticket + count - 1. Stored at the PDA derived from `[b"ticket", raffle pubkey, first_ticket as little-endian bytes]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Ticket {
+    pub is_initialized: bool,
+    /// The bump seed of the ticket PDA
+    pub bump_seed: u8,
+    pub raffle: Pubkey,
+    pub buyer: Pubkey,
+    pub first_ticket: u32,
+    pub count: u32,
+}
+
+impl Ticket {
+    pub fn contains(&self, ticket: u32) -> bool {
+        ticket >= self.first_ticket && ticket - self.first_ticket < self.count
+    }
+}
+
+impl Sealed for Ticket {}
+
+impl IsInitialized for Ticket {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Ticket {
+    const LEN: usize = 74;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Ticket::LEN];
+        let (is_initialized, bump_seed, raffle, buyer, first_ticket, count) = array_refs![src, 1, 1, 32, 32, 4, 4];
+
+        Ok(Ticket {
+            is_initialized: unpack_bool(is_initialized)?,
+            bump_seed: bump_seed[0],
+            raffle: Pubkey::new_from_array(*raffle),
+            buyer: Pubkey::new_from_array(*buyer),
+            first_ticket: u32::from_le_bytes(*first_ticket),
+            count: u32::from_le_bytes(*count),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Ticket::LEN];
+        let (is_initialized_dst, bump_seed_dst, raffle_dst, buyer_dst, first_ticket_dst, count_dst) =
+            mut_array_refs![dst, 1, 1, 32, 32, 4, 4];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        raffle_dst.copy_from_slice(self.raffle.as_ref());
+        buyer_dst.copy_from_slice(self.buyer.as_ref());
+        *first_ticket_dst = self.first_ticket.to_le_bytes();
+        *count_dst = self.count.to_le_bytes();
+    }
+}
+
+fn unpack_bool(src: &[u8; 1]) -> Result<bool, ProgramError> {
+    match src {
+        [0] => Ok(false),
+        [1] => Ok(true),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot_hashes(slots: &[u64]) -> Vec<u8> {
+        let mut data = (slots.len() as u64).to_le_bytes().to_vec();
+        for slot in slots {
+            data.extend_from_slice(&slot.to_le_bytes());
+            data.extend_from_slice(&[*slot as u8; 32]);
+        }
+        data
+    }
+
+    #[test]
+    fn draws_use_the_first_slot_from_the_draw_slot_on() {
+        let data = slot_hashes(&[105, 104, 102, 101, 100]);
+        assert_eq!(draw_hash(&data, 102), Some([102; 32]));
+        // 103 was skipped
+        assert_eq!(draw_hash(&data, 103), Some([104; 32]));
+        assert_eq!(draw_hash(&data, 100), Some([100; 32]));
+        assert_eq!(draw_hash(&data, 106), None);
+        // 98 and 99 might have been dropped already
+        assert_eq!(draw_hash(&data, 98), None);
+        assert_eq!(draw_hash(&data[..20], 102), None);
+
+        let raffle = Pubkey::new_unique();
+        assert!(winning_ticket(&[102; 32], &raffle, 7) < 7);
+        assert_eq!(winning_ticket(&[102; 32], &raffle, 1), 0);
+
+        let ticket = Ticket {
+            is_initialized: true,
+            bump_seed: 254,
+            raffle,
+            buyer: Pubkey::new_unique(),
+            first_ticket: 3,
+            count: 2,
+        };
+        assert!(!ticket.contains(2));
+        assert!(ticket.contains(4));
+        assert!(!ticket.contains(5));
+
+        let mut packed = vec![0u8; Ticket::LEN];
+        Ticket::pack(ticket, &mut packed).unwrap();
+        assert_eq!(Ticket::unpack(&packed).unwrap(), ticket);
+        assert_eq!(ticket.try_to_vec().unwrap(), packed);
+
+        let raffle = Raffle {
+            is_initialized: true,
+            bump_seed: 253,
+            escrow: Pubkey::new_unique(),
+            initializer: Pubkey::new_unique(),
+            proceeds_token_account: Pubkey::new_unique(),
+            token_y_mint: Pubkey::new_unique(),
+            ticket_price: 25,
+            max_tickets: 100,
+            tickets_sold: 40,
+            end_unix_timestamp: 1_700_000_000,
+            draw_slot: 123_456,
+            vrf: Pubkey::new_unique(),
+            vrf_counter: 7,
+            randomness: [0; 32],
+        };
+        assert!(raffle.uses_vrf());
+        assert!(raffle.is_draw_stale(123_456 + MAX_ENTRIES as u64 + 1));
+        assert!(!Raffle { randomness: [9; 32], ..raffle }.is_draw_stale(123_456 + MAX_ENTRIES as u64 + 1));
+        let mut packed = vec![0u8; Raffle::LEN];
+        Raffle::pack(raffle, &mut packed).unwrap();
+        assert_eq!(Raffle::unpack(&packed).unwrap(), raffle);
+        assert_eq!(raffle.try_to_vec().unwrap(), packed);
+    }
+}
@@ -0,0 +1,226 @@
+//! Sealed-bid auctions keep bids secret until bidding is over, so nobody can outbid the others by a hair at the last moment. They run in two windows, both driven by the clock.
+//!
+//! During the commit window bidders only send a commitment, the hash of their bid, with CommitBid, and lock the auction's deposit in a vault of their own. During the reveal window they reveal the bid and the salt it was hashed with using RevealBid, which returns the deposit. A revealed bid that beats every bid revealed before it is paid into the bidder's bid account, exactly like a PlaceBid, and the escrow settles to the best one with SettleAuction once the reveal window is over. Deposits of bids that weren't revealed in time are slashed to the initializer with SlashBid, which is what keeps bidders from committing to bids they have no intention of paying.
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    hash::hashv,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+/// The commitment a bidder sends with CommitBid. The bidder is part of it so nobody can copy someone else's commitment and reveal it after them.
+pub fn commitment(bidder: &Pubkey, amount: u64, salt: &[u8; 32]) -> [u8; 32] {
+    hashv(&[bidder.as_ref(), &amount.to_le_bytes(), salt]).to_bytes()
+}
+
+/// The settings of a sealed-bid auction, stored at the PDA derived from `[b"sealed_auction", escrow pubkey]`. The escrow itself is an English auction that is private to this account, so PlaceBid can't be used on it, and whose auction ends with the reveal window.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct SealedAuction {
+    pub is_initialized: bool,
+    /// The bump seed of the auction PDA
+    pub bump_seed: u8,
+    pub escrow: Pubkey,
+    /// Who initialized the escrow and paid the auction's rent
+    pub initializer: Pubkey,
+    /// The initializer's token account of token Y at InitSealedAuction, which slashed deposits are paid into
+    pub beneficiary_token_account: Pubkey,
+    pub token_y_mint: Pubkey,
+    /// The lowest bid the initializer accepts. Lower bids can still be revealed, which only returns their deposit.
+    pub min_bid: u64,
+    /// The amount of token Y every bidder locks with their commitment
+    pub deposit: u64,
+    /// Bids are committed before this, and revealed from then on until reveal_end_unix_timestamp
+    pub commit_end_unix_timestamp: i64,
+    pub reveal_end_unix_timestamp: i64,
+    /// How many commitments have been neither revealed nor slashed. The auction can only be closed once there are none.
+    pub open_commitments: u32,
+}
+
+impl SealedAuction {
+    pub fn is_commit_open(&self, unix_timestamp: i64) -> bool {
+        unix_timestamp < self.commit_end_unix_timestamp
+    }
+
+    pub fn is_reveal_open(&self, unix_timestamp: i64) -> bool {
+        !self.is_commit_open(unix_timestamp) && unix_timestamp < self.reveal_end_unix_timestamp
+    }
+}
+
+impl Sealed for SealedAuction {}
+
+impl IsInitialized for SealedAuction {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SealedAuction {
+    const LEN: usize = 166;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, SealedAuction::LEN];
+        let (
+            is_initialized,
+            bump_seed,
+            escrow,
+            initializer,
+            beneficiary_token_account,
+            token_y_mint,
+            min_bid,
+            deposit,
+            commit_end_unix_timestamp,
+            reveal_end_unix_timestamp,
+            open_commitments,
+        ) = array_refs![src, 1, 1, 32, 32, 32, 32, 8, 8, 8, 8, 4];
+
+        Ok(SealedAuction {
+            is_initialized: unpack_bool(is_initialized)?,
+            bump_seed: bump_seed[0],
+            escrow: Pubkey::new_from_array(*escrow),
+            initializer: Pubkey::new_from_array(*initializer),
+            beneficiary_token_account: Pubkey::new_from_array(*beneficiary_token_account),
+            token_y_mint: Pubkey::new_from_array(*token_y_mint),
+            min_bid: u64::from_le_bytes(*min_bid),
+            deposit: u64::from_le_bytes(*deposit),
+            commit_end_unix_timestamp: i64::from_le_bytes(*commit_end_unix_timestamp),
+            reveal_end_unix_timestamp: i64::from_le_bytes(*reveal_end_unix_timestamp),
+            open_commitments: u32::from_le_bytes(*open_commitments),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, SealedAuction::LEN];
+        let (
+            is_initialized_dst,
+            bump_seed_dst,
+            escrow_dst,
+            initializer_dst,
+            beneficiary_token_account_dst,
+            token_y_mint_dst,
+            min_bid_dst,
+            deposit_dst,
+            commit_end_dst,
+            reveal_end_dst,
+            open_commitments_dst,
+        ) = mut_array_refs![dst, 1, 1, 32, 32, 32, 32, 8, 8, 8, 8, 4];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        escrow_dst.copy_from_slice(self.escrow.as_ref());
+        initializer_dst.copy_from_slice(self.initializer.as_ref());
+        beneficiary_token_account_dst.copy_from_slice(self.beneficiary_token_account.as_ref());
+        token_y_mint_dst.copy_from_slice(self.token_y_mint.as_ref());
+        *min_bid_dst = self.min_bid.to_le_bytes();
+        *deposit_dst = self.deposit.to_le_bytes();
+        *commit_end_dst = self.commit_end_unix_timestamp.to_le_bytes();
+        *reveal_end_dst = self.reveal_end_unix_timestamp.to_le_bytes();
+        *open_commitments_dst = self.open_commitments.to_le_bytes();
+    }
+}
+
+/// A bidder's commitment, stored at the PDA derived from `[b"sealed_bid", auction pubkey, bidder pubkey]`. Its deposit is held in a token account owned by it, at the PDA derived from `[b"sealed_bid_vault", sealed bid pubkey]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct SealedBid {
+    pub is_initialized: bool,
+    /// The bump seed of the sealed bid PDA
+    pub bump_seed: u8,
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    /// See commitment
+    pub commitment: [u8; 32],
+}
+
+impl Sealed for SealedBid {}
+
+impl IsInitialized for SealedBid {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SealedBid {
+    const LEN: usize = 98;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, SealedBid::LEN];
+        let (is_initialized, bump_seed, auction, bidder, commitment) = array_refs![src, 1, 1, 32, 32, 32];
+
+        Ok(SealedBid {
+            is_initialized: unpack_bool(is_initialized)?,
+            bump_seed: bump_seed[0],
+            auction: Pubkey::new_from_array(*auction),
+            bidder: Pubkey::new_from_array(*bidder),
+            commitment: *commitment,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, SealedBid::LEN];
+        let (is_initialized_dst, bump_seed_dst, auction_dst, bidder_dst, commitment_dst) =
+            mut_array_refs![dst, 1, 1, 32, 32, 32];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        auction_dst.copy_from_slice(self.auction.as_ref());
+        bidder_dst.copy_from_slice(self.bidder.as_ref());
+        *commitment_dst = self.commitment;
+    }
+}
+
+fn unpack_bool(src: &[u8; 1]) -> Result<bool, ProgramError> {
+    match src {
+        [0] => Ok(false),
+        [1] => Ok(true),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commitments_bind_the_bid_and_the_bidder() {
+        let bidder = Pubkey::new_unique();
+        let salt = [7; 32];
+        let sealed_bid = SealedBid {
+            is_initialized: true,
+            bump_seed: 253,
+            auction: Pubkey::new_unique(),
+            bidder,
+            commitment: commitment(&bidder, 1_000, &salt),
+        };
+        assert_eq!(sealed_bid.commitment, commitment(&bidder, 1_000, &salt));
+        assert_ne!(sealed_bid.commitment, commitment(&bidder, 1_001, &salt));
+        assert_ne!(sealed_bid.commitment, commitment(&bidder, 1_000, &[8; 32]));
+        assert_ne!(sealed_bid.commitment, commitment(&Pubkey::new_unique(), 1_000, &salt));
+
+        let mut packed = vec![0u8; SealedBid::LEN];
+        SealedBid::pack(sealed_bid, &mut packed).unwrap();
+        assert_eq!(SealedBid::unpack(&packed).unwrap(), sealed_bid);
+        assert_eq!(sealed_bid.try_to_vec().unwrap(), packed);
+
+        let auction = SealedAuction {
+            is_initialized: true,
+            bump_seed: 254,
+            escrow: Pubkey::new_unique(),
+            initializer: Pubkey::new_unique(),
+            beneficiary_token_account: Pubkey::new_unique(),
+            token_y_mint: Pubkey::new_unique(),
+            min_bid: 500,
+            deposit: 50,
+            commit_end_unix_timestamp: 1_700_000_000,
+            reveal_end_unix_timestamp: 1_700_003_600,
+            open_commitments: 3,
+        };
+        assert!(auction.is_commit_open(1_699_999_999));
+        assert!(auction.is_reveal_open(1_700_000_000));
+        assert!(!auction.is_reveal_open(1_700_003_600));
+
+        let mut packed = vec![0u8; SealedAuction::LEN];
+        SealedAuction::pack(auction, &mut packed).unwrap();
+        assert_eq!(SealedAuction::unpack(&packed).unwrap(), auction);
+        assert_eq!(auction.try_to_vec().unwrap(), packed);
+    }
+}
@@ -0,0 +1,104 @@
+//! Signed orders let an initializer quote a price for their escrow off-chain, e.g. in answer to a taker's request for quote, without a transaction of their own. The initializer signs the order's message with their wallet, and the taker settles it with SettleSignedOrder, which checks the signature and exchanges the escrow at the signed price.
+//!
+//! The program doesn't check the signature itself. The taker puts an instruction of the ed25519 program right before SettleSignedOrder, see verify_instruction, which fails the transaction if the signature is invalid, and SettleSignedOrder reads it through the instructions sysvar to make sure it verified the initializer's signature of the order's message.
+
+use solana_program::{
+    account_info::AccountInfo,
+    ed25519_program,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+
+use crate::error::EscrowError;
+
+/// Every message starts with this, so a signed order can't pass for anything else the initializer's wallet signs
+pub const MESSAGE_PREFIX: &[u8] = b"solana-escrow signed order";
+
+// The offsets of the ed25519 program's instruction data, for a single signature
+const HEADER_LEN: usize = 16;
+const PUBKEY_OFFSET: usize = HEADER_LEN;
+const SIGNATURE_OFFSET: usize = PUBKEY_OFFSET + 32;
+const MESSAGE_OFFSET: usize = SIGNATURE_OFFSET + 64;
+
+/// The message the initializer signs: the escrow, the taker the order is for (the default pubkey for anyone), the amount of token X taken, the price in token Y and when the order expires
+pub fn message(escrow: &Pubkey, taker: &Pubkey, amount: u64, price: u64, expiry_unix_timestamp: i64) -> Vec<u8> {
+    let mut message = MESSAGE_PREFIX.to_vec();
+    message.extend_from_slice(escrow.as_ref());
+    message.extend_from_slice(taker.as_ref());
+    message.extend_from_slice(&amount.to_le_bytes());
+    message.extend_from_slice(&price.to_le_bytes());
+    message.extend_from_slice(&expiry_unix_timestamp.to_le_bytes());
+    message
+}
+
+/// The ed25519 program instruction that verifies `signature` of `message` by `signer`. It has to come right before SettleSignedOrder.
+pub fn verify_instruction(signer: &Pubkey, signature: &[u8; 64], message: &[u8]) -> Instruction {
+    let mut data = vec![1, 0];
+    // Every offset points into this instruction's own data, which the ed25519 program denotes by u16::MAX
+    let own_data = u16::MAX as usize;
+    for offset in [SIGNATURE_OFFSET, own_data, PUBKEY_OFFSET, own_data, MESSAGE_OFFSET, message.len(), own_data] {
+        data.extend_from_slice(&(offset as u16).to_le_bytes());
+    }
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+    Instruction { program_id: ed25519_program::id(), accounts: vec![], data }
+}
+
+// The signer and the message of an ed25519 program instruction with a single signature whose offsets all point into its own data, None for anything else
+fn unpack_verify_instruction(data: &[u8]) -> Option<(Pubkey, &[u8])> {
+    if data.len() < HEADER_LEN || data[0] != 1 {
+        return None;
+    }
+    let offsets: Vec<usize> = data[2..HEADER_LEN]
+        .chunks_exact(2)
+        .map(|offset| u16::from_le_bytes([offset[0], offset[1]]) as usize)
+        .collect();
+    let [_signature_offset, signature_index, pubkey_offset, pubkey_index, message_offset, message_len, message_index] =
+        offsets[..]
+    else {
+        return None;
+    };
+    let own_data = u16::MAX as usize;
+    if signature_index != own_data || pubkey_index != own_data || message_index != own_data {
+        return None;
+    }
+    let signer = Pubkey::try_from(data.get(pubkey_offset..pubkey_offset.checked_add(32)?)?).ok()?;
+    let message = data.get(message_offset..message_offset.checked_add(message_len)?)?;
+    Some((signer, message))
+}
+
+/// Checks that the instruction before the current one had the ed25519 program verify `signer`'s signature of `message`
+pub fn check_signature(instructions_sysvar: &AccountInfo, signer: &Pubkey, message: &[u8]) -> ProgramResult {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    let verify_index = current_index.checked_sub(1).ok_or(EscrowError::InvalidOrderSignature)?;
+    let verify_ix = load_instruction_at_checked(verify_index as usize, instructions_sysvar)?;
+    if verify_ix.program_id != ed25519_program::id() {
+        return Err(EscrowError::InvalidOrderSignature.into());
+    }
+    match unpack_verify_instruction(&verify_ix.data) {
+        Some((verified_signer, verified_message)) if verified_signer == *signer && verified_message == message => Ok(()),
+        _ => Err(EscrowError::InvalidOrderSignature.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_instruction_round_trip() {
+        let signer = Pubkey::new_unique();
+        let message = message(&Pubkey::new_unique(), &Pubkey::default(), 42, 1_000, 1_700_000_000);
+        let instruction = verify_instruction(&signer, &[7; 64], &message);
+        assert_eq!(unpack_verify_instruction(&instruction.data), Some((signer, &message[..])));
+
+        // Offsets into another instruction's data could point at anything
+        let mut data = instruction.data.clone();
+        data[4..6].copy_from_slice(&0u16.to_le_bytes());
+        assert_eq!(unpack_verify_instruction(&data), None);
+        assert_eq!(unpack_verify_instruction(&instruction.data[..MESSAGE_OFFSET]), None);
+    }
+}
@@ -1,53 +1,585 @@
 use solana_program::{
+    program_option::COption,
     program_pack::{IsInitialized, Pack, Sealed},
     program_error::ProgramError,
     pubkey::Pubkey,
+    system_program,
 };
 
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use borsh::{
+    schema::{Declaration, Definition},
+    BorshDeserialize, BorshSchema, BorshSerialize,
+};
+use bytemuck::{bytes_of, try_from_bytes, try_from_bytes_mut, Pod, Zeroable};
+use shank::ShankAccount;
+use spl_token::state::Account as TokenAccount;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    mem::size_of,
+};
+
+use crate::{
+    error::EscrowError,
+    math::{self, Rounding},
+};
 
 /// When writing Solana programs, be mindful of the fact that any accounts may be passed into the entrypoint, including different ones than those defined in the API inside instruction.rs. It's the program's responsibility to check that received accounts == expected accounts
 
+/// Escrow is zero-copy: it is `repr(C, packed)` and Pod, so its memory layout is exactly its fields in order, without padding. The processor borrows account data as an Escrow with load_mut and updates it in place, and Pack and Borsh are a plain copy of the bytes. That also makes the Borsh encoding byte-for-byte the same as the Pack layout, so tools that expect Borsh can read escrow accounts directly. Only add fixed-size Pod fields, at the end. bool isn't Pod, so flags are PodBools.
+///
+/// Fields wider than a byte aren't aligned, so they can only be copied out (`{ escrow.expected_amount }` in format strings), not borrowed.
+#[derive(BorshSchema, ShankAccount, Pod, Zeroable, Clone, Copy, Debug, PartialEq)]
+#[repr(C, packed)]
 pub struct Escrow {
-    pub is_initialized: bool,
+    /// ESCROW_DISCRIMINATOR once the escrow is initialized, so escrow accounts can be told apart from the program's other accounts by their first 8 bytes
+    pub discriminator: [u8; 8],
+    pub is_initialized: PodBool,
     pub initializer_pubkey: Pubkey,
     pub temp_token_account_pubkey: Pubkey,
     pub initializer_token_to_receive_account_pubkey: Pubkey,
     pub expected_amount: u64,
+    /// Unix timestamp after which anyone can return the tokens to the initializer. 0 means the escrow never expires.
+    pub expiry_unix_timestamp: i64,
+    /// The bump seed of the PDA that owns the temp token account, stored so it doesn't have to be searched for again
+    pub bump_seed: u8,
+    /// The mint of the tokens the initializer put in the escrow
+    pub token_x_mint: Pubkey,
+    /// The mint of the tokens the initializer expects to receive
+    pub token_y_mint: Pubkey,
+    /// The only account that can take the trade. The default pubkey (all zeroes) means anyone can.
+    pub allowed_taker_pubkey: Pubkey,
+    /// For Dutch auctions, the price at the start of the auction. The price then falls linearly to expected_amount, which acts as the floor. 0 means the escrow has a fixed price.
+    pub auction_start_amount: u64,
+    pub auction_start_unix_timestamp: i64,
+    pub auction_end_unix_timestamp: i64,
+    /// English auctions can't be exchanged. Takers bid with PlaceBid instead and the escrow settles to the highest bid once the auction ends.
+    pub is_english_auction: PodBool,
+    /// For two-phase escrows, the taker who accepted the escrow
+    pub highest_bidder: Pubkey,
+    /// 0 as long as nobody has bid. For two-phase escrows, the payment the taker made when accepting.
+    pub highest_bid_amount: u64,
+    /// The Pyth price account that prices an oracle escrow, quoting token X in token Y. The default pubkey means the escrow isn't priced by an oracle and expected_amount applies.
+    pub price_feed: Pubkey,
+    /// The premium on top of the oracle price in basis points, negative for a discount
+    pub premium_bps: i16,
+    /// For delegated escrows, the amount of token X the PDA may transfer out of the initializer's own token account, which temp_token_account_pubkey then points to. 0 means the tokens sit in a temp token account owned by the PDA.
+    pub delegated_amount: u64,
+    /// Two-phase escrows are taken with Accept, which locks the escrow, and only settle this many seconds later unless both parties sign. 0 means the escrow is taken with Exchange as usual.
+    pub dispute_window_seconds: i64,
+    /// When the escrow was accepted, 0 until then
+    pub accepted_unix_timestamp: i64,
+    /// How many of the approvers have to approve before the escrow can be exchanged or settled. 0 means no approvals are needed.
+    pub approval_threshold: u8,
+    pub approver_count: u8,
+    /// Bit i is set once approvers[i] has approved
+    pub approvals: u8,
+    /// The first approver_count entries are in use. The length is spelled out for shank, see MAX_APPROVERS.
+    pub approvers: [Pubkey; 8],
+    /// Vesting escrows don't pay the taker directly. Exchange moves the tokens into a vesting vault instead, from which they unlock in vesting_periods tranches, one every vesting_period_seconds. 0 periods means the taker is paid directly.
+    pub vesting_period_seconds: i64,
+    pub vesting_periods: u16,
+    /// Streaming escrows pay their recipient, the allowed taker, continuously between these two timestamps instead of being exchanged. A stream end of 0 means the escrow isn't a stream.
+    pub stream_start_unix_timestamp: i64,
+    pub stream_end_unix_timestamp: i64,
+    /// How much of the stream the recipient has withdrawn so far
+    pub stream_withdrawn_amount: u64,
+    /// When the escrow was initialized. There is no settlement timestamp to go with it, since escrows are closed when they settle: that time is the block time of the settling transaction, and for vesting escrows the vesting account's start_unix_timestamp.
+    pub created_unix_timestamp: i64,
+    /// ESCROW_VERSION once the escrow is initialized. Escrows of older layouts are upgraded with MigrateEscrow, see VersionedEscrow.
+    pub version: u8,
+    /// Rate-based escrows are priced at price_numerator of token Y per price_denominator of token X, and takers can take any part of what they offer. A denominator of 0 means the escrow is taken whole, for expected_amount.
+    pub price_numerator: u64,
+    pub price_denominator: u64,
+    /// Bytes the initializer attached at InitEscrow for their own use, e.g. a marketplace's listing id, an order reference, a URI or the hash of the terms. The program doesn't interpret them. The first metadata_len are in use, the length is spelled out for shank, see MAX_METADATA_LEN.
+    pub metadata_len: u8,
+    pub metadata: [u8; 128],
+    /// The id the client gave the escrow at InitEscrow, e.g. its off-chain order id. Escrows with an id live at the PDA derived from `[b"escrow", initializer, id]`, see find_escrow_address. 0 means the escrow has no id and its account is a keypair.
+    pub escrow_id: u64,
+    /// The lamports a taker has to lock with Reserve to have the escrow to themselves for a while. 0 means the escrow can't be reserved.
+    pub reservation_deposit: u64,
+    /// The taker holding the reservation and the slot it lapses at. The deposit sits in the escrow account until the taker exchanges, which refunds it, or the reservation lapses, which forfeits it to the initializer. The default pubkey means nobody has reserved the escrow.
+    pub reserved_by: Pubkey,
+    pub reserved_until_slot: u64,
+    /// The root of a merkle tree of the takers allowed to exchange the escrow, on top of allowed_taker_pubkey, see merkle. All zeroes means there is no such allowlist.
+    pub taker_merkle_root: [u8; 32],
+    /// For collection offers, the mint of the verified Metaplex collection whose NFTs the initializer buys with the escrowed tokens, see InitCollectionOffer. The default pubkey means the escrow isn't a collection offer.
+    pub collection: Pubkey,
+    /// Mints the initializer accepts as payment besides token Y, see SetPaymentOption, each with the initializer's token account that receives it and its price: the amount for the whole escrow, or for rate-based escrows the price_numerator. The first payment_option_count entries are in use, the lengths are spelled out for shank, see MAX_PAYMENT_OPTIONS.
+    pub payment_option_count: u8,
+    pub payment_option_mints: [Pubkey; 3],
+    pub payment_option_token_accounts: [Pubkey; 3],
+    pub payment_option_amounts: [u64; 3],
+    /// The price of the whole escrow in USD, with USD_DECIMALS decimals, see SetUsdPrice. It is paid in any mint on the StablecoinList at its oracle price. 0 if the escrow is priced in token Y.
+    pub usd_amount: u64,
+}
+
+/// A bool that can live in a Pod struct. Any byte is a valid PodBool, so Escrow::load checks that its flags are 0 or 1. Its Borsh encoding and schema are those of a bool.
+#[derive(Pod, Zeroable, Clone, Copy, Debug, Default, PartialEq)]
+#[repr(transparent)]
+pub struct PodBool(u8);
+
+impl From<bool> for PodBool {
+    fn from(value: bool) -> Self {
+        PodBool(value as u8)
+    }
+}
+
+impl From<PodBool> for bool {
+    fn from(value: PodBool) -> Self {
+        value.0 != 0
+    }
+}
+
+impl BorshSchema for PodBool {
+    fn add_definitions_recursively(_definitions: &mut HashMap<Declaration, Definition>) {}
+
+    fn declaration() -> Declaration {
+        bool::declaration()
+    }
+}
+
+/// How many approvers an escrow can have, limited by the width of the approvals bitmap
+pub const MAX_APPROVERS: usize = 8;
+
+/// How many mints an escrow can accept as payment besides token Y
+pub const MAX_PAYMENT_OPTIONS: usize = 3;
+
+/// The number of decimals of Escrow::usd_amount, so 1_000_000 is one dollar
+pub const USD_DECIMALS: u8 = 6;
+
+/// How many bytes of metadata an escrow can carry
+pub const MAX_METADATA_LEN: usize = 128;
+
+/// The longest reservation, about an hour at 400ms slots
+pub const MAX_RESERVATION_SLOTS: u64 = 9_000;
+
+/// The first 8 bytes of every initialized escrow account. Like an Anchor account discriminator, it is the first 8 bytes of sha256("account:Escrow").
+pub const ESCROW_DISCRIMINATOR: [u8; 8] = [31, 213, 123, 187, 186, 22, 218, 155];
+
+/// The version of the current escrow layout. EscrowV1 came before it.
+pub const ESCROW_VERSION: u8 = 2;
+
+/// The byte offset of each field in an escrow account, for getProgramAccounts memcmp filters. E.g. the open escrows of an initializer are the accounts with ESCROW_DISCRIMINATOR at DISCRIMINATOR and the initializer's pubkey at INITIALIZER_PUBKEY, and the escrows selling a mint have it at TOKEN_X_MINT. Fields are only ever appended, so these offsets don't change.
+pub mod escrow_offsets {
+    pub const DISCRIMINATOR: usize = 0;
+    pub const IS_INITIALIZED: usize = DISCRIMINATOR + 8;
+    pub const INITIALIZER_PUBKEY: usize = IS_INITIALIZED + 1;
+    pub const TEMP_TOKEN_ACCOUNT_PUBKEY: usize = INITIALIZER_PUBKEY + 32;
+    pub const INITIALIZER_TOKEN_TO_RECEIVE_ACCOUNT_PUBKEY: usize = TEMP_TOKEN_ACCOUNT_PUBKEY + 32;
+    pub const EXPECTED_AMOUNT: usize = INITIALIZER_TOKEN_TO_RECEIVE_ACCOUNT_PUBKEY + 32;
+    pub const EXPIRY_UNIX_TIMESTAMP: usize = EXPECTED_AMOUNT + 8;
+    pub const BUMP_SEED: usize = EXPIRY_UNIX_TIMESTAMP + 8;
+    pub const TOKEN_X_MINT: usize = BUMP_SEED + 1;
+    pub const TOKEN_Y_MINT: usize = TOKEN_X_MINT + 32;
+    pub const ALLOWED_TAKER_PUBKEY: usize = TOKEN_Y_MINT + 32;
+    pub const AUCTION_START_AMOUNT: usize = ALLOWED_TAKER_PUBKEY + 32;
+    pub const AUCTION_START_UNIX_TIMESTAMP: usize = AUCTION_START_AMOUNT + 8;
+    pub const AUCTION_END_UNIX_TIMESTAMP: usize = AUCTION_START_UNIX_TIMESTAMP + 8;
+    pub const IS_ENGLISH_AUCTION: usize = AUCTION_END_UNIX_TIMESTAMP + 8;
+    pub const HIGHEST_BIDDER: usize = IS_ENGLISH_AUCTION + 1;
+    pub const HIGHEST_BID_AMOUNT: usize = HIGHEST_BIDDER + 32;
+    pub const PRICE_FEED: usize = HIGHEST_BID_AMOUNT + 8;
+    pub const PREMIUM_BPS: usize = PRICE_FEED + 32;
+    pub const DELEGATED_AMOUNT: usize = PREMIUM_BPS + 2;
+    pub const DISPUTE_WINDOW_SECONDS: usize = DELEGATED_AMOUNT + 8;
+    pub const ACCEPTED_UNIX_TIMESTAMP: usize = DISPUTE_WINDOW_SECONDS + 8;
+    pub const APPROVAL_THRESHOLD: usize = ACCEPTED_UNIX_TIMESTAMP + 8;
+    pub const APPROVER_COUNT: usize = APPROVAL_THRESHOLD + 1;
+    pub const APPROVALS: usize = APPROVER_COUNT + 1;
+    pub const APPROVERS: usize = APPROVALS + 1;
+    pub const VESTING_PERIOD_SECONDS: usize = APPROVERS + 32 * super::MAX_APPROVERS;
+    pub const VESTING_PERIODS: usize = VESTING_PERIOD_SECONDS + 8;
+    pub const STREAM_START_UNIX_TIMESTAMP: usize = VESTING_PERIODS + 2;
+    pub const STREAM_END_UNIX_TIMESTAMP: usize = STREAM_START_UNIX_TIMESTAMP + 8;
+    pub const STREAM_WITHDRAWN_AMOUNT: usize = STREAM_END_UNIX_TIMESTAMP + 8;
+    pub const CREATED_UNIX_TIMESTAMP: usize = STREAM_WITHDRAWN_AMOUNT + 8;
+    pub const VERSION: usize = CREATED_UNIX_TIMESTAMP + 8;
+    pub const PRICE_NUMERATOR: usize = VERSION + 1;
+    pub const PRICE_DENOMINATOR: usize = PRICE_NUMERATOR + 8;
+    pub const METADATA_LEN: usize = PRICE_DENOMINATOR + 8;
+    pub const METADATA: usize = METADATA_LEN + 1;
+    pub const ESCROW_ID: usize = METADATA + super::MAX_METADATA_LEN;
+    pub const RESERVATION_DEPOSIT: usize = ESCROW_ID + 8;
+    pub const RESERVED_BY: usize = RESERVATION_DEPOSIT + 8;
+    pub const RESERVED_UNTIL_SLOT: usize = RESERVED_BY + 32;
+    pub const TAKER_MERKLE_ROOT: usize = RESERVED_UNTIL_SLOT + 8;
+    pub const COLLECTION: usize = TAKER_MERKLE_ROOT + 32;
+    pub const PAYMENT_OPTION_COUNT: usize = COLLECTION + 32;
+    pub const PAYMENT_OPTION_MINTS: usize = PAYMENT_OPTION_COUNT + 1;
+    pub const PAYMENT_OPTION_TOKEN_ACCOUNTS: usize = PAYMENT_OPTION_MINTS + 32 * super::MAX_PAYMENT_OPTIONS;
+    pub const PAYMENT_OPTION_AMOUNTS: usize = PAYMENT_OPTION_TOKEN_ACCOUNTS + 32 * super::MAX_PAYMENT_OPTIONS;
+    pub const USD_AMOUNT: usize = PAYMENT_OPTION_AMOUNTS + 8 * super::MAX_PAYMENT_OPTIONS;
 }
 
 /// Sealed is just Solana's version of Rust's Sized trait although there does not seem to be any difference between the two. 
 
 impl Sealed for Escrow {}
 
+impl Escrow {
+    /// Decodes the data of an escrow account, e.g. as returned by getAccountInfo. Fails if the data isn't an initialized escrow. Extensions after the base are left out, see extension.
+    pub fn try_from_account_data(data: &[u8]) -> Result<Self, ProgramError> {
+        Self::unpack(data.get(..Escrow::LEN).ok_or(ProgramError::InvalidAccountData)?)
+    }
+
+    /// Borrows the data of an escrow account as an Escrow, without copying it. Fails like Pack::unpack if the data isn't an initialized escrow.
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        let escrow = Self::cast(data)?;
+        if !escrow.is_initialized() {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        Ok(escrow)
+    }
+
+    /// Like load, but the escrow can be updated in place
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        Self::load(data)?;
+        try_from_bytes_mut(data).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Pod accepts any bytes, so this checks what it can't: the flags are 0 or 1, approver_count, metadata_len and payment_option_count are in bounds and initialized escrows start with the discriminator and are of the current version
+    fn cast(data: &[u8]) -> Result<&Self, ProgramError> {
+        let escrow: &Self = try_from_bytes(data).map_err(|_| ProgramError::InvalidAccountData)?;
+        let is_flag = |flag: PodBool| flag.0 <= 1;
+        if !is_flag(escrow.is_initialized)
+            || !is_flag(escrow.is_english_auction)
+            || escrow.approver_count as usize > MAX_APPROVERS
+            || escrow.metadata_len as usize > MAX_METADATA_LEN
+            || escrow.payment_option_count as usize > MAX_PAYMENT_OPTIONS
+            || (escrow.is_initialized() && (escrow.discriminator != ESCROW_DISCRIMINATOR || escrow.version != ESCROW_VERSION))
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(escrow)
+    }
+
+    /// Exchange, Cancel and ReclaimExpired all close the escrow account, so an escrow that still decodes as initialized hasn't been settled yet
+    pub fn is_open(&self) -> bool {
+        self.is_initialized.into()
+    }
+
+    /// The amount of token Y the initializer is still waiting for
+    pub fn remaining_amount(&self) -> u64 {
+        if self.is_open() {
+            self.expected_amount
+        } else {
+            0
+        }
+    }
+
+    /// Private escrows can only be taken by the allowed taker
+    pub fn can_be_taken_by(&self, taker: &Pubkey) -> bool {
+        self.allowed_taker_pubkey == Pubkey::default() || self.allowed_taker_pubkey == *taker
+    }
+
+    /// Whether takers have to prove they are in the escrow's merkle allowlist
+    pub fn has_taker_allowlist(&self) -> bool {
+        self.taker_merkle_root != [0; 32]
+    }
+
+    /// Whether `proof` shows the taker is in the escrow's merkle allowlist. Escrows without one take anyone's proof, including an empty one.
+    pub fn is_taker_in_allowlist(&self, taker: &Pubkey, proof: &[[u8; 32]]) -> bool {
+        !self.has_taker_allowlist() || crate::merkle::verify(&self.taker_merkle_root, taker, proof)
+    }
+
+    /// The metadata the initializer attached, empty if there is none
+    pub fn metadata(&self) -> &[u8] {
+        &self.metadata[..self.metadata_len as usize]
+    }
+
+    /// Only escrows taken with a plain Exchange can be reserved, and only if the initializer asked for a deposit. Delegated escrows can't, since the initializer could revoke the allowance and keep the deposit of a taker who then can't exchange. Neither can escrows with a merkle allowlist, since Reserve doesn't take a proof.
+    pub fn is_reservable(&self) -> bool {
+        self.reservation_deposit != 0
+            && !bool::from(self.is_english_auction)
+            && !self.is_two_phase()
+            && !self.is_stream()
+            && !self.is_delegated()
+            && !self.has_taker_allowlist()
+            && !self.is_collection_offer()
+    }
+
+    /// Whether a reservation gives its taker the escrow to themselves at the given slot
+    pub fn is_reserved(&self, slot: u64) -> bool {
+        self.reserved_by != Pubkey::default() && slot < self.reserved_until_slot
+    }
+
+    /// Rate-based escrows can be taken in part, see price_numerator
+    pub fn is_rate_based(&self) -> bool {
+        self.price_denominator != 0
+    }
+
+    /// The amount of token Y the taker pays for amount_x of token X at the given time: the rate for rate-based escrows, rounded up so the initializer never gets less than their rate, and current_price for all others. None on overflow.
+    pub fn price_of(&self, amount_x: u64, unix_timestamp: i64) -> Option<u64> {
+        if !self.is_rate_based() {
+            return self.current_price(unix_timestamp);
+        }
+        math::mul_div(amount_x, self.price_numerator, self.price_denominator, Rounding::Up)
+    }
+
+    /// What a taker should expect to pay for amount_x at the given time, protocol fee at fee_bps included, for clients to derive an Exchange's max_payment from. Token-2022 transfer fees and relayer tips come on top. None for escrows priced by an oracle or in USD, whose price isn't known until the exchange, and on overflow.
+    pub fn expected_payment(&self, amount_x: u64, fee_bps: u16, unix_timestamp: i64) -> Option<u64> {
+        if self.price_feed != Pubkey::default() || self.is_usd_priced() {
+            return None;
+        }
+        let price = self.price_of(amount_x, unix_timestamp)?;
+        price.checked_add(crate::protocol_fee(price, fee_bps)?)
+    }
+
+    /// The most token X a rate-based escrow gives for `payment` of token Y, rounded down so price_of it is never more than `payment`. Takers of a buy escrow who sell it `payment` of the token it wants pass this to Exchange. None if the escrow isn't rate-based.
+    pub fn amount_x_for(&self, payment: u64) -> Option<u64> {
+        if !self.is_rate_based() {
+            return None;
+        }
+        math::mul_div(payment, self.price_denominator, self.price_numerator, Rounding::Down)
+    }
+
+    /// The amount of token Y the taker has to pay at the given time. None on overflow.
+    pub fn current_price(&self, unix_timestamp: i64) -> Option<u64> {
+        if self.auction_start_amount == 0 || unix_timestamp >= self.auction_end_unix_timestamp {
+            return Some(self.expected_amount);
+        }
+        if unix_timestamp <= self.auction_start_unix_timestamp {
+            return Some(self.auction_start_amount);
+        }
+
+        let elapsed = (unix_timestamp - self.auction_start_unix_timestamp) as u64;
+        let duration = (self.auction_end_unix_timestamp - self.auction_start_unix_timestamp) as u64;
+        let total_decrease = self.auction_start_amount.checked_sub(self.expected_amount)?;
+        let decrease = math::mul_div(total_decrease, elapsed, duration, Rounding::Down)?;
+        self.auction_start_amount.checked_sub(decrease)
+    }
+
+    /// Escrows of wrapped SOL pay out native SOL: the temp token account is simply closed, which unwraps it
+    pub fn holds_native_sol(&self) -> bool {
+        self.token_x_mint == spl_token::native_mint::id()
+    }
+
+    /// Lamport escrows hold plain lamports in the PDA, see InitLamportEscrow. Their token X mint is the system program's id.
+    pub fn holds_lamports(&self) -> bool {
+        self.token_x_mint == system_program::id()
+    }
+
+    /// Delegated escrows leave the tokens with the initializer and only hold an allowance on them
+    pub fn is_delegated(&self) -> bool {
+        self.delegated_amount != 0
+    }
+
+    /// The amount of token X the escrow offers, given the account temp_token_account_pubkey points to. A delegated escrow offers its allowance, not the whole balance of the initializer's account.
+    pub fn escrowed_amount(&self, temp_token_account: &TokenAccount) -> u64 {
+        if self.is_delegated() {
+            self.delegated_amount
+        } else {
+            temp_token_account.amount
+        }
+    }
+
+    /// Two-phase escrows are taken with Accept and Settle instead of Exchange
+    pub fn is_two_phase(&self) -> bool {
+        self.dispute_window_seconds != 0
+    }
+
+    /// An accepted escrow can no longer be cancelled, reclaimed or updated, only settled
+    pub fn is_accepted(&self) -> bool {
+        self.accepted_unix_timestamp != 0
+    }
+
+    /// The approver's position in approvers, which is also their bit in approvals
+    pub fn approver_index(&self, approver: &Pubkey) -> Option<usize> {
+        self.approvers[..self.approver_count as usize]
+            .iter()
+            .position(|candidate| candidate == approver)
+    }
+
+    /// Whether enough approvers have approved for the tokens to be released
+    pub fn is_release_approved(&self) -> bool {
+        self.approvals.count_ones() >= self.approval_threshold as u32
+    }
+
+    /// Vesting escrows pay the taker through a vesting vault
+    pub fn is_vesting(&self) -> bool {
+        self.vesting_periods != 0
+    }
+
+    /// Streaming escrows are paid out with Withdraw instead of being exchanged
+    pub fn is_stream(&self) -> bool {
+        self.stream_end_unix_timestamp != 0
+    }
+
+    /// Collection offers are filled with FillCollectionOffer instead of being exchanged
+    pub fn is_collection_offer(&self) -> bool {
+        self.collection != Pubkey::default()
+    }
+
+    // Escrows with a fixed price or rate that are taken by a plain Exchange
+    fn is_plainly_priced(&self) -> bool {
+        self.auction_start_amount == 0
+            && !bool::from(self.is_english_auction)
+            && self.price_feed == Pubkey::default()
+            && !self.is_two_phase()
+            && !self.is_stream()
+            && !self.is_collection_offer()
+    }
+
+    /// Payment options only change which mint a plain Exchange pays in. Escrows priced any other way, or taken by anything but Exchange, can't have them.
+    pub fn can_have_payment_options(&self) -> bool {
+        self.is_plainly_priced() && !self.is_usd_priced()
+    }
+
+    /// USD-priced escrows are paid in any listed stablecoin, see SetUsdPrice
+    pub fn is_usd_priced(&self) -> bool {
+        self.usd_amount != 0
+    }
+
+    /// A USD price is for the whole escrow and takes the place of its payment options
+    pub fn can_be_usd_priced(&self) -> bool {
+        self.is_plainly_priced() && !self.is_rate_based() && self.payment_option_count == 0
+    }
+
+    /// The mints the escrow accepts as payment besides token Y
+    pub fn payment_option_mints(&self) -> &[Pubkey] {
+        &self.payment_option_mints[..self.payment_option_count as usize]
+    }
+
+    /// The escrow as it is taken by a taker paying in `mint`: for a payment option, token Y, the initializer's token account to receive it and the price are the option's. None if the escrow doesn't accept the mint.
+    pub fn paid_in(&self, mint: &Pubkey) -> Option<Escrow> {
+        if *mint == self.token_y_mint {
+            return Some(*self);
+        }
+        let index = self.payment_option_mints().iter().position(|option_mint| option_mint == mint)?;
+        let token_accounts = self.payment_option_token_accounts;
+        let amounts = self.payment_option_amounts;
+        let mut escrow = *self;
+        escrow.token_y_mint = *mint;
+        escrow.initializer_token_to_receive_account_pubkey = token_accounts[index];
+        if self.is_rate_based() {
+            escrow.price_numerator = amounts[index];
+        } else {
+            escrow.expected_amount = amounts[index];
+        }
+        Some(escrow)
+    }
+
+    /// The part of a stream the recipient can withdraw at unix_timestamp, given how much is left in the temp token account
+    pub fn withdrawable_amount(&self, temp_token_account: &TokenAccount, unix_timestamp: i64) -> u64 {
+        let total_amount = temp_token_account.amount.saturating_add(self.stream_withdrawn_amount);
+        crate::vesting::streamed_amount(
+            total_amount,
+            self.stream_start_unix_timestamp,
+            self.stream_end_unix_timestamp,
+            unix_timestamp,
+        )
+        .saturating_sub(self.stream_withdrawn_amount)
+    }
+
+    /// Whether the escrow is settled by a plain Exchange, with nothing but the price to agree on. Only those can be matched against each other.
+    pub fn can_be_matched(&self) -> bool {
+        !bool::from(self.is_english_auction)
+            && !self.is_two_phase()
+            && !self.is_stream()
+            && !self.is_vesting()
+            && self.price_feed == Pubkey::default()
+            && !self.is_rate_based()
+            && !self.has_taker_allowlist()
+            && !self.is_collection_offer()
+            && !self.holds_lamports()
+    }
+
+    /// An expiry of 0 means the escrow stays open until it is taken or cancelled
+    pub fn is_expired(&self, unix_timestamp: i64) -> bool {
+        self.expiry_unix_timestamp != 0 && unix_timestamp >= self.expiry_unix_timestamp
+    }
+
+    /// Whether the escrow can never be exchanged, given the account temp_token_account_pubkey points to (None if it was closed) and the escrow's PDA. That is when the temp token account is empty or no longer the PDA's, or, for a delegated escrow, when the PDA's allowance was revoked or cut below the escrowed amount. Accepted escrows and auctions with bids are waiting to be settled, so they never are.
+    pub fn is_stale(&self, temp_token_account: Option<&TokenAccount>, pda: &Pubkey) -> bool {
+        if self.is_accepted() || self.highest_bid_amount != 0 {
+            return false;
+        }
+        let temp_token_account = match temp_token_account {
+            Some(temp_token_account) => temp_token_account,
+            None => return true,
+        };
+        if self.is_delegated() {
+            temp_token_account.delegate != COption::Some(*pda) || temp_token_account.delegated_amount < self.delegated_amount
+        } else {
+            temp_token_account.owner != *pda || temp_token_account.amount == 0
+        }
+    }
+}
+
 impl IsInitialized for Escrow {
     fn is_initialized(&self) -> bool {
-        self.is_initialized
+        self.is_initialized.into()
     }
 }
 
 impl Pack for Escrow {
-    // Calculating the size of our type
-    const LEN: usize = 105;
+    // Calculating the size of our type. With repr(C, packed) that is just the sum of the field sizes.
+    const LEN: usize = size_of::<Escrow>();
     // Deserialize
-    // Turns an array of u8 into an instance of the Escrow struct we defined above
-    // Static constructor function that returns  a new instance of an escrow struct. 
+    // Turns an array of u8 into an instance of the Escrow struct we defined above. Since Escrow is Pod this is a single copy
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let src = array_ref![src, 0, Escrow::LEN];
-        let (
-            is_initialized,
-            initializer_pubkey,
-            temp_token_account_pubkey,
-            initializer_token_to_receive_account_pubkey,
-            expected_amount,
-        ) = array_refs![src, 1, 32, 32, 32, 8];
+        Self::cast(src.get(..Escrow::LEN).ok_or(ProgramError::InvalidAccountData)?).copied()
+    }
+
+    // Serialize
+    // Self = Escrow Struct, serialize it into a dst slice 
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[..Escrow::LEN].copy_from_slice(bytes_of(self));
+    }
+}
+
+impl BorshSerialize for Escrow {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(bytes_of(self))
+    }
+}
+
+impl BorshDeserialize for Escrow {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let src = buf
+            .get(..Escrow::LEN)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "not enough bytes for an escrow"))?;
+        let escrow = Self::unpack_from_slice(src).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        *buf = &buf[Escrow::LEN..];
+        Ok(escrow)
+    }
+}
+
+/// The escrow layout of the first deployment, before escrows had an expiry, stored their mints or had a PDA of their own. All of their temp token accounts are owned by the PDA derived from `[b"escrow"]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EscrowV1 {
+    pub is_initialized: bool,
+    pub initializer_pubkey: Pubkey,
+    pub temp_token_account_pubkey: Pubkey,
+    pub initializer_token_to_receive_account_pubkey: Pubkey,
+    pub expected_amount: u64,
+}
+
+/// The current layout
+pub type EscrowV2 = Escrow;
+
+impl Sealed for EscrowV1 {}
+
+impl IsInitialized for EscrowV1 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for EscrowV1 {
+    const LEN: usize = 105;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, EscrowV1::LEN];
+        let (is_initialized, initializer_pubkey, temp_token_account_pubkey, initializer_token_to_receive_account_pubkey, expected_amount) =
+            array_refs![src, 1, 32, 32, 32, 8];
         let is_initialized = match is_initialized {
             [0] => false,
             [1] => true,
             _ => return Err(ProgramError::InvalidAccountData),
         };
 
-        Ok(Escrow {
+        Ok(EscrowV1 {
             is_initialized,
             initializer_pubkey: Pubkey::new_from_array(*initializer_pubkey),
             temp_token_account_pubkey: Pubkey::new_from_array(*temp_token_account_pubkey),
@@ -56,30 +588,1577 @@ impl Pack for Escrow {
         })
     }
 
-    // Serialize
-    // Self = Escrow Struct, serialize it into a dst slice 
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let dst = array_mut_ref![dst, 0, Escrow::LEN];
+        let dst = array_mut_ref![dst, 0, EscrowV1::LEN];
+        let (is_initialized_dst, initializer_pubkey_dst, temp_token_account_pubkey_dst, initializer_token_to_receive_account_pubkey_dst, expected_amount_dst) =
+            mut_array_refs![dst, 1, 32, 32, 32, 8];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        initializer_pubkey_dst.copy_from_slice(self.initializer_pubkey.as_ref());
+        temp_token_account_pubkey_dst.copy_from_slice(self.temp_token_account_pubkey.as_ref());
+        initializer_token_to_receive_account_pubkey_dst.copy_from_slice(self.initializer_token_to_receive_account_pubkey.as_ref());
+        *expected_amount_dst = self.expected_amount.to_le_bytes();
+    }
+}
+
+/// An escrow account of any layout the program has had. The layouts have different sizes, which is how they are told apart: V1 has neither a discriminator nor a version. Only V2 escrows can be used, V1 ones have to go through MigrateEscrow first.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VersionedEscrow {
+    V1(EscrowV1),
+    V2(EscrowV2),
+}
+
+impl VersionedEscrow {
+    /// Decodes an initialized escrow account of any version
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        match data.len() {
+            EscrowV1::LEN => Ok(VersionedEscrow::V1(EscrowV1::unpack(data)?)),
+            EscrowV2::LEN => Ok(VersionedEscrow::V2(EscrowV2::unpack(data)?)),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    pub fn version(&self) -> u8 {
+        match self {
+            VersionedEscrow::V1(_) => 1,
+            VersionedEscrow::V2(escrow) => escrow.version,
+        }
+    }
+}
+
+/// The program-wide settings, stored in a single account at the PDA derived from `[b"config"]`. Like Escrow, its Borsh encoding matches the Pack layout.
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, ShankAccount, Clone, Copy, Debug, PartialEq)]
+pub struct Config {
+    pub is_initialized: bool,
+    /// The only account allowed to change the config
+    pub admin: Pubkey,
+    /// The protocol fee in basis points, charged to the taker in token Y on top of the escrow's expected amount. 0 disables the fee.
+    pub fee_bps: u16,
+    pub paused: bool,
+    /// How long, in seconds, an escrow stays open if InitEscrow doesn't set an expiry. 0 means such escrows never expire.
+    pub default_expiry_seconds: i64,
+    /// The bump seed of the config PDA
+    pub bump_seed: u8,
+    /// If set, InitEscrow only accepts mints that are on the MintAllowlist
+    pub allowlist_enabled: bool,
+    /// The share of the protocol fee, in basis points of the fee, that goes to the referrer when an Exchange names one
+    pub referral_share_bps: u16,
+    /// The owner of the token accounts protocol fees are paid into. The default pubkey means the fee vaults, see InitFeeVault.
+    pub fee_collector: Pubkey,
+    /// The share of the returned tokens, in basis points, that an initializer forfeits when cancelling an escrow
+    pub cancel_penalty_bps: u16,
+    /// A flat amount of token X, in the mint's smallest unit, forfeited on Cancel on top of cancel_penalty_bps
+    pub cancel_penalty_amount: u64,
+    /// If set, the penalty on a private escrow goes to its allowed taker instead of the fee collector
+    pub cancel_penalty_to_counterparty: bool,
+    /// The program asked to approve every taker before they take an escrow, see gate. The default pubkey means there is no gate.
+    pub gate_program: Pubkey,
+    /// How far ahead, in seconds, an escrow's expiry may be when it is set or extended. 0 means there is no limit.
+    pub max_expiry_seconds: i64,
+}
+
+/// The highest fee the config accepts, 5%
+pub const MAX_FEE_BPS: u16 = 500;
+
+impl Sealed for Config {}
+
+impl IsInitialized for Config {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Config {
+    /// The part of `returned_amount` an initializer forfeits by cancelling. Never more than what they would get back.
+    pub fn cancel_penalty(&self, returned_amount: u64) -> u64 {
+        let share = math::bps_of(returned_amount, self.cancel_penalty_bps, Rounding::Down).unwrap_or(returned_amount);
+        share.saturating_add(self.cancel_penalty_amount).min(returned_amount)
+    }
+
+    /// The owner of the token account the penalty for cancelling `escrow` is paid into. `fee_authority` is the PDA derived by find_fee_authority_address.
+    pub fn cancel_penalty_recipient(&self, escrow: &Escrow, fee_authority: &Pubkey) -> Pubkey {
+        if self.cancel_penalty_to_counterparty && escrow.allowed_taker_pubkey != Pubkey::default() {
+            escrow.allowed_taker_pubkey
+        } else if self.fee_collector != Pubkey::default() {
+            self.fee_collector
+        } else {
+            *fee_authority
+        }
+    }
+}
+
+impl Pack for Config {
+    const LEN: usize = 131;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Config::LEN];
+        let (
+            is_initialized,
+            admin,
+            fee_bps,
+            paused,
+            default_expiry_seconds,
+            bump_seed,
+            allowlist_enabled,
+            referral_share_bps,
+            fee_collector,
+            cancel_penalty_bps,
+            cancel_penalty_amount,
+            cancel_penalty_to_counterparty,
+            gate_program,
+            max_expiry_seconds,
+        ) = array_refs![src, 1, 32, 2, 1, 8, 1, 1, 2, 32, 2, 8, 1, 32, 8];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let paused = match paused {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let allowlist_enabled = match allowlist_enabled {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        let cancel_penalty_to_counterparty = match cancel_penalty_to_counterparty {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Config {
+            is_initialized,
+            admin: Pubkey::new_from_array(*admin),
+            fee_bps: u16::from_le_bytes(*fee_bps),
+            paused,
+            default_expiry_seconds: i64::from_le_bytes(*default_expiry_seconds),
+            bump_seed: bump_seed[0],
+            allowlist_enabled,
+            referral_share_bps: u16::from_le_bytes(*referral_share_bps),
+            fee_collector: Pubkey::new_from_array(*fee_collector),
+            cancel_penalty_bps: u16::from_le_bytes(*cancel_penalty_bps),
+            cancel_penalty_amount: u64::from_le_bytes(*cancel_penalty_amount),
+            cancel_penalty_to_counterparty,
+            gate_program: Pubkey::new_from_array(*gate_program),
+            max_expiry_seconds: i64::from_le_bytes(*max_expiry_seconds),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Config::LEN];
         let (
             is_initialized_dst,
-            initializer_pubkey_dst,
-            temp_token_account_pubkey_dst,
-            initializer_token_to_receive_account_pubkey_dst,
-            expected_amount_dst,
-        ) = mut_array_refs![dst, 1, 32, 32, 32, 8];
+            admin_dst,
+            fee_bps_dst,
+            paused_dst,
+            default_expiry_seconds_dst,
+            bump_seed_dst,
+            allowlist_enabled_dst,
+            referral_share_bps_dst,
+            fee_collector_dst,
+            cancel_penalty_bps_dst,
+            cancel_penalty_amount_dst,
+            cancel_penalty_to_counterparty_dst,
+            gate_program_dst,
+            max_expiry_seconds_dst,
+        ) = mut_array_refs![dst, 1, 32, 2, 1, 8, 1, 1, 2, 32, 2, 8, 1, 32, 8];
 
-        let Escrow {
+        let Config {
             is_initialized,
-            initializer_pubkey,
-            temp_token_account_pubkey,
-            initializer_token_to_receive_account_pubkey,
-            expected_amount,
+            admin,
+            fee_bps,
+            paused,
+            default_expiry_seconds,
+            bump_seed,
+            allowlist_enabled,
+            referral_share_bps,
+            fee_collector,
+            cancel_penalty_bps,
+            cancel_penalty_amount,
+            cancel_penalty_to_counterparty,
+            gate_program,
+            max_expiry_seconds,
         } = self;
 
         is_initialized_dst[0] = *is_initialized as u8;
-        initializer_pubkey_dst.copy_from_slice(initializer_pubkey.as_ref());
-        temp_token_account_pubkey_dst.copy_from_slice(temp_token_account_pubkey.as_ref());
-        initializer_token_to_receive_account_pubkey_dst.copy_from_slice(initializer_token_to_receive_account_pubkey.as_ref());
-        *expected_amount_dst = expected_amount.to_le_bytes();
+        admin_dst.copy_from_slice(admin.as_ref());
+        *fee_bps_dst = fee_bps.to_le_bytes();
+        paused_dst[0] = *paused as u8;
+        *default_expiry_seconds_dst = default_expiry_seconds.to_le_bytes();
+        bump_seed_dst[0] = *bump_seed;
+        allowlist_enabled_dst[0] = *allowlist_enabled as u8;
+        *referral_share_bps_dst = referral_share_bps.to_le_bytes();
+        fee_collector_dst.copy_from_slice(fee_collector.as_ref());
+        *cancel_penalty_bps_dst = cancel_penalty_bps.to_le_bytes();
+        *cancel_penalty_amount_dst = cancel_penalty_amount.to_le_bytes();
+        cancel_penalty_to_counterparty_dst[0] = *cancel_penalty_to_counterparty as u8;
+        gate_program_dst.copy_from_slice(gate_program.as_ref());
+        *max_expiry_seconds_dst = max_expiry_seconds.to_le_bytes();
     }
-}
\ No newline at end of file
+}
+
+/// The schedule of a vesting escrow's tokens after it has been exchanged, stored at the PDA derived from `[b"vesting", escrow account pubkey]`. The escrow itself is closed by then. Like Escrow, its Borsh encoding matches the Pack layout.
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, ShankAccount, Clone, Copy, Debug, PartialEq)]
+pub struct Vesting {
+    pub is_initialized: bool,
+    /// The bump seed of the vesting PDA, which signs for the vault
+    pub bump_seed: u8,
+    /// The escrow the tokens come from, part of the vesting PDA's seeds
+    pub escrow: Pubkey,
+    /// The taker, who the vested tokens are released to
+    pub beneficiary: Pubkey,
+    /// The token account holding the tokens that haven't been claimed yet, derived from `[b"vesting_vault", escrow account pubkey]`
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub start_unix_timestamp: i64,
+    pub period_seconds: i64,
+    pub periods: u16,
+}
+
+impl Sealed for Vesting {}
+
+impl Vesting {
+    /// The amount that has vested but hasn't been claimed yet
+    pub fn claimable_amount(&self, unix_timestamp: i64) -> u64 {
+        crate::vesting::vested_amount(
+            self.total_amount,
+            self.start_unix_timestamp,
+            self.period_seconds,
+            self.periods,
+            unix_timestamp,
+        )
+        .saturating_sub(self.claimed_amount)
+    }
+}
+
+impl IsInitialized for Vesting {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Vesting {
+    const LEN: usize = 164;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Vesting::LEN];
+        let (
+            is_initialized,
+            bump_seed,
+            escrow,
+            beneficiary,
+            vault,
+            mint,
+            total_amount,
+            claimed_amount,
+            start_unix_timestamp,
+            period_seconds,
+            periods,
+        ) = array_refs![src, 1, 1, 32, 32, 32, 32, 8, 8, 8, 8, 2];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Vesting {
+            is_initialized,
+            bump_seed: bump_seed[0],
+            escrow: Pubkey::new_from_array(*escrow),
+            beneficiary: Pubkey::new_from_array(*beneficiary),
+            vault: Pubkey::new_from_array(*vault),
+            mint: Pubkey::new_from_array(*mint),
+            total_amount: u64::from_le_bytes(*total_amount),
+            claimed_amount: u64::from_le_bytes(*claimed_amount),
+            start_unix_timestamp: i64::from_le_bytes(*start_unix_timestamp),
+            period_seconds: i64::from_le_bytes(*period_seconds),
+            periods: u16::from_le_bytes(*periods),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Vesting::LEN];
+        let (
+            is_initialized_dst,
+            bump_seed_dst,
+            escrow_dst,
+            beneficiary_dst,
+            vault_dst,
+            mint_dst,
+            total_amount_dst,
+            claimed_amount_dst,
+            start_unix_timestamp_dst,
+            period_seconds_dst,
+            periods_dst,
+        ) = mut_array_refs![dst, 1, 1, 32, 32, 32, 32, 8, 8, 8, 8, 2];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        escrow_dst.copy_from_slice(self.escrow.as_ref());
+        beneficiary_dst.copy_from_slice(self.beneficiary.as_ref());
+        vault_dst.copy_from_slice(self.vault.as_ref());
+        mint_dst.copy_from_slice(self.mint.as_ref());
+        *total_amount_dst = self.total_amount.to_le_bytes();
+        *claimed_amount_dst = self.claimed_amount.to_le_bytes();
+        *start_unix_timestamp_dst = self.start_unix_timestamp.to_le_bytes();
+        *period_seconds_dst = self.period_seconds.to_le_bytes();
+        *periods_dst = self.periods.to_le_bytes();
+    }
+}
+
+/// How many mints fit on the allowlist. MintAllowlist spells the array length out because shank can only read literals.
+pub const MAX_ALLOWED_MINTS: usize = 32;
+
+/// The mints escrows can trade while the config's allowlist_enabled is set, stored at the PDA derived from `[b"allowlist"]`. The first `count` entries of `mints` are in use.
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, ShankAccount, Clone, Debug, PartialEq)]
+pub struct MintAllowlist {
+    pub is_initialized: bool,
+    /// The bump seed of the allowlist PDA
+    pub bump_seed: u8,
+    pub count: u8,
+    pub mints: [Pubkey; 32],
+}
+
+impl Sealed for MintAllowlist {}
+
+impl MintAllowlist {
+    pub fn contains(&self, mint: &Pubkey) -> bool {
+        self.mints[..self.count as usize].contains(mint)
+    }
+}
+
+impl IsInitialized for MintAllowlist {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for MintAllowlist {
+    const LEN: usize = 3 + 32 * MAX_ALLOWED_MINTS;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, MintAllowlist::LEN];
+        let (is_initialized, bump_seed, count, mints_src) = array_refs![src, 1, 1, 1, 32 * MAX_ALLOWED_MINTS];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        if count[0] as usize > MAX_ALLOWED_MINTS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut mints = [Pubkey::default(); MAX_ALLOWED_MINTS];
+        for (mint, mint_src) in mints.iter_mut().zip(mints_src.chunks_exact(32)) {
+            *mint = Pubkey::new_from_array(*array_ref![mint_src, 0, 32]);
+        }
+
+        Ok(MintAllowlist {
+            is_initialized,
+            bump_seed: bump_seed[0],
+            count: count[0],
+            mints,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, MintAllowlist::LEN];
+        let (is_initialized_dst, bump_seed_dst, count_dst, mints_dst) =
+            mut_array_refs![dst, 1, 1, 1, 32 * MAX_ALLOWED_MINTS];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        count_dst[0] = self.count;
+        for (mint, mint_dst) in self.mints.iter().zip(mints_dst.chunks_exact_mut(32)) {
+            mint_dst.copy_from_slice(mint.as_ref());
+        }
+    }
+}
+
+/// How many stablecoins fit on the StablecoinList. StablecoinList spells the array lengths out because shank can only read literals.
+pub const MAX_STABLECOINS: usize = 8;
+
+/// The mints USD-priced escrows are paid in, stored at the PDA derived from `[b"stablecoins"]`, each with the Pyth price account quoting it in USD and how far, in basis points, its price may stray from one dollar before Exchange stops taking it. The first `count` entries are in use.
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, ShankAccount, Clone, Debug, PartialEq)]
+pub struct StablecoinList {
+    pub is_initialized: bool,
+    /// The bump seed of the stablecoin list PDA
+    pub bump_seed: u8,
+    pub count: u8,
+    pub mints: [Pubkey; 8],
+    pub price_feeds: [Pubkey; 8],
+    pub tolerance_bps: [u16; 8],
+}
+
+impl Sealed for StablecoinList {}
+
+impl StablecoinList {
+    /// The price feed and tolerance of a listed mint
+    pub fn get(&self, mint: &Pubkey) -> Option<(Pubkey, u16)> {
+        let index = self.mints[..self.count as usize].iter().position(|listed_mint| listed_mint == mint)?;
+        Some((self.price_feeds[index], self.tolerance_bps[index]))
+    }
+}
+
+impl IsInitialized for StablecoinList {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for StablecoinList {
+    const LEN: usize = 3 + (32 + 32 + 2) * MAX_STABLECOINS;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, StablecoinList::LEN];
+        let (is_initialized, bump_seed, count, mints_src, price_feeds_src, tolerance_bps_src) =
+            array_refs![src, 1, 1, 1, 32 * MAX_STABLECOINS, 32 * MAX_STABLECOINS, 2 * MAX_STABLECOINS];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        if count[0] as usize > MAX_STABLECOINS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut mints = [Pubkey::default(); MAX_STABLECOINS];
+        for (mint, mint_src) in mints.iter_mut().zip(mints_src.chunks_exact(32)) {
+            *mint = Pubkey::new_from_array(*array_ref![mint_src, 0, 32]);
+        }
+        let mut price_feeds = [Pubkey::default(); MAX_STABLECOINS];
+        for (price_feed, price_feed_src) in price_feeds.iter_mut().zip(price_feeds_src.chunks_exact(32)) {
+            *price_feed = Pubkey::new_from_array(*array_ref![price_feed_src, 0, 32]);
+        }
+        let mut tolerance_bps = [0u16; MAX_STABLECOINS];
+        for (tolerance, tolerance_src) in tolerance_bps.iter_mut().zip(tolerance_bps_src.chunks_exact(2)) {
+            *tolerance = u16::from_le_bytes(*array_ref![tolerance_src, 0, 2]);
+        }
+
+        Ok(StablecoinList {
+            is_initialized,
+            bump_seed: bump_seed[0],
+            count: count[0],
+            mints,
+            price_feeds,
+            tolerance_bps,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, StablecoinList::LEN];
+        let (is_initialized_dst, bump_seed_dst, count_dst, mints_dst, price_feeds_dst, tolerance_bps_dst) =
+            mut_array_refs![dst, 1, 1, 1, 32 * MAX_STABLECOINS, 32 * MAX_STABLECOINS, 2 * MAX_STABLECOINS];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        count_dst[0] = self.count;
+        for (mint, mint_dst) in self.mints.iter().zip(mints_dst.chunks_exact_mut(32)) {
+            mint_dst.copy_from_slice(mint.as_ref());
+        }
+        for (price_feed, price_feed_dst) in self.price_feeds.iter().zip(price_feeds_dst.chunks_exact_mut(32)) {
+            price_feed_dst.copy_from_slice(price_feed.as_ref());
+        }
+        for (tolerance, tolerance_dst) in self.tolerance_bps.iter().zip(tolerance_bps_dst.chunks_exact_mut(2)) {
+            tolerance_dst.copy_from_slice(&tolerance.to_le_bytes());
+        }
+    }
+}
+
+/// How many open escrows fit in a registry. EscrowRegistry spells the array length out because shank can only read literals.
+pub const MAX_REGISTERED_ESCROWS: usize = 64;
+
+/// The open escrows that offer token_x_mint for token_y_mint, stored at the PDA derived from `[b"registry", token X mint, token Y mint]`. Clients can read the offers of a pair from here instead of scanning all of the program's accounts. InitEscrow adds escrows to it and Exchange, Cancel and ReclaimExpired remove them, but only when the registry is passed to them. The first `count` entries of `escrows` are in use, in no particular order.
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, ShankAccount, Clone, Debug, PartialEq)]
+pub struct EscrowRegistry {
+    pub is_initialized: bool,
+    /// The bump seed of the registry PDA
+    pub bump_seed: u8,
+    pub token_x_mint: Pubkey,
+    pub token_y_mint: Pubkey,
+    pub count: u8,
+    pub escrows: [Pubkey; 64],
+}
+
+impl Sealed for EscrowRegistry {}
+
+impl EscrowRegistry {
+    pub fn new(token_x_mint: Pubkey, token_y_mint: Pubkey, bump_seed: u8) -> Self {
+        EscrowRegistry {
+            is_initialized: true,
+            bump_seed,
+            token_x_mint,
+            token_y_mint,
+            count: 0,
+            escrows: [Pubkey::default(); MAX_REGISTERED_ESCROWS],
+        }
+    }
+
+    pub fn contains(&self, escrow: &Pubkey) -> bool {
+        self.escrows[..self.count as usize].contains(escrow)
+    }
+
+    pub fn insert(&mut self, escrow: Pubkey) -> Result<(), ProgramError> {
+        if self.count as usize == MAX_REGISTERED_ESCROWS {
+            return Err(EscrowError::RegistryFull.into());
+        }
+        self.escrows[self.count as usize] = escrow;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Removes the escrow by moving the last entry into its place. Returns false if it wasn't registered.
+    pub fn remove(&mut self, escrow: &Pubkey) -> bool {
+        let index = match self.escrows[..self.count as usize].iter().position(|registered| registered == escrow) {
+            Some(index) => index,
+            None => return false,
+        };
+        self.count -= 1;
+        self.escrows[index] = self.escrows[self.count as usize];
+        self.escrows[self.count as usize] = Pubkey::default();
+        true
+    }
+}
+
+impl IsInitialized for EscrowRegistry {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for EscrowRegistry {
+    const LEN: usize = 67 + 32 * MAX_REGISTERED_ESCROWS;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, EscrowRegistry::LEN];
+        let (is_initialized, bump_seed, token_x_mint, token_y_mint, count, escrows_src) =
+            array_refs![src, 1, 1, 32, 32, 1, 32 * MAX_REGISTERED_ESCROWS];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        if count[0] as usize > MAX_REGISTERED_ESCROWS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut escrows = [Pubkey::default(); MAX_REGISTERED_ESCROWS];
+        for (escrow, escrow_src) in escrows.iter_mut().zip(escrows_src.chunks_exact(32)) {
+            *escrow = Pubkey::new_from_array(*array_ref![escrow_src, 0, 32]);
+        }
+
+        Ok(EscrowRegistry {
+            is_initialized,
+            bump_seed: bump_seed[0],
+            token_x_mint: Pubkey::new_from_array(*token_x_mint),
+            token_y_mint: Pubkey::new_from_array(*token_y_mint),
+            count: count[0],
+            escrows,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, EscrowRegistry::LEN];
+        let (is_initialized_dst, bump_seed_dst, token_x_mint_dst, token_y_mint_dst, count_dst, escrows_dst) =
+            mut_array_refs![dst, 1, 1, 32, 32, 1, 32 * MAX_REGISTERED_ESCROWS];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        token_x_mint_dst.copy_from_slice(self.token_x_mint.as_ref());
+        token_y_mint_dst.copy_from_slice(self.token_y_mint.as_ref());
+        count_dst[0] = self.count;
+        for (escrow, escrow_dst) in self.escrows.iter().zip(escrows_dst.chunks_exact_mut(32)) {
+            escrow_dst.copy_from_slice(escrow.as_ref());
+        }
+    }
+}
+
+/// What one taker got out of a rate-based escrow, stored at the PDA derived from `[b"fill", escrow account pubkey, taker pubkey]`. Partial fills leave no other trace once the escrow moves on or closes, so Exchange keeps this record when it is given one, adding up the taker's fills.
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, ShankAccount, Clone, Copy, Debug, Default, PartialEq)]
+pub struct FillRecord {
+    pub is_initialized: bool,
+    /// The bump seed of the fill record PDA
+    pub bump_seed: u8,
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    /// The amount of token X the taker received, over all their fills
+    pub amount: u64,
+    /// The amount of token Y the taker paid for it, before fees
+    pub price: u64,
+    pub fills: u32,
+    /// The slot of the taker's latest fill
+    pub slot: u64,
+}
+
+impl Sealed for FillRecord {}
+
+impl FillRecord {
+    pub fn record(&mut self, amount_x: u64, amount_y: u64, slot: u64) {
+        self.amount = self.amount.saturating_add(amount_x);
+        self.price = self.price.saturating_add(amount_y);
+        self.fills = self.fills.saturating_add(1);
+        self.slot = slot;
+    }
+}
+
+impl IsInitialized for FillRecord {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for FillRecord {
+    const LEN: usize = 94;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, FillRecord::LEN];
+        let (is_initialized, bump_seed, escrow, taker, amount, price, fills, slot) =
+            array_refs![src, 1, 1, 32, 32, 8, 8, 4, 8];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(FillRecord {
+            is_initialized,
+            bump_seed: bump_seed[0],
+            escrow: Pubkey::new_from_array(*escrow),
+            taker: Pubkey::new_from_array(*taker),
+            amount: u64::from_le_bytes(*amount),
+            price: u64::from_le_bytes(*price),
+            fills: u32::from_le_bytes(*fills),
+            slot: u64::from_le_bytes(*slot),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, FillRecord::LEN];
+        let (is_initialized_dst, bump_seed_dst, escrow_dst, taker_dst, amount_dst, price_dst, fills_dst, slot_dst) =
+            mut_array_refs![dst, 1, 1, 32, 32, 8, 8, 4, 8];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        escrow_dst.copy_from_slice(self.escrow.as_ref());
+        taker_dst.copy_from_slice(self.taker.as_ref());
+        *amount_dst = self.amount.to_le_bytes();
+        *price_dst = self.price.to_le_bytes();
+        *fills_dst = self.fills.to_le_bytes();
+        *slot_dst = self.slot.to_le_bytes();
+    }
+}
+
+/// Protocol-wide activity, stored at the PDA derived from `[b"stats"]`, so dashboards don't have to replay the program's history. See InitStats for the instructions that update it. Volumes add up the raw amounts of all mints.
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, ShankAccount, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub is_initialized: bool,
+    /// The bump seed of the stats PDA
+    pub bump_seed: u8,
+    pub escrows_created: u64,
+    /// Escrows that were exchanged in full. Partial fills of rate-based escrows only count towards the volume until the last one.
+    pub escrows_filled: u64,
+    /// Escrows that were cancelled or reclaimed after their expiry
+    pub escrows_cancelled: u64,
+    /// The amount of token X put into escrows
+    pub created_volume: u128,
+    /// The amounts of token X and token Y that changed hands in exchanges
+    pub exchanged_volume_x: u128,
+    pub exchanged_volume_y: u128,
+    /// The amount of token X returned to initializers
+    pub cancelled_volume: u128,
+}
+
+impl Sealed for Stats {}
+
+// The counters saturate instead of failing the instruction they are updated in
+impl Stats {
+    pub fn record_created(&mut self, amount_x: u64) {
+        self.escrows_created = self.escrows_created.saturating_add(1);
+        self.created_volume = self.created_volume.saturating_add(amount_x as u128);
+    }
+
+    pub fn record_exchange(&mut self, amount_x: u64, amount_y: u64, filled: bool) {
+        if filled {
+            self.escrows_filled = self.escrows_filled.saturating_add(1);
+        }
+        self.exchanged_volume_x = self.exchanged_volume_x.saturating_add(amount_x as u128);
+        self.exchanged_volume_y = self.exchanged_volume_y.saturating_add(amount_y as u128);
+    }
+
+    pub fn record_cancelled(&mut self, amount_x: u64) {
+        self.escrows_cancelled = self.escrows_cancelled.saturating_add(1);
+        self.cancelled_volume = self.cancelled_volume.saturating_add(amount_x as u128);
+    }
+}
+
+impl IsInitialized for Stats {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Stats {
+    const LEN: usize = 90;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Stats::LEN];
+        let (
+            is_initialized,
+            bump_seed,
+            escrows_created,
+            escrows_filled,
+            escrows_cancelled,
+            created_volume,
+            exchanged_volume_x,
+            exchanged_volume_y,
+            cancelled_volume,
+        ) = array_refs![src, 1, 1, 8, 8, 8, 16, 16, 16, 16];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Stats {
+            is_initialized,
+            bump_seed: bump_seed[0],
+            escrows_created: u64::from_le_bytes(*escrows_created),
+            escrows_filled: u64::from_le_bytes(*escrows_filled),
+            escrows_cancelled: u64::from_le_bytes(*escrows_cancelled),
+            created_volume: u128::from_le_bytes(*created_volume),
+            exchanged_volume_x: u128::from_le_bytes(*exchanged_volume_x),
+            exchanged_volume_y: u128::from_le_bytes(*exchanged_volume_y),
+            cancelled_volume: u128::from_le_bytes(*cancelled_volume),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Stats::LEN];
+        let (
+            is_initialized_dst,
+            bump_seed_dst,
+            escrows_created_dst,
+            escrows_filled_dst,
+            escrows_cancelled_dst,
+            created_volume_dst,
+            exchanged_volume_x_dst,
+            exchanged_volume_y_dst,
+            cancelled_volume_dst,
+        ) = mut_array_refs![dst, 1, 1, 8, 8, 8, 16, 16, 16, 16];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        *escrows_created_dst = self.escrows_created.to_le_bytes();
+        *escrows_filled_dst = self.escrows_filled.to_le_bytes();
+        *escrows_cancelled_dst = self.escrows_cancelled.to_le_bytes();
+        *created_volume_dst = self.created_volume.to_le_bytes();
+        *exchanged_volume_x_dst = self.exchanged_volume_x.to_le_bytes();
+        *exchanged_volume_y_dst = self.exchanged_volume_y.to_le_bytes();
+        *cancelled_volume_dst = self.cancelled_volume.to_le_bytes();
+    }
+}
+
+/// A taker's quote on an escrow, stored at the PDA derived from `[b"quote", escrow account pubkey, quoter pubkey]`, see PostQuote. The quoter offers `price` of token Y for `size` of token X, and the initializer settles the escrow with them by accepting the quote with AcceptQuote.
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, ShankAccount, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Quote {
+    pub is_initialized: bool,
+    /// The bump seed of the quote PDA
+    pub bump_seed: u8,
+    pub escrow: Pubkey,
+    pub quoter: Pubkey,
+    /// The amount of token X quoted for. The escrow has to offer exactly this much when the quote is accepted
+    pub size: u64,
+    /// The amount of token Y the quoter pays for it
+    pub price: u64,
+    /// 0 means the quote stands until it is accepted or cancelled
+    pub expiry_unix_timestamp: i64,
+    /// Whether the price is locked in the quote's vault, derived from `[b"quote_vault", quote pubkey]`. Otherwise the quote PDA is approved as delegate of the price on source_token_account.
+    pub is_locked: bool,
+    /// The quoter's token account of token Y the price comes from, and goes back to if the quote is cancelled
+    pub source_token_account: Pubkey,
+    /// The quoter's token account of token X that receives the escrow
+    pub receive_token_account: Pubkey,
+}
+
+impl Sealed for Quote {}
+
+impl Quote {
+    pub fn is_expired(&self, unix_timestamp: i64) -> bool {
+        self.expiry_unix_timestamp != 0 && unix_timestamp >= self.expiry_unix_timestamp
+    }
+}
+
+impl IsInitialized for Quote {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Quote {
+    const LEN: usize = 155;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Quote::LEN];
+        let (
+            is_initialized,
+            bump_seed,
+            escrow,
+            quoter,
+            size,
+            price,
+            expiry_unix_timestamp,
+            is_locked,
+            source_token_account,
+            receive_token_account,
+        ) = array_refs![src, 1, 1, 32, 32, 8, 8, 8, 1, 32, 32];
+        let unpack_bool = |src: &[u8; 1]| match src {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            _ => Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Quote {
+            is_initialized: unpack_bool(is_initialized)?,
+            bump_seed: bump_seed[0],
+            escrow: Pubkey::new_from_array(*escrow),
+            quoter: Pubkey::new_from_array(*quoter),
+            size: u64::from_le_bytes(*size),
+            price: u64::from_le_bytes(*price),
+            expiry_unix_timestamp: i64::from_le_bytes(*expiry_unix_timestamp),
+            is_locked: unpack_bool(is_locked)?,
+            source_token_account: Pubkey::new_from_array(*source_token_account),
+            receive_token_account: Pubkey::new_from_array(*receive_token_account),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Quote::LEN];
+        let (
+            is_initialized_dst,
+            bump_seed_dst,
+            escrow_dst,
+            quoter_dst,
+            size_dst,
+            price_dst,
+            expiry_unix_timestamp_dst,
+            is_locked_dst,
+            source_token_account_dst,
+            receive_token_account_dst,
+        ) = mut_array_refs![dst, 1, 1, 32, 32, 8, 8, 8, 1, 32, 32];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        escrow_dst.copy_from_slice(self.escrow.as_ref());
+        quoter_dst.copy_from_slice(self.quoter.as_ref());
+        *size_dst = self.size.to_le_bytes();
+        *price_dst = self.price.to_le_bytes();
+        *expiry_unix_timestamp_dst = self.expiry_unix_timestamp.to_le_bytes();
+        is_locked_dst[0] = self.is_locked as u8;
+        source_token_account_dst.copy_from_slice(self.source_token_account.as_ref());
+        receive_token_account_dst.copy_from_slice(self.receive_token_account.as_ref());
+    }
+}
+
+/// A taker's counteroffer on an escrow, stored at the PDA derived from `[b"counteroffer", escrow account pubkey, taker pubkey]`, see ProposeCounteroffer. The PDA is approved as delegate of the amount on payment_token_account, so AcceptCounteroffer can settle without the taker.
+#[derive(BorshSerialize, BorshDeserialize, BorshSchema, ShankAccount, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Counteroffer {
+    pub is_initialized: bool,
+    /// The bump seed of the counteroffer PDA
+    pub bump_seed: u8,
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    /// The amount of token Y the taker offers for the whole escrow, in place of its expected amount
+    pub amount: u64,
+    /// The taker's token account of token Y the amount comes from
+    pub payment_token_account: Pubkey,
+    /// The taker's token account of token X that receives the escrow
+    pub receive_token_account: Pubkey,
+}
+
+impl Sealed for Counteroffer {}
+
+impl IsInitialized for Counteroffer {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Counteroffer {
+    const LEN: usize = 138;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, Counteroffer::LEN];
+        let (is_initialized, bump_seed, escrow, taker, amount, payment_token_account, receive_token_account) =
+            array_refs![src, 1, 1, 32, 32, 8, 32, 32];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Counteroffer {
+            is_initialized,
+            bump_seed: bump_seed[0],
+            escrow: Pubkey::new_from_array(*escrow),
+            taker: Pubkey::new_from_array(*taker),
+            amount: u64::from_le_bytes(*amount),
+            payment_token_account: Pubkey::new_from_array(*payment_token_account),
+            receive_token_account: Pubkey::new_from_array(*receive_token_account),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, Counteroffer::LEN];
+        let (
+            is_initialized_dst,
+            bump_seed_dst,
+            escrow_dst,
+            taker_dst,
+            amount_dst,
+            payment_token_account_dst,
+            receive_token_account_dst,
+        ) = mut_array_refs![dst, 1, 1, 32, 32, 8, 32, 32];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        escrow_dst.copy_from_slice(self.escrow.as_ref());
+        taker_dst.copy_from_slice(self.taker.as_ref());
+        *amount_dst = self.amount.to_le_bytes();
+        payment_token_account_dst.copy_from_slice(self.payment_token_account.as_ref());
+        receive_token_account_dst.copy_from_slice(self.receive_token_account.as_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::schema::{BorshSchemaContainer, Definition, Fields};
+
+    fn escrow() -> Escrow {
+        Escrow {
+            discriminator: ESCROW_DISCRIMINATOR,
+            is_initialized: true.into(),
+            initializer_pubkey: Pubkey::new_unique(),
+            temp_token_account_pubkey: Pubkey::new_unique(),
+            initializer_token_to_receive_account_pubkey: Pubkey::new_unique(),
+            expected_amount: 42,
+            expiry_unix_timestamp: 1_700_000_000,
+            bump_seed: 254,
+            token_x_mint: Pubkey::new_unique(),
+            token_y_mint: Pubkey::new_unique(),
+            allowed_taker_pubkey: Pubkey::default(),
+            auction_start_amount: 0,
+            auction_start_unix_timestamp: 0,
+            auction_end_unix_timestamp: 0,
+            is_english_auction: false.into(),
+            highest_bidder: Pubkey::default(),
+            highest_bid_amount: 0,
+            price_feed: Pubkey::default(),
+            premium_bps: 0,
+            delegated_amount: 0,
+            dispute_window_seconds: 0,
+            accepted_unix_timestamp: 0,
+            approval_threshold: 0,
+            approver_count: 0,
+            approvals: 0,
+            approvers: [Pubkey::default(); MAX_APPROVERS],
+            vesting_period_seconds: 0,
+            vesting_periods: 0,
+            stream_start_unix_timestamp: 0,
+            stream_end_unix_timestamp: 0,
+            stream_withdrawn_amount: 0,
+            created_unix_timestamp: 1_690_000_000,
+            version: ESCROW_VERSION,
+            price_numerator: 0,
+            price_denominator: 0,
+            metadata_len: 0,
+            metadata: [0; MAX_METADATA_LEN],
+            escrow_id: 0,
+            reservation_deposit: 0,
+            reserved_by: Pubkey::default(),
+            reserved_until_slot: 0,
+            taker_merkle_root: [0; 32],
+            collection: Pubkey::default(),
+            payment_option_count: 0,
+            payment_option_mints: [Pubkey::default(); MAX_PAYMENT_OPTIONS],
+            payment_option_token_accounts: [Pubkey::default(); MAX_PAYMENT_OPTIONS],
+            payment_option_amounts: [0; MAX_PAYMENT_OPTIONS],
+            usd_amount: 0,
+        }
+    }
+
+    #[test]
+    fn pack_unpack_round_trip() {
+        let escrow = escrow();
+        let mut packed = [0u8; Escrow::LEN];
+        Escrow::pack(escrow, &mut packed).unwrap();
+
+        assert_eq!(Escrow::unpack(&packed).unwrap(), escrow);
+    }
+
+    #[test]
+    fn pack_unpack_uninitialized() {
+        // A freshly created escrow account is all zeroes
+        let zeroed = [0u8; Escrow::LEN];
+        assert!(!Escrow::unpack_unchecked(&zeroed).unwrap().is_initialized());
+        assert_eq!(Escrow::unpack(&zeroed), Err(ProgramError::UninitializedAccount));
+
+        let escrow = Escrow { is_initialized: false.into(), ..escrow() };
+        let mut packed = [0u8; Escrow::LEN];
+        escrow.pack_into_slice(&mut packed);
+        assert_eq!(Escrow::unpack_unchecked(&packed).unwrap(), escrow);
+    }
+
+    #[test]
+    fn unpack_invalid_data() {
+        let mut packed = [0u8; Escrow::LEN];
+        Escrow::pack(escrow(), &mut packed).unwrap();
+
+        assert_eq!(Escrow::unpack(&packed[..Escrow::LEN - 1]), Err(ProgramError::InvalidAccountData));
+
+        packed[escrow_offsets::IS_INITIALIZED] = 2;
+        assert_eq!(Escrow::unpack(&packed), Err(ProgramError::InvalidAccountData));
+
+        packed[escrow_offsets::IS_INITIALIZED] = 1;
+        packed[escrow_offsets::DISCRIMINATOR] ^= 1;
+        assert_eq!(Escrow::unpack(&packed), Err(ProgramError::InvalidAccountData));
+
+        packed[escrow_offsets::DISCRIMINATOR] ^= 1;
+        packed[escrow_offsets::METADATA_LEN] = MAX_METADATA_LEN as u8 + 1;
+        assert_eq!(Escrow::unpack(&packed), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn versioned_escrow_is_told_apart_by_size() {
+        let escrow = escrow();
+        let v1 = EscrowV1 {
+            is_initialized: true,
+            initializer_pubkey: escrow.initializer_pubkey,
+            temp_token_account_pubkey: escrow.temp_token_account_pubkey,
+            initializer_token_to_receive_account_pubkey: escrow.initializer_token_to_receive_account_pubkey,
+            expected_amount: escrow.expected_amount,
+        };
+        let mut packed_v1 = [0u8; EscrowV1::LEN];
+        EscrowV1::pack(v1, &mut packed_v1).unwrap();
+        let mut packed_v2 = [0u8; Escrow::LEN];
+        Escrow::pack(escrow, &mut packed_v2).unwrap();
+
+        assert_eq!(VersionedEscrow::unpack(&packed_v1), Ok(VersionedEscrow::V1(v1)));
+        assert_eq!(VersionedEscrow::unpack(&packed_v2), Ok(VersionedEscrow::V2(escrow)));
+        assert_eq!(VersionedEscrow::unpack(&packed_v2).unwrap().version(), ESCROW_VERSION);
+        assert_eq!(VersionedEscrow::unpack(&packed_v2[..EscrowV1::LEN + 1]), Err(ProgramError::InvalidAccountData));
+
+        // An escrow claiming another version than its size says is rejected rather than misread
+        packed_v2[escrow_offsets::VERSION] = 1;
+        assert_eq!(Escrow::unpack(&packed_v2), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn field_offsets_match_pack_layout() {
+        let escrow = escrow();
+        let mut packed = [0u8; Escrow::LEN];
+        Escrow::pack(escrow, &mut packed).unwrap();
+
+        let field = |offset: usize, len: usize| &packed[offset..offset + len];
+        assert_eq!(field(escrow_offsets::DISCRIMINATOR, 8), ESCROW_DISCRIMINATOR);
+        assert_eq!(field(escrow_offsets::INITIALIZER_PUBKEY, 32), escrow.initializer_pubkey.as_ref());
+        assert_eq!(field(escrow_offsets::TOKEN_X_MINT, 32), escrow.token_x_mint.as_ref());
+        assert_eq!(field(escrow_offsets::TOKEN_Y_MINT, 32), escrow.token_y_mint.as_ref());
+        assert_eq!(field(escrow_offsets::EXPECTED_AMOUNT, 8), escrow.expected_amount.to_le_bytes());
+        assert_eq!(field(escrow_offsets::CREATED_UNIX_TIMESTAMP, 8), escrow.created_unix_timestamp.to_le_bytes());
+        assert_eq!(field(escrow_offsets::VERSION, 1), [ESCROW_VERSION]);
+        assert_eq!(escrow_offsets::USD_AMOUNT + 8, Escrow::LEN);
+    }
+
+    #[test]
+    fn borsh_encoding_matches_pack_layout() {
+        let escrow = escrow();
+        let mut packed = [0u8; Escrow::LEN];
+        Escrow::pack(escrow, &mut packed).unwrap();
+
+        assert_eq!(escrow.try_to_vec().unwrap(), packed.to_vec());
+        assert_eq!(Escrow::try_from_slice(&packed).unwrap(), escrow);
+    }
+
+    #[test]
+    fn borsh_schema_is_stable() {
+        let container: BorshSchemaContainer = Escrow::schema_container();
+        let fields = match container.definitions.get(&container.declaration) {
+            Some(Definition::Struct { fields: Fields::NamedFields(fields) }) => fields,
+            _ => panic!("Escrow should be a struct with named fields"),
+        };
+        let fields: Vec<(&str, &str)> = fields
+            .iter()
+            .map(|(name, declaration)| (name.as_str(), declaration.as_str()))
+            .collect();
+
+        assert_eq!(
+            fields,
+            vec![
+                ("discriminator", "Array<u8, 8>"),
+                ("is_initialized", "bool"),
+                ("initializer_pubkey", "Pubkey"),
+                ("temp_token_account_pubkey", "Pubkey"),
+                ("initializer_token_to_receive_account_pubkey", "Pubkey"),
+                ("expected_amount", "u64"),
+                ("expiry_unix_timestamp", "i64"),
+                ("bump_seed", "u8"),
+                ("token_x_mint", "Pubkey"),
+                ("token_y_mint", "Pubkey"),
+                ("allowed_taker_pubkey", "Pubkey"),
+                ("auction_start_amount", "u64"),
+                ("auction_start_unix_timestamp", "i64"),
+                ("auction_end_unix_timestamp", "i64"),
+                ("is_english_auction", "bool"),
+                ("highest_bidder", "Pubkey"),
+                ("highest_bid_amount", "u64"),
+                ("price_feed", "Pubkey"),
+                ("premium_bps", "i16"),
+                ("delegated_amount", "u64"),
+                ("dispute_window_seconds", "i64"),
+                ("accepted_unix_timestamp", "i64"),
+                ("approval_threshold", "u8"),
+                ("approver_count", "u8"),
+                ("approvals", "u8"),
+                ("approvers", "Array<Pubkey, 8>"),
+                ("vesting_period_seconds", "i64"),
+                ("vesting_periods", "u16"),
+                ("stream_start_unix_timestamp", "i64"),
+                ("stream_end_unix_timestamp", "i64"),
+                ("stream_withdrawn_amount", "u64"),
+                ("created_unix_timestamp", "i64"),
+                ("version", "u8"),
+                ("price_numerator", "u64"),
+                ("price_denominator", "u64"),
+                ("metadata_len", "u8"),
+                ("metadata", "Array<u8, 128>"),
+                ("escrow_id", "u64"),
+                ("reservation_deposit", "u64"),
+                ("reserved_by", "Pubkey"),
+                ("reserved_until_slot", "u64"),
+                ("taker_merkle_root", "Array<u8, 32>"),
+                ("collection", "Pubkey"),
+                ("payment_option_count", "u8"),
+                ("payment_option_mints", "Array<Pubkey, 3>"),
+                ("payment_option_token_accounts", "Array<Pubkey, 3>"),
+                ("payment_option_amounts", "Array<u64, 3>"),
+                ("usd_amount", "u64"),
+            ]
+        );
+    }
+
+    #[test]
+    fn dutch_auction_price() {
+        let auction = Escrow {
+            expected_amount: 100,
+            auction_start_amount: 1_000,
+            auction_start_unix_timestamp: 1_000,
+            auction_end_unix_timestamp: 2_000,
+            ..escrow()
+        };
+
+        assert_eq!(auction.current_price(0), Some(1_000));
+        assert_eq!(auction.current_price(1_000), Some(1_000));
+        assert_eq!(auction.current_price(1_500), Some(550));
+        // Rounds in the initializer's favour
+        assert_eq!(auction.current_price(1_001), Some(1_000));
+        assert_eq!(auction.current_price(2_000), Some(100));
+        assert_eq!(auction.current_price(i64::MAX), Some(100));
+
+        let fixed_price = escrow();
+        assert_eq!(fixed_price.current_price(1_500), Some(fixed_price.expected_amount));
+    }
+
+    #[test]
+    fn rate_based_price_rounds_up() {
+        let mut escrow = escrow();
+        assert_eq!(escrow.price_of(7, 0), Some(escrow.expected_amount));
+
+        // 2 Y for every 3 X
+        escrow.price_numerator = 2;
+        escrow.price_denominator = 3;
+        assert_eq!(escrow.price_of(3, 0), Some(2));
+        assert_eq!(escrow.price_of(4, 0), Some(3));
+        assert_eq!(escrow.price_of(0, 0), Some(0));
+        escrow.price_numerator = u64::MAX;
+        escrow.price_denominator = 1;
+        assert_eq!(escrow.price_of(2, 0), None);
+    }
+
+    #[test]
+    fn amount_x_for_rounds_down() {
+        let mut escrow = escrow();
+        assert_eq!(escrow.amount_x_for(10), None);
+
+        // A buy escrow of 300 X for 200 Y
+        escrow.price_numerator = 200;
+        escrow.price_denominator = 300;
+        assert_eq!(escrow.amount_x_for(200), Some(300));
+        assert_eq!(escrow.amount_x_for(3), Some(4));
+        assert_eq!(escrow.price_of(4, 0), Some(3));
+        assert_eq!(escrow.amount_x_for(0), Some(0));
+    }
+
+    #[test]
+    fn expected_payment_adds_the_fee() {
+        let mut escrow = escrow();
+        escrow.expected_amount = 10_000;
+        assert_eq!(escrow.expected_payment(7, 25, 0), Some(10_025));
+        assert_eq!(escrow.expected_payment(7, 0, 0), Some(10_000));
+        escrow.expected_amount = u64::MAX;
+        assert_eq!(escrow.expected_payment(7, 25, 0), None);
+
+        // Oracle prices aren't known up front
+        escrow.price_feed = Pubkey::new_unique();
+        assert_eq!(escrow.expected_payment(7, 0, 0), None);
+    }
+
+    #[test]
+    fn release_approvals() {
+        let mut escrow = escrow();
+        assert!(escrow.is_release_approved());
+
+        escrow.approval_threshold = 2;
+        escrow.approver_count = 3;
+        escrow.approvers[..3].copy_from_slice(&[Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()]);
+        assert_eq!(escrow.approver_index(&escrow.approvers[2]), Some(2));
+        // Unused entries are zeroed, which must not make the default pubkey an approver
+        assert_eq!(escrow.approver_index(&Pubkey::default()), None);
+
+        escrow.approvals = 0b100;
+        assert!(!escrow.is_release_approved());
+        escrow.approvals = 0b101;
+        assert!(escrow.is_release_approved());
+
+        let mut packed = [0u8; Escrow::LEN];
+        Escrow::pack(escrow, &mut packed).unwrap();
+        assert_eq!(Escrow::unpack(&packed).unwrap(), escrow);
+    }
+
+    #[test]
+    fn stale_escrows() {
+        let mut escrow = escrow();
+        let pda = Pubkey::new_unique();
+        let mut temp_token_account = TokenAccount { owner: pda, amount: 10, ..TokenAccount::default() };
+        assert!(!escrow.is_stale(Some(&temp_token_account), &pda));
+        assert!(escrow.is_stale(None, &pda));
+        temp_token_account.amount = 0;
+        assert!(escrow.is_stale(Some(&temp_token_account), &pda));
+
+        // Bids and acceptance keep an escrow alive whatever its temp token account looks like
+        escrow.highest_bid_amount = 5;
+        assert!(!escrow.is_stale(None, &pda));
+        escrow.highest_bid_amount = 0;
+
+        // A delegated escrow's temp token account is the initializer's own, only the allowance matters
+        escrow.delegated_amount = 10;
+        let mut initializers_token_account = TokenAccount {
+            owner: escrow.initializer_pubkey,
+            amount: 10,
+            delegate: COption::Some(pda),
+            delegated_amount: 10,
+            ..TokenAccount::default()
+        };
+        assert!(!escrow.is_stale(Some(&initializers_token_account), &pda));
+        initializers_token_account.delegated_amount = 9;
+        assert!(escrow.is_stale(Some(&initializers_token_account), &pda));
+        initializers_token_account.delegate = COption::None;
+        assert!(escrow.is_stale(Some(&initializers_token_account), &pda));
+    }
+
+    #[test]
+    fn reservations() {
+        let mut escrow = escrow();
+        assert!(!escrow.is_reservable());
+        escrow.reservation_deposit = 1_000_000;
+        assert!(escrow.is_reservable());
+        assert!(!escrow.is_reserved(0));
+
+        escrow.reserved_by = Pubkey::new_unique();
+        escrow.reserved_until_slot = 100;
+        assert!(escrow.is_reserved(99));
+        assert!(!escrow.is_reserved(100));
+
+        // The initializer of a delegated escrow could pull the tokens from under the reservation
+        escrow.delegated_amount = 10;
+        assert!(!escrow.is_reservable());
+    }
+
+    #[test]
+    fn taker_allowlist() {
+        let mut escrow = escrow();
+        let taker = Pubkey::new_unique();
+        assert!(escrow.is_taker_in_allowlist(&taker, &[]));
+        assert!(escrow.can_be_matched());
+
+        let takers = [taker, Pubkey::new_unique(), Pubkey::new_unique()];
+        escrow.taker_merkle_root = crate::merkle::root(&takers).unwrap();
+        let proof = crate::merkle::proof(&takers, &taker).unwrap();
+        assert!(escrow.is_taker_in_allowlist(&taker, &proof));
+        assert!(!escrow.is_taker_in_allowlist(&taker, &[]));
+        assert!(!escrow.is_taker_in_allowlist(&Pubkey::new_unique(), &proof));
+        assert!(!escrow.can_be_matched());
+    }
+
+    #[test]
+    fn collection_offers() {
+        let mut escrow = escrow();
+        escrow.reservation_deposit = 1_000;
+        assert!(!escrow.is_collection_offer());
+        assert!(escrow.is_reservable());
+
+        escrow.collection = Pubkey::new_unique();
+        assert!(escrow.is_collection_offer());
+        assert!(!escrow.is_reservable());
+        assert!(!escrow.can_be_matched());
+    }
+
+    #[test]
+    fn lamport_escrows() {
+        let mut escrow = escrow();
+        assert!(!escrow.holds_lamports());
+        assert!(escrow.can_be_matched());
+
+        escrow.token_x_mint = system_program::id();
+        assert!(escrow.holds_lamports());
+        assert!(!escrow.holds_native_sol());
+        assert!(!escrow.can_be_matched());
+    }
+
+    #[test]
+    fn payment_options() {
+        let mut escrow = escrow();
+        let usdt = Pubkey::new_unique();
+        assert_eq!(escrow.paid_in(&escrow.token_y_mint), Some(escrow));
+        assert_eq!(escrow.paid_in(&usdt), None);
+
+        escrow.payment_option_count = 1;
+        escrow.payment_option_mints[0] = usdt;
+        escrow.payment_option_token_accounts[0] = Pubkey::new_unique();
+        escrow.payment_option_amounts[0] = 99;
+        let paid_in_usdt = escrow.paid_in(&usdt).unwrap();
+        assert_eq!(paid_in_usdt.token_y_mint, usdt);
+        assert_eq!(paid_in_usdt.initializer_token_to_receive_account_pubkey, escrow.payment_option_token_accounts[0]);
+        assert_eq!(paid_in_usdt.price_of(escrow.expected_amount, 0), Some(99));
+
+        // Entries past the count aren't accepted
+        escrow.payment_option_count = 0;
+        assert_eq!(escrow.paid_in(&usdt), None);
+    }
+
+    #[test]
+    fn config_pack_unpack_round_trip() {
+        let config = Config {
+            is_initialized: true,
+            admin: Pubkey::new_unique(),
+            fee_bps: 30,
+            paused: true,
+            default_expiry_seconds: 7 * 24 * 60 * 60,
+            bump_seed: 255,
+            allowlist_enabled: true,
+            referral_share_bps: 2_000,
+            fee_collector: Pubkey::new_unique(),
+            cancel_penalty_bps: 100,
+            cancel_penalty_amount: 5_000,
+            cancel_penalty_to_counterparty: true,
+            gate_program: Pubkey::new_unique(),
+            max_expiry_seconds: 30 * 24 * 60 * 60,
+        };
+        let mut packed = [0u8; Config::LEN];
+        Config::pack(config, &mut packed).unwrap();
+
+        assert_eq!(Config::unpack(&packed).unwrap(), config);
+        assert_eq!(config.try_to_vec().unwrap(), packed.to_vec());
+    }
+
+    #[test]
+    fn cancel_penalty() {
+        let mut packed = [0u8; Config::LEN];
+        packed[0] = 1;
+        let mut config = Config::unpack(&packed).unwrap();
+        assert_eq!(config.cancel_penalty(1_000_000), 0);
+
+        config.cancel_penalty_bps = 100;
+        assert_eq!(config.cancel_penalty(1_000_000), 10_000);
+        config.cancel_penalty_amount = 5_000;
+        assert_eq!(config.cancel_penalty(1_000_000), 15_000);
+
+        // The initializer can't lose more than they put in
+        assert_eq!(config.cancel_penalty(4_000), 4_000);
+        config.cancel_penalty_amount = u64::MAX;
+        assert_eq!(config.cancel_penalty(u64::MAX), u64::MAX);
+
+        let mut escrow = escrow();
+        let fee_authority = Pubkey::new_unique();
+        assert_eq!(config.cancel_penalty_recipient(&escrow, &fee_authority), fee_authority);
+        config.fee_collector = Pubkey::new_unique();
+        assert_eq!(config.cancel_penalty_recipient(&escrow, &fee_authority), config.fee_collector);
+
+        // Only private escrows have a counterparty to pay
+        config.cancel_penalty_to_counterparty = true;
+        assert_eq!(config.cancel_penalty_recipient(&escrow, &fee_authority), config.fee_collector);
+        escrow.allowed_taker_pubkey = Pubkey::new_unique();
+        assert_eq!(config.cancel_penalty_recipient(&escrow, &fee_authority), escrow.allowed_taker_pubkey);
+    }
+
+    #[test]
+    fn fill_record_pack_unpack_round_trip() {
+        let mut fill_record = FillRecord {
+            is_initialized: true,
+            bump_seed: 252,
+            escrow: Pubkey::new_unique(),
+            taker: Pubkey::new_unique(),
+            ..FillRecord::default()
+        };
+        fill_record.record(40, 60, 100);
+        fill_record.record(10, 15, 120);
+        assert_eq!((fill_record.amount, fill_record.price, fill_record.fills, fill_record.slot), (50, 75, 2, 120));
+        let mut packed = [0u8; FillRecord::LEN];
+        FillRecord::pack(fill_record, &mut packed).unwrap();
+
+        assert_eq!(FillRecord::unpack(&packed).unwrap(), fill_record);
+        assert_eq!(fill_record.try_to_vec().unwrap(), packed.to_vec());
+    }
+
+    #[test]
+    fn stats_pack_unpack_round_trip() {
+        let mut stats = Stats { is_initialized: true, bump_seed: 253, ..Stats::default() };
+        stats.record_created(100);
+        stats.record_exchange(40, 60, false);
+        stats.record_exchange(60, 90, true);
+        stats.record_cancelled(u64::MAX);
+        let mut packed = [0u8; Stats::LEN];
+        Stats::pack(stats, &mut packed).unwrap();
+
+        assert_eq!(Stats::unpack(&packed).unwrap(), stats);
+        assert_eq!(stats.try_to_vec().unwrap(), packed.to_vec());
+        assert_eq!((stats.escrows_created, stats.escrows_filled, stats.escrows_cancelled), (1, 1, 1));
+        assert_eq!((stats.exchanged_volume_x, stats.exchanged_volume_y), (100, 150));
+    }
+
+    #[test]
+    fn quote_pack_unpack_round_trip() {
+        let quote = Quote {
+            is_initialized: true,
+            bump_seed: 251,
+            escrow: Pubkey::new_unique(),
+            quoter: Pubkey::new_unique(),
+            size: 100,
+            price: 250,
+            expiry_unix_timestamp: 1_700_000_000,
+            is_locked: true,
+            source_token_account: Pubkey::new_unique(),
+            receive_token_account: Pubkey::new_unique(),
+        };
+        let mut packed = [0u8; Quote::LEN];
+        Quote::pack(quote, &mut packed).unwrap();
+
+        assert_eq!(Quote::unpack(&packed).unwrap(), quote);
+        assert_eq!(quote.try_to_vec().unwrap(), packed.to_vec());
+        assert!(!quote.is_expired(1_699_999_999));
+        assert!(quote.is_expired(1_700_000_000));
+        assert!(!Quote { expiry_unix_timestamp: 0, ..quote }.is_expired(i64::MAX));
+    }
+
+    #[test]
+    fn counteroffer_pack_unpack_round_trip() {
+        let counteroffer = Counteroffer {
+            is_initialized: true,
+            bump_seed: 250,
+            escrow: Pubkey::new_unique(),
+            taker: Pubkey::new_unique(),
+            amount: 900,
+            payment_token_account: Pubkey::new_unique(),
+            receive_token_account: Pubkey::new_unique(),
+        };
+        let mut packed = [0u8; Counteroffer::LEN];
+        Counteroffer::pack(counteroffer, &mut packed).unwrap();
+
+        assert_eq!(Counteroffer::unpack(&packed).unwrap(), counteroffer);
+        assert_eq!(counteroffer.try_to_vec().unwrap(), packed.to_vec());
+    }
+
+    #[test]
+    fn vesting_pack_unpack_round_trip() {
+        let vesting = Vesting {
+            is_initialized: true,
+            bump_seed: 252,
+            escrow: Pubkey::new_unique(),
+            beneficiary: Pubkey::new_unique(),
+            vault: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            total_amount: 1_000,
+            claimed_amount: 250,
+            start_unix_timestamp: 1_700_000_000,
+            period_seconds: 30 * 24 * 60 * 60,
+            periods: 12,
+        };
+        let mut packed = [0u8; Vesting::LEN];
+        Vesting::pack(vesting, &mut packed).unwrap();
+
+        assert_eq!(Vesting::unpack(&packed).unwrap(), vesting);
+        assert_eq!(vesting.try_to_vec().unwrap(), packed.to_vec());
+    }
+
+    #[test]
+    fn allowlist_pack_unpack_round_trip() {
+        let mut allowlist = MintAllowlist {
+            is_initialized: true,
+            bump_seed: 253,
+            count: 2,
+            mints: [Pubkey::default(); MAX_ALLOWED_MINTS],
+        };
+        allowlist.mints[0] = Pubkey::new_unique();
+        allowlist.mints[1] = Pubkey::new_unique();
+        let mut packed = vec![0u8; MintAllowlist::LEN];
+        MintAllowlist::pack(allowlist.clone(), &mut packed).unwrap();
+
+        assert_eq!(MintAllowlist::unpack(&packed).unwrap(), allowlist);
+        assert_eq!(allowlist.try_to_vec().unwrap(), packed);
+        assert!(allowlist.contains(&allowlist.mints[1]));
+        // Unused entries are zeroed, which must not make the default pubkey an allowed mint
+        assert!(!allowlist.contains(&Pubkey::default()));
+    }
+
+    #[test]
+    fn stablecoin_list_pack_unpack_round_trip() {
+        let mut stablecoins = StablecoinList {
+            is_initialized: true,
+            bump_seed: 254,
+            count: 1,
+            mints: [Pubkey::default(); MAX_STABLECOINS],
+            price_feeds: [Pubkey::default(); MAX_STABLECOINS],
+            tolerance_bps: [0; MAX_STABLECOINS],
+        };
+        stablecoins.mints[0] = Pubkey::new_unique();
+        stablecoins.price_feeds[0] = Pubkey::new_unique();
+        stablecoins.tolerance_bps[0] = 50;
+        let mut packed = vec![0u8; StablecoinList::LEN];
+        StablecoinList::pack(stablecoins.clone(), &mut packed).unwrap();
+
+        assert_eq!(StablecoinList::unpack(&packed).unwrap(), stablecoins);
+        assert_eq!(stablecoins.try_to_vec().unwrap(), packed);
+        assert_eq!(stablecoins.get(&stablecoins.mints[0]), Some((stablecoins.price_feeds[0], 50)));
+        assert_eq!(stablecoins.get(&Pubkey::default()), None);
+    }
+
+    #[test]
+    fn registry_insert_remove_round_trip() {
+        let mut registry = EscrowRegistry::new(Pubkey::new_unique(), Pubkey::new_unique(), 251);
+        let (first, second, third) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        for escrow in [first, second, third] {
+            registry.insert(escrow).unwrap();
+        }
+
+        assert!(registry.remove(&first));
+        assert!(!registry.remove(&first));
+        assert_eq!(&registry.escrows[..registry.count as usize], &[third, second]);
+
+        let mut packed = vec![0u8; EscrowRegistry::LEN];
+        EscrowRegistry::pack(registry.clone(), &mut packed).unwrap();
+        assert_eq!(EscrowRegistry::unpack(&packed).unwrap(), registry);
+        assert_eq!(registry.try_to_vec().unwrap(), packed);
+
+        for _ in registry.count as usize..MAX_REGISTERED_ESCROWS {
+            registry.insert(Pubkey::new_unique()).unwrap();
+        }
+        assert_eq!(registry.insert(Pubkey::new_unique()), Err(EscrowError::RegistryFull.into()));
+    }
+}
@@ -0,0 +1,58 @@
+//! Defense-in-depth checks for deployments built with the `strict-checks` feature. The program already validates everything it relies on, so these only repeat what a CPI or the runtime would catch later, or what can't go wrong with accounts this program created: writable flags, account owners, mints, data lengths and canonical PDAs. Failing early and explicitly costs compute units, which is why they are off by default.
+//!
+//! Without the feature every check returns Ok(()) straight away and compiles to nothing.
+
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{error::EscrowError, token};
+
+/// Whether the program was built with the `strict-checks` feature
+pub const ENABLED: bool = cfg!(feature = "strict-checks");
+
+/// Every one of the accounts has to be writable
+pub fn check_writable(accounts: &[&AccountInfo]) -> ProgramResult {
+    if ENABLED && accounts.iter().any(|account| !account.is_writable) {
+        return Err(EscrowError::AccountNotWritable.into());
+    }
+    Ok(())
+}
+
+/// The account has to be owned by `owner`
+pub fn check_owner(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if ENABLED && account.owner != owner {
+        return Err(ProgramError::IllegalOwner);
+    }
+    Ok(())
+}
+
+/// The account has to hold exactly `len` bytes
+pub fn check_data_len(account: &AccountInfo, len: usize) -> ProgramResult {
+    if ENABLED && account.data_len() != len {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+/// The account has to be a token account of `mint`
+pub fn check_token_account_mint(account: &AccountInfo, mint: &Pubkey) -> ProgramResult {
+    if ENABLED && token::unpack_token_account(account)?.mint != *mint {
+        return Err(EscrowError::TokenMintMismatch.into());
+    }
+    Ok(())
+}
+
+/// The account has to be a token account owned by `owner`
+pub fn check_token_account_owner(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if ENABLED && token::unpack_token_account(account)?.owner != *owner {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+/// `pda` has to be the canonical PDA of the seeds, i.e. the one find_program_address gives. The program derives PDAs from stored bump seeds, which it only ever stores canonical ones of.
+pub fn check_canonical_pda(pda: &Pubkey, seeds: &[&[u8]], program_id: &Pubkey) -> ProgramResult {
+    if ENABLED && Pubkey::find_program_address(seeds, program_id).0 != *pda {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
@@ -0,0 +1,184 @@
+//! Swap rings settle trades that can't be matched pairwise, e.g. A wants B's token, B wants C's and C wants A's. Each participant of a ring gives their leg's tokens to the next participant, and the last one gives theirs to the first.
+//!
+//! The ring's creator lays out the legs with InitRing. Every participant agrees to the ring by depositing their leg with DepositRingLeg, into a vault of their own owned by the ring. Once all legs are in, anyone can ExecuteRing, which pays every vault out to the next participant in one instruction, so either everyone gets their tokens or nobody does. If the ring isn't complete by its deadline, the participants take their deposits back with WithdrawRingLeg instead, and once nothing is deposited the creator can close the ring with CloseRing.
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+/// The fewest legs a ring has. Two parties can use a plain escrow.
+pub const MIN_RING_LEGS: usize = 3;
+
+/// The most legs a ring has. ExecuteRing takes five accounts per leg, so larger rings wouldn't fit in a transaction.
+pub const MAX_RING_LEGS: usize = 6;
+
+const LEG_LEN: usize = 73;
+
+/// One participant's part of a ring: they give amount of mint to the participant of the next leg
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct RingLeg {
+    pub participant: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// Whether the participant's tokens are in their vault, see DepositRingLeg
+    pub is_deposited: bool,
+}
+
+/// A swap ring, stored at the PDA derived from `[b"ring", creator pubkey, ring_id as little-endian bytes]`. The vault of each leg is a token account owned by the ring, at the PDA derived from `[b"ring_vault", ring pubkey, leg index]`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct SwapRing {
+    pub is_initialized: bool,
+    /// The bump seed of the ring PDA
+    pub bump_seed: u8,
+    /// Who laid out the ring and paid its rent
+    pub creator: Pubkey,
+    /// Tells apart the rings of one creator
+    pub ring_id: u64,
+    /// After this the ring can't be deposited into anymore, and the participants can withdraw their deposits
+    pub deadline_unix_timestamp: i64,
+    pub leg_count: u8,
+    /// The first leg_count entries are in use
+    pub legs: [RingLeg; 6],
+}
+
+impl SwapRing {
+    pub fn legs(&self) -> &[RingLeg] {
+        &self.legs[..self.leg_count as usize]
+    }
+
+    /// The index of the participant's leg, None if they aren't in the ring
+    pub fn leg_of(&self, participant: &Pubkey) -> Option<usize> {
+        self.legs().iter().position(|leg| leg.participant == *participant)
+    }
+
+    /// Who receives the tokens of the leg at index: the participant of the next leg, or of the first one for the last leg
+    pub fn recipient_of(&self, index: usize) -> Pubkey {
+        self.legs()[(index + 1) % self.legs().len()].participant
+    }
+
+    /// Every leg is deposited, so the ring can be executed
+    pub fn is_complete(&self) -> bool {
+        self.legs().iter().all(|leg| leg.is_deposited)
+    }
+
+    pub fn has_deposits(&self) -> bool {
+        self.legs().iter().any(|leg| leg.is_deposited)
+    }
+
+    pub fn is_expired(&self, unix_timestamp: i64) -> bool {
+        unix_timestamp >= self.deadline_unix_timestamp
+    }
+}
+
+impl Sealed for SwapRing {}
+
+impl IsInitialized for SwapRing {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+fn unpack_bool(src: &[u8; 1]) -> Result<bool, ProgramError> {
+    match src {
+        [0] => Ok(false),
+        [1] => Ok(true),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+impl Pack for SwapRing {
+    const LEN: usize = 51 + LEG_LEN * MAX_RING_LEGS;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, SwapRing::LEN];
+        let (is_initialized, bump_seed, creator, ring_id, deadline_unix_timestamp, leg_count, legs_src) =
+            array_refs![src, 1, 1, 32, 8, 8, 1, LEG_LEN * MAX_RING_LEGS];
+        if leg_count[0] as usize > MAX_RING_LEGS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let mut legs = [RingLeg::default(); MAX_RING_LEGS];
+        for (leg, leg_src) in legs.iter_mut().zip(legs_src.chunks_exact(LEG_LEN)) {
+            let (participant, mint, amount, is_deposited) = array_refs![array_ref![leg_src, 0, LEG_LEN], 32, 32, 8, 1];
+            *leg = RingLeg {
+                participant: Pubkey::new_from_array(*participant),
+                mint: Pubkey::new_from_array(*mint),
+                amount: u64::from_le_bytes(*amount),
+                is_deposited: unpack_bool(is_deposited)?,
+            };
+        }
+
+        Ok(SwapRing {
+            is_initialized: unpack_bool(is_initialized)?,
+            bump_seed: bump_seed[0],
+            creator: Pubkey::new_from_array(*creator),
+            ring_id: u64::from_le_bytes(*ring_id),
+            deadline_unix_timestamp: i64::from_le_bytes(*deadline_unix_timestamp),
+            leg_count: leg_count[0],
+            legs,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, SwapRing::LEN];
+        let (is_initialized_dst, bump_seed_dst, creator_dst, ring_id_dst, deadline_dst, leg_count_dst, legs_dst) =
+            mut_array_refs![dst, 1, 1, 32, 8, 8, 1, LEG_LEN * MAX_RING_LEGS];
+
+        is_initialized_dst[0] = self.is_initialized as u8;
+        bump_seed_dst[0] = self.bump_seed;
+        creator_dst.copy_from_slice(self.creator.as_ref());
+        *ring_id_dst = self.ring_id.to_le_bytes();
+        *deadline_dst = self.deadline_unix_timestamp.to_le_bytes();
+        leg_count_dst[0] = self.leg_count;
+        for (leg, leg_dst) in self.legs.iter().zip(legs_dst.chunks_exact_mut(LEG_LEN)) {
+            let (participant_dst, mint_dst, amount_dst, is_deposited_dst) =
+                mut_array_refs![array_mut_ref![leg_dst, 0, LEG_LEN], 32, 32, 8, 1];
+            participant_dst.copy_from_slice(leg.participant.as_ref());
+            mint_dst.copy_from_slice(leg.mint.as_ref());
+            *amount_dst = leg.amount.to_le_bytes();
+            is_deposited_dst[0] = leg.is_deposited as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legs_pay_the_next_participant() {
+        let mut ring = SwapRing {
+            is_initialized: true,
+            bump_seed: 252,
+            creator: Pubkey::new_unique(),
+            ring_id: 7,
+            deadline_unix_timestamp: 1_700_000_000,
+            leg_count: 3,
+            legs: [RingLeg::default(); MAX_RING_LEGS],
+        };
+        for (index, leg) in ring.legs.iter_mut().take(3).enumerate() {
+            *leg = RingLeg {
+                participant: Pubkey::new_unique(),
+                mint: Pubkey::new_unique(),
+                amount: 10 * (index as u64 + 1),
+                is_deposited: true,
+            };
+        }
+        ring.legs[1].is_deposited = false;
+
+        assert_eq!(ring.recipient_of(0), ring.legs[1].participant);
+        assert_eq!(ring.recipient_of(2), ring.legs[0].participant);
+        assert_eq!(ring.leg_of(&ring.legs[2].participant), Some(2));
+        assert_eq!(ring.leg_of(&Pubkey::default()), None);
+        assert!(!ring.is_complete());
+        assert!(ring.has_deposits());
+
+        let mut packed = vec![0u8; SwapRing::LEN];
+        SwapRing::pack(ring.clone(), &mut packed).unwrap();
+        assert_eq!(SwapRing::unpack(&packed).unwrap(), ring);
+        assert_eq!(ring.try_to_vec().unwrap(), packed);
+    }
+}
@@ -0,0 +1,129 @@
+use solana_program::{
+    account_info::AccountInfo,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar,
+};
+use std::convert::TryInto;
+
+use crate::error::EscrowError;
+
+// The Switchboard V2 program, whose VRF accounts raffles can be drawn with. Like the Pyth accounts in `oracle`, they are read by hand, since switchboard-v2 needs a newer solana-program than we build against. Only the fields the raffles need are read.
+solana_program::declare_id!("SW1TCH7qEPTdLsDHRgPuMQjbQxKdH2aBStViMFnt64f");
+
+/// The Anchor discriminator of VrfAccountData
+const VRF_ACCOUNT_DISCRIMINATOR: [u8; 8] = [101, 35, 62, 239, 103, 151, 6, 18];
+/// The Anchor discriminator of vrf_request_randomness, which is followed by the permission and the program state bumps
+const REQUEST_RANDOMNESS_DISCRIMINATOR: [u8; 8] = [230, 121, 14, 164, 28, 222, 117, 118];
+
+const STATUS_OFFSET: usize = 8;
+const COUNTER_OFFSET: usize = 9;
+const AUTHORITY_OFFSET: usize = 25;
+/// The current round's result is followed by its num_verified and 256 bytes of padding, and the account ends with another 1024 bytes of padding. Everything between the authority and the current round depends on the layout of the proof builders, so the result is found from the end instead.
+const RESULT_OFFSET_FROM_END: usize = 32 + 4 + 256 + 1024;
+
+/// VrfStatus::StatusVerified and VrfStatus::StatusCallbackSuccess, the statuses a VRF has once its proof was verified
+const STATUS_VERIFIED: u8 = 3;
+const STATUS_CALLBACK_SUCCESS: u8 = 4;
+
+/// What a raffle needs to know about a VRF account
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vrf {
+    /// Increases with every request
+    pub counter: u128,
+    /// Who can request randomness, the raffle for the VRFs raffles are drawn with
+    pub authority: Pubkey,
+    /// The verified result of the latest request, None while it is still being verified
+    pub result: Option<[u8; 32]>,
+}
+
+/// Unpacks a Switchboard VRF account. Fails if it isn't one.
+pub fn unpack_vrf(account: &AccountInfo) -> Result<Vrf, ProgramError> {
+    if *account.owner != id() {
+        return Err(EscrowError::InvalidVrfAccount.into());
+    }
+    unpack_vrf_data(&account.try_borrow_data()?)
+}
+
+fn unpack_vrf_data(data: &[u8]) -> Result<Vrf, ProgramError> {
+    let result_offset = match data.len().checked_sub(RESULT_OFFSET_FROM_END) {
+        Some(offset) if offset >= AUTHORITY_OFFSET + 32 && data[..8] == VRF_ACCOUNT_DISCRIMINATOR => offset,
+        _ => return Err(EscrowError::InvalidVrfAccount.into()),
+    };
+
+    let status = data[STATUS_OFFSET];
+    let result: [u8; 32] = data[result_offset..result_offset + 32].try_into().unwrap();
+    let is_verified = (status == STATUS_VERIFIED || status == STATUS_CALLBACK_SUCCESS) && result != [0; 32];
+    Ok(Vrf {
+        counter: u128::from_le_bytes(data[COUNTER_OFFSET..AUTHORITY_OFFSET].try_into().unwrap()),
+        authority: Pubkey::new_from_array(data[AUTHORITY_OFFSET..AUTHORITY_OFFSET + 32].try_into().unwrap()),
+        result: Some(result).filter(|_| is_verified),
+    })
+}
+
+/// Creates a vrf_request_randomness instruction, which the VRF's authority has to sign. The payer's wallet of wrapped SOL funds the oracle's fee through the VRF's escrow.
+#[allow(clippy::too_many_arguments)]
+pub fn request_randomness(
+    authority: &Pubkey,
+    vrf: &Pubkey,
+    oracle_queue: &Pubkey,
+    queue_authority: &Pubkey,
+    data_buffer: &Pubkey,
+    permission: &Pubkey,
+    vrf_escrow: &Pubkey,
+    payer_wallet: &Pubkey,
+    payer_authority: &Pubkey,
+    program_state: &Pubkey,
+    permission_bump: u8,
+    state_bump: u8,
+) -> Instruction {
+    let mut data = REQUEST_RANDOMNESS_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&[permission_bump, state_bump]);
+
+    #[allow(deprecated)]
+    let recent_blockhashes = sysvar::recent_blockhashes::id();
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*vrf, false),
+            AccountMeta::new(*oracle_queue, false),
+            AccountMeta::new_readonly(*queue_authority, false),
+            AccountMeta::new_readonly(*data_buffer, false),
+            AccountMeta::new(*permission, false),
+            AccountMeta::new(*vrf_escrow, false),
+            AccountMeta::new(*payer_wallet, false),
+            AccountMeta::new_readonly(*payer_authority, true),
+            AccountMeta::new_readonly(recent_blockhashes, false),
+            AccountMeta::new_readonly(*program_state, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vrf_result_is_only_read_once_verified() {
+        let authority = Pubkey::new_unique();
+        let mut data = vec![0u8; 29_058];
+        data[..8].copy_from_slice(&VRF_ACCOUNT_DISCRIMINATOR);
+        data[STATUS_OFFSET] = 1;
+        data[COUNTER_OFFSET..AUTHORITY_OFFSET].copy_from_slice(&5u128.to_le_bytes());
+        data[AUTHORITY_OFFSET..AUTHORITY_OFFSET + 32].copy_from_slice(authority.as_ref());
+        let result_offset = data.len() - RESULT_OFFSET_FROM_END;
+        data[result_offset..result_offset + 32].copy_from_slice(&[7; 32]);
+
+        assert_eq!(unpack_vrf_data(&data).unwrap(), Vrf { counter: 5, authority, result: None });
+        data[STATUS_OFFSET] = STATUS_CALLBACK_SUCCESS;
+        assert_eq!(unpack_vrf_data(&data).unwrap().result, Some([7; 32]));
+
+        data[0] = 0;
+        assert!(unpack_vrf_data(&data).is_err());
+        assert!(unpack_vrf_data(&VRF_ACCOUNT_DISCRIMINATOR).is_err());
+    }
+}
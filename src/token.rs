@@ -0,0 +1,252 @@
+use solana_program::{
+    account_info::AccountInfo,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+use arrayref::array_ref;
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+
+use crate::math::{self, Rounding};
+
+/// Token-2022 is a superset of the original token program: the first 165 bytes of its token accounts (and the first 82 bytes of its mints) have the exact same layout, and its base instructions are encoded the same way. Extensions are appended after the base data, which is why we can't use Pack::unpack directly - it insists on the exact length.
+pub mod token_2022 {
+    solana_program::declare_id!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+}
+
+/// The associated token account program. Its Create instruction has no data and takes the same accounts in every version of the program, so we build it ourselves instead of depending on spl-associated-token-account.
+pub mod associated_token {
+    solana_program::declare_id!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+}
+
+/// In Token-2022 accounts with extensions, the byte right after the base token account data says whether the account is a mint (1) or a token account (2)
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+/// Where the amount and the state sit in a token account: after the mint and the owner, and after the amount, the delegate and its COption tag
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+const TOKEN_ACCOUNT_STATE_OFFSET: usize = 108;
+
+/// The extensions follow the account type byte as type-length-value entries: a u16 type, a u16 length, then the value
+const EXTENSION_TYPE_TRANSFER_FEE_CONFIG: u16 = 1;
+/// TransferFeeConfig holds two authorities and the withheld amount before the older and the newer TransferFee
+const OLDER_TRANSFER_FEE_OFFSET: usize = 72;
+const TRANSFER_FEE_LEN: usize = 18;
+
+/// The fee a Token-2022 mint with the TransferFeeConfig extension withholds from every transfer, as the recipient receives the amount minus the fee
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransferFee {
+    /// The first epoch the fee applies to
+    pub epoch: u64,
+    pub maximum_fee: u64,
+    pub transfer_fee_basis_points: u16,
+}
+
+impl TransferFee {
+    fn unpack(data: &[u8]) -> Option<Self> {
+        let data: &[u8; TRANSFER_FEE_LEN] = data.get(..TRANSFER_FEE_LEN)?.try_into().ok()?;
+        Some(TransferFee {
+            epoch: u64::from_le_bytes(data[..8].try_into().ok()?),
+            maximum_fee: u64::from_le_bytes(data[8..16].try_into().ok()?),
+            transfer_fee_basis_points: u16::from_le_bytes(data[16..].try_into().ok()?),
+        })
+    }
+
+    /// The fee withheld from a transfer of amount, rounded up like Token-2022 does
+    pub fn calculate_fee(&self, amount: u64) -> u64 {
+        // More than the whole amount in fees only happens above 10_000 bps, which Token-2022 doesn't allow
+        let fee = math::bps_of(amount, self.transfer_fee_basis_points, Rounding::Up).unwrap_or(u64::MAX);
+        fee.min(self.maximum_fee)
+    }
+
+    /// The smallest amount to transfer so that at least net_amount arrives. None on overflow.
+    pub fn amount_before_fee(&self, net_amount: u64) -> Option<u64> {
+        let with_maximum_fee = net_amount.checked_add(self.maximum_fee);
+        let bps = self.transfer_fee_basis_points as u128;
+        let with_fee_rate = if bps < 10_000 {
+            u64::try_from((net_amount as u128 * 10_000).div_ceil(10_000 - bps)).ok()
+        } else {
+            None
+        };
+        let mut amount = match (with_maximum_fee, with_fee_rate) {
+            (Some(a), Some(b)) => a.min(b),
+            (a, b) => a.or(b)?,
+        };
+        // The rounding of the fee can leave the estimate a little short
+        while amount.checked_sub(self.calculate_fee(amount))? < net_amount {
+            amount = amount.checked_add(1)?;
+        }
+        Some(amount)
+    }
+}
+
+/// The value of one of a Token-2022 mint's extensions, None if the mint doesn't have it
+pub fn mint_extension(mint_data: &[u8], extension_type: u16) -> Result<Option<&[u8]>, ProgramError> {
+    let mut extensions = mint_data.get(TokenAccount::LEN + 1..).unwrap_or_default();
+    while let [type_low, type_high, length_low, length_high, rest @ ..] = extensions {
+        let length = u16::from_le_bytes([*length_low, *length_high]) as usize;
+        let value = rest.get(..length).ok_or(ProgramError::InvalidAccountData)?;
+        if u16::from_le_bytes([*type_low, *type_high]) == extension_type {
+            return Ok(Some(value));
+        }
+        extensions = &rest[length..];
+    }
+    Ok(None)
+}
+
+/// The transfer fee a mint charges in the given epoch, None if it doesn't charge one. Only Token-2022 mints can have the extension.
+pub fn unpack_transfer_fee(mint_account: &AccountInfo, epoch: u64) -> Result<Option<TransferFee>, ProgramError> {
+    if *mint_account.owner != token_2022::id() {
+        return Ok(None);
+    }
+    let data = mint_account.try_borrow_data()?;
+    let value = match mint_extension(&data, EXTENSION_TYPE_TRANSFER_FEE_CONFIG)? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let older = value.get(OLDER_TRANSFER_FEE_OFFSET..).and_then(TransferFee::unpack);
+    let newer = value.get(OLDER_TRANSFER_FEE_OFFSET + TRANSFER_FEE_LEN..).and_then(TransferFee::unpack);
+    let (older, newer) = older.zip(newer).ok_or(ProgramError::InvalidAccountData)?;
+    let fee = if epoch >= newer.epoch { newer } else { older };
+    Ok(Some(fee).filter(|fee| fee.transfer_fee_basis_points != 0 || fee.maximum_fee != 0))
+}
+
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    *program_id == spl_token::id() || *program_id == token_2022::id()
+}
+
+/// Unpacks the base token account data of an account owned by either token program
+pub fn unpack_token_account(account: &AccountInfo) -> Result<TokenAccount, ProgramError> {
+    if !is_token_program(account.owner) {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let data = account.try_borrow_data()?;
+    if data.len() > TokenAccount::LEN && data[TokenAccount::LEN] != ACCOUNT_TYPE_ACCOUNT {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let token_account = TokenAccount::unpack_from_slice(
+        data.get(..TokenAccount::LEN).ok_or(ProgramError::InvalidAccountData)?,
+    )?;
+    if !token_account.is_initialized() {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    Ok(token_account)
+}
+
+/// The amount of tokens in a token account owned by either token program, checked like unpack_token_account but without unpacking the rest of it
+pub fn unpack_token_amount(account: &AccountInfo) -> Result<u64, ProgramError> {
+    if !is_token_program(account.owner) {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let data = account.try_borrow_data()?;
+    if data.len() < TokenAccount::LEN || (data.len() > TokenAccount::LEN && data[TokenAccount::LEN] != ACCOUNT_TYPE_ACCOUNT) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if data[TOKEN_ACCOUNT_STATE_OFFSET] == AccountState::Uninitialized as u8 {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    Ok(u64::from_le_bytes(*array_ref![data, TOKEN_ACCOUNT_AMOUNT_OFFSET, 8]))
+}
+
+/// Unpacks the base mint data of a mint owned by either token program
+pub fn unpack_mint(account: &AccountInfo) -> Result<Mint, ProgramError> {
+    if !is_token_program(account.owner) {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let data = account.try_borrow_data()?;
+    // Mints are padded up to the token account length before the account type byte, so a mint and a token account can never be confused for one another
+    if data.len() > TokenAccount::LEN && data[TokenAccount::LEN] != ACCOUNT_TYPE_MINT {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mint = Mint::unpack_from_slice(data.get(..Mint::LEN).ok_or(ProgramError::InvalidAccountData)?)?;
+    if !mint.is_initialized() {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    Ok(mint)
+}
+
+/// The address of a wallet's associated token account for a mint of the given token program
+pub fn get_associated_token_address(wallet: &Pubkey, mint: &Pubkey, token_program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), token_program_id.as_ref(), mint.as_ref()],
+        &associated_token::id(),
+    )
+    .0
+}
+
+/// Creates the associated token account program's Create instruction. Older versions of the program want the rent sysvar as well, newer ones ignore it.
+pub fn create_associated_token_account(
+    payer: &Pubkey,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: associated_token::id(),
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(get_associated_token_address(wallet, mint, token_program_id), false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        data: vec![],
+    }
+}
+
+/// The spl-token instruction builders only accept the original token program id. Since Token-2022 shares the instruction encoding, we build the instruction with spl_token::id() and then point it at the program that actually owns the tokens.
+pub fn for_token_program(
+    instruction: Result<Instruction, ProgramError>,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = instruction?;
+    instruction.program_id = *token_program_id;
+    Ok(instruction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amount_before_fee_covers_the_fee() {
+        let fee = TransferFee { epoch: 0, maximum_fee: 1_000, transfer_fee_basis_points: 150 };
+        assert_eq!(fee.calculate_fee(100), 2);
+        assert_eq!(fee.calculate_fee(1_000_000), 1_000);
+        for net_amount in [0, 1, 99, 500, 12_345, 1_000_000] {
+            let amount = fee.amount_before_fee(net_amount).unwrap();
+            assert!(amount - fee.calculate_fee(amount) >= net_amount);
+            assert!(amount == 0 || amount - 1 - fee.calculate_fee(amount - 1) < net_amount);
+        }
+
+        let everything = TransferFee { epoch: 0, maximum_fee: 7, transfer_fee_basis_points: 10_000 };
+        assert_eq!(everything.amount_before_fee(100), Some(107));
+        assert_eq!(everything.amount_before_fee(u64::MAX), None);
+    }
+
+    #[test]
+    fn token_amount_matches_the_unpacked_account() {
+        let key = Pubkey::new_unique();
+        let owner = spl_token::id();
+        let mut lamports = 0;
+        let mut data = vec![0; TokenAccount::LEN];
+        let token_account = TokenAccount {
+            mint: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            amount: 42,
+            state: spl_token::state::AccountState::Initialized,
+            ..TokenAccount::default()
+        };
+        TokenAccount::pack(token_account, &mut data).unwrap();
+        let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_eq!(unpack_token_amount(&account), Ok(unpack_token_account(&account).unwrap().amount));
+
+        account.try_borrow_mut_data().unwrap()[TOKEN_ACCOUNT_STATE_OFFSET] = 0;
+        assert_eq!(unpack_token_amount(&account), Err(ProgramError::UninitializedAccount));
+    }
+}
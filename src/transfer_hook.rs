@@ -0,0 +1,222 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use std::convert::TryInto;
+
+use crate::token::{self, token_2022};
+
+/// Token-2022 mints with the TransferHook extension have the token program call a hook program on every transfer, passing it the extra accounts listed in a validation account at `[b"extra-account-metas", mint]` of the hook program. Whoever sends the transfer has to include those accounts. spl-transfer-hook-interface resolves them, but it needs a newer solana-program than we build against, so the validation account is read by hand, like the Pyth accounts in `oracle`.
+const EXTENSION_TYPE_TRANSFER_HOOK: u16 = 14;
+/// The TransferHook extension holds its authority before the hook program id
+const HOOK_PROGRAM_ID_OFFSET: usize = 32;
+
+/// The first 8 bytes of sha256("spl-transfer-hook-interface:execute"). They start the data of the Execute instruction the hook receives, and tag the Execute entry of the validation account.
+const EXECUTE_DISCRIMINATOR: [u8; 8] = [105, 37, 101, 197, 75, 251, 102, 26];
+/// An ExtraAccountMeta: a u8 discriminator, a 32 byte address config, is_signer and is_writable
+const EXTRA_ACCOUNT_META_LEN: usize = 35;
+
+/// The hook program of a mint, None if the mint doesn't have one
+pub fn hook_program_id(mint_account: &AccountInfo) -> Result<Option<Pubkey>, ProgramError> {
+    if *mint_account.owner != token_2022::id() {
+        return Ok(None);
+    }
+    let data = mint_account.try_borrow_data()?;
+    let program_id = match token::mint_extension(&data, EXTENSION_TYPE_TRANSFER_HOOK)? {
+        Some(value) => value
+            .get(HOOK_PROGRAM_ID_OFFSET..HOOK_PROGRAM_ID_OFFSET + 32)
+            .and_then(|slice| slice.try_into().ok())
+            .map(Pubkey::new_from_array)
+            .ok_or(ProgramError::InvalidAccountData)?,
+        None => return Ok(None),
+    };
+    Ok(Some(program_id).filter(|program_id| *program_id != Pubkey::default()))
+}
+
+/// Derives the validation account of a mint's hook
+pub fn find_validation_address(mint: &Pubkey, hook_program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"extra-account-metas", mint.as_ref()], hook_program_id).0
+}
+
+/// Appends what the hook of the transferred mint needs to a transfer_checked CPI of `amount`: the extra accounts its validation account lists, then the hook program and the validation account itself. All of them are looked up by key among `accounts`. Transfers of mints without a hook are left alone.
+pub fn add_hook_accounts<'a>(
+    transfer_ix: &mut Instruction,
+    account_infos: &mut Vec<AccountInfo<'a>>,
+    mint_account: &AccountInfo<'a>,
+    amount: u64,
+    accounts: &[AccountInfo<'a>],
+) -> ProgramResult {
+    let hook_program_id = match hook_program_id(mint_account)? {
+        Some(hook_program_id) => hook_program_id,
+        None => return Ok(()),
+    };
+    let find = |key: &Pubkey| {
+        accounts
+            .iter()
+            .find(|account| account.key == key)
+            .ok_or(ProgramError::NotEnoughAccountKeys)
+    };
+    let hook_program = find(&hook_program_id)?;
+    let validation_address = find_validation_address(mint_account.key, &hook_program_id);
+    let validation_account = find(&validation_address)?;
+
+    // The extra accounts can be derived from the Execute instruction the hook receives: the source, the mint, the destination, the authority, the validation account and the extra accounts before them
+    let mut execute_data = EXECUTE_DISCRIMINATOR.to_vec();
+    execute_data.extend_from_slice(&amount.to_le_bytes());
+    let mut execute_keys: Vec<Pubkey> = transfer_ix.accounts.iter().take(4).map(|meta| meta.pubkey).collect();
+    execute_keys.push(validation_address);
+
+    let validation_data = validation_account.try_borrow_data()?;
+    for extra_account_meta in execute_account_metas(&validation_data)?.chunks_exact(EXTRA_ACCOUNT_META_LEN) {
+        let address_config = &extra_account_meta[1..33];
+        let pubkey = match extra_account_meta[0] {
+            0 => Pubkey::new_from_array(address_config.try_into().map_err(|_| ProgramError::InvalidAccountData)?),
+            1 => {
+                let seeds = resolve_seeds(address_config, &execute_data, &execute_keys, &find)?;
+                find_pda(&seeds, &hook_program_id)
+            }
+            index @ 128.. => {
+                let program_id = execute_keys
+                    .get(index as usize - 128)
+                    .ok_or(ProgramError::InvalidAccountData)?;
+                let seeds = resolve_seeds(address_config, &execute_data, &execute_keys, &find)?;
+                find_pda(&seeds, program_id)
+            }
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        transfer_ix.accounts.push(AccountMeta {
+            pubkey,
+            is_signer: extra_account_meta[33] != 0,
+            is_writable: extra_account_meta[34] != 0,
+        });
+        account_infos.push(find(&pubkey)?.clone());
+        execute_keys.push(pubkey);
+    }
+
+    transfer_ix.accounts.push(AccountMeta::new_readonly(hook_program_id, false));
+    transfer_ix.accounts.push(AccountMeta::new_readonly(validation_address, false));
+    account_infos.push(hook_program.clone());
+    account_infos.push(validation_account.clone());
+    Ok(())
+}
+
+// The validation account is a list of 8 byte tag, u32 length and value entries. The Execute entry's value is a u32 count followed by that many ExtraAccountMetas.
+fn execute_account_metas(data: &[u8]) -> Result<&[u8], ProgramError> {
+    let mut entries = data;
+    while entries.len() >= 12 {
+        let length = u32::from_le_bytes(entries[8..12].try_into().unwrap()) as usize;
+        let value = entries.get(12..12 + length).ok_or(ProgramError::InvalidAccountData)?;
+        if entries[..8] == EXECUTE_DISCRIMINATOR {
+            let count = value
+                .get(..4)
+                .map(|count| u32::from_le_bytes(count.try_into().unwrap()) as usize)
+                .ok_or(ProgramError::InvalidAccountData)?;
+            return value
+                .get(4..4 + count * EXTRA_ACCOUNT_META_LEN)
+                .ok_or(ProgramError::InvalidAccountData);
+        }
+        entries = &entries[12 + length..];
+    }
+    Ok(&[])
+}
+
+// A PDA's seeds are packed into the address config one after another, each starting with its kind, until a 0 byte or the end
+fn resolve_seeds<'a, 'b>(
+    address_config: &[u8],
+    execute_data: &[u8],
+    execute_keys: &[Pubkey],
+    find: &impl Fn(&Pubkey) -> Result<&'b AccountInfo<'a>, ProgramError>,
+) -> Result<Vec<Vec<u8>>, ProgramError>
+where
+    'a: 'b,
+{
+    let mut seeds = vec![];
+    let mut config = address_config;
+    loop {
+        let seed = match config {
+            [] | [0, ..] => break,
+            // A literal
+            [1, length, rest @ ..] => {
+                let length = *length as usize;
+                let literal = rest.get(..length).ok_or(ProgramError::InvalidAccountData)?;
+                config = &rest[length..];
+                literal.to_vec()
+            }
+            // Part of the Execute instruction's data
+            [2, index, length, rest @ ..] => {
+                config = rest;
+                let (index, length) = (*index as usize, *length as usize);
+                execute_data.get(index..index + length).ok_or(ProgramError::InvalidAccountData)?.to_vec()
+            }
+            // The key of one of the Execute instruction's accounts
+            [3, index, rest @ ..] => {
+                config = rest;
+                execute_keys.get(*index as usize).ok_or(ProgramError::InvalidAccountData)?.to_bytes().to_vec()
+            }
+            // Part of the data of one of the Execute instruction's accounts
+            [4, account_index, data_index, length, rest @ ..] => {
+                config = rest;
+                let key = execute_keys.get(*account_index as usize).ok_or(ProgramError::InvalidAccountData)?;
+                let (data_index, length) = (*data_index as usize, *length as usize);
+                let data = find(key)?.try_borrow_data()?;
+                data.get(data_index..data_index + length).ok_or(ProgramError::InvalidAccountData)?.to_vec()
+            }
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        seeds.push(seed);
+    }
+    Ok(seeds)
+}
+
+fn find_pda(seeds: &[Vec<u8>], program_id: &Pubkey) -> Pubkey {
+    let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+    Pubkey::find_program_address(&seeds, program_id).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeds_are_read_from_the_address_config() {
+        let execute_keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut execute_data = EXECUTE_DISCRIMINATOR.to_vec();
+        execute_data.extend_from_slice(&42u64.to_le_bytes());
+        let find = |_: &Pubkey| Err::<&AccountInfo, _>(ProgramError::NotEnoughAccountKeys);
+
+        let mut address_config = [0u8; 32];
+        address_config[..10].copy_from_slice(&[1, 3, b'f', b'o', b'o', 3, 1, 2, 8, 8]);
+        let seeds = resolve_seeds(&address_config, &execute_data, &execute_keys, &find).unwrap();
+        assert_eq!(
+            seeds,
+            vec![b"foo".to_vec(), execute_keys[1].to_bytes().to_vec(), 42u64.to_le_bytes().to_vec()]
+        );
+
+        // Account data seeds need the account
+        address_config[..4].copy_from_slice(&[4, 0, 0, 8]);
+        assert_eq!(
+            resolve_seeds(&address_config, &execute_data, &execute_keys, &find),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+
+    #[test]
+    fn execute_entry_of_the_validation_account() {
+        let mut data = vec![];
+        // An entry of some other instruction comes first
+        data.extend_from_slice(&[7u8; 8]);
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data.extend_from_slice(&EXECUTE_DISCRIMINATOR);
+        data.extend_from_slice(&(4 + EXTRA_ACCOUNT_META_LEN as u32).to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&[9u8; EXTRA_ACCOUNT_META_LEN]);
+
+        assert_eq!(execute_account_metas(&data), Ok(&[9u8; EXTRA_ACCOUNT_META_LEN][..]));
+        assert_eq!(execute_account_metas(&data[..10]), Ok(&[][..]));
+        assert_eq!(execute_account_metas(&data[..data.len() - 1]), Err(ProgramError::InvalidAccountData));
+    }
+}
@@ -0,0 +1,253 @@
+//! Wrappers that check an account as they are created, so a processor that takes its accounts through them can't forget a check. Each one derefs to the AccountInfo it wraps, and `info` hands it back as a plain `&AccountInfo` for CPIs and helpers.
+//!
+//! What kind of escrow an account has to be stays in the processor, since that differs from instruction to instruction. Accounts whose key the escrow dictates, like its PDA or its mints, go through KnownAccount once the escrow has been read.
+
+use solana_program::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+};
+use spl_token::state::Account as TokenAccount;
+use std::ops::Deref;
+
+use crate::{
+    error::EscrowError,
+    state::{Config, Escrow},
+    token,
+};
+
+/// An account that signed the transaction
+pub struct Signer<'a, 'info> {
+    info: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> Signer<'a, 'info> {
+    pub fn new(info: &'a AccountInfo<'info>) -> Result<Self, ProgramError> {
+        if !info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        Ok(Signer { info })
+    }
+
+    pub fn info(&self) -> &'a AccountInfo<'info> {
+        self.info
+    }
+}
+
+impl<'a, 'info> Deref for Signer<'a, 'info> {
+    type Target = AccountInfo<'info>;
+    fn deref(&self) -> &Self::Target {
+        self.info
+    }
+}
+
+/// An initialized, writable token account of either token program, with its unpacked state
+pub struct WritableTokenAccount<'a, 'info> {
+    info: &'a AccountInfo<'info>,
+    pub state: TokenAccount,
+}
+
+impl<'a, 'info> WritableTokenAccount<'a, 'info> {
+    pub fn new(info: &'a AccountInfo<'info>) -> Result<Self, ProgramError> {
+        if !info.is_writable {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(WritableTokenAccount { info, state: token::unpack_token_account(info)? })
+    }
+
+    pub fn info(&self) -> &'a AccountInfo<'info> {
+        self.info
+    }
+}
+
+impl<'a, 'info> Deref for WritableTokenAccount<'a, 'info> {
+    type Target = AccountInfo<'info>;
+    fn deref(&self) -> &Self::Target {
+        self.info
+    }
+}
+
+/// An account that has to have a particular key, e.g. one stored in the escrow or a PDA derived from it
+pub struct KnownAccount<'a, 'info> {
+    info: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> KnownAccount<'a, 'info> {
+    /// Fails with `error` unless the account's key is `key`
+    pub fn new(
+        info: &'a AccountInfo<'info>,
+        key: &Pubkey,
+        error: impl Into<ProgramError>,
+    ) -> Result<Self, ProgramError> {
+        if info.key != key {
+            return Err(error.into());
+        }
+        Ok(KnownAccount { info })
+    }
+
+    pub fn info(&self) -> &'a AccountInfo<'info> {
+        self.info
+    }
+}
+
+impl<'a, 'info> Deref for KnownAccount<'a, 'info> {
+    type Target = AccountInfo<'info>;
+    fn deref(&self) -> &Self::Target {
+        self.info
+    }
+}
+
+/// The original token program or Token-2022
+pub struct TokenProgram<'a, 'info> {
+    info: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> TokenProgram<'a, 'info> {
+    pub fn new(info: &'a AccountInfo<'info>) -> Result<Self, ProgramError> {
+        if !token::is_token_program(info.key) {
+            return Err(EscrowError::InvalidTokenProgram.into());
+        }
+        Ok(TokenProgram { info })
+    }
+
+    pub fn info(&self) -> &'a AccountInfo<'info> {
+        self.info
+    }
+}
+
+impl<'a, 'info> Deref for TokenProgram<'a, 'info> {
+    type Target = AccountInfo<'info>;
+    fn deref(&self) -> &Self::Target {
+        self.info
+    }
+}
+
+/// An account type of this program that ProgramOwned can check, with the error a wrong account is reported as
+pub trait ProgramAccount: Pack + IsInitialized {
+    const INVALID_ACCOUNT_ERROR: EscrowError;
+
+    /// Whether an account holding this state may live at `key`. Accounts that only exist at a PDA check it here.
+    fn is_at_address(&self, _key: &Pubkey, _program_id: &Pubkey) -> bool {
+        true
+    }
+}
+
+impl ProgramAccount for Escrow {
+    const INVALID_ACCOUNT_ERROR: EscrowError = EscrowError::InvalidEscrowAccount;
+}
+
+impl ProgramAccount for Config {
+    const INVALID_ACCOUNT_ERROR: EscrowError = EscrowError::InvalidConfigAccount;
+
+    // There is only one config, at the PDA of its stored bump
+    fn is_at_address(&self, key: &Pubkey, program_id: &Pubkey) -> bool {
+        Pubkey::create_program_address(&[b"config", &[self.bump_seed]], program_id).as_ref() == Ok(key)
+    }
+}
+
+/// An account owned by the program and of the size of T, with its unpacked state. Anyone can create an account with bytes that look like an escrow, but only this program can write to accounts it owns.
+pub struct ProgramOwned<'a, 'info, T: ProgramAccount> {
+    info: &'a AccountInfo<'info>,
+    pub state: T,
+}
+
+impl<'a, 'info, T: ProgramAccount> ProgramOwned<'a, 'info, T> {
+    /// Checks the account and unpacks it, failing unless it is initialized
+    pub fn new(info: &'a AccountInfo<'info>, program_id: &Pubkey) -> Result<Self, ProgramError> {
+        Self::check_owner_and_size(info, program_id)?;
+        let state = T::unpack(&info.try_borrow_data()?)?;
+        if !state.is_at_address(info.key, program_id) {
+            return Err(T::INVALID_ACCOUNT_ERROR.into());
+        }
+        Ok(ProgramOwned { info, state })
+    }
+
+    /// Like new, but for an account that is about to be initialized, failing if it already is
+    pub fn new_uninitialized(info: &'a AccountInfo<'info>, program_id: &Pubkey) -> Result<Self, ProgramError> {
+        Self::check_owner_and_size(info, program_id)?;
+        let state = T::unpack_unchecked(&info.try_borrow_data()?)?;
+        if state.is_initialized() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        Ok(ProgramOwned { info, state })
+    }
+
+    pub fn info(&self) -> &'a AccountInfo<'info> {
+        self.info
+    }
+
+    fn check_owner_and_size(info: &AccountInfo, program_id: &Pubkey) -> Result<(), ProgramError> {
+        if info.owner != program_id || info.data_len() != T::LEN {
+            return Err(T::INVALID_ACCOUNT_ERROR.into());
+        }
+        Ok(())
+    }
+}
+
+impl<'a, 'info, T: ProgramAccount> Deref for ProgramOwned<'a, 'info, T> {
+    type Target = AccountInfo<'info>;
+    fn deref(&self) -> &Self::Target {
+        self.info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::clock::Epoch;
+
+    #[test]
+    fn accounts_are_checked_on_construction() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let (mut lamports, mut data) = (0, vec![0u8; Escrow::LEN]);
+        let mut info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &program_id, false, Epoch::default());
+
+        assert_eq!(Signer::new(&info).err(), Some(ProgramError::MissingRequiredSignature));
+        assert_eq!(WritableTokenAccount::new(&info).err(), Some(ProgramError::InvalidArgument));
+        assert_eq!(
+            ProgramOwned::<Escrow>::new(&info, &program_id).err(),
+            Some(ProgramError::UninitializedAccount)
+        );
+        assert!(ProgramOwned::<Escrow>::new_uninitialized(&info, &program_id).is_ok());
+        assert_eq!(
+            ProgramOwned::<Escrow>::new_uninitialized(&info, &Pubkey::new_unique()).err(),
+            Some(EscrowError::InvalidEscrowAccount.into())
+        );
+        assert_eq!(
+            ProgramOwned::<Config>::new(&info, &program_id).err(),
+            Some(EscrowError::InvalidConfigAccount.into())
+        );
+
+        assert!(KnownAccount::new(&info, &key, ProgramError::InvalidSeeds).is_ok());
+        assert_eq!(
+            KnownAccount::new(&info, &program_id, ProgramError::InvalidSeeds).err(),
+            Some(ProgramError::InvalidSeeds)
+        );
+        assert_eq!(TokenProgram::new(&info).err(), Some(EscrowError::InvalidTokenProgram.into()));
+
+        info.is_signer = true;
+        info.is_writable = true;
+        assert_eq!(Signer::new(&info).unwrap().key, &key);
+        assert_eq!(WritableTokenAccount::new(&info).err(), Some(ProgramError::IncorrectProgramId));
+    }
+
+    #[test]
+    fn config_has_to_be_at_its_pda() {
+        let program_id = Pubkey::new_unique();
+        let (config_address, bump_seed) = crate::find_config_address(&program_id);
+        let (mut lamports, mut data) = (0, vec![0u8; Config::LEN]);
+        let config = Config { is_initialized: true, bump_seed, ..Config::unpack_unchecked(&data).unwrap() };
+        Config::pack(config, &mut data).unwrap();
+        let key = Pubkey::new_unique();
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &program_id, false, Epoch::default());
+        assert_eq!(
+            ProgramOwned::<Config>::new(&info, &program_id).err(),
+            Some(EscrowError::InvalidConfigAccount.into())
+        );
+
+        let info = AccountInfo { key: &config_address, ..info };
+        assert_eq!(ProgramOwned::<Config>::new(&info, &program_id).unwrap().state, config);
+    }
+}
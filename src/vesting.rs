@@ -0,0 +1,74 @@
+use crate::math::{self, Rounding};
+
+/// The amount of `total_amount` that has vested at `unix_timestamp` under a schedule that releases it in `periods` equal tranches, one every `period_seconds` after `start_unix_timestamp`. Rounds down, so the last tranche picks up whatever the others leave over.
+pub fn vested_amount(
+    total_amount: u64,
+    start_unix_timestamp: i64,
+    period_seconds: i64,
+    periods: u16,
+    unix_timestamp: i64,
+) -> u64 {
+    if periods == 0 || period_seconds <= 0 || unix_timestamp <= start_unix_timestamp {
+        return 0;
+    }
+
+    let elapsed_periods = ((unix_timestamp - start_unix_timestamp) / period_seconds).min(periods as i64);
+    // elapsed_periods <= periods, so the result always fits back into a u64
+    math::mul_div(total_amount, elapsed_periods as u64, periods as u64, Rounding::Down).unwrap_or(total_amount)
+}
+
+/// The amount of `total_amount` that has streamed at `unix_timestamp` if it is released at a constant rate per second between `start_unix_timestamp` and `end_unix_timestamp`. Rounds down.
+pub fn streamed_amount(total_amount: u64, start_unix_timestamp: i64, end_unix_timestamp: i64, unix_timestamp: i64) -> u64 {
+    if unix_timestamp >= end_unix_timestamp {
+        return total_amount;
+    }
+    if unix_timestamp <= start_unix_timestamp {
+        return 0;
+    }
+
+    let elapsed_seconds = (unix_timestamp - start_unix_timestamp) as u64;
+    let duration_seconds = (end_unix_timestamp - start_unix_timestamp) as u64;
+    // elapsed_seconds < duration_seconds, so the result always fits back into a u64
+    math::mul_div(total_amount, elapsed_seconds, duration_seconds, Rounding::Down).unwrap_or(total_amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vests_in_tranches() {
+        // 1000 tokens over 4 periods of 100 seconds, starting at 1000
+        let vested = |unix_timestamp| vested_amount(1_000, 1_000, 100, 4, unix_timestamp);
+
+        assert_eq!(vested(0), 0);
+        assert_eq!(vested(1_000), 0);
+        assert_eq!(vested(1_099), 0);
+        assert_eq!(vested(1_100), 250);
+        assert_eq!(vested(1_399), 750);
+        assert_eq!(vested(1_400), 1_000);
+        assert_eq!(vested(i64::MAX), 1_000);
+
+        // Rounds down per tranche, the remainder vests with the last one
+        assert_eq!(vested_amount(10, 0, 1, 3, 1), 3);
+        assert_eq!(vested_amount(10, 0, 1, 3, 3), 10);
+        assert_eq!(vested_amount(u64::MAX, 0, 1, u16::MAX, 1), u64::MAX / u16::MAX as u64);
+    }
+
+    #[test]
+    fn streams_per_second() {
+        // 1000 tokens between 1000 and 1400, i.e. 2.5 tokens a second
+        let streamed = |unix_timestamp| streamed_amount(1_000, 1_000, 1_400, unix_timestamp);
+
+        assert_eq!(streamed(0), 0);
+        assert_eq!(streamed(1_000), 0);
+        assert_eq!(streamed(1_001), 2);
+        assert_eq!(streamed(1_002), 5);
+        assert_eq!(streamed(1_200), 500);
+        assert_eq!(streamed(1_399), 997);
+        assert_eq!(streamed(1_400), 1_000);
+        assert_eq!(streamed(i64::MAX), 1_000);
+
+        assert_eq!(streamed_amount(u64::MAX, 0, 2, 1), u64::MAX / 2);
+    }
+}
@@ -0,0 +1,122 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use spl_token::state::Account as TokenAccount;
+
+use crate::{error::EscrowError, state::Escrow};
+
+/// What an escrow can still be used for, as far as GetEscrow can tell
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum EscrowStatus {
+    /// Can be taken
+    Open,
+    /// Past its expiry, waiting for ReclaimExpired
+    Expired,
+    /// Accepted by a taker and waiting to be settled
+    Accepted,
+    /// Can never be exchanged, see CloseStaleEscrow
+    Stale,
+}
+
+/// The return data of GetEscrow, Borsh encoded. Like events, this is part of the program's API: only ever append fields.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct EscrowView {
+    pub initializer: Pubkey,
+    pub token_x_mint: Pubkey,
+    pub token_y_mint: Pubkey,
+    /// The amount of token X the escrow still offers, 0 if its temp token account was closed
+    pub amount_x: u64,
+    /// The amount of token Y a taker would pay for all of amount_x at the time of the call, see Escrow::price_of. Oracle-priced escrows report their stored expected amount, the price feed isn't read.
+    pub amount_y: u64,
+    pub expiry_unix_timestamp: i64,
+    pub status: EscrowStatus,
+    /// The metadata the initializer attached, see InitEscrow
+    pub metadata: Vec<u8>,
+    /// The id the client gave the escrow, 0 if none
+    pub escrow_id: u64,
+    /// The lamports Reserve takes, 0 if the escrow can't be reserved
+    pub reservation_deposit: u64,
+    /// The taker holding a reservation and the slot it lapses at, the default pubkey if nobody has reserved the escrow
+    pub reserved_by: Pubkey,
+    pub reserved_until_slot: u64,
+    /// The root of the escrow's taker allowlist, all zeroes if it has none
+    pub taker_merkle_root: [u8; 32],
+    /// The collection a collection offer buys from, the default pubkey if the escrow isn't one
+    pub collection: Pubkey,
+    /// The other mints the escrow accepts as payment, each with the amount a taker paying in it would pay for all of amount_x
+    pub payment_options: Vec<(Pubkey, u64)>,
+    /// The escrow's price in USD, with USD_DECIMALS decimals, 0 if it is priced in token Y. amount_y and the payment options don't apply to USD-priced escrows.
+    pub usd_amount: u64,
+}
+
+impl EscrowView {
+    /// Builds the view of an escrow at unix_timestamp, given the account temp_token_account_pubkey points to (None if it was closed) and the escrow's PDA
+    pub fn new(
+        escrow: &Escrow,
+        temp_token_account: Option<&TokenAccount>,
+        pda: &Pubkey,
+        unix_timestamp: i64,
+    ) -> Result<Self, ProgramError> {
+        let status = if escrow.is_accepted() {
+            EscrowStatus::Accepted
+        } else if escrow.is_stale(temp_token_account, pda) {
+            EscrowStatus::Stale
+        } else if escrow.is_expired(unix_timestamp) {
+            EscrowStatus::Expired
+        } else {
+            EscrowStatus::Open
+        };
+
+        let amount_x = temp_token_account.map_or(0, |temp_token_account| escrow.escrowed_amount(temp_token_account));
+        let payment_options = escrow
+            .payment_option_mints()
+            .iter()
+            .map(|mint| {
+                let amount_y = escrow.paid_in(mint).and_then(|paid_in| paid_in.price_of(amount_x, unix_timestamp));
+                Ok((*mint, amount_y.ok_or(EscrowError::PriceOverflow)?))
+            })
+            .collect::<Result<_, ProgramError>>()?;
+        Ok(EscrowView {
+            initializer: escrow.initializer_pubkey,
+            token_x_mint: escrow.token_x_mint,
+            token_y_mint: escrow.token_y_mint,
+            amount_x,
+            amount_y: escrow.price_of(amount_x, unix_timestamp).ok_or(EscrowError::PriceOverflow)?,
+            expiry_unix_timestamp: escrow.expiry_unix_timestamp,
+            status,
+            metadata: escrow.metadata().to_vec(),
+            escrow_id: escrow.escrow_id,
+            reservation_deposit: escrow.reservation_deposit,
+            reserved_by: escrow.reserved_by,
+            reserved_until_slot: escrow.reserved_until_slot,
+            taker_merkle_root: escrow.taker_merkle_root,
+            collection: escrow.collection,
+            payment_options,
+            usd_amount: escrow.usd_amount,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    #[test]
+    fn status_of_an_escrow() {
+        let pda = Pubkey::new_unique();
+        let mut escrow = Escrow { expected_amount: 50, expiry_unix_timestamp: 1_000, ..Escrow::zeroed() };
+        let temp_token_account = TokenAccount { owner: pda, amount: 100, ..TokenAccount::default() };
+
+        let view = EscrowView::new(&escrow, Some(&temp_token_account), &pda, 999).unwrap();
+        assert_eq!((view.amount_x, view.amount_y, view.status), (100, 50, EscrowStatus::Open));
+        let view = EscrowView::new(&escrow, Some(&temp_token_account), &pda, 1_000).unwrap();
+        assert_eq!(view.status, EscrowStatus::Expired);
+        let view = EscrowView::new(&escrow, None, &pda, 999).unwrap();
+        assert_eq!((view.amount_x, view.status), (0, EscrowStatus::Stale));
+
+        escrow.accepted_unix_timestamp = 900;
+        let view = EscrowView::new(&escrow, None, &pda, 999).unwrap();
+        assert_eq!(view.status, EscrowStatus::Accepted);
+        assert_eq!(EscrowView::try_from_slice(&view.try_to_vec().unwrap()).unwrap(), view);
+    }
+}
@@ -0,0 +1,37 @@
+//! Instruction data and account data are attacker-controlled input, so the decoders must reject any byte slice they don't understand with an error instead of panicking or reading out of bounds.
+
+use proptest::prelude::*;
+use solana_escrow::{instruction::EscrowInstruction, state::Escrow};
+use solana_program::program_pack::Pack;
+
+proptest! {
+    #[test]
+    fn unpack_instruction_never_panics(data in proptest::collection::vec(any::<u8>(), 0..64)) {
+        if let Ok(instruction) = EscrowInstruction::unpack(&data) {
+            // Whatever was accepted must survive a round trip through pack
+            let packed = instruction.pack();
+            prop_assert_eq!(EscrowInstruction::unpack(&packed).unwrap(), instruction);
+        }
+    }
+
+    #[test]
+    fn unpack_tagged_instruction_never_panics(tag in 0u8..92, rest in proptest::collection::vec(any::<u8>(), 0..24)) {
+        let mut data = vec![tag];
+        data.extend_from_slice(&rest);
+        let _ = EscrowInstruction::unpack(&data);
+    }
+
+    #[test]
+    fn unpack_escrow_never_panics(data in proptest::collection::vec(any::<u8>(), 0..Escrow::LEN * 2)) {
+        let _ = Escrow::unpack_unchecked(&data);
+    }
+
+    #[test]
+    fn unpack_escrow_round_trips(data in proptest::collection::vec(any::<u8>(), Escrow::LEN)) {
+        if let Ok(escrow) = Escrow::unpack_unchecked(&data) {
+            let mut packed = vec![0u8; Escrow::LEN];
+            escrow.pack_into_slice(&mut packed);
+            prop_assert_eq!(packed, data);
+        }
+    }
+}
@@ -0,0 +1,2749 @@
+use solana_escrow::{
+    compressed_escrow::{self, CompressedEscrow},
+    compression,
+    error::EscrowError,
+    escrow_pool::EscrowPool,
+    find_config_address, find_escrow_address, find_escrow_authority_address, find_fee_authority_address,
+    find_fill_record_address, find_legacy_escrow_authority_address, find_market_address, find_raffle_address,
+    find_registry_address, find_ring_address, find_stats_address, find_ticket_address,
+    instruction, merkle,
+    order_book::Market,
+    processor::Processor,
+    raffle::Raffle,
+    sealed_auction, signed_order,
+    state::{Config, Escrow, EscrowRegistry, EscrowV1, FillRecord, Stats},
+    swap_ring::SwapRing,
+    switchboard, token,
+};
+use solana_program::{
+    account_info::AccountInfo,
+    bpf_loader_upgradeable,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, InstructionError},
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_instruction, sysvar,
+};
+use solana_program_test::{processor, tokio, BanksClient, ProgramTest, ProgramTestBanksClientExt, ProgramTestContext};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+
+const AMOUNT_X: u64 = 1_000;
+const AMOUNT_Y: u64 = 500;
+
+/// A gate program that approves takers who pass a credential, any account it owns
+const GATE_PROGRAM_ID: Pubkey = Pubkey::new_from_array([7; 32]);
+const GATE_CREDENTIAL: Pubkey = Pubkey::new_from_array([8; 32]);
+
+fn process_gate(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if data.get(..8) != Some(&solana_escrow::gate::CHECK_DISCRIMINATOR[..]) || !accounts[0].is_signer {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if !accounts[2..].iter().any(|account| account.owner == program_id) {
+        return Err(ProgramError::Custom(42));
+    }
+    Ok(())
+}
+
+/// A stand-in for Switchboard that accepts every randomness request the VRF's authority signs. The tests write the VRF's result themselves, see vrf_account.
+fn process_switchboard(_program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+    if !accounts[0].is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/// A stand-in for the compression program. Instead of a concurrent merkle tree, the tree account holds the number of leaves followed by the leaves themselves, and replace_leaf checks the previous leaf against the stored one rather than against a proof.
+fn process_compression(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if !accounts[1].is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut tree = accounts[0].try_borrow_mut_data()?;
+    let leaf = |index: usize| 8 + index * 32..8 + (index + 1) * 32;
+    match data.get(..8) {
+        // init_empty_merkle_tree
+        Some([191, 11, 119, 7, 180, 107, 220, 110]) => Ok(()),
+        // append
+        Some([149, 120, 18, 222, 236, 225, 88, 203]) => {
+            let num_leaves = u64::from_le_bytes(tree[..8].try_into().unwrap());
+            tree[leaf(num_leaves as usize)].copy_from_slice(&data[8..40]);
+            tree[..8].copy_from_slice(&(num_leaves + 1).to_le_bytes());
+            Ok(())
+        }
+        // replace_leaf, whose data is the root, the previous leaf, the new leaf and the index
+        Some([204, 165, 76, 100, 73, 147, 0, 128]) => {
+            let index = u32::from_le_bytes(data[104..108].try_into().unwrap()) as usize;
+            if tree[leaf(index)] != data[40..72] {
+                return Err(ProgramError::InvalidArgument);
+            }
+            tree[leaf(index)].copy_from_slice(&data[72..104]);
+            Ok(())
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// The `anchor` feature's entrypoint wants the account infos to live as long as the accounts they point to, which processor! can't promise. Leaking a copy of them is fine for a test.
+#[cfg(feature = "anchor")]
+fn process_anchor(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    solana_escrow::anchor::entry(program_id, Box::leak(accounts.to_vec().into_boxed_slice()), data)
+}
+
+/// Everything Alice (the initializer) and Bob (the taker) need for a trade of AMOUNT_X X tokens against AMOUNT_Y Y tokens
+struct Trade {
+    context: ProgramTestContext,
+    banks_client: BanksClient,
+    payer: Keypair,
+    program_id: Pubkey,
+    admin: Keypair,
+    mint_x: Pubkey,
+    mint_y: Pubkey,
+    alice: Keypair,
+    alice_x: Pubkey,
+    alice_y: Pubkey,
+    bob: Keypair,
+    bob_x: Pubkey,
+    bob_y: Pubkey,
+    temp_token_account: Keypair,
+    escrow_account: Keypair,
+}
+
+async fn send(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    instructions: &[solana_program::instruction::Instruction],
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        &all_signers,
+        recent_blockhash,
+    );
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|e| e.unwrap())
+}
+
+/// Sends the instructions like `send`, after simulating them to read how many compute units the escrow program consumed from the logs
+async fn send_metered(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    instructions: &[solana_program::instruction::Instruction],
+    signers: &[&Keypair],
+) -> u64 {
+    let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        &all_signers,
+        recent_blockhash,
+    );
+    let simulation = banks_client.simulate_transaction(transaction.clone()).await.unwrap();
+    simulation.result.unwrap().unwrap();
+    let consumed_prefix = format!("Program {program_id} consumed ");
+    let units = simulation
+        .simulation_details
+        .unwrap()
+        .logs
+        .iter()
+        .filter_map(|log| log.strip_prefix(&consumed_prefix)?.split(' ').next()?.parse::<u64>().ok())
+        .sum();
+    banks_client.process_transaction(transaction).await.unwrap();
+    units
+}
+
+async fn create_mint(banks_client: &mut BanksClient, payer: &Keypair) -> Pubkey {
+    let mint = Keypair::new();
+    let rent = banks_client.get_rent().await.unwrap();
+    send(
+        banks_client,
+        payer,
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(Mint::LEN),
+                Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &payer.pubkey(),
+                None,
+                0,
+            )
+            .unwrap(),
+        ],
+        &[&mint],
+    )
+    .await
+    .unwrap();
+    mint.pubkey()
+}
+
+async fn create_token_account(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    account: &Keypair,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) {
+    let rent = banks_client.get_rent().await.unwrap();
+    send(
+        banks_client,
+        payer,
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                &account.pubkey(),
+                mint,
+                owner,
+            )
+            .unwrap(),
+            spl_token::instruction::mint_to(
+                &spl_token::id(),
+                mint,
+                &account.pubkey(),
+                &payer.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap(),
+        ],
+        &[account],
+    )
+    .await
+    .unwrap();
+}
+
+async fn token_balance(banks_client: &mut BanksClient, account: &Pubkey) -> u64 {
+    let account = banks_client.get_account(*account).await.unwrap().unwrap();
+    TokenAccount::unpack(&account.data).unwrap().amount
+}
+
+/// Waits for the next blockhash, so that sending the same instructions again isn't turned away as a duplicate transaction
+async fn next_blockhash(banks_client: &mut BanksClient) {
+    let blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    banks_client.get_new_latest_blockhash(&blockhash).await.unwrap();
+}
+
+async fn delegated_amount(banks_client: &mut BanksClient, account: &Pubkey) -> u64 {
+    let account = banks_client.get_account(*account).await.unwrap().unwrap();
+    TokenAccount::unpack(&account.data).unwrap().delegated_amount
+}
+
+/// The test validator's clock
+async fn now(banks_client: &mut BanksClient) -> i64 {
+    banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp
+}
+
+async fn warp_to_timestamp(trade: &mut Trade, unix_timestamp: i64) {
+    let mut clock = trade.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp = unix_timestamp;
+    trade.context.set_sysvar(&clock);
+}
+
+/// Gives Alice and Bob lamports for the rent of the accounts the newer flows create on their behalf
+async fn fund(trade: &mut Trade) {
+    let instructions = [
+        system_instruction::transfer(&trade.payer.pubkey(), &trade.alice.pubkey(), 1_000_000_000),
+        system_instruction::transfer(&trade.payer.pubkey(), &trade.bob.pubkey(), 1_000_000_000),
+    ];
+    send(&mut trade.banks_client, &trade.payer, &instructions, &[])
+        .await
+        .unwrap();
+}
+
+fn escrow_error(index: u8, error: EscrowError) -> TransactionError {
+    TransactionError::InstructionError(index, InstructionError::Custom(error as u32))
+}
+
+async fn setup() -> Trade {
+    let program_id = Pubkey::new_unique();
+    setup_program(ProgramTest::new("solana_escrow", program_id, processor!(Processor::process)), program_id).await
+}
+
+/// Like `setup`, with the escrow program already added to the given ProgramTest
+async fn setup_program(mut program_test: ProgramTest, program_id: Pubkey) -> Trade {
+    program_test.add_program("escrow_gate", GATE_PROGRAM_ID, processor!(process_gate));
+    program_test.add_program("switchboard", switchboard::id(), processor!(process_switchboard));
+    program_test.add_program("spl_account_compression", compression::id(), processor!(process_compression));
+    program_test.add_account(
+        GATE_CREDENTIAL,
+        Account { lamports: 1_000_000_000, owner: GATE_PROGRAM_ID, ..Account::default() },
+    );
+
+    // The program is loaded natively, so there is no program data account naming the upgrade authority InitializeConfig checks for. Add one by hand.
+    let admin = Keypair::new();
+    let (program_data, _bump_seed) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    let mut program_data_state = vec![3, 0, 0, 0];
+    program_data_state.extend_from_slice(&0u64.to_le_bytes());
+    program_data_state.push(1);
+    program_data_state.extend_from_slice(admin.pubkey().as_ref());
+    program_test.add_account(
+        program_data,
+        Account {
+            lamports: 1_000_000_000,
+            data: program_data_state,
+            owner: bpf_loader_upgradeable::id(),
+            ..Account::default()
+        },
+    );
+    program_test.add_account(
+        admin.pubkey(),
+        Account { lamports: 1_000_000_000, ..Account::default() },
+    );
+
+    // The context lets the flows that wait for a deadline or a slot move the clock
+    let context = program_test.start_with_context().await;
+    let (mut banks_client, payer) = (context.banks_client.clone(), context.payer.insecure_clone());
+    send(
+        &mut banks_client,
+        &payer,
+        &[instruction::initialize_config(&program_id, &admin.pubkey(), 0, 0, 0).unwrap()],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    let mint_x = create_mint(&mut banks_client, &payer).await;
+    let mint_y = create_mint(&mut banks_client, &payer).await;
+
+    let alice = Keypair::new();
+    let bob = Keypair::new();
+    let (alice_x, alice_y, bob_x, bob_y) = (Keypair::new(), Keypair::new(), Keypair::new(), Keypair::new());
+    create_token_account(&mut banks_client, &payer, &alice_x, &mint_x, &alice.pubkey(), AMOUNT_X).await;
+    create_token_account(&mut banks_client, &payer, &alice_y, &mint_y, &alice.pubkey(), 0).await;
+    create_token_account(&mut banks_client, &payer, &bob_x, &mint_x, &bob.pubkey(), 0).await;
+    create_token_account(&mut banks_client, &payer, &bob_y, &mint_y, &bob.pubkey(), AMOUNT_Y).await;
+
+    Trade {
+        context,
+        banks_client,
+        payer,
+        program_id,
+        admin,
+        mint_x,
+        mint_y,
+        alice,
+        alice_x: alice_x.pubkey(),
+        alice_y: alice_y.pubkey(),
+        bob,
+        bob_x: bob_x.pubkey(),
+        bob_y: bob_y.pubkey(),
+        temp_token_account: Keypair::new(),
+        escrow_account: Keypair::new(),
+    }
+}
+
+/// The first half of the program's flow: Alice moves her X tokens into a temp token account and hands it over to the escrow
+async fn init_escrow(trade: &mut Trade) -> Result<(), TransactionError> {
+    init_escrow_with_dispute_window(trade, 0).await
+}
+
+async fn init_escrow_with_dispute_window(
+    trade: &mut Trade,
+    dispute_window_seconds: i64,
+) -> Result<(), TransactionError> {
+    let init_escrow = instruction::init_two_phase_escrow(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_y,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        AMOUNT_Y,
+        0,
+        None,
+        dispute_window_seconds,
+    )
+    .unwrap();
+    init_escrow_with(trade, init_escrow).await
+}
+
+async fn init_escrow_with(
+    trade: &mut Trade,
+    init_escrow: solana_program::instruction::Instruction,
+) -> Result<(), TransactionError> {
+    let instructions = init_escrow_instructions(trade, init_escrow).await;
+    let signers = [&trade.alice, &trade.temp_token_account, &trade.escrow_account];
+    send(&mut trade.banks_client, &trade.payer, &instructions, &signers).await
+}
+
+/// The temp token account and escrow account to create and fund ahead of the given init instruction
+async fn init_escrow_instructions(
+    trade: &mut Trade,
+    init_escrow: solana_program::instruction::Instruction,
+) -> Vec<solana_program::instruction::Instruction> {
+    let rent = trade.banks_client.get_rent().await.unwrap();
+    vec![
+        system_instruction::create_account(
+            &trade.payer.pubkey(),
+            &trade.temp_token_account.pubkey(),
+            rent.minimum_balance(TokenAccount::LEN),
+            TokenAccount::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_account(
+            &spl_token::id(),
+            &trade.temp_token_account.pubkey(),
+            &trade.mint_x,
+            &trade.alice.pubkey(),
+        )
+        .unwrap(),
+        spl_token::instruction::transfer(
+            &spl_token::id(),
+            &trade.alice_x,
+            &trade.temp_token_account.pubkey(),
+            &trade.alice.pubkey(),
+            &[],
+            AMOUNT_X,
+        )
+        .unwrap(),
+        system_instruction::create_account(
+            &trade.payer.pubkey(),
+            &trade.escrow_account.pubkey(),
+            rent.minimum_balance(Escrow::LEN),
+            Escrow::LEN as u64,
+            &trade.program_id,
+        ),
+        init_escrow,
+    ]
+}
+
+/// Bob takes `amount` of Alice's escrow, paying at most everything he has
+fn exchange_args(trade: &Trade, amount: u64) -> instruction::ExchangeArgs {
+    instruction::ExchangeArgs {
+        taker: trade.bob.pubkey(),
+        takers_sending_token_account: trade.bob_y,
+        takers_token_to_receive_account: trade.bob_x,
+        pdas_temp_token_account: trade.temp_token_account.pubkey(),
+        initializers_main_account: trade.alice.pubkey(),
+        initializers_token_to_receive_account: trade.alice_y,
+        escrow_account: trade.escrow_account.pubkey(),
+        token_program_id: spl_token::id(),
+        token_x_mint: trade.mint_x,
+        token_y_mint: trade.mint_y,
+        y_token_program_id: spl_token::id(),
+        fee_vault: None,
+        referrer_token_account: None,
+        create_takers_token_account: false,
+        price_feed: None,
+        amount,
+        max_payment: AMOUNT_Y,
+    }
+}
+
+fn exchange_instruction(trade: &Trade, amount: u64) -> solana_program::instruction::Instruction {
+    instruction::exchange(&trade.program_id, exchange_args(trade, amount)).unwrap()
+}
+
+#[tokio::test]
+async fn test_init_escrow() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+
+    let escrow_account = trade
+        .banks_client
+        .get_account(trade.escrow_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let escrow = Escrow::unpack(&escrow_account.data).unwrap();
+    assert_eq!(escrow.initializer_pubkey, trade.alice.pubkey());
+    assert_eq!(escrow.temp_token_account_pubkey, trade.temp_token_account.pubkey());
+    assert_eq!(escrow.initializer_token_to_receive_account_pubkey, trade.alice_y);
+    assert_eq!({ escrow.expected_amount }, AMOUNT_Y);
+    assert!(escrow.created_unix_timestamp > 0);
+
+    let temp_token_account = trade
+        .banks_client
+        .get_account(trade.temp_token_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let (pda, _bump_seed) =
+        find_escrow_authority_address(&trade.escrow_account.pubkey(), &trade.program_id);
+    assert_eq!(TokenAccount::unpack(&temp_token_account.data).unwrap().owner, pda);
+}
+
+#[tokio::test]
+async fn test_init_escrow_with_metadata() {
+    let mut trade = setup().await;
+    let init_escrow = instruction::init_escrow(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_y,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        AMOUNT_Y,
+        0,
+        None,
+    )
+    .unwrap();
+
+    let error = init_escrow_with(&mut trade, instruction::with_metadata(init_escrow.clone(), &[7; 129]).unwrap())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            4,
+            InstructionError::Custom(solana_escrow::error::EscrowError::MetadataTooLong as u32)
+        )
+    );
+
+    let init_escrow = instruction::with_metadata(init_escrow, b"https://example.com/listings/42").unwrap();
+    init_escrow_with(&mut trade, init_escrow).await.unwrap();
+    let escrow_account = trade
+        .banks_client
+        .get_account(trade.escrow_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let escrow = Escrow::unpack(&escrow_account.data).unwrap();
+    assert_eq!(escrow.metadata(), b"https://example.com/listings/42");
+}
+
+#[tokio::test]
+async fn test_init_escrow_with_id() {
+    let mut trade = setup().await;
+    fund(&mut trade).await;
+    let init_escrow = instruction::init_escrow(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_y,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        AMOUNT_Y,
+        0,
+        None,
+    )
+    .unwrap();
+    // The escrow account created by init_escrow_with goes unused
+    let init_escrow = instruction::with_escrow_id(init_escrow, 42).unwrap();
+    init_escrow_with(&mut trade, init_escrow).await.unwrap();
+
+    let (escrow_address, _bump_seed) = find_escrow_address(&trade.alice.pubkey(), 42, &trade.program_id);
+    let escrow_account = trade.banks_client.get_account(escrow_address).await.unwrap().unwrap();
+    assert_eq!(escrow_account.owner, trade.program_id);
+    let escrow = Escrow::unpack(&escrow_account.data).unwrap();
+    assert_eq!({ escrow.escrow_id }, 42);
+    assert_eq!(escrow.initializer_pubkey, trade.alice.pubkey());
+}
+
+#[tokio::test]
+async fn test_init_escrow_with_rent_sysvar() {
+    let mut trade = setup().await;
+    // Clients written before the rent sysvar was dropped still pass it as account 4
+    let mut init_escrow = instruction::init_escrow(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_y,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        AMOUNT_Y,
+        0,
+        None,
+    )
+    .unwrap();
+    init_escrow.accounts.insert(4, AccountMeta::new_readonly(sysvar::rent::id(), false));
+    init_escrow_with(&mut trade, init_escrow).await.unwrap();
+
+    let escrow_account = trade
+        .banks_client
+        .get_account(trade.escrow_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!({ Escrow::unpack(&escrow_account.data).unwrap().expected_amount }, AMOUNT_Y);
+}
+
+#[tokio::test]
+async fn test_init_escrow_with_deposit() {
+    let mut trade = setup().await;
+    let rent = trade.banks_client.get_rent().await.unwrap();
+    let instructions = [
+        // Alice pays for the temp token account herself
+        system_instruction::transfer(
+            &trade.payer.pubkey(),
+            &trade.alice.pubkey(),
+            rent.minimum_balance(TokenAccount::LEN),
+        ),
+        system_instruction::create_account(
+            &trade.payer.pubkey(),
+            &trade.escrow_account.pubkey(),
+            rent.minimum_balance(Escrow::LEN),
+            Escrow::LEN as u64,
+            &trade.program_id,
+        ),
+        instruction::init_escrow_with_deposit(
+            &trade.program_id,
+            &trade.alice.pubkey(),
+            &trade.temp_token_account.pubkey(),
+            &trade.alice_x,
+            &trade.mint_x,
+            &trade.alice_y,
+            &trade.escrow_account.pubkey(),
+            &spl_token::id(),
+            AMOUNT_X,
+            AMOUNT_Y,
+            0,
+            None,
+        )
+        .unwrap(),
+    ];
+    let signers = [&trade.alice, &trade.temp_token_account, &trade.escrow_account];
+    send(&mut trade.banks_client, &trade.payer, &instructions, &signers)
+        .await
+        .unwrap();
+
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_x).await, 0);
+    assert_eq!(
+        token_balance(&mut trade.banks_client, &trade.temp_token_account.pubkey()).await,
+        AMOUNT_X
+    );
+
+    let exchange = exchange_instruction(&trade, AMOUNT_X);
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+}
+
+#[tokio::test]
+async fn test_init_escrow_creates_escrow_account() {
+    let mut trade = setup().await;
+    let rent = trade.banks_client.get_rent().await.unwrap();
+    let init_escrow = instruction::init_escrow_with_deposit(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_x,
+        &trade.mint_x,
+        &trade.alice_y,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        AMOUNT_X,
+        AMOUNT_Y,
+        0,
+        None,
+    )
+    .unwrap();
+    let instructions = [
+        // Alice pays for both accounts
+        system_instruction::transfer(
+            &trade.payer.pubkey(),
+            &trade.alice.pubkey(),
+            rent.minimum_balance(TokenAccount::LEN) + rent.minimum_balance(Escrow::LEN),
+        ),
+        instruction::with_escrow_account_creation(init_escrow),
+    ];
+    let signers = [&trade.alice, &trade.temp_token_account, &trade.escrow_account];
+    send(&mut trade.banks_client, &trade.payer, &instructions, &signers)
+        .await
+        .unwrap();
+
+    let escrow_account = trade.banks_client.get_account(trade.escrow_account.pubkey()).await.unwrap().unwrap();
+    assert_eq!(escrow_account.owner, trade.program_id);
+    assert_eq!(escrow_account.lamports, rent.minimum_balance(Escrow::LEN));
+    assert_eq!(Escrow::unpack(&escrow_account.data).unwrap().initializer_pubkey, trade.alice.pubkey());
+}
+
+#[tokio::test]
+async fn test_native_sol_escrow() {
+    let mut trade = setup().await;
+    let rent = trade.banks_client.get_rent().await.unwrap();
+    let lamports = 1_000_000_000;
+    let instructions = [
+        system_instruction::transfer(
+            &trade.payer.pubkey(),
+            &trade.alice.pubkey(),
+            lamports + rent.minimum_balance(TokenAccount::LEN),
+        ),
+        system_instruction::create_account(
+            &trade.payer.pubkey(),
+            &trade.escrow_account.pubkey(),
+            rent.minimum_balance(Escrow::LEN),
+            Escrow::LEN as u64,
+            &trade.program_id,
+        ),
+        instruction::init_native_sol_escrow(
+            &trade.program_id,
+            &trade.alice.pubkey(),
+            &trade.temp_token_account.pubkey(),
+            &trade.alice_y,
+            &trade.escrow_account.pubkey(),
+            lamports,
+            AMOUNT_Y,
+            0,
+            None,
+        )
+        .unwrap(),
+    ];
+    let signers = [&trade.alice, &trade.temp_token_account, &trade.escrow_account];
+    send(&mut trade.banks_client, &trade.payer, &instructions, &signers)
+        .await
+        .unwrap();
+    assert_eq!(
+        token_balance(&mut trade.banks_client, &trade.temp_token_account.pubkey()).await,
+        lamports
+    );
+
+    // Bob gets plain SOL in his main account, no wrapped SOL account needed
+    let exchange = instruction::exchange(
+        &trade.program_id,
+        instruction::ExchangeArgs {
+            takers_token_to_receive_account: trade.bob.pubkey(),
+            token_x_mint: spl_token::native_mint::id(),
+            ..exchange_args(&trade, lamports)
+        },
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+
+    assert_eq!(trade.banks_client.get_balance(trade.bob.pubkey()).await.unwrap(), lamports);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y);
+}
+
+#[tokio::test]
+async fn test_delegated_escrow() {
+    let mut trade = setup().await;
+    let rent = trade.banks_client.get_rent().await.unwrap();
+    let delegated_amount = AMOUNT_X - 400;
+    let instructions = [
+        system_instruction::create_account(
+            &trade.payer.pubkey(),
+            &trade.escrow_account.pubkey(),
+            rent.minimum_balance(Escrow::LEN),
+            Escrow::LEN as u64,
+            &trade.program_id,
+        ),
+        instruction::init_delegated_escrow(
+            &trade.program_id,
+            &trade.alice.pubkey(),
+            &trade.alice_x,
+            &trade.alice_y,
+            &trade.escrow_account.pubkey(),
+            &spl_token::id(),
+            delegated_amount,
+            AMOUNT_Y,
+            0,
+            None,
+        )
+        .unwrap(),
+    ];
+    let signers = [&trade.alice, &trade.escrow_account];
+    send(&mut trade.banks_client, &trade.payer, &instructions, &signers)
+        .await
+        .unwrap();
+    // Nothing moves until the exchange
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_x).await, AMOUNT_X);
+
+    let exchange = instruction::exchange(
+        &trade.program_id,
+        instruction::ExchangeArgs {
+            pdas_temp_token_account: trade.alice_x,
+            ..exchange_args(&trade, delegated_amount)
+        },
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, delegated_amount);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_x).await, AMOUNT_X - delegated_amount);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y);
+}
+
+#[tokio::test]
+async fn test_exchange() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+
+    let exchange = exchange_instruction(&trade, AMOUNT_X);
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_y).await, 0);
+    // Both the temp token account and the escrow account are closed
+    for closed in [trade.temp_token_account.pubkey(), trade.escrow_account.pubkey()] {
+        assert!(trade.banks_client.get_account(closed).await.unwrap().is_none());
+    }
+}
+
+/// The compute units InitEscrow and Exchange take. Programs loaded natively aren't metered, so the numbers only mean something under `cargo test-sbf`, which loads the BPF build instead. scripts/compare-compute-units.sh runs this against two revisions.
+#[tokio::test]
+async fn test_compute_units() {
+    let mut trade = setup().await;
+    let init_escrow = instruction::init_escrow(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_y,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        AMOUNT_Y,
+        0,
+        None,
+    )
+    .unwrap();
+    let instructions = init_escrow_instructions(&mut trade, init_escrow).await;
+    let signers = [&trade.alice, &trade.temp_token_account, &trade.escrow_account];
+    let init_escrow_units =
+        send_metered(&mut trade.banks_client, &trade.payer, &trade.program_id, &instructions, &signers).await;
+
+    let exchange = exchange_instruction(&trade, AMOUNT_X);
+    let exchange_units =
+        send_metered(&mut trade.banks_client, &trade.payer, &trade.program_id, &[exchange], &[&trade.bob]).await;
+
+    println!("compute units: InitEscrow {init_escrow_units}, Exchange {exchange_units}");
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+}
+
+// Run with `cargo test --features anchor`. The accounts are the ones the native builders list, only the data is Anchor's.
+#[cfg(feature = "anchor")]
+#[tokio::test]
+async fn test_anchor_program() {
+    use anchor_lang::InstructionData;
+    use solana_escrow::anchor;
+
+    // setup initializes the config with a native instruction, which the Anchor program's fallback takes
+    let program_id = anchor::ID;
+    let program_test = ProgramTest::new("solana_escrow", program_id, processor!(process_anchor));
+    let mut trade = setup_program(program_test, program_id).await;
+
+    let mut init_escrow = instruction::init_escrow(
+        &program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_y,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        AMOUNT_Y,
+        0,
+        None,
+    )
+    .unwrap();
+    let data = anchor::instruction::InitEscrow {
+        amount: AMOUNT_Y,
+        expiry_unix_timestamp: 0,
+        allowed_taker: Pubkey::default(),
+        deposit_amount: 0,
+        dispute_window_seconds: 0,
+        metadata: vec![],
+        escrow_id: 0,
+        reservation_deposit: 0,
+        taker_merkle_root: [0; 32],
+    }
+    .data();
+    // The discriminator takes the place of the tag, the arguments stay the same
+    assert_eq!(data[8..], init_escrow.data[1..]);
+    init_escrow.data = data;
+    init_escrow_with(&mut trade, init_escrow).await.unwrap();
+
+    let mut exchange = exchange_instruction(&trade, AMOUNT_X);
+    let data = anchor::instruction::Exchange {
+        amount: AMOUNT_X,
+        max_payment: AMOUNT_Y,
+        referrer_token_account: Pubkey::default(),
+        memo: String::new(),
+        taker_proof: vec![],
+        relayer: Pubkey::default(),
+        relayer_tip: 0,
+    }
+    .data();
+    assert_eq!(data[8..], exchange.data[1..]);
+    exchange.data = data;
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob]).await.unwrap();
+
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y);
+}
+
+#[tokio::test]
+async fn test_exchange_creates_takers_token_account() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+
+    let bob_ata = token::get_associated_token_address(&trade.bob.pubkey(), &trade.mint_x, &spl_token::id());
+    let rent = trade.banks_client.get_rent().await.unwrap();
+    let fund_bob = system_instruction::transfer(
+        &trade.payer.pubkey(),
+        &trade.bob.pubkey(),
+        rent.minimum_balance(TokenAccount::LEN),
+    );
+    let exchange = instruction::exchange(
+        &trade.program_id,
+        instruction::ExchangeArgs {
+            takers_token_to_receive_account: bob_ata,
+            create_takers_token_account: true,
+            ..exchange_args(&trade, AMOUNT_X)
+        },
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[fund_bob, exchange], &[&trade.bob])
+        .await
+        .unwrap();
+
+    assert_eq!(token_balance(&mut trade.banks_client, &bob_ata).await, AMOUNT_X);
+}
+
+#[tokio::test]
+async fn test_exchange_wrong_pda() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+
+    let mut exchange = exchange_instruction(&trade, AMOUNT_X);
+    exchange.accounts[8] = AccountMeta::new_readonly(Pubkey::new_unique(), false);
+    let error = send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+}
+
+#[tokio::test]
+async fn test_exchange_amount_mismatch() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+
+    let exchange = exchange_instruction(&trade, AMOUNT_X - 1);
+    let error = send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(solana_escrow::error::EscrowError::ExpectedAmountMismatch as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_exchange_max_payment() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+
+    let exchange = instruction::exchange(
+        &trade.program_id,
+        instruction::ExchangeArgs { max_payment: AMOUNT_Y - 1, ..exchange_args(&trade, AMOUNT_X) },
+    )
+    .unwrap();
+    let error = send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(solana_escrow::error::EscrowError::MaxPaymentExceeded as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_exchange_missing_signer() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+
+    let mut exchange = exchange_instruction(&trade, AMOUNT_X);
+    exchange.accounts[0].is_signer = false;
+    let error = send(&mut trade.banks_client, &trade.payer, &[exchange], &[])
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+// Run with `cargo test --features strict-checks`. The default build sends token X to whichever account the taker names.
+#[cfg(feature = "strict-checks")]
+#[tokio::test]
+async fn test_exchange_strict_checks_receiving_account_owner() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+
+    // Alice's X token account, which Bob doesn't own
+    let exchange = instruction::exchange(
+        &trade.program_id,
+        instruction::ExchangeArgs { takers_token_to_receive_account: trade.alice_x, ..exchange_args(&trade, AMOUNT_X) },
+    )
+    .unwrap();
+    let error = send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_y).await, AMOUNT_Y);
+}
+
+#[tokio::test]
+async fn test_exchange_with_memo() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+
+    // The memo needs the Memo program
+    let mut exchange = instruction::with_memo(exchange_instruction(&trade, AMOUNT_X), "invoice 42").unwrap();
+    let memo_program = exchange.accounts.pop().unwrap();
+    let error = send(&mut trade.banks_client, &trade.payer, &[exchange.clone()], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+
+    exchange.accounts.push(memo_program);
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y);
+}
+
+#[tokio::test]
+async fn test_rate_escrow_partial_fill() {
+    let mut trade = setup().await;
+    fund(&mut trade).await;
+    // 1 Y for every 2 X, the same terms as the fixed escrows
+    let init_rate_escrow = instruction::init_rate_escrow(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_y,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        1,
+        2,
+        0,
+    )
+    .unwrap();
+    init_escrow_with(&mut trade, init_rate_escrow).await.unwrap();
+
+    // Bob pays the rent of his fill record
+    let bob_lamports = trade.banks_client.get_balance(trade.bob.pubkey()).await.unwrap();
+    let exchange = instruction::with_fill_record(exchange_instruction(&trade, 300));
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+    let rent = trade.banks_client.get_rent().await.unwrap();
+    assert_eq!(
+        trade.banks_client.get_balance(trade.bob.pubkey()).await.unwrap(),
+        bob_lamports - rent.minimum_balance(FillRecord::LEN)
+    );
+    // The rest stays on offer
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, 150);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, 300);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.temp_token_account.pubkey()).await, AMOUNT_X - 300);
+
+    // Nobody can take more than what is left
+    let exchange = exchange_instruction(&trade, AMOUNT_X - 299);
+    assert!(send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob]).await.is_err());
+
+    let exchange = instruction::with_fill_record(exchange_instruction(&trade, AMOUNT_X - 300));
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+    for closed in [trade.temp_token_account.pubkey(), trade.escrow_account.pubkey()] {
+        assert!(trade.banks_client.get_account(closed).await.unwrap().is_none());
+    }
+
+    // Bob's fill record outlives the escrow
+    let (fill_record, _bump_seed) =
+        find_fill_record_address(&trade.escrow_account.pubkey(), &trade.bob.pubkey(), &trade.program_id);
+    let fill_record_account = trade.banks_client.get_account(fill_record).await.unwrap().unwrap();
+    let fill_record = FillRecord::unpack(&fill_record_account.data).unwrap();
+    assert_eq!(fill_record.taker, trade.bob.pubkey());
+    assert_eq!((fill_record.amount, fill_record.price, fill_record.fills), (AMOUNT_X, AMOUNT_Y, 2));
+}
+
+#[tokio::test]
+async fn test_two_phase_settlement() {
+    let mut trade = setup().await;
+    fund(&mut trade).await;
+    init_escrow_with_dispute_window(&mut trade, 24 * 60 * 60).await.unwrap();
+
+    let exchange = exchange_instruction(&trade, AMOUNT_X);
+    let error = send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(solana_escrow::error::EscrowError::AcceptRequired as u32)
+        )
+    );
+
+    let accept = instruction::accept(
+        &trade.program_id,
+        &trade.bob.pubkey(),
+        &trade.bob_y,
+        &trade.escrow_account.pubkey(),
+        &trade.mint_y,
+        &spl_token::id(),
+        AMOUNT_Y,
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[accept], &[&trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_y).await, 0);
+
+    let settle = |signed| {
+        instruction::settle(
+            &trade.program_id,
+            &trade.alice.pubkey(),
+            signed,
+            &trade.escrow_account.pubkey(),
+            &trade.temp_token_account.pubkey(),
+            &trade.bob_x,
+            &trade.bob.pubkey(),
+            &trade.alice_y,
+            &trade.alice.pubkey(),
+            &spl_token::id(),
+            &spl_token::id(),
+            &trade.mint_x,
+            &trade.mint_y,
+        )
+        .unwrap()
+    };
+
+    // Only Bob agreeing isn't enough while the dispute window is open
+    let mut bob_only = settle(true);
+    bob_only.accounts[0].is_signer = false;
+    let error = send(&mut trade.banks_client, &trade.payer, &[bob_only], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(solana_escrow::error::EscrowError::DisputeWindowOpen as u32)
+        )
+    );
+
+    send(&mut trade.banks_client, &trade.payer, &[settle(true)], &[&trade.alice, &trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y);
+}
+
+#[tokio::test]
+async fn test_release_approvals() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+
+    let approvers = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let approver_keys: Vec<Pubkey> = approvers.iter().map(|approver| approver.pubkey()).collect();
+    let set_approvers = instruction::set_approvers(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.escrow_account.pubkey(),
+        &approver_keys,
+        2,
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[set_approvers], &[&trade.alice])
+        .await
+        .unwrap();
+
+    let approve = |approver: &Keypair| {
+        instruction::approve_release(&trade.program_id, &approver.pubkey(), &trade.escrow_account.pubkey()).unwrap()
+    };
+    send(&mut trade.banks_client, &trade.payer, &[approve(&approvers[0])], &[&approvers[0]])
+        .await
+        .unwrap();
+
+    let exchange = exchange_instruction(&trade, AMOUNT_X);
+    let error = send(&mut trade.banks_client, &trade.payer, &[exchange.clone()], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(solana_escrow::error::EscrowError::ReleaseNotApproved as u32)
+        )
+    );
+
+    send(&mut trade.banks_client, &trade.payer, &[approve(&approvers[2]), exchange], &[&approvers[2], &trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+}
+
+#[tokio::test]
+async fn test_vesting_escrow() {
+    let mut trade = setup().await;
+    fund(&mut trade).await;
+    let init_vesting_escrow = instruction::init_vesting_escrow(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_y,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        AMOUNT_Y,
+        0,
+        24 * 60 * 60,
+        4,
+    )
+    .unwrap();
+    init_escrow_with(&mut trade, init_vesting_escrow).await.unwrap();
+
+    let exchange = instruction::exchange_into_vesting(
+        &trade.program_id,
+        &trade.bob.pubkey(),
+        &trade.bob_y,
+        &trade.temp_token_account.pubkey(),
+        &trade.alice.pubkey(),
+        &trade.alice_y,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        &trade.mint_x,
+        &trade.mint_y,
+        &spl_token::id(),
+        None,
+        AMOUNT_X,
+        AMOUNT_Y,
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y);
+
+    // The X tokens sit in the vault until the first tranche vests a day later
+    let (vault, _bump_seed) =
+        solana_escrow::find_vesting_vault_address(&trade.escrow_account.pubkey(), &trade.program_id);
+    assert_eq!(token_balance(&mut trade.banks_client, &vault).await, AMOUNT_X);
+
+    let claim = instruction::claim_vested(
+        &trade.program_id,
+        &trade.escrow_account.pubkey(),
+        &trade.bob_x,
+        &trade.bob.pubkey(),
+        &spl_token::id(),
+        &trade.mint_x,
+    )
+    .unwrap();
+    let error = send(&mut trade.banks_client, &trade.payer, &[claim], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(solana_escrow::error::EscrowError::NothingVested as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_stream_escrow() {
+    let mut trade = setup().await;
+    // A stream that practically never ends, so nothing accrues during the test
+    let init_stream_escrow = instruction::init_stream_escrow(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_x,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        &trade.bob.pubkey(),
+        i64::MAX,
+    )
+    .unwrap();
+    init_escrow_with(&mut trade, init_stream_escrow).await.unwrap();
+
+    let exchange = exchange_instruction(&trade, AMOUNT_X);
+    let error = send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(solana_escrow::error::EscrowError::StreamOnly as u32)
+        )
+    );
+
+    let withdraw = instruction::withdraw(
+        &trade.program_id,
+        &trade.bob.pubkey(),
+        &trade.bob_x,
+        &trade.temp_token_account.pubkey(),
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        &trade.mint_x,
+        &trade.alice.pubkey(),
+    )
+    .unwrap();
+    let error = send(&mut trade.banks_client, &trade.payer, &[withdraw], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(solana_escrow::error::EscrowError::NothingToWithdraw as u32)
+        )
+    );
+
+    let cancel = instruction::cancel_stream(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_x,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        &trade.mint_x,
+        &trade.bob_x,
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[cancel], &[&trade.alice])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_x).await, AMOUNT_X);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, 0);
+}
+
+// Bob's escrow is the mirror image of Alice's: her Y for his X
+async fn init_mirror_escrow(trade: &mut Trade) -> (Keypair, Keypair) {
+    let temp_token_account_b = Keypair::new();
+    let escrow_b = Keypair::new();
+    let rent = trade.banks_client.get_rent().await.unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &trade.payer.pubkey(),
+            &temp_token_account_b.pubkey(),
+            rent.minimum_balance(TokenAccount::LEN),
+            TokenAccount::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_account(
+            &spl_token::id(),
+            &temp_token_account_b.pubkey(),
+            &trade.mint_y,
+            &trade.bob.pubkey(),
+        )
+        .unwrap(),
+        spl_token::instruction::transfer(
+            &spl_token::id(),
+            &trade.bob_y,
+            &temp_token_account_b.pubkey(),
+            &trade.bob.pubkey(),
+            &[],
+            AMOUNT_Y,
+        )
+        .unwrap(),
+        system_instruction::create_account(
+            &trade.payer.pubkey(),
+            &escrow_b.pubkey(),
+            rent.minimum_balance(Escrow::LEN),
+            Escrow::LEN as u64,
+            &trade.program_id,
+        ),
+        instruction::init_escrow(
+            &trade.program_id,
+            &trade.bob.pubkey(),
+            &temp_token_account_b.pubkey(),
+            &trade.bob_x,
+            &escrow_b.pubkey(),
+            &spl_token::id(),
+            AMOUNT_X,
+            0,
+            None,
+        )
+        .unwrap(),
+    ];
+    send(&mut trade.banks_client, &trade.payer, &instructions, &[&trade.bob, &temp_token_account_b, &escrow_b])
+        .await
+        .unwrap();
+    (temp_token_account_b, escrow_b)
+}
+
+#[tokio::test]
+async fn test_match_escrows() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+    let (temp_token_account_b, escrow_b) = init_mirror_escrow(&mut trade).await;
+
+    let match_escrows = instruction::match_escrows(
+        &trade.program_id,
+        &trade.escrow_account.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice.pubkey(),
+        &trade.alice_y,
+        &escrow_b.pubkey(),
+        &temp_token_account_b.pubkey(),
+        &trade.bob.pubkey(),
+        &trade.bob_x,
+        &spl_token::id(),
+        &spl_token::id(),
+        &trade.mint_x,
+        &trade.mint_y,
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[match_escrows], &[])
+        .await
+        .unwrap();
+
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+    assert!(trade.banks_client.get_account(escrow_b.pubkey()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_order_book() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+    let (temp_token_account_b, escrow_b) = init_mirror_escrow(&mut trade).await;
+    let market = find_market_address(&trade.mint_x, &trade.mint_y, &trade.program_id).0;
+
+    let init_market = instruction::init_market(&trade.program_id, &trade.payer.pubkey(), &trade.mint_x, &trade.mint_y).unwrap();
+    let ask = instruction::place_order(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.escrow_account.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &market,
+    )
+    .unwrap();
+    let bid = instruction::place_order(
+        &trade.program_id,
+        &trade.bob.pubkey(),
+        &escrow_b.pubkey(),
+        &temp_token_account_b.pubkey(),
+        &market,
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[init_market, ask, bid], &[&trade.alice, &trade.bob])
+        .await
+        .unwrap();
+
+    let consume_orders = instruction::consume_orders(
+        &trade.program_id,
+        &market,
+        &trade.escrow_account.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice.pubkey(),
+        &trade.alice_y,
+        &escrow_b.pubkey(),
+        &temp_token_account_b.pubkey(),
+        &trade.bob.pubkey(),
+        &trade.bob_x,
+        &spl_token::id(),
+        &spl_token::id(),
+        &trade.mint_x,
+        &trade.mint_y,
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[consume_orders], &[])
+        .await
+        .unwrap();
+
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+    let market = trade.banks_client.get_account(market).await.unwrap().unwrap();
+    let market = Market::unpack(&market.data).unwrap();
+    assert_eq!((market.ask_count, market.bid_count), (0, 0));
+}
+
+#[tokio::test]
+async fn test_registry() {
+    let mut trade = setup().await;
+    let registry = find_registry_address(&trade.mint_x, &trade.mint_y, &trade.program_id).0;
+    let init_registry = instruction::init_registry(&trade.program_id, &trade.payer.pubkey(), &trade.mint_x, &trade.mint_y).unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[init_registry], &[])
+        .await
+        .unwrap();
+
+    let init_escrow = instruction::init_escrow(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_y,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        AMOUNT_Y,
+        0,
+        None,
+    )
+    .unwrap();
+    let init_escrow = instruction::with_registry(init_escrow, &trade.mint_x, &trade.mint_y);
+    init_escrow_with(&mut trade, init_escrow).await.unwrap();
+    let registry_account = trade.banks_client.get_account(registry).await.unwrap().unwrap();
+    assert!(EscrowRegistry::unpack(&registry_account.data).unwrap().contains(&trade.escrow_account.pubkey()));
+
+    let exchange = instruction::with_registry(exchange_instruction(&trade, AMOUNT_X), &trade.mint_x, &trade.mint_y);
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+    let registry_account = trade.banks_client.get_account(registry).await.unwrap().unwrap();
+    assert_eq!(EscrowRegistry::unpack(&registry_account.data).unwrap().count, 0);
+}
+
+#[tokio::test]
+async fn test_stats() {
+    let mut trade = setup().await;
+    let stats = find_stats_address(&trade.program_id).0;
+    let init_stats = instruction::init_stats(&trade.program_id, &trade.payer.pubkey()).unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[init_stats], &[])
+        .await
+        .unwrap();
+
+    let init_escrow = instruction::init_escrow(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_y,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        AMOUNT_Y,
+        0,
+        None,
+    )
+    .unwrap();
+    init_escrow_with(&mut trade, instruction::with_stats(init_escrow)).await.unwrap();
+    let exchange = instruction::with_stats(exchange_instruction(&trade, AMOUNT_X));
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+
+    let stats_account = trade.banks_client.get_account(stats).await.unwrap().unwrap();
+    let stats = Stats::unpack(&stats_account.data).unwrap();
+    assert_eq!((stats.escrows_created, stats.escrows_filled, stats.escrows_cancelled), (1, 1, 0));
+    assert_eq!(stats.created_volume, AMOUNT_X as u128);
+    assert_eq!((stats.exchanged_volume_x, stats.exchanged_volume_y), (AMOUNT_X as u128, AMOUNT_Y as u128));
+}
+
+#[tokio::test]
+async fn test_reserve() {
+    const DEPOSIT: u64 = 1_000_000;
+    let mut trade = setup().await;
+    let init_escrow = instruction::init_escrow(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_y,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        AMOUNT_Y,
+        0,
+        None,
+    )
+    .unwrap();
+    init_escrow_with(&mut trade, instruction::with_reservation_deposit(init_escrow, DEPOSIT).unwrap())
+        .await
+        .unwrap();
+
+    let fund_bob = system_instruction::transfer(&trade.payer.pubkey(), &trade.bob.pubkey(), 10 * DEPOSIT);
+    let reserve = instruction::reserve(
+        &trade.program_id,
+        &trade.bob.pubkey(),
+        &trade.escrow_account.pubkey(),
+        &trade.alice.pubkey(),
+        150,
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[fund_bob, reserve], &[&trade.bob])
+        .await
+        .unwrap();
+    let bobs_lamports = trade.banks_client.get_balance(trade.bob.pubkey()).await.unwrap();
+
+    // Alice can't back out while Bob holds the reservation
+    let cancel = instruction::cancel(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_x,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        &trade.mint_x,
+    )
+    .unwrap();
+    let error = send(&mut trade.banks_client, &trade.payer, &[cancel], &[&trade.alice])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(solana_escrow::error::EscrowError::EscrowReserved as u32)
+        )
+    );
+
+    // Exchanging in time refunds the deposit
+    let exchange = exchange_instruction(&trade, AMOUNT_X);
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+    assert_eq!(
+        trade.banks_client.get_balance(trade.bob.pubkey()).await.unwrap(),
+        bobs_lamports + DEPOSIT
+    );
+}
+
+#[tokio::test]
+async fn test_taker_allowlist() {
+    let mut trade = setup().await;
+    let takers = [Pubkey::new_unique(), trade.bob.pubkey(), Pubkey::new_unique()];
+    let init_escrow = instruction::init_escrow(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_y,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        AMOUNT_Y,
+        0,
+        None,
+    )
+    .unwrap();
+    let root = merkle::root(&takers).unwrap();
+    init_escrow_with(&mut trade, instruction::with_taker_merkle_root(init_escrow, root).unwrap())
+        .await
+        .unwrap();
+
+    // Without a proof Bob is turned away
+    let exchange = exchange_instruction(&trade, AMOUNT_X);
+    let error = send(&mut trade.banks_client, &trade.payer, &[exchange.clone()], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(solana_escrow::error::EscrowError::InvalidTakerProof as u32)
+        )
+    );
+
+    let proof = merkle::proof(&takers, &trade.bob.pubkey()).unwrap();
+    let exchange = instruction::with_taker_proof(exchange, proof).unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+}
+
+#[tokio::test]
+async fn test_pause() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+
+    let pause = instruction::pause(&trade.program_id, &trade.admin.pubkey()).unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[pause], &[&trade.admin])
+        .await
+        .unwrap();
+
+    let exchange = exchange_instruction(&trade, AMOUNT_X);
+    let error = send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(solana_escrow::error::EscrowError::ProgramPaused as u32)
+        )
+    );
+
+    let unpause = instruction::unpause(&trade.program_id, &trade.admin.pubkey()).unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[unpause], &[&trade.admin])
+        .await
+        .unwrap();
+    let exchange = exchange_instruction(&trade, AMOUNT_X);
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_set_fee() {
+    let mut trade = setup().await;
+    let fee_collector = Pubkey::new_unique();
+
+    let set_fee = instruction::set_fee(&trade.program_id, &trade.admin.pubkey(), 501, &fee_collector).unwrap();
+    let error = send(&mut trade.banks_client, &trade.payer, &[set_fee], &[&trade.admin])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(solana_escrow::error::EscrowError::FeeTooHigh as u32)
+        )
+    );
+
+    // Only the admin can change the fee
+    let set_fee = instruction::set_fee(&trade.program_id, &trade.bob.pubkey(), 50, &fee_collector).unwrap();
+    assert!(send(&mut trade.banks_client, &trade.payer, &[set_fee], &[&trade.bob]).await.is_err());
+
+    let set_fee = instruction::set_fee(&trade.program_id, &trade.admin.pubkey(), 50, &fee_collector).unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[set_fee], &[&trade.admin])
+        .await
+        .unwrap();
+    let (config, _bump_seed) = find_config_address(&trade.program_id);
+    let config_account = trade.banks_client.get_account(config).await.unwrap().unwrap();
+    let config = Config::unpack(&config_account.data).unwrap();
+    assert_eq!((config.fee_bps, config.fee_collector), (50, fee_collector));
+}
+
+#[tokio::test]
+async fn test_gate_program() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+    let set_gate_program = instruction::set_gate_program(&trade.program_id, &trade.admin.pubkey(), &GATE_PROGRAM_ID).unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[set_gate_program], &[&trade.admin])
+        .await
+        .unwrap();
+
+    // Without the gate program, and then without a credential, Bob is turned away
+    let exchange = exchange_instruction(&trade, AMOUNT_X);
+    let error = send(&mut trade.banks_client, &trade.payer, &[exchange.clone()], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(error, TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys));
+    let exchange = instruction::with_gate_accounts(exchange, &GATE_PROGRAM_ID, &[]);
+    let error = send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(error, TransactionError::InstructionError(0, InstructionError::Custom(42)));
+
+    let exchange = instruction::with_gate_accounts(
+        exchange_instruction(&trade, AMOUNT_X),
+        &GATE_PROGRAM_ID,
+        &[AccountMeta::new_readonly(GATE_CREDENTIAL, false)],
+    );
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+}
+
+#[tokio::test]
+async fn test_cancel_penalty() {
+    let mut trade = setup().await;
+    let set_cancel_penalty =
+        instruction::set_cancel_penalty(&trade.program_id, &trade.admin.pubkey(), 100, 10, false).unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[set_cancel_penalty], &[&trade.admin])
+        .await
+        .unwrap();
+    init_escrow(&mut trade).await.unwrap();
+
+    let cancel = instruction::cancel(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_x,
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+        &trade.mint_x,
+    )
+    .unwrap();
+    let error = send(&mut trade.banks_client, &trade.payer, &[cancel.clone()], &[&trade.alice])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(solana_escrow::error::EscrowError::InvalidFeeVault as u32)
+        )
+    );
+
+    // Without a fee collector the penalty goes to a token account of the fee authority
+    let fee_vault = Keypair::new();
+    let (fee_authority, _bump_seed) = find_fee_authority_address(&trade.program_id);
+    create_token_account(&mut trade.banks_client, &trade.payer, &fee_vault, &trade.mint_x, &fee_authority, 0).await;
+    let cancel = instruction::with_cancel_penalty_account(cancel, &fee_vault.pubkey());
+    send(&mut trade.banks_client, &trade.payer, &[cancel], &[&trade.alice])
+        .await
+        .unwrap();
+
+    let penalty = AMOUNT_X / 100 + 10;
+    assert_eq!(token_balance(&mut trade.banks_client, &fee_vault.pubkey()).await, penalty);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_x).await, AMOUNT_X - penalty);
+}
+
+#[tokio::test]
+async fn test_allowlist() {
+    let mut trade = setup().await;
+    let enable = instruction::set_allowlist_enabled(&trade.program_id, &trade.admin.pubkey(), true).unwrap();
+    let allow_x = instruction::add_allowed_mint(&trade.program_id, &trade.admin.pubkey(), &trade.mint_x).unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[enable, allow_x], &[&trade.admin])
+        .await
+        .unwrap();
+
+    // Only X is on the list so far
+    let error = init_escrow(&mut trade).await.unwrap_err();
+    assert_eq!(
+        error,
+        TransactionError::InstructionError(
+            4,
+            InstructionError::Custom(solana_escrow::error::EscrowError::MintNotAllowed as u32)
+        )
+    );
+
+    let allow_y = instruction::add_allowed_mint(&trade.program_id, &trade.admin.pubkey(), &trade.mint_y).unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[allow_y], &[&trade.admin])
+        .await
+        .unwrap();
+    init_escrow(&mut trade).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_update_escrow() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+
+    let update = instruction::update_escrow(
+        &trade.program_id,
+        &trade.alice.pubkey(),
+        &trade.escrow_account.pubkey(),
+        AMOUNT_Y / 2,
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[update], &[&trade.alice])
+        .await
+        .unwrap();
+
+    let exchange = exchange_instruction(&trade, AMOUNT_X);
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y / 2);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_y).await, AMOUNT_Y / 2);
+}
+
+/// A program account holding the given packed state, rent exempt
+fn state_account<T: Pack>(state: T, owner: &Pubkey) -> Account {
+    let mut data = vec![0u8; T::LEN];
+    T::pack(state, &mut data).unwrap();
+    Account { lamports: 1_000_000_000, data, owner: *owner, ..Account::default() }
+}
+
+#[tokio::test]
+async fn test_migrate_escrow() {
+    migrate_escrow(false).await;
+}
+
+#[tokio::test]
+async fn test_upgrade_escrow_account() {
+    migrate_escrow(true).await;
+}
+
+/// Migrates a V1 escrow, either in one go or by upgrading the account first and handing the temp token account over after
+async fn migrate_escrow(upgrade_first: bool) {
+    // A V1 escrow can only be written by the V1 program, so the accounts are added before the test validator starts
+    let program_id = Pubkey::new_unique();
+    let mut program_test =
+        ProgramTest::new("solana_escrow", program_id, processor!(Processor::process));
+    let (mint_x, mint_y) = (Pubkey::new_unique(), Pubkey::new_unique());
+    for mint in [mint_x, mint_y] {
+        let mint_state = Mint { supply: AMOUNT_X, is_initialized: true, ..Mint::default() };
+        program_test.add_account(mint, state_account(mint_state, &spl_token::id()));
+    }
+    let alice = Keypair::new();
+    let (legacy_pda, _bump_seed) = find_legacy_escrow_authority_address(&program_id);
+    let (temp_token_account, alice_x, alice_y) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    for (account, mint, owner, amount) in [
+        (temp_token_account, mint_x, legacy_pda, AMOUNT_X),
+        (alice_x, mint_x, alice.pubkey(), 0),
+        (alice_y, mint_y, alice.pubkey(), 0),
+    ] {
+        let token_account = TokenAccount { mint, owner, amount, state: AccountState::Initialized, ..TokenAccount::default() };
+        program_test.add_account(account, state_account(token_account, &spl_token::id()));
+    }
+    let escrow_account = Pubkey::new_unique();
+    let escrow_v1 = EscrowV1 {
+        is_initialized: true,
+        initializer_pubkey: alice.pubkey(),
+        temp_token_account_pubkey: temp_token_account,
+        initializer_token_to_receive_account_pubkey: alice_y,
+        expected_amount: AMOUNT_Y,
+    };
+    program_test.add_account(escrow_account, state_account(escrow_v1, &program_id));
+    // Cancel needs the config, which only InitializeConfig's upgrade authority check would stop us creating the usual way
+    let (config, bump_seed) = find_config_address(&program_id);
+    let config_state = Config {
+        is_initialized: true,
+        admin: Pubkey::new_unique(),
+        fee_bps: 0,
+        paused: false,
+        default_expiry_seconds: 0,
+        bump_seed,
+        allowlist_enabled: false,
+        referral_share_bps: 0,
+        fee_collector: Pubkey::default(),
+        cancel_penalty_bps: 0,
+        cancel_penalty_amount: 0,
+        cancel_penalty_to_counterparty: false,
+        gate_program: Pubkey::default(),
+        max_expiry_seconds: 0,
+    };
+    program_test.add_account(config, state_account(config_state, &program_id));
+    let (mut banks_client, payer, _recent_blockhash) = program_test.start().await;
+
+    // V1 escrows can't be used until they're migrated
+    let cancel = instruction::cancel(
+        &program_id,
+        &alice.pubkey(),
+        &temp_token_account,
+        &alice_x,
+        &escrow_account,
+        &spl_token::id(),
+        &mint_x,
+    )
+    .unwrap();
+    assert!(send(&mut banks_client, &payer, &[cancel.clone()], &[&alice]).await.is_err());
+
+    if upgrade_first {
+        let upgrade = instruction::upgrade_escrow_account(
+            &program_id,
+            &payer.pubkey(),
+            &escrow_account,
+            &temp_token_account,
+            &alice_y,
+        )
+        .unwrap();
+        send(&mut banks_client, &payer, &[upgrade], &[]).await.unwrap();
+        let escrow_data = banks_client.get_account(escrow_account).await.unwrap().unwrap().data;
+        assert_eq!(escrow_data.len(), Escrow::LEN);
+        let escrow = Escrow::unpack(&escrow_data).unwrap();
+        assert_eq!((escrow.token_x_mint, escrow.token_y_mint), (mint_x, mint_y));
+        assert_eq!({ escrow.expected_amount }, AMOUNT_Y);
+
+        // The temp token account is still the legacy PDA's
+        next_blockhash(&mut banks_client).await;
+        assert!(send(&mut banks_client, &payer, &[cancel.clone()], &[&alice]).await.is_err());
+    }
+
+    let migrate = instruction::migrate_escrow(&program_id, &payer.pubkey(), &escrow_account, &temp_token_account, &alice_y).unwrap();
+    send(&mut banks_client, &payer, &[migrate.clone()], &[]).await.unwrap();
+    let escrow_data = banks_client.get_account(escrow_account).await.unwrap().unwrap().data;
+    let escrow = Escrow::unpack(&escrow_data).unwrap();
+    assert_eq!((escrow.token_x_mint, escrow.token_y_mint), (mint_x, mint_y));
+    assert_eq!({ escrow.expected_amount }, AMOUNT_Y);
+
+    // Migrating twice fails instead of reading the new layout as the old one
+    next_blockhash(&mut banks_client).await;
+    assert!(send(&mut banks_client, &payer, &[migrate], &[]).await.is_err());
+
+    send(&mut banks_client, &payer, &[cancel], &[&alice]).await.unwrap();
+    assert_eq!(token_balance(&mut banks_client, &alice_x).await, AMOUNT_X);
+}
+
+#[tokio::test]
+async fn test_close_stale_escrow() {
+    let mut trade = setup().await;
+    let rent = trade.banks_client.get_rent().await.unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &trade.payer.pubkey(),
+            &trade.escrow_account.pubkey(),
+            rent.minimum_balance(Escrow::LEN),
+            Escrow::LEN as u64,
+            &trade.program_id,
+        ),
+        instruction::init_delegated_escrow(
+            &trade.program_id,
+            &trade.alice.pubkey(),
+            &trade.alice_x,
+            &trade.alice_y,
+            &trade.escrow_account.pubkey(),
+            &spl_token::id(),
+            AMOUNT_X,
+            AMOUNT_Y,
+            0,
+            None,
+        )
+        .unwrap(),
+    ];
+    let signers = [&trade.alice, &trade.escrow_account];
+    send(&mut trade.banks_client, &trade.payer, &instructions, &signers)
+        .await
+        .unwrap();
+
+    let close = instruction::close_stale_escrow(
+        &trade.program_id,
+        &trade.alice_x,
+        &trade.alice.pubkey(),
+        &trade.escrow_account.pubkey(),
+        &spl_token::id(),
+    )
+    .unwrap();
+    let error = send(&mut trade.banks_client, &trade.payer, &[close.clone()], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(error, escrow_error(0, EscrowError::EscrowNotStale));
+
+    // Once Alice takes the allowance back the escrow can't be exchanged anymore, and anyone can close it. Revoking in the same transaction keeps it from being a duplicate of the one above.
+    let revoke = spl_token::instruction::revoke(&spl_token::id(), &trade.alice_x, &trade.alice.pubkey(), &[]).unwrap();
+    let alice_lamports = trade.banks_client.get_balance(trade.alice.pubkey()).await.unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[revoke, close], &[&trade.alice])
+        .await
+        .unwrap();
+
+    assert!(trade.banks_client.get_account(trade.escrow_account.pubkey()).await.unwrap().is_none());
+    assert_eq!(
+        trade.banks_client.get_balance(trade.alice.pubkey()).await.unwrap(),
+        alice_lamports + rent.minimum_balance(Escrow::LEN)
+    );
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_x).await, AMOUNT_X);
+}
+
+#[tokio::test]
+async fn test_signed_order() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+    let price = AMOUNT_Y / 2;
+    let message = signed_order::message(&trade.escrow_account.pubkey(), &trade.bob.pubkey(), AMOUNT_X, price, i64::MAX);
+    let exchange = exchange_instruction(&trade, AMOUNT_X);
+    let settle = instruction::with_signed_order(exchange, &trade.bob.pubkey(), price, i64::MAX).unwrap();
+
+    // Bob can't sign his own price
+    let signature = <[u8; 64]>::try_from(trade.bob.sign_message(&message).as_ref()).unwrap();
+    let verify = signed_order::verify_instruction(&trade.bob.pubkey(), &signature, &message);
+    let error = send(&mut trade.banks_client, &trade.payer, &[verify, settle.clone()], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(error, escrow_error(1, EscrowError::InvalidOrderSignature));
+
+    let signature = <[u8; 64]>::try_from(trade.alice.sign_message(&message).as_ref()).unwrap();
+    let verify = signed_order::verify_instruction(&trade.alice.pubkey(), &signature, &message);
+    send(&mut trade.banks_client, &trade.payer, &[verify, settle], &[&trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, price);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_y).await, AMOUNT_Y - price);
+}
+
+#[tokio::test]
+async fn test_swap_ring() {
+    const AMOUNT_Z: u64 = 300;
+    let mut trade = setup().await;
+    fund(&mut trade).await;
+    let program_id = trade.program_id;
+    let mint_z = create_mint(&mut trade.banks_client, &trade.payer).await;
+    let (carol, carol_z) = (Keypair::new(), Keypair::new());
+    create_token_account(&mut trade.banks_client, &trade.payer, &carol_z, &mint_z, &carol.pubkey(), AMOUNT_Z).await;
+
+    // Alice's X goes to Bob, Bob's Y to Carol and Carol's Z back to Alice, each into their associated token account
+    let payouts = [
+        (trade.bob.pubkey(), trade.mint_x),
+        (carol.pubkey(), trade.mint_y),
+        (trade.alice.pubkey(), mint_z),
+    ];
+    let deadline = now(&mut trade.banks_client).await + 1_000;
+    let mut instructions: Vec<_> = payouts
+        .iter()
+        .map(|(wallet, mint)| {
+            token::create_associated_token_account(&trade.payer.pubkey(), wallet, mint, &spl_token::id())
+        })
+        .collect();
+    instructions.push(system_instruction::transfer(&trade.payer.pubkey(), &carol.pubkey(), 1_000_000_000));
+    instructions.push(
+        instruction::init_ring(
+            &program_id,
+            &trade.payer.pubkey(),
+            0,
+            deadline,
+            &[
+                (trade.alice.pubkey(), trade.mint_x, AMOUNT_X),
+                (trade.bob.pubkey(), trade.mint_y, AMOUNT_Y),
+                (carol.pubkey(), mint_z, AMOUNT_Z),
+            ],
+        )
+        .unwrap(),
+    );
+    send(&mut trade.banks_client, &trade.payer, &instructions, &[])
+        .await
+        .unwrap();
+
+    let (ring_account, _bump_seed) = find_ring_address(&trade.payer.pubkey(), 0, &program_id);
+    let deposit = |participant: &Pubkey, token_account: &Pubkey, leg_index: u8, mint: &Pubkey| {
+        instruction::deposit_ring_leg(
+            &program_id,
+            participant,
+            token_account,
+            &ring_account,
+            leg_index,
+            mint,
+            &spl_token::id(),
+        )
+        .unwrap()
+    };
+
+    // Only the ring's participants can deposit into it
+    let mallory = Keypair::new();
+    let error = send(
+        &mut trade.banks_client,
+        &trade.payer,
+        &[deposit(&mallory.pubkey(), &trade.alice_x, 0, &trade.mint_x)],
+        &[&mallory],
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(error, escrow_error(0, EscrowError::NotARingParticipant));
+
+    let deposits = [
+        deposit(&trade.alice.pubkey(), &trade.alice_x, 0, &trade.mint_x),
+        deposit(&trade.bob.pubkey(), &trade.bob_y, 1, &trade.mint_y),
+    ];
+    send(&mut trade.banks_client, &trade.payer, &deposits, &[&trade.alice, &trade.bob])
+        .await
+        .unwrap();
+
+    // Nobody is paid until Carol is in as well
+    let ring_data = trade.banks_client.get_account(ring_account).await.unwrap().unwrap().data;
+    let ring = SwapRing::unpack(&ring_data).unwrap();
+    let execute = instruction::execute_ring(&program_id, &ring_account, &ring, &[spl_token::id(); 3]).unwrap();
+    let error = send(&mut trade.banks_client, &trade.payer, &[execute.clone()], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(error, escrow_error(0, EscrowError::RingIncomplete));
+
+    let deposit_z = deposit(&carol.pubkey(), &carol_z.pubkey(), 2, &mint_z);
+    send(&mut trade.banks_client, &trade.payer, &[deposit_z, execute], &[&carol])
+        .await
+        .unwrap();
+    for ((wallet, mint), amount) in payouts.iter().zip([AMOUNT_X, AMOUNT_Y, AMOUNT_Z]) {
+        let token_account = token::get_associated_token_address(wallet, mint, &spl_token::id());
+        assert_eq!(token_balance(&mut trade.banks_client, &token_account).await, amount);
+    }
+    assert!(trade.banks_client.get_account(ring_account).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_quote() {
+    quote(false).await;
+}
+
+#[tokio::test]
+async fn test_locked_quote() {
+    quote(true).await;
+}
+
+/// Bob quotes for Alice's escrow, either with an allowance of the price or with it locked in the quote vault
+async fn quote(lock_funds: bool) {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+    fund(&mut trade).await;
+    let price = AMOUNT_Y - 100;
+    let post_quote = instruction::post_quote(
+        &trade.program_id,
+        &trade.bob.pubkey(),
+        &trade.escrow_account.pubkey(),
+        &trade.bob_y,
+        &trade.bob_x,
+        &trade.mint_y,
+        &spl_token::id(),
+        AMOUNT_X,
+        price,
+        0,
+        lock_funds,
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[post_quote], &[&trade.bob])
+        .await
+        .unwrap();
+    let locked = if lock_funds { price } else { 0 };
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_y).await, AMOUNT_Y - locked);
+
+    let accept_quote = |initializer: &Pubkey| {
+        instruction::accept_quote(
+            &trade.program_id,
+            initializer,
+            &trade.escrow_account.pubkey(),
+            &trade.temp_token_account.pubkey(),
+            &trade.alice_y,
+            &trade.bob.pubkey(),
+            &trade.bob_y,
+            &trade.bob_x,
+            lock_funds,
+            &spl_token::id(),
+            &spl_token::id(),
+            &trade.mint_x,
+            &trade.mint_y,
+        )
+        .unwrap()
+    };
+
+    // Only Alice can accept quotes on her escrow
+    let error = send(&mut trade.banks_client, &trade.payer, &[accept_quote(&trade.bob.pubkey())], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(error, TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+
+    send(&mut trade.banks_client, &trade.payer, &[accept_quote(&trade.alice.pubkey())], &[&trade.alice])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, price);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_y).await, AMOUNT_Y - price);
+}
+
+#[tokio::test]
+async fn test_counteroffer() {
+    let mut trade = setup().await;
+    init_escrow(&mut trade).await.unwrap();
+    fund(&mut trade).await;
+    let propose = instruction::propose_counteroffer(
+        &trade.program_id,
+        &trade.bob.pubkey(),
+        &trade.escrow_account.pubkey(),
+        &trade.bob_y,
+        &trade.bob_x,
+        &spl_token::id(),
+        AMOUNT_Y / 2,
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[propose], &[&trade.bob])
+        .await
+        .unwrap();
+
+    let accept_counteroffer = |initializer: &Pubkey| {
+        instruction::accept_counteroffer(
+            &trade.program_id,
+            initializer,
+            &trade.escrow_account.pubkey(),
+            &trade.temp_token_account.pubkey(),
+            &trade.alice_y,
+            &trade.bob.pubkey(),
+            &trade.bob_y,
+            &trade.bob_x,
+            &spl_token::id(),
+            &spl_token::id(),
+            &trade.mint_x,
+            &trade.mint_y,
+        )
+        .unwrap()
+    };
+
+    // Bob can't accept his own counteroffer
+    let error = send(&mut trade.banks_client, &trade.payer, &[accept_counteroffer(&trade.bob.pubkey())], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(error, TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+
+    let accept = accept_counteroffer(&trade.alice.pubkey());
+    send(&mut trade.banks_client, &trade.payer, &[accept], &[&trade.alice])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y / 2);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_y).await, AMOUNT_Y / 2);
+}
+
+#[tokio::test]
+async fn test_sealed_auction() {
+    const DEPOSIT: u64 = 50;
+    let mut trade = setup().await;
+    fund(&mut trade).await;
+    let (program_id, escrow) = (trade.program_id, trade.escrow_account.pubkey());
+    let commit_end = now(&mut trade.banks_client).await + 100;
+    let reveal_end = commit_end + 100;
+    let init_auction = instruction::init_sealed_auction(
+        &program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_y,
+        &escrow,
+        &spl_token::id(),
+        AMOUNT_Y / 2,
+        DEPOSIT,
+        commit_end,
+        reveal_end,
+    )
+    .unwrap();
+    init_escrow_with(&mut trade, init_auction).await.unwrap();
+
+    let salt = [9; 32];
+    let commitment = sealed_auction::commitment(&trade.bob.pubkey(), AMOUNT_Y, &salt);
+    let (bob, bob_y, mint_y) = (trade.bob.pubkey(), trade.bob_y, trade.mint_y);
+    let commit =
+        instruction::commit_bid(&program_id, &bob, &bob_y, &escrow, &mint_y, &spl_token::id(), commitment).unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[commit], &[&trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_y).await, AMOUNT_Y - DEPOSIT);
+
+    // Bob is held to the bid he committed to
+    warp_to_timestamp(&mut trade, commit_end).await;
+    let reveal = |amount: u64| {
+        instruction::reveal_bid(&program_id, &bob, &bob_y, &escrow, &mint_y, &spl_token::id(), amount, salt).unwrap()
+    };
+    let error = send(&mut trade.banks_client, &trade.payer, &[reveal(AMOUNT_Y - 1)], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(error, escrow_error(0, EscrowError::CommitmentMismatch));
+
+    // The deposit comes back and pays for part of the bid
+    let reveal = reveal(AMOUNT_Y);
+    send(&mut trade.banks_client, &trade.payer, &[reveal], &[&trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_y).await, 0);
+
+    warp_to_timestamp(&mut trade, reveal_end).await;
+    let settle = instruction::settle_auction(
+        &program_id,
+        &trade.payer.pubkey(),
+        false,
+        &escrow,
+        &trade.temp_token_account.pubkey(),
+        &trade.bob_x,
+        &trade.bob.pubkey(),
+        &trade.alice_y,
+        &trade.alice.pubkey(),
+        &spl_token::id(),
+        &spl_token::id(),
+        &trade.mint_x,
+        &trade.mint_y,
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[settle], &[])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+}
+
+/// Alice raffles her X off in five tickets, which Bob buys up, so the raffle is over and Bob is sure to win
+async fn init_sold_out_raffle(trade: &mut Trade, vrf: Option<&Pubkey>) -> (Pubkey, Raffle) {
+    fund(trade).await;
+    let (program_id, escrow) = (trade.program_id, trade.escrow_account.pubkey());
+    let end = now(&mut trade.banks_client).await + 1_000;
+    let init_raffle = instruction::init_raffle(
+        &program_id,
+        &trade.alice.pubkey(),
+        &trade.temp_token_account.pubkey(),
+        &trade.alice_y,
+        &escrow,
+        &spl_token::id(),
+        &trade.mint_y,
+        &spl_token::id(),
+        AMOUNT_Y / 5,
+        5,
+        end,
+        vrf,
+    )
+    .unwrap();
+    init_escrow_with(trade, init_raffle).await.unwrap();
+
+    let (bob, bob_y, mint_y) = (trade.bob.pubkey(), trade.bob_y, trade.mint_y);
+    let buy = instruction::buy_tickets(&program_id, &bob, &bob_y, &escrow, &mint_y, &spl_token::id(), 0, 5).unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[buy], &[&trade.bob])
+        .await
+        .unwrap();
+
+    let (raffle_account, _bump_seed) = find_raffle_address(&escrow, &program_id);
+    let raffle_data = trade.banks_client.get_account(raffle_account).await.unwrap().unwrap().data;
+    (raffle_account, Raffle::unpack(&raffle_data).unwrap())
+}
+
+fn settle_raffle(
+    trade: &Trade,
+    raffle_account: &Pubkey,
+    raffle: &Raffle,
+    winner: &Pubkey,
+    winners_token_account: &Pubkey,
+) -> solana_program::instruction::Instruction {
+    let (ticket_account, _bump_seed) = find_ticket_address(raffle_account, 0, &trade.program_id);
+    instruction::settle_raffle(
+        &trade.program_id,
+        raffle,
+        &trade.temp_token_account.pubkey(),
+        &trade.alice.pubkey(),
+        &ticket_account,
+        winner,
+        winners_token_account,
+        &spl_token::id(),
+        &spl_token::id(),
+        &trade.mint_x,
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_raffle() {
+    let mut trade = setup().await;
+    let (raffle_account, _raffle) = init_sold_out_raffle(&mut trade, None).await;
+    let draw = instruction::draw_raffle(&trade.program_id, &trade.escrow_account.pubkey()).unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[draw], &[])
+        .await
+        .unwrap();
+    let raffle_data = trade.banks_client.get_account(raffle_account).await.unwrap().unwrap().data;
+    let raffle = Raffle::unpack(&raffle_data).unwrap();
+
+    // Only whoever bought the winning ticket gets the prize
+    let settle = settle_raffle(&trade, &raffle_account, &raffle, &trade.alice.pubkey(), &trade.alice_x);
+    let error = send(&mut trade.banks_client, &trade.payer, &[settle], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(error, escrow_error(0, EscrowError::InvalidTicket));
+
+    // Warping skips the draw slot, so the raffle settles with the hash of the slot after it, which is only in SlotHashes once a later slot starts
+    trade.context.warp_to_slot(raffle.draw_slot + 1).unwrap();
+    trade.context.warp_to_slot(raffle.draw_slot + 2).unwrap();
+    let settle = settle_raffle(&trade, &raffle_account, &raffle, &trade.bob.pubkey(), &trade.bob_x);
+    send(&mut trade.banks_client, &trade.payer, &[settle], &[])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+    assert!(trade.banks_client.get_account(raffle_account).await.unwrap().is_none());
+}
+
+/// A Switchboard VRF account of `authority` at `counter`, verified once it has a result
+fn vrf_account(authority: &Pubkey, counter: u128, result: Option<[u8; 32]>) -> AccountSharedData {
+    let mut data = vec![0u8; 29_058];
+    data[..8].copy_from_slice(&[101, 35, 62, 239, 103, 151, 6, 18]);
+    // StatusVerified, or StatusRequestingRandomness while the proof is being verified
+    data[8] = if result.is_some() { 3 } else { 1 };
+    data[9..25].copy_from_slice(&counter.to_le_bytes());
+    data[25..57].copy_from_slice(authority.as_ref());
+    let result_offset = data.len() - (32 + 4 + 256 + 1024);
+    data[result_offset..result_offset + 32].copy_from_slice(&result.unwrap_or_default());
+    AccountSharedData::from(Account { lamports: 1_000_000_000, data, owner: switchboard::id(), ..Account::default() })
+}
+
+#[tokio::test]
+async fn test_raffle_with_vrf() {
+    let mut trade = setup().await;
+    let vrf = Pubkey::new_unique();
+    let (raffle_account, raffle) = init_sold_out_raffle(&mut trade, Some(&vrf)).await;
+    trade.context.set_account(&vrf, &vrf_account(&raffle_account, 1, None));
+
+    // The stand-in Switchboard doesn't read the queue's accounts
+    let (program_id, payer) = (trade.program_id, trade.payer.pubkey());
+    let [oracle_queue, queue_authority, data_buffer, permission, vrf_escrow, payer_wallet, program_state] =
+        [(); 7].map(|_| Pubkey::new_unique());
+    let request_randomness = |raffle: &Raffle| {
+        instruction::request_raffle_randomness(
+            &program_id,
+            raffle,
+            &oracle_queue,
+            &queue_authority,
+            &data_buffer,
+            &permission,
+            &vrf_escrow,
+            &payer_wallet,
+            &payer,
+            &program_state,
+            0,
+            0,
+        )
+        .unwrap()
+    };
+
+    // The raffle can only be drawn with the VRF it was set up with
+    let other_vrf = Raffle { vrf: Pubkey::new_unique(), ..raffle };
+    let error = send(&mut trade.banks_client, &trade.payer, &[request_randomness(&other_vrf)], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(error, escrow_error(0, EscrowError::InvalidVrfAccount));
+    let draw = instruction::draw_raffle(&program_id, &trade.escrow_account.pubkey()).unwrap();
+    let error = send(&mut trade.banks_client, &trade.payer, &[draw], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(error, escrow_error(0, EscrowError::WrongRandomnessProvider));
+
+    send(&mut trade.banks_client, &trade.payer, &[request_randomness(&raffle)], &[])
+        .await
+        .unwrap();
+
+    // Nothing can be consumed before the proof is verified
+    let consume = instruction::consume_raffle_randomness(&program_id, &raffle).unwrap();
+    let error = send(&mut trade.banks_client, &trade.payer, &[consume.clone()], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(error, escrow_error(0, EscrowError::RandomnessUnavailable));
+
+    trade.context.set_account(&vrf, &vrf_account(&raffle_account, 1, Some([7; 32])));
+    let settle = settle_raffle(&trade, &raffle_account, &raffle, &trade.bob.pubkey(), &trade.bob_x);
+    send(&mut trade.banks_client, &trade.payer, &[consume, settle], &[])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+}
+
+#[tokio::test]
+async fn test_escrow_pool() {
+    let mut trade = setup().await;
+    fund(&mut trade).await;
+    let program_id = trade.program_id;
+    let pool_account = Keypair::new();
+    let rent = trade.banks_client.get_rent().await.unwrap();
+    let space = EscrowPool::space(4);
+    let instructions = [
+        system_instruction::create_account(
+            &trade.payer.pubkey(),
+            &pool_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &program_id,
+        ),
+        instruction::init_escrow_pool(
+            &program_id,
+            &trade.alice.pubkey(),
+            &pool_account.pubkey(),
+            &trade.alice_y,
+            &trade.mint_x,
+            &trade.mint_y,
+            &spl_token::id(),
+        )
+        .unwrap(),
+    ];
+    send(&mut trade.banks_client, &trade.payer, &instructions, &[&pool_account, &trade.alice])
+        .await
+        .unwrap();
+    let pool_data = trade.banks_client.get_account(pool_account.pubkey()).await.unwrap().unwrap().data;
+    let pool = EscrowPool::unpack_header(&pool_data).unwrap();
+
+    let post = instruction::post_pool_entry(
+        &program_id,
+        &pool_account.pubkey(),
+        &pool,
+        &trade.alice_x,
+        &spl_token::id(),
+        AMOUNT_X,
+        AMOUNT_Y,
+        0,
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[post], &[&trade.alice])
+        .await
+        .unwrap();
+
+    // Only Alice can take her entry back
+    let not_the_maker = EscrowPool { maker: trade.bob.pubkey(), ..pool };
+    let cancel = instruction::cancel_pool_entry(
+        &program_id,
+        &pool_account.pubkey(),
+        &not_the_maker,
+        &trade.bob_x,
+        &spl_token::id(),
+        0,
+    )
+    .unwrap();
+    let error = send(&mut trade.banks_client, &trade.payer, &[cancel], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(error, escrow_error(0, EscrowError::InvalidEscrowPool));
+
+    let take = instruction::take_pool_entry(
+        &program_id,
+        &trade.bob.pubkey(),
+        &trade.bob_y,
+        &trade.bob_x,
+        &pool_account.pubkey(),
+        &pool,
+        &spl_token::id(),
+        &spl_token::id(),
+        None,
+        0,
+        AMOUNT_X,
+        AMOUNT_Y,
+    )
+    .unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[take], &[&trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+    let pool_data = trade.banks_client.get_account(pool_account.pubkey()).await.unwrap().unwrap().data;
+    assert_eq!(EscrowPool::unpack_header(&pool_data).unwrap().open_entries, 0);
+}
+
+#[tokio::test]
+async fn test_compressed_escrow() {
+    let mut trade = setup().await;
+    let program_id = trade.program_id;
+    let merkle_tree = Keypair::new();
+
+    // Trees deeper than the compression program supports are turned away before any account is read
+    let too_deep = instruction::init_compressed_tree(
+        &program_id,
+        &trade.payer.pubkey(),
+        &merkle_tree.pubkey(),
+        compressed_escrow::MAX_DEPTH + 1,
+        8,
+        0,
+    )
+    .unwrap();
+    let error = send(&mut trade.banks_client, &trade.payer, &[too_deep], &[])
+        .await
+        .unwrap_err();
+    assert_eq!(error, escrow_error(0, EscrowError::InvalidInstruction));
+
+    // A depth 3 tree in the stand-in's layout, whose canopy holds the whole tree, so no proof nodes are passed
+    let rent = trade.banks_client.get_rent().await.unwrap();
+    let tree_len = 8 + 32 * 8;
+    let instructions = [
+        system_instruction::create_account(
+            &trade.payer.pubkey(),
+            &merkle_tree.pubkey(),
+            rent.minimum_balance(tree_len),
+            tree_len as u64,
+            &compression::id(),
+        ),
+        instruction::init_compressed_tree(&program_id, &trade.payer.pubkey(), &merkle_tree.pubkey(), 3, 8, 3).unwrap(),
+        instruction::init_compressed_escrow(
+            &program_id,
+            &trade.alice.pubkey(),
+            &trade.alice_x,
+            &trade.alice_y,
+            &merkle_tree.pubkey(),
+            &trade.mint_x,
+            &trade.mint_y,
+            &spl_token::id(),
+            AMOUNT_X,
+            AMOUNT_Y,
+            0,
+        )
+        .unwrap(),
+    ];
+    send(&mut trade.banks_client, &trade.payer, &instructions, &[&merkle_tree, &trade.alice])
+        .await
+        .unwrap();
+
+    let escrow = CompressedEscrow {
+        initializer: trade.alice.pubkey(),
+        initializer_token_account: trade.alice_x,
+        initializer_token_to_receive_account: trade.alice_y,
+        token_x_mint: trade.mint_x,
+        token_y_mint: trade.mint_y,
+        amount_x: AMOUNT_X,
+        amount_y: AMOUNT_Y,
+        expiry_unix_timestamp: 0,
+        leaf_index: 0,
+    };
+    let exchange = |escrow: &CompressedEscrow| {
+        instruction::exchange_compressed(
+            &program_id,
+            &trade.bob.pubkey(),
+            &trade.bob_y,
+            &trade.bob_x,
+            &merkle_tree.pubkey(),
+            escrow,
+            &spl_token::id(),
+            &spl_token::id(),
+            None,
+            [0; 32],
+            &[],
+            AMOUNT_Y,
+        )
+        .unwrap()
+    };
+    let tree_key = merkle_tree.pubkey();
+    let cancel =
+        instruction::cancel_compressed(&program_id, &tree_key, &escrow, &spl_token::id(), [0; 32], &[]).unwrap();
+
+    // Bob can neither cancel Alice's escrow nor take it on terms she didn't list
+    let mut cancel_by_bob = cancel.clone();
+    cancel_by_bob.accounts[0] = AccountMeta::new_readonly(trade.bob.pubkey(), true);
+    let error = send(&mut trade.banks_client, &trade.payer, &[cancel_by_bob], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(error, TransactionError::InstructionError(0, InstructionError::InvalidAccountData));
+    let cheaper = exchange(&CompressedEscrow { amount_y: 1, ..escrow });
+    let error = send(&mut trade.banks_client, &trade.payer, &[cheaper], &[&trade.bob])
+        .await
+        .unwrap_err();
+    assert_eq!(error, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+
+    // A second listing shares the allowance, and cancelling it takes its share back
+    let list_again = instruction::init_compressed_escrow(
+        &program_id,
+        &trade.alice.pubkey(),
+        &trade.alice_x,
+        &trade.alice_y,
+        &tree_key,
+        &trade.mint_x,
+        &trade.mint_y,
+        &spl_token::id(),
+        AMOUNT_X,
+        AMOUNT_Y,
+        0,
+    )
+    .unwrap();
+    let relisted = CompressedEscrow { leaf_index: 1, ..escrow };
+    let cancel_relisted =
+        instruction::cancel_compressed(&program_id, &tree_key, &relisted, &spl_token::id(), [0; 32], &[]).unwrap();
+    send(&mut trade.banks_client, &trade.payer, &[list_again], &[&trade.alice])
+        .await
+        .unwrap();
+    assert_eq!(delegated_amount(&mut trade.banks_client, &trade.alice_x).await, 2 * AMOUNT_X);
+    send(&mut trade.banks_client, &trade.payer, &[cancel_relisted], &[&trade.alice])
+        .await
+        .unwrap();
+    assert_eq!(delegated_amount(&mut trade.banks_client, &trade.alice_x).await, AMOUNT_X);
+
+    let exchange = exchange(&escrow);
+    send(&mut trade.banks_client, &trade.payer, &[exchange], &[&trade.bob])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_y).await, AMOUNT_Y);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.alice_x).await, 0);
+    assert_eq!(token_balance(&mut trade.banks_client, &trade.bob_x).await, AMOUNT_X);
+
+    // The leaf was cleared, so the escrow is gone
+    let error = send(&mut trade.banks_client, &trade.payer, &[cancel], &[&trade.alice])
+        .await
+        .unwrap_err();
+    assert_eq!(error, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+}